@@ -0,0 +1,50 @@
+package io.github.maxwellnie.lan.device.android
+
+import android.app.Activity
+import androidx.biometric.BiometricManager.Authenticators
+import androidx.biometric.BiometricPrompt
+import androidx.core.content.ContextCompat
+import androidx.fragment.app.FragmentActivity
+import app.tauri.annotation.Command
+import app.tauri.annotation.InvokeArg
+import app.tauri.annotation.TauriPlugin
+import app.tauri.plugin.Invoke
+import app.tauri.plugin.Plugin
+
+@InvokeArg
+class AuthenticateArgs {
+  lateinit var reason: String
+}
+
+// 通过指纹、面容或锁屏密码确认危险操作，取消或验证失败时返回错误
+@TauriPlugin
+class BiometricPlugin(private val activity: Activity) : Plugin(activity) {
+  @Command
+  fun authenticate(invoke: Invoke) {
+    val args = invoke.parseArgs(AuthenticateArgs::class.java)
+    val callback = object : BiometricPrompt.AuthenticationCallback() {
+      override fun onAuthenticationSucceeded(result: BiometricPrompt.AuthenticationResult) {
+        invoke.resolve()
+      }
+
+      override fun onAuthenticationError(errorCode: Int, errString: CharSequence) {
+        invoke.reject(errString.toString(), errorCode.toString())
+      }
+      // 单次识别失败时系统界面会提示重试，不在这里结束
+    }
+
+    activity.runOnUiThread {
+      val prompt = BiometricPrompt(
+        activity as FragmentActivity,
+        ContextCompat.getMainExecutor(activity),
+        callback
+      )
+      // BIOMETRIC_WEAK 与锁屏密码的组合在所有支持的系统版本上可用
+      val info = BiometricPrompt.PromptInfo.Builder()
+        .setTitle(args.reason)
+        .setAllowedAuthenticators(Authenticators.BIOMETRIC_WEAK or Authenticators.DEVICE_CREDENTIAL)
+        .build()
+      prompt.authenticate(info)
+    }
+  }
+}
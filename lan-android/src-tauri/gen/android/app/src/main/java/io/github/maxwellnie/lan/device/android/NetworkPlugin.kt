@@ -0,0 +1,63 @@
+package io.github.maxwellnie.lan.device.android
+
+import android.app.Activity
+import android.content.Context
+import android.net.ConnectivityManager
+import android.net.Network
+import android.net.NetworkCapabilities
+import app.tauri.annotation.Command
+import app.tauri.annotation.InvokeArg
+import app.tauri.annotation.TauriPlugin
+import app.tauri.plugin.Channel
+import app.tauri.plugin.Invoke
+import app.tauri.plugin.JSObject
+import app.tauri.plugin.Plugin
+
+@InvokeArg
+class WatchNetworkArgs {
+  lateinit var channel: Channel
+}
+
+// 监听默认网络的变化（切换 Wi-Fi、断开、连上移动数据），通知 Rust 端重启设备发现
+@TauriPlugin
+class NetworkPlugin(private val activity: Activity) : Plugin(activity) {
+  private val connectivity =
+    activity.getSystemService(Context.CONNECTIVITY_SERVICE) as ConnectivityManager
+  private var callback: ConnectivityManager.NetworkCallback? = null
+  private var lastNetwork: Long? = null
+
+  @Command
+  fun watchNetwork(invoke: Invoke) {
+    val args = invoke.parseArgs(WatchNetworkArgs::class.java)
+    callback?.let { connectivity.unregisterNetworkCallback(it) }
+    lastNetwork = null
+
+    val networkCallback = object : ConnectivityManager.NetworkCallback() {
+      override fun onCapabilitiesChanged(network: Network, capabilities: NetworkCapabilities) {
+        // 同一网络的能力变化（信号强度等）会重复回调，只在网络本身变化时通知
+        if (lastNetwork == network.networkHandle) {
+          return
+        }
+        lastNetwork = network.networkHandle
+        val event = JSObject()
+        event.put("connected", true)
+        event.put("wifi", capabilities.hasTransport(NetworkCapabilities.TRANSPORT_WIFI))
+        args.channel.send(event)
+      }
+
+      override fun onLost(network: Network) {
+        if (lastNetwork != network.networkHandle) {
+          return
+        }
+        lastNetwork = null
+        val event = JSObject()
+        event.put("connected", false)
+        event.put("wifi", false)
+        args.channel.send(event)
+      }
+    }
+    connectivity.registerDefaultNetworkCallback(networkCallback)
+    callback = networkCallback
+    invoke.resolve()
+  }
+}
@@ -1,54 +1,416 @@
-use reqwest::Client;
-use std::time::Duration;
+use reqwest::header::{ACCEPT_LANGUAGE, CONTENT_TYPE};
+use reqwest::{Client, Method, RequestBuilder};
+use serde::de::DeserializeOwned;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::handshake::client::Request as WsRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use zeroize::Zeroizing;
 
 use crate::models::{
-    ApiResponse, AuthChallenge, AuthRequest, AuthResponse, AuthResult,
-    CommandResult, SystemInfo,
+    ApiResponse, AppUpdateStatus, AuthChallenge, AuthRequest, AuthResponse, AuthResult,
+    CatalogEntry, ClipboardText, CommandResult, ConnectionProfile, DeviceStatus, EventBatch,
+    FileUploadProgress, HandshakeRequest, HandshakeResponse, PairConfirmResponse,
+    PairStartResponse, RemoteFile, SpeedTestResult, SpeedTestUpload, SystemInfo, Transport,
 };
-use crate::crypto::calculate_hmac;
+use crate::error::ApiError;
+use crate::crypto::{
+    calculate_hmac, decrypt_payload, derive_payload_key, derive_verifier, encrypt_payload,
+    pairing_confirmation, payload_aad, start_pairing, verify_pairing_confirmation, FrameCodec,
+    PayloadKey, AAD_REQUEST, AAD_REQUEST_BODY, AAD_RESPONSE, PAYLOAD_CIPHER,
+};
+
+/// 客户端支持的协议版本
+pub const PROTOCOL_VERSION: u32 = 1;
+/// 时钟偏差超过该值时提示用户（毫秒）
+pub const CLOCK_SKEW_WARN_MS: i64 = 30_000;
+
+/// 默认请求超时（秒），局域网内足够
+const DEFAULT_TIMEOUT_SECS: u64 = 12;
+/// 加密的查询请求头，与服务端 payload 模块一致
+const PAYLOAD_HEADER: &str = "x-payload-request";
+/// 加密响应中原始的 Content-Type
+const PAYLOAD_CONTENT_TYPE_HEADER: &str = "x-payload-content-type";
 
+/// 按挑战中的参数派生登录验证器
+fn challenge_verifier(
+    challenge: &AuthChallenge,
+    password: &str,
+) -> Result<Zeroizing<[u8; 32]>, String> {
+    match (&challenge.salt, challenge.iterations) {
+        (Some(salt), Some(iterations)) => derive_verifier(password, salt, iterations),
+        _ => Err(
+            "Device did not offer a password verifier, update it or set its password again"
+                .to_string(),
+        ),
+    }
+}
+
+// 所有设备共用的 HTTP 客户端，复用连接池，刷新设备列表时不必每次重新建立连接
+static SHARED_CLIENT: OnceLock<Client> = OnceLock::new();
+// 接受无效证书的设备单独使用一个客户端，不影响其他设备的证书校验
+static INSECURE_CLIENT: OnceLock<Client> = OnceLock::new();
+
+fn shared_client(accept_invalid_certs: bool) -> Client {
+    let cell = if accept_invalid_certs {
+        &INSECURE_CLIENT
+    } else {
+        &SHARED_CLIENT
+    };
+    cell.get_or_init(|| {
+        Client::builder()
+            .pool_idle_timeout(Duration::from_secs(90))
+            .danger_accept_invalid_certs(accept_invalid_certs)
+            .build()
+            .expect("Failed to create HTTP client")
+    })
+    .clone()
+}
+
+#[derive(Clone)]
 pub struct ApiClient {
     client: Client,
+    /// 单个请求的超时，各设备可以不同
+    timeout: Duration,
     base_url: String,
-    token: Option<String>,
+    token: Option<Zeroizing<String>>,
+    /// 最近一次握手测得的时钟偏差（毫秒）
+    clock_skew_ms: Option<i64>,
+    /// 接收事件时优先使用的传输方式
+    transport: Transport,
+    /// 登录时协商的负载加密会话，未使用 TLS 时加密命令和响应
+    payload_session: Option<PayloadSession>,
+}
+
+/// 协商成功的负载加密会话
+#[derive(Clone)]
+struct PayloadSession {
+    /// 服务端分配的密钥ID，加密请求以它代替明文令牌
+    key_id: String,
+    key: PayloadKey,
+    /// 请求计数，服务端拒绝重复的计数；克隆的客户端共用同一个计数
+    counter: Arc<AtomicU64>,
+}
+
+impl PayloadSession {
+    fn next_counter(&self) -> u64 {
+        self.counter.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    /// 把地址中的查询参数（含令牌）加密为 PAYLOAD_HEADER 请求头的值，并从地址中移除
+    fn seal_query(
+        &self,
+        method: &str,
+        path: &str,
+        counter: u64,
+        url: &mut reqwest::Url,
+        encrypted_body: bool,
+    ) -> Result<String, ApiError> {
+        let query = serde_json::json!({
+            "query": url.query().unwrap_or_default(),
+            "encrypted_body": encrypted_body,
+        });
+        url.set_query(None);
+        let aad = payload_aad(AAD_REQUEST, method, path, counter);
+        let payload = encrypt_payload(&self.key, &aad, query.to_string().as_bytes())?;
+        Ok(serde_json::json!({
+            "key_id": self.key_id,
+            "counter": counter,
+            "payload": payload,
+        })
+        .to_string())
+    }
+}
+
+/// 查询请求的请求体
+enum QueryBody {
+    None,
+    /// 协商了负载加密时加密发送，如上传的文件
+    Private(Vec<u8>),
+    /// 始终明文发送，如网速测试的随机数据
+    Public(Vec<u8>),
+}
+
+/// 查询请求的响应，加密的响应已解密，Content-Type 为原始类型
+struct QueryResponse {
+    status: reqwest::StatusCode,
+    headers: reqwest::header::HeaderMap,
+    body: Vec<u8>,
+}
+
+impl QueryResponse {
+    fn content_type(&self) -> Option<&str> {
+        self.headers.get(CONTENT_TYPE).and_then(|v| v.to_str().ok())
+    }
+
+    fn json<T: DeserializeOwned>(&self) -> Result<T, ApiError> {
+        serde_json::from_slice(&self.body)
+            .map_err(|e| ApiError::Other(format!("Failed to parse response: {}", e)))
+    }
+}
+
+/// 连接使用的协议
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Scheme {
+    #[default]
+    Http,
+    /// HTTPS，accept_invalid_certs 为 true 时接受自签名证书
+    Https { accept_invalid_certs: bool },
+}
+
+impl Scheme {
+    fn from_profile(profile: &ConnectionProfile) -> Self {
+        if profile.tls {
+            Scheme::Https {
+                accept_invalid_certs: profile.accept_invalid_certs,
+            }
+        } else {
+            Scheme::Http
+        }
+    }
+}
+
+/// 由主机和端口得到服务地址
+///
+/// host 可以是 IP 地址（包括 IPv6）、主机名（如 desktop.home.lan），
+/// 也可以是带协议和端口的完整地址（如 https://desktop.home.lan:8443），其中的协议和端口优先
+pub fn base_url(host: &str, port: u16, scheme: Scheme) -> Result<String, String> {
+    let host = host.trim().trim_end_matches('/');
+    if host.is_empty() {
+        return Err("Host must not be empty".to_string());
+    }
+    let default_scheme = match scheme {
+        Scheme::Http => "http",
+        Scheme::Https { .. } => "https",
+    };
+    let candidate = if host.contains("://") {
+        host.to_string()
+    } else if host.parse::<std::net::Ipv6Addr>().is_ok() {
+        format!("{}://[{}]", default_scheme, host)
+    } else {
+        format!("{}://{}", default_scheme, host)
+    };
+
+    let url = reqwest::Url::parse(&candidate).map_err(|e| format!("Invalid host '{}': {}", host, e))?;
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err(format!("Unsupported scheme '{}'", url.scheme()));
+    }
+    if url.path() != "/" || url.query().is_some() {
+        return Err(format!("Host '{}' must not contain a path", host));
+    }
+    let hostname = url
+        .host_str()
+        .ok_or_else(|| format!("Invalid host '{}'", host))?;
+    Ok(format!(
+        "{}://{}:{}",
+        url.scheme(),
+        hostname,
+        url.port().unwrap_or(port)
+    ))
 }
 
 impl ApiClient {
-    pub fn new(ip: &str, port: u16) -> Self {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(12)) // 局域网内12秒超时
-            .build()
-            .expect("Failed to create HTTP client");
-        
+    pub fn new(host: &str, port: u16, scheme: Scheme) -> Self {
+        let profile = ConnectionProfile {
+            tls: matches!(scheme, Scheme::Https { .. }),
+            accept_invalid_certs: matches!(
+                scheme,
+                Scheme::Https {
+                    accept_invalid_certs: true
+                }
+            ),
+            ..ConnectionProfile::default()
+        };
+        Self::with_profile(host, port, &profile)
+    }
+
+    /// 按设备的连接设置创建客户端，host 的格式见 base_url
+    ///
+    /// 底层连接池在所有客户端间共享，创建客户端的开销很小
+    pub fn with_profile(host: &str, port: u16, profile: &ConnectionProfile) -> Self {
+        let timeout = profile.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS).max(1);
+        let scheme = Scheme::from_profile(profile);
+        let client = shared_client(matches!(
+            scheme,
+            Scheme::Https {
+                accept_invalid_certs: true
+            }
+        ));
+
+        let port = profile.port_override.unwrap_or(port);
+        // 地址无效时保留原样，请求时返回连接错误
+        let base_url = base_url(host, port, scheme).unwrap_or_else(|e| {
+            log::warn!("{}", e);
+            let scheme = if profile.tls { "https" } else { "http" };
+            format!("{}://{}:{}", scheme, host, port)
+        });
         Self {
             client,
-            base_url: format!("http://{}:{}", ip, port),
+            timeout: Duration::from_secs(timeout),
+            base_url,
             token: None,
+            clock_skew_ms: None,
+            transport: profile.transport,
+            payload_session: None,
+        }
+    }
+
+    /// 带超时和语言的请求，服务端按该语言返回错误消息
+    fn request(&self, method: reqwest::Method, url: &str) -> RequestBuilder {
+        self.client
+            .request(method, url)
+            .timeout(self.timeout)
+            .header(ACCEPT_LANGUAGE, crate::state::client_language())
+    }
+
+    fn get(&self, url: &str) -> RequestBuilder {
+        self.request(reqwest::Method::GET, url)
+    }
+
+    fn post(&self, url: &str) -> RequestBuilder {
+        self.request(reqwest::Method::POST, url)
+    }
+
+    /// 服务地址，如 https://desktop.home.lan:8443
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub fn transport(&self) -> Transport {
+        self.transport
+    }
+
+    /// 在地址上附加查询参数，with_token 为 true 时带上令牌（如果有）
+    fn append_query(&self, url: &mut reqwest::Url, query: &[(&str, &str)], with_token: bool) {
+        let token = self.token.as_deref().filter(|_| with_token);
+        if query.is_empty() && token.is_none() {
+            return;
+        }
+        let mut pairs = url.query_pairs_mut();
+        pairs.extend_pairs(query);
+        if let Some(token) = token {
+            pairs.append_pair("token", token);
+        }
+    }
+
+    /// WebSocket 的升级请求，与 HTTP 地址使用相同的主机、端口和加密方式
+    ///
+    /// 协商了负载加密时令牌加密后放在 PAYLOAD_HEADER 请求头中，服务端据此完成认证，之后的文本帧
+    /// 用返回的 FrameCodec 加解密；否则 query_token 为 true 时令牌作为查询参数发送，
+    /// 为 false 时由调用方在连接后发送认证消息
+    pub fn ws_request(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+        query_token: bool,
+    ) -> Result<(WsRequest, FrameCodec), ApiError> {
+        let address = self
+            .base_url
+            .strip_prefix("https://")
+            .map(|rest| format!("wss://{}", rest))
+            .or_else(|| self.base_url.strip_prefix("http://").map(|rest| format!("ws://{}", rest)))
+            .unwrap_or_else(|| self.base_url.clone());
+        let mut url = reqwest::Url::parse(&format!("{}{}", address, path))
+            .map_err(|e| ApiError::Other(format!("Invalid device address: {}", e)))?;
+        let session = self.payload_session.as_ref().filter(|_| self.token.is_some());
+        self.append_query(&mut url, query, query_token || session.is_some());
+
+        let mut header = None;
+        let mut codec = FrameCodec::default();
+        if let Some(session) = session {
+            let counter = session.next_counter();
+            header = Some(session.seal_query("GET", path, counter, &mut url, false)?);
+            codec = FrameCodec::encrypted(&session.key, path);
         }
+        let mut request = url
+            .as_str()
+            .into_client_request()
+            .map_err(|e| ApiError::Other(format!("Invalid device address: {}", e)))?;
+        if let Some(header) = header {
+            let value = HeaderValue::from_str(&header)
+                .map_err(|e| ApiError::Other(format!("Invalid request header: {}", e)))?;
+            request.headers_mut().insert(PAYLOAD_HEADER, value);
+        }
+        Ok((request, codec))
     }
     
     /// 健康检查
-    pub async fn health_check(&self) -> Result<bool, String> {
+    pub async fn health_check(&self) -> Result<bool, ApiError> {
         let url = format!("{}/api/health", self.base_url);
-        match self.client.get(&url).send().await {
+        match self.get(&url).send().await {
+            // 服务端要求健康检查密钥时返回 404，改用无需认证的 auth/check 确认服务在线
+            Ok(response) if response.status() == reqwest::StatusCode::NOT_FOUND => {
+                let url = format!("{}/api/auth/check", self.base_url);
+                match self.get(&url).send().await {
+                    Ok(response) => Ok(response.status().is_success()),
+                    Err(e) => Err(e.into()),
+                }
+            }
             Ok(response) => Ok(response.status().is_success()),
-            Err(e) => Err(format!("Request failed: {}", e)),
+            Err(e) => Err(e.into()),
         }
     }
     
+    /// 协议握手，交换协议版本并测量时钟偏差
+    pub async fn handshake(&mut self) -> Result<HandshakeResponse, ApiError> {
+        let url = format!("{}/api/handshake", self.base_url);
+        let sent_at = chrono::Utc::now().timestamp_millis();
+        let request = HandshakeRequest {
+            protocol_version: PROTOCOL_VERSION,
+            client_time: sent_at,
+            client_version: env!("CARGO_PKG_VERSION").to_string(),
+        };
+        let response = self
+            .post(&url)
+            .json(&request)
+            .send()
+            .await?;
+        let received_at = chrono::Utc::now().timestamp_millis();
+
+        let api_response: ApiResponse<HandshakeResponse> = response
+            .json()
+            .await?;
+
+        match api_response.data {
+            Some(handshake) if api_response.success => {
+                // 以往返时间的中点作为服务端记录时间的估计，扣除网络延迟
+                let midpoint = sent_at + (received_at - sent_at) / 2;
+                let skew = midpoint - handshake.server_time;
+                self.clock_skew_ms = Some(skew);
+                if skew.abs() > CLOCK_SKEW_WARN_MS {
+                    log::warn!("Clock differs from {} by {} ms", self.base_url, skew);
+                }
+                if !handshake.compatible {
+                    log::warn!(
+                        "Protocol version {} not supported by {} (server {})",
+                        PROTOCOL_VERSION,
+                        self.base_url,
+                        handshake.protocol_version
+                    );
+                }
+                Ok(handshake)
+            }
+            _ => Err(api_response.into()),
+        }
+    }
+
+    pub fn clock_skew_ms(&self) -> Option<i64> {
+        self.clock_skew_ms
+    }
+
     /// 检查是否需要认证
-    pub async fn check_auth_required(&self) -> Result<bool, String> {
+    pub async fn check_auth_required(&self) -> Result<bool, ApiError> {
         let url = format!("{}/api/auth/check", self.base_url);
-        let response = self.client
+        let response = self
             .get(&url)
             .send()
-            .await
-            .map_err(|e| format!("Request failed: {}", e))?;
+            .await?;
         
         let api_response: ApiResponse<serde_json::Value> = response
             .json()
-            .await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
+            .await?;
         
         if api_response.success {
             // 解析 requires_auth 字段
@@ -64,59 +426,88 @@ impl ApiClient {
         }
     }
     
-    /// 获取认证挑战
-    pub async fn get_challenge(&self) -> Result<String, String> {
+    /// 获取认证挑战及派生验证器的参数
+    pub async fn get_challenge(&self) -> Result<AuthChallenge, ApiError> {
         let url = format!("{}/api/auth/challenge", self.base_url);
-        let response = self.client
+        let response = self
             .post(&url)
             .json(&serde_json::json!({}))
             .send()
-            .await
-            .map_err(|e| format!("Request failed: {}", e))?;
+            .await?;
         
         let api_response: ApiResponse<AuthChallenge> = response
             .json()
-            .await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
+            .await?;
         
         if api_response.success {
-            Ok(api_response.data.unwrap().challenge)
+            Ok(api_response.data.unwrap())
         } else {
-            Err(api_response.error.unwrap_or_else(|| "Unknown error".to_string()))
+            Err(api_response.into())
         }
     }
     
     /// 认证
-    pub async fn authenticate(&mut self, password: &str) -> Result<AuthResult, String> {
+    pub async fn authenticate(&mut self, password: &str) -> Result<AuthResult, ApiError> {
         // 获取挑战
         let challenge = self.get_challenge().await?;
         
-        // 计算响应
-        let response = calculate_hmac(&challenge, password);
+        // 以验证器为密钥计算响应，密码本身不发送
+        let verifier = challenge_verifier(&challenge, password)?;
+        self.login(challenge.challenge, verifier.as_ref(), false).await
+    }
+
+    /// 使用配对密钥登录，无需密码
+    pub async fn authenticate_paired(&mut self, key: &[u8]) -> Result<AuthResult, ApiError> {
+        let challenge = self.get_challenge().await?;
+        self.login(challenge.challenge, key, true).await
+    }
+
+    /// 以 secret（验证器或配对密钥）对挑战计算响应并登录
+    async fn login(
+        &mut self,
+        challenge: String,
+        secret: &[u8],
+        paired: bool,
+    ) -> Result<AuthResult, ApiError> {
+        let response = calculate_hmac(&challenge, secret);
         
         // 发送认证请求
         let url = format!("{}/api/auth/login", self.base_url);
+        // 没有 TLS 时请求负载加密，密钥只在双方本地派生
+        let encrypt = !self.base_url.starts_with("https://");
+        let payload_key = encrypt.then(|| derive_payload_key(secret, &challenge));
         let auth_request = AuthRequest {
             challenge,
             response,
-            password: password.to_string(),
+            device_id: crate::state::client_device_id(),
+            encryption: encrypt.then(|| PAYLOAD_CIPHER.to_string()),
+            paired,
         };
         
-        let api_response = self.client
+        let api_response = self
             .post(&url)
             .json(&auth_request)
             .send()
-            .await
-            .map_err(|e| format!("Request failed: {}", e))?;
+            .await?;
         
         let auth_response: ApiResponse<AuthResponse> = api_response
             .json()
-            .await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
+            .await?;
         
         if auth_response.success {
             let data = auth_response.data.unwrap();
-            self.token = Some(data.token.clone());
+            self.token = Some(Zeroizing::new(data.token.clone()));
+            // 旧版本服务端不支持加密，继续使用明文
+            self.payload_session = match (payload_key, data.key_id) {
+                (Some(key), Some(key_id)) if data.encryption.as_deref() == Some(PAYLOAD_CIPHER) => {
+                    Some(PayloadSession {
+                        key_id,
+                        key,
+                        counter: Arc::new(AtomicU64::new(0)),
+                    })
+                }
+                _ => None,
+            };
             Ok(AuthResult {
                 success: true,
                 token: Some(data.token),
@@ -128,208 +519,624 @@ impl ApiClient {
                 success: false,
                 token: None,
                 expires_in: None,
-                error: auth_response.error,
+                error: Some(auth_response.error_text()),
             })
         }
     }
+
+    /// 通过 SPAKE2 与设备配对，返回保存在本机的配对密钥
+    ///
+    /// 配对过程中不发送任何可用于离线猜测密码的数据，之后用配对密钥登录
+    pub async fn pair(&self, password: &str) -> Result<Zeroizing<Vec<u8>>, ApiError> {
+        let challenge = self.get_challenge().await?;
+        let verifier = challenge_verifier(&challenge, password)?;
+        let device_id = crate::state::client_device_id();
+        let (spake, message) = start_pairing(verifier.as_ref(), &device_id);
+
+        let url = format!("{}/api/pair/start", self.base_url);
+        let response = self
+            .post(&url)
+            .json(&serde_json::json!({
+                "device_id": device_id,
+                "message": hex::encode(message),
+            }))
+            .send()
+            .await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(ApiError::Unsupported(
+                "Pairing is not supported by this device".to_string(),
+            ));
+        }
+        let api_response: ApiResponse<PairStartResponse> = response
+            .json()
+            .await?;
+        let start = match api_response.data {
+            Some(start) if api_response.success => start,
+            _ => return Err(api_response.into()),
+        };
+
+        let reply = hex::decode(&start.message)
+            .map_err(|_| "Invalid pairing message".to_string())?;
+        let key = Zeroizing::new(
+            spake.finish(&reply).map_err(|_| "Invalid pairing message".to_string())?,
+        );
+
+        let url = format!("{}/api/pair/confirm", self.base_url);
+        let response = self
+            .post(&url)
+            .json(&serde_json::json!({
+                "pairing_id": start.pairing_id,
+                "proof": hex::encode(pairing_confirmation(&key, "client", &start.pairing_id)),
+            }))
+            .send()
+            .await?;
+        let api_response: ApiResponse<PairConfirmResponse> = response
+            .json()
+            .await?;
+        let confirm = match api_response.data {
+            Some(confirm) if api_response.success => confirm,
+            _ => return Err(api_response.into()),
+        };
+
+        // 确认对方也得到了同一个密钥，防止冒充的设备
+        let proof = hex::decode(&confirm.proof).unwrap_or_default();
+        if !verify_pairing_confirmation(&key, "server", &start.pairing_id, &proof) {
+            return Err(ApiError::Other("Device failed to confirm pairing".to_string()));
+        }
+        Ok(key)
+    }
     
     /// 获取系统信息
-    pub async fn get_system_info(&self) -> Result<SystemInfo, String> {
-        let url = format!("{}/api/system/info", self.base_url);
-        
-        // 构建请求，如果有token则添加
-        let mut request = self.client.get(&url);
-        if let Some(ref token) = self.token {
-            request = request.query(&[("token", token)]);
-        }
-        
+    pub async fn get_system_info(&self) -> Result<SystemInfo, ApiError> {
+        let path = "/api/system/info";
+        let (request, counter) = self.query_request(Method::GET, path, &[], QueryBody::None)?;
         let response = request
             .send()
-            .await
-            .map_err(|e| format!("Request failed: {}", e))?;
+            .await?;
         
-        let api_response: ApiResponse<SystemInfo> = response
-            .json()
-            .await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
+        let api_response: ApiResponse<SystemInfo> = self
+            .query_response(response, "GET", path, counter)
+            .await?
+            .json()?;
         
         if api_response.success {
             Ok(api_response.data.unwrap())
         } else {
-            Err(api_response.error.unwrap_or_else(|| "Unknown error".to_string()))
+            Err(api_response.into())
         }
     }
     
+    /// 获取设备端程序的更新状态
+    pub async fn get_app_update_status(&self) -> Result<AppUpdateStatus, ApiError> {
+        let path = "/api/app/update";
+        let (request, counter) = self.query_request(Method::GET, path, &[], QueryBody::None)?;
+        let response = request
+            .send()
+            .await?;
+
+        let api_response: ApiResponse<AppUpdateStatus> = self
+            .query_response(response, "GET", path, counter)
+            .await?
+            .json()?;
+
+        match api_response.data {
+            Some(status) if api_response.success => Ok(status),
+            _ => Err(api_response.into()),
+        }
+    }
+
+    /// 设备状态：系统信息、时钟偏差和可用更新
+    pub async fn device_status(&self) -> Result<DeviceStatus, ApiError> {
+        let info = self.get_system_info().await?;
+        // 旧版本服务端不支持更新状态查询，失败时忽略
+        let available_update = self
+            .get_app_update_status()
+            .await
+            .ok()
+            .filter(|status| status.update_available)
+            .and_then(|status| status.latest_version);
+        Ok(DeviceStatus {
+            online: true,
+            cpu_usage: info.cpu_usage,
+            memory_usage: info.memory_used,
+            uptime: info.uptime_seconds,
+            os_type: info.os_type,
+            os_version: info.os_version,
+            clock_skew_ms: self.clock_skew_ms(),
+            available_update,
+        })
+    }
+
+    /// 通过设备同步保存的设备目录，返回合并后的完整目录
+    pub async fn sync_devices(&self, devices: Vec<CatalogEntry>) -> Result<Vec<CatalogEntry>, ApiError> {
+        let body = serde_json::json!({
+            "devices": devices,
+        });
+        let api_response: ApiResponse<Vec<CatalogEntry>> =
+            self.post_json("/api/sync/devices", body).await?;
+
+        match api_response.data {
+            Some(catalog) if api_response.success => Ok(catalog),
+            _ => Err(api_response.into()),
+        }
+    }
+
+    /// 长轮询服务端事件，since 为空时只返回当前游标
+    ///
+    /// 服务端没有新事件时最多等待 wait_secs 秒
+    pub async fn poll_events(&self, since: Option<u64>, wait_secs: u64) -> Result<EventBatch, ApiError> {
+        let path = "/api/events/poll";
+        let wait = wait_secs.to_string();
+        let since = since.map(|since| since.to_string());
+        let mut query = vec![("timeout", wait.as_str())];
+        if let Some(ref since) = since {
+            query.push(("since", since));
+        }
+
+        let (request, counter) = self.query_request(Method::GET, path, &query, QueryBody::None)?;
+        let response = request
+            // 请求会在服务端挂起，超时需要比等待时间更长
+            .timeout(Duration::from_secs(wait_secs + 10))
+            .send()
+            .await?;
+        let response = self.query_response(response, "GET", path, counter).await?;
+
+        if response.status == reqwest::StatusCode::NOT_FOUND {
+            return Err(ApiError::Unsupported(
+                "Event polling is not supported by this device".to_string(),
+            ));
+        }
+
+        let api_response: ApiResponse<EventBatch> = response.json()?;
+
+        match api_response.data {
+            Some(batch) if api_response.success => Ok(batch),
+            _ => Err(api_response.into()),
+        }
+    }
+
+    /// 发送带令牌的 POST 请求，协商了负载加密时加密请求体并解密响应
+    async fn post_json<T: serde::de::DeserializeOwned>(
+        &self,
+        path: &str,
+        body: serde_json::Value,
+    ) -> Result<ApiResponse<T>, ApiError> {
+        let token = self.token.as_deref()
+            .ok_or(ApiError::AuthRequired)?;
+        let url = format!("{}{}", self.base_url, path);
+
+        let Some(ref session) = self.payload_session else {
+            let mut body = body;
+            body["token"] = serde_json::Value::from(token.as_str());
+            let response = self
+                .post(&url)
+                .json(&body)
+                .send()
+                .await?;
+            return response
+                .json()
+                .await
+                .map_err(ApiError::from);
+        };
+
+        // 外层只有密钥ID和计数，令牌和其余字段只以密文传输
+        let counter = session.next_counter();
+        let mut body = body;
+        body["token"] = serde_json::Value::from(token.as_str());
+        let aad = payload_aad(AAD_REQUEST, "POST", path, counter);
+        let payload = encrypt_payload(&session.key, &aad, body.to_string().as_bytes())?;
+        let response = self
+            .post(&url)
+            .json(&serde_json::json!({
+                "key_id": session.key_id,
+                "counter": counter,
+                "payload": payload,
+            }))
+            .send()
+            .await?;
+        let envelope: serde_json::Value = response
+            .json()
+            .await?;
+        match envelope.get("payload") {
+            Some(payload) => {
+                let aad = payload_aad(AAD_RESPONSE, "POST", path, counter);
+                let plaintext = decrypt_payload(&session.key, &aad, payload)?;
+                serde_json::from_slice(&plaintext)
+                    .map_err(|e| ApiError::Other(format!("Failed to parse response: {}", e)))
+            }
+            // 服务端在解密前拒绝的请求以明文返回错误
+            None => serde_json::from_value(envelope)
+                .map_err(|e| ApiError::Other(format!("Failed to parse response: {}", e))),
+        }
+    }
+
     /// 执行命令
     pub async fn execute_command(
         &self,
         command: &str,
         args: Option<Vec<String>>,
-    ) -> Result<CommandResult, String> {
-        let token = self.token.as_ref()
-            .ok_or_else(|| "Not authenticated".to_string())?;
-        
-        let url = format!("{}/api/command/execute", self.base_url);
+    ) -> Result<CommandResult, ApiError> {
         let body = serde_json::json!({
-            "token": token,
             "command": command,
             "args": args,
         });
-        
-        let response = self.client
-            .post(&url)
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| format!("Request failed: {}", e))?;
-        
-        let api_response: ApiResponse<CommandResult> = response
-            .json()
-            .await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
+        let api_response: ApiResponse<CommandResult> =
+            self.post_json("/api/command/execute", body).await?;
         
         if api_response.success {
             Ok(api_response.data.unwrap())
         } else {
-            Err(api_response.error.unwrap_or_else(|| "Unknown error".to_string()))
+            Err(api_response.into())
         }
     }
     
     /// 关机
-    pub async fn shutdown(&self, delay: Option<u32>) -> Result<CommandResult, String> {
-        let token = self.token.as_ref()
-            .ok_or_else(|| "Not authenticated".to_string())?;
-        
-        let url = format!("{}/api/system/shutdown", self.base_url);
+    pub async fn shutdown(&self, delay: Option<u32>) -> Result<CommandResult, ApiError> {
         let args = delay.map(|d| vec![d.to_string()]);
         let body = serde_json::json!({
-            "token": token,
             "command": "shutdown",
             "args": args,
         });
-        
-        let response = self.client
-            .post(&url)
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| format!("Request failed: {}", e))?;
-        
-        let api_response: ApiResponse<CommandResult> = response
-            .json()
-            .await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
+        let api_response: ApiResponse<CommandResult> =
+            self.post_json("/api/system/shutdown", body).await?;
         
         if api_response.success {
             Ok(api_response.data.unwrap())
         } else {
-            Err(api_response.error.unwrap_or_else(|| "Unknown error".to_string()))
+            Err(api_response.into())
         }
     }
     
     /// 重启
-    pub async fn restart(&self, delay: Option<u32>) -> Result<CommandResult, String> {
-        let token = self.token.as_ref()
-            .ok_or_else(|| "Not authenticated".to_string())?;
-        
-        let url = format!("{}/api/system/restart", self.base_url);
+    pub async fn restart(&self, delay: Option<u32>) -> Result<CommandResult, ApiError> {
         let args = delay.map(|d| vec![d.to_string()]);
         let body = serde_json::json!({
-            "token": token,
             "command": "restart",
             "args": args,
         });
-        
-        let response = self.client
-            .post(&url)
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| format!("Request failed: {}", e))?;
-        
-        let api_response: ApiResponse<CommandResult> = response
-            .json()
-            .await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
+        let api_response: ApiResponse<CommandResult> =
+            self.post_json("/api/system/restart", body).await?;
         
         if api_response.success {
             Ok(api_response.data.unwrap())
         } else {
-            Err(api_response.error.unwrap_or_else(|| "Unknown error".to_string()))
+            Err(api_response.into())
         }
     }
     
     /// 睡眠
-    pub async fn sleep(&self) -> Result<CommandResult, String> {
-        let token = self.token.as_ref()
-            .ok_or_else(|| "Not authenticated".to_string())?;
-        
-        let url = format!("{}/api/system/sleep", self.base_url);
+    pub async fn sleep(&self) -> Result<CommandResult, ApiError> {
         let body = serde_json::json!({
-            "token": token,
             "command": "sleep",
             "args": null,
         });
-        
-        let response = self.client
-            .post(&url)
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| format!("Request failed: {}", e))?;
-        
-        let api_response: ApiResponse<CommandResult> = response
-            .json()
-            .await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
+        let api_response: ApiResponse<CommandResult> =
+            self.post_json("/api/system/sleep", body).await?;
         
         if api_response.success {
             Ok(api_response.data.unwrap())
         } else {
-            Err(api_response.error.unwrap_or_else(|| "Unknown error".to_string()))
+            Err(api_response.into())
         }
     }
     
     /// 锁屏
-    pub async fn lock(&self) -> Result<CommandResult, String> {
-        let token = self.token.as_ref()
-            .ok_or_else(|| "Not authenticated".to_string())?;
-        
-        let url = format!("{}/api/system/lock", self.base_url);
+    pub async fn lock(&self) -> Result<CommandResult, ApiError> {
         let body = serde_json::json!({
-            "token": token,
             "command": "lock",
             "args": null,
         });
-        
-        let response = self.client
-            .post(&url)
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| format!("Request failed: {}", e))?;
-        
-        let api_response: ApiResponse<CommandResult> = response
-            .json()
-            .await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
+        let api_response: ApiResponse<CommandResult> =
+            self.post_json("/api/system/lock", body).await?;
         
         if api_response.success {
             Ok(api_response.data.unwrap())
         } else {
-            Err(api_response.error.unwrap_or_else(|| "Unknown error".to_string()))
+            Err(api_response.into())
         }
     }
     
+    /// 测试与设备之间的吞吐量：先下载再上传指定大小的数据
+    pub async fn speed_test(&self, bytes: u64) -> Result<SpeedTestResult, ApiError> {
+        if self.token.is_none() {
+            return Err(ApiError::AuthRequired);
+        }
+        // 大数据量传输需要比普通请求更长的超时
+        let timeout = Duration::from_secs(60);
+
+        // 下载，随机数据在加密会话中同样以明文返回
+        let path = "/api/network/speedtest/download";
+        let started = Instant::now();
+        let bytes = bytes.to_string();
+        let (request, counter) =
+            self.query_request(Method::GET, path, &[("bytes", &bytes)], QueryBody::None)?;
+        let mut response = request
+            .timeout(timeout)
+            .send()
+            .await?;
+
+        if response.headers().get("content-type").and_then(|v| v.to_str().ok())
+            != Some("application/octet-stream")
+        {
+            let api_response: ApiResponse<serde_json::Value> = self
+                .query_response(response, "GET", path, counter)
+                .await?
+                .json()?;
+            return Err(api_response.into());
+        }
+
+        let mut downloaded = 0u64;
+        while let Some(chunk) = response.chunk().await? {
+            downloaded += chunk.len() as u64;
+        }
+        let download_ms = started.elapsed().as_millis().max(1) as u64;
+
+        // 上传，使用服务端计时以排除建立连接的开销
+        let path = "/api/network/speedtest/upload";
+        let body = QueryBody::Public(vec![0u8; downloaded as usize]);
+        let (request, counter) = self.query_request(Method::POST, path, &[], body)?;
+        let response = request
+            .timeout(timeout)
+            .send()
+            .await?;
+
+        let api_response: ApiResponse<SpeedTestUpload> = self
+            .query_response(response, "POST", path, counter)
+            .await?
+            .json()?;
+        let upload = match api_response.data {
+            Some(upload) if api_response.success => upload,
+            _ => return Err(api_response.into()),
+        };
+        let upload_ms = upload.duration_ms.max(1);
+
+        let mbps = |bytes: u64, ms: u64| bytes as f64 * 8.0 / 1_000_000.0 / (ms as f64 / 1000.0);
+        Ok(SpeedTestResult {
+            bytes: downloaded,
+            download_ms,
+            upload_ms,
+            download_mbps: mbps(downloaded, download_ms),
+            upload_mbps: mbps(upload.bytes, upload_ms),
+        })
+    }
+
+    /// 带上令牌（如果有）的查询请求，返回请求和用于解密响应的计数
+    ///
+    /// 未设置密码的设备不需要令牌。协商了负载加密时查询参数（含令牌）加密后放在
+    /// PAYLOAD_HEADER 请求头中，QueryBody::Private 的请求体一并加密
+    fn query_request(
+        &self,
+        method: Method,
+        path: &str,
+        query: &[(&str, &str)],
+        body: QueryBody,
+    ) -> Result<(RequestBuilder, Option<u64>), ApiError> {
+        let mut url = reqwest::Url::parse(&format!("{}{}", self.base_url, path))
+            .map_err(|e| ApiError::Other(format!("Invalid device address: {}", e)))?;
+        self.append_query(&mut url, query, true);
+        let session = self.payload_session.as_ref().filter(|_| self.token.is_some());
+        let Some(session) = session else {
+            let request = self.request(method, url.as_str());
+            let request = match body {
+                QueryBody::None => request,
+                QueryBody::Private(data) | QueryBody::Public(data) => request.body(data),
+            };
+            return Ok((request, None));
+        };
+
+        let counter = session.next_counter();
+        let encrypted_body = matches!(body, QueryBody::Private(_));
+        let header = session.seal_query(method.as_str(), path, counter, &mut url, encrypted_body)?;
+        let request = self
+            .request(method.clone(), url.as_str())
+            .header(PAYLOAD_HEADER, header);
+        let request = match body {
+            QueryBody::None => request,
+            QueryBody::Public(data) => request.body(data),
+            QueryBody::Private(data) => {
+                let aad = payload_aad(AAD_REQUEST_BODY, method.as_str(), path, counter);
+                request.body(encrypt_payload(&session.key, &aad, &data)?.to_string())
+            }
+        };
+        Ok((request, Some(counter)))
+    }
+
+    /// 读取查询请求的响应，counter 不为空时解密 {"payload": ...} 形式的响应体
+    async fn query_response(
+        &self,
+        response: reqwest::Response,
+        method: &str,
+        path: &str,
+        counter: Option<u64>,
+    ) -> Result<QueryResponse, ApiError> {
+        let status = response.status();
+        let mut headers = response.headers().clone();
+        let body = response.bytes().await?.to_vec();
+        let (Some(session), Some(counter)) = (self.payload_session.as_ref(), counter) else {
+            return Ok(QueryResponse { status, headers, body });
+        };
+        // 服务端在解密前拒绝的请求以明文返回错误
+        let payload = serde_json::from_slice::<serde_json::Value>(&body)
+            .ok()
+            .and_then(|mut envelope| envelope.get_mut("payload").map(serde_json::Value::take));
+        let Some(payload) = payload else {
+            return Ok(QueryResponse { status, headers, body });
+        };
+        let aad = payload_aad(AAD_RESPONSE, method, path, counter);
+        let body = decrypt_payload(&session.key, &aad, &payload)?;
+        match headers.remove(PAYLOAD_CONTENT_TYPE_HEADER) {
+            Some(content_type) => {
+                headers.insert(CONTENT_TYPE, content_type);
+            }
+            None => {
+                headers.remove(CONTENT_TYPE);
+            }
+        }
+        Ok(QueryResponse { status, headers, body })
+    }
+
+    /// 列出设备共享目录的内容，path 为空时列出共享根目录
+    pub async fn list_files(&self, path: Option<&str>) -> Result<Vec<RemoteFile>, ApiError> {
+        let api_path = "/api/files/list";
+        let query: Vec<_> = path.map(|path| ("path", path)).into_iter().collect();
+        let (request, counter) = self.query_request(Method::GET, api_path, &query, QueryBody::None)?;
+        let response = request.send().await?;
+        let response = self.query_response(response, "GET", api_path, counter).await?;
+        if response.status == reqwest::StatusCode::NOT_FOUND {
+            return Err(ApiError::Unsupported(
+                "This device does not support file transfer".to_string(),
+            ));
+        }
+
+        let api_response: ApiResponse<Vec<RemoteFile>> = response.json()?;
+        match api_response.data {
+            Some(files) if api_response.success => Ok(files),
+            _ => Err(api_response.into()),
+        }
+    }
+
+    /// 从 offset 开始下载文件的一块数据，返回数据和文件总大小
+    pub async fn download_chunk(&self, path: &str, offset: u64) -> Result<(Vec<u8>, u64), ApiError> {
+        let api_path = "/api/files/download";
+        let (request, counter) =
+            self.query_request(Method::GET, api_path, &[("path", path)], QueryBody::None)?;
+        let response = request
+            .header(reqwest::header::RANGE, format!("bytes={}-", offset))
+            .timeout(Duration::from_secs(60))
+            .send()
+            .await?;
+        let response = self.query_response(response, "GET", api_path, counter).await?;
+
+        if response.status == reqwest::StatusCode::NOT_FOUND {
+            return Err(ApiError::Unsupported(
+                "This device does not support file transfer".to_string(),
+            ));
+        }
+        if response.content_type() != Some("application/octet-stream") {
+            let api_response: ApiResponse<serde_json::Value> = response.json()?;
+            return Err(api_response.into());
+        }
+
+        // 分块响应在 Content-Range 中给出总大小，如 "bytes 0-1023/4096"
+        let total = response
+            .headers
+            .get(reqwest::header::CONTENT_RANGE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.rsplit('/').next())
+            .and_then(|v| v.parse().ok());
+        let data = response.body;
+        let total = total.unwrap_or(offset + data.len() as u64);
+        Ok((data, total))
+    }
+
+    /// 设备上已收到的上传字节数，用于断点续传
+    pub async fn upload_status(&self, path: &str) -> Result<u64, ApiError> {
+        let api_path = "/api/files/upload";
+        let (request, counter) =
+            self.query_request(Method::GET, api_path, &[("path", path)], QueryBody::None)?;
+        let response = request.send().await?;
+
+        let api_response: ApiResponse<FileUploadProgress> = self
+            .query_response(response, "GET", api_path, counter)
+            .await?
+            .json()?;
+        match api_response.data {
+            Some(progress) if api_response.success => Ok(progress.uploaded),
+            _ => Err(api_response.into()),
+        }
+    }
+
+    /// 上传一块数据，offset 必须等于设备已收到的字节数，返回已上传的字节数
+    ///
+    /// 设备上已有同名文件时，只有 overwrite 为 true 才会替换
+    pub async fn upload_chunk(
+        &self,
+        path: &str,
+        offset: u64,
+        data: Vec<u8>,
+        complete: bool,
+        overwrite: bool,
+    ) -> Result<u64, ApiError> {
+        let api_path = "/api/files/upload";
+        let offset = offset.to_string();
+        let query = [
+            ("path", path),
+            ("offset", offset.as_str()),
+            ("complete", if complete { "true" } else { "false" }),
+            ("overwrite", if overwrite { "true" } else { "false" }),
+        ];
+        let (request, counter) =
+            self.query_request(Method::POST, api_path, &query, QueryBody::Private(data))?;
+        let response = request
+            .header(CONTENT_TYPE, "application/octet-stream")
+            .timeout(Duration::from_secs(60))
+            .send()
+            .await?;
+
+        let api_response: ApiResponse<FileUploadProgress> = self
+            .query_response(response, "POST", api_path, counter)
+            .await?
+            .json()?;
+        match api_response.data {
+            Some(progress) if api_response.success => Ok(progress.uploaded),
+            _ => Err(api_response.into()),
+        }
+    }
+
+    /// 读取设备剪贴板中的文本
+    pub async fn get_clipboard(&self) -> Result<String, ApiError> {
+        let path = "/api/clipboard";
+        let (request, counter) = self.query_request(Method::GET, path, &[], QueryBody::None)?;
+        let response = request.send().await?;
+        let response = self.query_response(response, "GET", path, counter).await?;
+        if response.status == reqwest::StatusCode::NOT_FOUND {
+            return Err(ApiError::Unsupported(
+                "This device does not support clipboard sharing".to_string(),
+            ));
+        }
+
+        let api_response: ApiResponse<ClipboardText> = response.json()?;
+        match api_response.data {
+            Some(clipboard) if api_response.success => Ok(clipboard.text),
+            _ => Err(api_response.into()),
+        }
+    }
+
+    /// 把文本写入设备剪贴板
+    pub async fn set_clipboard(&self, text: &str) -> Result<(), ApiError> {
+        let body = serde_json::json!({ "text": text });
+        // 已登录时按会话加密传输剪贴板内容
+        let api_response: ApiResponse<serde_json::Value> = if self.token.is_some() {
+            self.post_json("/api/clipboard", body).await?
+        } else {
+            let url = format!("{}/api/clipboard", self.base_url);
+            let response = self.post(&url).json(&body).send().await?;
+            if response.status() == reqwest::StatusCode::NOT_FOUND {
+                return Err(ApiError::Unsupported(
+                    "This device does not support clipboard sharing".to_string(),
+                ));
+            }
+            response.json().await?
+        };
+        if api_response.success {
+            Ok(())
+        } else {
+            Err(api_response.into())
+        }
+    }
+
     pub fn set_token(&mut self, token: String) {
-        self.token = Some(token);
+        self.token = Some(Zeroizing::new(token));
+        // 外部设置的令牌没有经过协商，不能沿用之前的密钥
+        self.payload_session = None;
     }
     
     pub fn clear_token(&mut self) {
         self.token = None;
+        self.payload_session = None;
     }
     
     pub fn get_token(&self) -> Option<&String> {
-        self.token.as_ref()
+        self.token.as_deref()
     }
 }
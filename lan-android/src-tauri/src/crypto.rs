@@ -1,19 +1,243 @@
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use hkdf::Hkdf;
 use hmac::{Hmac, Mac};
 use sha2::Sha256;
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::spake2::Spake2;
 
 type HmacSha256 = Hmac<Sha256>;
 
-/// 计算 HMAC-SHA256
-pub fn calculate_hmac(challenge: &str, password: &str) -> String {
-    let mut mac = HmacSha256::new_from_slice(password.as_bytes())
+/// 负载加密算法，与服务端 payload 模块一致
+pub const PAYLOAD_CIPHER: &str = "aes-256-gcm-v2";
+const HKDF_INFO: &[u8] = b"lan-device-manager payload v2";
+pub const AAD_REQUEST: &[u8] = b"request";
+pub const AAD_RESPONSE: &[u8] = b"response";
+pub const AAD_REQUEST_BODY: &[u8] = b"request-body";
+const AAD_CLIENT_FRAME: &[u8] = b"client-frame";
+const AAD_SERVER_FRAME: &[u8] = b"server-frame";
+
+pub type PayloadKey = Zeroizing<[u8; 32]>;
+
+/// SPAKE2 中服务端一方的身份，与服务端 pairing 模块一致
+const PAIRING_SERVER_IDENTITY: &[u8] = b"lan-device-manager";
+
+/// 客户端接受的最大迭代次数，防止恶意设备让派生耗时过长
+const MAX_VERIFIER_ITERATIONS: u32 = 10_000_000;
+
+/// 派生登录验证器：PBKDF2-HMAC-SHA256(密码, 盐)，与服务端设置密码时的算法一致
+pub fn derive_verifier(
+    password: &str,
+    salt: &str,
+    iterations: u32,
+) -> Result<Zeroizing<[u8; 32]>, String> {
+    if iterations == 0 || iterations > MAX_VERIFIER_ITERATIONS {
+        return Err(format!("Unsupported verifier iterations: {}", iterations));
+    }
+    let mut key = Zeroizing::new([0u8; 32]);
+    pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), salt.as_bytes(), iterations, key.as_mut());
+    Ok(key)
+}
+
+/// 以验证器为密钥计算挑战的 HMAC-SHA256
+pub fn calculate_hmac(challenge: &str, verifier: &[u8]) -> String {
+    let mut mac = <HmacSha256 as Mac>::new_from_slice(verifier)
         .expect("HMAC can take key of any size");
     mac.update(challenge.as_bytes());
     let result = mac.finalize();
-    let bytes = result.into_bytes();
-    hex::encode(bytes)
+    let mut bytes = result.into_bytes();
+    let response = hex::encode(&bytes);
+    bytes.as_mut_slice().zeroize();
+    response
 }
 
 /// 生成随机设备ID
 pub fn generate_device_id() -> String {
     uuid::Uuid::new_v4().to_string()
 }
+
+/// 从登录验证器和挑战派生负载加密密钥
+pub fn derive_payload_key(verifier: &[u8], challenge: &str) -> PayloadKey {
+    let hkdf = Hkdf::<Sha256>::new(Some(challenge.as_bytes()), verifier);
+    let mut key = Zeroizing::new([0u8; 32]);
+    hkdf.expand(HKDF_INFO, key.as_mut())
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    key
+}
+
+/// 附加认证数据：方向、方法、路径和请求计数，与服务端 payload 模块一致
+pub fn payload_aad(direction: &[u8], method: &str, path: &str, counter: u64) -> Vec<u8> {
+    let mut aad = direction.to_vec();
+    aad.extend_from_slice(format!(" {} {} {}", method, path, counter).as_bytes());
+    aad
+}
+
+/// 加密负载，返回 {"nonce", "ciphertext"}（十六进制）
+pub fn encrypt_payload(
+    key: &PayloadKey,
+    aad: &[u8],
+    plaintext: &[u8],
+) -> Result<serde_json::Value, String> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key.as_ref()));
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, Payload { msg: plaintext, aad })
+        .map_err(|_| "Failed to encrypt payload".to_string())?;
+    Ok(serde_json::json!({
+        "nonce": hex::encode(nonce),
+        "ciphertext": hex::encode(ciphertext),
+    }))
+}
+
+/// 解密 encrypt_payload 格式的负载
+pub fn decrypt_payload(
+    key: &PayloadKey,
+    aad: &[u8],
+    payload: &serde_json::Value,
+) -> Result<Vec<u8>, String> {
+    let field = |name: &str| {
+        payload
+            .get(name)
+            .and_then(|v| v.as_str())
+            .and_then(|v| hex::decode(v).ok())
+            .ok_or_else(|| format!("Invalid encrypted payload: {}", name))
+    };
+    let nonce = field("nonce")?;
+    if nonce.len() != 12 {
+        return Err("Invalid encrypted payload: nonce".to_string());
+    }
+    let ciphertext = field("ciphertext")?;
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key.as_ref()));
+    cipher
+        .decrypt(Nonce::from_slice(&nonce), Payload { msg: &ciphertext, aad })
+        .map_err(|_| "Failed to decrypt payload".to_string())
+}
+
+/// 加密会话中单个方向的 WebSocket 文本帧，与服务端 payload 模块一致
+///
+/// 帧序号从 1 开始逐帧递增，加密后的文本帧为 {"payload": {...}}，二进制帧为 12 字节 nonce 接密文
+struct FrameCipher {
+    key: PayloadKey,
+    direction: &'static [u8],
+    path: String,
+    sequence: u64,
+}
+
+impl FrameCipher {
+    fn new(key: &PayloadKey, direction: &'static [u8], path: &str) -> Self {
+        Self {
+            key: key.clone(),
+            direction,
+            path: path.to_string(),
+            sequence: 0,
+        }
+    }
+
+    fn next_aad(&mut self) -> Vec<u8> {
+        self.sequence += 1;
+        payload_aad(self.direction, "GET", &self.path, self.sequence)
+    }
+
+    fn seal(&mut self, text: &str) -> Result<String, String> {
+        let aad = self.next_aad();
+        let payload = encrypt_payload(&self.key, &aad, text.as_bytes())?;
+        Ok(serde_json::json!({ "payload": payload }).to_string())
+    }
+
+    fn open(&mut self, text: &str) -> Result<String, String> {
+        let frame: serde_json::Value =
+            serde_json::from_str(text).map_err(|_| "Invalid encrypted frame".to_string())?;
+        let payload = frame
+            .get("payload")
+            .ok_or_else(|| "Invalid encrypted frame".to_string())?;
+        let aad = self.next_aad();
+        let plaintext = decrypt_payload(&self.key, &aad, payload)?;
+        String::from_utf8(plaintext).map_err(|_| "Invalid encrypted frame".to_string())
+    }
+
+    fn open_binary(&mut self, frame: &[u8]) -> Result<Vec<u8>, String> {
+        if frame.len() < 12 {
+            return Err("Invalid encrypted frame".to_string());
+        }
+        let (nonce, ciphertext) = frame.split_at(12);
+        let aad = self.next_aad();
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(self.key.as_ref()));
+        cipher
+            .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: &aad })
+            .map_err(|_| "Failed to decrypt payload".to_string())
+    }
+}
+
+/// WebSocket 文本帧的收发：加密会话中逐帧加解密，否则原样收发
+#[derive(Default)]
+pub struct FrameCodec {
+    /// 发出和收到的帧
+    ciphers: Option<(FrameCipher, FrameCipher)>,
+}
+
+impl FrameCodec {
+    pub fn encrypted(key: &PayloadKey, path: &str) -> Self {
+        Self {
+            ciphers: Some((
+                FrameCipher::new(key, AAD_CLIENT_FRAME, path),
+                FrameCipher::new(key, AAD_SERVER_FRAME, path),
+            )),
+        }
+    }
+
+    /// 加密会话的连接已在升级请求中认证，不再发送认证消息
+    pub fn is_encrypted(&self) -> bool {
+        self.ciphers.is_some()
+    }
+
+    pub fn seal(&mut self, text: &str) -> Result<String, String> {
+        match self.ciphers {
+            Some((ref mut outgoing, _)) => outgoing.seal(text),
+            None => Ok(text.to_string()),
+        }
+    }
+
+    pub fn open(&mut self, text: &str) -> Result<String, String> {
+        match self.ciphers {
+            Some((_, ref mut incoming)) => incoming.open(text),
+            None => Ok(text.to_string()),
+        }
+    }
+
+    pub fn open_binary(&mut self, data: Vec<u8>) -> Result<Vec<u8>, String> {
+        match self.ciphers {
+            Some((_, ref mut incoming)) => incoming.open_binary(&data),
+            None => Ok(data),
+        }
+    }
+}
+
+/// 开始 SPAKE2 配对，以登录验证器为口令、本机客户端ID为身份
+pub fn start_pairing(verifier: &[u8], device_id: &str) -> (Spake2, Vec<u8>) {
+    Spake2::start_a(verifier, device_id.as_bytes(), PAIRING_SERVER_IDENTITY)
+}
+
+fn pairing_mac(key: &[u8], role: &str, pairing_id: &str) -> HmacSha256 {
+    let mut mac =
+        <HmacSha256 as Mac>::new_from_slice(key).expect("HMAC can take key of any size");
+    mac.update(role.as_bytes());
+    mac.update(b":");
+    mac.update(pairing_id.as_bytes());
+    mac
+}
+
+/// 配对的密钥确认消息：HMAC(配对密钥, 角色 + ":" + 配对ID)
+pub fn pairing_confirmation(key: &[u8], role: &str, pairing_id: &str) -> Vec<u8> {
+    pairing_mac(key, role, pairing_id).finalize().into_bytes().to_vec()
+}
+
+/// 以常量时间校验对方的密钥确认
+pub fn verify_pairing_confirmation(
+    key: &[u8],
+    role: &str,
+    pairing_id: &str,
+    proof: &[u8],
+) -> bool {
+    pairing_mac(key, role, pairing_id).verify_slice(proof).is_ok()
+}
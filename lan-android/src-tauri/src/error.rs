@@ -0,0 +1,133 @@
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use std::fmt;
+
+use crate::models::ApiResponse;
+
+/// 访问设备接口的错误
+///
+/// 以 { kind, message, code } 的形式发送给前端，前端按 kind 决定是否要求重新登录，
+/// 不再需要匹配错误文本
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApiError {
+    /// 无法连接设备或连接中断
+    Network(String),
+    /// 请求超时
+    Timeout,
+    /// 设备需要登录，但还没有令牌
+    AuthRequired,
+    /// 令牌已过期或被吊销，需要重新登录
+    TokenExpired,
+    /// 设备不支持该接口（旧版本服务端）
+    Unsupported(String),
+    /// 设备返回的错误，code 为稳定的错误码，旧版本服务端不返回
+    Server {
+        code: Option<String>,
+        message: String,
+    },
+    /// 该设备的危险命令需要先通过指纹或锁屏密码确认
+    ConfirmationRequired,
+    /// 本地错误，例如设备未连接或响应无法解析
+    Other(String),
+}
+
+impl ApiError {
+    fn kind(&self) -> &'static str {
+        match self {
+            ApiError::Network(_) => "network",
+            ApiError::Timeout => "timeout",
+            ApiError::AuthRequired => "auth_required",
+            ApiError::TokenExpired => "token_expired",
+            ApiError::Unsupported(_) => "unsupported",
+            ApiError::Server { .. } => "server",
+            ApiError::ConfirmationRequired => "confirmation_required",
+            ApiError::Other(_) => "other",
+        }
+    }
+
+    /// 是否需要重新登录
+    pub fn is_auth(&self) -> bool {
+        matches!(self, ApiError::AuthRequired | ApiError::TokenExpired)
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::Network(e) => write!(f, "Request failed: {}", e),
+            ApiError::Timeout => write!(f, "Request timed out"),
+            ApiError::AuthRequired => write!(f, "Not authenticated"),
+            ApiError::TokenExpired => write!(
+                f,
+                "Authentication expired. Please reconnect and enter password again."
+            ),
+            ApiError::ConfirmationRequired => write!(
+                f,
+                "Confirm with your fingerprint or screen lock to run this command"
+            ),
+            ApiError::Unsupported(message)
+            | ApiError::Server { message, .. }
+            | ApiError::Other(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl Serialize for ApiError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("ApiError", 3)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        let code = match self {
+            ApiError::Server { code, .. } => code.as_deref(),
+            _ => None,
+        };
+        state.serialize_field("code", &code)?;
+        state.end()
+    }
+}
+
+impl From<reqwest::Error> for ApiError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            ApiError::Timeout
+        } else if e.is_decode() {
+            ApiError::Other(format!("Failed to parse response: {}", e))
+        } else {
+            ApiError::Network(e.to_string())
+        }
+    }
+}
+
+impl From<String> for ApiError {
+    fn from(message: String) -> Self {
+        ApiError::Other(message)
+    }
+}
+
+/// 设备返回的失败响应，按错误码区分令牌失效
+impl<T> From<ApiResponse<T>> for ApiError {
+    fn from(response: ApiResponse<T>) -> Self {
+        let code = response.error_code.clone();
+        match code.as_deref() {
+            Some("auth.invalid_token") => return ApiError::TokenExpired,
+            Some("auth.token_missing") => return ApiError::AuthRequired,
+            Some(_) => {}
+            // 旧版本服务端没有错误码，只能按错误文本判断
+            None => {
+                let error = response.error.as_deref().unwrap_or_default();
+                if error.contains("Invalid token") || error.contains("expired") {
+                    return ApiError::TokenExpired;
+                }
+                if error.contains("Token missing") {
+                    return ApiError::AuthRequired;
+                }
+            }
+        }
+        ApiError::Server {
+            code,
+            message: response.error_text(),
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use futures::{SinkExt, StreamExt};
+use std::time::Duration;
+use tauri::plugin::PermissionState;
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::Message;
+use zeroize::Zeroizing;
+
+use crate::api::ApiClient;
+use crate::error::ApiError;
+use crate::models::{ServerEvent, Transport};
+
+/// 每次长轮询在服务端等待的时间（秒）
+const POLL_WAIT_SECS: u64 = 25;
+/// 请求失败后的重试间隔，网络恢复后自动继续
+const RETRY_DELAY: Duration = Duration::from_secs(15);
+/// WebSocket 心跳间隔，避免空闲连接被代理或路由器断开
+const WS_PING_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 本地通知的正文，如 "backup.bat finished: exit 0"
+fn job_message(job: &str, success: bool, exit_code: Option<i32>, message: Option<&str>) -> String {
+    match (exit_code, message.filter(|m| !m.is_empty())) {
+        (Some(code), _) => format!("{} finished: exit {}", job, code),
+        (None, Some(message)) if !success => format!("{} failed: {}", job, message),
+        (None, _) if success => format!("{} finished", job),
+        (None, _) => format!("{} failed", job),
+    }
+}
+
+pub(crate) fn show_notification(app: &AppHandle, title: &str, body: &str) {
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        log::warn!("Failed to show notification: {}", e);
+    }
+}
+
+fn handle_event(app: &AppHandle, device_name: &str, event: ServerEvent) {
+    match event {
+        ServerEvent::JobCompleted {
+            job,
+            success,
+            exit_code,
+            message,
+        } => {
+            let body = job_message(&job, success, exit_code, message.as_deref());
+            show_notification(app, device_name, &body);
+        }
+        ServerEvent::Notification { title, message, .. } => {
+            show_notification(app, &title, &message);
+        }
+        ServerEvent::Other => {}
+    }
+}
+
+/// 通过 WebSocket 接收事件，直到连接断开
+async fn watch_websocket(
+    app: &AppHandle,
+    device_name: &str,
+    client: &ApiClient,
+    token: &str,
+) -> Result<(), String> {
+    let (request, mut codec) = client.ws_request("/ws", &[], false).map_err(|e| e.to_string())?;
+    let (mut socket, _) = tokio_tungstenite::connect_async(request)
+        .await
+        .map_err(|e| format!("WebSocket connection failed: {}", e))?;
+    // 服务端只向已认证的连接转发事件，加密会话已在升级请求中认证
+    if !codec.is_encrypted() {
+        let auth = serde_json::json!({ "type": "auth", "data": { "token": token } });
+        socket
+            .send(Message::Text(auth.to_string()))
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    let mut ping = tokio::time::interval(WS_PING_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = ping.tick() => {
+                let message = Message::Text(codec.seal(r#"{"type":"ping"}"#)?);
+                socket.send(message).await.map_err(|e| e.to_string())?;
+            }
+            incoming = socket.next() => match incoming {
+                Some(Ok(Message::Text(text))) => {
+                    let text = codec.open(&text)?;
+                    if text.contains(r#""type":"auth_error""#) {
+                        return Err("Invalid or expired token".to_string());
+                    }
+                    if let Ok(event) = serde_json::from_str::<ServerEvent>(&text) {
+                        handle_event(app, device_name, event);
+                    }
+                }
+                Some(Ok(Message::Close(_))) | None => return Ok(()),
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e.to_string()),
+            }
+        }
+    }
+}
+
+/// 通过长轮询接收事件，令牌失效或服务端不支持时返回
+async fn watch_polling(app: &AppHandle, device_name: &str, client: &ApiClient) {
+    let mut cursor = None;
+    loop {
+        match client.poll_events(cursor, POLL_WAIT_SECS).await {
+            Ok(batch) => {
+                // 首次请求只取得当前游标，不通知之前发生的事件
+                if cursor.is_some() {
+                    for buffered in batch.events {
+                        handle_event(app, device_name, buffered.event);
+                    }
+                }
+                cursor = Some(batch.cursor);
+            }
+            Err(e) if e.is_auth() || matches!(e, ApiError::Unsupported(_)) => {
+                log::info!("Stopped watching events from {}: {}", device_name, e);
+                return;
+            }
+            Err(e) => {
+                log::debug!("Event poll for {} failed: {}", device_name, e);
+                tokio::time::sleep(RETRY_DELAY).await;
+            }
+        }
+    }
+}
+
+/// 在后台接收设备事件，将任务完成和服务端通知转为本地通知
+///
+/// 默认使用长轮询，应用切到后台或网络切换后也能继续接收；设备设置为
+/// WebSocket 时先尝试 WebSocket，断开后改用长轮询
+pub fn spawn_watcher(app: AppHandle, device_name: String, client: ApiClient) -> JoinHandle<()> {
+    // Android 13 起发送通知需要运行时授权，只在尚未询问过时请求
+    let notification = app.notification();
+    if matches!(
+        notification.permission_state(),
+        Ok(PermissionState::Prompt | PermissionState::PromptWithRationale)
+    ) {
+        let _ = notification.request_permission();
+    }
+
+    tokio::spawn(async move {
+        let token = client.get_token().cloned().map(Zeroizing::new);
+        if let (Transport::Websocket, Some(token)) = (client.transport(), token) {
+            match watch_websocket(&app, &device_name, &client, &token).await {
+                Ok(()) => log::info!("WebSocket to {} closed, switching to polling", device_name),
+                Err(e) => log::warn!(
+                    "WebSocket to {} failed: {}, switching to polling",
+                    device_name,
+                    e
+                ),
+            }
+        }
+        watch_polling(&app, &device_name, &client).await;
+    })
+}
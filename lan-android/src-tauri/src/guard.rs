@@ -0,0 +1,63 @@
+use std::time::Duration;
+use tauri::plugin::{Builder, TauriPlugin};
+use tauri::{AppHandle, Wry};
+
+use crate::error::ApiError;
+
+/// 确认后在这段时间内执行危险命令无需再次确认
+pub const CONFIRMATION_VALID_FOR: Duration = Duration::from_secs(60);
+
+/// 需要确认的命令：关机、重启和自定义命令，睡眠和锁屏可以随时撤销
+pub fn is_destructive(command: &str) -> bool {
+    !matches!(command, "sleep" | "lock")
+}
+
+/// Android 端 BiometricPlugin 的句柄，由 init 注册
+#[cfg(target_os = "android")]
+struct Biometric(tauri::plugin::PluginHandle<Wry>);
+
+/// 通过指纹、面容或锁屏密码确认操作，用户取消或验证失败时返回 ConfirmationRequired
+#[cfg(target_os = "android")]
+pub async fn confirm(app: &AppHandle, reason: String) -> Result<(), ApiError> {
+    use tauri::Manager;
+
+    let app = app.clone();
+    // 插件调用会阻塞到用户完成验证
+    let result = tokio::task::spawn_blocking(move || {
+        app.state::<Biometric>()
+            .0
+            .run_mobile_plugin::<()>("authenticate", serde_json::json!({ "reason": reason }))
+    })
+    .await
+    .map_err(|e| ApiError::Other(e.to_string()))?;
+
+    result.map_err(|e| {
+        log::info!("Command confirmation failed: {}", e);
+        ApiError::ConfirmationRequired
+    })
+}
+
+/// 其他平台没有系统验证界面，直接通过
+#[cfg(not(target_os = "android"))]
+pub async fn confirm(_app: &AppHandle, _reason: String) -> Result<(), ApiError> {
+    Ok(())
+}
+
+/// 确认操作的插件，仅在 Android 上注册系统验证界面
+pub fn init() -> TauriPlugin<Wry> {
+    Builder::new("biometric")
+        .setup(|_app, _api| {
+            #[cfg(target_os = "android")]
+            {
+                use tauri::Manager;
+
+                let handle = _api.register_android_plugin(
+                    "io.github.maxwellnie.lan.device.android",
+                    "BiometricPlugin",
+                )?;
+                _app.manage(Biometric(handle));
+            }
+            Ok(())
+        })
+        .build()
+}
@@ -6,6 +6,17 @@ pub mod api;
 pub mod models;
 pub mod state;
 pub mod crypto;
+pub mod spake2;
+pub mod ssdp;
+pub mod events;
+pub mod error;
+pub mod guard;
+pub mod metrics;
+pub mod network;
+pub mod refresh;
+pub mod schedule;
+pub mod shell;
+pub mod transfer;
 
 use state::AppState;
 
@@ -13,13 +24,17 @@ use state::AppState;
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
-        .plugin(tauri_plugin_sql::Builder::default().build())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(network::init())
+        .plugin(guard::init())
         .manage(Arc::new(Mutex::new(AppState::new())))
         .invoke_handler(tauri::generate_handler![
             start_discovery,
             stop_discovery,
             restart_discovery,
             get_discovered_devices,
+            search_ssdp_devices,
             check_device_auth_required,
             connect_to_device,
             disconnect_device,
@@ -30,11 +45,38 @@ pub fn run() {
             save_device,
             delete_device,
             update_device_name,
+            update_device_metadata,
+            update_connection_profile,
+            set_require_confirmation,
+            sync_saved_devices,
             get_device_password,
             clear_device_password,
+            run_speed_test,
+            get_client_device_id,
+            set_client_language,
+            set_status_refresh_interval,
+            list_schedules,
+            add_schedule,
+            delete_schedule,
+            set_schedule_enabled,
+            get_schedule_runs,
+            list_remote_files,
+            download_remote_file,
+            upload_file_to_device,
+            push_clipboard,
+            pull_clipboard,
+            open_shell_session,
+            write_shell_input,
+            resize_shell,
+            close_shell_session,
+            list_shell_sessions,
+            subscribe_device_metrics,
+            unsubscribe_device_metrics,
         ])
-        .setup(|_app| {
+        .setup(|app| {
             log::info!("LanDevice Manager Android client starting...");
+            refresh::spawn(app.handle().clone());
+            schedule::spawn(app.handle().clone());
             Ok(())
         })
         .run(tauri::generate_context!())
@@ -44,10 +86,11 @@ pub fn run() {
 // 开始设备发现
 #[tauri::command]
 async fn start_discovery(
+    app: tauri::AppHandle,
     state: tauri::State<'_, Arc<Mutex<AppState>>>,
 ) -> Result<String, String> {
     let mut state = state.lock().await;
-    state.start_discovery().await.map_err(|e| e.to_string())
+    state.start_discovery(app).await.map_err(|e| e.to_string())
 }
 
 // 停止设备发现
@@ -62,10 +105,11 @@ async fn stop_discovery(
 // 重启设备发现（用于网络变化后）
 #[tauri::command]
 async fn restart_discovery(
+    app: tauri::AppHandle,
     state: tauri::State<'_, Arc<Mutex<AppState>>>,
 ) -> Result<String, String> {
     let mut state = state.lock().await;
-    state.restart_discovery().await.map_err(|e| e.to_string())
+    state.restart_discovery(app).await.map_err(|e| e.to_string())
 }
 
 // 获取已发现的设备
@@ -77,26 +121,46 @@ async fn get_discovered_devices(
     Ok(state.get_discovered_devices().await)
 }
 
+// 通过 SSDP 搜索设备（mDNS 被屏蔽的网络）
+#[tauri::command]
+async fn search_ssdp_devices(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    timeout_ms: Option<u64>,
+) -> Result<Vec<models::DeviceInfo>, String> {
+    // 搜索期间不持有状态锁
+    let timeout = std::time::Duration::from_millis(timeout_ms.unwrap_or(3000).clamp(500, 10_000));
+    let devices = ssdp::search(timeout).await?;
+    state.lock().await.set_ssdp_devices(devices.clone());
+    Ok(devices)
+}
+
 // 检查设备是否需要认证
 #[tauri::command]
 async fn check_device_auth_required(
     state: tauri::State<'_, Arc<Mutex<AppState>>>,
     ip: String,
     port: u16,
-) -> Result<bool, String> {
+) -> Result<bool, error::ApiError> {
     let state = state.lock().await;
-    state.check_device_auth_required(&ip, port).await.map_err(|e| e.to_string())
+    state.check_device_auth_required(&ip, port).await
 }
 
-// 连接到设备
+// 连接到设备，成功后在后台接收任务完成等事件
 #[tauri::command]
 async fn connect_to_device(
+    app: tauri::AppHandle,
     state: tauri::State<'_, Arc<Mutex<AppState>>>,
     device: models::SavedDevice,
     password: Option<String>,
 ) -> Result<models::ConnectResult, String> {
     let mut state = state.lock().await;
-    state.connect_to_device(device, password).await.map_err(|e| e.to_string())
+    let device_id = device.id.clone();
+    let result = state.connect_to_device(device, password).await.map_err(|e| e.to_string())?;
+    // 复用现有连接时事件接收任务仍在运行
+    if result.success && !state.is_watching_events(&device_id) {
+        state.watch_events(app, &device_id)?;
+    }
+    Ok(result)
 }
 
 // 断开设备连接
@@ -109,27 +173,38 @@ async fn disconnect_device(
     state.disconnect_device(&device_id).await.map_err(|e| e.to_string())
 }
 
-// 认证设备
+// 认证设备，成功后用新令牌重新接收事件
 #[tauri::command]
 async fn authenticate_device(
+    app: tauri::AppHandle,
     state: tauri::State<'_, Arc<Mutex<AppState>>>,
     device_id: String,
     password: String,
-) -> Result<models::AuthResult, String> {
+) -> Result<models::AuthResult, error::ApiError> {
     let mut state = state.lock().await;
-    state.authenticate_device(&device_id, &password).await.map_err(|e| e.to_string())
+    let result = state.authenticate_device(&device_id, &password).await?;
+    if result.success {
+        state.watch_events(app, &device_id)?;
+    }
+    Ok(result)
 }
 
-// 执行命令
+// 执行命令，设备要求确认时先弹出指纹或锁屏密码验证
 #[tauri::command]
 async fn execute_command(
+    app: tauri::AppHandle,
     state: tauri::State<'_, Arc<Mutex<AppState>>>,
     device_id: String,
     command: String,
     args: Option<Vec<String>>,
-) -> Result<models::CommandResult, String> {
+) -> Result<models::CommandResult, error::ApiError> {
+    // 验证期间不持有状态锁，后台刷新等任务可以继续
+    if state.lock().await.needs_confirmation(&device_id, &command) {
+        guard::confirm(&app, format!("Run {} on the device", command)).await?;
+        state.lock().await.record_confirmation(&device_id);
+    }
     let mut state = state.lock().await;
-    state.execute_command(&device_id, &command, args).await.map_err(|e| e.to_string())
+    state.execute_command(&device_id, &command, args).await
 }
 
 // 获取设备状态
@@ -137,9 +212,28 @@ async fn execute_command(
 async fn get_device_status(
     state: tauri::State<'_, Arc<Mutex<AppState>>>,
     device_id: String,
-) -> Result<models::DeviceStatus, String> {
+) -> Result<models::DeviceStatus, error::ApiError> {
     let mut state = state.lock().await;
-    state.get_device_status(&device_id).await.map_err(|e| e.to_string())
+    state.get_device_status(&device_id).await
+}
+
+// 设置执行危险命令前是否需要指纹或锁屏密码确认
+#[tauri::command]
+async fn set_require_confirmation(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    device_id: String,
+    required: bool,
+) -> Result<bool, String> {
+    // 关闭确认本身也需要验证，验证期间不持有状态锁
+    let confirmed = state.lock().await.disables_confirmation(&device_id, required);
+    if confirmed {
+        guard::confirm(&app, "Turn off confirmation for this device".to_string())
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    let mut state = state.lock().await;
+    state.set_require_confirmation(&device_id, required, confirmed)
 }
 
 // 获取保存的设备
@@ -183,6 +277,49 @@ async fn update_device_name(
     state.update_device_name(&device_id, &name).await.map_err(|e| e.to_string())
 }
 
+// 更新设备分组和收藏状态
+#[tauri::command]
+async fn update_device_metadata(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    device_id: String,
+    group: Option<String>,
+    favorite: bool,
+) -> Result<bool, String> {
+    let mut state = state.lock().await;
+    state.update_device_metadata(&device_id, group, favorite)
+}
+
+// 更新设备的连接设置（超时、端口、HTTPS、传输方式）
+#[tauri::command]
+async fn update_connection_profile(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    device_id: String,
+    profile: models::ConnectionProfile,
+) -> Result<bool, String> {
+    let confirmed = state
+        .lock()
+        .await
+        .disables_confirmation(&device_id, profile.require_confirmation);
+    if confirmed {
+        guard::confirm(&app, "Turn off confirmation for this device".to_string())
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    let mut state = state.lock().await;
+    state.update_connection_profile(&device_id, profile, confirmed)
+}
+
+// 通过已连接的设备同步保存的设备列表
+#[tauri::command]
+async fn sync_saved_devices(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    device_id: String,
+) -> Result<models::SyncResult, String> {
+    let mut state = state.lock().await;
+    state.sync_saved_devices(&device_id).await
+}
+
 // 获取设备密码
 #[tauri::command]
 async fn get_device_password(
@@ -202,3 +339,239 @@ async fn clear_device_password(
     let mut state = state.lock().await;
     state.clear_device_password(&device_id).await.map_err(|e| e.to_string())
 }
+
+// 测试与设备之间的网速
+#[tauri::command]
+async fn run_speed_test(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    device_id: String,
+    bytes: Option<u64>,
+) -> Result<models::SpeedTestResult, error::ApiError> {
+    let state = state.lock().await;
+    state.speed_test(&device_id, bytes.unwrap_or(10 * 1024 * 1024)).await
+}
+
+// 列出设备共享目录的内容，不传路径时列出共享根目录
+#[tauri::command]
+async fn list_remote_files(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    device_id: String,
+    path: Option<String>,
+) -> Result<Vec<models::RemoteFile>, error::ApiError> {
+    let client = state.lock().await.connected_client(&device_id)?;
+    match client.list_files(path.as_deref()).await {
+        Err(e) if e.is_auth() => {
+            let client = state.lock().await.refreshed_client(&device_id).await?;
+            client.list_files(path.as_deref()).await
+        }
+        result => result,
+    }
+}
+
+// 下载设备上的文件，返回保存的本地路径；进度通过 file-transfer 事件发送
+#[tauri::command]
+async fn download_remote_file(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    device_id: String,
+    remote_path: String,
+    local_path: Option<String>,
+) -> Result<String, error::ApiError> {
+    let local_path = local_path
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|| transfer::default_download_path(&remote_path));
+    // 传输期间不持有状态锁
+    transfer::download(&app, state.inner(), &device_id, &remote_path, &local_path).await?;
+    Ok(local_path.to_string_lossy().to_string())
+}
+
+// 上传本地文件到设备，进度通过 file-transfer 事件发送
+#[tauri::command]
+async fn upload_file_to_device(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    device_id: String,
+    local_path: String,
+    remote_path: String,
+    overwrite: Option<bool>,
+) -> Result<u64, error::ApiError> {
+    transfer::upload(
+        &app,
+        state.inner(),
+        &device_id,
+        std::path::Path::new(&local_path),
+        &remote_path,
+        overwrite.unwrap_or(false),
+    )
+    .await
+}
+
+// 订阅设备的实时指标，指标通过 device-metrics 事件发送，订阅期间后台刷新跳过该设备
+#[tauri::command]
+async fn subscribe_device_metrics(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    device_id: String,
+    interval_ms: Option<u64>,
+) -> Result<(), error::ApiError> {
+    let client = state.lock().await.connected_client(&device_id)?;
+    match metrics::subscribe(&app, &client, &device_id, interval_ms).await {
+        Err(e) if e.is_auth() => {
+            let client = state.lock().await.refreshed_client(&device_id).await?;
+            metrics::subscribe(&app, &client, &device_id, interval_ms).await
+        }
+        result => result,
+    }
+}
+
+// 取消实时指标订阅，页面关闭时调用
+#[tauri::command]
+async fn unsubscribe_device_metrics(device_id: String) -> Result<bool, String> {
+    Ok(metrics::unsubscribe(&device_id))
+}
+
+// 打开设备的交互式终端，返回会话 id；输出通过 shell-output 事件发送
+#[tauri::command]
+async fn open_shell_session(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    device_id: String,
+    cols: u16,
+    rows: u16,
+) -> Result<String, error::ApiError> {
+    if state.lock().await.needs_confirmation(&device_id, "shell") {
+        guard::confirm(&app, "Open a remote shell on the device".to_string()).await?;
+        state.lock().await.record_confirmation(&device_id);
+    }
+    let client = state.lock().await.connected_client(&device_id)?;
+    match shell::open(&app, &client, &device_id, cols, rows).await {
+        Err(e) if e.is_auth() => {
+            let client = state.lock().await.refreshed_client(&device_id).await?;
+            shell::open(&app, &client, &device_id, cols, rows).await
+        }
+        result => result,
+    }
+}
+
+// 向终端写入输入
+#[tauri::command]
+async fn write_shell_input(session_id: String, data: String) -> Result<(), error::ApiError> {
+    shell::write(&session_id, data)
+}
+
+// 调整终端大小
+#[tauri::command]
+async fn resize_shell(session_id: String, cols: u16, rows: u16) -> Result<(), error::ApiError> {
+    shell::resize(&session_id, cols, rows)
+}
+
+// 关闭终端
+#[tauri::command]
+async fn close_shell_session(session_id: String) -> Result<bool, String> {
+    Ok(shell::close(&session_id))
+}
+
+// 列出打开的终端，不传设备时列出全部
+#[tauri::command]
+async fn list_shell_sessions(
+    device_id: Option<String>,
+) -> Result<Vec<shell::ShellSessionInfo>, String> {
+    Ok(shell::list(device_id.as_deref()))
+}
+
+// 把手机剪贴板中的文本发送到设备的剪贴板
+#[tauri::command]
+async fn push_clipboard(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    device_id: String,
+) -> Result<(), error::ApiError> {
+    use tauri_plugin_clipboard_manager::ClipboardExt;
+
+    let text = app
+        .clipboard()
+        .read_text()
+        .map_err(|_| error::ApiError::Other("Clipboard is empty".to_string()))?;
+    if text.is_empty() {
+        return Err(error::ApiError::Other("Clipboard is empty".to_string()));
+    }
+    let mut state = state.lock().await;
+    state.set_device_clipboard(&device_id, &text).await
+}
+
+// 把设备剪贴板中的文本复制到手机剪贴板，返回复制的文本
+#[tauri::command]
+async fn pull_clipboard(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    device_id: String,
+) -> Result<String, error::ApiError> {
+    use tauri_plugin_clipboard_manager::ClipboardExt;
+
+    let text = state.lock().await.get_device_clipboard(&device_id).await?;
+    app.clipboard()
+        .write_text(text.clone())
+        .map_err(|e| error::ApiError::Other(format!("Failed to write clipboard: {}", e)))?;
+    Ok(text)
+}
+
+// 获取本机客户端ID，用于在电脑端配置该手机的权限
+#[tauri::command]
+async fn get_client_device_id() -> Result<String, String> {
+    Ok(state::client_device_id())
+}
+
+// 设置界面语言，之后建立的连接按该语言接收错误消息
+#[tauri::command]
+async fn set_client_language(language: String) -> Result<(), String> {
+    state::set_client_language(&language);
+    Ok(())
+}
+
+// 设置已连接设备状态的自动刷新间隔（秒），0 表示关闭
+#[tauri::command]
+async fn set_status_refresh_interval(secs: u64) -> Result<(), String> {
+    refresh::set_interval(secs)
+}
+
+// 列出计划命令，不传设备ID时列出全部
+#[tauri::command]
+async fn list_schedules(device_id: Option<String>) -> Result<Vec<models::ScheduledCommand>, String> {
+    schedule::list(device_id.as_deref())
+}
+
+// 添加计划命令，危险命令在创建时确认，到期执行时不再弹出验证
+#[tauri::command]
+async fn add_schedule(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    device_id: String,
+    command: String,
+    args: Option<Vec<String>>,
+    run_at: i64,
+    repeat_secs: Option<u64>,
+) -> Result<models::ScheduledCommand, error::ApiError> {
+    if state.lock().await.needs_confirmation(&device_id, &command) {
+        guard::confirm(&app, format!("Schedule {} on the device", command)).await?;
+        state.lock().await.record_confirmation(&device_id);
+    }
+    Ok(schedule::add(&device_id, &command, args, run_at, repeat_secs)?)
+}
+
+// 删除计划命令
+#[tauri::command]
+async fn delete_schedule(id: String) -> Result<bool, String> {
+    schedule::delete(&id)
+}
+
+// 启用或暂停计划命令
+#[tauri::command]
+async fn set_schedule_enabled(id: String, enabled: bool) -> Result<bool, String> {
+    schedule::set_enabled(&id, enabled)
+}
+
+// 获取计划命令的执行记录
+#[tauri::command]
+async fn get_schedule_runs(schedule_id: String) -> Result<Vec<models::ScheduleRun>, String> {
+    schedule::runs(&schedule_id)
+}
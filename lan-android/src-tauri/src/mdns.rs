@@ -1,6 +1,7 @@
 use mdns_sd::{ServiceDaemon, ServiceEvent};
 use std::collections::HashMap;
 use std::sync::Arc;
+use tauri::{AppHandle, Emitter};
 use tokio::sync::Mutex;
 
 use crate::models::DeviceInfo;
@@ -11,10 +12,12 @@ pub struct MdnsDiscovery {
     devices: Arc<Mutex<HashMap<String, DeviceInfo>>>,
     /// 设备UUID到设备ID的映射（用于快速查找已知设备）
     uuid_to_id: Arc<Mutex<HashMap<String, String>>>,
+    /// 已发现设备的 TXT 信息变化时向前端发送事件
+    app: AppHandle,
 }
 
 impl MdnsDiscovery {
-    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn new(app: AppHandle) -> Result<Self, Box<dyn std::error::Error>> {
         let daemon = ServiceDaemon::new()?;
 
         Ok(Self {
@@ -22,6 +25,7 @@ impl MdnsDiscovery {
             service_type: "_lanmanager._tcp.local.".to_string(),
             devices: Arc::new(Mutex::new(HashMap::new())),
             uuid_to_id: Arc::new(Mutex::new(HashMap::new())),
+            app,
         })
     }
 
@@ -33,6 +37,7 @@ impl MdnsDiscovery {
         // 启动监听任务
         let devices = self.devices.clone();
         let uuid_to_id = self.uuid_to_id.clone();
+        let app = self.app.clone();
 
         std::thread::spawn(move || {
             log::info!("mDNS listener thread started");
@@ -95,6 +100,17 @@ impl MdnsDiscovery {
                                 .map(|v| v.val_str().to_string())
                                 .unwrap_or_else(|| "1.0.0".to_string());
 
+                            let txt = |key: &str| txt_records.get(key)
+                                .map(|v| v.val_str().trim().to_string())
+                                .filter(|v| !v.is_empty());
+
+                            // 优先使用服务端配置的显示名称
+                            let name = txt("display_name")
+                                .or_else(|| txt("device"))
+                                .unwrap_or(clean_hostname);
+                            let os = txt("os");
+                            let os_version = txt("os_version");
+
                             let requires_auth = txt_records.get("auth")
                                 .or_else(|| txt_records.get("AUTH"))
                                 .map(|v| v.val_str() == "required")
@@ -105,6 +121,11 @@ impl MdnsDiscovery {
                                 let mut devices_guard = devices.lock().await;
                                 let mut uuid_map_guard = uuid_to_id.lock().await;
 
+                                // 同一设备再次解析时的旧信息，用于判断 TXT 记录是否变化
+                                let previous = uuid_map_guard.get(&uuid)
+                                    .and_then(|existing_id| devices_guard.get(existing_id))
+                                    .cloned();
+
                                 // 检查是否已存在相同 UUID 的设备
                                 if let Some(existing_id) = uuid_map_guard.get(&uuid) {
                                     if existing_id != &fullname {
@@ -122,14 +143,30 @@ impl MdnsDiscovery {
                                 let device = DeviceInfo {
                                     id: fullname.clone(),
                                     uuid: uuid.clone(),
-                                    name: clean_hostname,
+                                    name,
                                     ip_address: ip.to_string(),
                                     port: port,
                                     version,
                                     requires_auth,
                                     discovered_at: chrono::Utc::now(),
+                                    os,
+                                    os_version,
                                 };
 
+                                // 服务端设置或清除密码等会改变 TXT 记录，通知前端更新已保存的设备
+                                if let Some(previous) = previous {
+                                    if previous.requires_auth != device.requires_auth
+                                        || previous.name != device.name
+                                        || previous.os_version != device.os_version
+                                    {
+                                        log::info!(
+                                            "Device {} TXT changed - requires_auth: {} -> {}",
+                                            uuid, previous.requires_auth, device.requires_auth
+                                        );
+                                        let _ = app.emit("discovered-device-updated", device.clone());
+                                    }
+                                }
+
                                 // 更新映射关系
                                 uuid_map_guard.insert(uuid.clone(), fullname.clone());
                                 devices_guard.insert(fullname.clone(), device);
@@ -217,20 +254,3 @@ impl MdnsDiscovery {
         Ok(())
     }
 }
-
-impl Clone for MdnsDiscovery {
-    fn clone(&self) -> Self {
-        // 创建新的 daemon
-        let daemon = ServiceDaemon::new().unwrap_or_else(|e| {
-            log::error!("Failed to create daemon in clone: {}", e);
-            panic!("Failed to create daemon in clone: {}", e);
-        });
-
-        Self {
-            daemon,
-            service_type: self.service_type.clone(),
-            devices: self.devices.clone(),
-            uuid_to_id: self.uuid_to_id.clone(),
-        }
-    }
-}
@@ -0,0 +1,240 @@
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex as StdMutex, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::net::TcpStream;
+use tokio::sync::oneshot;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use crate::api::ApiClient;
+use crate::crypto::FrameCodec;
+use crate::error::ApiError;
+
+/// 等待认证结果和首个指标的时间
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(10);
+/// WebSocket 心跳间隔，避免空闲连接被路由器断开
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// 随 device-metrics 事件发送的实时指标
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricsSample {
+    pub device_id: String,
+    pub cpu_usage: f32,
+    pub memory_used: u64,
+    pub uptime_seconds: u64,
+}
+
+/// 订阅因连接断开而结束时发送的 device-metrics-stopped 事件
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricsStopped {
+    pub device_id: String,
+    pub error: Option<String>,
+}
+
+/// 服务端 metrics 消息中的指标，只取页面需要的字段
+#[derive(Debug, Deserialize)]
+struct LiveMetrics {
+    cpu_usage: f32,
+    memory_used: u64,
+    uptime_seconds: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+enum ServerMessage {
+    AuthSuccess,
+    AuthError {
+        message: String,
+    },
+    Metrics(LiveMetrics),
+    Error {
+        message: String,
+    },
+    #[serde(other)]
+    Other,
+}
+
+struct Subscription {
+    id: u64,
+    // drop 时通知推送任务取消订阅并关闭连接
+    _stop: oneshot::Sender<()>,
+}
+
+// 每个设备最多一个订阅，重复订阅时替换旧的
+static SUBSCRIPTIONS: OnceLock<StdMutex<HashMap<String, Subscription>>> = OnceLock::new();
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+fn subscriptions() -> std::sync::MutexGuard<'static, HashMap<String, Subscription>> {
+    SUBSCRIPTIONS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+}
+
+fn sample(device_id: &str, metrics: LiveMetrics) -> MetricsSample {
+    MetricsSample {
+        device_id: device_id.to_string(),
+        cpu_usage: metrics.cpu_usage,
+        memory_used: metrics.memory_used,
+        uptime_seconds: metrics.uptime_seconds,
+    }
+}
+
+/// 发送一条文本消息，加密会话中加密后发送
+async fn send_text(
+    socket: &mut Socket,
+    codec: &mut FrameCodec,
+    text: &str,
+) -> Result<(), String> {
+    let text = codec.seal(text)?;
+    socket.send(Message::Text(text)).await.map_err(|e| e.to_string())
+}
+
+/// 读取下一条服务端消息，忽略心跳和无关的广播
+async fn next_message(
+    socket: &mut Socket,
+    codec: &mut FrameCodec,
+) -> Result<ServerMessage, ApiError> {
+    loop {
+        let message = tokio::time::timeout(RESPONSE_TIMEOUT, socket.next())
+            .await
+            .map_err(|_| ApiError::Timeout)?;
+        match message {
+            Some(Ok(Message::Text(text))) => match serde_json::from_str(&codec.open(&text)?) {
+                Ok(ServerMessage::Other) | Err(_) => continue,
+                Ok(message) => return Ok(message),
+            },
+            Some(Ok(Message::Close(_))) | None => {
+                return Err(ApiError::Network("Connection closed".to_string()))
+            }
+            Some(Ok(_)) => continue,
+            Some(Err(e)) => return Err(ApiError::Network(e.to_string())),
+        }
+    }
+}
+
+/// 连接设备并订阅实时指标，收到首个指标后返回；之后的指标通过 device-metrics 事件发送
+pub async fn subscribe(
+    app: &AppHandle,
+    client: &ApiClient,
+    device_id: &str,
+    interval_ms: Option<u64>,
+) -> Result<(), ApiError> {
+    let (request, mut codec) = client.ws_request("/ws", &[], false)?;
+    let (mut socket, _) = tokio_tungstenite::connect_async(request)
+        .await
+        .map_err(|e| ApiError::Network(e.to_string()))?;
+
+    if let Some(token) = client.get_token() {
+        // 加密会话已在升级请求中认证，服务端直接返回认证结果
+        if !codec.is_encrypted() {
+            let auth = serde_json::json!({ "type": "auth", "data": { "token": token } });
+            send_text(&mut socket, &mut codec, &auth.to_string())
+                .await
+                .map_err(ApiError::Network)?;
+        }
+        match next_message(&mut socket, &mut codec).await? {
+            ServerMessage::AuthSuccess => {}
+            ServerMessage::AuthError { .. } => return Err(ApiError::TokenExpired),
+            ServerMessage::Error { message } => {
+                return Err(ApiError::Server {
+                    code: None,
+                    message,
+                })
+            }
+            _ => return Err(ApiError::Other("Unexpected response".to_string())),
+        }
+    }
+
+    let subscribe =
+        serde_json::json!({ "type": "subscribe_metrics", "data": { "interval_ms": interval_ms } });
+    send_text(&mut socket, &mut codec, &subscribe.to_string())
+        .await
+        .map_err(ApiError::Network)?;
+    let first = match next_message(&mut socket, &mut codec).await? {
+        ServerMessage::Metrics(metrics) => metrics,
+        ServerMessage::Error { message } if message == "Not authenticated" => {
+            return Err(ApiError::AuthRequired)
+        }
+        // 旧版本服务端无法解析订阅消息
+        ServerMessage::Error { .. } => {
+            return Err(ApiError::Unsupported(
+                "Live metrics are not supported by this device".to_string(),
+            ))
+        }
+        _ => return Err(ApiError::Other("Unexpected response".to_string())),
+    };
+    let _ = app.emit("device-metrics", sample(device_id, first));
+
+    let id = NEXT_ID.fetch_add(1, Ordering::SeqCst);
+    let (stop, mut stopped) = oneshot::channel();
+    subscriptions().insert(device_id.to_string(), Subscription { id, _stop: stop });
+    log::info!("Subscribed to live metrics from {}", device_id);
+
+    let app = app.clone();
+    let device_id = device_id.to_string();
+    tokio::spawn(async move {
+        let mut ping = tokio::time::interval(PING_INTERVAL);
+        let error = loop {
+            tokio::select! {
+                // 订阅被取消或替换
+                _ = &mut stopped => {
+                    let unsubscribe = r#"{"type":"unsubscribe_metrics"}"#;
+                    let _ = send_text(&mut socket, &mut codec, unsubscribe).await;
+                    let _ = socket.close(None).await;
+                    log::info!("Unsubscribed from live metrics from {}", device_id);
+                    return;
+                }
+                _ = ping.tick() => {
+                    if let Err(e) = socket.send(Message::Ping(Vec::new())).await {
+                        break Some(e.to_string());
+                    }
+                }
+                incoming = socket.next() => match incoming {
+                    Some(Ok(Message::Text(text))) => {
+                        let text = match codec.open(&text) {
+                            Ok(text) => text,
+                            Err(e) => break Some(e),
+                        };
+                        if let Ok(ServerMessage::Metrics(metrics)) = serde_json::from_str(&text) {
+                            let _ = app.emit("device-metrics", sample(&device_id, metrics));
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | None => break None,
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => break Some(e.to_string()),
+                },
+            }
+        };
+
+        {
+            let mut subscriptions = subscriptions();
+            if subscriptions.get(&device_id).map(|s| s.id) == Some(id) {
+                subscriptions.remove(&device_id);
+            }
+        }
+        log::info!("Live metrics from {} stopped", device_id);
+        let _ = app.emit(
+            "device-metrics-stopped",
+            MetricsStopped { device_id, error },
+        );
+    });
+
+    Ok(())
+}
+
+/// 取消设备的实时指标订阅，没有订阅时返回 false
+pub fn unsubscribe(device_id: &str) -> bool {
+    subscriptions().remove(device_id).is_some()
+}
+
+/// 设备是否正在推送实时指标，订阅期间后台刷新跳过该设备
+pub fn is_subscribed(device_id: &str) -> bool {
+    subscriptions().contains_key(device_id)
+}
@@ -11,6 +11,12 @@ pub struct DeviceInfo {
     pub version: String,
     pub requires_auth: bool,
     pub discovered_at: DateTime<Utc>,
+    /// 操作系统类型，如 "Windows"（从 TXT 记录获取）
+    #[serde(default)]
+    pub os: Option<String>,
+    /// 操作系统版本，如 "Microsoft Windows 11 Pro"
+    #[serde(default)]
+    pub os_version: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,6 +29,80 @@ pub struct SavedDevice {
     pub custom_name: Option<String>,
     pub last_connected: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
+    /// 分组名称，为空表示未分组
+    #[serde(default)]
+    pub group: Option<String>,
+    #[serde(default)]
+    pub favorite: bool,
+    /// 名称、分组、收藏最后修改时间（Unix 毫秒），同步时较新的一方生效
+    #[serde(default)]
+    pub updated_at: i64,
+    /// 本机对该设备的连接设置，不参与同步
+    #[serde(default)]
+    pub profile: ConnectionProfile,
+}
+
+/// 接收设备事件时优先使用的传输方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Transport {
+    /// HTTP 长轮询，适合后台运行和不稳定的网络
+    #[default]
+    Http,
+    /// WebSocket，延迟更低，连接失败时回退到长轮询
+    Websocket,
+}
+
+/// 单个设备的连接设置，例如通过电力线连接的树莓派需要更长的超时
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConnectionProfile {
+    pub transport: Transport,
+    /// 请求超时（秒），为空时使用默认值
+    pub timeout_secs: Option<u64>,
+    /// 覆盖发现得到的端口，例如设备在另一端口上启用了 HTTPS
+    pub port_override: Option<u16>,
+    /// 使用 HTTPS 连接
+    pub tls: bool,
+    /// 接受自签名等无效证书，仅在 tls 为 true 时生效
+    pub accept_invalid_certs: bool,
+    /// 优先使用的主机名或地址（如 desktop.home.lan 或 https://desktop.home.lan:8443），
+    /// 无法连接时回退到发现得到的 IP
+    pub host: Option<String>,
+    /// 上次连接时实际使用的备用地址，首选地址可用时为空
+    pub last_fallback: Option<String>,
+    /// 执行关机、重启和自定义命令前需要指纹或锁屏密码确认
+    pub require_confirmation: bool,
+}
+
+/// 与其他客户端同步的设备目录条目，与服务端 sync 模块的格式一致
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CatalogEntry {
+    pub uuid: String,
+    pub name: String,
+    #[serde(default)]
+    pub custom_name: Option<String>,
+    pub ip_address: String,
+    pub port: u16,
+    #[serde(default)]
+    pub group: Option<String>,
+    #[serde(default)]
+    pub favorite: bool,
+    /// 已删除的设备
+    #[serde(default)]
+    pub deleted: bool,
+    pub updated_at: i64,
+}
+
+/// 一次同步的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncResult {
+    /// 从其他客户端新增或更新的设备数
+    pub updated: usize,
+    /// 被其他客户端删除的设备数
+    pub removed: usize,
+    /// 同步后本地保存的设备数
+    pub total: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,6 +113,10 @@ pub struct DeviceStatus {
     pub uptime: u64,
     pub os_type: String,
     pub os_version: String,
+    /// 与设备的时钟偏差（毫秒，正值表示本机时间较快），握手失败时为空
+    pub clock_skew_ms: Option<i64>,
+    /// 设备端有可用的新版本时为新版本号
+    pub available_update: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -57,24 +141,63 @@ pub struct CommandResult {
     pub stderr: String,
     pub exit_code: Option<i32>,
     pub execution_time_ms: u64,
+    /// 执行失败时的错误码和本地化消息
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_message: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuthChallenge {
     pub challenge: String,
+    /// 派生登录验证器的盐和迭代次数，旧版本服务端不返回
+    #[serde(default)]
+    pub salt: Option<String>,
+    #[serde(default)]
+    pub iterations: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuthRequest {
     pub challenge: String,
+    /// 以验证器为密钥对挑战计算的 HMAC
     pub response: String,
-    pub password: String,
+    /// 本机客户端ID，服务端据此应用按客户端配置的权限
+    pub device_id: String,
+    /// 请求的负载加密算法，未使用 TLS 时设置
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encryption: Option<String>,
+    /// 响应以配对密钥而不是验证器计算
+    #[serde(default)]
+    pub paired: bool,
+}
+
+/// 配对第一步的响应
+#[derive(Debug, Clone, Deserialize)]
+pub struct PairStartResponse {
+    pub pairing_id: String,
+    /// 服务端的 SPAKE2 消息（十六进制）
+    pub message: String,
+}
+
+/// 配对第二步的响应
+#[derive(Debug, Clone, Deserialize)]
+pub struct PairConfirmResponse {
+    /// 服务端的密钥确认（十六进制）
+    pub proof: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuthResponse {
     pub token: String,
     pub expires_in: u64,
+    /// 服务端确认的负载加密算法，旧版本服务端不返回
+    #[serde(default)]
+    pub encryption: Option<String>,
+    /// 负载加密的密钥ID，加密请求以它代替明文令牌
+    #[serde(default)]
+    pub key_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -82,6 +205,41 @@ pub struct ApiResponse<T> {
     pub success: bool,
     pub data: Option<T>,
     pub error: Option<String>,
+    /// 稳定的错误码，旧版本服务端不返回
+    #[serde(default)]
+    pub error_code: Option<String>,
+    /// 按 Accept-Language 本地化的错误消息
+    #[serde(default)]
+    pub error_message: Option<String>,
+    /// 错误的结构化说明，命令被拒绝时包含命中的规则和需要修改的配置项（config_key）
+    #[serde(default)]
+    pub error_details: Option<serde_json::Value>,
+}
+
+impl<T> ApiResponse<T> {
+    /// 显示给用户的错误文本，优先使用本地化消息
+    ///
+    /// 认证类错误保留原文，调用方和前端据此判断是否需要重新登录
+    pub fn error_text(self) -> String {
+        let auth_error = self
+            .error_code
+            .as_deref()
+            .map_or(false, |code| code.starts_with("auth."));
+        let message = if auth_error { None } else { self.error_message };
+        let text = message
+            .or(self.error)
+            .unwrap_or_else(|| "Unknown error".to_string());
+        // 命令被拒绝时指出电脑端需要修改的配置项
+        match self
+            .error_details
+            .as_ref()
+            .and_then(|details| details.get("config_key"))
+            .and_then(|key| key.as_str())
+        {
+            Some(key) => format!("{} (setting: {})", text, key),
+            None => text,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -92,3 +250,143 @@ pub struct SystemInfo {
     pub os_type: String,
     pub os_version: String,
 }
+
+/// 设备端程序的更新状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppUpdateStatus {
+    pub current_version: String,
+    pub latest_version: Option<String>,
+    pub update_available: bool,
+}
+
+/// 网速测试结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpeedTestResult {
+    pub bytes: u64,
+    pub download_ms: u64,
+    pub upload_ms: u64,
+    pub download_mbps: f64,
+    pub upload_mbps: f64,
+}
+
+/// 设备剪贴板中的文本
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClipboardText {
+    pub text: String,
+}
+
+/// 设备共享目录中的文件或目录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteFile {
+    pub name: String,
+    pub path: String,
+    /// 所在的共享根目录
+    #[serde(default)]
+    pub root: String,
+    pub is_dir: bool,
+    pub size: u64,
+    #[serde(default)]
+    pub modified: Option<String>,
+}
+
+/// 设备返回的上传进度
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileUploadProgress {
+    pub path: String,
+    pub uploaded: u64,
+}
+
+/// 协议握手请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HandshakeRequest {
+    pub protocol_version: u32,
+    pub client_time: i64,
+    pub client_version: String,
+}
+
+/// 协议握手响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HandshakeResponse {
+    pub protocol_version: u32,
+    pub min_protocol_version: u32,
+    pub server_version: String,
+    pub server_time: i64,
+    pub clock_skew_ms: i64,
+    pub clock_skew_warning: bool,
+    pub compatible: bool,
+}
+
+/// 服务端返回的上传测试结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpeedTestUpload {
+    pub bytes: u64,
+    pub duration_ms: u64,
+}
+
+/// 服务端推送的事件，只解析客户端关心的类型
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data")]
+pub enum ServerEvent {
+    #[serde(rename = "job_completed")]
+    JobCompleted {
+        job: String,
+        success: bool,
+        #[serde(default)]
+        exit_code: Option<i32>,
+        #[serde(default)]
+        message: Option<String>,
+    },
+    #[serde(rename = "notification")]
+    Notification {
+        id: String,
+        title: String,
+        message: String,
+        level: String,
+    },
+    #[serde(other)]
+    Other,
+}
+
+/// 带序号的服务端事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BufferedEvent {
+    pub seq: u64,
+    pub event: ServerEvent,
+}
+
+/// 长轮询结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventBatch {
+    pub cursor: u64,
+    pub events: Vec<BufferedEvent>,
+    #[serde(default)]
+    pub missed: bool,
+}
+
+/// 在本机定时发送给设备的命令，适用于不支持服务端计划任务的旧版本设备
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledCommand {
+    pub id: String,
+    pub device_id: String,
+    pub command: String,
+    #[serde(default)]
+    pub args: Option<Vec<String>>,
+    /// 下次执行时间（Unix 毫秒）
+    pub next_run: i64,
+    /// 重复间隔（秒），为空表示只执行一次
+    #[serde(default)]
+    pub repeat_secs: Option<u64>,
+    pub enabled: bool,
+}
+
+/// 计划命令的一次执行记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleRun {
+    pub schedule_id: String,
+    pub device_id: String,
+    pub command: String,
+    /// 执行时间（Unix 毫秒）
+    pub ran_at: i64,
+    pub success: bool,
+    pub message: String,
+}
@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::plugin::{Builder, TauriPlugin};
+use tauri::{AppHandle, Emitter, Manager, Wry};
+use tokio::sync::Mutex;
+
+use crate::state::AppState;
+
+/// 网络切换后等待的时间，新网络的地址和路由就绪后再重启发现
+#[cfg(target_os = "android")]
+const SETTLE_DELAY: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// 默认网络的变化，由 Android 端的 NetworkPlugin 发送
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkChange {
+    pub connected: bool,
+    /// 新网络是否为 Wi-Fi，移动数据网络上无法发现局域网设备
+    pub wifi: bool,
+}
+
+/// 处理网络变化：重启正在进行的设备发现，并通知界面重新检查已保存设备的状态
+#[cfg_attr(not(target_os = "android"), allow(dead_code))]
+async fn on_network_changed(app: AppHandle, change: NetworkChange) {
+    log::info!(
+        "Network changed (connected: {}, wifi: {})",
+        change.connected,
+        change.wifi
+    );
+
+    if change.connected {
+        let state = app.state::<Arc<Mutex<AppState>>>().inner().clone();
+        let mut state = state.lock().await;
+        if state.is_discovering() {
+            if let Err(e) = state.restart_discovery(app.clone()).await {
+                log::warn!("Failed to restart discovery after network change: {}", e);
+            }
+        }
+    }
+
+    let _ = app.emit("network-changed", change);
+}
+
+#[cfg(target_os = "android")]
+fn watch(app: AppHandle, plugin: tauri::plugin::PluginHandle<Wry>) {
+    use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+    use tauri::ipc::{Channel, InvokeResponseBody};
+
+    // 注册监听时会立即收到当前网络，这不是变化
+    let initial = Arc::new(AtomicBool::new(true));
+    // 短时间内连续变化只处理最后一次
+    let generation = Arc::new(AtomicU64::new(0));
+
+    let channel = Channel::new(move |body: InvokeResponseBody| {
+        let change: NetworkChange = match body.deserialize() {
+            Ok(change) => change,
+            Err(e) => {
+                log::warn!("Invalid network change event: {}", e);
+                return Ok(());
+            }
+        };
+        if initial.swap(false, Ordering::SeqCst) && change.connected {
+            return Ok(());
+        }
+
+        let current = generation.fetch_add(1, Ordering::SeqCst) + 1;
+        let generation = generation.clone();
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(SETTLE_DELAY).await;
+            if generation.load(Ordering::SeqCst) == current {
+                on_network_changed(app, change).await;
+            }
+        });
+        Ok(())
+    });
+
+    if let Err(e) =
+        plugin.run_mobile_plugin::<()>("watchNetwork", serde_json::json!({ "channel": channel }))
+    {
+        log::warn!("Failed to watch network changes: {}", e);
+    }
+}
+
+/// 网络变化监听插件，仅在 Android 上生效
+pub fn init() -> TauriPlugin<Wry> {
+    Builder::new("network")
+        .setup(|_app, _api| {
+            #[cfg(target_os = "android")]
+            {
+                let handle = _api.register_android_plugin(
+                    "io.github.maxwellnie.lan.device.android",
+                    "NetworkPlugin",
+                )?;
+                watch(_app.clone(), handle);
+            }
+            Ok(())
+        })
+        .build()
+}
@@ -0,0 +1,102 @@
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::{Mutex, Notify};
+
+use crate::error::ApiError;
+use crate::models::DeviceStatus;
+use crate::state::AppState;
+
+/// 默认刷新间隔（秒）
+pub const DEFAULT_INTERVAL_SECS: u64 = 30;
+/// 最短刷新间隔（秒），避免频繁请求设备
+const MIN_INTERVAL_SECS: u64 = 5;
+
+// 0 表示不自动刷新
+static INTERVAL_SECS: AtomicU64 = AtomicU64::new(DEFAULT_INTERVAL_SECS);
+// 修改间隔后唤醒刷新任务，新间隔立即生效
+static INTERVAL_CHANGED: OnceLock<Notify> = OnceLock::new();
+
+fn interval_changed() -> &'static Notify {
+    INTERVAL_CHANGED.get_or_init(Notify::new)
+}
+
+/// 随 device-status 事件发送的单个设备状态
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceStatusUpdate {
+    pub device_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<DeviceStatus>,
+    /// 获取失败时的错误，前端据此把设备标为离线或要求重新登录
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ApiError>,
+}
+
+/// 设置自动刷新间隔（秒），0 表示关闭
+pub fn set_interval(secs: u64) -> Result<(), String> {
+    if secs != 0 && secs < MIN_INTERVAL_SECS {
+        return Err(format!(
+            "Refresh interval must be 0 or at least {} seconds",
+            MIN_INTERVAL_SECS
+        ));
+    }
+    INTERVAL_SECS.store(secs, Ordering::SeqCst);
+    interval_changed().notify_one();
+    Ok(())
+}
+
+/// 刷新所有已连接设备的状态，请求期间不持有状态锁
+async fn refresh_all(app: &AppHandle, state: &Arc<Mutex<AppState>>) {
+    let mut clients = state.lock().await.connected_clients();
+    // 订阅了实时指标的设备已在持续推送，不再轮询
+    clients.retain(|(device_id, _)| !crate::metrics::is_subscribed(device_id));
+    let results =
+        futures::future::join_all(clients.into_iter().map(|(device_id, client)| async move {
+            let result = client.device_status().await;
+            (device_id, result)
+        }))
+        .await;
+
+    for (device_id, result) in results {
+        // 令牌失效时由 AppState 重新登录后重试
+        let result = match result {
+            Err(e) if e.is_auth() => state.lock().await.get_device_status(&device_id).await,
+            result => result,
+        };
+        let update = match result {
+            Ok(status) => DeviceStatusUpdate {
+                device_id,
+                status: Some(status),
+                error: None,
+            },
+            Err(e) => DeviceStatusUpdate {
+                device_id,
+                status: None,
+                error: Some(e),
+            },
+        };
+        let _ = app.emit("device-status", update);
+    }
+}
+
+/// 在后台定期刷新已连接设备的状态并发送 device-status 事件
+pub fn spawn(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<Arc<Mutex<AppState>>>().inner().clone();
+        loop {
+            let secs = INTERVAL_SECS.load(Ordering::SeqCst);
+            if secs == 0 {
+                interval_changed().notified().await;
+                continue;
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_secs(secs)) => {
+                    refresh_all(&app, &state).await;
+                }
+                _ = interval_changed().notified() => {}
+            }
+        }
+    });
+}
@@ -0,0 +1,356 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use std::sync::{Arc, Mutex as StdMutex, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Mutex;
+
+use crate::models::{ScheduleRun, ScheduledCommand};
+use crate::state::{app_data_dir, AppState};
+
+/// 检查到期计划的间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+/// 每个计划保留的执行记录条数
+const MAX_RUNS_PER_SCHEDULE: i64 = 50;
+/// 最短重复间隔（秒）
+const MIN_REPEAT_SECS: u64 = 60;
+
+static DB: OnceLock<Result<StdMutex<Connection>, String>> = OnceLock::new();
+
+fn open() -> Result<Connection, String> {
+    let dir = app_data_dir();
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create data dir: {}", e))?;
+    let conn = Connection::open(dir.join("schedules.db"))
+        .map_err(|e| format!("Failed to open schedule database: {}", e))?;
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS schedules (
+            id TEXT PRIMARY KEY,
+            device_id TEXT NOT NULL,
+            command TEXT NOT NULL,
+            args TEXT,
+            next_run INTEGER NOT NULL,
+            repeat_secs INTEGER,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            created_at INTEGER NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS schedule_runs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            schedule_id TEXT NOT NULL,
+            device_id TEXT NOT NULL,
+            command TEXT NOT NULL,
+            ran_at INTEGER NOT NULL,
+            success INTEGER NOT NULL,
+            message TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_schedule_runs_schedule ON schedule_runs(schedule_id);",
+    )
+    .map_err(|e| format!("Failed to initialize schedule database: {}", e))?;
+    Ok(conn)
+}
+
+/// 在数据库连接上执行操作，首次调用时打开数据库
+fn with_db<T>(f: impl FnOnce(&Connection) -> rusqlite::Result<T>) -> Result<T, String> {
+    let db = DB.get_or_init(|| open().map(StdMutex::new)).as_ref()?;
+    let conn = db
+        .lock()
+        .map_err(|_| "Schedule database lock poisoned".to_string())?;
+    f(&conn).map_err(|e| format!("Schedule database error: {}", e))
+}
+
+fn now_millis() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+fn row_to_schedule(row: &rusqlite::Row) -> rusqlite::Result<ScheduledCommand> {
+    let args: Option<String> = row.get(3)?;
+    let repeat_secs: Option<i64> = row.get(5)?;
+    Ok(ScheduledCommand {
+        id: row.get(0)?,
+        device_id: row.get(1)?,
+        command: row.get(2)?,
+        args: args.and_then(|a| serde_json::from_str(&a).ok()),
+        next_run: row.get(4)?,
+        repeat_secs: repeat_secs.map(|s| s as u64),
+        enabled: row.get(6)?,
+    })
+}
+
+const SCHEDULE_COLUMNS: &str = "id, device_id, command, args, next_run, repeat_secs, enabled";
+
+/// 列出计划命令，device_id 为空时列出全部
+pub fn list(device_id: Option<&str>) -> Result<Vec<ScheduledCommand>, String> {
+    with_db(|conn| {
+        let sql = format!(
+            "SELECT {} FROM schedules WHERE ?1 IS NULL OR device_id = ?1 ORDER BY next_run",
+            SCHEDULE_COLUMNS
+        );
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(params![device_id], row_to_schedule)?;
+        rows.collect()
+    })
+}
+
+/// 添加计划命令，run_at 为首次执行时间（Unix 毫秒）
+pub fn add(
+    device_id: &str,
+    command: &str,
+    args: Option<Vec<String>>,
+    run_at: i64,
+    repeat_secs: Option<u64>,
+) -> Result<ScheduledCommand, String> {
+    if command.trim().is_empty() {
+        return Err("Command cannot be empty".to_string());
+    }
+    if let Some(secs) = repeat_secs {
+        if secs < MIN_REPEAT_SECS {
+            return Err(format!(
+                "Repeat interval must be at least {} seconds",
+                MIN_REPEAT_SECS
+            ));
+        }
+    }
+    // 一次性计划不能设在过去，重复计划从下一个未来时间点开始
+    let now = now_millis();
+    let next_run = match repeat_secs {
+        None if run_at <= now => return Err("Scheduled time is in the past".to_string()),
+        None => run_at,
+        Some(secs) => advance(run_at, secs, now),
+    };
+
+    let schedule = ScheduledCommand {
+        id: uuid::Uuid::new_v4().to_string(),
+        device_id: device_id.to_string(),
+        command: command.to_string(),
+        args,
+        next_run,
+        repeat_secs,
+        enabled: true,
+    };
+    let args_json = schedule
+        .args
+        .as_ref()
+        .map(|a| serde_json::to_string(a).unwrap_or_default());
+    with_db(|conn| {
+        conn.execute(
+            "INSERT INTO schedules (id, device_id, command, args, next_run, repeat_secs, enabled, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, 1, ?7)",
+            params![
+                schedule.id,
+                schedule.device_id,
+                schedule.command,
+                args_json,
+                schedule.next_run,
+                schedule.repeat_secs.map(|s| s as i64),
+                now
+            ],
+        )
+    })?;
+    Ok(schedule)
+}
+
+/// 删除计划命令及其执行记录
+pub fn delete(id: &str) -> Result<bool, String> {
+    with_db(|conn| {
+        conn.execute(
+            "DELETE FROM schedule_runs WHERE schedule_id = ?1",
+            params![id],
+        )?;
+        let deleted = conn.execute("DELETE FROM schedules WHERE id = ?1", params![id])?;
+        Ok(deleted > 0)
+    })
+}
+
+/// 删除设备的所有计划命令
+pub fn delete_for_device(device_id: &str) -> Result<(), String> {
+    with_db(|conn| {
+        conn.execute(
+            "DELETE FROM schedule_runs WHERE device_id = ?1",
+            params![device_id],
+        )?;
+        conn.execute(
+            "DELETE FROM schedules WHERE device_id = ?1",
+            params![device_id],
+        )?;
+        Ok(())
+    })
+}
+
+/// 启用或暂停计划，重新启用时跳过暂停期间错过的执行
+pub fn set_enabled(id: &str, enabled: bool) -> Result<bool, String> {
+    with_db(|conn| {
+        let schedule = conn
+            .query_row(
+                &format!("SELECT {} FROM schedules WHERE id = ?1", SCHEDULE_COLUMNS),
+                params![id],
+                row_to_schedule,
+            )
+            .optional()?;
+        let Some(schedule) = schedule else {
+            return Ok(false);
+        };
+        let next_run = match schedule.repeat_secs {
+            Some(secs) if enabled => advance(schedule.next_run, secs, now_millis()),
+            _ => schedule.next_run,
+        };
+        conn.execute(
+            "UPDATE schedules SET enabled = ?2, next_run = ?3 WHERE id = ?1",
+            params![id, enabled, next_run],
+        )?;
+        Ok(true)
+    })
+}
+
+/// 计划的执行记录，最新的在前
+pub fn runs(schedule_id: &str) -> Result<Vec<ScheduleRun>, String> {
+    with_db(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT schedule_id, device_id, command, ran_at, success, message
+             FROM schedule_runs WHERE schedule_id = ?1 ORDER BY ran_at DESC",
+        )?;
+        let rows = stmt.query_map(params![schedule_id], |row| {
+            Ok(ScheduleRun {
+                schedule_id: row.get(0)?,
+                device_id: row.get(1)?,
+                command: row.get(2)?,
+                ran_at: row.get(3)?,
+                success: row.get(4)?,
+                message: row.get(5)?,
+            })
+        })?;
+        rows.collect()
+    })
+}
+
+/// 把重复计划的执行时间推进到 now 之后，离线期间错过的执行只补一次
+fn advance(next_run: i64, repeat_secs: u64, now: i64) -> i64 {
+    let step = repeat_secs as i64 * 1000;
+    if next_run > now {
+        return next_run;
+    }
+    next_run + ((now - next_run) / step + 1) * step
+}
+
+fn due(now: i64) -> Result<Vec<ScheduledCommand>, String> {
+    with_db(|conn| {
+        let sql = format!(
+            "SELECT {} FROM schedules WHERE enabled = 1 AND next_run <= ?1",
+            SCHEDULE_COLUMNS
+        );
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(params![now], row_to_schedule)?;
+        rows.collect()
+    })
+}
+
+/// 记录执行结果，并推进重复计划或停用一次性计划
+fn finish(schedule: &ScheduledCommand, run: &ScheduleRun) -> Result<(), String> {
+    with_db(|conn| {
+        conn.execute(
+            "INSERT INTO schedule_runs (schedule_id, device_id, command, ran_at, success, message)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                run.schedule_id,
+                run.device_id,
+                run.command,
+                run.ran_at,
+                run.success,
+                run.message
+            ],
+        )?;
+        conn.execute(
+            "DELETE FROM schedule_runs WHERE schedule_id = ?1 AND id NOT IN (
+                SELECT id FROM schedule_runs WHERE schedule_id = ?1 ORDER BY ran_at DESC LIMIT ?2
+            )",
+            params![run.schedule_id, MAX_RUNS_PER_SCHEDULE],
+        )?;
+        match schedule.repeat_secs {
+            Some(secs) => conn.execute(
+                "UPDATE schedules SET next_run = ?2 WHERE id = ?1",
+                params![schedule.id, advance(schedule.next_run, secs, run.ran_at)],
+            )?,
+            None => conn.execute(
+                "UPDATE schedules SET enabled = 0 WHERE id = ?1",
+                params![schedule.id],
+            )?,
+        };
+        Ok(())
+    })
+}
+
+/// 执行到期的计划命令，发送通知和 schedule-run 事件
+async fn run_due(app: &AppHandle, state: &Arc<Mutex<AppState>>) {
+    let schedules = match due(now_millis()) {
+        Ok(schedules) => schedules,
+        Err(e) => {
+            log::warn!("Failed to load due schedules: {}", e);
+            return;
+        }
+    };
+
+    for schedule in schedules {
+        let (result, device_name) = {
+            let mut state = state.lock().await;
+            let device_name = state
+                .get_saved_devices()
+                .into_iter()
+                .find(|d| d.id == schedule.device_id)
+                .map(|d| d.custom_name.unwrap_or(d.name))
+                .unwrap_or_else(|| schedule.device_id.clone());
+            let result = state
+                .execute_scheduled_command(
+                    &schedule.device_id,
+                    &schedule.command,
+                    schedule.args.clone(),
+                )
+                .await;
+            (result, device_name)
+        };
+
+        let (success, message) = match result {
+            Ok(result) if result.success => (true, result.stdout.trim().to_string()),
+            Ok(result) => {
+                let message = result
+                    .error_message
+                    .unwrap_or_else(|| result.stderr.trim().to_string());
+                (false, message)
+            }
+            Err(e) => (false, e.to_string()),
+        };
+        log::info!(
+            "Scheduled {} on {}: {}",
+            schedule.command,
+            schedule.device_id,
+            if success { "ok" } else { message.as_str() }
+        );
+
+        let run = ScheduleRun {
+            schedule_id: schedule.id.clone(),
+            device_id: schedule.device_id.clone(),
+            command: schedule.command.clone(),
+            ran_at: now_millis(),
+            success,
+            message,
+        };
+        if let Err(e) = finish(&schedule, &run) {
+            log::warn!("Failed to record schedule run: {}", e);
+        }
+
+        let body = if run.success {
+            format!("Scheduled {} sent", run.command)
+        } else {
+            format!("Scheduled {} failed: {}", run.command, run.message)
+        };
+        crate::events::show_notification(app, &device_name, &body);
+        let _ = app.emit("schedule-run", run);
+    }
+}
+
+/// 在后台定期检查并执行到期的计划命令
+pub fn spawn(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<Arc<Mutex<AppState>>>().inner().clone();
+        loop {
+            run_due(&app, &state).await;
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    });
+}
@@ -0,0 +1,263 @@
+use futures::{SinkExt, StreamExt};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Mutex as StdMutex, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::{self, Message};
+
+use crate::api::ApiClient;
+use crate::error::ApiError;
+
+/// 终端空闲时的心跳间隔，避免连接被路由器断开
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 发送给终端的消息
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ShellInput {
+    Input { data: String },
+    Resize { cols: u16, rows: u16 },
+}
+
+/// 随 shell-output 事件发送的终端输出
+#[derive(Debug, Clone, Serialize)]
+pub struct ShellOutput {
+    pub session_id: String,
+    pub device_id: String,
+    pub data: String,
+}
+
+/// 终端关闭时发送的 shell-closed 事件，error 为空表示正常退出或主动关闭
+#[derive(Debug, Clone, Serialize)]
+pub struct ShellClosed {
+    pub session_id: String,
+    pub device_id: String,
+    pub exit_code: Option<u32>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ShellSessionInfo {
+    pub session_id: String,
+    pub device_id: String,
+}
+
+struct ShellSession {
+    device_id: String,
+    input: mpsc::UnboundedSender<ShellInput>,
+}
+
+// 会话按 id 保存，多个设备可以同时打开终端；移除会话即关闭连接
+static SESSIONS: OnceLock<StdMutex<HashMap<String, ShellSession>>> = OnceLock::new();
+
+fn sessions() -> std::sync::MutexGuard<'static, HashMap<String, ShellSession>> {
+    SESSIONS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+}
+
+/// 握手被拒绝时按状态码转换错误，服务端在响应体中说明原因
+fn connect_error(error: tungstenite::Error) -> ApiError {
+    let tungstenite::Error::Http(response) = error else {
+        return ApiError::Network(error.to_string());
+    };
+    let reason = response
+        .body()
+        .as_deref()
+        .and_then(|body| serde_json::from_slice::<serde_json::Value>(body).ok())
+        .and_then(|body| body.get("error")?.as_str().map(str::to_string));
+    match response.status().as_u16() {
+        401 => ApiError::TokenExpired,
+        404 => ApiError::Unsupported("Remote shell is not supported by this device".to_string()),
+        status => ApiError::Server {
+            code: None,
+            message: reason.unwrap_or_else(|| format!("Shell connection rejected ({})", status)),
+        },
+    }
+}
+
+/// 取出缓冲区中完整的 UTF-8 文本，被截断的多字节字符留到下一块输出
+fn take_text(pending: &mut Vec<u8>) -> String {
+    let valid = match std::str::from_utf8(pending) {
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        _ => pending.len(),
+    };
+    let rest = pending.split_off(valid);
+    let text = String::from_utf8_lossy(pending).into_owned();
+    *pending = rest;
+    text
+}
+
+/// 连接设备的终端，返回会话 id；输出通过 shell-output 事件发送
+pub async fn open(
+    app: &AppHandle,
+    client: &ApiClient,
+    device_id: &str,
+    cols: u16,
+    rows: u16,
+) -> Result<String, ApiError> {
+    let cols = cols.to_string();
+    let rows = rows.to_string();
+    let (request, mut codec) =
+        client.ws_request("/ws/shell", &[("cols", &cols), ("rows", &rows)], true)?;
+    let (socket, _) = tokio_tungstenite::connect_async(request)
+        .await
+        .map_err(connect_error)?;
+
+    let session_id = uuid::Uuid::new_v4().to_string();
+    let (input, mut input_rx) = mpsc::unbounded_channel();
+    sessions().insert(
+        session_id.clone(),
+        ShellSession {
+            device_id: device_id.to_string(),
+            input,
+        },
+    );
+    log::info!("Shell session {} opened on {}", session_id, device_id);
+
+    let app = app.clone();
+    let mut closed = ShellClosed {
+        session_id: session_id.clone(),
+        device_id: device_id.to_string(),
+        exit_code: None,
+        error: None,
+    };
+    tokio::spawn(async move {
+        let (mut sink, mut stream) = socket.split();
+        let mut pending = Vec::new();
+        let mut ping = tokio::time::interval(PING_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = ping.tick() => {
+                    if sink.send(Message::Ping(Vec::new())).await.is_err() {
+                        closed.error = Some("Connection lost".to_string());
+                        break;
+                    }
+                }
+                input = input_rx.recv() => match input {
+                    Some(input) => {
+                        let text = serde_json::to_string(&input).unwrap_or_default();
+                        let text = match codec.seal(&text) {
+                            Ok(text) => text,
+                            Err(e) => {
+                                closed.error = Some(e);
+                                break;
+                            }
+                        };
+                        if let Err(e) = sink.send(Message::Text(text)).await {
+                            closed.error = Some(e.to_string());
+                            break;
+                        }
+                    }
+                    // 会话已被关闭
+                    None => {
+                        let _ = sink.send(Message::Close(None)).await;
+                        break;
+                    }
+                },
+                incoming = stream.next() => match incoming {
+                    Some(Ok(Message::Binary(data))) => {
+                        let data = match codec.open_binary(data) {
+                            Ok(data) => data,
+                            Err(e) => {
+                                closed.error = Some(e);
+                                break;
+                            }
+                        };
+                        pending.extend_from_slice(&data);
+                        let data = take_text(&mut pending);
+                        if !data.is_empty() {
+                            let output = ShellOutput {
+                                session_id: closed.session_id.clone(),
+                                device_id: closed.device_id.clone(),
+                                data,
+                            };
+                            let _ = app.emit("shell-output", output);
+                        }
+                    }
+                    Some(Ok(Message::Text(text))) => {
+                        let text = match codec.open(&text) {
+                            Ok(text) => text,
+                            Err(e) => {
+                                closed.error = Some(e);
+                                break;
+                            }
+                        };
+                        let Ok(control) = serde_json::from_str::<serde_json::Value>(&text) else {
+                            continue;
+                        };
+                        match control.get("type").and_then(|t| t.as_str()) {
+                            Some("exit") => {
+                                closed.exit_code =
+                                    control.get("code").and_then(|c| c.as_u64()).map(|c| c as u32);
+                            }
+                            Some("error") => {
+                                closed.error =
+                                    control.get("message").and_then(|m| m.as_str()).map(str::to_string);
+                            }
+                            _ => {}
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | None => break,
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => {
+                        closed.error = Some(e.to_string());
+                        break;
+                    }
+                },
+            }
+        }
+
+        sessions().remove(&closed.session_id);
+        log::info!(
+            "Shell session {} on {} closed",
+            closed.session_id,
+            closed.device_id
+        );
+        let _ = app.emit("shell-closed", closed);
+    });
+
+    Ok(session_id)
+}
+
+fn send(session_id: &str, input: ShellInput) -> Result<(), ApiError> {
+    sessions()
+        .get(session_id)
+        .and_then(|session| session.input.send(input).ok())
+        .ok_or_else(|| ApiError::Other("Shell session is closed".to_string()))
+}
+
+/// 向终端写入输入
+pub fn write(session_id: &str, data: String) -> Result<(), ApiError> {
+    send(session_id, ShellInput::Input { data })
+}
+
+/// 调整终端大小
+pub fn resize(session_id: &str, cols: u16, rows: u16) -> Result<(), ApiError> {
+    send(session_id, ShellInput::Resize { cols, rows })
+}
+
+/// 关闭终端，会话不存在时返回 false
+pub fn close(session_id: &str) -> bool {
+    sessions().remove(session_id).is_some()
+}
+
+/// 关闭设备的所有终端
+pub fn close_device(device_id: &str) {
+    sessions().retain(|_, session| session.device_id != device_id);
+}
+
+/// 当前打开的终端，device_id 为空时列出全部
+pub fn list(device_id: Option<&str>) -> Vec<ShellSessionInfo> {
+    sessions()
+        .iter()
+        .filter(|(_, session)| device_id.map_or(true, |id| session.device_id == id))
+        .map(|(session_id, session)| ShellSessionInfo {
+            session_id: session_id.clone(),
+            device_id: session.device_id.clone(),
+        })
+        .collect()
+}
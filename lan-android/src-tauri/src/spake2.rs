@@ -0,0 +1,112 @@
+use aes_gcm::aead::OsRng;
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::IsIdentity;
+use sha2::{Digest, Sha256, Sha512};
+use zeroize::Zeroize;
+
+/// 协议常量点 M、N 的生成种子，由哈希得到，没有人知道它们相对于基点的离散对数
+const SEED_M: &[u8] = b"lan-device-manager SPAKE2 M";
+const SEED_N: &[u8] = b"lan-device-manager SPAKE2 N";
+/// 口令映射为标量时的域分隔前缀
+const PASSWORD_DOMAIN: &[u8] = b"lan-device-manager SPAKE2 password";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    A,
+    B,
+}
+
+/// ristretto255 上的 SPAKE2（RFC 9382），与服务端 spake2 模块一致，A 为客户端一方，B 为服务端一方
+///
+/// 双方口令不一致时 finish 仍会得到（不同的）密钥，由调用方的密钥确认发现
+pub struct Spake2 {
+    side: Side,
+    secret: Scalar,
+    password: Scalar,
+    id_a: Vec<u8>,
+    id_b: Vec<u8>,
+    message: [u8; 32],
+}
+
+impl Spake2 {
+    /// 以 A 的身份开始交换，返回状态和发给对方的消息
+    pub fn start_a(password: &[u8], id_a: &[u8], id_b: &[u8]) -> (Self, Vec<u8>) {
+        Self::start(Side::A, password, id_a, id_b)
+    }
+
+    /// 以 B 的身份开始交换，返回状态和发给对方的消息
+    pub fn start_b(password: &[u8], id_a: &[u8], id_b: &[u8]) -> (Self, Vec<u8>) {
+        Self::start(Side::B, password, id_a, id_b)
+    }
+
+    fn start(side: Side, password: &[u8], id_a: &[u8], id_b: &[u8]) -> (Self, Vec<u8>) {
+        let password = Scalar::hash_from_bytes::<Sha512>(&[PASSWORD_DOMAIN, password].concat());
+        let secret = Scalar::random(&mut OsRng);
+        let message = (RISTRETTO_BASEPOINT_POINT * secret + blinding(side) * password)
+            .compress()
+            .to_bytes();
+        let state = Self {
+            side,
+            secret,
+            password,
+            id_a: id_a.to_vec(),
+            id_b: id_b.to_vec(),
+            message,
+        };
+        (state, message.to_vec())
+    }
+
+    /// 以对方的消息完成交换，返回 32 字节的共享密钥
+    pub fn finish(self, peer: &[u8]) -> Result<Vec<u8>, String> {
+        let invalid = || "Invalid SPAKE2 message".to_string();
+        let peer_point = CompressedRistretto::from_slice(peer)
+            .map_err(|_| invalid())?
+            .decompress()
+            .filter(|point| !point.is_identity())
+            .ok_or_else(invalid)?;
+        let peer_side = match self.side {
+            Side::A => Side::B,
+            Side::B => Side::A,
+        };
+        let shared = (peer_point - blinding(peer_side) * self.password) * self.secret;
+        if shared.is_identity() {
+            return Err(invalid());
+        }
+
+        let (message_a, message_b) = match self.side {
+            Side::A => (&self.message[..], peer),
+            Side::B => (peer, &self.message[..]),
+        };
+        // 按 RFC 9382 的顺序对各部分加长度前缀后计算哈希
+        let mut transcript = Sha256::new();
+        for part in [
+            &self.id_a[..],
+            &self.id_b[..],
+            message_a,
+            message_b,
+            shared.compress().as_bytes(),
+            self.password.as_bytes(),
+        ] {
+            transcript.update((part.len() as u64).to_le_bytes());
+            transcript.update(part);
+        }
+        Ok(transcript.finalize().to_vec())
+    }
+}
+
+impl Drop for Spake2 {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+        self.password.zeroize();
+    }
+}
+
+/// A 的消息用 M 混淆，B 的消息用 N 混淆
+fn blinding(side: Side) -> RistrettoPoint {
+    match side {
+        Side::A => RistrettoPoint::hash_from_bytes::<Sha512>(SEED_M),
+        Side::B => RistrettoPoint::hash_from_bytes::<Sha512>(SEED_N),
+    }
+}
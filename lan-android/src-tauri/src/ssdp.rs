@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+
+use crate::models::DeviceInfo;
+
+/// SSDP 多播地址和端口
+const SSDP_ADDR: Ipv4Addr = Ipv4Addr::new(239, 255, 255, 250);
+const SSDP_PORT: u16 = 1900;
+/// 与服务端 ssdp 模块的搜索目标一致
+const SEARCH_TARGET: &str = "urn:lan-device-manager:service:agent:1";
+
+/// 解析 SSDP 响应的头部，名称统一转为大写
+fn parse_headers(message: &str) -> Option<HashMap<String, String>> {
+    let mut lines = message.lines();
+    if !lines.next()?.starts_with("HTTP/1.1 200") {
+        return None;
+    }
+    Some(
+        lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_ascii_uppercase(), value.trim().to_string()))
+            .collect(),
+    )
+}
+
+/// 从 LOCATION（如 http://192.168.1.10:8080/api/health）中取出端口
+fn port_from_location(location: &str) -> Option<u16> {
+    let authority = location.split("://").nth(1)?.split('/').next()?;
+    authority.rsplit_once(':')?.1.parse().ok()
+}
+
+fn device_from_response(message: &str, peer: SocketAddr) -> Option<DeviceInfo> {
+    let headers = parse_headers(message)?;
+    if headers.get("ST").map(|st| st.as_str()) != Some(SEARCH_TARGET) {
+        return None;
+    }
+    let port = headers.get("LOCATION").and_then(|l| port_from_location(l))?;
+    let uuid = headers.get("X-DEVICE-UUID").cloned().or_else(|| {
+        // USN 格式：uuid:<uuid>::<search target>
+        headers
+            .get("USN")?
+            .strip_prefix("uuid:")?
+            .split("::")
+            .next()
+            .map(|s| s.to_string())
+    })?;
+
+    Some(DeviceInfo {
+        id: format!("ssdp:{}", uuid),
+        uuid,
+        name: headers
+            .get("X-DEVICE-NAME")
+            .cloned()
+            .unwrap_or_else(|| peer.ip().to_string()),
+        ip_address: peer.ip().to_string(),
+        port,
+        version: headers
+            .get("X-DEVICE-VERSION")
+            .cloned()
+            .unwrap_or_else(|| "1.0.0".to_string()),
+        requires_auth: headers.get("X-DEVICE-AUTH").map(|a| a.as_str()) == Some("required"),
+        discovered_at: chrono::Utc::now(),
+        os: None,
+        os_version: None,
+    })
+}
+
+/// 发送 SSDP 搜索并在超时前收集响应，用于 mDNS 被屏蔽的网络
+pub async fn search(timeout: Duration) -> Result<Vec<DeviceInfo>, String> {
+    let socket = UdpSocket::bind("0.0.0.0:0")
+        .await
+        .map_err(|e| format!("Failed to bind SSDP socket: {}", e))?;
+    let _ = socket.set_multicast_ttl_v4(2);
+
+    let mx = timeout.as_secs().clamp(1, 5);
+    let request = format!(
+        "M-SEARCH * HTTP/1.1\r\nHOST: {}:{}\r\nMAN: \"ssdp:discover\"\r\nMX: {}\r\nST: {}\r\n\r\n",
+        SSDP_ADDR, SSDP_PORT, mx, SEARCH_TARGET
+    );
+    let group = SocketAddr::V4(SocketAddrV4::new(SSDP_ADDR, SSDP_PORT));
+    // UDP 可能丢包，发送两次
+    for _ in 0..2 {
+        socket
+            .send_to(request.as_bytes(), group)
+            .await
+            .map_err(|e| format!("Failed to send SSDP search: {}", e))?;
+    }
+
+    let mut devices: HashMap<String, DeviceInfo> = HashMap::new();
+    let mut buf = [0u8; 2048];
+    let deadline = tokio::time::Instant::now() + timeout;
+    while let Ok(received) = tokio::time::timeout_at(deadline, socket.recv_from(&mut buf)).await {
+        let Ok((len, peer)) = received else {
+            continue;
+        };
+        let message = String::from_utf8_lossy(&buf[..len]);
+        if let Some(device) = device_from_response(&message, peer) {
+            log::info!(
+                "SSDP found device {} at {}:{}",
+                device.name,
+                device.ip_address,
+                device.port
+            );
+            devices.insert(device.uuid.clone(), device);
+        }
+    }
+
+    Ok(devices.into_values().collect())
+}
@@ -1,12 +1,16 @@
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::RwLock;
+use std::time::Instant;
+use zeroize::Zeroizing;
 
 use crate::api::ApiClient;
+use crate::error::ApiError;
 use crate::mdns::MdnsDiscovery;
-use crate::models::{DeviceInfo, SavedDevice, AuthResult, CommandResult, DeviceStatus, ConnectResult};
+use crate::models::{DeviceInfo, SavedDevice, AuthResult, CommandResult, DeviceStatus, ConnectResult, SpeedTestResult, CatalogEntry, SyncResult, ConnectionProfile};
 
 /// 获取应用数据目录
-fn app_data_dir() -> PathBuf {
+pub(crate) fn app_data_dir() -> PathBuf {
     // 尝试使用 Tauri 的标准路径
     #[cfg(target_os = "android")]
     {
@@ -29,17 +33,72 @@ fn app_data_dir() -> PathBuf {
     }
 }
 
+/// 获取本机客户端ID，首次调用时生成并保存
+pub fn client_device_id() -> String {
+    let path = app_data_dir().join("client.uuid");
+    if let Ok(id) = std::fs::read_to_string(&path) {
+        let id = id.trim();
+        if uuid::Uuid::parse_str(id).is_ok() {
+            return id.to_string();
+        }
+    }
+
+    let id = uuid::Uuid::new_v4().to_string();
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    match std::fs::write(&path, &id) {
+        Ok(_) => log::info!("Generated client device ID: {}", id),
+        Err(e) => log::error!("Failed to save client device ID: {}", e),
+    }
+    id
+}
+
+// 未经验证就关闭确认时返回的错误
+const CONFIRMATION_OFF_REQUIRED: &str =
+    "Confirm with your fingerprint or screen lock to turn off confirmation";
+
+// 当前界面语言，作为 Accept-Language 发送给服务端
+static CLIENT_LANGUAGE: RwLock<String> = RwLock::new(String::new());
+
+pub fn set_client_language(language: &str) {
+    if let Ok(mut current) = CLIENT_LANGUAGE.write() {
+        *current = language.to_string();
+    }
+}
+
+pub fn client_language() -> String {
+    match CLIENT_LANGUAGE.read() {
+        Ok(language) if !language.is_empty() => language.clone(),
+        _ => "en".to_string(),
+    }
+}
+
 pub struct AppState {
     mdns_discovery: Option<MdnsDiscovery>,
     connected_devices: HashMap<String, ApiClient>,
     saved_devices: Vec<SavedDevice>,
-    device_passwords: HashMap<String, String>, // 存储设备密码
-    device_tokens: HashMap<String, String>,    // 存储设备token
+    device_passwords: HashMap<String, Zeroizing<String>>, // 存储设备密码，释放时清零
+    device_tokens: HashMap<String, Zeroizing<String>>,    // 存储设备token，释放时清零
+    /// 已删除设备的 UUID 和删除时间（Unix 毫秒），同步时告知其他客户端
+    deleted_devices: HashMap<String, i64>,
+    /// 最近一次 SSDP 搜索发现的设备，按 UUID 索引
+    ssdp_devices: HashMap<String, DeviceInfo>,
+    /// 后台接收设备事件的任务，按设备ID索引
+    event_watchers: HashMap<String, tokio::task::JoinHandle<()>>,
+    /// 与设备配对得到的密钥（十六进制），按设备 UUID 索引，之后连接无需密码
+    pairing_keys: HashMap<String, Zeroizing<String>>,
+    /// 最近一次确认危险命令的时间，按设备ID索引
+    confirmed_at: HashMap<String, Instant>,
 }
 
 impl AppState {
     pub fn new() -> Self {
         let saved_devices = Self::load_saved_devices();
+        let deleted_devices = std::fs::read_to_string(Self::deleted_devices_file_path())
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
         
         Self {
             mdns_discovery: None,
@@ -47,6 +106,104 @@ impl AppState {
             saved_devices,
             device_passwords: HashMap::new(),
             device_tokens: HashMap::new(),
+            deleted_devices,
+            ssdp_devices: HashMap::new(),
+            event_watchers: HashMap::new(),
+            pairing_keys: Self::load_pairing_keys(),
+            confirmed_at: HashMap::new(),
+        }
+    }
+
+    fn pairing_keys_file_path() -> PathBuf {
+        app_data_dir().join("pairings.json")
+    }
+
+    fn load_pairing_keys() -> HashMap<String, Zeroizing<String>> {
+        let Ok(json) = std::fs::read_to_string(Self::pairing_keys_file_path()) else {
+            return HashMap::new();
+        };
+        let json = Zeroizing::new(json);
+        serde_json::from_str::<HashMap<String, String>>(&json)
+            .map(|keys| keys.into_iter().map(|(uuid, key)| (uuid, Zeroizing::new(key))).collect())
+            .unwrap_or_default()
+    }
+
+    fn persist_pairing_keys(&self) {
+        let keys: HashMap<&str, &str> = self.pairing_keys.iter()
+            .map(|(uuid, key)| (uuid.as_str(), key.as_str()))
+            .collect();
+        match serde_json::to_string(&keys).map(Zeroizing::new) {
+            Ok(json) => {
+                if let Err(e) = std::fs::write(Self::pairing_keys_file_path(), json.as_bytes()) {
+                    log::error!("Failed to save pairing keys: {}", e);
+                }
+            }
+            Err(e) => log::error!("Failed to serialize pairing keys: {}", e),
+        }
+    }
+
+    /// 忘记与设备的配对，下次连接时需要输入密码
+    fn forget_pairing(&mut self, uuid: &str) {
+        if self.pairing_keys.remove(uuid).is_some() {
+            self.persist_pairing_keys();
+        }
+    }
+
+    /// 使用保存的配对密钥登录，只在登录成功时返回结果
+    ///
+    /// 设备拒绝配对密钥（已取消配对或修改了密码）时删除本地密钥
+    async fn login_with_pairing(&mut self, client: &mut ApiClient, uuid: &str) -> Option<AuthResult> {
+        let key = hex::decode(self.pairing_keys.get(uuid)?.as_str()).ok().map(Zeroizing::new)?;
+        match client.authenticate_paired(&key).await {
+            Ok(result) if result.success => Some(result),
+            Ok(result) => {
+                log::warn!("Pairing with {} rejected: {:?}", uuid, result.error);
+                self.forget_pairing(uuid);
+                None
+            }
+            Err(e) => {
+                log::warn!("Paired login to {} failed: {}", uuid, e);
+                None
+            }
+        }
+    }
+
+    /// 使用密码登录：先与设备配对并保存配对密钥，设备不支持配对时使用挑战-响应登录
+    async fn login_with_password(
+        &mut self,
+        client: &mut ApiClient,
+        uuid: &str,
+        password: &str,
+    ) -> Result<AuthResult, ApiError> {
+        let key = match client.pair(password).await {
+            Ok(key) => key,
+            Err(ApiError::Unsupported(_)) => return client.authenticate(password).await,
+            Err(e) => {
+                return Ok(AuthResult {
+                    success: false,
+                    token: None,
+                    expires_in: None,
+                    error: Some(e.to_string()),
+                })
+            }
+        };
+        self.pairing_keys.insert(uuid.to_string(), Zeroizing::new(hex::encode(key.as_slice())));
+        self.persist_pairing_keys();
+        client.authenticate_paired(&key).await
+    }
+
+    fn deleted_devices_file_path() -> PathBuf {
+        app_data_dir().join("deleted_devices.json")
+    }
+
+    fn persist_deleted_devices(&self) {
+        match serde_json::to_string(&self.deleted_devices) {
+            Ok(json) => {
+                if let Err(e) = std::fs::write(Self::deleted_devices_file_path(), json) {
+                    log::error!("Failed to save deleted devices: {}", e);
+                }
+            }
+            Err(e) => log::error!("Failed to serialize deleted devices: {}", e),
         }
     }
     
@@ -117,12 +274,12 @@ impl AppState {
     }
 
     /// 开始设备发现
-    pub async fn start_discovery(&mut self) -> Result<String, String> {
+    pub async fn start_discovery(&mut self, app: tauri::AppHandle) -> Result<String, String> {
         if self.mdns_discovery.is_some() {
             return Err("Discovery already running".to_string());
         }
 
-        let mut discovery = MdnsDiscovery::new()
+        let mut discovery = MdnsDiscovery::new(app)
             .map_err(|e| format!("Failed to create discovery: {}", e))?;
         
         discovery.start()
@@ -132,6 +289,11 @@ impl AppState {
         Ok("Discovery started".to_string())
     }
 
+    /// 是否正在进行设备发现
+    pub fn is_discovering(&self) -> bool {
+        self.mdns_discovery.is_some()
+    }
+
     /// 停止设备发现
     pub async fn stop_discovery(&mut self) -> Result<String, String> {
         if let Some(mut discovery) = self.mdns_discovery.take() {
@@ -142,7 +304,7 @@ impl AppState {
     }
 
     /// 重启设备发现（用于网络变化后重新订阅多播组）
-    pub async fn restart_discovery(&mut self) -> Result<String, String> {
+    pub async fn restart_discovery(&mut self, app: tauri::AppHandle) -> Result<String, String> {
         log::info!("Restarting mDNS discovery due to network change");
         
         // 停止现有发现
@@ -154,7 +316,7 @@ impl AppState {
         tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
         
         // 重新启动发现
-        let mut discovery = MdnsDiscovery::new()
+        let mut discovery = MdnsDiscovery::new(app)
             .map_err(|e| format!("Failed to create discovery: {}", e))?;
         
         discovery.start()
@@ -165,53 +327,156 @@ impl AppState {
         Ok("Discovery restarted".to_string())
     }
 
+    /// 保存 SSDP 搜索结果，之后随 mDNS 发现的设备一起返回
+    pub fn set_ssdp_devices(&mut self, devices: Vec<DeviceInfo>) {
+        self.ssdp_devices = devices.into_iter().map(|d| (d.uuid.clone(), d)).collect();
+    }
+
     /// 获取已发现的设备，并同步更新已保存设备的信息
     pub async fn get_discovered_devices(&mut self) -> Vec<DeviceInfo> {
-        if let Some(discovery) = &self.mdns_discovery {
-            let discovered = discovery.get_devices().await;
-            let mut updated = false;
-            
-            // 同步更新已保存设备的信息（支持端口号/IP变化后自动更新）
-            for device in &discovered {
-                if let Some(saved) = self.saved_devices.iter_mut().find(|d| d.uuid == device.uuid) {
-                    if saved.ip_address != device.ip_address || saved.port != device.port {
-                        log::info!(
-                            "Updating saved device {} - IP: {} -> {}, Port: {} -> {}",
-                            saved.name, saved.ip_address, device.ip_address, saved.port, device.port
-                        );
-                        saved.ip_address = device.ip_address.clone();
-                        saved.port = device.port;
-                        saved.id = device.id.clone();
-                        updated = true;
-                    }
+        let mut discovered = match &self.mdns_discovery {
+            Some(discovery) => discovery.get_devices().await,
+            None => Vec::new(),
+        };
+        // 同一设备同时通过两种方式发现时以 mDNS 为准
+        for device in self.ssdp_devices.values() {
+            if !discovered.iter().any(|d| d.uuid == device.uuid) {
+                discovered.push(device.clone());
+            }
+        }
+
+        let mut updated = false;
+        
+        // 同步更新已保存设备的信息（支持端口号/IP变化后自动更新）
+        for device in &discovered {
+            if let Some(saved) = self.saved_devices.iter_mut().find(|d| d.uuid == device.uuid) {
+                if saved.ip_address != device.ip_address || saved.port != device.port {
+                    log::info!(
+                        "Updating saved device {} - IP: {} -> {}, Port: {} -> {}",
+                        saved.name, saved.ip_address, device.ip_address, saved.port, device.port
+                    );
+                    saved.ip_address = device.ip_address.clone();
+                    saved.port = device.port;
+                    saved.id = device.id.clone();
+                    updated = true;
                 }
             }
-            
-            // 如果有更新，持久化到文件
-            if updated {
-                self.persist_saved_devices();
+        }
+        
+        // 如果有更新，持久化到文件
+        if updated {
+            self.persist_saved_devices();
+        }
+
+        discovered
+    }
+
+    /// 已保存设备的连接设置，未保存的设备使用默认设置
+    fn connection_profile(&self, ip: &str, port: u16) -> ConnectionProfile {
+        self.saved_devices.iter()
+            .find(|d| d.ip_address == ip && d.port == port)
+            .map(|d| d.profile.clone())
+            .unwrap_or_default()
+    }
+
+    /// 设备的候选地址：设置了主机名时先尝试主机名，再尝试发现得到的 IP
+    ///
+    /// 备用地址使用相同的协议，不会从 HTTPS 降级为 HTTP
+    fn candidate_clients(device: &SavedDevice) -> Vec<ApiClient> {
+        let mut clients = Vec::new();
+        if let Some(host) = device.profile.host.as_deref().filter(|h| !h.trim().is_empty()) {
+            clients.push(ApiClient::with_profile(host, device.port, &device.profile));
+        }
+        if !device.ip_address.is_empty() {
+            clients.push(ApiClient::with_profile(&device.ip_address, device.port, &device.profile));
+        }
+        clients
+    }
+
+    /// 返回第一个能通过健康检查的客户端；都失败时返回首选地址的客户端和错误
+    async fn reachable_client(&mut self, device: &SavedDevice) -> (ApiClient, Result<bool, ApiError>) {
+        let mut candidates = Self::candidate_clients(device).into_iter();
+        let Some(first) = candidates.next() else {
+            return (
+                ApiClient::with_profile(&device.ip_address, device.port, &device.profile),
+                Err(ApiError::Other("Device has no address".to_string())),
+            );
+        };
+        let first_health = first.health_check().await;
+        if matches!(first_health, Ok(true)) {
+            self.record_fallback(&device.uuid, None);
+            return (first, first_health);
+        }
+
+        for client in candidates {
+            if let Ok(true) = client.health_check().await {
+                log::info!(
+                    "{} is unreachable, connected to {} instead",
+                    first.base_url(),
+                    client.base_url()
+                );
+                self.record_fallback(&device.uuid, Some(client.base_url().to_string()));
+                return (client, Ok(true));
             }
-            
-            discovered
-        } else {
-            Vec::new()
+        }
+        (first, first_health)
+    }
+
+    /// 保存设备实际使用的备用地址，供界面提示首选地址不可用
+    fn record_fallback(&mut self, uuid: &str, fallback: Option<String>) {
+        let Some(saved) = self.saved_devices.iter_mut().find(|d| d.uuid == uuid) else {
+            return;
+        };
+        if saved.profile.last_fallback != fallback {
+            saved.profile.last_fallback = fallback;
+            self.persist_saved_devices();
         }
     }
 
     /// 检查设备是否需要认证
-    pub async fn check_device_auth_required(&self, ip: &str, port: u16) -> Result<bool, String> {
-        let client = ApiClient::new(ip, port);
+    pub async fn check_device_auth_required(&self, ip: &str, port: u16) -> Result<bool, ApiError> {
+        let client = ApiClient::with_profile(ip, port, &self.connection_profile(ip, port));
         client.check_auth_required().await
     }
 
     /// 连接到设备
     pub async fn connect_to_device(&mut self, device: SavedDevice, password: Option<String>) -> Result<ConnectResult, String> {
-        // 创建 API 客户端
-        let mut client = ApiClient::new(&device.ip_address, device.port);
+        // 前端传入的设备可能不含连接设置，以本地保存的为准
+        let mut device = device;
+        if let Some(saved) = self.saved_devices.iter().find(|d| d.uuid == device.uuid || d.id == device.id) {
+            device.profile = saved.profile.clone();
+        }
+
+        // 已连接的设备复用现有客户端和令牌，刷新设备列表时不必重新握手和登录
+        if password.is_none() {
+            if let Some(existing) = self.connected_devices.get(&device.id) {
+                let same_address = Self::candidate_clients(&device)
+                    .iter()
+                    .any(|c| c.base_url() == existing.base_url());
+                if same_address && matches!(existing.health_check().await, Ok(true)) {
+                    return Ok(ConnectResult {
+                        success: true,
+                        requires_auth: existing.get_token().is_some(),
+                        error: None,
+                    });
+                }
+            }
+        }
+
+        // 重新连接后使用新的客户端和令牌，原来的事件接收任务不再有效
+        self.stop_watching_events(&device.id);
+
+        // 依次尝试首选地址和备用地址，记录实际使用的备用地址
+        let (mut client, health) = self.reachable_client(&device).await;
         
         // 测试连接
-        match client.health_check().await {
+        match health {
             Ok(true) => {
+                // 握手失败（旧版本服务端不支持）不影响连接
+                if let Err(e) = client.handshake().await {
+                    log::debug!("Handshake with {} skipped: {}", device.ip_address, e);
+                }
+
                 // 检查是否需要认证
                 let requires_auth = match client.check_auth_required().await {
                     Ok(required) => required,
@@ -219,17 +484,31 @@ impl AppState {
                 };
 
                 if requires_auth {
+                    // 已配对的设备使用配对密钥登录，无需密码
+                    if let Some(auth_result) = self.login_with_pairing(&mut client, &device.uuid).await {
+                        self.save_device_internal(device.clone());
+                        if let Some(ref token) = auth_result.token {
+                            self.device_tokens.insert(device.id.clone(), Zeroizing::new(token.clone()));
+                        }
+                        self.connected_devices.insert(device.id.clone(), client);
+                        return Ok(ConnectResult {
+                            success: true,
+                            requires_auth: true,
+                            error: None,
+                        });
+                    }
+
                     // 如果需要认证，检查是否提供了密码
                     if let Some(pwd) = password {
                         // 尝试认证
-                        match client.authenticate(&pwd).await {
+                        match self.login_with_password(&mut client, &device.uuid, &pwd).await {
                             Ok(auth_result) => {
                                 if auth_result.success {
                                     // 认证成功，保存设备和密码
                                     self.save_device_internal(device.clone());
-                                    self.device_passwords.insert(device.id.clone(), pwd);
+                                    self.device_passwords.insert(device.id.clone(), Zeroizing::new(pwd));
                                     if let Some(ref token) = auth_result.token {
-                                        self.device_tokens.insert(device.id.clone(), token.clone());
+                                        self.device_tokens.insert(device.id.clone(), Zeroizing::new(token.clone()));
                                     }
                                     self.connected_devices.insert(device.id.clone(), client);
                                     
@@ -290,94 +569,304 @@ impl AppState {
     /// 断开设备连接
     pub async fn disconnect_device(&mut self, device_id: &str) -> Result<bool, String> {
         self.connected_devices.remove(device_id);
+        self.stop_watching_events(device_id);
+        crate::shell::close_device(device_id);
+        crate::metrics::unsubscribe(device_id);
         Ok(true)
     }
 
+    /// 在后台接收已连接设备的事件并显示为本地通知
+    pub fn watch_events(&mut self, app: tauri::AppHandle, device_id: &str) -> Result<(), String> {
+        let client = self.connected_devices.get(device_id)
+            .ok_or_else(|| "Device not connected".to_string())?
+            .clone();
+        let device_name = self.saved_devices.iter()
+            .find(|d| d.id == device_id)
+            .map(|d| d.custom_name.clone().unwrap_or_else(|| d.name.clone()))
+            .unwrap_or_else(|| device_id.to_string());
+
+        self.stop_watching_events(device_id);
+        let watcher = crate::events::spawn_watcher(app, device_name, client);
+        self.event_watchers.insert(device_id.to_string(), watcher);
+        Ok(())
+    }
+
+    /// 是否正在接收设备事件
+    pub fn is_watching_events(&self, device_id: &str) -> bool {
+        self.event_watchers
+            .get(device_id)
+            .map_or(false, |watcher| !watcher.is_finished())
+    }
+
+    /// 停止接收设备事件
+    pub fn stop_watching_events(&mut self, device_id: &str) {
+        if let Some(watcher) = self.event_watchers.remove(device_id) {
+            watcher.abort();
+        }
+    }
+
     /// 认证设备
     pub async fn authenticate_device(
         &mut self,
         device_id: &str,
         password: &str,
-    ) -> Result<AuthResult, String> {
-        let client = self.connected_devices.get_mut(device_id)
-            .ok_or_else(|| "Device not connected".to_string())?;
+    ) -> Result<AuthResult, ApiError> {
+        let mut client = self.connected_devices.remove(device_id)
+            .ok_or_else(|| ApiError::Other("Device not connected".to_string()))?;
+        let uuid = self.device_uuid(device_id);
 
-        let result = client.authenticate(password).await?;
+        let result = self.login_with_password(&mut client, &uuid, password).await;
+        self.connected_devices.insert(device_id.to_string(), client);
+        let result = result?;
         
         if result.success {
             // 保存密码和token
-            self.device_passwords.insert(device_id.to_string(), password.to_string());
+            self.device_passwords.insert(device_id.to_string(), Zeroizing::new(password.to_string()));
             if let Some(ref token) = result.token {
-                self.device_tokens.insert(device_id.to_string(), token.clone());
+                self.device_tokens.insert(device_id.to_string(), Zeroizing::new(token.clone()));
             }
         }
         
         Ok(result)
     }
 
-    /// 执行命令
-    pub async fn execute_command(
-        &mut self,
+    /// 已保存设备的 UUID，未保存时使用设备ID
+    fn device_uuid(&self, device_id: &str) -> String {
+        self.saved_devices.iter()
+            .find(|d| d.id == device_id)
+            .map(|d| d.uuid.clone())
+            .unwrap_or_else(|| device_id.to_string())
+    }
+
+    /// 令牌失效时用配对密钥或保存的密码重新登录，成功后调用方重试一次请求
+    ///
+    /// 都不可用时清除本地令牌，由前端要求用户重新输入密码
+    async fn reauthenticate(&mut self, device_id: &str) -> bool {
+        let Some(mut client) = self.connected_devices.remove(device_id) else {
+            return false;
+        };
+        let uuid = self.device_uuid(device_id);
+        client.clear_token();
+
+        let mut result = self.login_with_pairing(&mut client, &uuid).await;
+        if result.is_none() {
+            if let Some(password) = self.device_passwords.get(device_id).cloned() {
+                result = self.login_with_password(&mut client, &uuid, &password).await
+                    .ok()
+                    .filter(|r| r.success);
+            }
+        }
+
+        let success = match result.and_then(|r| r.token) {
+            Some(token) => {
+                log::info!("Token for device {} expired, signed in again", device_id);
+                self.device_tokens.insert(device_id.to_string(), Zeroizing::new(token));
+                true
+            }
+            None => {
+                log::warn!("Token expired for device {}, authentication required", device_id);
+                self.device_tokens.remove(device_id);
+                false
+            }
+        };
+        self.connected_devices.insert(device_id.to_string(), client);
+        success
+    }
+
+    async fn send_command(
+        &self,
         device_id: &str,
         command: &str,
         args: Option<Vec<String>>,
-    ) -> Result<CommandResult, String> {
+    ) -> Result<CommandResult, ApiError> {
         let client = self.connected_devices.get(device_id)
-            .ok_or_else(|| "Device not connected".to_string())?;
+            .ok_or_else(|| ApiError::Other("Device not connected".to_string()))?;
 
-        let result = match command {
+        match command {
             "shutdown" => client.shutdown(args.as_ref().and_then(|a| a.first()).and_then(|s| s.parse().ok())).await,
             "restart" => client.restart(args.as_ref().and_then(|a| a.first()).and_then(|s| s.parse().ok())).await,
             "sleep" => client.sleep().await,
             "lock" => client.lock().await,
             _ => client.execute_command(command, args).await,
-        };
+        }
+    }
 
-        // 检查是否是认证错误
-        if let Err(ref e) = result {
-            let error_str = e.to_string();
-            if error_str.contains("Invalid") || error_str.contains("expired") || error_str.contains("token") {
-                log::warn!("Token expired for device {}, authentication required", device_id);
-                // 清除本地认证状态
-                self.device_tokens.remove(device_id);
-                return Err("Authentication expired. Please reconnect and enter password again.".to_string());
+    /// 已连接设备的客户端副本，供后台任务在不持有状态锁的情况下访问设备
+    pub fn connected_clients(&self) -> Vec<(String, ApiClient)> {
+        self.connected_devices
+            .iter()
+            .map(|(id, client)| (id.clone(), client.clone()))
+            .collect()
+    }
+
+    /// 已连接设备的客户端副本，供文件传输等耗时操作在不持有状态锁的情况下使用
+    pub fn connected_client(&self, device_id: &str) -> Result<ApiClient, ApiError> {
+        self.connected_devices
+            .get(device_id)
+            .cloned()
+            .ok_or_else(|| ApiError::Other("Device not connected".to_string()))
+    }
+
+    /// 令牌失效时重新登录，返回带新令牌的客户端副本
+    pub async fn refreshed_client(&mut self, device_id: &str) -> Result<ApiClient, ApiError> {
+        if !self.reauthenticate(device_id).await {
+            return Err(ApiError::TokenExpired);
+        }
+        self.connected_client(device_id)
+    }
+
+    /// 命令是否需要先确认：设备开启了确认，且最近没有确认过
+    pub fn needs_confirmation(&self, device_id: &str, command: &str) -> bool {
+        let required = self.saved_devices.iter()
+            .find(|d| d.id == device_id || d.uuid == device_id)
+            .map_or(false, |d| d.profile.require_confirmation);
+        let recent = self.confirmed_at
+            .get(device_id)
+            .map_or(false, |at| at.elapsed() < crate::guard::CONFIRMATION_VALID_FOR);
+        required && crate::guard::is_destructive(command) && !recent
+    }
+
+    /// 记录用户已确认，之后一段时间内执行危险命令无需再次确认
+    pub fn record_confirmation(&mut self, device_id: &str) {
+        self.confirmed_at.insert(device_id.to_string(), Instant::now());
+    }
+
+    /// 是否会关闭设备的确认要求，关闭前需要先通过 guard::confirm 验证
+    pub fn disables_confirmation(&self, device_id: &str, required: bool) -> bool {
+        !required && self.saved_devices.iter()
+            .find(|d| d.id == device_id || d.uuid == device_id)
+            .is_some_and(|d| d.profile.require_confirmation)
+    }
+
+    /// 设置执行关机、重启和自定义命令前是否需要指纹或锁屏密码确认
+    ///
+    /// 关闭确认时 confirmed 必须为 true，即调用方已经通过 guard::confirm 验证
+    pub fn set_require_confirmation(
+        &mut self,
+        device_id: &str,
+        required: bool,
+        confirmed: bool,
+    ) -> Result<bool, String> {
+        if self.disables_confirmation(device_id, required) && !confirmed {
+            return Err(CONFIRMATION_OFF_REQUIRED.to_string());
+        }
+        let device = self.saved_devices.iter_mut()
+            .find(|d| d.id == device_id || d.uuid == device_id)
+            .ok_or_else(|| "Device not found".to_string())?;
+        device.profile.require_confirmation = required;
+        self.persist_saved_devices();
+        Ok(true)
+    }
+
+    /// 执行命令，令牌失效时重新登录后重试
+    ///
+    /// 需要确认的命令在确认之前不会发送给设备
+    pub async fn execute_command(
+        &mut self,
+        device_id: &str,
+        command: &str,
+        args: Option<Vec<String>>,
+    ) -> Result<CommandResult, ApiError> {
+        if self.needs_confirmation(device_id, command) {
+            return Err(ApiError::ConfirmationRequired);
+        }
+        self.send_command_with_reauth(device_id, command, args).await
+    }
+
+    /// 执行计划命令，设备未连接时先用配对密钥或保存的密码连接
+    ///
+    /// 创建计划时已经确认过，执行时不再要求确认
+    pub async fn execute_scheduled_command(
+        &mut self,
+        device_id: &str,
+        command: &str,
+        args: Option<Vec<String>>,
+    ) -> Result<CommandResult, ApiError> {
+        if !self.connected_devices.contains_key(device_id) {
+            let device = self.saved_devices.iter()
+                .find(|d| d.id == device_id)
+                .cloned()
+                .ok_or_else(|| ApiError::Other("Device not found".to_string()))?;
+            let password = self.device_passwords.get(device_id).map(|p| p.to_string());
+            let result = self.connect_to_device(device, password).await?;
+            if !result.success {
+                return Err(ApiError::Network(
+                    result.error.unwrap_or_else(|| "Device is offline".to_string()),
+                ));
+            }
+        }
+        self.send_command_with_reauth(device_id, command, args).await
+    }
+
+    async fn send_command_with_reauth(
+        &mut self,
+        device_id: &str,
+        command: &str,
+        args: Option<Vec<String>>,
+    ) -> Result<CommandResult, ApiError> {
+        // 令牌失效时设备拒绝了请求，命令没有执行，重试是安全的
+        match self.send_command(device_id, command, args.clone()).await {
+            Err(e) if e.is_auth() => {
+                if self.reauthenticate(device_id).await {
+                    return self.send_command(device_id, command, args).await;
+                }
+                Err(e)
             }
+            result => result,
         }
+    }
 
-        result
+    async fn fetch_device_status(&self, device_id: &str) -> Result<DeviceStatus, ApiError> {
+        let client = self.connected_devices.get(device_id)
+            .ok_or_else(|| ApiError::Other("Device not connected".to_string()))?;
+        client.device_status().await
     }
 
-    /// 获取设备状态
-    pub async fn get_device_status(&mut self, device_id: &str) -> Result<DeviceStatus, String> {
-        // 尝试使用现有连接获取状态
-        if let Some(client) = self.connected_devices.get(device_id) {
-            match client.get_system_info().await {
-                Ok(info) => {
-                    return Ok(DeviceStatus {
-                        online: true,
-                        cpu_usage: info.cpu_usage,
-                        memory_usage: info.memory_used,
-                        uptime: info.uptime_seconds,
-                        os_type: info.os_type,
-                        os_version: info.os_version,
-                    });
+    /// 获取设备状态，令牌失效时重新登录后重试
+    pub async fn get_device_status(&mut self, device_id: &str) -> Result<DeviceStatus, ApiError> {
+        match self.fetch_device_status(device_id).await {
+            Err(e) if e.is_auth() => {
+                if self.reauthenticate(device_id).await {
+                    return self.fetch_device_status(device_id).await;
                 }
-                Err(e) => {
-                    // 检查是否是认证错误
-                    let error_str = e.to_string();
-                    if error_str.contains("Invalid") || error_str.contains("expired") || error_str.contains("token") {
-                        log::warn!("Token expired for device {}, authentication required", device_id);
-                        // Token 失效，清除本地认证状态，要求用户重新输入密码
-                        self.device_tokens.remove(device_id);
-                        return Err("Authentication expired. Please reconnect and enter password again.".to_string());
-                    } else {
-                        return Err(e);
-                    }
+                Err(e)
+            }
+            result => result,
+        }
+    }
+
+    /// 读取设备剪贴板中的文本，令牌失效时重新登录后重试
+    pub async fn get_device_clipboard(&mut self, device_id: &str) -> Result<String, ApiError> {
+        match self.connected_client(device_id)?.get_clipboard().await {
+            Err(e) if e.is_auth() => {
+                if self.reauthenticate(device_id).await {
+                    return self.connected_client(device_id)?.get_clipboard().await;
                 }
+                Err(e)
             }
+            result => result,
+        }
+    }
+
+    /// 把文本写入设备剪贴板，令牌失效时重新登录后重试
+    pub async fn set_device_clipboard(&mut self, device_id: &str, text: &str) -> Result<(), ApiError> {
+        match self.connected_client(device_id)?.set_clipboard(text).await {
+            Err(e) if e.is_auth() => {
+                if self.reauthenticate(device_id).await {
+                    return self.connected_client(device_id)?.set_clipboard(text).await;
+                }
+                Err(e)
+            }
+            result => result,
+        }
+    }
+
+    /// 测试与已连接设备之间的网速
+    pub async fn speed_test(&self, device_id: &str, bytes: u64) -> Result<SpeedTestResult, ApiError> {
+        match self.connected_devices.get(device_id) {
+            Some(client) => client.speed_test(bytes).await,
+            None => Err(ApiError::Other("Device not connected".to_string())),
         }
-        
-        Err("Device not connected".to_string())
     }
 
     /// 获取保存的设备
@@ -401,6 +890,13 @@ impl AppState {
             log::info!("Updated existing device with UUID: {}, new ID: {}, new IP: {}, new Port: {}",
                 uuid, existing.id, existing.ip_address, existing.port);
         } else {
+            let mut device = device;
+            if device.updated_at == 0 {
+                device.updated_at = chrono::Utc::now().timestamp_millis();
+            }
+            // 重新添加的设备不再视为已删除
+            self.deleted_devices.remove(&uuid);
+            self.persist_deleted_devices();
             self.saved_devices.push(device);
             log::info!("Added new device with UUID: {}, ID: {}", uuid, id);
         }
@@ -415,7 +911,7 @@ impl AppState {
         
         // 如果有密码，保存密码
         if let Some(pwd) = password {
-            self.device_passwords.insert(device.id, pwd);
+            self.device_passwords.insert(device.id, Zeroizing::new(pwd));
         }
         
         Ok(true)
@@ -433,11 +929,19 @@ impl AppState {
             // 使用 device id 作为键删除密码和token（与 connect_to_device 中插入时使用的键一致）
             self.device_passwords.remove(id);
             self.device_tokens.remove(id);
+            self.forget_pairing(uuid);
             // 持久化保存设备列表
             self.persist_saved_devices();
+            self.deleted_devices.insert(uuid.clone(), chrono::Utc::now().timestamp_millis());
+            self.persist_deleted_devices();
+            if let Err(e) = crate::schedule::delete_for_device(id) {
+                log::warn!("Failed to delete schedules for {}: {}", device_id, e);
+            }
             log::info!("Device deleted and persisted: {}", device_id);
         }
         self.connected_devices.remove(device_id);
+        crate::shell::close_device(device_id);
+        crate::metrics::unsubscribe(device_id);
         Ok(true)
     }
 
@@ -445,15 +949,152 @@ impl AppState {
     pub async fn update_device_name(&mut self, device_id: &str, name: &str) -> Result<bool, String> {
         if let Some(device) = self.saved_devices.iter_mut().find(|d| d.id == device_id || d.uuid == device_id) {
             device.custom_name = Some(name.to_string());
+            device.updated_at = chrono::Utc::now().timestamp_millis();
+            self.persist_saved_devices();
             Ok(true)
         } else {
             Err("Device not found".to_string())
         }
     }
 
+    /// 更新设备分组和收藏状态（支持通过 ID 或 UUID 查找）
+    pub fn update_device_metadata(
+        &mut self,
+        device_id: &str,
+        group: Option<String>,
+        favorite: bool,
+    ) -> Result<bool, String> {
+        let device = self.saved_devices.iter_mut()
+            .find(|d| d.id == device_id || d.uuid == device_id)
+            .ok_or_else(|| "Device not found".to_string())?;
+        device.group = group.map(|g| g.trim().to_string()).filter(|g| !g.is_empty());
+        device.favorite = favorite;
+        device.updated_at = chrono::Utc::now().timestamp_millis();
+        self.persist_saved_devices();
+        Ok(true)
+    }
+
+    /// 更新设备的连接设置（支持通过 ID 或 UUID 查找），下次连接时生效
+    ///
+    /// 与 set_require_confirmation 相同，关闭确认时 confirmed 必须为 true
+    pub fn update_connection_profile(
+        &mut self,
+        device_id: &str,
+        profile: ConnectionProfile,
+        confirmed: bool,
+    ) -> Result<bool, String> {
+        if profile.timeout_secs == Some(0) || profile.port_override == Some(0) {
+            return Err("Timeout and port must be greater than 0".to_string());
+        }
+        if self.disables_confirmation(device_id, profile.require_confirmation) && !confirmed {
+            return Err(CONFIRMATION_OFF_REQUIRED.to_string());
+        }
+        if let Some(ref host) = profile.host {
+            crate::api::base_url(host, 1, crate::api::Scheme::Http)?;
+        }
+        let device = self.saved_devices.iter_mut()
+            .find(|d| d.id == device_id || d.uuid == device_id)
+            .ok_or_else(|| "Device not found".to_string())?;
+        device.profile = profile;
+        self.persist_saved_devices();
+        Ok(true)
+    }
+
+    /// 通过已连接的设备与其他客户端同步保存的设备列表
+    ///
+    /// 提交本地目录（包括已删除设备），再按修改时间合并服务端返回的完整目录
+    pub async fn sync_saved_devices(&mut self, device_id: &str) -> Result<SyncResult, String> {
+        let client = self.connected_devices.get(device_id)
+            .ok_or_else(|| "Device not connected".to_string())?;
+
+        let mut local: Vec<CatalogEntry> = self.saved_devices.iter()
+            .map(|d| CatalogEntry {
+                uuid: d.uuid.clone(),
+                name: d.name.clone(),
+                custom_name: d.custom_name.clone(),
+                ip_address: d.ip_address.clone(),
+                port: d.port,
+                group: d.group.clone(),
+                favorite: d.favorite,
+                deleted: false,
+                updated_at: d.updated_at,
+            })
+            .collect();
+        local.extend(self.deleted_devices.iter().map(|(uuid, deleted_at)| CatalogEntry {
+            uuid: uuid.clone(),
+            name: String::new(),
+            custom_name: None,
+            ip_address: String::new(),
+            port: 0,
+            group: None,
+            favorite: false,
+            deleted: true,
+            updated_at: *deleted_at,
+        }));
+
+        let catalog = client.sync_devices(local).await.map_err(|e| e.to_string())?;
+
+        let (mut updated, mut removed) = (0, 0);
+        for entry in catalog {
+            let local_updated_at = self.saved_devices.iter()
+                .find(|d| d.uuid == entry.uuid)
+                .map(|d| d.updated_at)
+                .or_else(|| self.deleted_devices.get(&entry.uuid).copied());
+            if local_updated_at.map_or(false, |t| t >= entry.updated_at) {
+                continue;
+            }
+
+            if entry.deleted {
+                if self.saved_devices.iter().any(|d| d.uuid == entry.uuid) {
+                    self.saved_devices.retain(|d| d.uuid != entry.uuid);
+                    removed += 1;
+                }
+                self.deleted_devices.insert(entry.uuid, entry.updated_at);
+                continue;
+            }
+
+            self.deleted_devices.remove(&entry.uuid);
+            match self.saved_devices.iter_mut().find(|d| d.uuid == entry.uuid) {
+                Some(device) => {
+                    device.name = entry.name;
+                    device.custom_name = entry.custom_name;
+                    device.group = entry.group;
+                    device.favorite = entry.favorite;
+                    device.updated_at = entry.updated_at;
+                }
+                None => self.saved_devices.push(SavedDevice {
+                    // 尚未通过 mDNS 发现，先使用 UUID 作为 ID，发现后会更新
+                    id: entry.uuid.clone(),
+                    uuid: entry.uuid,
+                    name: entry.name,
+                    ip_address: entry.ip_address,
+                    port: entry.port,
+                    custom_name: entry.custom_name,
+                    last_connected: None,
+                    created_at: chrono::Utc::now(),
+                    group: entry.group,
+                    favorite: entry.favorite,
+                    updated_at: entry.updated_at,
+                    profile: Default::default(),
+                }),
+            }
+            updated += 1;
+        }
+
+        self.persist_saved_devices();
+        self.persist_deleted_devices();
+        log::info!("Device sync via {}: {} updated, {} removed", device_id, updated, removed);
+
+        Ok(SyncResult {
+            updated,
+            removed,
+            total: self.saved_devices.len(),
+        })
+    }
+
     /// 获取设备密码
     pub fn get_device_password(&self, device_id: &str) -> Option<String> {
-        self.device_passwords.get(device_id).cloned()
+        self.device_passwords.get(device_id).map(|p| p.to_string())
     }
 
     /// 清除设备密码
@@ -466,7 +1107,7 @@ impl AppState {
 
     /// 获取设备token
     pub fn get_device_token(&self, device_id: &str) -> Option<String> {
-        self.device_tokens.get(device_id).cloned()
+        self.device_tokens.get(device_id).map(|t| t.to_string())
     }
 
     /// 使用保存的密码重新连接设备
@@ -478,7 +1119,7 @@ impl AppState {
             .ok_or_else(|| "Device not found".to_string())?;
 
         // 获取保存的密码
-        let password = self.device_passwords.get(device_id).cloned();
+        let password = self.device_passwords.get(device_id).map(|p| p.to_string());
 
         // 尝试连接
         let result = self.connect_to_device(device, password).await?;
@@ -0,0 +1,287 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::sync::Mutex;
+
+use crate::api::ApiClient;
+use crate::error::ApiError;
+use crate::state::AppState;
+
+/// 每次上传的数据量，需小于服务端的请求体上限
+const UPLOAD_CHUNK_SIZE: usize = 512 * 1024;
+/// 单块传输失败后的重试次数
+const MAX_RETRIES: u32 = 3;
+/// 下载未完成时保存数据的临时文件后缀
+const PARTIAL_SUFFIX: &str = ".part";
+
+/// 随 file-transfer 事件发送的传输进度
+#[derive(Debug, Clone, Serialize)]
+pub struct TransferProgress {
+    pub device_id: String,
+    /// download 或 upload
+    pub direction: &'static str,
+    /// 设备上的文件路径
+    pub remote_path: String,
+    pub local_path: String,
+    pub transferred: u64,
+    pub total: u64,
+    pub done: bool,
+}
+
+/// 传输过程中使用的设备客户端，令牌失效时重新登录一次后继续
+struct Transfer<'a> {
+    app: &'a AppHandle,
+    state: &'a Arc<Mutex<AppState>>,
+    client: ApiClient,
+    progress: TransferProgress,
+    reauthenticated: bool,
+}
+
+impl Transfer<'_> {
+    /// 处理单块传输的错误，返回是否应该重试
+    async fn should_retry(&mut self, error: &ApiError, attempt: u32) -> bool {
+        match error {
+            ApiError::TokenExpired | ApiError::AuthRequired if !self.reauthenticated => {
+                self.reauthenticated = true;
+                let refreshed = self
+                    .state
+                    .lock()
+                    .await
+                    .refreshed_client(&self.progress.device_id)
+                    .await;
+                match refreshed {
+                    Ok(client) => {
+                        self.client = client;
+                        true
+                    }
+                    Err(_) => false,
+                }
+            }
+            // 网络中断时稍后重试，已传输的部分不需要重新传输
+            ApiError::Network(_) | ApiError::Timeout if attempt < MAX_RETRIES => {
+                tokio::time::sleep(Duration::from_secs(1 << attempt)).await;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn report(&mut self, transferred: u64, total: u64, done: bool) {
+        self.progress.transferred = transferred;
+        self.progress.total = total;
+        self.progress.done = done;
+        let _ = self.app.emit("file-transfer", self.progress.clone());
+    }
+}
+
+fn partial_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(PARTIAL_SUFFIX);
+    PathBuf::from(name)
+}
+
+fn io_error(action: &str, e: std::io::Error) -> ApiError {
+    ApiError::Other(format!("Failed to {}: {}", action, e))
+}
+
+/// 下载文件的默认保存位置
+pub fn default_download_path(remote_path: &str) -> PathBuf {
+    // 设备可能是 Windows，路径分隔符按两种都处理
+    let name = remote_path
+        .rsplit(['/', '\\'])
+        .find(|s| !s.is_empty())
+        .unwrap_or("download");
+    dirs::download_dir()
+        .unwrap_or_else(|| crate::state::app_data_dir().join("downloads"))
+        .join(name)
+}
+
+/// 分块下载设备上的文件，本地保留未完成的部分以便中断后续传
+pub async fn download(
+    app: &AppHandle,
+    state: &Arc<Mutex<AppState>>,
+    device_id: &str,
+    remote_path: &str,
+    local_path: &Path,
+) -> Result<u64, ApiError> {
+    let client = state.lock().await.connected_client(device_id)?;
+    if let Some(dir) = local_path.parent() {
+        tokio::fs::create_dir_all(dir)
+            .await
+            .map_err(|e| io_error("create download directory", e))?;
+    }
+
+    let partial = partial_path(local_path);
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&partial)
+        .await
+        .map_err(|e| io_error("open download file", e))?;
+    let mut offset = file
+        .metadata()
+        .await
+        .map_err(|e| io_error("read download file", e))?
+        .len();
+    if offset > 0 {
+        log::info!("Resuming download of {} at {} bytes", remote_path, offset);
+    }
+
+    let mut transfer = Transfer {
+        app,
+        state,
+        client,
+        progress: TransferProgress {
+            device_id: device_id.to_string(),
+            direction: "download",
+            remote_path: remote_path.to_string(),
+            local_path: local_path.to_string_lossy().to_string(),
+            transferred: offset,
+            total: 0,
+            done: false,
+        },
+        reauthenticated: false,
+    };
+
+    let mut attempt = 0;
+    let total = loop {
+        let (data, total) = match transfer.client.download_chunk(remote_path, offset).await {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                attempt += 1;
+                if transfer.should_retry(&e, attempt).await {
+                    continue;
+                }
+                return Err(e);
+            }
+        };
+        attempt = 0;
+        file.write_all(&data)
+            .await
+            .map_err(|e| io_error("write download file", e))?;
+        offset += data.len() as u64;
+        if offset >= total || data.is_empty() {
+            break total;
+        }
+        transfer.report(offset, total, false);
+    };
+
+    file.flush()
+        .await
+        .map_err(|e| io_error("write download file", e))?;
+    drop(file);
+    tokio::fs::rename(&partial, local_path)
+        .await
+        .map_err(|e| io_error("save downloaded file", e))?;
+    transfer.report(total, total, true);
+    log::info!("Downloaded {} ({} bytes)", remote_path, total);
+    Ok(total)
+}
+
+/// 分块上传本地文件到设备，从设备已收到的位置继续；overwrite 为 false 时不替换已有文件
+pub async fn upload(
+    app: &AppHandle,
+    state: &Arc<Mutex<AppState>>,
+    device_id: &str,
+    local_path: &Path,
+    remote_path: &str,
+    overwrite: bool,
+) -> Result<u64, ApiError> {
+    let client = state.lock().await.connected_client(device_id)?;
+    let mut file = tokio::fs::File::open(local_path)
+        .await
+        .map_err(|e| io_error("open file", e))?;
+    let total = file
+        .metadata()
+        .await
+        .map_err(|e| io_error("read file", e))?
+        .len();
+
+    let mut transfer = Transfer {
+        app,
+        state,
+        client,
+        progress: TransferProgress {
+            device_id: device_id.to_string(),
+            direction: "upload",
+            remote_path: remote_path.to_string(),
+            local_path: local_path.to_string_lossy().to_string(),
+            transferred: 0,
+            total,
+            done: false,
+        },
+        reauthenticated: false,
+    };
+
+    let uploaded = match transfer.client.upload_status(remote_path).await {
+        Err(e) if e.is_auth() => {
+            if !transfer.should_retry(&e, 0).await {
+                return Err(e);
+            }
+            transfer.client.upload_status(remote_path).await?
+        }
+        result => result?,
+    };
+    // 设备上的部分数据比本地文件还大，说明文件已变化，从头上传
+    let mut offset = if uploaded <= total { uploaded } else { 0 };
+    if offset > 0 {
+        log::info!("Resuming upload of {} at {} bytes", remote_path, offset);
+    }
+
+    let mut buffer = vec![0u8; UPLOAD_CHUNK_SIZE];
+    let mut attempt = 0;
+    loop {
+        file.seek(std::io::SeekFrom::Start(offset))
+            .await
+            .map_err(|e| io_error("read file", e))?;
+        let len = read_full(&mut file, &mut buffer)
+            .await
+            .map_err(|e| io_error("read file", e))?;
+        let complete = offset + len as u64 >= total;
+
+        match transfer
+            .client
+            .upload_chunk(remote_path, offset, buffer[..len].to_vec(), complete, overwrite)
+            .await
+        {
+            Ok(uploaded) => {
+                attempt = 0;
+                offset = uploaded;
+                if complete {
+                    break;
+                }
+                transfer.report(offset, total, false);
+            }
+            Err(e) => {
+                attempt += 1;
+                if !transfer.should_retry(&e, attempt).await {
+                    return Err(e);
+                }
+                // 失败的请求可能已经写入，以设备记录的位置为准
+                if let Ok(uploaded) = transfer.client.upload_status(remote_path).await {
+                    offset = uploaded.min(total);
+                }
+            }
+        }
+    }
+
+    transfer.report(total, total, true);
+    log::info!("Uploaded {} ({} bytes)", remote_path, total);
+    Ok(total)
+}
+
+/// 读满缓冲区或读到文件末尾
+async fn read_full(file: &mut tokio::fs::File, buffer: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        let n = file.read(&mut buffer[filled..]).await?;
+        if n == 0 {
+            break;
+        }
+        filled += n;
+    }
+    Ok(filled)
+}
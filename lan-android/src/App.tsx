@@ -1,12 +1,30 @@
 import { useState, useEffect } from 'react';
 import { HashRouter, Routes, Route } from 'react-router-dom';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { useTranslation } from 'react-i18next';
 import HostsList from './pages/HostsList';
 import DiscoverHosts from './pages/DiscoverHosts';
 import HostDetail from './pages/HostDetail';
-import { Host } from './types';
-import { getErrorMessage } from './utils/errorParser';
+import RemoteFiles from './pages/RemoteFiles';
+import RemoteShell from './pages/RemoteShell';
+import { DeviceStatusUpdate, Host } from './types';
+import { getErrorMessage, isAuthError } from './utils/errorParser';
+import { syncStatusRefreshInterval } from './utils/statusRefresh';
+
+interface DiscoveredDeviceUpdate {
+  uuid: string;
+  name: string;
+  ip_address: string;
+  port: number;
+  requires_auth: boolean;
+  os_version?: string;
+}
+
+interface NetworkChange {
+  connected: boolean;
+  wifi: boolean;
+}
 
 interface SavedDevice {
   id: string;
@@ -25,6 +43,64 @@ function App() {
 
   useEffect(() => {
     loadSavedDevices();
+    syncStatusRefreshInterval();
+  }, []);
+
+  // 后台定期刷新已连接设备的状态；令牌失效不代表设备离线
+  useEffect(() => {
+    const unlisten = listen<DeviceStatusUpdate>('device-status', (event) => {
+      const { device_id, status, error } = event.payload;
+      if (status) {
+        updateHostStatus(device_id, status.online ? 'Online' : 'Offline');
+      } else if (!isAuthError(error)) {
+        updateHostStatus(device_id, 'Offline');
+      }
+    });
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
+  // 桌面端设置或清除密码后，TXT 记录变化会推送到这里
+  useEffect(() => {
+    const unlisten = listen<DiscoveredDeviceUpdate>('discovered-device-updated', (event) => {
+      const device = event.payload;
+      setHosts(prev => prev.map(h => {
+        if (h.uuid !== device.uuid) {
+          return h;
+        }
+        const updated: Host = {
+          ...h,
+          name: h.customName ? h.name : device.name,
+          ip: device.ip_address,
+          port: device.port,
+          os: device.os_version || h.os,
+          requiresAuth: device.requires_auth,
+        };
+        checkHostStatus(updated);
+        return updated;
+      }));
+    });
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
+  // 切换 Wi-Fi 后设备的可达性可能变化，重新检查所有已保存设备
+  useEffect(() => {
+    const unlisten = listen<NetworkChange>('network-changed', (event) => {
+      if (!event.payload.connected) {
+        setHosts(prev => prev.map(h => ({ ...h, status: 'Offline' })));
+        return;
+      }
+      setHosts(prev => {
+        prev.forEach(host => checkHostStatus(host));
+        return prev;
+      });
+    });
+    return () => {
+      unlisten.then(fn => fn());
+    };
   }, []);
 
   const loadSavedDevices = async () => {
@@ -204,6 +280,14 @@ function App() {
           path="/host/:id"
           element={<HostDetail hosts={hosts} onStatusChange={updateHostStatus} />}
         />
+        <Route
+          path="/host/:id/files"
+          element={<RemoteFiles hosts={hosts} />}
+        />
+        <Route
+          path="/host/:id/shell"
+          element={<RemoteShell hosts={hosts} />}
+        />
       </Routes>
     </HashRouter>
   );
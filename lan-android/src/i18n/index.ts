@@ -1,4 +1,5 @@
 import i18n from 'i18next';
+import { invoke } from '@tauri-apps/api/core';
 import { initReactI18next } from 'react-i18next';
 import LanguageDetector from 'i18next-browser-languagedetector';
 import en from './locales/en.json';
@@ -28,4 +29,11 @@ i18n
     },
   });
 
+// 让服务端按当前界面语言返回错误消息
+const syncClientLanguage = (language: string) => {
+  invoke('set_client_language', { language }).catch(() => {});
+};
+syncClientLanguage(i18n.language);
+i18n.on('languageChanged', syncClientLanguage);
+
 export default i18n;
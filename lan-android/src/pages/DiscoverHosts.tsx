@@ -27,6 +27,8 @@ interface DiscoveredDevice {
   port: number;
   version: string;
   requires_auth: boolean;
+  os?: string | null;
+  os_version?: string | null;
 }
 
 const DiscoverHosts = ({ onAdd, existingHosts }: DiscoverHostsProps) => {
@@ -224,7 +226,7 @@ const DiscoverHosts = ({ onAdd, existingHosts }: DiscoverHostsProps) => {
       icon: '',
       iconColor: '',
       uptime: '0m',
-      os: 'Auto-detected Device',
+      os: device.os_version || device.os || 'Auto-detected Device',
       version: device.version,
       requiresAuth: device.requires_auth,
     };
@@ -596,7 +598,7 @@ const DiscoverHosts = ({ onAdd, existingHosts }: DiscoverHostsProps) => {
                       color: '#8b9aa8',
                       fontFamily: 'monospace',
                       margin: 0
-                    }}>{device.ip_address}</p>
+                    }}>{[device.os_version || device.os, device.ip_address].filter(Boolean).join(' · ')}</p>
                     {device.requires_auth && (
                       <div style={{
                         display: 'flex',
@@ -1,8 +1,18 @@
 import { useState, useEffect } from 'react';
 import { useParams, useNavigate } from 'react-router-dom';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { useTranslation } from 'react-i18next';
-import { Host, DeviceStatus, CommandResult } from '../types';
+import {
+  Host,
+  DeviceStatus,
+  DeviceStatusUpdate,
+  CommandResult,
+  MetricsSample,
+  MetricsStopped,
+  ScheduledCommand,
+  ScheduleRun
+} from '../types';
 import {
   IconArrowBack,
   IconCheck,
@@ -18,6 +28,28 @@ import {
   getDeviceIconBgColor
 } from '../components/Icons';
 import { parseError } from '../utils/errorParser';
+import {
+  STATUS_REFRESH_INTERVALS,
+  getStatusRefreshInterval,
+  setStatusRefreshInterval
+} from '../utils/statusRefresh';
+
+// 与服务端时钟偏差超过 30 秒时提示
+const CLOCK_SKEW_WARN_MS = 30_000;
+
+type ScheduleAction = 'shutdown' | 'restart' | 'sleep' | 'lock';
+const SCHEDULE_ACTIONS: ScheduleAction[] = ['shutdown', 'restart', 'sleep', 'lock'];
+// 重复间隔（秒），0 表示只执行一次
+const SCHEDULE_REPEATS = [0, 86_400, 604_800];
+
+const selectStyle = {
+  padding: '8px 12px',
+  borderRadius: '8px',
+  backgroundColor: '#1a2332',
+  color: '#ffffff',
+  border: 'none',
+  fontSize: '14px'
+};
 
 interface HostDetailProps {
   hosts: Host[];
@@ -38,6 +70,12 @@ const HostDetail = ({ hosts, onStatusChange }: HostDetailProps) => {
   const [commandInput, setCommandInput] = useState('');
   const [commandOutput, setCommandOutput] = useState<string | null>(null);
   const [isExecuting, setIsExecuting] = useState(false);
+  const [refreshInterval, setRefreshInterval] = useState(getStatusRefreshInterval());
+  const [requireConfirmation, setRequireConfirmation] = useState(false);
+  const [schedules, setSchedules] = useState<ScheduledCommand[]>([]);
+  const [scheduleAction, setScheduleAction] = useState<ScheduleAction>('shutdown');
+  const [scheduleTime, setScheduleTime] = useState('');
+  const [scheduleRepeat, setScheduleRepeat] = useState(0);
 
   useEffect(() => {
     if (host && host.status === 'Online') {
@@ -45,6 +83,160 @@ const HostDetail = ({ hosts, onStatusChange }: HostDetailProps) => {
     }
   }, [host?.id]);
 
+  // 后台刷新任务推送的状态，设备在线状态由 App 统一更新
+  useEffect(() => {
+    if (!host) return;
+    const unlisten = listen<DeviceStatusUpdate>('device-status', (event) => {
+      if (event.payload.device_id === host.id && event.payload.status) {
+        setDeviceStatus(event.payload.status);
+      }
+    });
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, [host?.id]);
+
+  // 页面打开期间订阅实时指标，代替后台轮询；离开页面时取消订阅
+  useEffect(() => {
+    if (!host || host.status !== 'Online') return;
+    const unlistenMetrics = listen<MetricsSample>('device-metrics', (event) => {
+      const sample = event.payload;
+      if (sample.device_id !== host.id) return;
+      setDeviceStatus(prev => prev && {
+        ...prev,
+        cpu_usage: sample.cpu_usage,
+        memory_usage: sample.memory_used,
+        uptime: sample.uptime_seconds
+      });
+    });
+    // 推送中断后后台刷新会自动恢复，这里立即刷新一次在线状态
+    const unlistenStopped = listen<MetricsStopped>('device-metrics-stopped', (event) => {
+      if (event.payload.device_id === host.id) fetchDeviceStatus();
+    });
+    invoke('subscribe_device_metrics', { deviceId: host.id, intervalMs: null })
+      .catch(error => console.warn('Live metrics unavailable:', error));
+    return () => {
+      invoke('unsubscribe_device_metrics', { deviceId: host.id }).catch(() => {});
+      unlistenMetrics.then(fn => fn());
+      unlistenStopped.then(fn => fn());
+    };
+  }, [host?.id, host?.status]);
+
+  // 危险命令确认是本机对该设备的设置，保存在连接设置中
+  useEffect(() => {
+    if (!host) return;
+    invoke<Array<{ id: string; profile?: { require_confirmation?: boolean } }>>('get_saved_devices')
+      .then(devices => {
+        const saved = devices.find(d => d.id === host.id);
+        setRequireConfirmation(saved?.profile?.require_confirmation ?? false);
+      })
+      .catch(() => {});
+  }, [host?.id]);
+
+  // 计划命令保存在本机，到期后由后台任务发送给设备
+  const loadSchedules = async () => {
+    if (!host) return;
+    try {
+      setSchedules(await invoke<ScheduledCommand[]>('list_schedules', { deviceId: host.id }));
+    } catch (error) {
+      console.error('Failed to load schedules:', error);
+    }
+  };
+
+  useEffect(() => {
+    if (!host) return;
+    loadSchedules();
+    const unlisten = listen<ScheduleRun>('schedule-run', (event) => {
+      const run = event.payload;
+      if (run.device_id !== host.id) return;
+      loadSchedules();
+      const action = t(`hostDetail.${run.command}`, { defaultValue: run.command });
+      showToast(
+        run.success
+          ? t('hostDetail.scheduleRan', { action })
+          : t('hostDetail.scheduleFailed', { action, error: run.message }),
+        run.success ? 'success' : 'error'
+      );
+    });
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, [host?.id]);
+
+  const handleAddSchedule = async () => {
+    if (!host || !scheduleTime) return;
+    const runAt = new Date(scheduleTime).getTime();
+    if (Number.isNaN(runAt)) return;
+    try {
+      await invoke('add_schedule', {
+        deviceId: host.id,
+        command: scheduleAction,
+        args: null,
+        runAt,
+        repeatSecs: scheduleRepeat || null,
+      });
+      setScheduleTime('');
+      await loadSchedules();
+    } catch (error) {
+      showToast(parseError(error).message, 'error');
+    }
+  };
+
+  const handleDeleteSchedule = async (scheduleId: string) => {
+    try {
+      await invoke('delete_schedule', { id: scheduleId });
+      setSchedules(prev => prev.filter(s => s.id !== scheduleId));
+    } catch (error) {
+      showToast(parseError(error).message, 'error');
+    }
+  };
+
+  const scheduleRepeatLabel = (secs?: number) => {
+    if (!secs) return t('hostDetail.scheduleOnce');
+    if (secs === 86_400) return t('hostDetail.scheduleDaily');
+    if (secs === 604_800) return t('hostDetail.scheduleWeekly');
+    return t('hostDetail.autoRefreshEvery', { seconds: secs });
+  };
+
+  const handlePushClipboard = async () => {
+    if (!host) return;
+    try {
+      await invoke('push_clipboard', { deviceId: host.id });
+      showToast(t('hostDetail.clipboardPushed', { name: host.name }));
+    } catch (error) {
+      showToast(parseError(error).message, 'error');
+    }
+  };
+
+  const handlePullClipboard = async () => {
+    if (!host) return;
+    try {
+      await invoke<string>('pull_clipboard', { deviceId: host.id });
+      showToast(t('hostDetail.clipboardPulled', { name: host.name }));
+    } catch (error) {
+      showToast(parseError(error).message, 'error');
+    }
+  };
+
+  const handleRequireConfirmationChange = async (required: boolean) => {
+    if (!host) return;
+    try {
+      await invoke('set_require_confirmation', { deviceId: host.id, required });
+      setRequireConfirmation(required);
+    } catch (error) {
+      showToast(parseError(error).message, 'error');
+    }
+  };
+
+  const handleRefreshIntervalChange = async (secs: number) => {
+    try {
+      await setStatusRefreshInterval(secs);
+      setRefreshInterval(secs);
+    } catch (error) {
+      showToast(parseError(error).message, 'error');
+    }
+  };
+
   const showToast = (message: string, type: 'success' | 'error' = 'success') => {
     setToast({ message, type });
     setTimeout(() => setToast(null), 3000);
@@ -122,7 +314,7 @@ const HostDetail = ({ hosts, onStatusChange }: HostDetailProps) => {
         showToast(t('toast.commandExecuted'));
       } else {
         // 解析后端返回的错误信息
-        const parsedError = parseError(result.stderr || '');
+        const parsedError = parseError(result.error_message || result.stderr || '');
         setCommandOutput(`${t('toast.error')}: ${parsedError.message}`);
         showToast(parsedError.type === 'unknown' ? t('toast.commandFailed') : parsedError.message, 'error');
       }
@@ -153,6 +345,11 @@ const HostDetail = ({ hosts, onStatusChange }: HostDetailProps) => {
     return `${minutes}m`;
   };
 
+  const formatMemory = (bytes: number): string => {
+    if (bytes >= 1024 * 1024 * 1024) return `${(bytes / 1024 / 1024 / 1024).toFixed(1)} GB`;
+    return `${Math.round(bytes / 1024 / 1024)} MB`;
+  };
+
   if (!host) {
     return (
       <div style={{
@@ -330,6 +527,8 @@ const HostDetail = ({ hosts, onStatusChange }: HostDetailProps) => {
         }}>
           {[
             { label: t('hostDetail.status'), value: host.status === 'Online' ? t('hostDetail.running') : t('hostDetail.stopped'), color: host.status === 'Online' ? '#10b981' : '#ef4444' },
+            { label: t('hostDetail.cpu'), value: deviceStatus ? `${deviceStatus.cpu_usage.toFixed(1)}%` : '--', color: '#ffffff' },
+            { label: t('hostDetail.memory'), value: deviceStatus ? formatMemory(deviceStatus.memory_usage) : '--', color: '#ffffff' },
             { label: t('hostDetail.uptime'), value: deviceStatus?.uptime ? formatUptime(deviceStatus.uptime) : '--', color: '#ffffff' },
             { label: t('hostDetail.os'), value: deviceStatus?.os_version || host.os || t('app.unknown'), color: '#ffffff' },
           ].map((item, index, arr) => (
@@ -347,6 +546,43 @@ const HostDetail = ({ hosts, onStatusChange }: HostDetailProps) => {
         </div>
       </div>
 
+      {/* Clock Skew Warning */}
+      {deviceStatus?.clock_skew_ms != null && Math.abs(deviceStatus.clock_skew_ms) > CLOCK_SKEW_WARN_MS && (
+        <div style={{ padding: '0 20px 24px' }}>
+          <div style={{
+            padding: '12px 16px',
+            borderRadius: '12px',
+            backgroundColor: 'rgba(245, 158, 11, 0.15)',
+            color: '#f59e0b',
+            display: 'flex',
+            alignItems: 'center',
+            gap: '8px',
+            fontSize: '14px'
+          }}>
+            <IconError style={{ width: '20px', height: '20px', flexShrink: 0 }} />
+            <span>{t('hostDetail.clockSkewWarning', { seconds: Math.round(Math.abs(deviceStatus.clock_skew_ms) / 1000) })}</span>
+          </div>
+        </div>
+      )}
+
+      {/* Update Available */}
+      {deviceStatus?.available_update && (
+        <div style={{ padding: '0 20px 24px' }}>
+          <div style={{
+            padding: '12px 16px',
+            borderRadius: '12px',
+            backgroundColor: 'rgba(19, 164, 236, 0.15)',
+            color: '#13a4ec',
+            display: 'flex',
+            alignItems: 'center',
+            gap: '8px',
+            fontSize: '14px'
+          }}>
+            <span>{t('hostDetail.updateAvailable', { version: deviceStatus.available_update })}</span>
+          </div>
+        </div>
+      )}
+
       {/* Power Controls */}
       <div style={{ padding: '0 20px 24px' }}>
         <h2 style={{
@@ -506,6 +742,212 @@ const HostDetail = ({ hosts, onStatusChange }: HostDetailProps) => {
         >
           {t('hostDetail.refreshStatus')}
         </button>
+        <button
+          onClick={() => navigate(`/host/${host.id}/files`)}
+          disabled={host.status === 'Offline'}
+          style={{
+            width: '100%',
+            padding: '14px',
+            marginTop: '12px',
+            borderRadius: '12px',
+            backgroundColor: '#1a2332',
+            color: '#ffffff',
+            border: 'none',
+            fontSize: '16px',
+            fontWeight: 500,
+            cursor: host.status === 'Offline' ? 'not-allowed' : 'pointer',
+            opacity: host.status === 'Offline' ? 0.5 : 1
+          }}
+        >
+          {t('hostDetail.browseFiles')}
+        </button>
+        <button
+          onClick={() => navigate(`/host/${host.id}/shell`)}
+          disabled={host.status === 'Offline'}
+          style={{
+            width: '100%',
+            padding: '14px',
+            marginTop: '12px',
+            borderRadius: '12px',
+            backgroundColor: '#1a2332',
+            color: '#ffffff',
+            border: 'none',
+            fontSize: '16px',
+            fontWeight: 500,
+            cursor: host.status === 'Offline' ? 'not-allowed' : 'pointer',
+            opacity: host.status === 'Offline' ? 0.5 : 1
+          }}
+        >
+          {t('hostDetail.openShell')}
+        </button>
+        <div style={{ display: 'flex', gap: '12px', marginTop: '12px' }}>
+          <button
+            onClick={handlePushClipboard}
+            disabled={host.status === 'Offline'}
+            style={{
+              flex: 1,
+              padding: '14px',
+              borderRadius: '12px',
+              backgroundColor: '#1a2332',
+              color: '#ffffff',
+              border: 'none',
+              fontSize: '16px',
+              fontWeight: 500,
+              cursor: host.status === 'Offline' ? 'not-allowed' : 'pointer',
+              opacity: host.status === 'Offline' ? 0.5 : 1
+            }}
+          >
+            {t('hostDetail.pushClipboard')}
+          </button>
+          <button
+            onClick={handlePullClipboard}
+            disabled={host.status === 'Offline'}
+            style={{
+              flex: 1,
+              padding: '14px',
+              borderRadius: '12px',
+              backgroundColor: '#1a2332',
+              color: '#ffffff',
+              border: 'none',
+              fontSize: '16px',
+              fontWeight: 500,
+              cursor: host.status === 'Offline' ? 'not-allowed' : 'pointer',
+              opacity: host.status === 'Offline' ? 0.5 : 1
+            }}
+          >
+            {t('hostDetail.pullClipboard')}
+          </button>
+        </div>
+        <div style={{
+          display: 'flex',
+          alignItems: 'center',
+          justifyContent: 'space-between',
+          marginTop: '12px',
+          color: '#8b9aa8',
+          fontSize: '14px'
+        }}>
+          <span>{t('hostDetail.autoRefresh')}</span>
+          <select
+            value={refreshInterval}
+            onChange={(e) => handleRefreshIntervalChange(Number(e.target.value))}
+            style={selectStyle}
+          >
+            {STATUS_REFRESH_INTERVALS.map(secs => (
+              <option key={secs} value={secs}>
+                {secs === 0 ? t('hostDetail.autoRefreshOff') : t('hostDetail.autoRefreshEvery', { seconds: secs })}
+              </option>
+            ))}
+          </select>
+        </div>
+        <label style={{
+          display: 'flex',
+          alignItems: 'center',
+          justifyContent: 'space-between',
+          gap: '12px',
+          marginTop: '12px',
+          color: '#8b9aa8',
+          fontSize: '14px'
+        }}>
+          <span>{t('hostDetail.requireConfirmation')}</span>
+          <input
+            type="checkbox"
+            checked={requireConfirmation}
+            onChange={(e) => handleRequireConfirmationChange(e.target.checked)}
+          />
+        </label>
+
+        <div style={{ marginTop: '24px' }}>
+          <h3 style={{ color: '#ffffff', fontSize: '16px', fontWeight: 600, marginBottom: '12px' }}>
+            {t('hostDetail.scheduledCommands')}
+          </h3>
+          <div style={{ display: 'flex', flexWrap: 'wrap', gap: '8px', marginBottom: '12px' }}>
+            <select
+              value={scheduleAction}
+              onChange={(e) => setScheduleAction(e.target.value as ScheduleAction)}
+              style={selectStyle}
+            >
+              {SCHEDULE_ACTIONS.map(action => (
+                <option key={action} value={action}>{t(`hostDetail.${action}`)}</option>
+              ))}
+            </select>
+            <input
+              type="datetime-local"
+              value={scheduleTime}
+              onChange={(e) => setScheduleTime(e.target.value)}
+              style={{ ...selectStyle, flex: 1, minWidth: '180px' }}
+            />
+            <select
+              value={scheduleRepeat}
+              onChange={(e) => setScheduleRepeat(Number(e.target.value))}
+              style={selectStyle}
+            >
+              {SCHEDULE_REPEATS.map(secs => (
+                <option key={secs} value={secs}>{scheduleRepeatLabel(secs)}</option>
+              ))}
+            </select>
+            <button
+              onClick={handleAddSchedule}
+              disabled={!scheduleTime}
+              style={{
+                padding: '8px 16px',
+                borderRadius: '8px',
+                backgroundColor: '#13a4ec',
+                color: '#ffffff',
+                border: 'none',
+                fontSize: '14px',
+                cursor: scheduleTime ? 'pointer' : 'not-allowed',
+                opacity: scheduleTime ? 1 : 0.5
+              }}
+            >
+              {t('hostDetail.addSchedule')}
+            </button>
+          </div>
+          {schedules.length === 0 ? (
+            <p style={{ color: '#8b9aa8', fontSize: '14px' }}>{t('hostDetail.noSchedules')}</p>
+          ) : (
+            schedules.map(schedule => (
+              <div
+                key={schedule.id}
+                style={{
+                  display: 'flex',
+                  alignItems: 'center',
+                  justifyContent: 'space-between',
+                  padding: '10px 12px',
+                  marginBottom: '8px',
+                  borderRadius: '8px',
+                  backgroundColor: '#1a2332',
+                  opacity: schedule.enabled ? 1 : 0.5
+                }}
+              >
+                <div style={{ fontSize: '14px' }}>
+                  <div style={{ color: '#ffffff' }}>
+                    {t(`hostDetail.${schedule.command}`, { defaultValue: schedule.command })}
+                    {' · '}
+                    {scheduleRepeatLabel(schedule.repeat_secs)}
+                  </div>
+                  <div style={{ color: '#8b9aa8', fontSize: '12px' }}>
+                    {schedule.enabled
+                      ? new Date(schedule.next_run).toLocaleString()
+                      : t('hostDetail.scheduleDone')}
+                  </div>
+                </div>
+                <button
+                  onClick={() => handleDeleteSchedule(schedule.id)}
+                  style={{
+                    background: 'none',
+                    border: 'none',
+                    color: '#ef4444',
+                    cursor: 'pointer',
+                    display: 'flex'
+                  }}
+                  aria-label={t('hostDetail.deleteSchedule')}
+                >
+                  <IconClose />
+                </button>
+              </div>
+            ))
+          )}
+        </div>
       </div>
     </div>
   );
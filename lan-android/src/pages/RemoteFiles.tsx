@@ -0,0 +1,238 @@
+import { useState, useEffect } from 'react';
+import { useParams, useNavigate } from 'react-router-dom';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { useTranslation } from 'react-i18next';
+import { Host, RemoteFile, TransferProgress } from '../types';
+import { IconArrowBack } from '../components/Icons';
+import { parseError } from '../utils/errorParser';
+
+interface RemoteFilesProps {
+  hosts: Host[];
+}
+
+const formatSize = (bytes: number) => {
+  if (bytes < 1024) return `${bytes} B`;
+  if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(1)} KB`;
+  if (bytes < 1024 * 1024 * 1024) return `${(bytes / 1024 / 1024).toFixed(1)} MB`;
+  return `${(bytes / 1024 / 1024 / 1024).toFixed(1)} GB`;
+};
+
+// 设备可能是 Windows，上级目录按两种分隔符计算
+const parentPath = (path: string, roots: string[]) => {
+  if (roots.includes(path)) return null;
+  const index = Math.max(path.lastIndexOf('/'), path.lastIndexOf('\\'));
+  return index > 0 ? path.slice(0, index) : null;
+};
+
+const RemoteFiles = ({ hosts }: RemoteFilesProps) => {
+  const { t } = useTranslation();
+  const { id } = useParams<{ id: string }>();
+  const navigate = useNavigate();
+  const host = hosts.find(h => h.id === id);
+
+  const [path, setPath] = useState<string | null>(null);
+  const [roots, setRoots] = useState<string[]>([]);
+  const [files, setFiles] = useState<RemoteFile[]>([]);
+  const [isLoading, setIsLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+  // 按设备上的文件路径记录下载进度
+  const [transfers, setTransfers] = useState<Record<string, TransferProgress>>({});
+  const [message, setMessage] = useState<string | null>(null);
+
+  useEffect(() => {
+    if (host) loadFiles(path);
+  }, [host?.id, path]);
+
+  useEffect(() => {
+    if (!host) return;
+    const unlisten = listen<TransferProgress>('file-transfer', (event) => {
+      const progress = event.payload;
+      if (progress.device_id !== host.id) return;
+      setTransfers(prev => {
+        const next = { ...prev };
+        if (progress.done) {
+          delete next[progress.remote_path];
+        } else {
+          next[progress.remote_path] = progress;
+        }
+        return next;
+      });
+    });
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, [host?.id]);
+
+  const loadFiles = async (dir: string | null) => {
+    if (!host) return;
+    setIsLoading(true);
+    setError(null);
+    try {
+      const entries = await invoke<RemoteFile[]>('list_remote_files', { deviceId: host.id, path: dir });
+      setFiles(entries);
+      if (dir === null) {
+        setRoots(entries.map(e => e.path));
+      }
+    } catch (err) {
+      setError(parseError(err).message);
+    } finally {
+      setIsLoading(false);
+    }
+  };
+
+  const handleDownload = async (file: RemoteFile) => {
+    if (!host) return;
+    setMessage(null);
+    try {
+      const saved = await invoke<string>('download_remote_file', {
+        deviceId: host.id,
+        remotePath: file.path,
+        localPath: null,
+      });
+      setMessage(t('remoteFiles.saved', { path: saved }));
+    } catch (err) {
+      setMessage(parseError(err).message);
+    } finally {
+      setTransfers(prev => {
+        const next = { ...prev };
+        delete next[file.path];
+        return next;
+      });
+    }
+  };
+
+  const goUp = () => setPath(path === null ? null : parentPath(path, roots));
+
+  if (!host) {
+    return (
+      <div style={{ minHeight: '100vh', backgroundColor: '#0f1419', padding: '48px 20px' }}>
+        <p style={{ color: '#8b9aa8' }}>{t('hostDetail.deviceNotFound')}</p>
+      </div>
+    );
+  }
+
+  return (
+    <div style={{ minHeight: '100vh', backgroundColor: '#0f1419', paddingBottom: '40px' }}>
+      {/* Header */}
+      <div style={{
+        padding: '16px 20px',
+        paddingTop: '48px',
+        display: 'flex',
+        alignItems: 'center',
+        gap: '12px'
+      }}>
+        <button
+          onClick={() => (path === null ? navigate(`/host/${host.id}`) : goUp())}
+          style={{
+            width: '40px',
+            height: '40px',
+            borderRadius: '50%',
+            backgroundColor: '#1a2332',
+            border: 'none',
+            display: 'flex',
+            alignItems: 'center',
+            justifyContent: 'center',
+            cursor: 'pointer',
+            flexShrink: 0
+          }}
+        >
+          <IconArrowBack style={{ width: '20px', height: '20px', color: '#ffffff' }} />
+        </button>
+        <div style={{ minWidth: 0 }}>
+          <h1 style={{ color: '#ffffff', fontSize: '20px', fontWeight: 600, margin: 0 }}>
+            {t('remoteFiles.title')}
+          </h1>
+          <p style={{
+            color: '#8b9aa8',
+            fontSize: '12px',
+            margin: 0,
+            overflow: 'hidden',
+            textOverflow: 'ellipsis',
+            whiteSpace: 'nowrap'
+          }}>
+            {path ?? host.name}
+          </p>
+        </div>
+      </div>
+
+      {message && (
+        <p style={{ color: '#8b9aa8', fontSize: '13px', padding: '0 20px', wordBreak: 'break-all' }}>{message}</p>
+      )}
+
+      <div style={{ padding: '0 20px' }}>
+        {isLoading && <p style={{ color: '#8b9aa8', fontSize: '14px' }}>{t('app.loading')}</p>}
+        {error && <p style={{ color: '#ef4444', fontSize: '14px' }}>{error}</p>}
+        {!isLoading && !error && files.length === 0 && (
+          <p style={{ color: '#8b9aa8', fontSize: '14px' }}>
+            {path === null ? t('remoteFiles.noRoots') : t('remoteFiles.empty')}
+          </p>
+        )}
+        {!isLoading && !error && files.map(file => {
+          const progress = transfers[file.path];
+          return (
+            <div
+              key={file.path}
+              onClick={() => file.is_dir && setPath(file.path)}
+              style={{
+                padding: '12px 14px',
+                marginBottom: '8px',
+                borderRadius: '12px',
+                backgroundColor: '#1a2332',
+                cursor: file.is_dir ? 'pointer' : 'default'
+              }}
+            >
+              <div style={{ display: 'flex', alignItems: 'center', justifyContent: 'space-between', gap: '12px' }}>
+                <div style={{ minWidth: 0 }}>
+                  <div style={{
+                    color: '#ffffff',
+                    fontSize: '14px',
+                    overflow: 'hidden',
+                    textOverflow: 'ellipsis',
+                    whiteSpace: 'nowrap'
+                  }}>
+                    {file.is_dir ? `${file.name}/` : file.name}
+                  </div>
+                  {!file.is_dir && (
+                    <div style={{ color: '#8b9aa8', fontSize: '12px' }}>{formatSize(file.size)}</div>
+                  )}
+                </div>
+                {!file.is_dir && (
+                  <button
+                    onClick={() => handleDownload(file)}
+                    disabled={!!progress}
+                    style={{
+                      padding: '6px 12px',
+                      borderRadius: '8px',
+                      backgroundColor: '#13a4ec',
+                      color: '#ffffff',
+                      border: 'none',
+                      fontSize: '13px',
+                      cursor: progress ? 'not-allowed' : 'pointer',
+                      opacity: progress ? 0.5 : 1,
+                      flexShrink: 0
+                    }}
+                  >
+                    {t('remoteFiles.download')}
+                  </button>
+                )}
+              </div>
+              {progress && progress.total > 0 && (
+                <div style={{ marginTop: '8px', height: '4px', borderRadius: '2px', backgroundColor: '#0f1419' }}>
+                  <div style={{
+                    width: `${Math.min(100, (progress.transferred / progress.total) * 100)}%`,
+                    height: '100%',
+                    borderRadius: '2px',
+                    backgroundColor: '#13a4ec'
+                  }} />
+                </div>
+              )}
+            </div>
+          );
+        })}
+      </div>
+    </div>
+  );
+};
+
+export default RemoteFiles;
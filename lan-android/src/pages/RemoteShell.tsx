@@ -0,0 +1,246 @@
+import { useState, useEffect, useRef } from 'react';
+import { useParams, useNavigate } from 'react-router-dom';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { useTranslation } from 'react-i18next';
+import { Host, ShellClosed, ShellOutput } from '../types';
+import { IconArrowBack } from '../components/Icons';
+import { parseError } from '../utils/errorParser';
+
+interface RemoteShellProps {
+  hosts: Host[];
+}
+
+// 页面只显示纯文本，去掉颜色和光标控制序列
+const ANSI_PATTERN = /\x1b\[[0-?]*[ -/]*[@-~]|\x1b\][^\x07\x1b]*(\x07|\x1b\\)|\x1b[@-_]|\r/g;
+// 保留的输出长度，避免长时间运行后页面变慢
+const MAX_OUTPUT_LENGTH = 100_000;
+const TERMINAL_COLS = 80;
+const TERMINAL_ROWS = 24;
+
+const RemoteShell = ({ hosts }: RemoteShellProps) => {
+  const { t } = useTranslation();
+  const { id } = useParams<{ id: string }>();
+  const navigate = useNavigate();
+  const host = hosts.find(h => h.id === id);
+
+  const [output, setOutput] = useState('');
+  const [input, setInput] = useState('');
+  const [sessionId, setSessionId] = useState<string | null>(null);
+  const [isConnecting, setIsConnecting] = useState(false);
+  const [status, setStatus] = useState<string | null>(null);
+  const [attempt, setAttempt] = useState(0);
+  const outputRef = useRef<HTMLPreElement>(null);
+
+  useEffect(() => {
+    if (!host) return;
+    let cancelled = false;
+    let opened: string | null = null;
+    // 终端可能在 open_shell_session 返回前就开始输出
+    const isOwn = (event: { session_id: string; device_id: string }) =>
+      opened === null ? event.device_id === host.id : event.session_id === opened;
+
+    const unlistenOutput = listen<ShellOutput>('shell-output', (event) => {
+      if (!isOwn(event.payload)) return;
+      const text = event.payload.data.replace(ANSI_PATTERN, '');
+      setOutput(prev => (prev + text).slice(-MAX_OUTPUT_LENGTH));
+    });
+    const unlistenClosed = listen<ShellClosed>('shell-closed', (event) => {
+      const closed = event.payload;
+      if (!isOwn(closed)) return;
+      setSessionId(null);
+      if (closed.error) {
+        setStatus(closed.error);
+      } else if (closed.exit_code !== undefined && closed.exit_code !== null) {
+        setStatus(t('remoteShell.exited', { code: closed.exit_code }));
+      } else {
+        setStatus(t('remoteShell.closed'));
+      }
+    });
+
+    setIsConnecting(true);
+    setStatus(null);
+    invoke<string>('open_shell_session', {
+      deviceId: host.id,
+      cols: TERMINAL_COLS,
+      rows: TERMINAL_ROWS,
+    })
+      .then(session => {
+        // 页面已离开时立即关闭刚打开的终端
+        if (cancelled) {
+          invoke('close_shell_session', { sessionId: session });
+          return;
+        }
+        opened = session;
+        setSessionId(session);
+      })
+      .catch(err => {
+        if (!cancelled) setStatus(parseError(err).message);
+      })
+      .finally(() => {
+        if (!cancelled) setIsConnecting(false);
+      });
+
+    return () => {
+      cancelled = true;
+      if (opened) invoke('close_shell_session', { sessionId: opened });
+      unlistenOutput.then(fn => fn());
+      unlistenClosed.then(fn => fn());
+    };
+  }, [host?.id, attempt]);
+
+  useEffect(() => {
+    outputRef.current?.scrollTo(0, outputRef.current.scrollHeight);
+  }, [output]);
+
+  const handleSend = async () => {
+    if (!sessionId) return;
+    const data = input;
+    setInput('');
+    try {
+      await invoke('write_shell_input', { sessionId, data: `${data}\r` });
+    } catch (err) {
+      setStatus(parseError(err).message);
+    }
+  };
+
+  if (!host) {
+    return (
+      <div style={{ minHeight: '100vh', backgroundColor: '#0f1419', padding: '48px 20px' }}>
+        <p style={{ color: '#8b9aa8' }}>{t('hostDetail.deviceNotFound')}</p>
+      </div>
+    );
+  }
+
+  return (
+    <div style={{
+      height: '100vh',
+      backgroundColor: '#0f1419',
+      display: 'flex',
+      flexDirection: 'column'
+    }}>
+      {/* Header */}
+      <div style={{
+        padding: '16px 20px',
+        paddingTop: '48px',
+        display: 'flex',
+        alignItems: 'center',
+        gap: '12px'
+      }}>
+        <button
+          onClick={() => navigate(`/host/${host.id}`)}
+          style={{
+            width: '40px',
+            height: '40px',
+            borderRadius: '50%',
+            backgroundColor: '#1a2332',
+            border: 'none',
+            display: 'flex',
+            alignItems: 'center',
+            justifyContent: 'center',
+            cursor: 'pointer',
+            flexShrink: 0
+          }}
+        >
+          <IconArrowBack style={{ width: '20px', height: '20px', color: '#ffffff' }} />
+        </button>
+        <div style={{ minWidth: 0 }}>
+          <h1 style={{ color: '#ffffff', fontSize: '20px', fontWeight: 600, margin: 0 }}>
+            {t('remoteShell.title')}
+          </h1>
+          <p style={{ color: '#8b9aa8', fontSize: '12px', margin: 0 }}>{host.name}</p>
+        </div>
+      </div>
+
+      <pre
+        ref={outputRef}
+        style={{
+          flex: 1,
+          margin: '0 20px',
+          padding: '12px',
+          borderRadius: '12px',
+          backgroundColor: '#000000',
+          color: '#d1d5db',
+          fontSize: '12px',
+          fontFamily: 'monospace',
+          overflow: 'auto',
+          whiteSpace: 'pre-wrap',
+          wordBreak: 'break-all'
+        }}
+      >
+        {isConnecting ? t('remoteShell.connecting') : output}
+      </pre>
+
+      {status && (
+        <div style={{
+          display: 'flex',
+          alignItems: 'center',
+          justifyContent: 'space-between',
+          gap: '12px',
+          padding: '8px 20px 0'
+        }}>
+          <p style={{ color: '#8b9aa8', fontSize: '13px', margin: 0 }}>{status}</p>
+          {!sessionId && (
+            <button
+              onClick={() => setAttempt(a => a + 1)}
+              style={{
+                padding: '6px 12px',
+                borderRadius: '8px',
+                backgroundColor: '#1a2332',
+                color: '#ffffff',
+                border: 'none',
+                fontSize: '13px',
+                cursor: 'pointer',
+                flexShrink: 0
+              }}
+            >
+              {t('remoteShell.reconnect')}
+            </button>
+          )}
+        </div>
+      )}
+
+      <div style={{ display: 'flex', gap: '8px', padding: '12px 20px 24px' }}>
+        <input
+          type="text"
+          value={input}
+          onChange={(e) => setInput(e.target.value)}
+          onKeyDown={(e) => e.key === 'Enter' && handleSend()}
+          placeholder={t('remoteShell.inputPlaceholder')}
+          disabled={!sessionId}
+          autoCapitalize="off"
+          autoCorrect="off"
+          spellCheck={false}
+          style={{
+            flex: 1,
+            padding: '12px',
+            borderRadius: '10px',
+            backgroundColor: '#1a2332',
+            color: '#ffffff',
+            border: 'none',
+            fontSize: '14px',
+            fontFamily: 'monospace'
+          }}
+        />
+        <button
+          onClick={handleSend}
+          disabled={!sessionId}
+          style={{
+            padding: '12px 16px',
+            borderRadius: '10px',
+            backgroundColor: '#13a4ec',
+            color: '#ffffff',
+            border: 'none',
+            fontSize: '14px',
+            cursor: sessionId ? 'pointer' : 'not-allowed',
+            opacity: sessionId ? 1 : 0.5
+          }}
+        >
+          {t('remoteShell.send')}
+        </button>
+      </div>
+    </div>
+  );
+};
+
+export default RemoteShell;
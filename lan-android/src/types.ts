@@ -31,6 +31,36 @@ export interface DeviceStatus {
   uptime: number;
   os_type: string;
   os_version: string;
+  clock_skew_ms?: number | null;
+  available_update?: string | null;
+}
+
+/**
+ * Structured error returned by device commands (see src-tauri/src/error.rs)
+ */
+export interface ApiError {
+  kind: 'network' | 'timeout' | 'auth_required' | 'token_expired' | 'unsupported' | 'server' | 'other';
+  message: string;
+  code?: string | null;
+}
+
+// 后台刷新任务发送的 device-status 事件
+export interface DeviceStatusUpdate {
+  device_id: string;
+  status?: DeviceStatus;
+  error?: ApiError;
+}
+
+export interface MetricsSample {
+  device_id: string;
+  cpu_usage: number;
+  memory_used: number;
+  uptime_seconds: number;
+}
+
+export interface MetricsStopped {
+  device_id: string;
+  error?: string | null;
 }
 
 export interface AuthResult {
@@ -52,4 +82,65 @@ export interface CommandResult {
   stderr: string;
   exit_code?: number;
   execution_time_ms: number;
+  error_code?: string;
+  error_message?: string;
+}
+
+export interface SpeedTestResult {
+  bytes: number;
+  download_ms: number;
+  upload_ms: number;
+  download_mbps: number;
+  upload_mbps: number;
+}
+
+export interface ScheduledCommand {
+  id: string;
+  device_id: string;
+  command: string;
+  args?: string[];
+  next_run: number;
+  repeat_secs?: number;
+  enabled: boolean;
+}
+
+export interface ScheduleRun {
+  schedule_id: string;
+  device_id: string;
+  command: string;
+  ran_at: number;
+  success: boolean;
+  message: string;
+}
+
+export interface RemoteFile {
+  name: string;
+  path: string;
+  root: string;
+  is_dir: boolean;
+  size: number;
+  modified?: string;
+}
+
+export interface TransferProgress {
+  device_id: string;
+  direction: 'download' | 'upload';
+  remote_path: string;
+  local_path: string;
+  transferred: number;
+  total: number;
+  done: boolean;
+}
+
+export interface ShellOutput {
+  session_id: string;
+  device_id: string;
+  data: string;
+}
+
+export interface ShellClosed {
+  session_id: string;
+  device_id: string;
+  exit_code?: number;
+  error?: string;
 }
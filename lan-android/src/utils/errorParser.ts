@@ -1,4 +1,5 @@
 import i18n from '../i18n';
+import { ApiError } from '../types';
 
 export interface ParsedError {
   message: string;
@@ -6,6 +7,19 @@ export interface ParsedError {
   originalError?: string;
 }
 
+function isApiError(error: unknown): error is ApiError {
+  return typeof error === 'object' && error !== null && 'kind' in error && 'message' in error;
+}
+
+// 可以直接按 kind 判断的错误，其余按消息文本匹配
+const errorKinds: Partial<Record<ApiError['kind'], { type: ParsedError['type']; key: string }>> = {
+  network: { type: 'network', key: 'errors.networkError' },
+  timeout: { type: 'connection', key: 'errors.timeout' },
+  auth_required: { type: 'auth', key: 'auth.reconnectRequired' },
+  token_expired: { type: 'auth', key: 'auth.reconnectRequired' },
+  confirmation_required: { type: 'permission', key: 'errors.confirmationRequired' },
+};
+
 const errorPatterns: Array<{
   patterns: string[];
   type: ParsedError['type'];
@@ -71,9 +85,29 @@ const errorPatterns: Array<{
  * Parse complex error messages into user-friendly localized messages
  */
 export function parseError(error: unknown): ParsedError {
+  if (isApiError(error)) {
+    const known = errorKinds[error.kind];
+    if (known) {
+      return { message: i18n.t(known.key), type: known.type, originalError: error.message };
+    }
+    error = error.message;
+  }
+
   const errorStr = String(error).toLowerCase();
   const originalError = String(error);
 
+  // Malformed request - 服务端会指出出错的字段和期望的类型，保留这部分内容
+  const invalidRequest = originalError.match(
+    /(?:invalid request|the request was not understood|无法识别请求)[:：]\s*(.*)$/i
+  );
+  if (invalidRequest) {
+    return {
+      message: i18n.t('errors.invalidRequest', { detail: invalidRequest[1] }),
+      type: 'server',
+      originalError
+    };
+  }
+
   // Check against known patterns
   for (const { patterns, type, key } of errorPatterns) {
     if (patterns.some(pattern => errorStr.includes(pattern.toLowerCase()))) {
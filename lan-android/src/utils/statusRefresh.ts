@@ -0,0 +1,23 @@
+import { invoke } from '@tauri-apps/api/core';
+
+const STORAGE_KEY = 'statusRefreshInterval';
+
+/** Seconds between background status refreshes, 0 turns them off */
+export const STATUS_REFRESH_INTERVALS = [0, 15, 30, 60, 300];
+const DEFAULT_INTERVAL = 30;
+
+export function getStatusRefreshInterval(): number {
+  const stored = localStorage.getItem(STORAGE_KEY);
+  const secs = stored === null ? NaN : Number(stored);
+  return STATUS_REFRESH_INTERVALS.includes(secs) ? secs : DEFAULT_INTERVAL;
+}
+
+// 保存在本地，启动时同步给后台刷新任务
+export async function setStatusRefreshInterval(secs: number): Promise<void> {
+  await invoke('set_status_refresh_interval', { secs });
+  localStorage.setItem(STORAGE_KEY, String(secs));
+}
+
+export function syncStatusRefreshInterval(): void {
+  invoke('set_status_refresh_interval', { secs: getStatusRefreshInterval() }).catch(() => {});
+}
@@ -1,3 +1,8 @@
 fn main() {
+    // 使用自带的 protoc，构建时无需另外安装
+    let protoc = protoc_bin_vendored::protoc_bin_path().expect("vendored protoc not available");
+    std::env::set_var("PROTOC", protoc);
+    tonic_build::compile_protos("proto/lan_device.proto").expect("Failed to compile protos");
+
     tauri_build::build()
 }
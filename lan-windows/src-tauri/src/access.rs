@@ -0,0 +1,84 @@
+use chrono::{DateTime, Local};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 保留的最近请求数量
+const ACCESS_LOG_SIZE: usize = 500;
+
+/// 一次 HTTP 请求的访问记录，在处理完成后写入
+#[derive(Debug, Clone, Serialize)]
+pub struct AccessLogEntry {
+    pub timestamp: DateTime<Local>,
+    pub ip: String,
+    pub method: String,
+    pub path: String,
+    pub status: u16,
+    pub latency_ms: u64,
+}
+
+// 访问日志与界面日志分开保存，避免每个请求都挤占界面日志
+static ACCESS_LOG: Lazy<Mutex<VecDeque<AccessLogEntry>>> =
+    Lazy::new(|| Mutex::new(VecDeque::with_capacity(ACCESS_LOG_SIZE)));
+
+// 最近一次认证通过的请求，用于空闲自动停止服务器
+static LAST_ACTIVITY: Lazy<Mutex<Instant>> = Lazy::new(|| Mutex::new(Instant::now()));
+
+/// 标记有客户端在使用：令牌验证通过，或未设置密码时的普通请求
+pub fn mark_active() {
+    if let Ok(mut last) = LAST_ACTIVITY.lock() {
+        *last = Instant::now();
+    }
+}
+
+/// 距离最近一次认证通过的请求已经过去的时间
+pub fn idle_for() -> Duration {
+    LAST_ACTIVITY
+        .lock()
+        .map(|last| last.elapsed())
+        .unwrap_or_default()
+}
+
+/// 记录一次请求：写入日志文件，并保留最近的记录供界面查看
+pub fn record(ip: &str, method: &str, path: &str, status: u16, latency: Duration) {
+    let latency_ms = latency.as_millis() as u64;
+    log::info!(
+        "[Access] [{}] {} {} {} {}ms",
+        ip,
+        method,
+        path,
+        status,
+        latency_ms
+    );
+
+    if let Ok(mut entries) = ACCESS_LOG.lock() {
+        if entries.len() == ACCESS_LOG_SIZE {
+            entries.pop_front();
+        }
+        entries.push_back(AccessLogEntry {
+            timestamp: Local::now(),
+            ip: ip.to_string(),
+            method: method.to_string(),
+            path: path.to_string(),
+            status,
+            latency_ms,
+        });
+    }
+}
+
+/// 最近的访问记录，最新的在前
+pub fn recent_requests() -> Vec<AccessLogEntry> {
+    ACCESS_LOG
+        .lock()
+        .map(|entries| entries.iter().rev().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// 清空访问记录
+pub fn clear() {
+    if let Ok(mut entries) = ACCESS_LOG.lock() {
+        entries.clear();
+    }
+}
@@ -1,9 +1,16 @@
+use axum::extract::ws::WebSocketUpgrade;
 use axum::extract::ConnectInfo;
 use axum::{
-    extract::{Json, Query, State},
-    http::StatusCode,
-    response::Json as AxumJson,
-    routing::{get, post},
+    extract::{DefaultBodyLimit, Extension, Path, Query, State},
+    http::{
+        header::{
+            ACCEPT_RANGES, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, ETAG,
+            IF_NONE_MATCH, RANGE,
+        },
+        HeaderMap, HeaderValue, StatusCode,
+    },
+    response::{IntoResponse, Json as AxumJson, Response},
+    routing::{delete, get, post},
     Router,
 };
 use http::Request;
@@ -11,14 +18,18 @@ use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
 use std::convert::Infallible;
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::task::{Context, Poll};
 use std::time::{Duration, Instant};
 use tokio::net::TcpListener;
 use tokio::sync::Notify;
 use tokio::sync::{Mutex, RwLock};
+use tower::limit::GlobalConcurrencyLimitLayer;
 use tower::{Layer, Service};
+use tower_http::compression::CompressionLayer;
 use tower_http::cors::{Any, CorsLayer};
+use tower_http::timeout::TimeoutLayer;
 
 // 线程本地存储，用于在中间件和handler之间共享客户端IP
 thread_local! {
@@ -52,8 +63,13 @@ pub fn is_ip_blacklisted(ip: &str) -> bool {
     // 检查IP是否在黑名单中
     config.ip_blacklist.iter().any(|blocked_ip| {
         let blocked = blocked_ip.trim();
-        // 支持精确匹配和通配符匹配
-        if blocked.contains('*') {
+        // 支持精确匹配、CIDR 网段和通配符匹配
+        if let Some((network, prefix)) = crate::config::parse_cidr(blocked) {
+            ip_part
+                .parse()
+                .map(|ip| crate::config::cidr_contains(network, prefix, ip))
+                .unwrap_or(false)
+        } else if blocked.contains('*') {
             // 通配符匹配，如 192.168.1.*
             let pattern = blocked.replace('*', ".*");
             regex::Regex::new(&format!("^{}$", pattern))
@@ -66,10 +82,57 @@ pub fn is_ip_blacklisted(ip: &str) -> bool {
     })
 }
 
+/// 检查IP是否来自局域网之外（启用 lan_only 时拒绝）
+///
+/// 局域网地址包括本机、RFC1918 私有网段、链路本地地址和 IPv6 唯一本地地址，无法解析的地址视为外部
+pub fn is_outside_lan(ip: &str) -> bool {
+    if !get_config().lan_only {
+        return false;
+    }
+
+    let addr = match crate::stats::ip_only(ip).parse::<std::net::IpAddr>() {
+        Ok(std::net::IpAddr::V6(v6)) => v6
+            .to_ipv4_mapped()
+            .map(std::net::IpAddr::V4)
+            .unwrap_or(std::net::IpAddr::V6(v6)),
+        Ok(addr) => addr,
+        Err(_) => return true,
+    };
+    let is_lan = match addr {
+        std::net::IpAddr::V4(v4) => v4.is_loopback() || v4.is_private() || v4.is_link_local(),
+        std::net::IpAddr::V6(v6) => {
+            let first = v6.segments()[0];
+            // fe80::/10 为链路本地，fc00::/7 为唯一本地地址
+            v6.is_loopback() || (first & 0xffc0) == 0xfe80 || (first & 0xfe00) == 0xfc00
+        }
+    };
+    !is_lan
+}
+
 use crate::auth::AuthManager;
-use crate::config::get_config;
-use crate::models::{AuthResponse, CommandResult, SystemInfo};
-use crate::websocket::{ws_handler, WebSocketManager};
+use crate::config::{
+    config_version, get_config, AppConfig, ClientPermissions, FieldError, ListenerConfig,
+};
+use crate::disks::DisksHealthReport;
+use crate::display::MonitorInfo;
+use crate::events::EventBatch;
+use crate::extract::Json;
+use crate::facts::HostFacts;
+use crate::files::{FileEntry, FileSearchOptions, FileSearchResult};
+use crate::media::AudioDevice;
+use crate::network::{ConnectionsInfo, PingResult, SpeedTestUpload, TraceResult};
+use crate::peripherals::PeripheralsInfo;
+use crate::power::{FocusStatus, InhibitorInfo};
+use crate::printers::PrinterInfo;
+use crate::self_update::AppUpdateStatus;
+use crate::sessions::{SessionAction, SessionInfo};
+use crate::payload::PAYLOAD_CIPHER;
+use crate::policy::{CommandCheck, PolicyDecision};
+use crate::models::{AuthResponse, CommandResult, PresenceInfo, StaticSystemInfo, SystemInfo};
+use crate::stats::{AuthOutcome, ClientStats};
+use crate::sync::CatalogEntry;
+use crate::updates::UpdateState;
+use crate::websocket::{ws_handler, WebSocketManager, WsMessage};
 
 pub struct ApiServer {
     port: u16,
@@ -78,6 +141,7 @@ pub struct ApiServer {
     shutdown_notify: Option<Arc<Notify>>,
     server_handle: Option<tokio::task::JoinHandle<()>>,
     is_running: Arc<RwLock<bool>>,
+    exit_signal: ExitSignal,
 }
 
 impl Clone for ApiServer {
@@ -89,17 +153,39 @@ impl Clone for ApiServer {
             shutdown_notify: None,
             server_handle: None,
             is_running: self.is_running.clone(),
+            exit_signal: self.exit_signal.clone(),
         }
     }
 }
 
-// 全局日志存储，用于从 API 层发送日志到 UI
-use crate::models::{LogEntry, LogLevel};
-use chrono::Local;
-use once_cell::sync::Lazy;
-use std::sync::Mutex as StdMutex;
+/// 服务器任务退出信号，用于区分主动停止和意外退出
+#[derive(Clone, Default)]
+pub struct ExitSignal {
+    exited: Arc<Notify>,
+    stop_requested: Arc<AtomicBool>,
+}
+
+impl ExitSignal {
+    /// 等待服务器任务退出，返回是否为意外退出
+    pub async fn wait(&self) -> bool {
+        self.exited.notified().await;
+        !self.stop_requested()
+    }
+
+    /// 是否已请求停止
+    pub fn stop_requested(&self) -> bool {
+        self.stop_requested.load(Ordering::SeqCst)
+    }
 
-pub static API_LOGS: Lazy<StdMutex<Vec<LogEntry>>> = Lazy::new(|| StdMutex::new(Vec::new()));
+    /// 通知监视者服务器任务已退出
+    pub fn notify_exited(&self) {
+        self.exited.notify_one();
+    }
+}
+
+// 从 API 层发送日志到 UI，存放在 logger::API_LOGS
+use crate::models::{LogEntry, LogLevel};
+use chrono::{DateTime, Local};
 
 pub fn log_to_ui(level: &str, message: &str) {
     let log_level = match level {
@@ -117,30 +203,8 @@ pub fn log_to_ui(level: &str, message: &str) {
         source: None,
     };
 
-    if let Ok(mut logs) = API_LOGS.lock() {
-        logs.push(entry.clone());
-        // 限制日志数量
-        if logs.len() > 50 {
-            logs.remove(0);
-        }
-    }
-
     // 同时写入日志文件
-    crate::logger::write_log_to_file(&entry);
-}
-
-pub fn get_api_logs(limit: usize) -> Vec<LogEntry> {
-    if let Ok(logs) = API_LOGS.lock() {
-        logs.iter().rev().take(limit).cloned().collect()
-    } else {
-        Vec::new()
-    }
-}
-
-pub fn clear_api_logs() {
-    if let Ok(mut logs) = API_LOGS.lock() {
-        logs.clear();
-    }
+    crate::logger::record(&crate::logger::API_LOGS, entry);
 }
 
 #[derive(Debug, Deserialize)]
@@ -151,13 +215,97 @@ struct ChallengeRequest {
 #[derive(Debug, Serialize)]
 struct ChallengeResponse {
     challenge: String,
+    /// 派生登录验证器的盐，未设置密码时不返回
+    #[serde(skip_serializing_if = "Option::is_none")]
+    salt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    iterations: Option<u32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct LoginRequest {
+    pub challenge: String,
+    /// 以验证器为密钥对挑战计算的 HMAC（十六进制）
+    pub response: String,
+    /// 客户端设备ID，写入令牌便于识别来源
+    #[serde(default)]
+    pub device_id: Option<String>,
+    /// 请求的负载加密算法（目前只支持 aes-256-gcm-v2），用于没有 TLS 的连接
+    #[serde(default)]
+    pub encryption: Option<String>,
+    /// 响应以配对密钥而不是验证器计算，需要同时提供 device_id
+    #[serde(default)]
+    pub paired: bool,
 }
 
 #[derive(Debug, Deserialize)]
-struct LoginRequest {
-    challenge: String,
-    response: String,
-    password: String,
+struct PairStartRequest {
+    device_id: String,
+    /// 客户端的 SPAKE2 消息（十六进制）
+    message: String,
+}
+
+#[derive(Debug, Serialize)]
+struct PairStartResponse {
+    pairing_id: String,
+    /// 服务端的 SPAKE2 消息（十六进制）
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PairConfirmRequest {
+    pairing_id: String,
+    /// 客户端的密钥确认（十六进制）
+    proof: String,
+}
+
+#[derive(Debug, Serialize)]
+struct PairConfirmResponse {
+    /// 服务端的密钥确认（十六进制），客户端据此确认连接的是知道密码的设备
+    proof: String,
+}
+
+/// 当前协议版本，客户端与服务端协议不兼容时应提示升级
+pub const PROTOCOL_VERSION: u32 = 1;
+/// 仍然兼容的最低协议版本
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+/// 时钟偏差超过该值时发出警告（毫秒）
+pub const CLOCK_SKEW_WARN_MS: i64 = 30_000;
+/// 长轮询未指定 timeout 时的等待时间（秒）
+const EVENT_POLL_DEFAULT_TIMEOUT_SECS: u64 = 25;
+/// 长轮询的最长等待时间（秒），留在常见代理的空闲超时以内
+const EVENT_POLL_MAX_TIMEOUT_SECS: u64 = 60;
+/// /api/logs 每页默认和最多返回的日志条数
+const DEFAULT_LOG_PAGE: usize = 100;
+const MAX_LOG_PAGE: usize = 500;
+
+#[derive(Debug, Deserialize)]
+struct HandshakeRequest {
+    protocol_version: u32,
+    /// 客户端发送请求时的时间（Unix 时间戳，毫秒）
+    client_time: i64,
+    #[serde(default)]
+    client_version: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct HandshakeResponse {
+    protocol_version: u32,
+    min_protocol_version: u32,
+    server_version: &'static str,
+    /// 服务端时间（Unix 时间戳，毫秒）
+    server_time: i64,
+    /// 客户端时间减去服务端时间，未扣除网络延迟
+    clock_skew_ms: i64,
+    clock_skew_warning: bool,
+    compatible: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct ConfigPatchRequest {
+    token: String,
+    /// 要修改的配置项，键为 AppConfig 字段名
+    changes: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -167,9 +315,202 @@ struct CommandRequest {
     args: Option<Vec<String>>,
 }
 
+#[derive(Debug, Deserialize)]
+struct TokenRequest {
+    token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PingRequest {
+    token: String,
+    target: String,
+    count: Option<u32>,
+    timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TracerouteRequest {
+    token: String,
+    target: String,
+    max_hops: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SpeedTestQuery {
+    token: Option<String>,
+    /// 下载测试的数据量（字节）
+    bytes: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ConnectionsQuery {
+    token: Option<String>,
+    /// 只返回本地或远端端口匹配的连接
+    port: Option<u16>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeviceSyncRequest {
+    token: String,
+    /// 客户端本地的设备目录，只拉取时为空
+    #[serde(default)]
+    devices: Vec<CatalogEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SessionActionRequest {
+    token: String,
+    session_id: u32,
+    /// lock / disconnect / logoff
+    action: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct DisplayModeRequest {
+    token: String,
+    monitor: String,
+    width: Option<u32>,
+    height: Option<u32>,
+    refresh_rate: Option<u32>,
+    /// 为 true 时设为主显示器
+    #[serde(default)]
+    primary: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct AudioDeviceRequest {
+    token: String,
+    device_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct FocusRequest {
+    token: String,
+    enabled: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct InhibitRequest {
+    token: String,
+    duration_secs: u64,
+    reason: Option<String>,
+    #[serde(default)]
+    keep_display: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenUrlRequest {
+    token: String,
+    url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenPathRequest {
+    token: String,
+    path: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PrintQuery {
+    token: Option<String>,
+    printer: Option<String>,
+    /// 打印共享目录中的文件；为空时打印请求体中上传的文件
+    path: Option<String>,
+    /// 上传文件的文件名，用于确定文件类型
+    filename: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 struct TokenQuery {
     token: Option<String>,
+    /// 为 true 时忽略缓存，重新读取静态系统信息
+    refresh: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EventPollQuery {
+    token: Option<String>,
+    /// 上次返回的游标，省略时只返回当前游标
+    since: Option<u64>,
+    /// 没有新事件时的最长等待时间（秒）
+    timeout: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LogsQuery {
+    token: Option<String>,
+    /// 只返回早于该时间的日志，即上一页的 next_before
+    before_timestamp: Option<DateTime<Local>>,
+    limit: Option<usize>,
+}
+
+/// 一页日志，最新的在前
+#[derive(Debug, Serialize)]
+struct LogPage {
+    logs: Vec<LogEntry>,
+    /// 下一页的 before_timestamp，没有更多日志时为空
+    next_before: Option<DateTime<Local>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileSearchQuery {
+    token: Option<String>,
+    pattern: String,
+    #[serde(default)]
+    regex: bool,
+    root: Option<String>,
+    content: Option<String>,
+    #[serde(default)]
+    offset: usize,
+    #[serde(default)]
+    limit: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct ShellQuery {
+    token: Option<String>,
+    cols: Option<u16>,
+    rows: Option<u16>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileListQuery {
+    token: Option<String>,
+    /// 为空时列出共享根目录
+    path: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileTransferQuery {
+    token: Option<String>,
+    path: String,
+    /// 上传数据在文件中的起始位置
+    #[serde(default)]
+    offset: u64,
+    /// 最后一块上传数据
+    #[serde(default)]
+    complete: bool,
+    /// 目标文件已存在时是否替换，默认拒绝上传
+    #[serde(default)]
+    overwrite: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClipboardRequest {
+    token: Option<String>,
+    text: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ClipboardText {
+    text: String,
+}
+
+/// 上传进度，客户端据此断点续传
+#[derive(Debug, Serialize)]
+struct FileUploadProgress {
+    path: String,
+    uploaded: u64,
 }
 
 #[derive(Debug, Serialize)]
@@ -177,6 +518,241 @@ struct ApiResponse<T> {
     success: bool,
     data: Option<T>,
     error: Option<String>,
+    /// 错误的结构化说明，如命令被拒绝时的判定结果（见 policy::PolicyDecision）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error_details: Option<serde_json::Value>,
+}
+
+/// 客户端可用功能
+#[derive(Debug, Serialize)]
+struct Capabilities {
+    version: &'static str,
+    requires_auth: bool,
+    commands: Vec<String>,
+    custom_commands: Vec<String>,
+    features: Vec<&'static str>,
+}
+
+/// 服务端看到的客户端请求信息，供客户端排查连接问题
+#[derive(Debug, Serialize)]
+struct EchoInfo {
+    client_ip: String,
+    method: String,
+    path: String,
+    /// 请求头，认证相关的值已隐藏
+    headers: std::collections::BTreeMap<String, String>,
+    token_present: bool,
+    token_valid: bool,
+    /// 令牌过期时间（Unix 时间戳，秒）
+    token_expires_at: Option<i64>,
+    device_id: Option<String>,
+    /// 令牌可访问的权限范围
+    scopes: Vec<String>,
+    requires_auth: bool,
+    server_time: chrono::DateTime<chrono::Local>,
+    server_version: &'static str,
+}
+
+/// 根据响应内容计算弱 ETag（响应可能被压缩，因此使用弱校验）
+fn compute_etag(bytes: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    let digest = Sha256::digest(bytes);
+    format!("W/\"{}\"", hex::encode(&digest[..8]))
+}
+
+/// 检查请求的 If-None-Match 是否命中 ETag
+fn etag_matches(headers: &HeaderMap, etag: &str) -> bool {
+    let etag = etag.trim_start_matches("W/");
+    headers
+        .get(IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| {
+            value.split(',').any(|tag| {
+                let tag = tag.trim();
+                tag == "*" || tag.trim_start_matches("W/") == etag
+            })
+        })
+        .unwrap_or(false)
+}
+
+fn not_modified(etag: String) -> Response {
+    (StatusCode::NOT_MODIFIED, [(ETAG, etag)]).into_response()
+}
+
+/// 序列化响应并按内容生成 ETag，命中 If-None-Match 时返回 304
+fn json_with_etag<T: Serialize>(headers: &HeaderMap, body: &T) -> Response {
+    let bytes = match serde_json::to_vec(body) {
+        Ok(bytes) => bytes,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    let etag = compute_etag(&bytes);
+    if etag_matches(headers, &etag) {
+        return not_modified(etag);
+    }
+    (
+        [(CONTENT_TYPE, "application/json".to_string()), (ETAG, etag)],
+        bytes,
+    )
+        .into_response()
+}
+
+/// 使用预先确定的 ETag（如配置版本号），命中时不再构造和序列化响应
+fn json_with_version_etag<T, F>(headers: &HeaderMap, etag: String, body: F) -> Response
+where
+    T: Serialize,
+    F: FnOnce() -> T,
+{
+    if etag_matches(headers, &etag) {
+        return not_modified(etag);
+    }
+    let mut response = AxumJson(body()).into_response();
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        response.headers_mut().insert(ETAG, value);
+    }
+    response
+}
+
+/// 未设置密码或 token 有效时返回 true
+pub fn is_token_authorized(auth_manager: &AuthManager, token: Option<&str>) -> bool {
+    !auth_manager.is_password_set()
+        || token
+            .map(|token| auth_manager.verify_token(token))
+            .unwrap_or(false)
+}
+
+/// 检查令牌所属客户端的权限
+///
+/// 只有未配置 client_permissions 或没有令牌（未设置密码）时不受限制，
+/// 没有适用条目的客户端会得到拒绝全部功能的权限（见 ClientPermissions::deny_all）
+pub fn client_allowed(
+    auth_manager: &AuthManager,
+    token: Option<&str>,
+    check: impl FnOnce(&ClientPermissions) -> bool,
+) -> bool {
+    token
+        .and_then(|token| auth_manager.permissions(token))
+        .map_or(true, |permissions| check(&permissions))
+}
+
+/// 客户端权限不足时的响应
+fn permission_denied<T>(ip: &str, action: &str) -> Result<AxumJson<ApiResponse<T>>, StatusCode> {
+    log::warn!("[Command] [{}] {} REJECTED: Permission denied", ip, action);
+    log_to_ui("warn", &format!("[{}] {} REJECTED: Permission denied", ip, action));
+    Ok(AxumJson(ApiResponse {
+        success: false,
+        data: None,
+        error: Some("Permission denied".to_string()),
+        error_details: None,
+    }))
+}
+
+/// 按客户端权限和命令白名单判定命令能否执行，结果记入判定日志
+pub fn check_command(
+    auth_manager: &AuthManager,
+    ip: &str,
+    token: &str,
+    command: &str,
+) -> PolicyDecision {
+    let client = auth_manager.permission_source(token);
+    let decision = crate::policy::evaluate_command(
+        command,
+        client.as_ref().map(|(source, permissions)| (source.as_str(), permissions)),
+        &get_config(),
+    );
+    let identity = auth_manager.identity(token);
+    crate::policy::record(ip, identity.as_deref(), &decision);
+    decision
+}
+
+/// 命令被拒绝时的响应，error_details 中说明命中的规则和相关配置项
+fn command_denied<T>(
+    ip: &str,
+    action: &str,
+    decision: PolicyDecision,
+) -> Result<AxumJson<ApiResponse<T>>, StatusCode> {
+    log::warn!("[Command] [{}] {} REJECTED: {}", ip, action, decision.reason);
+    log_to_ui("warn", &format!("[{}] {} REJECTED: {}", ip, action, decision.reason));
+    Ok(AxumJson(ApiResponse {
+        success: false,
+        data: None,
+        error: Some(decision.reason.clone()),
+        error_details: serde_json::to_value(&decision).ok(),
+    }))
+}
+
+/// 令牌无效时的响应
+fn invalid_token<T>() -> Result<AxumJson<ApiResponse<T>>, StatusCode> {
+    Ok(AxumJson(ApiResponse {
+        success: false,
+        data: None,
+        error: Some("Invalid or expired token".to_string()),
+        error_details: None,
+    }))
+}
+
+/// 只读接口的访问检查，未设置密码或令牌有效时通过，未通过时返回拒绝的响应
+fn require_token<T>(
+    state: &AppState,
+    ip: &str,
+    token: Option<&str>,
+    action: &str,
+) -> Option<Result<AxumJson<ApiResponse<T>>, StatusCode>> {
+    if is_token_authorized(&state.auth_manager, token) {
+        return None;
+    }
+    log::warn!("[Access] [{}] {} denied: Invalid token", ip, action);
+    crate::stats::record_auth(ip, AuthOutcome::TokenRejected);
+    Some(invalid_token())
+}
+
+/// 在 require_token 的基础上检查客户端权限是否包含 scope（见 PERMISSION_SCOPES）
+fn require_scope<T>(
+    state: &AppState,
+    ip: &str,
+    token: Option<&str>,
+    scope: &str,
+    action: &str,
+) -> Option<Result<AxumJson<ApiResponse<T>>, StatusCode>> {
+    if let Some(denied) = require_token(state, ip, token, action) {
+        return Some(denied);
+    }
+    if !client_allowed(&state.auth_manager, token, |p| p.allows_scope(scope)) {
+        return Some(permission_denied(ip, action));
+    }
+    None
+}
+
+/// 修改类接口的访问检查，无论是否设置密码都必须携带有效令牌
+fn require_session<T>(
+    state: &AppState,
+    ip: &str,
+    token: Option<&str>,
+    action: &str,
+) -> Option<Result<AxumJson<ApiResponse<T>>, StatusCode>> {
+    if token.is_some_and(|token| state.auth_manager.verify_token(token)) {
+        return None;
+    }
+    log::warn!("[Command] [{}] {} REJECTED: Invalid token", ip, action);
+    crate::stats::record_auth(ip, AuthOutcome::TokenRejected);
+    log_to_ui("warn", &format!("[{}] {} REJECTED: Invalid token", ip, action));
+    Some(invalid_token())
+}
+
+/// 在 require_session 的基础上检查客户端权限是否包含 scope
+fn require_session_scope<T>(
+    state: &AppState,
+    ip: &str,
+    token: Option<&str>,
+    scope: &str,
+    action: &str,
+) -> Option<Result<AxumJson<ApiResponse<T>>, StatusCode>> {
+    if let Some(denied) = require_session(state, ip, token, action) {
+        return Some(denied);
+    }
+    if !client_allowed(&state.auth_manager, token, |p| p.allows_scope(scope)) {
+        return Some(permission_denied(ip, action));
+    }
+    None
 }
 
 // 应用状态结构体
@@ -184,7 +760,7 @@ struct ApiResponse<T> {
 pub struct AppState {
     pub auth_manager: AuthManager,
     pub ws_manager: Arc<Mutex<WebSocketManager>>,
-    pub system_info_cache: Arc<Mutex<Option<(SystemInfo, Instant)>>>, // 缓存系统信息
+    pub system_info_cache: Arc<Mutex<Option<(StaticSystemInfo, Instant)>>>, // 缓存静态系统信息
 }
 
 // 客户端IP中间件 - 用于在请求扩展中存储客户端IP
@@ -225,7 +801,6 @@ where
         self.inner.poll_ready(cx)
     }
 
-    #[allow(clippy::redundant_async_block)]
     fn call(&mut self, mut req: Request<B>) -> Self::Future {
         // 尝试从扩展中获取客户端地址
         let client_ip = req
@@ -244,14 +819,53 @@ where
                 .status(StatusCode::FORBIDDEN)
                 .body(axum::body::Body::from("Access denied: IP is blacklisted"))
                 .unwrap();
+            crate::access::record(
+                &client_ip,
+                req.method().as_str(),
+                req.uri().path(),
+                StatusCode::FORBIDDEN.as_u16(),
+                Duration::ZERO,
+            );
             
             return Box::pin(async move { Ok(response) });
         }
 
-        // 记录请求日志
+        // 只允许局域网访问时拒绝外部地址，通常说明端口被转发到了公网
+        if is_outside_lan(&client_ip) {
+            log::warn!(
+                "[Security] Request from non-LAN IP blocked: {} {} {}",
+                client_ip,
+                req.method(),
+                req.uri().path()
+            );
+            log_to_ui(
+                "warn",
+                &format!("[Security] Blocked request from outside the local network: {}", client_ip),
+            );
+
+            let response = axum::response::Response::builder()
+                .status(StatusCode::FORBIDDEN)
+                .body(axum::body::Body::from("Access denied: only local network clients are allowed"))
+                .unwrap();
+            crate::access::record(
+                &client_ip,
+                req.method().as_str(),
+                req.uri().path(),
+                StatusCode::FORBIDDEN.as_u16(),
+                Duration::ZERO,
+            );
+
+            return Box::pin(async move { Ok(response) });
+        }
+
+        let started = Instant::now();
         let method = req.method().to_string();
         let path = req.uri().path().to_string();
-        log_to_ui("info", &format!("[{}] {} {}", client_ip, method, path));
+
+        // 未设置密码时没有认证，除健康检查外的请求都算作使用
+        if path != "/api/health" && !get_config().has_password() {
+            crate::access::mark_active();
+        }
 
         // 将客户端IP存入请求扩展，供后续handler使用
         req.extensions_mut().insert(ClientIp(client_ip.clone()));
@@ -260,7 +874,14 @@ where
         set_client_ip(&client_ip);
 
         let future = self.inner.call(req);
-        Box::pin(async move { future.await })
+        Box::pin(async move {
+            let response = future.await?;
+            // 处理完成后写入访问日志和客户端统计，记录状态码和耗时
+            let (status, latency) = (response.status().as_u16(), started.elapsed());
+            crate::access::record(&client_ip, &method, &path, status, latency);
+            crate::stats::record_request(&client_ip, &method, &path, status, latency);
+            Ok(response)
+        })
     }
 }
 
@@ -274,9 +895,15 @@ impl ApiServer {
             shutdown_notify: None,
             server_handle: None,
             is_running: Arc::new(RwLock::new(false)),
+            exit_signal: ExitSignal::default(),
         }
     }
 
+    /// 获取服务器退出信号
+    pub fn exit_signal(&self) -> ExitSignal {
+        self.exit_signal.clone()
+    }
+
     pub async fn start(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         // 检查是否已经在运行
         {
@@ -288,6 +915,7 @@ impl ApiServer {
 
         let shutdown_notify = Arc::new(Notify::new());
         self.shutdown_notify = Some(shutdown_notify.clone());
+        self.exit_signal.stop_requested.store(false, Ordering::SeqCst);
 
         let app_state = AppState {
             auth_manager: self.auth_manager.clone(),
@@ -301,34 +929,162 @@ impl ApiServer {
             .allow_methods(Any)
             .allow_headers(Any);
 
-        // 创建路由
-        let app = Router::new()
-            .route("/api/health", get(health_check))
-            .route("/api/auth/challenge", post(get_challenge))
-            .route("/api/auth/login", post(login))
-            .route("/api/auth/check", get(check_auth_required))
-            .route("/api/system/info", get(get_system_info_handler))
+        let config = get_config();
+
+        // 命令类路由执行时间较长，使用单独的超时
+        let command_routes = Router::new()
+            .route(
+                "/api/printers/print",
+                post(print_handler).layer(DefaultBodyLimit::max(config.print_max_upload_bytes)),
+            )
             .route("/api/system/shutdown", post(shutdown_handler))
             .route("/api/system/restart", post(restart_handler))
             .route("/api/system/sleep", post(sleep_handler))
             .route("/api/system/lock", post(lock_handler))
             .route("/api/command/execute", post(execute_command_handler))
+            .route("/api/command/check", post(command_check_handler))
+            .route("/api/network/ping", post(ping_handler))
+            .route("/api/network/traceroute", post(traceroute_handler))
+            .route(
+                "/api/network/speedtest/download",
+                get(speedtest_download_handler),
+            )
+            .route("/api/network/speedtest/upload", post(speedtest_upload_handler))
+            .layer(TimeoutLayer::new(Duration::from_secs(
+                config.http_command_timeout_secs.max(1),
+            )));
+
+        let api_routes = Router::new()
+            .route("/api/health", get(health_check))
+            .route("/api/debug/echo", get(echo_handler))
+            .route("/api/handshake", post(handshake_handler))
+            .route("/api/auth/challenge", post(get_challenge))
+            .route("/api/auth/login", post(login))
+            .route("/api/pair/start", post(pair_start))
+            .route("/api/pair/confirm", post(pair_confirm))
+            .route("/api/auth/check", get(check_auth_required))
+            .route("/api/system/info", get(get_system_info_handler))
+            .route("/api/system/presence", get(presence_handler))
+            .route("/api/system/facts", get(facts_handler))
+            .route("/api/system/peripherals", get(peripherals_handler))
+            .route("/api/system/disks/health", get(disks_health_handler))
+            .route("/api/network/connections", get(connections_handler))
+            .route("/api/system/sessions", get(list_sessions_handler))
+            .route("/api/system/sessions/action", post(session_action_handler))
+            .route("/api/system/updates", get(updates_status_handler))
+            .route("/api/system/updates/scan", post(updates_scan_handler))
+            .route("/api/system/updates/install", post(updates_install_handler))
+            .route(
+                "/api/system/focus",
+                get(get_focus_handler).post(set_focus_handler),
+            )
+            .route(
+                "/api/power/inhibit",
+                get(list_inhibitors_handler).post(add_inhibitor_handler),
+            )
+            .route("/api/power/inhibit/:id", delete(release_inhibitor_handler))
+            .route(
+                "/api/display/modes",
+                get(list_display_modes_handler).post(set_display_mode_handler),
+            )
+            .route(
+                "/api/media/devices",
+                get(list_audio_devices_handler).post(set_audio_device_handler),
+            )
+            .route("/api/apps/open-url", post(open_url_handler))
+            .route("/api/apps/open-path", post(open_path_handler))
+            .route("/api/printers", get(list_printers_handler))
+            .route("/api/capabilities", get(capabilities_handler))
+            .route(
+                "/api/config",
+                get(get_config_handler).patch(patch_config_handler),
+            )
+            .route("/api/files/search", get(file_search_handler))
+            .route(
+                "/api/clipboard",
+                get(get_clipboard_handler).post(set_clipboard_handler),
+            )
+            .route("/api/files/list", get(file_list_handler))
+            .route("/api/files/download", get(file_download_handler))
+            .route(
+                "/api/files/upload",
+                get(file_upload_status_handler).post(file_upload_handler),
+            )
+            .route("/api/stats/clients", get(client_stats_handler))
+            .route("/api/logs", get(logs_handler))
+            .route("/api/app/update", get(app_update_handler))
+            .route("/api/sync/devices", post(device_sync_handler))
+            .layer(TimeoutLayer::new(Duration::from_secs(
+                config.http_request_timeout_secs.max(1),
+            )));
+
+        // 创建路由
+        let app = Router::new()
+            .merge(command_routes)
+            .merge(api_routes)
             .route("/ws", get(ws_handler))
+            .route("/ws/shell", get(shell_ws_handler))
+            // 长轮询自带等待时间，不受普通请求超时限制
+            .route("/api/events/poll", get(events_poll_handler))
+            .layer(DefaultBodyLimit::max(config.http_max_body_bytes))
+            .layer(GlobalConcurrencyLimitLayer::new(
+                config.http_max_concurrent_requests.max(1),
+            ))
             .layer(cors)
+            // 调试抓包在压缩之前记录，保证看到的是原始内容
+            // 为错误响应补充错误码和本地化消息
+            .layer(axum::middleware::from_fn(crate::messages::localize_middleware))
+            .layer(axum::middleware::from_fn(crate::capture::capture_middleware))
+            // 加密层在最外层，内部的中间件和 handler 只处理明文
+            .layer(axum::middleware::from_fn_with_state(
+                self.auth_manager.clone(),
+                crate::payload::payload_middleware,
+            ));
+
+        // 对较大的响应（日志、进程列表等）启用 gzip/deflate 压缩
+        let app = if config.enable_http_compression {
+            app.layer(CompressionLayer::new())
+        } else {
+            app
+        };
+
+        // 按IP限流紧挨着 ClientIpLayer，被限流的请求不再经过解密、抓包和压缩
+        let app = app
+            .layer(axum::middleware::from_fn(crate::ratelimit::rate_limit_middleware))
             .layer(ClientIpLayer)
             .with_state(app_state);
 
-        let addr = SocketAddr::from(([0, 0, 0, 0], self.port));
-        let listener = match TcpListener::bind(addr).await {
-            Ok(l) => l,
-            Err(e) => {
-                log::error!("Failed to bind to port {}: {}", self.port, e);
-                return Err(format!("Port {} is already in use or cannot be bound", self.port).into());
-            }
+        // 主监听器使用 api_port/bind_addr，其余来自 extra_listeners
+        let primary = ListenerConfig {
+            bind_addr: config.bind_addr.clone(),
+            port: self.port,
+            ..Default::default()
         };
-        let actual_port = listener.local_addr()?.port();
+        let mut listeners = Vec::new();
+        for (index, listener_config) in std::iter::once(&primary)
+            .chain(config.extra_listeners.iter())
+            .enumerate()
+        {
+            match bind_listener(listener_config).await {
+                Ok(listener) => listeners.push(listener),
+                Err(e) if index == 0 => {
+                    log::error!("Failed to bind to port {}: {}", self.port, e);
+                    return Err(format!("Port {} is already in use or cannot be bound", self.port).into());
+                }
+                // 附加监听器失败不影响主服务器
+                Err(e) => log::error!(
+                    "Failed to start listener {}:{}: {}",
+                    listener_config.bind_addr,
+                    listener_config.port,
+                    e
+                ),
+            }
+        }
+        let actual_port = listeners[0].local_addr()?.port();
 
-        log::info!("API server listening on port {}", actual_port);
+        for listener in &listeners {
+            log::info!("API server listening on {}", listener.describe());
+        }
 
         // 设置运行状态
         {
@@ -337,29 +1093,39 @@ impl ApiServer {
         }
 
         let is_running = self.is_running.clone();
+        let exit_signal = self.exit_signal.clone();
+
+        // 关闭通知需要传递给所有监听器
+        let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+        tokio::spawn(async move {
+            shutdown_notify.notified().await;
+            log::info!("API server graceful shutdown triggered");
+            let _ = shutdown_tx.send(true);
+        });
 
-        // 启动服务器并保存 handle (启用 ConnectInfo)
-        let handle = tokio::spawn(async move {
-            let server = axum::serve(
-                listener,
-                app.into_make_service_with_connect_info::<SocketAddr>(),
-            );
-
-            // 使用 graceful shutdown
-            let graceful = server.with_graceful_shutdown(async move {
-                // 等待关闭通知
-                shutdown_notify.notified().await;
-                log::info!("API server graceful shutdown triggered");
-            });
+        // 启动服务器，任一监听器出错时整体退出，由监视任务决定是否重启
+        let serve_task = tokio::spawn(async move {
+            let servers = listeners
+                .into_iter()
+                .map(|listener| serve_listener(listener, app.clone(), shutdown_rx.clone()));
+            futures::future::try_join_all(servers).await.map(|_| ())
+        });
 
-            if let Err(e) = graceful.await {
-                log::error!("API server error: {}", e);
+        // 监视服务器任务并保存 handle，任务出错或 panic 时同样会通知退出
+        let handle = tokio::spawn(async move {
+            match serve_task.await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => log::error!("API server error: {}", e),
+                Err(e) => log::error!("API server task aborted: {}", e),
             }
 
             // 设置停止状态
             let mut running = is_running.write().await;
             *running = false;
+            drop(running);
             log::info!("API server stopped");
+
+            exit_signal.notify_exited();
         });
 
         self.server_handle = Some(handle);
@@ -370,6 +1136,8 @@ impl ApiServer {
     pub async fn stop(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         log::info!("Stopping API server...");
 
+        self.exit_signal.stop_requested.store(true, Ordering::SeqCst);
+
         // 触发关闭通知
         if let Some(notify) = self.shutdown_notify.take() {
             notify.notify_one();
@@ -400,18 +1168,250 @@ impl ApiServer {
     pub async fn is_running(&self) -> bool {
         *self.is_running.read().await
     }
+
+    /// 通过 WebSocket 向所有已连接客户端广播消息，返回接收者数量
+    pub async fn broadcast(&self, message: WsMessage) -> usize {
+        match &self.ws_manager {
+            Some(manager) => manager.lock().await.broadcast(message),
+            None => 0,
+        }
+    }
 }
 
-// 健康检查 - 不需要认证
-async fn health_check() -> AxumJson<ApiResponse<serde_json::Value>> {
-    AxumJson(ApiResponse {
-        success: true,
-        data: Some(serde_json::json!({
+/// 已绑定的监听器
+enum BoundListener {
+    Http(TcpListener),
+    Https(std::net::TcpListener, axum_server::tls_rustls::RustlsConfig),
+}
+
+impl BoundListener {
+    fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        match self {
+            BoundListener::Http(listener) => listener.local_addr(),
+            BoundListener::Https(listener, _) => listener.local_addr(),
+        }
+    }
+
+    fn describe(&self) -> String {
+        let scheme = match self {
+            BoundListener::Http(_) => "http",
+            BoundListener::Https(..) => "https",
+        };
+        match self.local_addr() {
+            Ok(addr) => format!("{}://{}", scheme, addr),
+            Err(_) => scheme.to_string(),
+        }
+    }
+}
+
+/// 按配置绑定监听地址，启用 TLS 时加载证书
+async fn bind_listener(config: &ListenerConfig) -> Result<BoundListener, String> {
+    let ip = config
+        .bind_addr
+        .parse::<std::net::IpAddr>()
+        .map_err(|_| format!("Invalid bind address '{}'", config.bind_addr))?;
+    let addr = SocketAddr::new(ip, config.port);
+
+    if !config.tls {
+        return TcpListener::bind(addr)
+            .await
+            .map(BoundListener::Http)
+            .map_err(|e| e.to_string());
+    }
+
+    let (Some(cert), Some(key)) = (&config.cert_path, &config.key_path) else {
+        return Err("TLS listener requires cert_path and key_path".to_string());
+    };
+    // 使用 ring 作为加密实现，重复安装时忽略错误
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    let tls = axum_server::tls_rustls::RustlsConfig::from_pem_file(cert, key)
+        .await
+        .map_err(|e| format!("Failed to load TLS certificate: {}", e))?;
+    let listener = std::net::TcpListener::bind(addr).map_err(|e| e.to_string())?;
+    listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+    Ok(BoundListener::Https(listener, tls))
+}
+
+/// 在单个监听器上运行服务，收到关闭通知后优雅退出
+async fn serve_listener(
+    listener: BoundListener,
+    app: Router,
+    mut shutdown: tokio::sync::watch::Receiver<bool>,
+) -> std::io::Result<()> {
+    let service = app.into_make_service_with_connect_info::<SocketAddr>();
+    match listener {
+        BoundListener::Http(listener) => {
+            axum::serve(listener, service)
+                .with_graceful_shutdown(async move {
+                    let _ = shutdown.changed().await;
+                })
+                .await
+        }
+        BoundListener::Https(listener, tls) => {
+            let handle = axum_server::Handle::new();
+            let shutdown_handle = handle.clone();
+            tokio::spawn(async move {
+                let _ = shutdown.changed().await;
+                shutdown_handle.graceful_shutdown(Some(Duration::from_secs(2)));
+            });
+            axum_server::from_tcp_rustls(listener, tls)
+                .handle(handle)
+                .serve(service)
+                .await
+        }
+    }
+}
+
+/// 解析命令名和参数：custom 命令的实际命令在第一个参数中，
+/// 命令名包含空格时（如 "ping 127.0.0.1"）拆分为命令名和参数
+pub(crate) fn resolve_command(
+    command: &str,
+    args: Option<Vec<String>>,
+) -> (String, Option<Vec<String>>) {
+    if command == "custom" {
+        if let Some(args) = &args {
+            if let Some(first_arg) = args.first() {
+                // 第一个参数可能包含完整命令（如 "ping 127.0.0.1"）
+                // 需要分割成命令名和参数
+                let parts: Vec<&str> = first_arg.split_whitespace().collect();
+                if let Some((first, rest)) = parts.split_first() {
+                    let cmd = first.to_string();
+                    let mut all_args: Vec<String> = rest.iter().map(|s| s.to_string()).collect();
+                    // 合并原有的其他 args（从第二个元素开始）
+                    let remaining_args: Vec<String> = args.iter().skip(1).cloned().collect();
+                    all_args.extend(remaining_args);
+                    (cmd, if all_args.is_empty() { None } else { Some(all_args) })
+                } else {
+                    (first_arg.clone(), None)
+                }
+            } else {
+                ("custom".to_string(), None)
+            }
+        } else {
+            ("custom".to_string(), None)
+        }
+    } else if command.contains(' ') {
+        // 如果命令名包含空格，分割成命令名和参数
+        let parts: Vec<&str> = command.split_whitespace().collect();
+        if let Some((first, rest)) = parts.split_first() {
+            let cmd = first.to_string();
+            let mut all_args: Vec<String> = rest.iter().map(|s| s.to_string()).collect();
+            // 合并原有的 args
+            if let Some(existing_args) = &args {
+                all_args.extend(existing_args.clone());
+            }
+            (cmd, if all_args.is_empty() { None } else { Some(all_args) })
+        } else {
+            (command.to_string(), args)
+        }
+    } else {
+        (command.to_string(), args)
+    }
+}
+
+/// 在阻塞线程池中执行命令，避免阻塞异步运行时（也使请求超时能够生效）
+pub(crate) async fn run_command(command: &str, args: Option<Vec<String>>) -> Result<CommandResult, String> {
+    let command = command.to_string();
+    tokio::task::spawn_blocking(move || {
+        let executor = crate::command::CommandExecutor::new();
+        executor.execute(&command, args.as_deref())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// 健康检查密钥请求头
+const HEALTH_SECRET_HEADER: &str = "x-health-secret";
+
+// 健康检查 - 不需要认证；配置了密钥时缺少或错误的请求与不存在的路径一样返回 404
+async fn health_check(
+    headers: HeaderMap,
+) -> Result<AxumJson<ApiResponse<serde_json::Value>>, StatusCode> {
+    let config = get_config();
+    if let Some(ref secret) = config.health_secret {
+        let provided = headers
+            .get(HEALTH_SECRET_HEADER)
+            .map(|v| v.as_bytes())
+            .unwrap_or_default();
+        // 逐字节比较全部内容，避免通过响应时间猜测密钥
+        let matches = provided.len() == secret.len()
+            && provided
+                .iter()
+                .zip(secret.as_bytes())
+                .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+                == 0;
+        if !matches {
+            return Err(StatusCode::NOT_FOUND);
+        }
+    }
+
+    let data = if config.stealth_health {
+        serde_json::json!({ "status": "healthy" })
+    } else {
+        serde_json::json!({
             "status": "healthy",
             "version": env!("CARGO_PKG_VERSION"),
             "service": "lan-device-manager"
-        })),
+        })
+    };
+    Ok(AxumJson(ApiResponse {
+        success: true,
+        data: Some(data),
+        error: None,
+        error_details: None,
+    }))
+}
+
+// 协议握手 - 无需认证，交换协议版本和时间
+async fn handshake_handler(
+    Json(req): Json<HandshakeRequest>,
+) -> AxumJson<ApiResponse<HandshakeResponse>> {
+    let ip = get_client_ip();
+    let server_time = chrono::Utc::now().timestamp_millis();
+    let clock_skew_ms = req.client_time - server_time;
+    let clock_skew_warning = clock_skew_ms.abs() > CLOCK_SKEW_WARN_MS;
+    let compatible = (MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&req.protocol_version);
+
+    log::info!(
+        "[Access] [{}] Handshake: protocol {}, client {}",
+        ip,
+        req.protocol_version,
+        req.client_version.as_deref().unwrap_or("unknown")
+    );
+    if clock_skew_warning {
+        log::warn!(
+            "[Access] [{}] Client clock differs from server by {} ms",
+            ip,
+            clock_skew_ms
+        );
+        log_to_ui(
+            "warn",
+            &format!("[{}] Client clock is off by {}s", ip, clock_skew_ms / 1000),
+        );
+    }
+    if !compatible {
+        log::warn!(
+            "[Access] [{}] Incompatible protocol version {} (supported {}-{})",
+            ip,
+            req.protocol_version,
+            MIN_PROTOCOL_VERSION,
+            PROTOCOL_VERSION
+        );
+    }
+
+    AxumJson(ApiResponse {
+        success: true,
+        data: Some(HandshakeResponse {
+            protocol_version: PROTOCOL_VERSION,
+            min_protocol_version: MIN_PROTOCOL_VERSION,
+            server_version: env!("CARGO_PKG_VERSION"),
+            server_time,
+            clock_skew_ms,
+            clock_skew_warning,
+            compatible,
+        }),
         error: None,
+        error_details: None,
     })
 }
 
@@ -441,28 +1441,94 @@ async fn check_auth_required(
             "message": if is_auth_required { "Password authentication required" } else { "No authentication required" }
         })),
         error: None,
+        error_details: None,
     })
 }
 
 // 获取认证挑战
 async fn get_challenge(
     State(state): State<AppState>,
+    // 挑战按来源IP限额，直接读取本次请求的 ClientIp，不依赖线程局部变量
+    Extension(ClientIp(ip)): Extension<ClientIp>,
     Json(_req): Json<ChallengeRequest>,
 ) -> Result<AxumJson<ApiResponse<ChallengeResponse>>, StatusCode> {
-    let ip = get_client_ip();
 
-    let challenge = state.auth_manager.generate_challenge();
+    let challenge = match state
+        .auth_manager
+        .generate_challenge(&crate::stats::ip_only(&ip))
+    {
+        Ok(challenge) => challenge,
+        Err(e) => {
+            log::warn!("[Auth] [{}] Challenge REJECTED: {}", ip, e);
+            log_to_ui("warn", &format!("[{}] Challenge REJECTED: {}", ip, e));
+            return Ok(AxumJson(ApiResponse {
+                success: false,
+                data: None,
+                error: Some(e),
+                error_details: None,
+            }));
+        }
+    };
 
     log::info!("[Auth] [{}] Challenge requested", ip);
     log_to_ui("info", &format!("[{}] Challenge requested", ip));
 
+    let (salt, iterations) = state.auth_manager.verifier_params().unzip();
+
     Ok(AxumJson(ApiResponse {
         success: true,
-        data: Some(ChallengeResponse { challenge }),
+        data: Some(ChallengeResponse {
+            challenge,
+            salt,
+            iterations,
+        }),
         error: None,
+        error_details: None,
     }))
 }
 
+/// 校验登录请求并签发令牌，按请求协商负载加密，记录登录结果并登记会话
+///
+/// REST 和 gRPC 登录共用，登记的会话在吊销（如加入黑名单）时随之结束
+pub fn login_session(
+    auth_manager: &AuthManager,
+    ip: &str,
+    req: &LoginRequest,
+) -> Result<AuthResponse, Box<dyn std::error::Error>> {
+    let mut result = match (req.paired, req.device_id.as_deref()) {
+        (true, Some(device_id)) => {
+            auth_manager.authenticate_paired(&req.challenge, &req.response, device_id)
+        }
+        (true, None) => Err("Device ID is required for paired login".into()),
+        (false, device_id) => auth_manager.authenticate(&req.challenge, &req.response, device_id),
+    };
+    if let (Ok(response), Some(PAYLOAD_CIPHER)) = (&mut result, req.encryption.as_deref()) {
+        match auth_manager.enable_payload_encryption(&response.token, &req.challenge) {
+            Ok(key_id) => {
+                response.encryption = Some(PAYLOAD_CIPHER.to_string());
+                response.key_id = Some(key_id);
+            }
+            Err(e) => log::warn!("[Auth] [{}] Payload encryption not enabled: {}", ip, e),
+        }
+    }
+
+    match result {
+        Ok(ref response) => {
+            crate::stats::record_auth(ip, AuthOutcome::LoginSuccess);
+            if let Some(claims) = auth_manager.decode_token(&response.token) {
+                crate::connections::api_login(
+                    &claims.jti,
+                    ip,
+                    Some(claims.identity()),
+                    response.expires_in,
+                );
+            }
+        }
+        Err(_) => crate::stats::record_auth(ip, AuthOutcome::LoginFailure),
+    }
+    result
+}
+
 // 登录
 async fn login(
     State(state): State<AppState>,
@@ -470,17 +1536,19 @@ async fn login(
 ) -> Result<AxumJson<ApiResponse<AuthResponse>>, StatusCode> {
     let ip = get_client_ip();
 
-    match state
-        .auth_manager
-        .authenticate(&req.challenge, &req.response, &req.password)
-    {
+    match login_session(&state.auth_manager, &ip, &req) {
         Ok(response) => {
-            log::info!("[Auth] [{}] Login SUCCESS", ip);
+            log::info!(
+                "[Auth] [{}] Login SUCCESS (device: {})",
+                ip,
+                req.device_id.as_deref().unwrap_or("unknown")
+            );
             log_to_ui("success", &format!("[{}] Login SUCCESS", ip));
             Ok(AxumJson(ApiResponse {
                 success: true,
                 data: Some(response),
                 error: None,
+                error_details: None,
             }))
         }
         Err(e) => {
@@ -490,6 +1558,75 @@ async fn login(
                 success: false,
                 data: None,
                 error: Some(e.to_string()),
+                error_details: None,
+            }))
+        }
+    }
+}
+
+// 开始 SPAKE2 配对，失败按登录失败计入，连续失败后该IP和设备ID被锁定
+async fn pair_start(
+    State(state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    Json(req): Json<PairStartRequest>,
+) -> Result<AxumJson<ApiResponse<PairStartResponse>>, StatusCode> {
+    let client = crate::stats::ip_only(&ip);
+    match state
+        .auth_manager
+        .start_pairing(&req.device_id, &client, &req.message)
+    {
+        Ok((pairing_id, message)) => {
+            log::info!("[Auth] [{}] Pairing started (device: {})", ip, req.device_id);
+            Ok(AxumJson(ApiResponse {
+                success: true,
+                data: Some(PairStartResponse {
+                    pairing_id,
+                    message,
+                }),
+                error: None,
+                error_details: None,
+            }))
+        }
+        Err(e) => {
+            log::warn!("[Auth] [{}] Pairing REJECTED: {}", ip, e);
+            crate::stats::record_auth(&ip, AuthOutcome::LoginFailure);
+            Ok(AxumJson(ApiResponse {
+                success: false,
+                data: None,
+                error: Some(e),
+                error_details: None,
+            }))
+        }
+    }
+}
+
+// 完成配对，密钥确认失败按登录失败计入
+async fn pair_confirm(
+    State(state): State<AppState>,
+    Json(req): Json<PairConfirmRequest>,
+) -> Result<AxumJson<ApiResponse<PairConfirmResponse>>, StatusCode> {
+    let ip = get_client_ip();
+
+    match state.auth_manager.confirm_pairing(&req.pairing_id, &req.proof) {
+        Ok((device_id, proof)) => {
+            log::info!("[Auth] [{}] Pairing SUCCESS (device: {})", ip, device_id);
+            log_to_ui("success", &format!("[{}] Pairing SUCCESS", ip));
+            Ok(AxumJson(ApiResponse {
+                success: true,
+                data: Some(PairConfirmResponse { proof }),
+                error: None,
+                error_details: None,
+            }))
+        }
+        Err(e) => {
+            log::warn!("[Auth] [{}] Pairing FAILED: {}", ip, e);
+            crate::stats::record_auth(&ip, AuthOutcome::LoginFailure);
+            log_to_ui("warn", &format!("[{}] Pairing FAILED: {}", ip, e));
+            Ok(AxumJson(ApiResponse {
+                success: false,
+                data: None,
+                error: Some(e),
+                error_details: None,
             }))
         }
     }
@@ -499,7 +1636,8 @@ async fn login(
 async fn get_system_info_handler(
     State(state): State<AppState>,
     Query(query): Query<TokenQuery>,
-) -> Result<AxumJson<ApiResponse<SystemInfo>>, StatusCode> {
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
     let ip = get_client_ip();
 
     // 检查是否设置了密码
@@ -516,11 +1654,13 @@ async fn get_system_info_handler(
                     "warn",
                     &format!("[{}] System info request denied: Token missing", ip),
                 );
-                return Ok(AxumJson(ApiResponse {
+                return Ok(AxumJson(ApiResponse::<SystemInfo> {
                     success: false,
                     data: None,
                     error: Some("Authentication required. Token missing.".to_string()),
-                }));
+                    error_details: None,
+                })
+                .into_response());
             }
         };
 
@@ -529,57 +1669,87 @@ async fn get_system_info_handler(
                 "[Access] [{}] System info request denied: Invalid token",
                 ip
             );
+            crate::stats::record_auth(&ip, AuthOutcome::TokenRejected);
             log_to_ui(
                 "warn",
                 &format!("[{}] System info request denied: Invalid token", ip),
             );
-            return Ok(AxumJson(ApiResponse {
+            return Ok(AxumJson(ApiResponse::<SystemInfo> {
                 success: false,
                 data: None,
                 error: Some("Invalid or expired token".to_string()),
-            }));
+                error_details: None,
+            })
+            .into_response());
         }
     }
 
     log::info!("[Access] [{}] System info requested", ip);
     log_to_ui("info", &format!("[{}] System info requested", ip));
 
-    // 检查缓存（缓存5分钟）
-    let cache_duration = Duration::from_secs(300);
-    {
+    // 静态信息按配置的 TTL 缓存，实时指标每次刷新
+    let cache_duration = Duration::from_secs(get_config().system_info_cache_ttl_secs);
+    let cached = {
         let cache = state.system_info_cache.lock().await;
-        if let Some((ref info, ref timestamp)) = *cache {
-            if timestamp.elapsed() < cache_duration {
-                // 缓存有效，直接返回
-                log::info!("[Access] [{}] System info served from cache", ip);
-                log_to_ui("info", &format!("[{}] System info served from cache", ip));
-                return Ok(AxumJson(ApiResponse {
-                    success: true,
-                    data: Some(info.clone()),
-                    error: None,
-                }));
+        match *cache {
+            Some((ref info, ref timestamp)) if timestamp.elapsed() < cache_duration => {
+                Some(info.clone())
             }
+            _ => None,
         }
-    }
-
-    // 缓存无效或过期，重新获取
-    match crate::command::get_system_info() {
-        Ok(info) => {
-            // 更新缓存
-            let mut cache = state.system_info_cache.lock().await;
-            *cache = Some((info.clone(), Instant::now()));
+    };
 
-            log::info!("[Access] [{}] System info retrieved and served", ip);
-            log_to_ui(
-                "info",
-                &format!("[{}] System info retrieved and served", ip),
-            );
+    let static_info = match cached {
+        Some(info) if !query.refresh.unwrap_or(false) => {
+            log::info!("[Access] [{}] Static system info served from cache", ip);
+            Ok(info)
+        }
+        _ => {
+            let result = tokio::task::spawn_blocking(crate::command::get_static_system_info)
+                .await
+                .unwrap_or_else(|e| Err(e.to_string()));
+            if let Ok(ref info) = result {
+                // 更新缓存
+                let mut cache = state.system_info_cache.lock().await;
+                *cache = Some((info.clone(), Instant::now()));
+            }
+            result
+        }
+    };
 
-            Ok(AxumJson(ApiResponse {
-                success: true,
-                data: Some(info),
-                error: None,
-            }))
+    match static_info {
+        Ok(info) => {
+            let metrics = tokio::task::spawn_blocking(crate::command::get_live_metrics)
+                .await
+                .map_err(|e| e.to_string());
+
+            match metrics {
+                Ok(metrics) => {
+                    log::info!("[Access] [{}] System info retrieved and served", ip);
+                    log_to_ui(
+                        "info",
+                        &format!("[{}] System info retrieved and served", ip),
+                    );
+
+                    let body = ApiResponse {
+                        success: true,
+                        data: Some(SystemInfo::from_parts(info, metrics)),
+                        error: None,
+                        error_details: None,
+                    };
+                    Ok(json_with_etag(&headers, &body))
+                }
+                Err(e) => {
+                    log::error!("[Access] [{}] Failed to get system metrics: {}", ip, e);
+                    Ok(AxumJson(ApiResponse::<SystemInfo> {
+                        success: false,
+                        data: None,
+                        error: Some(e),
+                        error_details: None,
+                    })
+                    .into_response())
+                }
+            }
         }
         Err(e) => {
             log::error!("[Access] [{}] Failed to get system info: {}", ip, e);
@@ -587,352 +1757,2200 @@ async fn get_system_info_handler(
                 "error",
                 &format!("[{}] Failed to get system info: {}", ip, e),
             );
-            Ok(AxumJson(ApiResponse {
+            Ok(AxumJson(ApiResponse::<SystemInfo> {
                 success: false,
                 data: None,
                 error: Some(e.to_string()),
-            }))
+                error_details: None,
+            })
+            .into_response())
         }
     }
 }
 
-// 关机
-async fn shutdown_handler(
+// 获取锁屏状态和用户空闲时间 - 需要认证
+async fn presence_handler(
     State(state): State<AppState>,
-    Json(req): Json<CommandRequest>,
-) -> Result<AxumJson<ApiResponse<CommandResult>>, StatusCode> {
+    Query(query): Query<TokenQuery>,
+) -> Result<AxumJson<ApiResponse<PresenceInfo>>, StatusCode> {
     let ip = get_client_ip();
 
-    if !state.auth_manager.verify_token(&req.token) {
-        log::warn!("[Command] [{}] Shutdown REJECTED: Invalid token", ip);
-        log_to_ui(
-            "warn",
-            &format!("[{}] Shutdown REJECTED: Invalid token", ip),
-        );
-        return Ok(AxumJson(ApiResponse {
+    if let Some(denied) = require_token(&state, &ip, query.token.as_deref(), "Presence request") {
+        return denied;
+    }
+
+    log::info!("[Access] [{}] Presence requested", ip);
+
+    match tokio::task::spawn_blocking(crate::command::get_presence).await {
+        Ok(presence) => Ok(AxumJson(ApiResponse {
+            success: true,
+            data: Some(presence),
+            error: None,
+            error_details: None,
+        })),
+        Err(e) => Ok(AxumJson(ApiResponse {
             success: false,
             data: None,
-            error: Some("Invalid or expired token".to_string()),
-        }));
+            error: Some(e.to_string()),
+            error_details: None,
+        })),
     }
+}
 
-    // 先记录调用（在命令执行前）
-    log::info!("[Command] [{}] Shutdown REQUEST", ip);
-    log_to_ui("info", &format!("[{}] Shutdown REQUEST", ip));
+// 获取 USB 和蓝牙外设列表 - 需要认证
+async fn peripherals_handler(
+    State(state): State<AppState>,
+    Query(query): Query<TokenQuery>,
+) -> Result<AxumJson<ApiResponse<PeripheralsInfo>>, StatusCode> {
+    let ip = get_client_ip();
 
-    let executor = crate::command::CommandExecutor::new();
-    match executor.execute("shutdown", req.args.as_deref()) {
-        Ok(result) => {
-            if result.success {
-                // 关机成功前先记录，因为系统可能立即关闭
-                log::info!("[Command] [{}] Shutdown SUCCESS", ip);
-                log_to_ui("success", &format!("[{}] Shutdown SUCCESS", ip));
-            } else {
-                log::error!("[Command] [{}] Shutdown FAILED: {}", ip, result.stderr);
-                log_to_ui(
-                    "error",
-                    &format!("[{}] Shutdown FAILED: {}", ip, result.stderr),
-                );
-            }
-            let error_msg = if result.success {
-                None
-            } else {
-                Some(result.stderr.clone())
+    if let Some(denied) =
+        require_token(&state, &ip, query.token.as_deref(), "Peripherals request")
+    {
+        return denied;
+    }
+
+    log::info!("[Access] [{}] Peripherals requested", ip);
+
+    match tokio::task::spawn_blocking(crate::peripherals::get_peripherals).await {
+        Ok(peripherals) => Ok(AxumJson(ApiResponse {
+            success: true,
+            data: Some(peripherals),
+            error: None,
+            error_details: None,
+        })),
+        Err(e) => Ok(AxumJson(ApiResponse {
+            success: false,
+            data: None,
+            error: Some(e.to_string()),
+            error_details: None,
+        })),
+    }
+}
+
+// 获取主机环境信息（域、时区、BIOS 等） - 需要认证
+async fn facts_handler(
+    State(state): State<AppState>,
+    Query(query): Query<TokenQuery>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let ip = get_client_ip();
+
+    if let Some(denied) =
+        require_token::<HostFacts>(&state, &ip, query.token.as_deref(), "Host facts request")
+    {
+        return Ok(denied.into_response());
+    }
+
+    log::info!("[Access] [{}] Host facts requested", ip);
+
+    let result = tokio::task::spawn_blocking(crate::facts::get_host_facts)
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+
+    match result {
+        Ok(facts) => {
+            let body = ApiResponse {
+                success: true,
+                data: Some(facts),
+                error: None,
+                error_details: None,
             };
-            Ok(AxumJson(ApiResponse {
-                success: result.success,
-                data: Some(result),
-                error: error_msg,
-            }))
+            Ok(json_with_etag(&headers, &body))
         }
         Err(e) => {
-            log::error!("[Command] [{}] Shutdown ERROR: {}", ip, e);
-            log_to_ui("error", &format!("[{}] Shutdown ERROR: {}", ip, e));
-            Ok(AxumJson(ApiResponse {
+            log::warn!("[Access] [{}] Host facts request failed: {}", ip, e);
+            Ok(AxumJson(ApiResponse::<HostFacts> {
                 success: false,
                 data: None,
-                error: Some(e.to_string()),
-            }))
+                error: Some(e),
+                error_details: None,
+            })
+            .into_response())
         }
     }
 }
 
-// 重启
-async fn restart_handler(
+// 列出显示器及支持的分辨率 - 需要认证
+async fn list_display_modes_handler(
     State(state): State<AppState>,
-    Json(req): Json<CommandRequest>,
-) -> Result<AxumJson<ApiResponse<CommandResult>>, StatusCode> {
+    Query(query): Query<TokenQuery>,
+) -> Result<AxumJson<ApiResponse<Vec<MonitorInfo>>>, StatusCode> {
     let ip = get_client_ip();
 
-    if !state.auth_manager.verify_token(&req.token) {
-        log::warn!("[Command] [{}] Restart REJECTED: Invalid token", ip);
-        log_to_ui("warn", &format!("[{}] Restart REJECTED: Invalid token", ip));
-        return Ok(AxumJson(ApiResponse {
-            success: false,
-            data: None,
-            error: Some("Invalid or expired token".to_string()),
-        }));
+    if let Some(denied) =
+        require_scope(&state, &ip, query.token.as_deref(), "display", "Display modes request")
+    {
+        return denied;
     }
 
-    log::info!("[Command] [{}] Restart REQUEST", ip);
-    log_to_ui("info", &format!("[{}] Restart REQUEST", ip));
+    log::info!("[Access] [{}] Display modes requested", ip);
 
-    let executor = crate::command::CommandExecutor::new();
-    match executor.execute("restart", req.args.as_deref()) {
-        Ok(result) => {
-            if result.success {
-                log::info!("[Command] [{}] Restart SUCCESS", ip);
-                log_to_ui("success", &format!("[{}] Restart SUCCESS", ip));
-            } else {
-                log::error!("[Command] [{}] Restart FAILED: {}", ip, result.stderr);
-                log_to_ui(
-                    "error",
-                    &format!("[{}] Restart FAILED: {}", ip, result.stderr),
-                );
+    let result = tokio::task::spawn_blocking(crate::display::list_monitors)
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+
+    match result {
+        Ok(monitors) => Ok(AxumJson(ApiResponse {
+            success: true,
+            data: Some(monitors),
+            error: None,
+            error_details: None,
+        })),
+        Err(e) => Ok(AxumJson(ApiResponse {
+            success: false,
+            data: None,
+            error: Some(e),
+            error_details: None,
+        })),
+    }
+}
+
+// 切换显示器分辨率或主显示器 - 需要认证
+async fn set_display_mode_handler(
+    State(state): State<AppState>,
+    Json(req): Json<DisplayModeRequest>,
+) -> Result<AxumJson<ApiResponse<Vec<MonitorInfo>>>, StatusCode> {
+    let ip = get_client_ip();
+
+    if let Some(denied) =
+        require_session_scope(&state, &ip, Some(&req.token), "display", "Display change")
+    {
+        return denied;
+    }
+
+    log::info!("[Command] [{}] Display change '{}' REQUEST", ip, req.monitor);
+    log_to_ui(
+        "info",
+        &format!("[{}] Display change '{}' REQUEST", ip, req.monitor),
+    );
+
+    let result = tokio::task::spawn_blocking(move || {
+        match (req.width, req.height) {
+            (Some(width), Some(height)) => {
+                crate::display::set_mode(&req.monitor, width, height, req.refresh_rate)?
             }
-            let error_msg = if result.success {
-                None
-            } else {
-                Some(result.stderr.clone())
-            };
+            (None, None) if req.primary => {}
+            _ => return Err("Both width and height are required".to_string()),
+        }
+        if req.primary {
+            crate::display::set_primary(&req.monitor)?;
+        }
+        // 返回修改后的显示器状态
+        crate::display::list_monitors()
+    })
+    .await
+    .unwrap_or_else(|e| Err(e.to_string()));
+
+    match result {
+        Ok(monitors) => {
+            log_to_ui("success", &format!("[{}] Display change SUCCESS", ip));
             Ok(AxumJson(ApiResponse {
-                success: result.success,
-                data: Some(result),
-                error: error_msg,
+                success: true,
+                data: Some(monitors),
+                error: None,
+                error_details: None,
             }))
         }
         Err(e) => {
-            log::error!("[Command] [{}] Restart ERROR: {}", ip, e);
-            log_to_ui("error", &format!("[{}] Restart ERROR: {}", ip, e));
+            log::error!("[Command] [{}] Display change ERROR: {}", ip, e);
+            log_to_ui("error", &format!("[{}] Display change ERROR: {}", ip, e));
             Ok(AxumJson(ApiResponse {
                 success: false,
                 data: None,
-                error: Some(e.to_string()),
+                error: Some(e),
+                error_details: None,
             }))
         }
     }
 }
 
-// 睡眠
-async fn sleep_handler(
+// 列出音频播放设备 - 需要认证
+async fn list_audio_devices_handler(
     State(state): State<AppState>,
-    Json(req): Json<CommandRequest>,
-) -> Result<AxumJson<ApiResponse<CommandResult>>, StatusCode> {
+    Query(query): Query<TokenQuery>,
+) -> Result<AxumJson<ApiResponse<Vec<AudioDevice>>>, StatusCode> {
     let ip = get_client_ip();
 
-    if !state.auth_manager.verify_token(&req.token) {
-        log::warn!("[Command] [{}] Sleep REJECTED: Invalid token", ip);
-        log_to_ui("warn", &format!("[{}] Sleep REJECTED: Invalid token", ip));
-        return Ok(AxumJson(ApiResponse {
+    if let Some(denied) =
+        require_scope(&state, &ip, query.token.as_deref(), "media", "Audio devices request")
+    {
+        return denied;
+    }
+
+    log::info!("[Access] [{}] Audio devices requested", ip);
+
+    let result = tokio::task::spawn_blocking(crate::media::list_playback_devices)
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+
+    match result {
+        Ok(devices) => Ok(AxumJson(ApiResponse {
+            success: true,
+            data: Some(devices),
+            error: None,
+            error_details: None,
+        })),
+        Err(e) => Ok(AxumJson(ApiResponse {
             success: false,
             data: None,
-            error: Some("Invalid or expired token".to_string()),
-        }));
+            error: Some(e),
+            error_details: None,
+        })),
     }
+}
 
-    log::info!("[Command] [{}] Sleep REQUEST", ip);
-    log_to_ui("info", &format!("[{}] Sleep REQUEST", ip));
+// 设置默认音频播放设备 - 需要认证
+async fn set_audio_device_handler(
+    State(state): State<AppState>,
+    Json(req): Json<AudioDeviceRequest>,
+) -> Result<AxumJson<ApiResponse<Vec<AudioDevice>>>, StatusCode> {
+    let ip = get_client_ip();
 
-    let executor = crate::command::CommandExecutor::new();
-    match executor.execute("sleep", None) {
-        Ok(result) => {
-            if result.success {
-                log::info!("[Command] [{}] Sleep SUCCESS", ip);
-                log_to_ui("success", &format!("[{}] Sleep SUCCESS", ip));
-            } else {
-                log::error!("[Command] [{}] Sleep FAILED: {}", ip, result.stderr);
-                log_to_ui(
-                    "error",
-                    &format!("[{}] Sleep FAILED: {}", ip, result.stderr),
-                );
-            }
-            let error_msg = if result.success {
-                None
-            } else {
-                Some(result.stderr.clone())
-            };
+    if let Some(denied) =
+        require_session_scope(&state, &ip, Some(&req.token), "media", "Set audio device")
+    {
+        return denied;
+    }
+
+    log::info!("[Command] [{}] Set audio device '{}' REQUEST", ip, req.device_id);
+    log_to_ui("info", &format!("[{}] Set audio device REQUEST", ip));
+
+    let result = tokio::task::spawn_blocking(move || {
+        crate::media::set_default_playback_device(&req.device_id)?;
+        crate::media::list_playback_devices()
+    })
+    .await
+    .unwrap_or_else(|e| Err(e.to_string()));
+
+    match result {
+        Ok(devices) => {
+            log_to_ui("success", &format!("[{}] Set audio device SUCCESS", ip));
             Ok(AxumJson(ApiResponse {
-                success: result.success,
-                data: Some(result),
-                error: error_msg,
+                success: true,
+                data: Some(devices),
+                error: None,
+                error_details: None,
             }))
         }
         Err(e) => {
-            log::error!("[Command] [{}] Sleep ERROR: {}", ip, e);
-            log_to_ui("error", &format!("[{}] Sleep ERROR: {}", ip, e));
+            log::error!("[Command] [{}] Set audio device ERROR: {}", ip, e);
+            log_to_ui("error", &format!("[{}] Set audio device ERROR: {}", ip, e));
             Ok(AxumJson(ApiResponse {
                 success: false,
                 data: None,
-                error: Some(e.to_string()),
+                error: Some(e),
+                error_details: None,
             }))
         }
     }
 }
 
-// 锁屏
-async fn lock_handler(
+// 列出已登录的用户会话 - 需要认证
+async fn list_sessions_handler(
     State(state): State<AppState>,
-    Json(req): Json<CommandRequest>,
-) -> Result<AxumJson<ApiResponse<CommandResult>>, StatusCode> {
+    Query(query): Query<TokenQuery>,
+) -> Result<AxumJson<ApiResponse<Vec<SessionInfo>>>, StatusCode> {
     let ip = get_client_ip();
 
-    if !state.auth_manager.verify_token(&req.token) {
-        log::warn!("[Command] [{}] Lock REJECTED: Invalid token", ip);
-        log_to_ui("warn", &format!("[{}] Lock REJECTED: Invalid token", ip));
-        return Ok(AxumJson(ApiResponse {
+    if let Some(denied) =
+        require_scope(&state, &ip, query.token.as_deref(), "sessions", "Sessions request")
+    {
+        return denied;
+    }
+
+    log::info!("[Access] [{}] Sessions requested", ip);
+
+    let result = tokio::task::spawn_blocking(crate::sessions::list_sessions)
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+
+    match result {
+        Ok(sessions) => Ok(AxumJson(ApiResponse {
+            success: true,
+            data: Some(sessions),
+            error: None,
+            error_details: None,
+        })),
+        Err(e) => Ok(AxumJson(ApiResponse {
             success: false,
             data: None,
-            error: Some("Invalid or expired token".to_string()),
-        }));
+            error: Some(e),
+            error_details: None,
+        })),
     }
+}
 
-    log::info!("[Command] [{}] Lock REQUEST", ip);
-    log_to_ui("info", &format!("[{}] Lock REQUEST", ip));
+// 锁定、断开或注销指定会话 - 需要认证
+async fn session_action_handler(
+    State(state): State<AppState>,
+    Json(req): Json<SessionActionRequest>,
+) -> Result<AxumJson<ApiResponse<String>>, StatusCode> {
+    let ip = get_client_ip();
 
-    let executor = crate::command::CommandExecutor::new();
-    match executor.execute("lock", None) {
-        Ok(result) => {
-            if result.success {
-                log::info!("[Command] [{}] Lock SUCCESS", ip);
-                log_to_ui("success", &format!("[{}] Lock SUCCESS", ip));
-            } else {
-                log::error!("[Command] [{}] Lock FAILED: {}", ip, result.stderr);
-                log_to_ui("error", &format!("[{}] Lock FAILED: {}", ip, result.stderr));
-            }
-            let error_msg = if result.success {
-                None
-            } else {
-                Some(result.stderr.clone())
-            };
+    if let Some(denied) =
+        require_session_scope(&state, &ip, Some(&req.token), "sessions", "Session action")
+    {
+        return denied;
+    }
+
+    let action = match SessionAction::parse(&req.action) {
+        Ok(action) => action,
+        Err(e) => {
+            return Ok(AxumJson(ApiResponse {
+                success: false,
+                data: None,
+                error: Some(e),
+                error_details: None,
+            }))
+        }
+    };
+
+    log::info!(
+        "[Command] [{}] Session {} {} REQUEST",
+        ip,
+        req.session_id,
+        req.action
+    );
+    log_to_ui(
+        "info",
+        &format!("[{}] Session {} {} REQUEST", ip, req.session_id, req.action),
+    );
+
+    let session_id = req.session_id;
+    let result =
+        tokio::task::spawn_blocking(move || crate::sessions::session_action(session_id, action))
+            .await
+            .unwrap_or_else(|e| Err(e.to_string()));
+
+    match result {
+        Ok(()) => {
+            log_to_ui("success", &format!("[{}] Session {} {} SUCCESS", ip, session_id, req.action));
             Ok(AxumJson(ApiResponse {
-                success: result.success,
-                data: Some(result),
-                error: error_msg,
+                success: true,
+                data: Some(format!("Session {} {} succeeded", session_id, req.action)),
+                error: None,
+                error_details: None,
             }))
         }
         Err(e) => {
-            log::error!("[Command] [{}] Lock ERROR: {}", ip, e);
-            log_to_ui("error", &format!("[{}] Lock ERROR: {}", ip, e));
+            log::error!("[Command] [{}] Session action ERROR: {}", ip, e);
+            log_to_ui("error", &format!("[{}] Session action ERROR: {}", ip, e));
             Ok(AxumJson(ApiResponse {
                 success: false,
                 data: None,
-                error: Some(e.to_string()),
+                error: Some(e),
+                error_details: None,
             }))
         }
     }
 }
 
-// 执行命令
-async fn execute_command_handler(
+// 获取磁盘 SMART 健康状态 - 需要认证
+async fn disks_health_handler(
     State(state): State<AppState>,
-    Json(req): Json<CommandRequest>,
-) -> Result<AxumJson<ApiResponse<CommandResult>>, StatusCode> {
+    Query(query): Query<TokenQuery>,
+) -> Result<AxumJson<ApiResponse<DisksHealthReport>>, StatusCode> {
     let ip = get_client_ip();
 
-    if !state.auth_manager.verify_token(&req.token) {
-        log::warn!("[Command] [{}] Execute REJECTED: Invalid token", ip);
-        log_to_ui("warn", &format!("[{}] Execute REJECTED: Invalid token", ip));
-        return Ok(AxumJson(ApiResponse {
-            success: false,
-            data: None,
-            error: Some("Invalid or expired token".to_string()),
-        }));
+    if let Some(denied) =
+        require_token(&state, &ip, query.token.as_deref(), "Disk health request")
+    {
+        return denied;
     }
 
-    // 处理 custom 命令类型：将实际的命令名称从 args 中提取出来
-    // 同时处理命令名包含空格的情况（如 "ping 127.0.0.1"）
-    let (actual_command, actual_args) = if req.command == "custom" {
-        if let Some(args) = &req.args {
-            if let Some(first_arg) = args.first() {
-                // 第一个参数可能包含完整命令（如 "ping 127.0.0.1"）
-                // 需要分割成命令名和参数
-                let parts: Vec<&str> = first_arg.split_whitespace().collect();
-                if let Some((first, rest)) = parts.split_first() {
-                    let cmd = first.to_string();
-                    let mut all_args: Vec<String> = rest.iter().map(|s| s.to_string()).collect();
-                    // 合并原有的其他 args（从第二个元素开始）
-                    let remaining_args: Vec<String> = args.iter().skip(1).cloned().collect();
-                    all_args.extend(remaining_args);
-                    (cmd, if all_args.is_empty() { None } else { Some(all_args) })
-                } else {
-                    (first_arg.clone(), None)
-                }
-            } else {
-                ("custom".to_string(), None)
-            }
-        } else {
-            ("custom".to_string(), None)
-        }
-    } else if req.command.contains(' ') {
-        // 如果命令名包含空格，分割成命令名和参数
-        let parts: Vec<&str> = req.command.split_whitespace().collect();
-        if let Some((first, rest)) = parts.split_first() {
-            let cmd = first.to_string();
-            let mut all_args: Vec<String> = rest.iter().map(|s| s.to_string()).collect();
-            // 合并原有的 args
-            if let Some(existing_args) = &req.args {
-                all_args.extend(existing_args.clone());
-            }
-            (cmd, if all_args.is_empty() { None } else { Some(all_args) })
-        } else {
-            (req.command.clone(), req.args.clone())
-        }
-    } else {
-        (req.command.clone(), req.args.clone())
-    };
+    log::info!("[Access] [{}] Disk health requested", ip);
 
-    log::info!("[Command] [{}] Execute '{}' REQUEST", ip, actual_command);
-    log_to_ui(
-        "info",
-        &format!("[{}] Execute '{}' REQUEST", ip, actual_command),
-    );
+    let result = tokio::task::spawn_blocking(crate::disks::get_disks_health)
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
 
-    let executor = crate::command::CommandExecutor::new();
-    match executor.execute(&actual_command, actual_args.as_deref()) {
-        Ok(result) => {
-            if result.success {
-                log::info!("[Command] [{}] Execute '{}' SUCCESS", ip, actual_command);
-                log_to_ui(
-                    "success",
-                    &format!("[{}] Execute '{}' SUCCESS", ip, actual_command),
-                );
-            } else {
-                log::error!(
-                    "[Command] [{}] Execute '{}' FAILED: {}",
-                    ip,
-                    actual_command,
-                    result.stderr
-                );
-                log_to_ui(
-                    "error",
-                    &format!(
-                        "[{}] Execute '{}' FAILED: {}",
-                        ip, actual_command, result.stderr
-                    ),
-                );
+    match result {
+        Ok(report) => {
+            if !report.all_passed {
+                log_to_ui("warn", &format!("[{}] Disk health check reported failing disks", ip));
             }
-            let error_msg = if result.success {
-                None
-            } else {
-                Some(result.stderr.clone())
-            };
             Ok(AxumJson(ApiResponse {
-                success: result.success,
-                data: Some(result),
-                error: error_msg,
+                success: true,
+                data: Some(report),
+                error: None,
+                error_details: None,
             }))
         }
         Err(e) => {
-            log::error!("[Command] [{}] Execute '{}' ERROR: {}", ip, actual_command, e);
-            log_to_ui(
-                "error",
-                &format!("[{}] Execute '{}' ERROR: {}", ip, actual_command, e),
-            );
+            log::warn!("[Access] [{}] Failed to get disk health: {}", ip, e);
             Ok(AxumJson(ApiResponse {
                 success: false,
                 data: None,
-                error: Some(e.to_string()),
+                error: Some(e),
+                error_details: None,
             }))
         }
     }
 }
+
+// 获取 Windows Update 状态 - 需要认证
+async fn updates_status_handler(
+    State(state): State<AppState>,
+    Query(query): Query<TokenQuery>,
+) -> Result<AxumJson<ApiResponse<UpdateState>>, StatusCode> {
+    let ip = get_client_ip();
+
+    if let Some(denied) =
+        require_scope(&state, &ip, query.token.as_deref(), "updates", "Update status request")
+    {
+        return denied;
+    }
+
+    Ok(AxumJson(ApiResponse {
+        success: true,
+        data: Some(crate::updates::get_state()),
+        error: None,
+        error_details: None,
+    }))
+}
+
+// 在后台扫描 Windows 更新
+async fn updates_scan_handler(
+    State(state): State<AppState>,
+    Json(req): Json<TokenRequest>,
+) -> Result<AxumJson<ApiResponse<UpdateState>>, StatusCode> {
+    update_operation(&state, &req.token, "scan", crate::updates::start_scan)
+}
+
+// 在后台下载并安装 Windows 更新
+async fn updates_install_handler(
+    State(state): State<AppState>,
+    Json(req): Json<TokenRequest>,
+) -> Result<AxumJson<ApiResponse<UpdateState>>, StatusCode> {
+    update_operation(&state, &req.token, "install", crate::updates::start_install)
+}
+
+fn update_operation(
+    state: &AppState,
+    token: &str,
+    operation: &str,
+    start: fn() -> Result<(), String>,
+) -> Result<AxumJson<ApiResponse<UpdateState>>, StatusCode> {
+    let ip = get_client_ip();
+
+    let action = format!("Update {}", operation);
+    if let Some(denied) = require_session_scope(state, &ip, Some(token), "updates", &action) {
+        return denied;
+    }
+
+    log::info!("[Command] [{}] Update {} REQUEST", ip, operation);
+    log_to_ui("info", &format!("[{}] Update {} REQUEST", ip, operation));
+
+    match start() {
+        Ok(()) => Ok(AxumJson(ApiResponse {
+            success: true,
+            data: Some(crate::updates::get_state()),
+            error: None,
+            error_details: None,
+        })),
+        Err(e) => {
+            log::warn!("[Command] [{}] Update {} FAILED: {}", ip, operation, e);
+            log_to_ui("error", &format!("[{}] Update {} FAILED: {}", ip, operation, e));
+            Ok(AxumJson(ApiResponse {
+                success: false,
+                data: None,
+                error: Some(e),
+                error_details: None,
+            }))
+        }
+    }
+}
+
+// 获取专注模式状态 - 需要认证
+async fn get_focus_handler(
+    State(state): State<AppState>,
+    Query(query): Query<TokenQuery>,
+) -> Result<AxumJson<ApiResponse<FocusStatus>>, StatusCode> {
+    let ip = get_client_ip();
+
+    if let Some(denied) =
+        require_scope(&state, &ip, query.token.as_deref(), "power", "Focus status request")
+    {
+        return denied;
+    }
+
+    Ok(AxumJson(ApiResponse {
+        success: true,
+        data: Some(crate::power::get_focus_status()),
+        error: None,
+        error_details: None,
+    }))
+}
+
+// 开启/关闭专注模式
+async fn set_focus_handler(
+    State(state): State<AppState>,
+    Json(req): Json<FocusRequest>,
+) -> Result<AxumJson<ApiResponse<FocusStatus>>, StatusCode> {
+    let ip = get_client_ip();
+
+    if let Some(denied) =
+        require_session_scope(&state, &ip, Some(&req.token), "power", "Focus mode")
+    {
+        return denied;
+    }
+
+    let action = if req.enabled { "ON" } else { "OFF" };
+    log::info!("[Command] [{}] Focus mode {} REQUEST", ip, action);
+    log_to_ui("info", &format!("[{}] Focus mode {} REQUEST", ip, action));
+
+    let enabled = req.enabled;
+    let result = tokio::task::spawn_blocking(move || crate::power::set_focus_mode(enabled))
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+
+    match result {
+        Ok(status) => {
+            log_to_ui("success", &format!("[{}] Focus mode {} SUCCESS", ip, action));
+            crate::events::publish(WsMessage::StatusChanged {
+                field: "focus_mode".to_string(),
+                value: serde_json::json!(enabled),
+            });
+            Ok(AxumJson(ApiResponse {
+                success: true,
+                data: Some(status),
+                error: None,
+                error_details: None,
+            }))
+        }
+        Err(e) => {
+            log::error!("[Command] [{}] Focus mode {} ERROR: {}", ip, action, e);
+            log_to_ui("error", &format!("[{}] Focus mode {} ERROR: {}", ip, action, e));
+            Ok(AxumJson(ApiResponse {
+                success: false,
+                data: None,
+                error: Some(e),
+                error_details: None,
+            }))
+        }
+    }
+}
+
+// 列出当前的防睡眠锁 - 需要认证
+async fn list_inhibitors_handler(
+    State(state): State<AppState>,
+    Query(query): Query<TokenQuery>,
+) -> Result<AxumJson<ApiResponse<Vec<InhibitorInfo>>>, StatusCode> {
+    let ip = get_client_ip();
+
+    if let Some(denied) =
+        require_scope(&state, &ip, query.token.as_deref(), "power", "Inhibitor list")
+    {
+        return denied;
+    }
+
+    Ok(AxumJson(ApiResponse {
+        success: true,
+        data: Some(crate::power::list_inhibitors()),
+        error: None,
+        error_details: None,
+    }))
+}
+
+// 申请防睡眠锁
+async fn add_inhibitor_handler(
+    State(state): State<AppState>,
+    Json(req): Json<InhibitRequest>,
+) -> Result<AxumJson<ApiResponse<InhibitorInfo>>, StatusCode> {
+    let ip = get_client_ip();
+
+    if let Some(denied) =
+        require_session_scope(&state, &ip, Some(&req.token), "power", "Keep awake")
+    {
+        return denied;
+    }
+
+    let reason = req
+        .reason
+        .unwrap_or_else(|| "Remote keep awake request".to_string());
+    log::info!(
+        "[Command] [{}] Keep awake {}s REQUEST: {}",
+        ip,
+        req.duration_secs,
+        reason
+    );
+    log_to_ui(
+        "info",
+        &format!("[{}] Keep awake {}s REQUEST", ip, req.duration_secs),
+    );
+
+    let client = crate::stats::ip_only(&ip);
+    let result = tokio::task::spawn_blocking(move || {
+        crate::power::add_inhibitor(&reason, &client, req.duration_secs, req.keep_display)
+    })
+    .await
+    .unwrap_or_else(|e| Err(e.to_string()));
+
+    match result {
+        Ok(info) => {
+            log_to_ui("success", &format!("[{}] Keep awake SUCCESS", ip));
+            Ok(AxumJson(ApiResponse {
+                success: true,
+                data: Some(info),
+                error: None,
+                error_details: None,
+            }))
+        }
+        Err(e) => {
+            log::error!("[Command] [{}] Keep awake ERROR: {}", ip, e);
+            log_to_ui("error", &format!("[{}] Keep awake ERROR: {}", ip, e));
+            Ok(AxumJson(ApiResponse {
+                success: false,
+                data: None,
+                error: Some(e),
+                error_details: None,
+            }))
+        }
+    }
+}
+
+// 释放防睡眠锁 - 需要认证
+async fn release_inhibitor_handler(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<TokenQuery>,
+) -> Result<AxumJson<ApiResponse<bool>>, StatusCode> {
+    let ip = get_client_ip();
+
+    if let Some(denied) =
+        require_session_scope(&state, &ip, query.token.as_deref(), "power", "Release keep awake")
+    {
+        return denied;
+    }
+
+    let released = crate::power::release_inhibitor(&id);
+    log::info!("[Command] [{}] Release keep awake {}: {}", ip, id, released);
+    log_to_ui("info", &format!("[{}] Release keep awake {}", ip, id));
+
+    Ok(AxumJson(ApiResponse {
+        success: released,
+        data: Some(released),
+        error: if released {
+            None
+        } else {
+            Some("Inhibitor not found".to_string())
+        },
+        error_details: None,
+    }))
+}
+
+// 在主机上打开 URL
+async fn open_url_handler(
+    State(state): State<AppState>,
+    Json(req): Json<OpenUrlRequest>,
+) -> Result<AxumJson<ApiResponse<()>>, StatusCode> {
+    let ip = get_client_ip();
+
+    if let Some(denied) = require_session_scope(&state, &ip, Some(&req.token), "apps", "Open URL") {
+        return denied;
+    }
+
+    log::info!("[Command] [{}] Open URL '{}' REQUEST", ip, req.url);
+    log_to_ui("info", &format!("[{}] Open URL '{}' REQUEST", ip, req.url));
+
+    match crate::apps::open_url(&req.url) {
+        Ok(()) => {
+            log_to_ui("success", &format!("[{}] Open URL SUCCESS", ip));
+            Ok(AxumJson(ApiResponse {
+                success: true,
+                data: Some(()),
+                error: None,
+                error_details: None,
+            }))
+        }
+        Err(e) => {
+            log::warn!("[Command] [{}] Open URL FAILED: {}", ip, e);
+            log_to_ui("error", &format!("[{}] Open URL FAILED: {}", ip, e));
+            Ok(AxumJson(ApiResponse {
+                success: false,
+                data: None,
+                error: Some(e),
+                error_details: None,
+            }))
+        }
+    }
+}
+
+// 在主机上打开共享目录中的文件
+async fn open_path_handler(
+    State(state): State<AppState>,
+    Json(req): Json<OpenPathRequest>,
+) -> Result<AxumJson<ApiResponse<()>>, StatusCode> {
+    let ip = get_client_ip();
+
+    if let Some(denied) =
+        require_session_scope(&state, &ip, Some(&req.token), "apps", "Open path")
+    {
+        return denied;
+    }
+    // 打开文件会启动关联的程序，需要在命令白名单中单独启用
+    let decision = check_command(&state.auth_manager, &ip, &req.token, "open-file");
+    if !decision.allowed {
+        return command_denied(&ip, "Open path", decision);
+    }
+
+    log::info!("[Command] [{}] Open path '{}' REQUEST", ip, req.path);
+    log_to_ui("info", &format!("[{}] Open path '{}' REQUEST", ip, req.path));
+
+    let path = req.path.clone();
+    let result = tokio::task::spawn_blocking(move || crate::apps::open_shared_path(&path))
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+
+    match result {
+        Ok(()) => {
+            log_to_ui("success", &format!("[{}] Open path SUCCESS", ip));
+            Ok(AxumJson(ApiResponse {
+                success: true,
+                data: Some(()),
+                error: None,
+                error_details: None,
+            }))
+        }
+        Err(e) => {
+            log::warn!("[Command] [{}] Open path FAILED: {}", ip, e);
+            log_to_ui("error", &format!("[{}] Open path FAILED: {}", ip, e));
+            Ok(AxumJson(ApiResponse {
+                success: false,
+                data: None,
+                error: Some(e),
+                error_details: None,
+            }))
+        }
+    }
+}
+
+// 列出打印机 - 需要认证
+async fn list_printers_handler(
+    State(state): State<AppState>,
+    Query(query): Query<TokenQuery>,
+) -> Result<AxumJson<ApiResponse<Vec<PrinterInfo>>>, StatusCode> {
+    let ip = get_client_ip();
+
+    if let Some(denied) =
+        require_scope(&state, &ip, query.token.as_deref(), "printers", "Printer list")
+    {
+        return denied;
+    }
+
+    log::info!("[Access] [{}] Printer list requested", ip);
+
+    let result = tokio::task::spawn_blocking(crate::printers::list_printers)
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+
+    match result {
+        Ok(printers) => Ok(AxumJson(ApiResponse {
+            success: true,
+            data: Some(printers),
+            error: None,
+            error_details: None,
+        })),
+        Err(e) => {
+            log::error!("[Access] [{}] Failed to list printers: {}", ip, e);
+            Ok(AxumJson(ApiResponse {
+                success: false,
+                data: None,
+                error: Some(e),
+                error_details: None,
+            }))
+        }
+    }
+}
+
+// 打印上传的文件或共享目录中的文件
+async fn print_handler(
+    State(state): State<AppState>,
+    Query(query): Query<PrintQuery>,
+    body: axum::body::Bytes,
+) -> Result<AxumJson<ApiResponse<()>>, StatusCode> {
+    let ip = get_client_ip();
+
+    if let Some(denied) =
+        require_session_scope(&state, &ip, query.token.as_deref(), "printers", "Print")
+    {
+        return denied;
+    }
+    // 打印共享目录中的文件还需要文件访问权限
+    if query.path.is_some()
+        && !client_allowed(&state.auth_manager, query.token.as_deref(), |p| {
+            p.allows_scope("files")
+        })
+    {
+        return permission_denied(&ip, "Print");
+    }
+
+    let target = query
+        .path
+        .clone()
+        .or_else(|| query.filename.clone())
+        .unwrap_or_else(|| "upload".to_string());
+    log::info!("[Command] [{}] Print '{}' REQUEST", ip, target);
+    log_to_ui("info", &format!("[{}] Print '{}' REQUEST", ip, target));
+
+    let result = tokio::task::spawn_blocking(move || {
+        let path = match &query.path {
+            Some(path) => crate::files::resolve_shared_path(path)?,
+            None => {
+                if body.is_empty() {
+                    return Err("No file uploaded".to_string());
+                }
+                let filename = query.filename.as_deref().unwrap_or("document.pdf");
+                crate::printers::save_upload(filename, &body)?
+            }
+        };
+        crate::printers::print_file(&path, query.printer.as_deref())
+    })
+    .await
+    .unwrap_or_else(|e| Err(e.to_string()));
+
+    match result {
+        Ok(()) => {
+            log::info!("[Command] [{}] Print '{}' SUCCESS", ip, target);
+            log_to_ui("success", &format!("[{}] Print '{}' SUCCESS", ip, target));
+            Ok(AxumJson(ApiResponse {
+                success: true,
+                data: Some(()),
+                error: None,
+                error_details: None,
+            }))
+        }
+        Err(e) => {
+            log::error!("[Command] [{}] Print '{}' FAILED: {}", ip, target, e);
+            log_to_ui("error", &format!("[{}] Print '{}' FAILED: {}", ip, target, e));
+            Ok(AxumJson(ApiResponse {
+                success: false,
+                data: None,
+                error: Some(e),
+                error_details: None,
+            }))
+        }
+    }
+}
+
+// 关机
+async fn shutdown_handler(
+    State(state): State<AppState>,
+    Json(req): Json<CommandRequest>,
+) -> Result<AxumJson<ApiResponse<CommandResult>>, StatusCode> {
+    let ip = get_client_ip();
+
+    if let Some(denied) = require_session(&state, &ip, Some(&req.token), "Shutdown") {
+        return denied;
+    }
+
+    let decision = check_command(&state.auth_manager, &ip, &req.token, "shutdown");
+    if !decision.allowed {
+        return command_denied(&ip, "Shutdown", decision);
+    }
+
+    // 先记录调用（在命令执行前）
+    log::info!("[Command] [{}] Shutdown REQUEST", ip);
+    log_to_ui("info", &format!("[{}] Shutdown REQUEST", ip));
+
+    match run_command("shutdown", req.args.clone()).await {
+        Ok(result) => {
+            if result.success {
+                // 关机成功前先记录，因为系统可能立即关闭
+                log::info!("[Command] [{}] Shutdown SUCCESS", ip);
+                log_to_ui("success", &format!("[{}] Shutdown SUCCESS", ip));
+            } else {
+                log::error!("[Command] [{}] Shutdown FAILED: {}", ip, result.stderr);
+                log_to_ui(
+                    "error",
+                    &format!("[{}] Shutdown FAILED: {}", ip, result.stderr),
+                );
+            }
+            let error_msg = if result.success {
+                None
+            } else {
+                Some(result.stderr.clone())
+            };
+            Ok(AxumJson(ApiResponse {
+                success: result.success,
+                data: Some(result),
+                error: error_msg,
+                error_details: None,
+            }))
+        }
+        Err(e) => {
+            log::error!("[Command] [{}] Shutdown ERROR: {}", ip, e);
+            log_to_ui("error", &format!("[{}] Shutdown ERROR: {}", ip, e));
+            Ok(AxumJson(ApiResponse {
+                success: false,
+                data: None,
+                error: Some(e.to_string()),
+                error_details: None,
+            }))
+        }
+    }
+}
+
+// 重启
+async fn restart_handler(
+    State(state): State<AppState>,
+    Json(req): Json<CommandRequest>,
+) -> Result<AxumJson<ApiResponse<CommandResult>>, StatusCode> {
+    let ip = get_client_ip();
+
+    if let Some(denied) = require_session(&state, &ip, Some(&req.token), "Restart") {
+        return denied;
+    }
+
+    let decision = check_command(&state.auth_manager, &ip, &req.token, "restart");
+    if !decision.allowed {
+        return command_denied(&ip, "Restart", decision);
+    }
+
+    log::info!("[Command] [{}] Restart REQUEST", ip);
+    log_to_ui("info", &format!("[{}] Restart REQUEST", ip));
+
+    match run_command("restart", req.args.clone()).await {
+        Ok(result) => {
+            if result.success {
+                log::info!("[Command] [{}] Restart SUCCESS", ip);
+                log_to_ui("success", &format!("[{}] Restart SUCCESS", ip));
+            } else {
+                log::error!("[Command] [{}] Restart FAILED: {}", ip, result.stderr);
+                log_to_ui(
+                    "error",
+                    &format!("[{}] Restart FAILED: {}", ip, result.stderr),
+                );
+            }
+            let error_msg = if result.success {
+                None
+            } else {
+                Some(result.stderr.clone())
+            };
+            Ok(AxumJson(ApiResponse {
+                success: result.success,
+                data: Some(result),
+                error: error_msg,
+                error_details: None,
+            }))
+        }
+        Err(e) => {
+            log::error!("[Command] [{}] Restart ERROR: {}", ip, e);
+            log_to_ui("error", &format!("[{}] Restart ERROR: {}", ip, e));
+            Ok(AxumJson(ApiResponse {
+                success: false,
+                data: None,
+                error: Some(e.to_string()),
+                error_details: None,
+            }))
+        }
+    }
+}
+
+// 睡眠
+async fn sleep_handler(
+    State(state): State<AppState>,
+    Json(req): Json<CommandRequest>,
+) -> Result<AxumJson<ApiResponse<CommandResult>>, StatusCode> {
+    let ip = get_client_ip();
+
+    if let Some(denied) = require_session(&state, &ip, Some(&req.token), "Sleep") {
+        return denied;
+    }
+
+    let decision = check_command(&state.auth_manager, &ip, &req.token, "sleep");
+    if !decision.allowed {
+        return command_denied(&ip, "Sleep", decision);
+    }
+
+    log::info!("[Command] [{}] Sleep REQUEST", ip);
+    log_to_ui("info", &format!("[{}] Sleep REQUEST", ip));
+
+    match run_command("sleep", None).await {
+        Ok(result) => {
+            if result.success {
+                log::info!("[Command] [{}] Sleep SUCCESS", ip);
+                log_to_ui("success", &format!("[{}] Sleep SUCCESS", ip));
+            } else {
+                log::error!("[Command] [{}] Sleep FAILED: {}", ip, result.stderr);
+                log_to_ui(
+                    "error",
+                    &format!("[{}] Sleep FAILED: {}", ip, result.stderr),
+                );
+            }
+            let error_msg = if result.success {
+                None
+            } else {
+                Some(result.stderr.clone())
+            };
+            Ok(AxumJson(ApiResponse {
+                success: result.success,
+                data: Some(result),
+                error: error_msg,
+                error_details: None,
+            }))
+        }
+        Err(e) => {
+            log::error!("[Command] [{}] Sleep ERROR: {}", ip, e);
+            log_to_ui("error", &format!("[{}] Sleep ERROR: {}", ip, e));
+            Ok(AxumJson(ApiResponse {
+                success: false,
+                data: None,
+                error: Some(e.to_string()),
+                error_details: None,
+            }))
+        }
+    }
+}
+
+// 锁屏
+async fn lock_handler(
+    State(state): State<AppState>,
+    Json(req): Json<CommandRequest>,
+) -> Result<AxumJson<ApiResponse<CommandResult>>, StatusCode> {
+    let ip = get_client_ip();
+
+    if let Some(denied) = require_session(&state, &ip, Some(&req.token), "Lock") {
+        return denied;
+    }
+
+    let decision = check_command(&state.auth_manager, &ip, &req.token, "lock");
+    if !decision.allowed {
+        return command_denied(&ip, "Lock", decision);
+    }
+
+    log::info!("[Command] [{}] Lock REQUEST", ip);
+    log_to_ui("info", &format!("[{}] Lock REQUEST", ip));
+
+    match run_command("lock", None).await {
+        Ok(result) => {
+            if result.success {
+                log::info!("[Command] [{}] Lock SUCCESS", ip);
+                log_to_ui("success", &format!("[{}] Lock SUCCESS", ip));
+            } else {
+                log::error!("[Command] [{}] Lock FAILED: {}", ip, result.stderr);
+                log_to_ui("error", &format!("[{}] Lock FAILED: {}", ip, result.stderr));
+            }
+            let error_msg = if result.success {
+                None
+            } else {
+                Some(result.stderr.clone())
+            };
+            Ok(AxumJson(ApiResponse {
+                success: result.success,
+                data: Some(result),
+                error: error_msg,
+                error_details: None,
+            }))
+        }
+        Err(e) => {
+            log::error!("[Command] [{}] Lock ERROR: {}", ip, e);
+            log_to_ui("error", &format!("[{}] Lock ERROR: {}", ip, e));
+            Ok(AxumJson(ApiResponse {
+                success: false,
+                data: None,
+                error: Some(e.to_string()),
+                error_details: None,
+            }))
+        }
+    }
+}
+
+// 执行命令
+async fn execute_command_handler(
+    State(state): State<AppState>,
+    Json(req): Json<CommandRequest>,
+) -> Result<AxumJson<ApiResponse<CommandResult>>, StatusCode> {
+    let ip = get_client_ip();
+
+    if let Some(denied) = require_session(&state, &ip, Some(&req.token), "Execute") {
+        return denied;
+    }
+
+    let (actual_command, actual_args) = resolve_command(&req.command, req.args.clone());
+
+    let decision = check_command(&state.auth_manager, &ip, &req.token, &actual_command);
+    if !decision.allowed {
+        return command_denied(&ip, &format!("Execute '{}'", actual_command), decision);
+    }
+
+    log::info!("[Command] [{}] Execute '{}' REQUEST", ip, actual_command);
+    log_to_ui(
+        "info",
+        &format!("[{}] Execute '{}' REQUEST", ip, actual_command),
+    );
+
+    match run_command(&actual_command, actual_args).await {
+        Ok(result) => {
+            if result.success {
+                log::info!("[Command] [{}] Execute '{}' SUCCESS", ip, actual_command);
+                log_to_ui(
+                    "success",
+                    &format!("[{}] Execute '{}' SUCCESS", ip, actual_command),
+                );
+            } else {
+                log::error!(
+                    "[Command] [{}] Execute '{}' FAILED: {}",
+                    ip,
+                    actual_command,
+                    result.stderr
+                );
+                log_to_ui(
+                    "error",
+                    &format!(
+                        "[{}] Execute '{}' FAILED: {}",
+                        ip, actual_command, result.stderr
+                    ),
+                );
+            }
+            let error_msg = if result.success {
+                None
+            } else {
+                Some(result.stderr.clone())
+            };
+            Ok(AxumJson(ApiResponse {
+                success: result.success,
+                data: Some(result),
+                error: error_msg,
+                error_details: None,
+            }))
+        }
+        Err(e) => {
+            log::error!("[Command] [{}] Execute '{}' ERROR: {}", ip, actual_command, e);
+            log_to_ui(
+                "error",
+                &format!("[{}] Execute '{}' ERROR: {}", ip, actual_command, e),
+            );
+            Ok(AxumJson(ApiResponse {
+                success: false,
+                data: None,
+                error: Some(e.to_string()),
+                error_details: None,
+            }))
+        }
+    }
+}
+
+// 检查命令能否执行，只做判定不执行 - 需要认证
+async fn command_check_handler(
+    State(state): State<AppState>,
+    Json(req): Json<CommandRequest>,
+) -> Result<AxumJson<ApiResponse<CommandCheck>>, StatusCode> {
+    let ip = get_client_ip();
+
+    if let Some(denied) =
+        require_session_scope(&state, &ip, Some(&req.token), "commands", "Command check")
+    {
+        return denied;
+    }
+
+    let (command, args) = resolve_command(&req.command, req.args);
+    let client = state.auth_manager.permission_source(&req.token);
+    let decision = crate::policy::evaluate_command(
+        &command,
+        client.as_ref().map(|(source, permissions)| (source.as_str(), permissions)),
+        &get_config(),
+    );
+    log::info!(
+        "[Command] [{}] Check '{}': {}",
+        ip,
+        command,
+        if decision.allowed { "allowed" } else { "denied" }
+    );
+
+    Ok(AxumJson(ApiResponse {
+        success: true,
+        data: Some(CommandCheck { args, decision }),
+        error: None,
+        error_details: None,
+    }))
+}
+
+// 客户端访问统计 - 需要认证
+async fn client_stats_handler(
+    State(state): State<AppState>,
+    Query(query): Query<TokenQuery>,
+) -> Result<AxumJson<ApiResponse<Vec<ClientStats>>>, StatusCode> {
+    let ip = get_client_ip();
+
+    if let Some(denied) =
+        require_scope(&state, &ip, query.token.as_deref(), "logs", "Client stats request")
+    {
+        return denied;
+    }
+
+    log::info!("[Access] [{}] Client stats requested", ip);
+
+    Ok(AxumJson(ApiResponse {
+        success: true,
+        data: Some(crate::stats::get_client_stats()),
+        error: None,
+        error_details: None,
+    }))
+}
+
+// 分页读取日志 - 需要认证
+async fn logs_handler(
+    State(state): State<AppState>,
+    Query(query): Query<LogsQuery>,
+) -> Result<AxumJson<ApiResponse<LogPage>>, StatusCode> {
+    let ip = get_client_ip();
+
+    if let Some(denied) =
+        require_scope(&state, &ip, query.token.as_deref(), "logs", "Logs request")
+    {
+        return denied;
+    }
+
+    let limit = query.limit.unwrap_or(DEFAULT_LOG_PAGE).clamp(1, MAX_LOG_PAGE);
+    let logs = crate::logger::recent_logs(query.before_timestamp, limit);
+    let next_before = (logs.len() == limit)
+        .then(|| logs.last().map(|entry| entry.timestamp))
+        .flatten();
+
+    Ok(AxumJson(ApiResponse {
+        success: true,
+        data: Some(LogPage { logs, next_before }),
+        error: None,
+        error_details: None,
+    }))
+}
+
+// 获取本程序的更新状态 - 需要认证，供手机端提示电脑端版本过旧
+async fn app_update_handler(
+    State(state): State<AppState>,
+    Query(query): Query<TokenQuery>,
+) -> Result<AxumJson<ApiResponse<AppUpdateStatus>>, StatusCode> {
+    let ip = get_client_ip();
+
+    if let Some(denied) =
+        require_scope(&state, &ip, query.token.as_deref(), "updates", "App update status")
+    {
+        return denied;
+    }
+
+    log::info!("[Access] [{}] App update status requested", ip);
+
+    Ok(AxumJson(ApiResponse {
+        success: true,
+        data: Some(crate::self_update::get_status()),
+        error: None,
+        error_details: None,
+    }))
+}
+
+// 同步客户端保存的设备目录 - 需要认证
+async fn device_sync_handler(
+    State(state): State<AppState>,
+    Json(req): Json<DeviceSyncRequest>,
+) -> Result<AxumJson<ApiResponse<Vec<CatalogEntry>>>, StatusCode> {
+    let ip = get_client_ip();
+
+    if let Some(denied) =
+        require_session_scope(&state, &ip, Some(&req.token), "sync", "Device sync")
+    {
+        return denied;
+    }
+
+    log::info!(
+        "[Access] [{}] Device sync with {} entries",
+        ip,
+        req.devices.len()
+    );
+
+    Ok(AxumJson(match crate::sync::sync_catalog(req.devices) {
+        Ok(catalog) => ApiResponse {
+            success: true,
+            data: Some(catalog),
+            error: None,
+            error_details: None,
+        },
+        Err(e) => {
+            log::warn!("[Access] [{}] Device sync failed: {}", ip, e);
+            ApiResponse {
+                success: false,
+                data: None,
+                error: Some(e),
+                error_details: None,
+            }
+        }
+    }))
+}
+
+// 长轮询事件 - 需要认证，供无法保持 WebSocket 连接的客户端使用
+async fn events_poll_handler(
+    State(state): State<AppState>,
+    Query(query): Query<EventPollQuery>,
+) -> Result<AxumJson<ApiResponse<EventBatch>>, StatusCode> {
+    let ip = get_client_ip();
+
+    if let Some(denied) =
+        require_scope(&state, &ip, query.token.as_deref(), "events", "Event poll")
+    {
+        return denied;
+    }
+
+    let batch = match query.since {
+        Some(since) => {
+            let timeout = query
+                .timeout
+                .unwrap_or(EVENT_POLL_DEFAULT_TIMEOUT_SECS)
+                .min(EVENT_POLL_MAX_TIMEOUT_SECS);
+            crate::events::poll(since, Duration::from_secs(timeout)).await
+        }
+        None => EventBatch {
+            cursor: crate::events::cursor(),
+            events: Vec::new(),
+            missed: false,
+        },
+    };
+
+    Ok(AxumJson(ApiResponse {
+        success: true,
+        data: Some(batch),
+        error: None,
+        error_details: None,
+    }))
+}
+
+// 获取可用功能 - 需要认证
+async fn capabilities_handler(
+    State(state): State<AppState>,
+    Query(query): Query<TokenQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let ip = get_client_ip();
+
+    if let Some(denied) =
+        require_token::<Capabilities>(&state, &ip, query.token.as_deref(), "Capabilities request")
+    {
+        return denied.into_response();
+    }
+
+    let requires_auth = state.auth_manager.is_password_set();
+    // 功能列表只随配置和密码状态变化
+    let etag = format!("W/\"caps-{}-{}\"", config_version(), requires_auth as u8);
+
+    json_with_version_etag(&headers, etag, || {
+        let config = get_config();
+        let custom_enabled = config.command_whitelist.iter().any(|c| c == "custom");
+        let mut features = vec![
+            "websocket",
+            "notifications",
+            "client_stats",
+            "debug_echo",
+            "handshake",
+            "app_update",
+            "device_sync",
+            "events_poll",
+            "payload_encryption",
+            "pairing",
+            "command_check",
+            "logs",
+            "clipboard",
+            "metrics_stream",
+        ];
+        if !config.shared_roots.is_empty() {
+            features.push("file_search");
+            features.push("file_watch");
+            features.push("file_transfer");
+        }
+        if requires_auth && config.command_whitelist.iter().any(|c| c == "shell") {
+            features.push("shell");
+        }
+        if config.grpc_port != 0 {
+            features.push("grpc");
+        }
+        if config.enable_api_keys {
+            features.push("api_keys");
+        }
+        ApiResponse {
+            success: true,
+            data: Some(Capabilities {
+                version: env!("CARGO_PKG_VERSION"),
+                requires_auth,
+                commands: config.command_whitelist,
+                custom_commands: if custom_enabled {
+                    config.custom_commands
+                } else {
+                    Vec::new()
+                },
+                features,
+            }),
+            error: None,
+            error_details: None,
+        }
+    })
+}
+
+// 回显请求信息 - 无需认证，令牌无效时同样返回，便于客户端排查
+async fn echo_handler(
+    State(state): State<AppState>,
+    Query(query): Query<TokenQuery>,
+    method: axum::http::Method,
+    uri: axum::http::Uri,
+    headers: HeaderMap,
+) -> Result<AxumJson<ApiResponse<EchoInfo>>, StatusCode> {
+    let ip = get_client_ip();
+    log::info!("[Access] [{}] Debug echo requested", ip);
+
+    let headers = headers
+        .iter()
+        .map(|(name, value)| {
+            let value = match name.as_str() {
+                "authorization" | "cookie" => "***".to_string(),
+                _ => String::from_utf8_lossy(value.as_bytes()).into_owned(),
+            };
+            (name.to_string(), value)
+        })
+        .collect();
+
+    let requires_auth = state.auth_manager.is_password_set();
+    let claims = query
+        .token
+        .as_deref()
+        .and_then(|token| state.auth_manager.decode_token(token));
+    // 未设置密码或令牌有效且未限制范围时，可访问全部功能
+    let permissions = query
+        .token
+        .as_deref()
+        .filter(|_| claims.is_some())
+        .and_then(|token| state.auth_manager.permissions(token));
+    let scopes = match permissions {
+        // 没有适用条目时 scopes 只含占位值（见 ClientPermissions::deny_all），不公布
+        Some(permissions) if !permissions.scopes.is_empty() => permissions
+            .scopes
+            .into_iter()
+            .filter(|s| crate::config::PERMISSION_SCOPES.contains(&s.as_str()))
+            .collect(),
+        _ if claims.is_some() || !requires_auth => crate::config::PERMISSION_SCOPES
+            .iter()
+            .map(|s| s.to_string())
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    Ok(AxumJson(ApiResponse {
+        success: true,
+        data: Some(EchoInfo {
+            client_ip: ip,
+            method: method.to_string(),
+            path: uri.path().to_string(),
+            headers,
+            token_present: query.token.is_some(),
+            token_valid: claims.is_some(),
+            token_expires_at: claims.as_ref().map(|c| c.exp),
+            device_id: claims.and_then(|c| c.device_id),
+            scopes,
+            requires_auth,
+            server_time: chrono::Local::now(),
+            server_version: env!("CARGO_PKG_VERSION"),
+        }),
+        error: None,
+        error_details: None,
+    }))
+}
+
+// 获取当前配置（不含密码哈希）- 需要认证
+async fn get_config_handler(
+    State(state): State<AppState>,
+    Query(query): Query<TokenQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let ip = get_client_ip();
+
+    if let Some(denied) =
+        require_scope::<AppConfig>(&state, &ip, query.token.as_deref(), "config", "Config request")
+    {
+        return denied.into_response();
+    }
+
+    log::info!("[Access] [{}] Config requested", ip);
+
+    let etag = format!("W/\"config-{}\"", config_version());
+    json_with_version_etag(&headers, etag, || {
+        ApiResponse {
+            success: true,
+            data: Some(get_config().redacted()),
+            error: None,
+            error_details: None,
+        }
+    })
+}
+
+// 修改配置 - 需要认证，先校验再保存
+async fn patch_config_handler(
+    State(state): State<AppState>,
+    Json(req): Json<ConfigPatchRequest>,
+) -> Result<AxumJson<ApiResponse<Vec<FieldError>>>, StatusCode> {
+    let ip = get_client_ip();
+
+    if let Some(denied) =
+        require_session_scope(&state, &ip, Some(&req.token), "config", "Config update")
+    {
+        return denied;
+    }
+
+    let invalid = |errors: Vec<FieldError>| {
+        Ok(AxumJson(ApiResponse {
+            success: false,
+            data: Some(errors),
+            error: Some("Invalid configuration".to_string()),
+            error_details: None,
+        }))
+    };
+
+    // 只能远程修改允许的设置，安全相关设置只能在本机界面修改
+    let errors: Vec<FieldError> = req
+        .changes
+        .keys()
+        .filter(|key| !crate::config::REMOTE_EDITABLE_FIELDS.contains(&key.as_str()))
+        .map(|key| FieldError {
+            field: key.clone(),
+            message: "Unknown setting or can only be changed on this computer".to_string(),
+        })
+        .collect();
+    if !errors.is_empty() {
+        log::warn!(
+            "[Command] [{}] Config update REJECTED: {} protected or unknown field(s)",
+            ip,
+            errors.len()
+        );
+        return invalid(errors);
+    }
+
+    // 在配置锁内合并，避免覆盖同时进行的其他修改
+    let result = crate::config::try_update_config(|current| {
+        let mut merged = match serde_json::to_value(current) {
+            Ok(serde_json::Value::Object(map)) => map,
+            _ => {
+                return Err(vec![FieldError {
+                    field: "changes".to_string(),
+                    message: "Failed to read current configuration".to_string(),
+                }])
+            }
+        };
+        merged.extend(req.changes);
+        let new_config: AppConfig = serde_json::from_value(serde_json::Value::Object(merged))
+            .map_err(|e| {
+                vec![FieldError {
+                    field: "changes".to_string(),
+                    message: e.to_string(),
+                }]
+            })?;
+        let errors = new_config.validate();
+        if errors.is_empty() {
+            Ok(new_config)
+        } else {
+            Err(errors)
+        }
+    });
+    let result = match result {
+        Ok(result) => result,
+        Err(errors) => {
+            log::warn!("[Command] [{}] Config update REJECTED: {} invalid field(s)", ip, errors.len());
+            return invalid(errors);
+        }
+    };
+    match result {
+        Ok(()) => {
+            crate::logger::reload_logger_config();
+            log::info!("[Command] [{}] Config updated", ip);
+            log_to_ui("success", &format!("[{}] Config updated", ip));
+            Ok(AxumJson(ApiResponse {
+                success: true,
+                data: Some(Vec::new()),
+                error: None,
+                error_details: None,
+            }))
+        }
+        Err(e) => Ok(AxumJson(ApiResponse {
+            success: false,
+            data: None,
+            error: Some(format!("Failed to save config: {}", e)),
+            error_details: None,
+        })),
+    }
+}
+
+// 在共享目录中搜索文件 - 需要认证
+async fn file_search_handler(
+    State(state): State<AppState>,
+    Query(query): Query<FileSearchQuery>,
+) -> Result<AxumJson<ApiResponse<FileSearchResult>>, StatusCode> {
+    let ip = get_client_ip();
+
+    if let Some(denied) =
+        require_scope(&state, &ip, query.token.as_deref(), "files", "File search")
+    {
+        return denied;
+    }
+
+    log::info!("[Access] [{}] File search '{}'", ip, query.pattern);
+    log_to_ui("info", &format!("[{}] File search '{}'", ip, query.pattern));
+
+    let options = FileSearchOptions {
+        pattern: query.pattern,
+        regex: query.regex,
+        root: query.root,
+        content: query.content,
+        offset: query.offset,
+        limit: query.limit,
+    };
+    let result = tokio::task::spawn_blocking(move || crate::files::search(&options))
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+
+    match result {
+        Ok(result) => Ok(AxumJson(ApiResponse {
+            success: true,
+            data: Some(result),
+            error: None,
+            error_details: None,
+        })),
+        Err(e) => {
+            log::warn!("[Access] [{}] File search failed: {}", ip, e);
+            Ok(AxumJson(ApiResponse {
+                success: false,
+                data: None,
+                error: Some(e),
+                error_details: None,
+            }))
+        }
+    }
+}
+
+// 读取电脑剪贴板中的文本
+async fn get_clipboard_handler(
+    State(state): State<AppState>,
+    Query(query): Query<TokenQuery>,
+) -> Result<AxumJson<ApiResponse<ClipboardText>>, StatusCode> {
+    let ip = get_client_ip();
+    if let Some(denied) =
+        require_scope(&state, &ip, query.token.as_deref(), "clipboard", "Clipboard read")
+    {
+        return denied;
+    }
+
+    let result = tokio::task::spawn_blocking(crate::clipboard::read_text)
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+    match result {
+        Ok(text) => {
+            // 不记录剪贴板内容
+            log::info!("[Access] [{}] Clipboard read ({} bytes)", ip, text.len());
+            log_to_ui("info", &format!("[{}] Clipboard read", ip));
+            Ok(AxumJson(ApiResponse {
+                success: true,
+                data: Some(ClipboardText { text }),
+                error: None,
+                error_details: None,
+            }))
+        }
+        Err(e) => {
+            log::warn!("[Access] [{}] Clipboard read failed: {}", ip, e);
+            Ok(AxumJson(ApiResponse {
+                success: false,
+                data: None,
+                error: Some(e),
+                error_details: None,
+            }))
+        }
+    }
+}
+
+// 把手机发来的文本写入电脑剪贴板
+async fn set_clipboard_handler(
+    State(state): State<AppState>,
+    Json(req): Json<ClipboardRequest>,
+) -> Result<AxumJson<ApiResponse<()>>, StatusCode> {
+    let ip = get_client_ip();
+    if let Some(denied) =
+        require_scope(&state, &ip, req.token.as_deref(), "clipboard", "Clipboard write")
+    {
+        return denied;
+    }
+
+    let len = req.text.len();
+    let result = tokio::task::spawn_blocking(move || crate::clipboard::write_text(req.text))
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+    match result {
+        Ok(()) => {
+            log::info!("[Access] [{}] Clipboard write ({} bytes)", ip, len);
+            log_to_ui("info", &format!("[{}] Clipboard updated", ip));
+            Ok(AxumJson(ApiResponse {
+                success: true,
+                data: Some(()),
+                error: None,
+                error_details: None,
+            }))
+        }
+        Err(e) => {
+            log::warn!("[Access] [{}] Clipboard write failed: {}", ip, e);
+            Ok(AxumJson(ApiResponse {
+                success: false,
+                data: None,
+                error: Some(e),
+                error_details: None,
+            }))
+        }
+    }
+}
+
+/// 终端连接被拒绝时的响应，握手阶段只能通过状态码告知客户端
+fn shell_rejected(status: StatusCode, error: String) -> Response {
+    (
+        status,
+        AxumJson(ApiResponse::<()> {
+            success: false,
+            data: None,
+            error: Some(error),
+            error_details: None,
+        }),
+    )
+        .into_response()
+}
+
+// 打开交互式终端，需要在命令白名单中启用 shell
+//
+// 加密会话通过 PAYLOAD_HEADER 请求头连接，令牌在加密的查询参数中，终端数据逐帧加密
+async fn shell_ws_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    // 会话在升级后的任务中运行，直接读取本次请求的 ClientIp，不依赖线程局部变量
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    Query(query): Query<ShellQuery>,
+    session: Option<Extension<crate::payload::PayloadSession>>,
+) -> Response {
+    // 终端可以完全控制电脑，未设置密码时一律拒绝
+    if !state.auth_manager.is_password_set() {
+        log::warn!("[Shell] [{}] Session REJECTED: No password is set", ip);
+        log_to_ui("warn", &format!("[{}] Remote shell REJECTED: set a password to allow it", ip));
+        return shell_rejected(
+            StatusCode::FORBIDDEN,
+            "Remote shell requires a password to be set on the device".to_string(),
+        );
+    }
+    let authorized = query
+        .token
+        .as_deref()
+        .is_some_and(|token| state.auth_manager.verify_token(token));
+    if !authorized {
+        log::warn!("[Shell] [{}] Session denied: Invalid token", ip);
+        crate::stats::record_auth(&ip, AuthOutcome::TokenRejected);
+        return shell_rejected(StatusCode::UNAUTHORIZED, "Invalid or expired token".to_string());
+    }
+
+    let token = query.token.unwrap_or_default();
+    let decision = check_command(&state.auth_manager, &ip, &token, "shell");
+    if !decision.allowed {
+        log::warn!("[Shell] [{}] Session REJECTED: {}", ip, decision.reason);
+        log_to_ui("warn", &format!("[{}] Remote shell REJECTED: {}", ip, decision.reason));
+        return shell_rejected(StatusCode::FORBIDDEN, decision.reason);
+    }
+
+    let Some(slot) = crate::shell::acquire_slot() else {
+        log::warn!("[Shell] [{}] Session REJECTED: Too many open sessions", ip);
+        return shell_rejected(
+            StatusCode::TOO_MANY_REQUESTS,
+            "Too many open shell sessions".to_string(),
+        );
+    };
+
+    let cols = query.cols.unwrap_or(80);
+    let rows = query.rows.unwrap_or(24);
+    ws.max_message_size(crate::websocket::WS_HARD_MESSAGE_LIMIT)
+        .on_upgrade(move |socket| {
+            crate::shell::run_session(socket, ip, cols, rows, session.map(|s| s.0), slot)
+        })
+}
+
+fn files_result<T>(
+    ip: &str,
+    action: &str,
+    result: Result<T, String>,
+) -> Result<AxumJson<ApiResponse<T>>, StatusCode> {
+    match result {
+        Ok(data) => Ok(AxumJson(ApiResponse {
+            success: true,
+            data: Some(data),
+            error: None,
+            error_details: None,
+        })),
+        Err(e) => {
+            log::warn!("[Access] [{}] {} failed: {}", ip, action, e);
+            Ok(AxumJson(ApiResponse {
+                success: false,
+                data: None,
+                error: Some(e),
+                error_details: None,
+            }))
+        }
+    }
+}
+
+// 列出共享目录的内容
+async fn file_list_handler(
+    State(state): State<AppState>,
+    Query(query): Query<FileListQuery>,
+) -> Result<AxumJson<ApiResponse<Vec<FileEntry>>>, StatusCode> {
+    let ip = get_client_ip();
+    if let Some(denied) = require_scope(&state, &ip, query.token.as_deref(), "files", "File list") {
+        return denied;
+    }
+
+    let path = query.path.unwrap_or_default();
+    log::info!("[Access] [{}] File list '{}'", ip, path);
+
+    let result = tokio::task::spawn_blocking(move || crate::files::list_dir(Some(&path)))
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+    files_result(&ip, "File list", result)
+}
+
+/// 解析 "bytes=START-" 或 "bytes=START-END"，不支持多段范围
+fn parse_byte_range(value: &str) -> Option<(u64, Option<u64>)> {
+    let (start, end) = value.strip_prefix("bytes=")?.split_once('-')?;
+    let start = start.trim().parse().ok()?;
+    let end = match end.trim() {
+        "" => None,
+        end => Some(end.parse().ok()?),
+    };
+    Some((start, end))
+}
+
+// 下载共享文件，支持 Range 请求以便分块和断点续传
+//
+// 单次响应最多返回 files::MAX_TRANSFER_CHUNK 字节，文件更大时以 206 返回并在
+// Content-Range 中给出总大小；失败时返回 JSON 错误
+async fn file_download_handler(
+    State(state): State<AppState>,
+    Query(query): Query<FileTransferQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let ip = get_client_ip();
+    if let Some(denied) =
+        require_scope::<()>(&state, &ip, query.token.as_deref(), "files", "File download")
+    {
+        return denied.into_response();
+    }
+
+    let range = match headers.get(RANGE).and_then(|v| v.to_str().ok()) {
+        Some(value) => match parse_byte_range(value) {
+            Some(range) => Some(range),
+            None => return StatusCode::RANGE_NOT_SATISFIABLE.into_response(),
+        },
+        None => None,
+    };
+    let (start, end) = range.unwrap_or((0, None));
+    // 只记录第一块，避免分块下载刷屏
+    if start == 0 {
+        log::info!("[Access] [{}] File download '{}'", ip, query.path);
+        log_to_ui("info", &format!("[{}] File download '{}'", ip, query.path));
+    }
+
+    let path = query.path.clone();
+    let result = tokio::task::spawn_blocking(move || crate::files::read_range(&path, start, end))
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+
+    let (data, total) = match result {
+        Ok(result) => result,
+        Err(e) => return files_result::<()>(&ip, "File download", Err(e)).into_response(),
+    };
+    let len = data.len() as u64;
+    let partial = len > 0 && (range.is_some() || len < total);
+
+    let mut response = Response::builder()
+        .header(CONTENT_TYPE, "application/octet-stream")
+        .header(CONTENT_LENGTH, len)
+        .header(ACCEPT_RANGES, "bytes")
+        // 文件内容原样传输，Content-Length 与 Content-Range 保持一致
+        .header(CONTENT_ENCODING, "identity");
+    if partial {
+        response = response.status(StatusCode::PARTIAL_CONTENT).header(
+            CONTENT_RANGE,
+            format!("bytes {}-{}/{}", start, start + len - 1, total),
+        );
+    }
+    response
+        .body(axum::body::Body::from(data))
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
+
+// 查询已上传的字节数，用于断点续传
+async fn file_upload_status_handler(
+    State(state): State<AppState>,
+    Query(query): Query<FileTransferQuery>,
+) -> Result<AxumJson<ApiResponse<FileUploadProgress>>, StatusCode> {
+    let ip = get_client_ip();
+    if let Some(denied) =
+        require_scope(&state, &ip, query.token.as_deref(), "files", "File upload")
+    {
+        return denied;
+    }
+
+    let path = query.path;
+    let result = tokio::task::spawn_blocking(move || {
+        crate::files::uploaded_size(&path).map(|uploaded| FileUploadProgress { path, uploaded })
+    })
+    .await
+    .unwrap_or_else(|e| Err(e.to_string()));
+    files_result(&ip, "File upload", result)
+}
+
+// 上传一块文件数据，offset 必须等于已上传的字节数，complete 为 true 时完成上传
+async fn file_upload_handler(
+    State(state): State<AppState>,
+    Query(query): Query<FileTransferQuery>,
+    body: axum::body::Bytes,
+) -> Result<AxumJson<ApiResponse<FileUploadProgress>>, StatusCode> {
+    let ip = get_client_ip();
+    if let Some(denied) =
+        require_scope(&state, &ip, query.token.as_deref(), "files", "File upload")
+    {
+        return denied;
+    }
+
+    if query.complete {
+        log::info!("[Access] [{}] File upload '{}' complete", ip, query.path);
+        log_to_ui("info", &format!("[{}] File upload '{}'", ip, query.path));
+    }
+
+    let FileTransferQuery {
+        path,
+        offset,
+        complete,
+        overwrite,
+        ..
+    } = query;
+    let result = tokio::task::spawn_blocking(move || {
+        crate::files::write_chunk(&path, offset, &body, complete, overwrite)
+            .map(|uploaded| FileUploadProgress { path, uploaded })
+    })
+    .await
+    .unwrap_or_else(|e| Err(e.to_string()));
+    files_result(&ip, "File upload", result)
+}
+
+// 获取监听端口和已建立的连接 - 需要认证
+async fn connections_handler(
+    State(state): State<AppState>,
+    Query(query): Query<ConnectionsQuery>,
+) -> Result<AxumJson<ApiResponse<ConnectionsInfo>>, StatusCode> {
+    let ip = get_client_ip();
+
+    if let Some(denied) =
+        require_scope(&state, &ip, query.token.as_deref(), "network", "Connections request")
+    {
+        return denied;
+    }
+
+    log::info!("[Access] [{}] Connections requested", ip);
+
+    let port = query.port;
+    let result = tokio::task::spawn_blocking(move || crate::network::get_connections(port))
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+
+    match result {
+        Ok(connections) => Ok(AxumJson(ApiResponse {
+            success: true,
+            data: Some(connections),
+            error: None,
+            error_details: None,
+        })),
+        Err(e) => {
+            log::warn!("[Access] [{}] Connections request failed: {}", ip, e);
+            Ok(AxumJson(ApiResponse {
+                success: false,
+                data: None,
+                error: Some(e),
+                error_details: None,
+            }))
+        }
+    }
+}
+
+// ping 目标主机
+async fn ping_handler(
+    State(state): State<AppState>,
+    Json(req): Json<PingRequest>,
+) -> Result<AxumJson<ApiResponse<PingResult>>, StatusCode> {
+    let ip = get_client_ip();
+
+    if let Some(denied) = require_session_scope(&state, &ip, Some(&req.token), "network", "Ping") {
+        return denied;
+    }
+
+    log::info!("[Command] [{}] Ping '{}' REQUEST", ip, req.target);
+    log_to_ui("info", &format!("[{}] Ping '{}' REQUEST", ip, req.target));
+
+    let result = tokio::task::spawn_blocking(move || {
+        crate::network::ping(
+            &req.target,
+            req.count.unwrap_or(4),
+            req.timeout_ms.unwrap_or(1000),
+        )
+    })
+    .await
+    .unwrap_or_else(|e| Err(e.to_string()));
+
+    match result {
+        Ok(result) => Ok(AxumJson(ApiResponse {
+            success: true,
+            data: Some(result),
+            error: None,
+            error_details: None,
+        })),
+        Err(e) => {
+            log::warn!("[Command] [{}] Ping FAILED: {}", ip, e);
+            log_to_ui("error", &format!("[{}] Ping FAILED: {}", ip, e));
+            Ok(AxumJson(ApiResponse {
+                success: false,
+                data: None,
+                error: Some(e),
+                error_details: None,
+            }))
+        }
+    }
+}
+
+// 追踪到目标主机的路由
+async fn traceroute_handler(
+    State(state): State<AppState>,
+    Json(req): Json<TracerouteRequest>,
+) -> Result<AxumJson<ApiResponse<TraceResult>>, StatusCode> {
+    let ip = get_client_ip();
+
+    if let Some(denied) =
+        require_session_scope(&state, &ip, Some(&req.token), "network", "Traceroute")
+    {
+        return denied;
+    }
+
+    log::info!("[Command] [{}] Traceroute '{}' REQUEST", ip, req.target);
+    log_to_ui("info", &format!("[{}] Traceroute '{}' REQUEST", ip, req.target));
+
+    let result = tokio::task::spawn_blocking(move || {
+        crate::network::traceroute(&req.target, req.max_hops.unwrap_or(20))
+    })
+    .await
+    .unwrap_or_else(|e| Err(e.to_string()));
+
+    match result {
+        Ok(result) => Ok(AxumJson(ApiResponse {
+            success: true,
+            data: Some(result),
+            error: None,
+            error_details: None,
+        })),
+        Err(e) => {
+            log::warn!("[Command] [{}] Traceroute FAILED: {}", ip, e);
+            log_to_ui("error", &format!("[{}] Traceroute FAILED: {}", ip, e));
+            Ok(AxumJson(ApiResponse {
+                success: false,
+                data: None,
+                error: Some(e),
+                error_details: None,
+            }))
+        }
+    }
+}
+
+// 网速测试：下载指定大小的随机数据
+async fn speedtest_download_handler(
+    State(state): State<AppState>,
+    Query(query): Query<SpeedTestQuery>,
+) -> Response {
+    let ip = get_client_ip();
+
+    if let Some(denied) =
+        require_session_scope::<()>(&state, &ip, query.token.as_deref(), "network", "Speed test")
+    {
+        return denied.into_response();
+    }
+
+    let total = query
+        .bytes
+        .unwrap_or(10 * 1024 * 1024)
+        .min(get_config().speedtest_max_bytes);
+    log::info!("[Command] [{}] Speed test download {} bytes", ip, total);
+    log_to_ui("info", &format!("[{}] Speed test download {} bytes", ip, total));
+
+    let chunk = axum::body::Bytes::from(crate::network::speedtest_chunk());
+    let stream = futures::stream::unfold(0u64, move |sent| {
+        let chunk = chunk.clone();
+        async move {
+            if sent >= total {
+                return None;
+            }
+            let len = (total - sent).min(chunk.len() as u64) as usize;
+            Some((Ok::<_, Infallible>(chunk.slice(0..len)), sent + len as u64))
+        }
+    });
+
+    Response::builder()
+        .header(CONTENT_TYPE, "application/octet-stream")
+        .header(CONTENT_LENGTH, total)
+        // 标记为不压缩，避免压缩层改变测试结果
+        .header(CONTENT_ENCODING, "identity")
+        // 随机数据无需加密，加密会话中同样原样返回
+        .extension(crate::payload::PlainResponse)
+        .body(axum::body::Body::from_stream(stream))
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
+
+// 网速测试：接收上传数据并返回服务端计时
+async fn speedtest_upload_handler(
+    State(state): State<AppState>,
+    Query(query): Query<SpeedTestQuery>,
+    body: axum::body::Body,
+) -> Result<AxumJson<ApiResponse<SpeedTestUpload>>, StatusCode> {
+    use futures::StreamExt;
+
+    let ip = get_client_ip();
+
+    if let Some(denied) =
+        require_session_scope(&state, &ip, query.token.as_deref(), "network", "Speed test")
+    {
+        return denied;
+    }
+
+    // 逐块读取并丢弃，不在内存中保留上传数据
+    let max_bytes = get_config().speedtest_max_bytes;
+    let started = Instant::now();
+    let mut received = 0u64;
+    let mut stream = body.into_data_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|_| StatusCode::BAD_REQUEST)?;
+        received += chunk.len() as u64;
+        if received > max_bytes {
+            return Err(StatusCode::PAYLOAD_TOO_LARGE);
+        }
+    }
+    let duration_ms = started.elapsed().as_millis() as u64;
+
+    log::info!(
+        "[Command] [{}] Speed test upload {} bytes in {} ms",
+        ip,
+        received,
+        duration_ms
+    );
+    log_to_ui(
+        "info",
+        &format!("[{}] Speed test upload {} bytes in {} ms", ip, received, duration_ms),
+    );
+
+    Ok(AxumJson(ApiResponse {
+        success: true,
+        data: Some(SpeedTestUpload {
+            bytes: received,
+            duration_ms,
+        }),
+        error: None,
+        error_details: None,
+    }))
+}
@@ -0,0 +1,123 @@
+use std::path::Path;
+use std::process::Command;
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+/// 允许远程打开的 URL 协议
+const ALLOWED_URL_SCHEMES: [&str; 3] = ["http://", "https://", "mailto:"];
+
+/// 允许远程打开的文件扩展名：文档、图片和音视频；可执行文件、脚本和快捷方式都不在其中
+const OPENABLE_EXTENSIONS: &[&str] = &[
+    "txt", "md", "pdf", "rtf", "csv", "doc", "docx", "xls", "xlsx", "ppt", "pptx", "odt", "ods",
+    "odp", "jpg", "jpeg", "png", "gif", "bmp", "webp", "heic", "mp3", "wav", "flac", "aac", "ogg",
+    "m4a", "mp4", "mkv", "avi", "mov", "wmv", "webm",
+];
+
+/// 使用系统默认程序打开目标（文件、目录或 URL）
+fn open_with_default(target: &str) -> Result<(), String> {
+    #[cfg(target_os = "windows")]
+    {
+        Command::new("rundll32")
+            .args(["url.dll,FileProtocolHandler", target])
+            .creation_flags(CREATE_NO_WINDOW)
+            .spawn()
+            .map_err(|e| e.to_string())?;
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        Command::new("xdg-open")
+            .arg(target)
+            .spawn()
+            .map_err(|e| e.to_string())?;
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        Command::new("open")
+            .arg(target)
+            .spawn()
+            .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+/// 在文件管理器中显示路径（Windows 下会选中该文件）
+pub fn reveal_path(path: &str) -> Result<(), String> {
+    #[cfg(target_os = "windows")]
+    {
+        Command::new("explorer")
+            .args(["/select,", path])
+            .creation_flags(CREATE_NO_WINDOW)
+            .spawn()
+            .map_err(|e| e.to_string())?;
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        open_with_default(path)?;
+    }
+
+    Ok(())
+}
+
+/// 在默认浏览器（或邮件客户端）中打开 URL，只允许 http/https/mailto
+pub fn open_url(url: &str) -> Result<(), String> {
+    let url = url.trim();
+    let lower = url.to_lowercase();
+    if !ALLOWED_URL_SCHEMES.iter().any(|scheme| lower.starts_with(scheme)) {
+        return Err("Only http, https and mailto URLs are allowed".to_string());
+    }
+    if url.chars().any(|c| c.is_whitespace() || c.is_control() || c == '"') {
+        return Err("URL contains invalid characters".to_string());
+    }
+
+    open_with_default(url)
+}
+
+/// 使用默认程序打开共享目录中的文件或目录，文件只能是 OPENABLE_EXTENSIONS 中的类型
+pub fn open_shared_path(path: &str) -> Result<(), String> {
+    let path = crate::files::resolve_shared_path(path)?;
+    if !path.is_dir() && !is_openable(&path) {
+        return Err("This file type cannot be opened remotely".to_string());
+    }
+    open_with_default(&path_to_string(&path))
+}
+
+fn is_openable(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| OPENABLE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+fn path_to_string(path: &Path) -> String {
+    let path = path.to_string_lossy().to_string();
+    // canonicalize 在 Windows 上会返回 \\?\ 前缀，部分程序无法识别
+    path.strip_prefix(r"\\?\").map(str::to_string).unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn documents_and_media_are_openable() {
+        assert!(is_openable(Path::new("report.PDF")));
+        assert!(is_openable(Path::new("photos/holiday.jpg")));
+    }
+
+    #[test]
+    fn executables_and_shortcuts_are_not_openable() {
+        for name in ["setup.exe", "run.bat", "script.ps1", "app.lnk", "app.desktop", "run.sh"] {
+            assert!(!is_openable(Path::new(name)), "{}", name);
+        }
+        assert!(!is_openable(Path::new("README")));
+        assert!(!is_openable(Path::new("notes.txt.exe")));
+    }
+}
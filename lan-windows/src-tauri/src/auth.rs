@@ -1,30 +1,185 @@
 use argon2::password_hash::{rand_core::OsRng, SaltString};
-use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
-use chrono::{DateTime, Duration, Utc};
+use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier as _};
+use chrono::{Duration, Utc};
 use hmac::{Hmac, Mac};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use rand::RngCore;
 use sha2::Sha256;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use uuid::Uuid;
+use zeroize::Zeroizing;
 
+use crate::config::{ApiKeyConfig, PasswordVerifier, API_KEY_PREFIX};
 use crate::models::{AuthChallenge, AuthResponse};
+use crate::pairing::{PairedDeviceInfo, PairingStore};
+use crate::payload::{derive_key, PayloadKey, ReplayWindow};
 
 type HmacSha256 = Hmac<Sha256>;
 
-#[derive(Debug, Clone)]
-pub struct Session {
-    pub created_at: DateTime<Utc>,
-    pub last_access: DateTime<Utc>,
+/// 单个IP同时持有的未使用挑战上限
+const MAX_CHALLENGES_PER_CLIENT: usize = 16;
+/// 全局挑战数量上限，超出时淘汰最早过期的
+const MAX_CHALLENGES: usize = 1024;
+
+/// 令牌有效期（秒）
+const TOKEN_TTL_SECS: i64 = 3600;
+/// 登录令牌的默认权限范围
+const SCOPE_FULL: &str = "full";
+/// 静态 API 密钥的权限范围，具体限制见 ApiKeyConfig::permissions
+const SCOPE_API_KEY: &str = "api_key";
+/// 使用配对密钥登录的令牌
+const SCOPE_PAIRED: &str = "paired";
+
+/// JWT 载荷
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims {
+    /// 令牌ID，吊销时使用
+    pub jti: String,
+    pub scope: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub device_id: Option<String>,
+    pub iat: i64,
+    pub exp: i64,
+    /// 签发时的令牌代数，吊销全部令牌时递增
+    pub gen: u64,
 }
 
-#[derive(Debug, Clone)]
+impl Claims {
+    /// 令牌对应的客户端身份，用于日志和审计：设备ID、API 密钥名或会话ID
+    pub fn identity(&self) -> String {
+        match self.device_id {
+            Some(ref device_id) => format!("device:{}", device_id),
+            // API 密钥的 jti 为 "apikey:<名称>"
+            None if self.scope == SCOPE_API_KEY => self.jti.clone(),
+            None => format!("session:{}", self.jti),
+        }
+    }
+}
+
+/// 按令牌载荷选择 client_permissions 中的条目
+///
+/// 只有配对登录的令牌证明了设备身份，才使用该设备ID的条目；其余令牌携带的 device_id
+/// 由客户端自行声明，一律使用 "default"。未配置任何条目时返回 None（不受限制），
+/// 已配置但没有适用条目时拒绝全部功能
+fn client_permissions_for(
+    claims: &Claims,
+    client_permissions: &HashMap<String, crate::config::ClientPermissions>,
+) -> Option<(String, crate::config::ClientPermissions)> {
+    if client_permissions.is_empty() {
+        return None;
+    }
+    let key = claims
+        .device_id
+        .as_deref()
+        .filter(|id| claims.scope == SCOPE_PAIRED && client_permissions.contains_key(*id))
+        .unwrap_or(crate::config::DEFAULT_CLIENT_KEY);
+    let permissions = client_permissions
+        .get(key)
+        .cloned()
+        .unwrap_or_else(crate::config::ClientPermissions::deny_all);
+    Some((format!("client_permissions.{}", key), permissions))
+}
+
+/// 持久化的吊销列表，服务重启后仍然生效
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct RevocationList {
+    /// 令牌代数，低于此值的令牌全部失效
+    generation: u64,
+    /// 已吊销的令牌ID -> 过期时间戳，过期后即可移除
+    tokens: HashMap<String, i64>,
+}
+
+impl RevocationList {
+    fn path() -> PathBuf {
+        crate::config::AppConfig::config_path().with_file_name("revoked_tokens.json")
+    }
+
+    fn load() -> Self {
+        std::fs::read_to_string(Self::path())
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) {
+        let result = crate::config::AppConfig::ensure_config_dir().and_then(|_| {
+            let content = serde_json::to_string(self).map_err(std::io::Error::other)?;
+            crate::config::write_atomic(&Self::path(), content.as_bytes())
+        });
+        if let Err(e) = result {
+            log::error!("Failed to save token revocation list: {}", e);
+        }
+    }
+}
+
+/// 加载令牌签名密钥，不存在时生成并保存，保证重启后已签发的令牌仍然有效
+fn load_or_create_secret() -> Zeroizing<Vec<u8>> {
+    let path = crate::config::AppConfig::config_path().with_file_name("jwt.key");
+    if let Ok(content) = std::fs::read_to_string(&path) {
+        let content = Zeroizing::new(content);
+        if let Ok(secret) = hex::decode(content.trim()) {
+            if secret.len() >= 32 {
+                return Zeroizing::new(secret);
+            }
+        }
+        log::warn!("Invalid JWT signing key, generating a new one");
+    }
+
+    let mut secret = Zeroizing::new(vec![0u8; 32]);
+    rand::rngs::OsRng.fill_bytes(&mut secret);
+    let encoded = Zeroizing::new(hex::encode(&*secret));
+    match crate::config::AppConfig::ensure_config_dir()
+        .and_then(|_| std::fs::write(&path, encoded.as_bytes()))
+    {
+        Ok(()) => log::info!("Generated new JWT signing key"),
+        Err(e) => log::error!(
+            "Failed to save JWT signing key, tokens will not survive a restart: {}",
+            e
+        ),
+    }
+    secret
+}
+
+/// 挑战存储的统计信息
+#[derive(Debug, Clone, Serialize)]
+pub struct ChallengeStats {
+    /// 当前未使用的挑战数
+    pub active: usize,
+    pub issued: u64,
+    /// 过期被清理的挑战数
+    pub expired: u64,
+    /// 达到全局上限被淘汰的挑战数
+    pub evicted: u64,
+    /// 因单个IP超限被拒绝的请求数
+    pub rejected: u64,
+}
+
+#[derive(Debug, Default)]
+struct ChallengeCounters {
+    issued: AtomicU64,
+    expired: AtomicU64,
+    evicted: AtomicU64,
+    rejected: AtomicU64,
+}
+
+#[derive(Clone)]
 pub struct AuthManager {
     password_hash: Arc<Mutex<Option<String>>>,
-    jwt_secret: String,
-    sessions: Arc<Mutex<HashMap<String, Session>>>,
+    /// 登录验证器，挑战响应以它为 HMAC 密钥
+    verifier: Arc<Mutex<Option<PasswordVerifier>>>,
+    jwt_secret: Arc<Zeroizing<Vec<u8>>>,
+    revocations: Arc<Mutex<RevocationList>>,
     challenges: Arc<Mutex<HashMap<String, AuthChallenge>>>,
-    max_sessions: usize,
+    challenge_counters: Arc<ChallengeCounters>,
+    /// 已配对的客户端和进行中的配对
+    pairings: Arc<PairingStore>,
+    /// 协商了负载加密的令牌（按 jti，即密钥ID）及其密钥、过期时间和已使用的请求计数
+    payload_keys: Arc<Mutex<HashMap<String, (PayloadKey, i64, ReplayWindow)>>>,
 }
 
 impl AuthManager {
@@ -40,21 +195,25 @@ impl AuthManager {
             log::info!("No password in config, authentication is disabled");
             None
         };
+        if password_hash.is_some() && config.password_verifier.is_none() {
+            log::warn!("Password has no login verifier, re-enter it in Settings to allow clients to log in");
+        }
 
         Self {
             password_hash: Arc::new(Mutex::new(password_hash)),
-            jwt_secret: Uuid::new_v4().to_string(),
-            sessions: Arc::new(Mutex::new(HashMap::new())),
+            verifier: Arc::new(Mutex::new(config.password_verifier)),
+            jwt_secret: Arc::new(load_or_create_secret()),
+            revocations: Arc::new(Mutex::new(RevocationList::load())),
             challenges: Arc::new(Mutex::new(HashMap::new())),
-            max_sessions: 10,
+            challenge_counters: Arc::new(ChallengeCounters::default()),
+            pairings: Arc::new(PairingStore::load()),
+            payload_keys: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
     /// 设置密码（首次设置）
-    pub fn set_password(&mut self, password: &str) -> Result<bool, Box<dyn std::error::Error>> {
-        if password.len() < 8 {
-            return Err("Password must be at least 8 characters long".into());
-        }
+    pub fn set_password(&self, password: &str) -> Result<bool, Box<dyn std::error::Error>> {
+        crate::config::get_config().password_policy.validate(password)?;
 
         let argon2 = Argon2::default();
         let salt = SaltString::generate(&mut OsRng);
@@ -63,15 +222,21 @@ impl AuthManager {
             Err(e) => return Err(format!("Failed to hash password: {}", e).into()),
         };
 
+        let verifier = PasswordVerifier::new(password);
+
         // 更新内存中的密码
         {
             let mut hash = self.password_hash.lock().unwrap();
             *hash = Some(password_hash.clone());
+            *self.verifier.lock().unwrap() = Some(verifier.clone());
         }
 
         // 保存到配置文件
         let mut config = crate::config::AppConfig::load();
         config.password_hash = Some(password_hash);
+        config.password_verifier = Some(verifier);
+        // 配对基于旧密码，修改密码后需要重新配对
+        self.pairings.clear();
         if let Err(e) = config.save() {
             log::error!("Failed to save password to config: {}", e);
             return Err(format!("Failed to save password: {}", e).into());
@@ -98,7 +263,7 @@ impl AuthManager {
 
     /// 修改密码
     pub fn change_password(
-        &mut self,
+        &self,
         old_password: &str,
         new_password: &str,
     ) -> Result<bool, Box<dyn std::error::Error>> {
@@ -106,10 +271,6 @@ impl AuthManager {
             return Err("Current password is incorrect".into());
         }
 
-        if new_password.len() < 8 {
-            return Err("New password must be at least 8 characters long".into());
-        }
-
         self.set_password(new_password)
     }
 
@@ -119,155 +280,421 @@ impl AuthManager {
         hash.is_some()
     }
 
+    /// 密码只有旧版本留下的哈希、还没有登录验证器，需要在本机重新输入一次密码
+    pub fn needs_verifier(&self) -> bool {
+        self.is_password_set() && self.verifier.lock().unwrap().is_none()
+    }
+
+    /// 在本机重新输入密码后补充登录验证器，密码与已保存的哈希不符时返回 Err
+    ///
+    /// 与 set_password 不同，这里不修改密码，已有的配对和会话继续有效
+    pub fn restore_verifier(&self, password: &str) -> Result<(), String> {
+        if !self.verify_password(password) {
+            return Err("Current password is incorrect".to_string());
+        }
+        let verifier = PasswordVerifier::new(password);
+        crate::config::update_config(|cfg| cfg.password_verifier = Some(verifier.clone()))
+            .map_err(|e| format!("Failed to save password: {}", e))?;
+        *self.verifier.lock().unwrap() = Some(verifier);
+        log::info!("Login verifier derived from the existing password");
+        Ok(())
+    }
+
     /// 清除密码
-    pub fn clear_password(&mut self) {
+    pub fn clear_password(&self) {
         let mut hash = self.password_hash.lock().unwrap();
         *hash = None;
+        *self.verifier.lock().unwrap() = None;
+        self.pairings.clear();
         log::info!("Password cleared");
     }
 
-    /// 生成认证挑战
-    pub fn generate_challenge(&self) -> String {
-        let challenge = Uuid::new_v4().to_string();
-        let expires_at = Utc::now() + Duration::minutes(5);
-
-        let auth_challenge = AuthChallenge {
-            challenge: challenge.clone(),
-            expires_at,
-        };
-
+    /// 生成认证挑战，client 为客户端IP
+    pub fn generate_challenge(&self, client: &str) -> Result<String, String> {
+        let now = Utc::now();
         let mut challenges = self.challenges.lock().unwrap();
-        challenges.insert(challenge.clone(), auth_challenge);
 
         // 清理过期挑战
-        challenges.retain(|_, v| v.expires_at > Utc::now());
+        let before = challenges.len();
+        challenges.retain(|_, v| v.expires_at > now);
+        let expired = before - challenges.len();
+        if expired > 0 {
+            self.challenge_counters
+                .expired
+                .fetch_add(expired as u64, Ordering::Relaxed);
+        }
+
+        let pending = challenges.values().filter(|c| c.client == client).count();
+        if pending >= MAX_CHALLENGES_PER_CLIENT {
+            self.challenge_counters.rejected.fetch_add(1, Ordering::Relaxed);
+            log::warn!("Too many pending challenges from {}", client);
+            return Err("Too many pending challenges, please try again later".to_string());
+        }
 
-        challenge
+        // 达到全局上限时淘汰最早过期的挑战
+        if challenges.len() >= MAX_CHALLENGES {
+            let oldest = challenges
+                .iter()
+                .min_by_key(|(_, c)| c.expires_at)
+                .map(|(k, _)| k.clone());
+            if let Some(k) = oldest {
+                challenges.remove(&k);
+                self.challenge_counters.evicted.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let challenge = Uuid::new_v4().to_string();
+        challenges.insert(
+            challenge.clone(),
+            AuthChallenge {
+                challenge: challenge.clone(),
+                expires_at: now + Duration::minutes(5),
+                client: client.to_string(),
+            },
+        );
+        self.challenge_counters.issued.fetch_add(1, Ordering::Relaxed);
+
+        Ok(challenge)
     }
 
-    /// 验证挑战响应并生成令牌
+    /// 客户端派生验证器所需的盐和迭代次数，随挑战一起返回
+    pub fn verifier_params(&self) -> Option<(String, u32)> {
+        let verifier = self.verifier.lock().unwrap();
+        verifier.as_ref().map(|v| (v.salt.clone(), v.iterations))
+    }
+
+    /// 获取挑战存储的统计信息
+    pub fn challenge_stats(&self) -> ChallengeStats {
+        let active = self.challenges.lock().unwrap().len();
+        let counters = &self.challenge_counters;
+        ChallengeStats {
+            active,
+            issued: counters.issued.load(Ordering::Relaxed),
+            expired: counters.expired.load(Ordering::Relaxed),
+            evicted: counters.evicted.load(Ordering::Relaxed),
+            rejected: counters.rejected.load(Ordering::Relaxed),
+        }
+    }
+
+    /// 验证挑战响应并生成令牌，device_id 为客户端设备ID（可选）
+    ///
+    /// 响应为以验证器为密钥对挑战计算的 HMAC，请求中不包含密码
     pub fn authenticate(
         &self,
         challenge: &str,
         response: &str,
-        password: &str,
+        device_id: Option<&str>,
     ) -> Result<AuthResponse, Box<dyn std::error::Error>> {
-        // 验证挑战是否有效
-        {
-            let challenges = self.challenges.lock().unwrap();
-            if let Some(auth_challenge) = challenges.get(challenge) {
-                if auth_challenge.expires_at < Utc::now() {
-                    return Err("Challenge has expired".into());
-                }
-            } else {
-                return Err("Invalid challenge".into());
-            }
-        }
+        self.take_challenge(challenge)?;
+        let key = self
+            .verifier_key()
+            .ok_or("Password must be re-entered on the device before clients can log in")?;
+        self.issue_token(challenge, &key, response, SCOPE_FULL, device_id)
+    }
 
-        // 验证密码
-        if !self.verify_password(password) {
-            return Err("Invalid password".into());
+    /// 使用配对密钥登录，响应为以配对密钥对挑战计算的 HMAC
+    pub fn authenticate_paired(
+        &self,
+        challenge: &str,
+        response: &str,
+        device_id: &str,
+    ) -> Result<AuthResponse, Box<dyn std::error::Error>> {
+        self.take_challenge(challenge)?;
+        let key = self.pairings.key(device_id).ok_or("Device is not paired")?;
+        let auth = self.issue_token(challenge, &key, response, SCOPE_PAIRED, Some(device_id))?;
+        self.pairings.touch(device_id);
+        Ok(auth)
+    }
+
+    /// 挑战只能使用一次，无论验证是否成功都删除
+    fn take_challenge(&self, challenge: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let auth_challenge = self.challenges.lock().unwrap().remove(challenge);
+        match auth_challenge {
+            Some(c) if c.expires_at < Utc::now() => Err("Challenge has expired".into()),
+            Some(_) => Ok(()),
+            None => Err("Invalid challenge".into()),
         }
+    }
 
-        // 验证HMAC响应
-        let expected_response = self.calculate_hmac(challenge, password);
-        if expected_response != response {
+    /// 验证 HMAC 响应并签发令牌
+    fn issue_token(
+        &self,
+        challenge: &str,
+        key: &[u8],
+        response: &str,
+        scope: &str,
+        device_id: Option<&str>,
+    ) -> Result<AuthResponse, Box<dyn std::error::Error>> {
+        if !Self::verify_hmac(challenge, key, response) {
             return Err("Invalid response".into());
         }
 
-        // 删除已使用的挑战
-        {
-            let mut challenges = self.challenges.lock().unwrap();
-            challenges.remove(challenge);
-        }
+        let token = self.generate_token(scope, device_id)?;
+        log::info!("New token issued");
 
-        // 生成令牌
-        let token = self.generate_token();
+        Ok(AuthResponse {
+            token,
+            expires_in: TOKEN_TTL_SECS as u64,
+            encryption: None,
+            key_id: None,
+        })
+    }
 
-        // 保存会话
-        {
-            let mut sessions = self.sessions.lock().unwrap();
-
-            // 如果会话数超过限制，删除最旧的
-            if sessions.len() >= self.max_sessions {
-                let oldest = sessions
-                    .iter()
-                    .min_by_key(|(_, s)| s.created_at)
-                    .map(|(k, _)| k.clone());
-                if let Some(k) = oldest {
-                    sessions.remove(&k);
-                }
-            }
+    fn verifier_key(&self) -> Option<Zeroizing<Vec<u8>>> {
+        let verifier = self.verifier.lock().unwrap();
+        verifier.as_ref().and_then(PasswordVerifier::key)
+    }
 
-            sessions.insert(
-                token.clone(),
-                Session {
-                    created_at: Utc::now(),
-                    last_access: Utc::now(),
-                    device_id: None,
-                },
-            );
+    /// 开始 SPAKE2 配对，message 为客户端消息（十六进制），client 为客户端IP，
+    /// 返回配对ID和服务端消息
+    pub fn start_pairing(
+        &self,
+        device_id: &str,
+        client: &str,
+        message: &str,
+    ) -> Result<(String, String), String> {
+        let verifier = self
+            .verifier_key()
+            .ok_or_else(|| "Password not set".to_string())?;
+        // 无法解码的消息与无效消息一样计入失败
+        let message = hex::decode(message).unwrap_or_default();
+        let (pairing_id, reply) = self.pairings.start(&verifier, device_id, client, &message)?;
+        Ok((pairing_id, hex::encode(reply)))
+    }
+
+    /// 完成配对，proof 为客户端的密钥确认（十六进制），返回设备ID和服务端的确认
+    pub fn confirm_pairing(&self, pairing_id: &str, proof: &str) -> Result<(String, String), String> {
+        let proof = hex::decode(proof).map_err(|_| "Invalid pairing proof".to_string())?;
+        let (device_id, server_proof) = self.pairings.confirm(pairing_id, &proof)?;
+        Ok((device_id, hex::encode(server_proof)))
+    }
+
+    pub fn paired_devices(&self) -> Vec<PairedDeviceInfo> {
+        self.pairings.list()
+    }
+
+    /// 取消配对，该设备需要重新输入密码
+    pub fn unpair(&self, device_id: &str) -> bool {
+        self.pairings.remove(device_id)
+    }
+
+    /// 查找已启用的静态 API 密钥
+    fn find_api_key(&self, key: &str) -> Option<ApiKeyConfig> {
+        if !key.starts_with(API_KEY_PREFIX) {
+            return None;
         }
+        let config = crate::config::get_config();
+        if !config.enable_api_keys {
+            return None;
+        }
+        let hash = ApiKeyConfig::hash_key(key);
+        config.api_keys.into_iter().find(|k| k.key_hash == hash)
+    }
 
-        log::info!("New session created");
+    /// 解析并校验令牌，返回载荷；签名无效、已过期或已吊销时返回 None
+    ///
+    /// 静态 API 密钥也可以代替令牌使用，删除密钥或关闭该功能后立即失效
+    pub fn decode_token(&self, token: &str) -> Option<Claims> {
+        if token.starts_with(API_KEY_PREFIX) {
+            return self.find_api_key(token).map(|api_key| Claims {
+                jti: format!("apikey:{}", api_key.name),
+                scope: SCOPE_API_KEY.to_string(),
+                device_id: None,
+                iat: api_key.created_at,
+                exp: i64::MAX,
+                gen: 0,
+            });
+        }
 
-        Ok(AuthResponse {
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.leeway = 0;
+        let claims = jsonwebtoken::decode::<Claims>(
             token,
-            expires_in: 3600, // 1小时
-        })
+            &DecodingKey::from_secret(&self.jwt_secret),
+            &validation,
+        )
+        .ok()?
+        .claims;
+
+        let revocations = self.revocations.lock().unwrap();
+        if claims.gen < revocations.generation || revocations.tokens.contains_key(&claims.jti) {
+            return None;
+        }
+        Some(claims)
+    }
+
+    /// 为令牌启用负载加密，密钥由验证器（或配对密钥）和本次登录的挑战派生
+    ///
+    /// 返回密钥ID（令牌的 jti），加密请求只携带密钥ID，令牌放在密文中
+    pub fn enable_payload_encryption(&self, token: &str, challenge: &str) -> Result<String, String> {
+        let claims = self
+            .decode_token(token)
+            .ok_or_else(|| "Invalid token".to_string())?;
+        // 配对登录的令牌使用配对密钥，其余使用验证器
+        let secret = if claims.scope == SCOPE_PAIRED {
+            claims.device_id.as_deref().and_then(|id| self.pairings.key(id))
+        } else {
+            self.verifier_key()
+        }
+        .ok_or_else(|| "Password not set".to_string())?;
+        let now = Utc::now().timestamp();
+        let mut keys = self.payload_keys.lock().unwrap();
+        keys.retain(|_, (_, exp, _)| *exp > now);
+        keys.insert(
+            claims.jti.clone(),
+            (derive_key(&secret, challenge), claims.exp, ReplayWindow::default()),
+        );
+        Ok(claims.jti)
+    }
+
+    /// 密钥ID对应的负载加密密钥，未协商或已过期时返回 None
+    pub fn payload_key(&self, key_id: &str) -> Option<PayloadKey> {
+        let now = Utc::now().timestamp();
+        let keys = self.payload_keys.lock().unwrap();
+        keys.get(key_id)
+            .filter(|(_, exp, _)| *exp > now)
+            .map(|(key, _, _)| key.clone())
+    }
+
+    /// 记录加密请求的计数，重复或过旧的计数（重放的请求）返回 false
+    pub fn accept_payload_counter(&self, key_id: &str, counter: u64) -> bool {
+        let mut keys = self.payload_keys.lock().unwrap();
+        keys.get_mut(key_id)
+            .map_or(false, |(_, _, window)| window.accept(counter))
+    }
+
+    /// 令牌是否协商了负载加密，协商后不再接受该令牌的明文请求
+    pub fn requires_payload_encryption(&self, token: &str) -> bool {
+        let Some(claims) = self.decode_token(token) else {
+            return false;
+        };
+        self.payload_keys.lock().unwrap().contains_key(&claims.jti)
+    }
+
+    /// 令牌对应的客户端身份，令牌无效时返回 None
+    pub fn identity(&self, token: &str) -> Option<String> {
+        self.decode_token(token).map(|claims| claims.identity())
     }
 
     /// 验证令牌
     pub fn verify_token(&self, token: &str) -> bool {
-        let mut sessions = self.sessions.lock().unwrap();
+        let valid = self.decode_token(token).is_some();
+        if valid {
+            crate::access::mark_active();
+        }
+        valid
+    }
 
-        if let Some(session) = sessions.get_mut(token) {
-            // 检查会话是否过期（1小时）
-            if Utc::now() - session.created_at > Duration::hours(1) {
-                sessions.remove(token);
-                return false;
-            }
+    /// 获取令牌所属客户端的权限限制，None 表示不受限制
+    pub fn permissions(&self, token: &str) -> Option<crate::config::ClientPermissions> {
+        self.permission_source(token).map(|(_, permissions)| permissions)
+    }
 
-            // 更新最后访问时间
-            session.last_access = Utc::now();
-            return true;
+    /// 令牌适用的客户端权限，以及这些权限所在的配置项（如 "client_permissions.default"）
+    pub fn permission_source(
+        &self,
+        token: &str,
+    ) -> Option<(String, crate::config::ClientPermissions)> {
+        if let Some(api_key) = self.find_api_key(token) {
+            return Some((
+                format!("api_keys.{}.permissions", api_key.name),
+                api_key.permissions,
+            ));
         }
-
-        false
+        let claims = self.decode_token(token)?;
+        client_permissions_for(&claims, &crate::config::get_config().client_permissions)
     }
 
     /// 吊销令牌
     pub fn revoke_token(&self, token: &str) -> bool {
-        let mut sessions = self.sessions.lock().unwrap();
-        sessions.remove(token).is_some()
+        // API 密钥没有过期时间，只能通过删除密钥撤销
+        let Some(claims) = self.decode_token(token).filter(|c| c.scope != SCOPE_API_KEY) else {
+            return false;
+        };
+
+        self.revoke_jti(&claims.jti, claims.exp);
+        crate::connections::disconnected(&claims.jti);
+        true
+    }
+
+    /// 按令牌ID吊销，exp 之后该条目可以清理
+    fn revoke_jti(&self, jti: &str, exp: i64) {
+        let now = Utc::now().timestamp();
+        let mut revocations = self.revocations.lock().unwrap();
+        // 顺便清理已经过期的条目
+        revocations.tokens.retain(|_, exp| *exp > now);
+        revocations.tokens.insert(jti.to_string(), exp);
+        revocations.save();
     }
 
-    /// 吊销所有会话
+    /// IP 加入黑名单后吊销从这些地址登录的会话，并断开它们的 WebSocket 连接，返回结束的连接数
+    pub fn revoke_blacklisted_sessions(&self) -> usize {
+        let ended = crate::connections::end_blacklisted();
+        for connection in &ended {
+            if let Some(expires_at) = connection.expires_at {
+                self.revoke_jti(&connection.id, expires_at.timestamp());
+            }
+            log::warn!(
+                "[Security] [{}] {:?} connection {} ended: IP is blacklisted",
+                connection.ip,
+                connection.kind,
+                connection.id
+            );
+        }
+        ended.len()
+    }
+
+    /// 吊销所有已签发的令牌
     pub fn revoke_all_sessions(&self) {
-        let mut sessions = self.sessions.lock().unwrap();
-        sessions.clear();
+        let mut revocations = self.revocations.lock().unwrap();
+        revocations.generation += 1;
+        revocations.tokens.clear();
+        revocations.save();
+        crate::connections::end_api_sessions();
         log::info!("All sessions revoked");
     }
 
-    /// 计算HMAC响应
-    fn calculate_hmac(&self, challenge: &str, password: &str) -> String {
-        let mut mac =
-            HmacSha256::new_from_slice(password.as_bytes()).expect("HMAC can take key of any size");
+    /// 生成新的 API 密钥，只在创建时返回明文
+    pub fn generate_api_key() -> Zeroizing<String> {
+        let mut bytes = Zeroizing::new([0u8; 32]);
+        rand::rngs::OsRng.fill_bytes(bytes.as_mut());
+        Zeroizing::new(format!("{}{}", API_KEY_PREFIX, hex::encode(bytes.as_ref())))
+    }
+
+    /// 以常量时间比较HMAC响应，避免通过响应时间推测正确值
+    fn verify_hmac(challenge: &str, key: &[u8], response: &str) -> bool {
+        let Ok(response) = hex::decode(response) else {
+            return false;
+        };
+        let mut mac = HmacSha256::new_from_slice(key).expect("HMAC can take key of any size");
         mac.update(challenge.as_bytes());
-        let result = mac.finalize();
-        let bytes = result.into_bytes();
-        hex::encode(bytes)
+        mac.verify_slice(&response).is_ok()
     }
 
-    /// 生成JWT令牌（简化版）
-    fn generate_token(&self) -> String {
-        Uuid::new_v4().to_string()
+    /// 签发JWT令牌
+    fn generate_token(&self, scope: &str, device_id: Option<&str>) -> Result<String, String> {
+        let now = Utc::now();
+        let claims = Claims {
+            jti: Uuid::new_v4().to_string(),
+            scope: scope.to_string(),
+            device_id: device_id.map(str::to_string),
+            iat: now.timestamp(),
+            exp: (now + Duration::seconds(TOKEN_TTL_SECS)).timestamp(),
+            gen: self.revocations.lock().unwrap().generation,
+        };
+        jsonwebtoken::encode(
+            &Header::new(Algorithm::HS256),
+            &claims,
+            &EncodingKey::from_secret(&self.jwt_secret),
+        )
+        .map_err(|e| format!("Failed to sign token: {}", e))
     }
 
-    /// 获取活跃会话数
-    pub fn get_session_count(&self) -> usize {
-        let sessions = self.sessions.lock().unwrap();
-        sessions.len()
+    /// 获取已吊销但尚未过期的令牌数
+    pub fn get_revoked_count(&self) -> usize {
+        let now = Utc::now().timestamp();
+        let revocations = self.revocations.lock().unwrap();
+        revocations.tokens.values().filter(|exp| **exp > now).count()
     }
 
     /// 重新加载密码（配置热重载时调用）
@@ -275,12 +702,119 @@ impl AuthManager {
         let config = crate::config::AppConfig::load();
         let mut hash = self.password_hash.lock().unwrap();
         *hash = config.password_hash;
+        *self.verifier.lock().unwrap() = config.password_verifier;
         log::info!("Password reloaded from config");
     }
 }
 
+// 手动实现 Debug，避免令牌和密钥出现在日志或调试输出中
+impl std::fmt::Debug for AuthManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuthManager")
+            .field("password_set", &self.is_password_set())
+            .field("revoked_tokens", &self.get_revoked_count())
+            .finish_non_exhaustive()
+    }
+}
+
 impl Default for AuthManager {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+impl AuthManager {
+    /// 只保存在内存中的实例，已设置密码但不读写配置目录
+    pub(crate) fn in_memory() -> Self {
+        let verifier = PasswordVerifier {
+            salt: "00".to_string(),
+            iterations: 1,
+            verifier: hex::encode([7u8; 32]),
+        };
+        Self {
+            password_hash: Arc::new(Mutex::new(Some("test".to_string()))),
+            verifier: Arc::new(Mutex::new(Some(verifier))),
+            jwt_secret: Arc::new(Zeroizing::new(vec![7u8; 32])),
+            revocations: Arc::new(Mutex::new(RevocationList::default())),
+            challenges: Arc::new(Mutex::new(HashMap::new())),
+            challenge_counters: Arc::new(ChallengeCounters::default()),
+            pairings: Arc::new(PairingStore::in_memory()),
+            payload_keys: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// 签发一个登录令牌
+    pub(crate) fn issue_test_token(&self) -> String {
+        self.generate_token(SCOPE_FULL, None).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ClientPermissions;
+
+    fn claims(scope: &str, device_id: Option<&str>) -> Claims {
+        Claims {
+            jti: "jti".to_string(),
+            scope: scope.to_string(),
+            device_id: device_id.map(str::to_string),
+            iat: 0,
+            exp: i64::MAX,
+            gen: 0,
+        }
+    }
+
+    fn only(scope: &str) -> ClientPermissions {
+        ClientPermissions {
+            scopes: vec![scope.to_string()],
+            ..ClientPermissions::default()
+        }
+    }
+
+    fn configured() -> HashMap<String, ClientPermissions> {
+        HashMap::from([
+            ("default".to_string(), only("logs")),
+            ("phone".to_string(), only("files")),
+        ])
+    }
+
+    #[test]
+    fn paired_device_gets_its_entry() {
+        let (key, permissions) =
+            client_permissions_for(&claims(SCOPE_PAIRED, Some("phone")), &configured()).unwrap();
+        assert_eq!(key, "client_permissions.phone");
+        assert!(permissions.allows_scope("files"));
+    }
+
+    #[test]
+    fn claimed_device_id_gets_default() {
+        let (key, permissions) =
+            client_permissions_for(&claims(SCOPE_FULL, Some("phone")), &configured()).unwrap();
+        assert_eq!(key, "client_permissions.default");
+        assert!(!permissions.allows_scope("files"));
+    }
+
+    #[test]
+    fn missing_device_id_gets_default() {
+        let (key, _) = client_permissions_for(&claims(SCOPE_FULL, None), &configured()).unwrap();
+        assert_eq!(key, "client_permissions.default");
+    }
+
+    #[test]
+    fn missing_entry_denies() {
+        let client_permissions = HashMap::from([("phone".to_string(), only("files"))]);
+        let (_, permissions) =
+            client_permissions_for(&claims(SCOPE_PAIRED, Some("tablet")), &client_permissions)
+                .unwrap();
+        for scope in crate::config::PERMISSION_SCOPES {
+            assert!(!permissions.allows_scope(scope));
+        }
+    }
+
+    #[test]
+    fn unconfigured_is_unrestricted() {
+        assert!(client_permissions_for(&claims(SCOPE_FULL, None), &HashMap::new()).is_none());
+    }
+}
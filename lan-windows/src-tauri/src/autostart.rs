@@ -0,0 +1,82 @@
+use serde::Serialize;
+use tauri::AppHandle;
+use tauri_plugin_autostart::ManagerExt;
+
+/// 开机自启动的配置与系统注册状态
+#[derive(Debug, Clone, Serialize)]
+pub struct AutostartStatus {
+    /// 配置中的 auto_start_on_boot
+    pub configured: bool,
+    /// 系统中是否实际注册了自启动
+    pub registered: bool,
+    pub in_sync: bool,
+    /// 读取或修改系统注册失败时的错误
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+fn status_with(app: &AppHandle, error: Option<String>) -> AutostartStatus {
+    let configured = crate::config::get_config().auto_start_on_boot;
+    let (registered, error) = match app.autolaunch().is_enabled() {
+        Ok(registered) => (registered, error),
+        Err(e) => (false, error.or_else(|| Some(e.to_string()))),
+    };
+    AutostartStatus {
+        configured,
+        registered,
+        in_sync: configured == registered,
+        error,
+    }
+}
+
+/// 读取当前状态，不做修改
+pub fn status(app: &AppHandle) -> AutostartStatus {
+    status_with(app, None)
+}
+
+/// 启动时以系统注册为准更新配置，用户可能在任务管理器等处关闭了自启动
+pub fn sync_config_from_system(app: &AppHandle) -> AutostartStatus {
+    let status = status(app);
+    if status.in_sync || status.error.is_some() {
+        return status;
+    }
+
+    log::info!(
+        "Autostart registration ({}) differs from config ({}), updating config",
+        status.registered,
+        status.configured
+    );
+    let registered = status.registered;
+    if let Err(e) = crate::config::update_config(|cfg| cfg.auto_start_on_boot = registered) {
+        return AutostartStatus {
+            error: Some(e.to_string()),
+            ..status
+        };
+    }
+    self::status(app)
+}
+
+/// 保存设置后以配置为准注册或取消自启动
+pub fn apply_config(app: &AppHandle) -> AutostartStatus {
+    let status = status(app);
+    if status.in_sync {
+        return status;
+    }
+
+    let autolaunch = app.autolaunch();
+    let result = if status.configured {
+        autolaunch.enable()
+    } else {
+        autolaunch.disable()
+    };
+    match result {
+        Ok(()) => {
+            log::info!("Autostart registration set to {}", status.configured);
+            self::status(app)
+        }
+        Err(e) => {
+            log::warn!("Failed to update autostart registration: {}", e);
+            status_with(app, Some(e.to_string()))
+        }
+    }
+}
@@ -0,0 +1,268 @@
+use axum::{
+    body::{to_bytes, Body, Bytes},
+    extract::Request,
+    http::{header::CONTENT_TYPE, HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Local};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::api::ClientIp;
+use crate::config::SECRET_FIELDS;
+
+/// 默认保留的请求数量
+pub const DEFAULT_CAPTURE_LIMIT: usize = 50;
+/// 最多保留的请求数量
+pub const MAX_CAPTURE_LIMIT: usize = 500;
+/// 单个请求/响应体保存的最大字节数，超出部分截断
+const MAX_CAPTURED_BODY: usize = 64 * 1024;
+/// 除配置中的 SECRET_FIELDS 外，请求和响应里需要脱敏的 JSON 字段和查询参数
+const SECRET_KEYS: &[&str] = &[
+    "password",
+    "old_password",
+    "new_password",
+    "key_hash",
+    "token",
+    "response",
+];
+const REDACTED: &str = "***";
+
+/// 一次完整的请求/响应记录
+#[derive(Debug, Clone, Serialize)]
+pub struct CapturedExchange {
+    pub timestamp: DateTime<Local>,
+    pub client_ip: String,
+    pub method: String,
+    pub path: String,
+    pub query: Option<String>,
+    pub request_content_type: Option<String>,
+    pub request_body: Option<String>,
+    pub status: u16,
+    pub response_content_type: Option<String>,
+    pub response_body: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// 调试抓包状态
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptureStatus {
+    pub enabled: bool,
+    pub limit: usize,
+    pub captured: usize,
+}
+
+struct CaptureBuffer {
+    limit: usize,
+    entries: VecDeque<CapturedExchange>,
+}
+
+// 默认关闭，只在排查问题时临时开启
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static BUFFER: Lazy<Mutex<CaptureBuffer>> = Lazy::new(|| {
+    Mutex::new(CaptureBuffer {
+        limit: DEFAULT_CAPTURE_LIMIT,
+        entries: VecDeque::new(),
+    })
+});
+
+/// 开启或关闭调试抓包，可同时调整保留的数量
+pub fn set_enabled(enabled: bool, limit: Option<usize>) -> CaptureStatus {
+    if let Ok(mut buffer) = BUFFER.lock() {
+        if let Some(limit) = limit {
+            buffer.limit = limit.clamp(1, MAX_CAPTURE_LIMIT);
+            while buffer.entries.len() > buffer.limit {
+                buffer.entries.pop_front();
+            }
+        }
+    }
+    ENABLED.store(enabled, Ordering::SeqCst);
+    log::info!(
+        "Debug capture {}",
+        if enabled { "enabled" } else { "disabled" }
+    );
+    status()
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+pub fn status() -> CaptureStatus {
+    let (limit, captured) = BUFFER
+        .lock()
+        .map(|buffer| (buffer.limit, buffer.entries.len()))
+        .unwrap_or((DEFAULT_CAPTURE_LIMIT, 0));
+    CaptureStatus {
+        enabled: is_enabled(),
+        limit,
+        captured,
+    }
+}
+
+/// 获取记录，最新的在前
+pub fn get_captures() -> Vec<CapturedExchange> {
+    match BUFFER.lock() {
+        Ok(buffer) => buffer.entries.iter().rev().cloned().collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+pub fn clear_captures() {
+    if let Ok(mut buffer) = BUFFER.lock() {
+        buffer.entries.clear();
+    }
+}
+
+fn push(exchange: CapturedExchange) {
+    if let Ok(mut buffer) = BUFFER.lock() {
+        while buffer.entries.len() >= buffer.limit {
+            buffer.entries.pop_front();
+        }
+        buffer.entries.push_back(exchange);
+    }
+}
+
+fn content_type(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string())
+}
+
+/// 只缓冲 JSON 和文本内容，二进制内容（文件、测速数据）只记录类型
+fn is_textual(content_type: Option<&str>) -> bool {
+    content_type.map_or(false, |ct| {
+        ct.starts_with("application/json") || ct.starts_with("text/plain")
+    })
+}
+
+fn is_secret(key: &str) -> bool {
+    SECRET_KEYS.contains(&key) || SECRET_FIELDS.contains(&key)
+}
+
+fn redact_json(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if is_secret(key) && !value.is_null() {
+                    *value = serde_json::Value::String(REDACTED.to_string());
+                } else {
+                    redact_json(value);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact_json),
+        _ => {}
+    }
+}
+
+/// 脱敏并转换为字符串，非 JSON 内容原样保留
+fn render_body(bytes: &Bytes) -> Option<String> {
+    if bytes.is_empty() {
+        return None;
+    }
+    let text = match serde_json::from_slice::<serde_json::Value>(bytes) {
+        Ok(mut json) => {
+            redact_json(&mut json);
+            json.to_string()
+        }
+        Err(_) => String::from_utf8_lossy(bytes).into_owned(),
+    };
+    if text.len() > MAX_CAPTURED_BODY {
+        let mut end = MAX_CAPTURED_BODY;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        return Some(format!(
+            "{}... ({} bytes truncated)",
+            &text[..end],
+            text.len() - end
+        ));
+    }
+    Some(text)
+}
+
+fn redact_query(query: &str) -> String {
+    query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((key, _)) if is_secret(key) => format!("{}={}", key, REDACTED),
+            _ => pair.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// 记录请求和响应内容的中间件，未开启时直接透传
+pub async fn capture_middleware(request: Request, next: Next) -> Response {
+    if !is_enabled() {
+        return next.run(request).await;
+    }
+
+    let started = Instant::now();
+    let client_ip = request
+        .extensions()
+        .get::<ClientIp>()
+        .map(|ip| ip.0.clone())
+        .unwrap_or_else(|| "unknown".to_string());
+    let method = request.method().to_string();
+    let path = request.uri().path().to_string();
+    let query = request.uri().query().map(redact_query);
+    let request_content_type = content_type(request.headers());
+
+    let (parts, body) = request.into_parts();
+    let (request, request_body) = if is_textual(request_content_type.as_deref()) {
+        let limit = crate::config::get_config().http_max_body_bytes;
+        match to_bytes(body, limit).await {
+            Ok(bytes) => {
+                let rendered = render_body(&bytes);
+                (Request::from_parts(parts, Body::from(bytes)), rendered)
+            }
+            Err(_) => return StatusCode::PAYLOAD_TOO_LARGE.into_response(),
+        }
+    } else {
+        (Request::from_parts(parts, body), None)
+    };
+
+    let response = next.run(request).await;
+
+    let status = response.status().as_u16();
+    let response_content_type = content_type(response.headers());
+    let (response, response_body) = if is_textual(response_content_type.as_deref()) {
+        let (parts, body) = response.into_parts();
+        match to_bytes(body, usize::MAX).await {
+            Ok(bytes) => {
+                let rendered = render_body(&bytes);
+                (Response::from_parts(parts, Body::from(bytes)), rendered)
+            }
+            Err(e) => {
+                log::warn!("Debug capture failed to read response body: {}", e);
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        }
+    } else {
+        (response, None)
+    };
+
+    push(CapturedExchange {
+        timestamp: Local::now(),
+        client_ip,
+        method,
+        path,
+        query,
+        request_content_type,
+        request_body,
+        status,
+        response_content_type,
+        response_body,
+        duration_ms: started.elapsed().as_millis() as u64,
+    });
+
+    response
+}
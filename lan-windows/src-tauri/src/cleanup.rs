@@ -0,0 +1,137 @@
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+/// 临时文件至少保留的时长，避免删除正在运行的程序刚写入的文件
+const TEMP_MIN_AGE: Duration = Duration::from_secs(3600);
+
+/// 清理结果
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CleanupReport {
+    pub bytes_freed: u64,
+    pub files_removed: u64,
+    /// 被占用或无权限而未能删除的文件数
+    pub failed: u64,
+}
+
+/// 清空回收站
+#[cfg(target_os = "windows")]
+pub fn empty_recycle_bin() -> Result<CleanupReport, String> {
+    use windows::core::PCWSTR;
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::Shell::{
+        SHEmptyRecycleBinW, SHQueryRecycleBinW, SHERB_NOCONFIRMATION, SHERB_NOPROGRESSUI,
+        SHERB_NOSOUND, SHQUERYRBINFO,
+    };
+
+    let mut info = SHQUERYRBINFO {
+        cbSize: std::mem::size_of::<SHQUERYRBINFO>() as u32,
+        ..Default::default()
+    };
+    unsafe { SHQueryRecycleBinW(PCWSTR::null(), &mut info) }.map_err(|e| e.to_string())?;
+
+    // 回收站为空时 SHEmptyRecycleBinW 会返回错误
+    if info.i64NumItems > 0 {
+        unsafe {
+            SHEmptyRecycleBinW(
+                HWND::default(),
+                PCWSTR::null(),
+                SHERB_NOCONFIRMATION | SHERB_NOPROGRESSUI | SHERB_NOSOUND,
+            )
+        }
+        .map_err(|e| e.to_string())?;
+    }
+
+    Ok(CleanupReport {
+        bytes_freed: info.i64Size.max(0) as u64,
+        files_removed: info.i64NumItems.max(0) as u64,
+        failed: 0,
+    })
+}
+
+/// 清空回收站（freedesktop 规范的 Trash 目录）
+#[cfg(target_os = "linux")]
+pub fn empty_recycle_bin() -> Result<CleanupReport, String> {
+    let trash = dirs::data_dir()
+        .ok_or_else(|| "Cannot locate the trash directory".to_string())?
+        .join("Trash");
+
+    let mut report = CleanupReport::default();
+    for sub in ["files", "info"] {
+        let dir = trash.join(sub);
+        if dir.is_dir() {
+            purge_dir(&dir, None, &mut report);
+        }
+    }
+    Ok(report)
+}
+
+/// 清空废纸篓
+#[cfg(target_os = "macos")]
+pub fn empty_recycle_bin() -> Result<CleanupReport, String> {
+    let trash = dirs::home_dir()
+        .ok_or_else(|| "Cannot locate the trash directory".to_string())?
+        .join(".Trash");
+
+    let mut report = CleanupReport::default();
+    if trash.is_dir() {
+        purge_dir(&trash, None, &mut report);
+    }
+    Ok(report)
+}
+
+/// 清理系统临时目录中超过一小时未修改的文件
+pub fn clean_temp() -> Result<CleanupReport, String> {
+    let temp = std::env::temp_dir();
+    if !temp.is_dir() {
+        return Err(format!("Temp directory '{}' does not exist", temp.display()));
+    }
+
+    let cutoff = SystemTime::now()
+        .checked_sub(TEMP_MIN_AGE)
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+    let mut report = CleanupReport::default();
+    purge_dir(&temp, Some(cutoff), &mut report);
+    Ok(report)
+}
+
+/// 删除目录下的内容（不删除目录本身），cutoff 不为空时只删除在此之前修改的文件
+fn purge_dir(dir: &Path, cutoff: Option<SystemTime>, report: &mut CleanupReport) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        report.failed += 1;
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        // 不跟随符号链接，只删除链接本身
+        let Ok(metadata) = fs::symlink_metadata(&path) else {
+            report.failed += 1;
+            continue;
+        };
+
+        if metadata.is_dir() {
+            purge_dir(&path, cutoff, report);
+            // 目录中仍有未删除的文件时会失败，忽略即可
+            let _ = fs::remove_dir(&path);
+            continue;
+        }
+
+        let expired = match cutoff {
+            Some(cutoff) => metadata.modified().map(|m| m < cutoff).unwrap_or(false),
+            None => true,
+        };
+        if !expired {
+            continue;
+        }
+
+        match fs::remove_file(&path) {
+            Ok(()) => {
+                report.bytes_freed += metadata.len();
+                report.files_removed += 1;
+            }
+            Err(_) => report.failed += 1,
+        }
+    }
+}
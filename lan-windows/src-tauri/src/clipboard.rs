@@ -0,0 +1,45 @@
+use once_cell::sync::OnceCell;
+use tauri::AppHandle;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+/// 通过 API 读写的剪贴板文本最大字节数
+pub const MAX_TEXT_BYTES: usize = 256 * 1024;
+
+// API 服务器没有 AppState，启动时保存应用句柄用于访问剪贴板
+static APP_HANDLE: OnceCell<AppHandle> = OnceCell::new();
+
+pub fn set_app_handle(handle: AppHandle) {
+    let _ = APP_HANDLE.set(handle);
+}
+
+fn app_handle() -> Result<&'static AppHandle, String> {
+    APP_HANDLE
+        .get()
+        .ok_or_else(|| "Clipboard is not available".to_string())
+}
+
+/// 读取剪贴板中的文本，剪贴板为空或不是文本时返回空字符串
+pub fn read_text() -> Result<String, String> {
+    let text = app_handle()?.clipboard().read_text().unwrap_or_default();
+    if text.len() > MAX_TEXT_BYTES {
+        return Err(format!(
+            "Clipboard text is larger than {} bytes",
+            MAX_TEXT_BYTES
+        ));
+    }
+    Ok(text)
+}
+
+/// 把文本写入剪贴板
+pub fn write_text(text: String) -> Result<(), String> {
+    if text.len() > MAX_TEXT_BYTES {
+        return Err(format!(
+            "Clipboard text is larger than {} bytes",
+            MAX_TEXT_BYTES
+        ));
+    }
+    app_handle()?
+        .clipboard()
+        .write_text(text)
+        .map_err(|e| format!("Failed to write clipboard: {}", e))
+}
@@ -1,8 +1,11 @@
 use crate::config::get_config;
-use crate::models::{CommandResult, SystemInfo};
+use crate::models::{CommandResult, GpuMetrics, LiveMetrics, PresenceInfo, StaticSystemInfo, SystemInfo};
 use encoding_rs::GBK;
+use once_cell::sync::Lazy;
 use std::process::Command;
-use std::time::Instant;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
@@ -10,6 +13,13 @@ use std::os::windows::process::CommandExt;
 #[cfg(target_os = "windows")]
 const CREATE_NO_WINDOW: u32 = 0x08000000;
 
+/// 能读到实时显卡数据（nvidia-smi）时的刷新间隔
+const GPU_SAMPLE_INTERVAL: Duration = Duration::from_secs(5);
+
+// 最近一次的显卡指标及采样时间，读取需要启动外部进程，不在每次请求时执行
+static GPU_SAMPLE: Lazy<Mutex<Option<(Vec<GpuMetrics>, Instant)>>> = Lazy::new(|| Mutex::new(None));
+static GPU_REFRESHING: AtomicBool = AtomicBool::new(false);
+
 /// 将 GBK 编码的字节转换为 UTF-8 字符串
 /// 如果转换失败，则返回原始字节的 lossy 转换
 fn decode_gbk_to_utf8(bytes: &[u8]) -> String {
@@ -41,6 +51,7 @@ fn set_utf8_encoding() {
 #[cfg(not(target_os = "windows"))]
 fn set_utf8_encoding() {}
 
+#[derive(Clone)]
 pub struct CommandExecutor {
     timeout_seconds: u64,
 }
@@ -52,14 +63,6 @@ impl CommandExecutor {
         }
     }
 
-    /// 获取当前的白名单（从配置读取）
-    fn get_whitelist(&self) -> Vec<String> {
-        let config = get_config();
-        // 白名单只包含显式启用的命令（内置命令的勾选状态）
-        // 自定义命令是否可执行取决于它是否在 command_whitelist 中
-        config.command_whitelist.clone()
-    }
-
     /// 执行命令
     pub fn execute(
         &self,
@@ -78,40 +81,43 @@ impl CommandExecutor {
         log::info!("Executing command: {}, is_custom: {}, whitelist: {:?}, custom_commands: {:?}", 
             command_type, is_custom_command, config.command_whitelist, config.custom_commands);
 
-        if is_custom_command {
-            // 自定义命令：先检查 "custom" 总开关
-            if !self.is_allowed("custom") {
-                log::warn!("Custom commands are disabled. 'custom' not in whitelist: {:?}", config.command_whitelist);
-                return Ok(CommandResult {
-                    success: false,
-                    stdout: String::new(),
-                    stderr: "Custom commands are disabled. Please enable 'Custom Commands' in the whitelist.".to_string(),
-                    exit_code: Some(-1),
-                    execution_time_ms: start.elapsed().as_millis() as u64,
-                });
-            }
-            // 再检查具体命令是否在白名单中
-            if !self.is_allowed(command_type) {
-                log::warn!("Command '{}' is not in whitelist: {:?}", command_type, config.command_whitelist);
-                return Ok(CommandResult {
-                    success: false,
-                    stdout: String::new(),
-                    stderr: format!("Command '{}' is not in whitelist. Current whitelist: {:?}", command_type, config.command_whitelist),
-                    exit_code: Some(-1),
-                    execution_time_ms: start.elapsed().as_millis() as u64,
-                });
-            }
-        } else {
-            // 内置命令：直接检查是否在白名单中
-            if !self.is_allowed(command_type) {
-                return Ok(CommandResult {
+        // 自定义命令需要同时启用 "custom" 总开关和该命令本身
+        let decision = crate::policy::evaluate_command(command_type, None, &config);
+        if !decision.allowed {
+            log::warn!("{} (whitelist: {:?})", decision.reason, config.command_whitelist);
+            return Ok(CommandResult {
+                success: false,
+                stdout: String::new(),
+                stderr: decision.reason,
+                exit_code: Some(-1),
+                execution_time_ms: start.elapsed().as_millis() as u64,
+            });
+        }
+
+        // 在进程内完成、输出结构化 JSON 结果的内置命令
+        let report = match command_type {
+            "empty-recycle-bin" => Some(crate::cleanup::empty_recycle_bin()),
+            "clean-temp" => Some(crate::cleanup::clean_temp()),
+            _ => None,
+        };
+        if let Some(report) = report {
+            let execution_time_ms = start.elapsed().as_millis() as u64;
+            return Ok(match report {
+                Ok(report) => CommandResult {
+                    success: true,
+                    stdout: serde_json::to_string(&report).unwrap_or_default(),
+                    stderr: String::new(),
+                    exit_code: Some(0),
+                    execution_time_ms,
+                },
+                Err(e) => CommandResult {
                     success: false,
                     stdout: String::new(),
-                    stderr: format!("Command '{}' is not in whitelist", command_type),
+                    stderr: e,
                     exit_code: Some(-1),
-                    execution_time_ms: start.elapsed().as_millis() as u64,
-                });
-            }
+                    execution_time_ms,
+                },
+            });
         }
 
         let result = match command_type {
@@ -122,6 +128,9 @@ impl CommandExecutor {
             "systeminfo" => self.execute_systeminfo(),
             "tasklist" => self.execute_tasklist(),
             "wmic" => self.execute_wmic(args),
+            "flushdns" => self.execute_flushdns(),
+            "renew-dhcp" => self.execute_renew_dhcp(args),
+            "reset-network-adapter" => self.execute_reset_network_adapter(args),
             _ => {
                 if is_custom_command {
                     self.execute_custom(command_type, args)
@@ -163,12 +172,6 @@ impl CommandExecutor {
         }
     }
 
-    /// 检查命令是否允许执行
-    fn is_allowed(&self, command: &str) -> bool {
-        let whitelist = self.get_whitelist();
-        whitelist.iter().any(|c| c == command)
-    }
-
     /// 执行关机命令
     fn execute_shutdown(
         &self,
@@ -252,7 +255,8 @@ impl CommandExecutor {
 
         #[cfg(target_os = "linux")]
         {
-            Command::new("systemctl").arg("suspend").output()
+            // 没有 systemd 的发行版上 loginctl 由 elogind 提供
+            run_first_success(&[("systemctl", &["suspend"]), ("loginctl", &["suspend"])])
         }
 
         #[cfg(target_os = "macos")]
@@ -273,17 +277,47 @@ impl CommandExecutor {
 
         #[cfg(target_os = "linux")]
         {
-            // Try gnome-screensaver-command or loginctl
-            Command::new("loginctl").arg("lock-session").output()
+            // 优先通过会话总线通知屏保锁屏；以服务方式运行、没有会话总线时
+            // 回退到 loginctl，lock-sessions 会锁定所有会话
+            run_first_success(&[
+                (
+                    "dbus-send",
+                    &[
+                        "--session",
+                        "--type=method_call",
+                        "--print-reply",
+                        "--dest=org.freedesktop.ScreenSaver",
+                        "/org/freedesktop/ScreenSaver",
+                        "org.freedesktop.ScreenSaver.Lock",
+                    ],
+                ),
+                (
+                    "dbus-send",
+                    &[
+                        "--session",
+                        "--type=method_call",
+                        "--print-reply",
+                        "--dest=org.gnome.ScreenSaver",
+                        "/org/gnome/ScreenSaver",
+                        "org.gnome.ScreenSaver.Lock",
+                    ],
+                ),
+                ("loginctl", &["lock-session"]),
+                ("loginctl", &["lock-sessions"]),
+            ])
         }
 
         #[cfg(target_os = "macos")]
         {
-            Command::new(
-                "/System/Library/CoreServices/Menu Extras/User.menu/Contents/Resources/CGSession",
-            )
-            .arg("-suspend")
-            .output()
+            // CGSession 在 macOS 11 之后被移除；新系统上关闭显示器，
+            // 在"立即要求密码"开启时即为锁屏
+            run_first_success(&[
+                (
+                    "/System/Library/CoreServices/Menu Extras/User.menu/Contents/Resources/CGSession",
+                    &["-suspend"],
+                ),
+                ("pmset", &["displaysleepnow"]),
+            ])
         }
     }
 
@@ -300,12 +334,12 @@ impl CommandExecutor {
 
         #[cfg(target_os = "linux")]
         {
-            Command::new("uname").args(["-a"]).output()
+            Ok(text_output(linux_system_report()))
         }
 
         #[cfg(target_os = "macos")]
         {
-            Command::new("uname").args(["-a"]).output()
+            Ok(text_output(macos_system_report()))
         }
     }
 
@@ -320,12 +354,17 @@ impl CommandExecutor {
 
         #[cfg(target_os = "linux")]
         {
-            Command::new("ps").args(&["aux"]).output()
+            Command::new("ps")
+                .args(["-eo", "pid,user,pcpu,pmem,rss,etime,comm", "--sort=-pcpu"])
+                .output()
         }
 
         #[cfg(target_os = "macos")]
         {
-            Command::new("ps").args(&["aux"]).output()
+            // BSD ps 不支持 --sort，-r 按 CPU 使用率排序
+            Command::new("ps")
+                .args(["-Arco", "pid,user,pcpu,pmem,rss,etime,comm"])
+                .output()
         }
     }
 
@@ -354,6 +393,108 @@ impl CommandExecutor {
         }
     }
 
+    /// 清除 DNS 缓存
+    fn execute_flushdns(&self) -> Result<std::process::Output, std::io::Error> {
+        #[cfg(target_os = "windows")]
+        {
+            Command::new("ipconfig")
+                .arg("/flushdns")
+                .creation_flags(CREATE_NO_WINDOW)
+                .output()
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            Command::new("resolvectl").arg("flush-caches").output()
+        }
+
+        #[cfg(target_os = "macos")]
+        {
+            Command::new("dscacheutil").arg("-flushcache").output()
+        }
+    }
+
+    /// 重新获取 DHCP 租约，可选参数为网卡名称
+    fn execute_renew_dhcp(
+        &self,
+        args: Option<&[String]>,
+    ) -> Result<std::process::Output, std::io::Error> {
+        let adapter = match args.and_then(|a| a.first()) {
+            Some(name) => Some(validate_adapter_name(name)?),
+            None => None,
+        };
+
+        #[cfg(target_os = "windows")]
+        {
+            let mut cmd = Command::new("ipconfig");
+            cmd.arg("/renew");
+            if let Some(adapter) = adapter {
+                cmd.arg(adapter);
+            }
+            cmd.creation_flags(CREATE_NO_WINDOW).output()
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            let mut cmd = Command::new("dhclient");
+            if let Some(adapter) = adapter {
+                cmd.arg(adapter);
+            }
+            cmd.output()
+        }
+
+        #[cfg(target_os = "macos")]
+        {
+            Command::new("ipconfig")
+                .args(["set", adapter.as_deref().unwrap_or("en0"), "DHCP"])
+                .output()
+        }
+    }
+
+    /// 禁用并重新启用指定网卡，参数为网卡名称
+    fn execute_reset_network_adapter(
+        &self,
+        args: Option<&[String]>,
+    ) -> Result<std::process::Output, std::io::Error> {
+        let adapter = args.and_then(|a| a.first()).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Adapter name is required",
+            )
+        })?;
+        let adapter = validate_adapter_name(adapter)?;
+
+        #[cfg(target_os = "windows")]
+        {
+            Command::new("powershell")
+                .args([
+                    "-NoProfile",
+                    "-Command",
+                    &format!("Restart-NetAdapter -Name '{}' -Confirm:$false", adapter),
+                ])
+                .creation_flags(CREATE_NO_WINDOW)
+                .output()
+        }
+
+        #[cfg(not(target_os = "windows"))]
+        {
+            #[cfg(target_os = "linux")]
+            let toggle = |state: &str| {
+                Command::new("ip")
+                    .args(["link", "set", "dev", adapter.as_str(), state])
+                    .output()
+            };
+            #[cfg(target_os = "macos")]
+            let toggle = |state: &str| Command::new("ifconfig").args([adapter.as_str(), state]).output();
+
+            let down = toggle("down")?;
+            if !down.status.success() {
+                return Ok(down);
+            }
+            toggle("up")
+        }
+    }
+
     /// 执行自定义命令
     fn execute_custom(
         &self,
@@ -387,6 +528,26 @@ impl CommandExecutor {
     }
 }
 
+/// 校验网卡名称，避免把参数注入到命令中
+fn validate_adapter_name(name: &str) -> Result<String, std::io::Error> {
+    let name = name.trim();
+    let valid = !name.is_empty()
+        && name.len() <= 128
+        && !name.starts_with('-')
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_' | '.' | '#' | '(' | ')'));
+
+    if valid {
+        Ok(name.to_string())
+    } else {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Invalid adapter name '{}'", name),
+        ))
+    }
+}
+
 impl Default for CommandExecutor {
     fn default() -> Self {
         Self::new()
@@ -395,6 +556,12 @@ impl Default for CommandExecutor {
 
 /// 获取系统信息
 pub fn get_system_info() -> Result<SystemInfo, String> {
+    let info = get_static_system_info()?;
+    Ok(SystemInfo::from_parts(info, get_live_metrics()))
+}
+
+/// 获取很少变化的系统信息（操作系统、主机名、架构、内存总量）
+pub fn get_static_system_info() -> Result<StaticSystemInfo, String> {
     // 设置 UTF-8 编码
     set_utf8_encoding();
 
@@ -412,29 +579,130 @@ pub fn get_system_info() -> Result<SystemInfo, String> {
     #[cfg(target_os = "macos")]
     let (os_type, os_version) = { ("macOS".to_string(), get_macos_version()) };
 
-    let architecture = std::env::consts::ARCH.to_string();
+    let architecture = get_architecture();
 
     // 获取内存信息（简化版）
-    let (memory_total, memory_used) = get_memory_info();
-
-    // 获取CPU使用率（简化版）
-    let cpu_usage = get_cpu_usage();
+    let (memory_total, _) = get_memory_info();
 
-    // 获取系统运行时间
-    let uptime_seconds = get_uptime();
-
-    Ok(SystemInfo {
+    Ok(StaticSystemInfo {
         os_type,
         os_version,
         hostname,
         architecture,
-        cpu_usage,
         memory_total,
-        memory_used,
-        uptime_seconds,
     })
 }
 
+/// 获取实时指标（CPU、已用内存、运行时间）
+pub fn get_live_metrics() -> LiveMetrics {
+    let (_, memory_used) = get_memory_info();
+
+    LiveMetrics {
+        // 获取CPU使用率（简化版）
+        cpu_usage: get_cpu_usage(),
+        memory_used,
+        // 获取系统运行时间
+        uptime_seconds: get_uptime(),
+        gpus: get_gpu_metrics(),
+    }
+}
+
+/// 获取工作站锁定状态和用户空闲时间
+pub fn get_presence() -> PresenceInfo {
+    PresenceInfo {
+        locked: is_workstation_locked(),
+        idle_seconds: get_idle_seconds(),
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn is_workstation_locked() -> Option<bool> {
+    use windows::Win32::Foundation::BOOL;
+    use windows::Win32::System::StationsAndDesktops::{
+        CloseDesktop, OpenInputDesktop, SwitchDesktop, DESKTOP_CONTROL_FLAGS,
+        DESKTOP_SWITCHDESKTOP,
+    };
+
+    // 锁屏时输入桌面切换为安全桌面，普通进程无法打开或切换到它
+    unsafe {
+        match OpenInputDesktop(DESKTOP_CONTROL_FLAGS(0), BOOL(0), DESKTOP_SWITCHDESKTOP) {
+            Ok(desktop) => {
+                let locked = SwitchDesktop(desktop).is_err();
+                let _ = CloseDesktop(desktop);
+                Some(locked)
+            }
+            Err(_) => Some(true),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn is_workstation_locked() -> Option<bool> {
+    let session = std::env::var("XDG_SESSION_ID").ok()?;
+    Command::new("loginctl")
+        .args(["show-session", &session, "-p", "LockedHint", "--value"])
+        .output()
+        .ok()
+        .and_then(|o| String::from_utf8(o.stdout).ok())
+        .and_then(|s| match s.trim() {
+            "yes" => Some(true),
+            "no" => Some(false),
+            _ => None,
+        })
+}
+
+#[cfg(target_os = "macos")]
+fn is_workstation_locked() -> Option<bool> {
+    None
+}
+
+#[cfg(target_os = "windows")]
+fn get_idle_seconds() -> Option<u64> {
+    use windows::Win32::System::SystemInformation::GetTickCount;
+    use windows::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+
+    let mut info = LASTINPUTINFO {
+        cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+        dwTime: 0,
+    };
+    unsafe {
+        if !GetLastInputInfo(&mut info).as_bool() {
+            return None;
+        }
+        // GetTickCount 约 49.7 天回绕一次，使用 wrapping_sub 计算差值
+        Some(GetTickCount().wrapping_sub(info.dwTime) as u64 / 1000)
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn get_idle_seconds() -> Option<u64> {
+    // 需要安装 xprintidle（X11），输出为毫秒
+    Command::new("xprintidle")
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .and_then(|o| String::from_utf8(o.stdout).ok())
+        .and_then(|s| s.trim().parse::<u64>().ok())
+        .map(|ms| ms / 1000)
+}
+
+#[cfg(target_os = "macos")]
+fn get_idle_seconds() -> Option<u64> {
+    // HIDIdleTime 单位为纳秒
+    Command::new("ioreg")
+        .args(["-c", "IOHIDSystem", "-d", "4"])
+        .output()
+        .ok()
+        .and_then(|o| String::from_utf8(o.stdout).ok())
+        .and_then(|s| {
+            s.lines()
+                .find(|l| l.contains("\"HIDIdleTime\""))
+                .and_then(|l| l.split('=').nth(1))
+                .and_then(|v| v.trim().parse::<u64>().ok())
+        })
+        .map(|ns| ns / 1_000_000_000)
+}
+
 #[cfg(target_os = "windows")]
 fn get_windows_version() -> String {
     Command::new("cmd")
@@ -453,21 +721,208 @@ fn get_windows_version() -> String {
 
 #[cfg(target_os = "linux")]
 fn get_linux_version() -> String {
-    std::fs::read_to_string("/etc/os-release")
-        .ok()
-        .and_then(|content| {
-            content
-                .lines()
-                .find(|l| l.starts_with("PRETTY_NAME="))
-                .map(|l| {
-                    l.trim_start_matches("PRETTY_NAME=")
-                        .trim_matches('"')
-                        .to_string()
-                })
+    let read = |path: &str| std::fs::read_to_string(path).ok();
+    // os-release 在部分旧系统上只存在于 /usr/lib，最后回退到内核版本
+    read("/etc/os-release")
+        .or_else(|| read("/usr/lib/os-release"))
+        .and_then(|content| parse_key_value(&content, "PRETTY_NAME"))
+        .or_else(|| {
+            read("/etc/lsb-release")
+                .and_then(|content| parse_key_value(&content, "DISTRIB_DESCRIPTION"))
         })
+        .or_else(|| read("/proc/sys/kernel/osrelease").map(|v| format!("Linux {}", v.trim())))
         .unwrap_or_else(|| "Unknown".to_string())
 }
 
+/// 解析 KEY=value 格式的文件（os-release、lsb-release），去掉引号
+#[cfg(any(target_os = "linux", test))]
+fn parse_key_value(content: &str, key: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let (k, v) = line.split_once('=')?;
+        (k.trim() == key).then(|| v.trim().trim_matches('"').trim_matches('\'').to_string())
+    })
+}
+
+/// 从 /proc/meminfo 解析总内存和已用内存（MB）
+#[cfg(any(target_os = "linux", test))]
+fn parse_meminfo(content: &str) -> Option<(u64, u64)> {
+    let field = |name: &str| {
+        content
+            .lines()
+            .find(|l| l.starts_with(name))
+            .and_then(|l| l.split_whitespace().nth(1))
+            .and_then(|s| s.parse::<u64>().ok())
+    };
+    let total = field("MemTotal:")?;
+    // 3.14 之前的内核没有 MemAvailable，用空闲+缓存估算
+    let available = field("MemAvailable:").or_else(|| {
+        Some(field("MemFree:")? + field("Buffers:").unwrap_or(0) + field("Cached:").unwrap_or(0))
+    })?;
+    Some((total / 1024, total.saturating_sub(available) / 1024))
+}
+
+/// 从 /proc/stat 的 cpu 汇总行解析 (空闲, 总计) 时间片
+#[cfg(any(target_os = "linux", test))]
+fn parse_cpu_times(stat: &str) -> Option<(u64, u64)> {
+    let line = stat.lines().find(|l| l.starts_with("cpu "))?;
+    let values: Vec<u64> = line
+        .split_whitespace()
+        .skip(1)
+        .filter_map(|v| v.parse().ok())
+        .collect();
+    if values.len() < 4 {
+        return None;
+    }
+    // idle + iowait
+    let idle = values[3] + values.get(4).copied().unwrap_or(0);
+    // guest 时间已包含在 user/nice 中，不重复计算
+    let total = values.iter().take(8).sum();
+    Some((idle, total))
+}
+
+/// 从 /proc/cpuinfo 解析 CPU 型号（"model name\t: ..."）
+#[cfg(any(target_os = "linux", test))]
+fn parse_cpu_model(content: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        (key.trim() == "model name").then(|| value.trim().to_string())
+    })
+}
+
+/// 根据两次采样计算 CPU 使用率（百分比）
+#[cfg(any(target_os = "linux", test))]
+fn cpu_usage_between(previous: (u64, u64), current: (u64, u64)) -> f32 {
+    let total = current.1.saturating_sub(previous.1);
+    let idle = current.0.saturating_sub(previous.0);
+    if total == 0 {
+        return 0.0;
+    }
+    (total.saturating_sub(idle) as f32 / total as f32 * 100.0).clamp(0.0, 100.0)
+}
+
+/// 汇总 Linux 系统信息，作为 systeminfo 命令的输出
+#[cfg(target_os = "linux")]
+fn linux_system_report() -> String {
+    let read = |path: &str| std::fs::read_to_string(path).unwrap_or_default();
+    let cpu_model =
+        parse_cpu_model(&read("/proc/cpuinfo")).unwrap_or_else(|| "Unknown".to_string());
+    let cpu_count = std::thread::available_parallelism().map_or(0, |n| n.get());
+    let (memory_total, memory_used) = get_memory_info();
+
+    format_report(&[
+        ("Host Name", report_hostname()),
+        ("OS Name", get_linux_version()),
+        (
+            "Kernel",
+            read("/proc/sys/kernel/osrelease").trim().to_string(),
+        ),
+        ("Architecture", get_architecture()),
+        (
+            "Processor",
+            format!("{} ({} logical)", cpu_model, cpu_count),
+        ),
+        ("Total Memory", format!("{} MB", memory_total)),
+        ("Used Memory", format!("{} MB", memory_used)),
+        ("Uptime", format_uptime(get_uptime())),
+    ])
+}
+
+/// 汇总 macOS 系统信息，作为 systeminfo 命令的输出
+#[cfg(target_os = "macos")]
+fn macos_system_report() -> String {
+    let sysctl = |name: &str| sysctl_value(name).unwrap_or_else(|| "Unknown".to_string());
+    let build = Command::new("sw_vers")
+        .arg("-buildVersion")
+        .output()
+        .ok()
+        .and_then(|o| String::from_utf8(o.stdout).ok())
+        .map(|s| s.trim().to_string())
+        .unwrap_or_default();
+    let (memory_total, memory_used) = get_memory_info();
+
+    format_report(&[
+        ("Host Name", report_hostname()),
+        (
+            "OS Name",
+            format!("macOS {} ({})", get_macos_version(), build),
+        ),
+        ("Model", sysctl("hw.model")),
+        ("Kernel", format!("Darwin {}", sysctl("kern.osrelease"))),
+        ("Architecture", get_architecture()),
+        (
+            "Processor",
+            format!(
+                "{} ({} logical)",
+                sysctl("machdep.cpu.brand_string"),
+                sysctl("hw.logicalcpu")
+            ),
+        ),
+        ("Total Memory", format!("{} MB", memory_total)),
+        ("Used Memory", format!("{} MB", memory_used)),
+        ("Memory Pressure", get_memory_pressure()),
+        ("Uptime", format_uptime(get_uptime())),
+    ])
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn report_hostname() -> String {
+    hostname::get()
+        .ok()
+        .and_then(|h| h.into_string().ok())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn format_uptime(uptime: u64) -> String {
+    format!(
+        "{}d {}h {}m",
+        uptime / 86400,
+        uptime % 86400 / 3600,
+        uptime % 3600 / 60
+    )
+}
+
+/// 按 "名称: 值" 对齐输出，与 Windows systeminfo 的格式相近
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn format_report(fields: &[(&str, String)]) -> String {
+    fields
+        .iter()
+        .map(|(name, value)| format!("{:<16} {}", format!("{}:", name), value))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// 依次尝试多个命令，返回第一个成功的结果；全部失败时返回最后一个结果
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn run_first_success(
+    candidates: &[(&str, &[&str])],
+) -> Result<std::process::Output, std::io::Error> {
+    let mut last = Err(std::io::Error::new(
+        std::io::ErrorKind::NotFound,
+        "No command available",
+    ));
+    for (program, args) in candidates {
+        let result = Command::new(program).args(*args).output();
+        if matches!(result, Ok(ref output) if output.status.success()) {
+            return result;
+        }
+        log::debug!("'{}' failed, trying next fallback", program);
+        last = result;
+    }
+    last
+}
+
+/// 将进程内生成的文本包装为命令输出
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn text_output(text: String) -> std::process::Output {
+    use std::os::unix::process::ExitStatusExt;
+    std::process::Output {
+        status: std::process::ExitStatus::from_raw(0),
+        stdout: text.into_bytes(),
+        stderr: Vec::new(),
+    }
+}
+
 #[cfg(target_os = "macos")]
 fn get_macos_version() -> String {
     Command::new("sw_vers")
@@ -479,6 +934,82 @@ fn get_macos_version() -> String {
         .unwrap_or_else(|| "Unknown".to_string())
 }
 
+#[cfg(target_os = "macos")]
+fn sysctl_value(name: &str) -> Option<String> {
+    Command::new("sysctl")
+        .args(["-n", name])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .and_then(|o| String::from_utf8(o.stdout).ok())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+/// 系统架构；在 Apple Silicon 上通过 Rosetta 运行 x86_64 版本时报告实际硬件架构
+fn get_architecture() -> String {
+    #[cfg(target_os = "macos")]
+    {
+        if sysctl_value("sysctl.proc_translated").as_deref() == Some("1") {
+            return format!("aarch64 ({} via Rosetta)", std::env::consts::ARCH);
+        }
+    }
+    std::env::consts::ARCH.to_string()
+}
+
+/// 内存压力等级和剩余内存百分比，与活动监视器的"内存压力"对应
+#[cfg(target_os = "macos")]
+fn get_memory_pressure() -> String {
+    let level = match sysctl_value("kern.memorystatus_vm_pressure_level").as_deref() {
+        Some("1") => "Normal",
+        Some("2") => "Warning",
+        Some("4") => "Critical",
+        _ => "Unknown",
+    };
+    match sysctl_value("kern.memorystatus_level") {
+        Some(free) => format!("{} ({}% free)", level, free),
+        None => level.to_string(),
+    }
+}
+
+/// 从 vm_stat 输出计算已用内存（字节）：活跃 + 联动 + 压缩，与活动监视器一致
+#[cfg(any(target_os = "macos", test))]
+fn parse_vm_stat(content: &str) -> Option<u64> {
+    let page_size = content
+        .lines()
+        .next()?
+        .split("page size of ")
+        .nth(1)?
+        .split_whitespace()
+        .next()?
+        .parse::<u64>()
+        .ok()?;
+    let pages = |name: &str| {
+        content
+            .lines()
+            .find(|l| l.starts_with(name))
+            .and_then(|l| l.split(':').nth(1))
+            .and_then(|v| v.trim().trim_end_matches('.').parse::<u64>().ok())
+    };
+    let used = pages("Pages active")?
+        + pages("Pages wired down")?
+        + pages("Pages occupied by compressor").unwrap_or(0);
+    Some(used * page_size)
+}
+
+/// 解析 `sysctl -n kern.boottime` 的输出，例如 "{ sec = 1700000000, usec = 0 } ..."
+#[cfg(any(target_os = "macos", test))]
+fn parse_boottime(output: &str) -> Option<u64> {
+    output
+        .split("sec =")
+        .nth(1)?
+        .split(',')
+        .next()?
+        .trim()
+        .parse()
+        .ok()
+}
+
 fn get_memory_info() -> (u64, u64) {
     #[cfg(target_os = "windows")]
     {
@@ -506,44 +1037,171 @@ fn get_memory_info() -> (u64, u64) {
     {
         std::fs::read_to_string("/proc/meminfo")
             .ok()
-            .and_then(|content| {
-                let total = content
-                    .lines()
-                    .find(|l| l.starts_with("MemTotal:"))
-                    .and_then(|l| l.split_whitespace().nth(1))
-                    .and_then(|s| s.parse::<u64>().ok());
-                let available = content
-                    .lines()
-                    .find(|l| l.starts_with("MemAvailable:"))
-                    .and_then(|l| l.split_whitespace().nth(1))
-                    .and_then(|s| s.parse::<u64>().ok());
-
-                match (total, available) {
-                    (Some(t), Some(a)) => (t / 1024, (t - a) / 1024),
-                    _ => (0, 0),
-                }
-            })
+            .and_then(|content| parse_meminfo(&content))
             .unwrap_or((0, 0))
     }
 
     #[cfg(target_os = "macos")]
     {
-        Command::new("sysctl")
-            .args(&["-n", "hw.memsize"])
+        let total = sysctl_value("hw.memsize")
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(0);
+        let used = Command::new("vm_stat")
             .output()
             .ok()
             .and_then(|o| String::from_utf8(o.stdout).ok())
-            .and_then(|s| s.trim().parse::<u64>().ok())
-            .map(|total| (total / 1024 / 1024, total / 1024 / 1024 / 2))
-            .unwrap_or((0, 0))
+            .and_then(|s| parse_vm_stat(&s))
+            .unwrap_or(0);
+        (total / 1024 / 1024, used.min(total) / 1024 / 1024)
     }
 }
 
+#[cfg(target_os = "linux")]
+fn get_cpu_usage() -> f32 {
+    // 与上一次调用之间的平均使用率；首次调用时短暂采样
+    static LAST_SAMPLE: Lazy<Mutex<Option<(u64, u64)>>> = Lazy::new(|| Mutex::new(None));
+    let sample = || {
+        std::fs::read_to_string("/proc/stat")
+            .ok()
+            .and_then(|stat| parse_cpu_times(&stat))
+    };
+
+    let Ok(mut last) = LAST_SAMPLE.lock() else {
+        return 0.0;
+    };
+    let previous = match *last {
+        Some(previous) => Some(previous),
+        None => {
+            let first = sample();
+            std::thread::sleep(std::time::Duration::from_millis(200));
+            first
+        }
+    };
+    let current = sample();
+    *last = current;
+    match (previous, current) {
+        (Some(previous), Some(current)) => cpu_usage_between(previous, current),
+        _ => 0.0,
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
 fn get_cpu_usage() -> f32 {
     // 简化实现，实际应该使用系统API
     0.0
 }
 
+/// 获取缓存的显卡指标，过期后在后台刷新；首次调用时同步采样
+///
+/// 只能读到静态信息（WMI）或没有显卡时，按 system_info_cache_ttl_secs 刷新
+fn get_gpu_metrics() -> Vec<GpuMetrics> {
+    let cached = GPU_SAMPLE.lock().ok().and_then(|sample| sample.clone());
+    let Some((gpus, taken)) = cached else {
+        return refresh_gpu_metrics();
+    };
+
+    let live = gpus.iter().any(|gpu| gpu.utilization_percent.is_some());
+    let max_age = if live {
+        GPU_SAMPLE_INTERVAL
+    } else {
+        Duration::from_secs(get_config().system_info_cache_ttl_secs)
+    };
+    if taken.elapsed() >= max_age && !GPU_REFRESHING.swap(true, Ordering::SeqCst) {
+        std::thread::spawn(|| {
+            refresh_gpu_metrics();
+            GPU_REFRESHING.store(false, Ordering::SeqCst);
+        });
+    }
+    gpus
+}
+
+fn refresh_gpu_metrics() -> Vec<GpuMetrics> {
+    let gpus = sample_gpu_metrics();
+    if let Ok(mut sample) = GPU_SAMPLE.lock() {
+        *sample = Some((gpus.clone(), Instant::now()));
+    }
+    gpus
+}
+
+/// 读取显卡指标：优先使用 nvidia-smi（NVML），Windows 下回退到 WMI 只读取显存总量
+fn sample_gpu_metrics() -> Vec<GpuMetrics> {
+    let nvidia = get_nvidia_gpu_metrics();
+    if !nvidia.is_empty() {
+        return nvidia;
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        get_wmi_gpu_info()
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        Vec::new()
+    }
+}
+
+fn get_nvidia_gpu_metrics() -> Vec<GpuMetrics> {
+    let mut command = Command::new("nvidia-smi");
+    command.args([
+        "--query-gpu=name,utilization.gpu,memory.used,memory.total,temperature.gpu",
+        "--format=csv,noheader,nounits",
+    ]);
+    #[cfg(target_os = "windows")]
+    command.creation_flags(CREATE_NO_WINDOW);
+
+    let output = match command.output() {
+        Ok(output) if output.status.success() => output,
+        _ => return Vec::new(),
+    };
+
+    // 格式：NVIDIA GeForce RTX 3080, 12, 1024, 10240, 45
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(',').map(|f| f.trim()).collect();
+            if fields.len() < 5 {
+                return None;
+            }
+            Some(GpuMetrics {
+                name: fields[0].to_string(),
+                utilization_percent: fields[1].parse().ok(),
+                memory_used: fields[2].parse().ok(),
+                memory_total: fields[3].parse().ok(),
+                temperature: fields[4].parse().ok(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(target_os = "windows")]
+fn get_wmi_gpu_info() -> Vec<GpuMetrics> {
+    Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-Command",
+            "Get-CimInstance Win32_VideoController | ForEach-Object { \"$($_.Name)|$($_.AdapterRAM)\" }",
+        ])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .ok()
+        .map(|o| decode_gbk_to_utf8(&o.stdout))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let (name, ram) = line.trim().split_once('|')?;
+            Some(GpuMetrics {
+                name: name.to_string(),
+                utilization_percent: None,
+                memory_used: None,
+                // AdapterRAM 为 32 位字段，超过 4GB 的显卡会显示不准确
+                memory_total: ram.parse::<u64>().ok().map(|b| b / 1024 / 1024),
+                temperature: None,
+            })
+        })
+        .collect()
+}
+
 fn get_uptime() -> u64 {
     #[cfg(target_os = "windows")]
     {
@@ -575,14 +1233,79 @@ fn get_uptime() -> u64 {
 
     #[cfg(target_os = "macos")]
     {
-        Command::new("sysctl")
-            .args(&["-n", "kern.boottime"])
-            .output()
-            .ok()
-            .and_then(|o| {
-                // 解析macOS启动时间
-                Some(0) // 简化处理
-            })
-            .unwrap_or(0)
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        sysctl_value("kern.boottime")
+            .and_then(|s| parse_boottime(&s))
+            .map_or(0, |boot| now.saturating_sub(boot))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_os_release() {
+        let content = "NAME=\"Ubuntu\"\nPRETTY_NAME=\"Ubuntu 22.04.4 LTS\"\nID=ubuntu\n";
+        assert_eq!(
+            parse_key_value(content, "PRETTY_NAME").as_deref(),
+            Some("Ubuntu 22.04.4 LTS")
+        );
+        assert_eq!(parse_key_value(content, "ID").as_deref(), Some("ubuntu"));
+        assert_eq!(parse_key_value(content, "VERSION_ID"), None);
+    }
+
+    #[test]
+    fn test_parse_meminfo() {
+        let content = "MemTotal: 8192000 kB\nMemFree: 1024000 kB\nMemAvailable: 4096000 kB\n";
+        assert_eq!(parse_meminfo(content), Some((8000, 4000)));
+
+        // 旧内核没有 MemAvailable
+        let legacy = "MemTotal: 4096 kB\nMemFree: 1024 kB\nBuffers: 512 kB\nCached: 512 kB\n";
+        assert_eq!(parse_meminfo(legacy), Some((4, 2)));
+
+        assert_eq!(parse_meminfo(""), None);
+    }
+
+    #[test]
+    fn test_parse_cpu_model() {
+        let content = "processor\t: 0\nmodel name\t: AMD Ryzen 7 5800X 8-Core Processor\n";
+        assert_eq!(
+            parse_cpu_model(content).as_deref(),
+            Some("AMD Ryzen 7 5800X 8-Core Processor")
+        );
+    }
+
+    #[test]
+    fn test_cpu_usage() {
+        let stat = "cpu  100 0 100 700 100 0 0 0 0 0\ncpu0 50 0 50 350 50 0 0 0 0 0\n";
+        let first = parse_cpu_times(stat).unwrap();
+        assert_eq!(first, (800, 1000));
+
+        let second = parse_cpu_times("cpu  200 0 200 1300 100 0 0 0 0 0\n").unwrap();
+        assert!((cpu_usage_between(first, second) - 25.0).abs() < 0.01);
+
+        // 两次采样相同时不应除以零
+        assert_eq!(cpu_usage_between(first, first), 0.0);
+    }
+
+    #[test]
+    fn test_parse_vm_stat() {
+        let content = "Mach Virtual Memory Statistics: (page size of 16384 bytes)\n\
+            Pages free:                               10000.\n\
+            Pages active:                            200000.\n\
+            Pages wired down:                        100000.\n\
+            Pages occupied by compressor:             50000.\n";
+        assert_eq!(parse_vm_stat(content), Some(350000 * 16384));
+        assert_eq!(parse_vm_stat("Pages active: 1.\n"), None);
+    }
+
+    #[test]
+    fn test_parse_boottime() {
+        let output = "{ sec = 1700000000, usec = 123456 } Tue Nov 14 22:13:20 2023";
+        assert_eq!(parse_boottime(output), Some(1700000000));
+        assert_eq!(parse_boottime("invalid"), None);
     }
 }
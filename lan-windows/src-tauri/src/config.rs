@@ -1,7 +1,10 @@
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
+use zeroize::Zeroizing;
 
 /// 主题类型
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
@@ -14,13 +17,369 @@ pub enum Theme {
     Glass,
 }
 
+/// 常见弱密码，启用 deny_common 时拒绝（比较时忽略大小写）
+const COMMON_PASSWORDS: &[&str] = &[
+    "password",
+    "password1",
+    "password123",
+    "passw0rd",
+    "12345678",
+    "123456789",
+    "1234567890",
+    "87654321",
+    "11111111",
+    "00000000",
+    "qwerty123",
+    "qwertyuiop",
+    "1q2w3e4r",
+    "1qaz2wsx",
+    "abc12345",
+    "abcd1234",
+    "iloveyou",
+    "sunshine",
+    "princess",
+    "football",
+    "baseball",
+    "welcome1",
+    "admin123",
+    "administrator",
+    "letmein1",
+    "trustno1",
+    "changeme",
+    "computer",
+    "internet",
+    "woaini1314",
+];
+
+/// 密码策略
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct PasswordPolicy {
+    /// 最小长度（字符数）
+    pub min_length: usize,
+    /// 是否要求包含大写字母
+    pub require_uppercase: bool,
+    /// 是否要求包含小写字母
+    pub require_lowercase: bool,
+    /// 是否要求包含数字
+    pub require_digit: bool,
+    /// 是否要求包含符号
+    pub require_symbol: bool,
+    /// 是否拒绝常见弱密码
+    pub deny_common: bool,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            require_uppercase: false,
+            require_lowercase: false,
+            require_digit: false,
+            require_symbol: false,
+            deny_common: true,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    /// 检查密码是否满足策略
+    pub fn validate(&self, password: &str) -> Result<(), String> {
+        if password.chars().count() < self.min_length {
+            return Err(format!(
+                "Password must be at least {} characters long",
+                self.min_length
+            ));
+        }
+
+        let checks = [
+            (
+                self.require_uppercase,
+                password.chars().any(|c| c.is_uppercase()),
+                "an uppercase letter",
+            ),
+            (
+                self.require_lowercase,
+                password.chars().any(|c| c.is_lowercase()),
+                "a lowercase letter",
+            ),
+            (
+                self.require_digit,
+                password.chars().any(|c| c.is_ascii_digit()),
+                "a digit",
+            ),
+            (
+                self.require_symbol,
+                password
+                    .chars()
+                    .any(|c| !c.is_alphanumeric() && !c.is_whitespace()),
+                "a symbol",
+            ),
+        ];
+        if let Some((_, _, missing)) = checks
+            .iter()
+            .find(|(required, present, _)| *required && !*present)
+        {
+            return Err(format!("Password must contain {}", missing));
+        }
+
+        if self.deny_common
+            && COMMON_PASSWORDS
+                .iter()
+                .any(|common| common.eq_ignore_ascii_case(password))
+        {
+            return Err("Password is too common".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+/// 托盘菜单设置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct TrayConfig {
+    /// 托盘菜单中显示的快捷操作，按顺序排列（见 tray::QUICK_ACTIONS）
+    pub quick_actions: Vec<String>,
+}
+
+impl Default for TrayConfig {
+    fn default() -> Self {
+        Self {
+            quick_actions: crate::tray::QUICK_ACTIONS
+                .iter()
+                .map(|action| action.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// 可按客户端限制的功能范围
+pub const PERMISSION_SCOPES: &[&str] = &[
+    "commands", "files", "config", "sessions", "updates", "apps", "sync", "logs", "clipboard",
+    "display", "media", "power", "network", "printers", "events",
+];
+
+/// 允许通过 API 远程修改的配置项，只包含显示和资源限制类设置
+///
+/// 密码、命令白名单、共享目录、客户端权限、网络访问限制、日志路径等安全相关设置只能在本机界面修改
+pub const REMOTE_EDITABLE_FIELDS: &[&str] = &[
+    "device_display_name",
+    "message_language",
+    "theme",
+    "min_log_level",
+    "log_buffer_size",
+    "log_file_max_size",
+    "update_check_interval_hours",
+    "system_info_cache_ttl_secs",
+    "http_max_concurrent_requests",
+    "http_max_body_bytes",
+    "http_request_timeout_secs",
+    "http_command_timeout_secs",
+    "enable_http_compression",
+    "ws_max_messages_per_sec",
+    "ws_max_message_size",
+    "ws_max_concurrent_commands",
+    "ws_broadcast_capacity",
+    "file_search_max_content_bytes",
+    "print_max_upload_bytes",
+    "speedtest_max_bytes",
+];
+
+/// client_permissions 中的特殊键，应用于没有单独配置的客户端
+pub const DEFAULT_CLIENT_KEY: &str = "default";
+
+/// 客户端权限
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ClientPermissions {
+    /// 允许执行的命令（含 shutdown、lock 等电源操作），为空表示不限制
+    pub allowed_commands: Vec<String>,
+    /// 是否允许执行自定义命令
+    pub allow_custom_commands: bool,
+    /// 允许使用的功能范围（见 PERMISSION_SCOPES），为空表示不限制
+    pub scopes: Vec<String>,
+}
+
+impl Default for ClientPermissions {
+    fn default() -> Self {
+        Self {
+            allowed_commands: vec![],
+            allow_custom_commands: true,
+            scopes: vec![],
+        }
+    }
+}
+
+impl ClientPermissions {
+    /// 不允许任何命令和功能，用于 client_permissions 中没有适用条目的客户端
+    pub fn deny_all() -> Self {
+        Self {
+            allowed_commands: vec![],
+            allow_custom_commands: false,
+            // 只包含不存在的范围，allows_scope 对任何功能都返回 false
+            scopes: vec!["none".to_string()],
+        }
+    }
+
+    /// 是否允许使用某个功能范围
+    pub fn allows_scope(&self, scope: &str) -> bool {
+        self.scopes.is_empty() || self.scopes.iter().any(|s| s == scope)
+    }
+
+    /// 是否允许使用至少一个功能范围，没有适用条目的客户端（见 deny_all）返回 false
+    pub fn allows_any_scope(&self) -> bool {
+        PERMISSION_SCOPES.iter().any(|scope| self.allows_scope(scope))
+    }
+}
+
+/// 派生登录验证器时的 PBKDF2 迭代次数
+pub const VERIFIER_ITERATIONS: u32 = 100_000;
+
+/// 登录验证器：PBKDF2-HMAC-SHA256(密码, 盐)
+///
+/// 客户端按挑战中返回的盐和迭代次数计算同样的值，以它为密钥对挑战做 HMAC，
+/// 登录请求中不再包含密码
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PasswordVerifier {
+    /// 随机盐（十六进制），按字符串原样参与派生
+    pub salt: String,
+    pub iterations: u32,
+    /// 派生结果（十六进制）
+    pub verifier: String,
+}
+
+impl PasswordVerifier {
+    /// 用新的随机盐为密码生成验证器
+    pub fn new(password: &str) -> Self {
+        use rand::RngCore;
+
+        let mut salt = [0u8; 16];
+        rand::rngs::OsRng.fill_bytes(&mut salt);
+        let salt = hex::encode(salt);
+        let verifier = Self::derive(password, &salt, VERIFIER_ITERATIONS);
+        Self {
+            salt,
+            iterations: VERIFIER_ITERATIONS,
+            verifier: hex::encode(verifier.as_ref()),
+        }
+    }
+
+    /// 派生验证器，客户端和服务端使用同一算法
+    pub fn derive(password: &str, salt: &str, iterations: u32) -> Zeroizing<[u8; 32]> {
+        let mut key = Zeroizing::new([0u8; 32]);
+        pbkdf2::pbkdf2_hmac::<sha2::Sha256>(
+            password.as_bytes(),
+            salt.as_bytes(),
+            iterations,
+            key.as_mut(),
+        );
+        key
+    }
+
+    /// 验证器的原始字节，作为挑战 HMAC 的密钥
+    pub fn key(&self) -> Option<Zeroizing<Vec<u8>>> {
+        hex::decode(&self.verifier).ok().map(Zeroizing::new)
+    }
+}
+
+/// API 密钥的前缀，用于区分密钥和登录令牌
+pub const API_KEY_PREFIX: &str = "ldm_";
+
+/// 供脚本和家庭自动化调用的静态 API 密钥，配置中只保存哈希
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ApiKeyConfig {
+    /// 密钥名称，用于日志和管理
+    pub name: String,
+    /// 密钥的 SHA-256 哈希（十六进制）
+    pub key_hash: String,
+    /// 密钥可用的命令和功能范围，与登录会话的权限分开配置
+    pub permissions: ClientPermissions,
+    /// 创建时间（Unix 时间戳）
+    pub created_at: i64,
+}
+
+impl ApiKeyConfig {
+    /// 计算密钥的哈希
+    pub fn hash_key(key: &str) -> String {
+        use sha2::{Digest, Sha256};
+        hex::encode(Sha256::digest(key.as_bytes()))
+    }
+}
+
+/// 附加的 API 监听器，可以为不同网段设置不同的地址和 TLS
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ListenerConfig {
+    pub bind_addr: String,
+    pub port: u16,
+    /// 是否启用 HTTPS
+    pub tls: bool,
+    /// PEM 格式证书路径，启用 TLS 时必填
+    pub cert_path: Option<String>,
+    /// PEM 格式私钥路径，启用 TLS 时必填
+    pub key_path: Option<String>,
+}
+
+impl Default for ListenerConfig {
+    fn default() -> Self {
+        Self {
+            bind_addr: "0.0.0.0".to_string(),
+            port: 8443,
+            tls: false,
+            cert_path: None,
+            key_path: None,
+        }
+    }
+}
+
+/// 配置中的敏感字段，接口返回、不含密钥的导出、崩溃报告和调试抓包中都需要隐藏
+pub const SECRET_FIELDS: &[&str] = &[
+    "password_hash",
+    "password_verifier",
+    "api_keys",
+    "health_secret",
+];
+
 /// 应用配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AppConfig {
     /// API 服务器端口
     pub api_port: u16,
+    /// API 服务器监听地址
+    pub bind_addr: String,
+    /// 除主端口外同时监听的地址（例如在另一个网段上启用 HTTPS）
+    pub extra_listeners: Vec<ListenerConfig>,
+    /// 在局域网中显示的设备名称，为空时使用主机名
+    pub device_display_name: String,
+    /// 设备UUID来源：random 为随机生成并保存在 AppData，machine 为由本机硬件标识派生（重装后不变）
+    pub device_id_source: String,
+    /// 是否通过 mDNS 广播本机，关闭后（隐身模式）只能通过 IP 地址连接
+    pub enable_mdns: bool,
+    /// 是否同时响应 SSDP 搜索，用于屏蔽了 mDNS 的网络
+    pub enable_ssdp: bool,
+    /// /api/health 只返回状态，不返回版本和服务名称，降低被局域网扫描识别的可能
+    pub stealth_health: bool,
+    /// 设置后 /api/health 要求 X-Health-Secret 请求头与之相同，否则按不存在的路径返回 404
+    pub health_secret: Option<String>,
+    /// gRPC 服务端口，0 表示不启用
+    pub grpc_port: u16,
+    /// 客户端未发送 Accept-Language 时错误消息使用的语言（见 messages::SUPPORTED_LANGUAGES）
+    pub message_language: String,
+    /// 自动检查新版本的间隔（小时），0 表示不自动检查
+    pub update_check_interval_hours: u64,
     /// 密码哈希（Argon2id）
     pub password_hash: Option<String>,
+    /// 登录验证器，设置密码时与密码哈希一起生成
+    pub password_verifier: Option<PasswordVerifier>,
+    /// 设置密码时的强度要求
+    pub password_policy: PasswordPolicy,
+    /// 是否接受静态 API 密钥（代替挑战-响应登录，供脚本使用）
+    pub enable_api_keys: bool,
+    /// 已创建的 API 密钥
+    pub api_keys: Vec<ApiKeyConfig>,
     /// 日志缓冲区大小（条数）
     pub log_buffer_size: usize,
     /// 日志文件路径
@@ -29,10 +388,20 @@ pub struct AppConfig {
     pub enable_log_file: bool,
     /// 日志文件最大大小（MB）
     pub log_file_max_size: u64,
+    /// 记录日志的最低级别（见 logger::MIN_LOG_LEVELS），低于该级别的日志不保存、不写文件也不广播
+    pub min_log_level: String,
     /// 是否自动启动 API 服务器（应用启动时）
     pub auto_start_api: bool,
     /// 是否开机自启动
     pub auto_start_on_boot: bool,
+    /// 托盘菜单设置
+    pub tray: TrayConfig,
+    /// API 服务器意外退出时是否自动重启
+    pub auto_restart_api: bool,
+    /// 自动重启的最大连续尝试次数
+    pub auto_restart_max_attempts: u32,
+    /// 连续多少分钟没有认证通过的请求后自动停止服务器和广播，0 表示不自动停止
+    pub idle_stop_minutes: u64,
     /// 命令白名单（内置命令）
     pub command_whitelist: Vec<String>,
     /// 自定义命令列表（用户可以执行的额外命令）
@@ -43,19 +412,103 @@ pub struct AppConfig {
     pub ip_blacklist: Vec<String>,
     /// 是否启用IP黑名单
     pub enable_ip_blacklist: bool,
+    /// 只接受来自局域网地址（私有网段、链路本地和本机）的请求，防止端口被意外转发到公网
+    pub lan_only: bool,
+    /// 受信任的网络（Wi-Fi 名称、IP 地址或 CIDR 网段），不在其中时停止服务器和广播；为空表示不限制
+    pub trusted_networks: Vec<String>,
+    /// 静态系统信息（操作系统、主机名等）缓存时间（秒），0 表示不缓存
+    pub system_info_cache_ttl_secs: u64,
+    /// HTTP 最大并发请求数
+    pub http_max_concurrent_requests: usize,
+    /// 每个客户端IP每秒允许的 HTTP 请求数，0 表示不限制
+    pub http_rate_limit_rps: u32,
+    /// 每个客户端IP允许的突发请求数
+    pub http_rate_limit_burst: u32,
+    /// HTTP 请求体最大字节数
+    pub http_max_body_bytes: usize,
+    /// 普通 HTTP 请求超时（秒）
+    pub http_request_timeout_secs: u64,
+    /// 命令类 HTTP 请求超时（秒）
+    pub http_command_timeout_secs: u64,
+    /// 是否启用 HTTP 响应压缩（gzip/deflate）
+    pub enable_http_compression: bool,
+    /// 每个 WebSocket 连接每秒允许的最大消息数
+    pub ws_max_messages_per_sec: u32,
+    /// WebSocket 单条文本消息最大字节数
+    pub ws_max_message_size: usize,
+    /// 每个 WebSocket 连接允许同时执行的最大命令数
+    pub ws_max_concurrent_commands: usize,
+    /// WebSocket 连接建立后必须在此时间内完成认证（秒），超时断开
+    pub ws_auth_timeout_secs: u64,
+    /// 每个 WebSocket 连接允许的认证失败次数，达到后断开
+    pub ws_max_auth_failures: u32,
+    /// 服务端事件广播通道容量，处理较慢的连接落后超过此数量时会丢失消息并收到 resync 提示
+    ///
+    /// 重启应用后生效
+    pub ws_broadcast_capacity: usize,
+    /// 允许远程浏览和搜索的共享根目录
+    pub shared_roots: Vec<String>,
+    /// 按内容搜索时单个文件的最大字节数，超过的文件会被跳过
+    pub file_search_max_content_bytes: u64,
+    /// 远程打印上传文件的最大字节数
+    pub print_max_upload_bytes: usize,
+    /// 网速测试单次传输的最大字节数
+    pub speedtest_max_bytes: u64,
+    /// 按已配对设备的ID限制可用的命令和功能，键 "default" 应用于其他已认证客户端；
+    /// 配置了任何条目后，没有适用条目的客户端不能使用任何功能
+    pub client_permissions: HashMap<String, ClientPermissions>,
+}
+
+/// 原子写入文件：先写入同目录的临时文件并刷盘，再重命名覆盖目标文件，
+/// 写入过程中崩溃不会留下半个文件
+pub fn write_atomic(path: &std::path::Path, content: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let tmp_path = path.with_extension("tmp");
+    {
+        let mut file = std::fs::File::create(&tmp_path)?;
+        file.write_all(content)?;
+        file.sync_all()?;
+    }
+    // Windows 上 rename 会替换已存在的目标文件（MoveFileExW + MOVEFILE_REPLACE_EXISTING）
+    if let Err(e) = std::fs::rename(&tmp_path, path) {
+        let _ = std::fs::remove_file(&tmp_path);
+        return Err(e);
+    }
+    Ok(())
 }
 
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
             api_port: 8080,
+            bind_addr: "0.0.0.0".to_string(),
+            extra_listeners: vec![],
+            device_display_name: String::new(),
+            device_id_source: "random".to_string(),
+            enable_mdns: true,
+            enable_ssdp: false,
+            stealth_health: false,
+            health_secret: None,
+            grpc_port: 0,
+            message_language: "en".to_string(),
+            update_check_interval_hours: 24,
             password_hash: None,
+            password_verifier: None,
+            password_policy: PasswordPolicy::default(),
+            enable_api_keys: false,
+            api_keys: vec![],
             log_buffer_size: 100,
             log_file_path: None,
             enable_log_file: true,
             log_file_max_size: 10,
+            min_log_level: "info".to_string(),
             auto_start_api: false,
             auto_start_on_boot: false,
+            tray: TrayConfig::default(),
+            auto_restart_api: true,
+            auto_restart_max_attempts: 5,
+            idle_stop_minutes: 0,
             command_whitelist: vec![
                 "shutdown".to_string(),
                 "restart".to_string(),
@@ -69,6 +522,27 @@ impl Default for AppConfig {
             theme: Theme::default(),
             ip_blacklist: vec![],
             enable_ip_blacklist: false,
+            lan_only: false,
+            trusted_networks: vec![],
+            system_info_cache_ttl_secs: 300,
+            http_max_concurrent_requests: 64,
+            http_rate_limit_rps: 20,
+            http_rate_limit_burst: 60,
+            http_max_body_bytes: 1024 * 1024,
+            http_request_timeout_secs: 15,
+            http_command_timeout_secs: 60,
+            enable_http_compression: true,
+            ws_max_messages_per_sec: 20,
+            ws_max_message_size: 64 * 1024,
+            ws_max_concurrent_commands: 2,
+            ws_auth_timeout_secs: 10,
+            ws_max_auth_failures: 3,
+            ws_broadcast_capacity: 128,
+            shared_roots: vec![],
+            file_search_max_content_bytes: 1024 * 1024,
+            print_max_upload_bytes: 20 * 1024 * 1024,
+            speedtest_max_bytes: 100 * 1024 * 1024,
+            client_permissions: HashMap::new(),
         }
     }
 }
@@ -98,26 +572,31 @@ impl AppConfig {
         Ok(config_dir)
     }
 
+    /// 配置备份文件路径（上一次保存的版本）
+    pub fn backup_path() -> PathBuf {
+        Self::config_path().with_extension("json.bak")
+    }
+
+    /// 读取并解析配置文件
+    fn read_file(path: &std::path::Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        serde_json::from_str::<AppConfig>(&content).map_err(|e| e.to_string())
+    }
+
     /// 从文件加载配置
     pub fn load() -> Self {
         let config_path = Self::config_path();
 
         if config_path.exists() {
-            match std::fs::read_to_string(&config_path) {
-                Ok(content) => match serde_json::from_str::<AppConfig>(&content) {
-                    Ok(config) => {
-                        log::info!("Config loaded - command_whitelist: {:?}, custom_commands: {:?}", 
-                            config.command_whitelist, config.custom_commands);
-                        config
-                    }
-                    Err(e) => {
-                        log::error!("Failed to parse config: {}, using default", e);
-                        Self::default()
-                    }
-                },
+            match Self::read_file(&config_path) {
+                Ok(config) => {
+                    log::info!("Config loaded - command_whitelist: {:?}, custom_commands: {:?}", 
+                        config.command_whitelist, config.custom_commands);
+                    config
+                }
                 Err(e) => {
-                    log::error!("Failed to read config file: {}, using default", e);
-                    Self::default()
+                    log::error!("Failed to load config: {}, trying backup", e);
+                    Self::recover_from_backup()
                 }
             }
         } else {
@@ -129,6 +608,27 @@ impl AppConfig {
         }
     }
 
+    /// 主配置文件损坏时从备份恢复，备份也不可用时使用默认配置
+    fn recover_from_backup() -> Self {
+        let backup_path = Self::backup_path();
+        match Self::read_file(&backup_path) {
+            Ok(config) => {
+                log::warn!("Config recovered from backup {:?}", backup_path);
+                // 保留损坏的文件便于排查，再用备份覆盖主文件
+                let config_path = Self::config_path();
+                let _ = std::fs::rename(&config_path, config_path.with_extension("json.corrupt"));
+                if let Err(e) = config.save() {
+                    log::error!("Failed to restore config from backup: {}", e);
+                }
+                config
+            }
+            Err(e) => {
+                log::error!("Failed to load config backup: {}, using default", e);
+                Self::default()
+            }
+        }
+    }
+
     /// 保存配置到文件
     pub fn save(&self) -> std::io::Result<()> {
         Self::ensure_config_dir()?;
@@ -137,7 +637,14 @@ impl AppConfig {
         let content = serde_json::to_string_pretty(self)
             .map_err(std::io::Error::other)?;
 
-        std::fs::write(&config_path, content)?;
+        // 只备份能正常解析的旧版本，避免用损坏的文件覆盖有效备份
+        if Self::read_file(&config_path).is_ok() {
+            if let Err(e) = std::fs::copy(&config_path, Self::backup_path()) {
+                log::warn!("Failed to back up config: {}", e);
+            }
+        }
+
+        write_atomic(&config_path, content.as_bytes())?;
         log::info!("Config saved to {:?}", config_path);
         Ok(())
     }
@@ -154,6 +661,7 @@ impl AppConfig {
             .map_err(|e| format!("Failed to hash password: {}", e))?;
 
         self.password_hash = Some(password_hash.to_string());
+        self.password_verifier = Some(PasswordVerifier::new(password));
         Ok(())
     }
 
@@ -185,12 +693,334 @@ impl AppConfig {
     /// 清除密码
     pub fn clear_password(&mut self) {
         self.password_hash = None;
+        self.password_verifier = None;
+    }
+
+    /// 返回把 SECRET_FIELDS 中的字段恢复为默认值后的副本
+    pub fn redacted(&self) -> Self {
+        let mut value = serde_json::to_value(self).unwrap_or_default();
+        if let Some(map) = value.as_object_mut() {
+            for field in SECRET_FIELDS {
+                map.remove(*field);
+            }
+        }
+        serde_json::from_value(value).unwrap_or_default()
+    }
+}
+
+/// 配置校验错误，field 为出错的字段名（列表项带下标，如 ip_blacklist[2]）
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/// 解析 CIDR 网段，如 192.168.1.0/24、fd00::/8
+pub fn parse_cidr(value: &str) -> Option<(std::net::IpAddr, u8)> {
+    let (addr, prefix) = value.trim().split_once('/')?;
+    let addr: std::net::IpAddr = addr.parse().ok()?;
+    let prefix: u8 = prefix.parse().ok()?;
+    let max = if addr.is_ipv4() { 32 } else { 128 };
+    (prefix <= max).then_some((addr, prefix))
+}
+
+/// 检查 IP 是否属于 CIDR 网段
+pub fn cidr_contains(network: std::net::IpAddr, prefix: u8, ip: std::net::IpAddr) -> bool {
+    use std::net::IpAddr;
+    match (network, ip) {
+        (IpAddr::V4(net), IpAddr::V4(ip)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+            u32::from(net) & mask == u32::from(ip) & mask
+        }
+        (IpAddr::V6(net), IpAddr::V6(ip)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+            u128::from(net) & mask == u128::from(ip) & mask
+        }
+        _ => false,
+    }
+}
+
+/// 校验黑名单条目：精确IP、CIDR 网段或 192.168.1.* 形式的通配符
+fn is_valid_blacklist_entry(entry: &str) -> bool {
+    let entry = entry.trim();
+    if entry.parse::<std::net::IpAddr>().is_ok() || parse_cidr(entry).is_some() {
+        return true;
+    }
+    let octets: Vec<&str> = entry.split('.').collect();
+    entry.contains('*')
+        && octets.len() == 4
+        && octets
+            .iter()
+            .all(|o| *o == "*" || o.parse::<u8>().is_ok())
+}
+
+/// 检查日志文件路径是否可写，不存在时尝试创建后删除
+fn check_log_path_writable(path: &std::path::Path) -> Result<(), String> {
+    let existed = path.exists();
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Cannot create directory: {}", e))?;
+    }
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Log file is not writable: {}", e))?;
+    if !existed {
+        let _ = std::fs::remove_file(path);
+    }
+    Ok(())
+}
+
+impl AppConfig {
+    /// 校验配置，返回所有字段错误；为空表示配置有效
+    pub fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        let mut error = |field: String, message: &str| {
+            errors.push(FieldError {
+                field,
+                message: message.to_string(),
+            })
+        };
+
+        if self.api_port == 0 {
+            error("api_port".into(), "Port must be between 1 and 65535");
+        }
+        if let Some(ref secret) = self.health_secret {
+            // 作为请求头的值，只允许可见的 ASCII 字符
+            if secret.is_empty() || !secret.chars().all(|c| c.is_ascii_graphic()) {
+                error(
+                    "health_secret".into(),
+                    "Must be non-empty printable ASCII without spaces",
+                );
+            }
+        }
+        if self.device_display_name.len() > 63 {
+            error(
+                "device_display_name".into(),
+                "Must be at most 63 bytes long",
+            );
+        }
+        if !crate::device_id::DEVICE_ID_SOURCES.contains(&self.device_id_source.as_str()) {
+            error("device_id_source".into(), "Must be one of: random, machine");
+        }
+        if self.bind_addr.parse::<std::net::IpAddr>().is_err() {
+            error(
+                "bind_addr".into(),
+                "Must be an IP address such as 0.0.0.0 or 192.168.1.10",
+            );
+        }
+
+        // 同一端口只有在两个地址都是具体地址且不相同时才能共存
+        let conflicts = |a: &std::net::IpAddr, b: &std::net::IpAddr| {
+            a.is_unspecified() || b.is_unspecified() || a == b
+        };
+        let mut bound: Vec<(std::net::IpAddr, u16)> = self
+            .bind_addr
+            .parse()
+            .map(|ip| vec![(ip, self.api_port)])
+            .unwrap_or_default();
+        for (i, listener) in self.extra_listeners.iter().enumerate() {
+            let field = |name: &str| format!("extra_listeners[{}].{}", i, name);
+            let ip = listener.bind_addr.parse::<std::net::IpAddr>();
+            if ip.is_err() {
+                error(field("bind_addr"), "Must be an IP address");
+            }
+            if listener.port == 0 {
+                error(field("port"), "Port must be between 1 and 65535");
+            } else if let Ok(ip) = ip {
+                if bound
+                    .iter()
+                    .any(|(other, port)| *port == listener.port && conflicts(other, &ip))
+                {
+                    error(field("port"), "Port is already used by another listener");
+                }
+                bound.push((ip, listener.port));
+            }
+            if listener.tls {
+                for (name, path) in [
+                    ("cert_path", &listener.cert_path),
+                    ("key_path", &listener.key_path),
+                ] {
+                    match path {
+                        Some(path) if std::path::Path::new(path).is_file() => {}
+                        Some(_) => error(field(name), "File does not exist"),
+                        None => error(field(name), "Required when TLS is enabled"),
+                    }
+                }
+            }
+        }
+
+        if self.grpc_port != 0 {
+            if let Ok(ip) = self.bind_addr.parse::<std::net::IpAddr>() {
+                if bound
+                    .iter()
+                    .any(|(other, port)| *port == self.grpc_port && conflicts(other, &ip))
+                {
+                    error("grpc_port".into(), "Port is already used by another listener");
+                }
+            }
+        }
+
+        if !crate::messages::SUPPORTED_LANGUAGES.contains(&self.message_language.as_str()) {
+            error("message_language".into(), "Must be one of: en, zh");
+        }
+
+        if !crate::logger::MIN_LOG_LEVELS.contains(&self.min_log_level.as_str()) {
+            error("min_log_level".into(), "Must be one of: info, warn, error");
+        }
+
+        for (i, entry) in self.ip_blacklist.iter().enumerate() {
+            if !is_valid_blacklist_entry(entry) {
+                error(
+                    format!("ip_blacklist[{}]", i),
+                    "Must be an IP address, a CIDR range (192.168.1.0/24) or a wildcard (192.168.1.*)",
+                );
+            }
+        }
+
+        for (i, entry) in self.trusted_networks.iter().enumerate() {
+            if entry.trim().is_empty() {
+                error(
+                    format!("trusted_networks[{}]", i),
+                    "Must be a Wi-Fi name, an IP address or a CIDR range",
+                );
+            }
+        }
+
+        for (field, list) in [
+            ("command_whitelist", &self.command_whitelist),
+            ("custom_commands", &self.custom_commands),
+        ] {
+            for (i, command) in list.iter().enumerate() {
+                if command.trim().is_empty() {
+                    error(format!("{}[{}]", field, i), "Command must not be empty");
+                } else if list[..i].contains(command) {
+                    error(format!("{}[{}]", field, i), "Duplicate command");
+                }
+            }
+        }
+
+        if self.enable_log_file {
+            if let Some(ref path) = self.log_file_path {
+                if let Err(message) = check_log_path_writable(std::path::Path::new(path)) {
+                    error("log_file_path".into(), &message);
+                }
+            }
+        }
+
+        for (i, root) in self.shared_roots.iter().enumerate() {
+            if !std::path::Path::new(root).is_dir() {
+                error(format!("shared_roots[{}]", i), "Directory does not exist");
+            }
+        }
+
+        for (client, permissions) in &self.client_permissions {
+            for (i, scope) in permissions.scopes.iter().enumerate() {
+                if !PERMISSION_SCOPES.contains(&scope.as_str()) {
+                    error(
+                        format!("client_permissions.{}.scopes[{}]", client, i),
+                        "Unknown scope",
+                    );
+                }
+            }
+        }
+
+        for (i, key) in self.api_keys.iter().enumerate() {
+            if key.name.trim().is_empty() {
+                error(format!("api_keys[{}].name", i), "Must not be empty");
+            }
+            if self.api_keys[..i].iter().any(|other| other.name == key.name) {
+                error(format!("api_keys[{}].name", i), "Duplicate name");
+            }
+            for (j, scope) in key.permissions.scopes.iter().enumerate() {
+                if !PERMISSION_SCOPES.contains(&scope.as_str()) {
+                    error(
+                        format!("api_keys[{}].permissions.scopes[{}]", i, j),
+                        "Unknown scope",
+                    );
+                }
+            }
+        }
+
+        for (i, action) in self.tray.quick_actions.iter().enumerate() {
+            if !crate::tray::QUICK_ACTIONS.contains(&action.as_str()) {
+                error(
+                    format!("tray.quick_actions[{}]", i),
+                    "Unknown quick action",
+                );
+            }
+        }
+
+        // 必须为正数的限制项
+        for (field, value) in [
+            ("log_buffer_size", self.log_buffer_size as u64),
+            ("log_file_max_size", self.log_file_max_size),
+            ("http_max_concurrent_requests", self.http_max_concurrent_requests as u64),
+            ("http_rate_limit_burst", self.http_rate_limit_burst as u64),
+            ("http_max_body_bytes", self.http_max_body_bytes as u64),
+            ("http_request_timeout_secs", self.http_request_timeout_secs),
+            ("http_command_timeout_secs", self.http_command_timeout_secs),
+            ("ws_max_messages_per_sec", self.ws_max_messages_per_sec as u64),
+            ("ws_max_message_size", self.ws_max_message_size as u64),
+            ("ws_max_concurrent_commands", self.ws_max_concurrent_commands as u64),
+            ("ws_auth_timeout_secs", self.ws_auth_timeout_secs),
+            ("ws_max_auth_failures", self.ws_max_auth_failures as u64),
+            ("ws_broadcast_capacity", self.ws_broadcast_capacity as u64),
+            ("password_policy.min_length", self.password_policy.min_length as u64),
+        ] {
+            if value == 0 {
+                error(field.into(), "Must be greater than 0");
+            }
+        }
+
+        // 超过协议层上限的消息在读取时就会断开连接，这个值不会生效
+        if self.ws_max_message_size > crate::websocket::WS_HARD_MESSAGE_LIMIT {
+            error(
+                "ws_max_message_size".into(),
+                &format!(
+                    "Must not exceed {} bytes",
+                    crate::websocket::WS_HARD_MESSAGE_LIMIT
+                ),
+            );
+        }
+
+        errors
     }
 }
 
 // 全局配置实例
 pub static GLOBAL_CONFIG: Lazy<Arc<Mutex<AppConfig>>> =
-    Lazy::new(|| Arc::new(Mutex::new(AppConfig::load())));
+    Lazy::new(|| Arc::new(Mutex::new(load_with_overrides())));
+
+/// 加载配置文件并应用环境变量和命令行参数的覆盖
+fn load_with_overrides() -> AppConfig {
+    let mut config = AppConfig::load();
+    crate::overrides::OVERRIDES.apply(&mut config);
+    config
+}
+
+/// 保存配置，被覆盖的字段保留配置文件中原来的值
+fn save_without_overrides(config: &AppConfig) -> std::io::Result<()> {
+    let overrides = &*crate::overrides::OVERRIDES;
+    if overrides.is_empty() {
+        return config.save();
+    }
+    let on_disk = AppConfig::read_file(&AppConfig::config_path()).unwrap_or_default();
+    let mut config = config.clone();
+    overrides.restore(&mut config, &on_disk);
+    config.save()
+}
+
+// 配置版本号，每次修改或重新加载配置时递增，用于生成 ETag
+//
+// 从随机值开始：若每次启动都从 0 开始，重启并修改配置后的版本号可能与客户端
+// 缓存的旧 ETag 相同，客户端会收到 304 而继续使用过期的配置
+static CONFIG_VERSION: Lazy<AtomicU64> = Lazy::new(|| AtomicU64::new(rand::random()));
+
+/// 获取当前配置版本号
+pub fn config_version() -> u64 {
+    CONFIG_VERSION.load(Ordering::SeqCst)
+}
 
 /// 获取全局配置的克隆
 pub fn get_config() -> AppConfig {
@@ -210,12 +1040,192 @@ where
 {
     let mut config = GLOBAL_CONFIG.lock().unwrap();
     f(&mut config);
-    config.save()
+    CONFIG_VERSION.fetch_add(1, Ordering::SeqCst);
+    save_without_overrides(&config)
+}
+
+/// 在配置锁内基于当前配置计算新配置，闭包返回 Err 时不修改也不保存
+///
+/// 用于需要读取-修改-写入的场景，避免覆盖同时进行的其他修改
+pub fn try_update_config<E, F>(f: F) -> Result<std::io::Result<()>, E>
+where
+    F: FnOnce(&AppConfig) -> Result<AppConfig, E>,
+{
+    let mut config = GLOBAL_CONFIG.lock().unwrap();
+    *config = f(&config)?;
+    CONFIG_VERSION.fetch_add(1, Ordering::SeqCst);
+    Ok(save_without_overrides(&config))
 }
 
 /// 重新加载配置
 pub fn reload_config() {
-    let new_config = AppConfig::load();
+    let new_config = load_with_overrides();
     let mut config = GLOBAL_CONFIG.lock().unwrap();
     *config = new_config;
+    CONFIG_VERSION.fetch_add(1, Ordering::SeqCst);
+}
+
+/// 配置文件外部修改后等待写入完成的时间
+const WATCH_DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// 配置文件与内存中的配置不同时重新加载，返回新的配置，未变化时返回 None
+fn reload_if_changed() -> Option<AppConfig> {
+    let mut new_config = match AppConfig::read_file(&AppConfig::config_path()) {
+        Ok(config) => config,
+        Err(e) => {
+            // 编辑器可能还没写完，也可能是无效的修改，保持当前配置
+            log::warn!("Ignoring invalid config file change: {}", e);
+            return None;
+        }
+    };
+
+    crate::overrides::OVERRIDES.apply(&mut new_config);
+
+    let mut config = GLOBAL_CONFIG.lock().unwrap();
+    // 本程序自身保存配置也会触发事件，内容相同时忽略
+    if serde_json::to_value(&*config).ok() == serde_json::to_value(&new_config).ok() {
+        return None;
+    }
+    *config = new_config.clone();
+    CONFIG_VERSION.fetch_add(1, Ordering::SeqCst);
+    Some(new_config)
+}
+
+/// 监视 config.json 的外部修改并自动重新加载，重新加载后调用 on_reload
+pub fn watch_config_file<F>(on_reload: F) -> Result<(), String>
+where
+    F: Fn(&AppConfig) + Send + 'static,
+{
+    use notify::{EventKind, RecursiveMode, Watcher};
+
+    let config_dir = AppConfig::ensure_config_dir().map_err(|e| e.to_string())?;
+    let config_path = AppConfig::config_path();
+    let (tx, rx) = std::sync::mpsc::channel::<()>();
+
+    // 保存时通过重命名替换文件，因此监视所在目录而不是文件本身
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        let Ok(event) = res else {
+            return;
+        };
+        if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+            && event.paths.iter().any(|p| p.file_name() == config_path.file_name())
+        {
+            let _ = tx.send(());
+        }
+    })
+    .map_err(|e| e.to_string())?;
+    watcher
+        .watch(&config_dir, RecursiveMode::NonRecursive)
+        .map_err(|e| e.to_string())?;
+
+    std::thread::spawn(move || {
+        // watcher 随线程存活
+        let _watcher = watcher;
+        while rx.recv().is_ok() {
+            // 合并短时间内的连续事件
+            while rx.recv_timeout(WATCH_DEBOUNCE).is_ok() {}
+            if let Some(config) = reload_if_changed() {
+                log::info!("Config file changed on disk, reloaded");
+                on_reload(&config);
+            }
+        }
+    });
+
+    log::info!("Watching config file in {:?}", config_dir);
+    Ok(())
+}
+
+/// 导出文件格式版本
+const EXPORT_FORMAT_VERSION: u32 = 1;
+
+/// 配置导出文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigExport {
+    pub format_version: u32,
+    pub exported_at: String,
+    pub config: AppConfig,
+    /// 设备UUID，仅在导出时选择包含才有
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_uuid: Option<String>,
+}
+
+/// 导入结果，调用方据此决定是否需要刷新认证状态
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImportSummary {
+    pub password_imported: bool,
+    pub device_uuid_imported: bool,
+}
+
+/// 将当前配置导出到文件；不包含密钥时去掉密码哈希、API 密钥和健康检查密钥，便于在多台电脑间复制配置
+pub fn export_config(
+    path: &std::path::Path,
+    include_secrets: bool,
+    include_device_uuid: bool,
+) -> Result<(), String> {
+    let config = if include_secrets {
+        get_config()
+    } else {
+        get_config().redacted()
+    };
+
+    let device_uuid = if include_device_uuid {
+        Some(crate::device_id::DeviceId::get_or_create().map_err(|e| e.to_string())?)
+    } else {
+        None
+    };
+
+    let export = ConfigExport {
+        format_version: EXPORT_FORMAT_VERSION,
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        config,
+        device_uuid,
+    };
+    let content = serde_json::to_string_pretty(&export).map_err(|e| e.to_string())?;
+    write_atomic(path, content.as_bytes()).map_err(|e| e.to_string())?;
+    log::info!("Config exported to {:?}", path);
+    Ok(())
+}
+
+/// 从导出文件导入配置；文件中没有密码哈希、API 密钥或健康检查密钥时保留本机的设置
+pub fn import_config(path: &std::path::Path) -> Result<ImportSummary, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let export: ConfigExport =
+        serde_json::from_str(&content).map_err(|e| format!("Invalid config file: {}", e))?;
+    if export.format_version > EXPORT_FORMAT_VERSION {
+        return Err(format!(
+            "Unsupported config file version {}",
+            export.format_version
+        ));
+    }
+
+    let mut imported = export.config;
+    if let Some(ref hash) = imported.password_hash {
+        argon2::PasswordHash::new(hash).map_err(|_| "Invalid password hash in config file")?;
+    }
+    let summary = ImportSummary {
+        password_imported: imported.password_hash.is_some(),
+        device_uuid_imported: export.device_uuid.is_some(),
+    };
+
+    if let Some(ref uuid) = export.device_uuid {
+        crate::device_id::DeviceId::set(uuid).map_err(|e| e.to_string())?;
+    }
+
+    update_config(|cfg| {
+        if imported.password_hash.is_none() {
+            imported.password_hash = cfg.password_hash.take();
+            imported.password_verifier = cfg.password_verifier.take();
+        }
+        if imported.api_keys.is_empty() {
+            imported.api_keys = std::mem::take(&mut cfg.api_keys);
+        }
+        if imported.health_secret.is_none() {
+            imported.health_secret = cfg.health_secret.take();
+        }
+        *cfg = imported;
+    })
+    .map_err(|e| e.to_string())?;
+
+    log::info!("Config imported from {:?}", path);
+    Ok(summary)
 }
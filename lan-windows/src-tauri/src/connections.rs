@@ -0,0 +1,171 @@
+use chrono::{DateTime, Local};
+use once_cell::sync::{Lazy, OnceCell};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+/// 客户端连接方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionKind {
+    /// REST API 会话，从登录到注销
+    Api,
+    /// WebSocket 连接，从建立到断开
+    WebSocket,
+}
+
+/// 当前连接的客户端，随 client-* 事件发送给界面
+#[derive(Debug, Clone, Serialize)]
+pub struct ClientConnection {
+    pub id: String,
+    pub kind: ConnectionKind,
+    pub ip: String,
+    /// 认证后为令牌对应的设备或会话（见 Claims::identity）
+    pub device: Option<String>,
+    pub authenticated: bool,
+    pub connected_at: DateTime<Local>,
+    /// API 会话在令牌过期时结束
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Local>>,
+}
+
+static CONNECTIONS: Lazy<Mutex<HashMap<String, ClientConnection>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+// WebSocket 连接的关闭信号，服务端需要主动断开连接时通知
+static CLOSE_SIGNALS: Lazy<Mutex<HashMap<String, Arc<Notify>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+// API 服务器没有 AppState，启动时保存应用句柄用于发送事件
+static APP_HANDLE: OnceCell<AppHandle> = OnceCell::new();
+
+pub fn set_app_handle(handle: AppHandle) {
+    let _ = APP_HANDLE.set(handle);
+}
+
+fn emit(event: &str, connection: &ClientConnection) {
+    if let Some(handle) = APP_HANDLE.get() {
+        let _ = handle.emit(event, connection.clone());
+    }
+}
+
+/// WebSocket 连接建立，返回连接ID和关闭信号；收到信号时连接应断开
+pub fn connected(ip: &str) -> (String, Arc<Notify>) {
+    let connection = ClientConnection {
+        id: Uuid::new_v4().to_string(),
+        kind: ConnectionKind::WebSocket,
+        ip: crate::stats::ip_only(ip),
+        device: None,
+        authenticated: false,
+        connected_at: Local::now(),
+        expires_at: None,
+    };
+    emit("client-connected", &connection);
+    let id = connection.id.clone();
+    if let Ok(mut connections) = CONNECTIONS.lock() {
+        connections.insert(id.clone(), connection);
+    }
+    let close = Arc::new(Notify::new());
+    if let Ok(mut signals) = CLOSE_SIGNALS.lock() {
+        signals.insert(id.clone(), close.clone());
+    }
+    (id, close)
+}
+
+/// WebSocket 连接认证成功
+pub fn authenticated(id: &str, device: Option<String>) {
+    let Ok(mut connections) = CONNECTIONS.lock() else {
+        return;
+    };
+    if let Some(connection) = connections.get_mut(id) {
+        connection.authenticated = true;
+        connection.device = device;
+        emit("client-authenticated", connection);
+    }
+}
+
+/// REST API 登录成功，以令牌ID标识会话
+pub fn api_login(session: &str, ip: &str, device: Option<String>, expires_in: u64) {
+    let now = Local::now();
+    let connection = ClientConnection {
+        id: session.to_string(),
+        kind: ConnectionKind::Api,
+        ip: crate::stats::ip_only(ip),
+        device,
+        authenticated: true,
+        connected_at: now,
+        expires_at: Some(now + chrono::Duration::seconds(expires_in as i64)),
+    };
+    emit("client-authenticated", &connection);
+    if let Ok(mut connections) = CONNECTIONS.lock() {
+        connections.insert(connection.id.clone(), connection);
+    }
+}
+
+/// 连接断开或会话注销
+pub fn disconnected(id: &str) {
+    if let Ok(mut signals) = CLOSE_SIGNALS.lock() {
+        signals.remove(id);
+    }
+    let removed = CONNECTIONS
+        .lock()
+        .ok()
+        .and_then(|mut connections| connections.remove(id));
+    if let Some(connection) = removed {
+        emit("client-disconnected", &connection);
+    }
+}
+
+/// 结束满足条件的连接记录，通知 WebSocket 连接断开，并逐个发送 client-disconnected
+fn remove_where<F>(predicate: F) -> Vec<ClientConnection>
+where
+    F: Fn(&ClientConnection) -> bool,
+{
+    let removed: Vec<ClientConnection> = match CONNECTIONS.lock() {
+        Ok(mut connections) => {
+            let ids: Vec<String> = connections
+                .values()
+                .filter(|c| predicate(c))
+                .map(|c| c.id.clone())
+                .collect();
+            ids.iter().filter_map(|id| connections.remove(id)).collect()
+        }
+        Err(_) => Vec::new(),
+    };
+    if let Ok(mut signals) = CLOSE_SIGNALS.lock() {
+        for connection in &removed {
+            if let Some(close) = signals.remove(&connection.id) {
+                close.notify_one();
+            }
+        }
+    }
+    for connection in &removed {
+        emit("client-disconnected", connection);
+    }
+    removed
+}
+
+/// 吊销全部令牌后结束所有 API 会话
+pub fn end_api_sessions() {
+    remove_where(|c| c.kind == ConnectionKind::Api);
+}
+
+/// 结束来自黑名单IP的连接：断开 WebSocket，返回被结束的连接供调用方吊销 API 会话
+pub fn end_blacklisted() -> Vec<ClientConnection> {
+    remove_where(|c| crate::api::is_ip_blacklisted(&c.ip))
+}
+
+/// 当前连接的客户端，最早连接的在前；令牌已过期的 API 会话视为断开
+pub fn list() -> Vec<ClientConnection> {
+    let now = Local::now();
+    remove_where(|c| c.expires_at.map_or(false, |expires_at| expires_at <= now));
+    let mut list: Vec<ClientConnection> = CONNECTIONS
+        .lock()
+        .map(|connections| connections.values().cloned().collect())
+        .unwrap_or_default();
+    list.sort_by_key(|c| c.connected_at);
+    list
+}
@@ -0,0 +1,207 @@
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::io::{Read, Seek, SeekFrom, Write as _};
+use std::path::{Path, PathBuf};
+
+use crate::config::{AppConfig, SECRET_FIELDS};
+
+/// 崩溃报告中附带的最近日志行数
+const CRASH_LOG_LINES: usize = 200;
+/// 读取日志文件末尾的最大字节数
+const LOG_TAIL_BYTES: u64 = 64 * 1024;
+/// 最多保留的崩溃报告数量，超出时删除最旧的
+const MAX_CRASH_REPORTS: usize = 20;
+
+/// 崩溃报告文件信息
+#[derive(Debug, Clone, Serialize)]
+pub struct CrashReportInfo {
+    pub file_name: String,
+    pub path: String,
+    pub size: u64,
+    pub created_at: Option<DateTime<Local>>,
+}
+
+/// 崩溃报告目录
+pub fn crash_dir() -> PathBuf {
+    AppConfig::config_path()
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("crashes")
+}
+
+/// 安装 panic 钩子，在默认输出之后写入崩溃报告
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        match write_crash_report(info) {
+            Ok(path) => log::error!("Crash report written to {:?}", path),
+            Err(e) => log::error!("Failed to write crash report: {}", e),
+        }
+    }));
+}
+
+/// 直接读取磁盘上的配置，避免在 panic 时再去获取可能已被占用的锁
+fn config_summary() -> String {
+    let content = match std::fs::read_to_string(AppConfig::config_path()) {
+        Ok(content) => content,
+        Err(e) => return format!("(unavailable: {})", e),
+    };
+    match serde_json::from_str::<serde_json::Value>(&content) {
+        Ok(serde_json::Value::Object(mut map)) => {
+            for field in SECRET_FIELDS {
+                if map.get(*field).map_or(false, |v| !v.is_null()) {
+                    map.insert(field.to_string(), "***".into());
+                }
+            }
+            serde_json::to_string_pretty(&map).unwrap_or_default()
+        }
+        Ok(_) | Err(_) => "(invalid config file)".to_string(),
+    }
+}
+
+/// 读取日志文件的最后若干行
+fn log_tail() -> String {
+    let path = std::fs::read_to_string(AppConfig::config_path())
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+        .and_then(|config| config.get("log_file_path")?.as_str().map(PathBuf::from))
+        .unwrap_or_else(AppConfig::default_log_path);
+
+    let read = || -> std::io::Result<String> {
+        let mut file = std::fs::File::open(&path)?;
+        let len = file.metadata()?.len();
+        file.seek(SeekFrom::Start(len.saturating_sub(LOG_TAIL_BYTES)))?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    };
+    match read() {
+        Ok(content) => {
+            let lines: Vec<&str> = content.lines().collect();
+            let start = lines.len().saturating_sub(CRASH_LOG_LINES);
+            lines[start..].join("\n")
+        }
+        Err(e) => format!("(unavailable: {})", e),
+    }
+}
+
+fn write_crash_report(info: &std::panic::PanicHookInfo<'_>) -> std::io::Result<PathBuf> {
+    let now = Local::now();
+    let message = info
+        .payload()
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| info.payload().downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "(non-string panic payload)".to_string());
+    let location = info
+        .location()
+        .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()))
+        .unwrap_or_else(|| "unknown".to_string());
+    let thread = std::thread::current();
+
+    let mut report = String::new();
+    let _ = writeln!(report, "LanDevice Manager crash report");
+    let _ = writeln!(report, "Time: {}", now.to_rfc3339());
+    let _ = writeln!(report, "Version: {}", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(
+        report,
+        "OS: {} ({})",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+    let _ = writeln!(report, "Thread: {}", thread.name().unwrap_or("unnamed"));
+    let _ = writeln!(report, "Message: {}", message);
+    let _ = writeln!(report, "Location: {}", location);
+    let _ = writeln!(report, "\n== Backtrace ==");
+    let _ = writeln!(report, "{}", std::backtrace::Backtrace::force_capture());
+    let _ = writeln!(report, "\n== Config ==");
+    let _ = writeln!(report, "{}", config_summary());
+    let _ = writeln!(report, "\n== Recent log ({} lines) ==", CRASH_LOG_LINES);
+    let _ = writeln!(report, "{}", log_tail());
+
+    let dir = crash_dir();
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("crash-{}.txt", now.format("%Y%m%d_%H%M%S%.3f")));
+    crate::config::write_atomic(&path, report.as_bytes())?;
+    prune_reports();
+    Ok(path)
+}
+
+/// 删除超出数量的旧报告
+fn prune_reports() {
+    let reports = list_crash_reports();
+    for report in reports.iter().skip(MAX_CRASH_REPORTS) {
+        let _ = std::fs::remove_file(&report.path);
+    }
+}
+
+/// 列出崩溃报告，最新的在前
+pub fn list_crash_reports() -> Vec<CrashReportInfo> {
+    let Ok(entries) = std::fs::read_dir(crash_dir()) else {
+        return Vec::new();
+    };
+    let mut reports: Vec<CrashReportInfo> = entries
+        .flatten()
+        .filter(|e| {
+            let name = e.file_name().to_string_lossy().into_owned();
+            name.starts_with("crash-") && name.ends_with(".txt")
+        })
+        .filter_map(|e| {
+            let metadata = e.metadata().ok()?;
+            Some(CrashReportInfo {
+                file_name: e.file_name().to_string_lossy().into_owned(),
+                path: e.path().to_string_lossy().into_owned(),
+                size: metadata.len(),
+                created_at: metadata.modified().ok().map(DateTime::<Local>::from),
+            })
+        })
+        .collect();
+    // 文件名包含时间戳，按名称倒序即为时间倒序
+    reports.sort_by(|a, b| b.file_name.cmp(&a.file_name));
+    reports
+}
+
+/// 删除所有崩溃报告
+pub fn clear_crash_reports() -> usize {
+    list_crash_reports()
+        .iter()
+        .filter(|r| std::fs::remove_file(&r.path).is_ok())
+        .count()
+}
+
+/// 将崩溃报告、当前日志和配置摘要打包为 zip 诊断包
+pub fn create_diagnostics_bundle(path: &Path) -> Result<usize, String> {
+    let file = std::fs::File::create(path).map_err(|e| e.to_string())?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+
+    let mut add = |name: &str, content: &[u8]| -> Result<(), String> {
+        zip.start_file(name, options).map_err(|e| e.to_string())?;
+        zip.write_all(content).map_err(|e| e.to_string())
+    };
+
+    let reports = list_crash_reports();
+    for report in &reports {
+        let content = std::fs::read(&report.path).map_err(|e| e.to_string())?;
+        add(&format!("crashes/{}", report.file_name), &content)?;
+    }
+    add("config.json", config_summary().as_bytes())?;
+    add("recent.log", log_tail().as_bytes())?;
+    let summary = format!(
+        "version: {}\nos: {} ({})\ncreated: {}\ncrash_reports: {}\n",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        Local::now().to_rfc3339(),
+        reports.len()
+    );
+    add("summary.txt", summary.as_bytes())?;
+
+    zip.finish().map_err(|e| e.to_string())?;
+    log::info!("Diagnostics bundle written to {:?}", path);
+    Ok(reports.len())
+}
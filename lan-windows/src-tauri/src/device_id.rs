@@ -2,6 +2,12 @@ use std::fs;
 use std::path::PathBuf;
 use uuid::Uuid;
 
+/// 可配置的设备UUID来源：random 为首次运行时随机生成并保存，machine 为由本机硬件标识派生
+pub const DEVICE_ID_SOURCES: &[&str] = &["random", "machine"];
+
+/// 派生设备UUID时使用的命名空间前缀，避免直接暴露机器标识
+const MACHINE_ID_NAMESPACE: &str = "lan-device-manager:";
+
 /// 设备唯一标识符管理
 pub struct DeviceId {
     uuid: String,
@@ -16,8 +22,25 @@ impl DeviceId {
     /// 
     /// 后续调用时：
     /// - 直接返回已保存的UUID
+    ///
+    /// 配置为 machine 时优先使用由本机硬件标识派生的UUID，重装应用或清空 AppData 后保持不变
     pub fn get_or_create() -> Result<String, Box<dyn std::error::Error>> {
         let config_path = Self::get_config_path()?;
+
+        if crate::config::get_config().device_id_source == "machine" {
+            match Self::machine_uuid() {
+                Some(uuid) => {
+                    // 同时写入文件，切换回 random 时沿用同一个UUID
+                    if fs::read_to_string(&config_path).ok().as_deref().map(str::trim)
+                        != Some(uuid.as_str())
+                    {
+                        Self::save_uuid(&config_path, &uuid)?;
+                    }
+                    return Ok(uuid);
+                }
+                None => log::warn!("Failed to read machine identifier, using saved device UUID"),
+            }
+        }
         
         // 尝试读取已有UUID
         if config_path.exists() {
@@ -47,6 +70,76 @@ impl DeviceId {
         Ok(new_uuid)
     }
     
+    /// 使用指定的UUID替换当前设备UUID（导入配置时使用）
+    pub fn set(uuid: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let uuid = uuid.trim();
+        if !Self::is_valid_uuid(uuid) {
+            return Err(format!("Invalid device UUID: {}", uuid).into());
+        }
+        let config_path = Self::get_config_path()?;
+        Self::save_uuid(&config_path, uuid)
+    }
+    
+    /// 生成新的设备UUID替换当前的UUID
+    ///
+    /// 客户端以UUID识别设备，更换后本机会被当作一台新设备
+    pub fn regenerate() -> Result<String, Box<dyn std::error::Error>> {
+        if crate::config::get_config().device_id_source == "machine" {
+            return Err("Device UUID is derived from this machine, switch to a random UUID to regenerate it".into());
+        }
+        let new_uuid = Uuid::new_v4().to_string();
+        let config_path = Self::get_config_path()?;
+        Self::save_uuid(&config_path, &new_uuid)?;
+        log::warn!("Device UUID regenerated: {}", new_uuid);
+        Ok(new_uuid)
+    }
+    
+    /// 由本机硬件标识派生的UUID（UUID v5），读取不到标识时返回 None
+    pub fn machine_uuid() -> Option<String> {
+        let machine_id = Self::read_machine_id()?;
+        let name = format!("{}{}", MACHINE_ID_NAMESPACE, machine_id.trim().to_lowercase());
+        Some(Uuid::new_v5(&Uuid::NAMESPACE_OID, name.as_bytes()).to_string())
+    }
+
+    /// Windows 安装时生成的 MachineGuid，重装本应用不会改变
+    #[cfg(target_os = "windows")]
+    fn read_machine_id() -> Option<String> {
+        use windows::core::w;
+        use windows::Win32::System::Registry::{
+            RegGetValueW, HKEY_LOCAL_MACHINE, RRF_RT_REG_SZ, RRF_SUBKEY_WOW6464KEY,
+        };
+
+        let mut buffer = [0u16; 64];
+        let mut size = (buffer.len() * 2) as u32;
+        let result = unsafe {
+            RegGetValueW(
+                HKEY_LOCAL_MACHINE,
+                w!("SOFTWARE\\Microsoft\\Cryptography"),
+                w!("MachineGuid"),
+                RRF_RT_REG_SZ | RRF_SUBKEY_WOW6464KEY,
+                None,
+                Some(buffer.as_mut_ptr().cast()),
+                Some(&mut size),
+            )
+        };
+        if result.is_err() {
+            return None;
+        }
+        let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+        let id = String::from_utf16_lossy(&buffer[..len]);
+        (!id.is_empty()).then_some(id)
+    }
+
+    /// systemd 的 machine-id，没有时使用 DMI 中的主板UUID
+    #[cfg(not(target_os = "windows"))]
+    fn read_machine_id() -> Option<String> {
+        ["/etc/machine-id", "/var/lib/dbus/machine-id", "/sys/class/dmi/id/product_uuid"]
+            .iter()
+            .filter_map(|path| fs::read_to_string(path).ok())
+            .map(|id| id.trim().to_string())
+            .find(|id| !id.is_empty())
+    }
+    
     /// 获取配置文件路径
     /// 
     /// Windows: %APPDATA%\LanDeviceManager\device.uuid
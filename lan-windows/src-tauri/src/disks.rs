@@ -0,0 +1,207 @@
+use serde::Serialize;
+use std::process::Command;
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+/// SMART 属性
+#[derive(Debug, Clone, Serialize)]
+pub struct SmartAttribute {
+    pub id: u64,
+    pub name: String,
+    pub value: u64,
+    pub worst: u64,
+    pub threshold: u64,
+    pub raw: u64,
+    /// 当前值已低于阈值或曾经低于阈值
+    pub failing: bool,
+}
+
+/// 单块磁盘的健康状态
+#[derive(Debug, Clone, Serialize)]
+pub struct DiskHealth {
+    pub device: String,
+    pub model: Option<String>,
+    pub serial: Option<String>,
+    /// SMART 自检是否通过，无法判断时为 None
+    pub passed: Option<bool>,
+    pub temperature: Option<f32>,
+    pub power_on_hours: Option<u64>,
+    pub attributes: Vec<SmartAttribute>,
+    /// 数据来源：smartctl 或 wmi
+    pub source: String,
+}
+
+/// 所有磁盘的健康报告
+#[derive(Debug, Clone, Serialize)]
+pub struct DisksHealthReport {
+    pub disks: Vec<DiskHealth>,
+    /// 所有能判断状态的磁盘都通过检查
+    pub all_passed: bool,
+}
+
+/// 获取所有磁盘的 SMART 健康状态
+pub fn get_disks_health() -> Result<DisksHealthReport, String> {
+    let mut disks = smartctl_disks();
+
+    #[cfg(target_os = "windows")]
+    if disks.is_empty() {
+        disks = wmi_disks()?;
+    }
+
+    if disks.is_empty() {
+        return Err(
+            "No disk health data available. Install smartmontools and run with administrator privileges."
+                .to_string(),
+        );
+    }
+
+    let all_passed = disks.iter().all(|d| d.passed != Some(false));
+    Ok(DisksHealthReport { disks, all_passed })
+}
+
+fn run_smartctl(args: &[&str]) -> Option<serde_json::Value> {
+    let mut command = Command::new("smartctl");
+    command.args(args);
+    #[cfg(target_os = "windows")]
+    command.creation_flags(CREATE_NO_WINDOW);
+
+    // smartctl 用退出码的位表示磁盘状态，非 0 时输出仍然有效
+    let output = command.output().ok()?;
+    serde_json::from_slice(&output.stdout).ok()
+}
+
+fn smartctl_disks() -> Vec<DiskHealth> {
+    let Some(scan) = run_smartctl(&["--scan", "-j"]) else {
+        return Vec::new();
+    };
+
+    let devices: Vec<String> = scan
+        .get("devices")
+        .and_then(|d| d.as_array())
+        .map(|list| {
+            list.iter()
+                .filter_map(|d| d.get("name").and_then(|n| n.as_str()).map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    devices
+        .iter()
+        .filter_map(|device| {
+            let info = run_smartctl(&["-a", "-j", device])?;
+            Some(parse_smartctl(device, &info))
+        })
+        .collect()
+}
+
+fn parse_smartctl(device: &str, info: &serde_json::Value) -> DiskHealth {
+    let attributes = info
+        .pointer("/ata_smart_attributes/table")
+        .and_then(|t| t.as_array())
+        .map(|table| {
+            table
+                .iter()
+                .map(|attr| {
+                    let number = |key: &str| attr.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+                    let value = number("value");
+                    let threshold = number("thresh");
+                    let when_failed = attr
+                        .get("when_failed")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("");
+                    SmartAttribute {
+                        id: number("id"),
+                        name: attr
+                            .get("name")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("")
+                            .to_string(),
+                        value,
+                        worst: number("worst"),
+                        threshold,
+                        raw: attr.pointer("/raw/value").and_then(|v| v.as_u64()).unwrap_or(0),
+                        failing: !when_failed.is_empty() || (threshold > 0 && value <= threshold),
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    DiskHealth {
+        device: device.to_string(),
+        model: info
+            .get("model_name")
+            .and_then(|v| v.as_str())
+            .map(str::to_string),
+        serial: info
+            .get("serial_number")
+            .and_then(|v| v.as_str())
+            .map(str::to_string),
+        passed: info.pointer("/smart_status/passed").and_then(|v| v.as_bool()),
+        temperature: info
+            .pointer("/temperature/current")
+            .and_then(|v| v.as_f64())
+            .map(|t| t as f32),
+        power_on_hours: info.pointer("/power_on_time/hours").and_then(|v| v.as_u64()),
+        attributes,
+        source: "smartctl".to_string(),
+    }
+}
+
+/// 未安装 smartctl 时通过 Get-PhysicalDisk 读取存储子系统报告的健康状态
+#[cfg(target_os = "windows")]
+fn wmi_disks() -> Result<Vec<DiskHealth>, String> {
+    let output = Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-Command",
+            "Get-PhysicalDisk | ForEach-Object { $r = $_ | Get-StorageReliabilityCounter -ErrorAction SilentlyContinue; [PSCustomObject]@{ DeviceId = $_.DeviceId; Model = $_.FriendlyName; Serial = $_.SerialNumber; Health = [string]$_.HealthStatus; Temperature = $r.Temperature; PowerOnHours = $r.PowerOnHours } } | ConvertTo-Json -Compress",
+        ])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map_err(|e| e.to_string())?;
+
+    let text = String::from_utf8_lossy(&output.stdout);
+    // 只有一块磁盘时 ConvertTo-Json 输出对象而不是数组
+    let items = match serde_json::from_str::<serde_json::Value>(text.trim()) {
+        Ok(serde_json::Value::Array(items)) => items,
+        Ok(item @ serde_json::Value::Object(_)) => vec![item],
+        _ => return Ok(Vec::new()),
+    };
+
+    Ok(items
+        .iter()
+        .map(|item| {
+            let text = |key: &str| {
+                item.get(key)
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+            };
+            DiskHealth {
+                device: text("DeviceId")
+                    .map(|id| format!("PhysicalDisk{}", id))
+                    .unwrap_or_else(|| "unknown".to_string()),
+                model: text("Model"),
+                serial: text("Serial"),
+                passed: match text("Health").as_deref() {
+                    Some("Healthy") => Some(true),
+                    Some("Warning") | Some("Unhealthy") => Some(false),
+                    _ => None,
+                },
+                temperature: item
+                    .get("Temperature")
+                    .and_then(|v| v.as_f64())
+                    .filter(|t| *t > 0.0)
+                    .map(|t| t as f32),
+                power_on_hours: item.get("PowerOnHours").and_then(|v| v.as_u64()),
+                attributes: Vec::new(),
+                source: "wmi".to_string(),
+            }
+        })
+        .collect())
+}
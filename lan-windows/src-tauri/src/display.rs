@@ -0,0 +1,388 @@
+use serde::Serialize;
+
+/// 显示模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct DisplayMode {
+    pub width: u32,
+    pub height: u32,
+    pub refresh_rate: u32,
+}
+
+/// 显示器及其支持的模式
+#[derive(Debug, Clone, Serialize)]
+pub struct MonitorInfo {
+    /// 设备名，切换模式时使用（Windows 为 \\.\DISPLAY1，Linux 为 xrandr 输出名）
+    pub name: String,
+    pub description: Option<String>,
+    pub primary: bool,
+    pub position_x: i32,
+    pub position_y: i32,
+    pub current: Option<DisplayMode>,
+    pub modes: Vec<DisplayMode>,
+}
+
+/// 查找显示器并确认请求的模式受支持
+#[cfg(not(target_os = "macos"))]
+fn find_supported_mode(
+    name: &str,
+    width: u32,
+    height: u32,
+    refresh_rate: Option<u32>,
+) -> Result<(MonitorInfo, DisplayMode), String> {
+    let monitor = list_monitors()?
+        .into_iter()
+        .find(|m| m.name == name)
+        .ok_or_else(|| format!("Monitor '{}' not found", name))?;
+
+    let mode = monitor
+        .modes
+        .iter()
+        .filter(|m| m.width == width && m.height == height)
+        .filter(|m| refresh_rate.map_or(true, |rate| m.refresh_rate == rate))
+        .max_by_key(|m| m.refresh_rate)
+        .copied()
+        .ok_or_else(|| {
+            format!(
+                "Mode {}x{}{} is not supported by '{}'",
+                width,
+                height,
+                refresh_rate
+                    .map(|r| format!("@{}Hz", r))
+                    .unwrap_or_default(),
+                name
+            )
+        })?;
+
+    Ok((monitor, mode))
+}
+
+#[cfg(target_os = "windows")]
+fn wide_to_string(wide: &[u16]) -> String {
+    let len = wide.iter().position(|&c| c == 0).unwrap_or(wide.len());
+    String::from_utf16_lossy(&wide[..len])
+}
+
+#[cfg(target_os = "windows")]
+fn current_devmode(
+    name: &windows::core::HSTRING,
+) -> Option<windows::Win32::Graphics::Gdi::DEVMODEW> {
+    use windows::Win32::Graphics::Gdi::{EnumDisplaySettingsW, DEVMODEW, ENUM_CURRENT_SETTINGS};
+
+    let mut devmode = DEVMODEW {
+        dmSize: std::mem::size_of::<DEVMODEW>() as u16,
+        ..Default::default()
+    };
+    unsafe { EnumDisplaySettingsW(name, ENUM_CURRENT_SETTINGS, &mut devmode) }
+        .as_bool()
+        .then_some(devmode)
+}
+
+/// 列出连接到桌面的显示器
+#[cfg(target_os = "windows")]
+pub fn list_monitors() -> Result<Vec<MonitorInfo>, String> {
+    use windows::core::{HSTRING, PCWSTR};
+    use windows::Win32::Graphics::Gdi::{
+        EnumDisplayDevicesW, EnumDisplaySettingsW, DEVMODEW, DISPLAY_DEVICEW,
+        DISPLAY_DEVICE_ATTACHED_TO_DESKTOP, DISPLAY_DEVICE_PRIMARY_DEVICE,
+        ENUM_DISPLAY_SETTINGS_MODE,
+    };
+
+    let mut monitors = Vec::new();
+    for index in 0.. {
+        let mut device = DISPLAY_DEVICEW {
+            cb: std::mem::size_of::<DISPLAY_DEVICEW>() as u32,
+            ..Default::default()
+        };
+        if !unsafe { EnumDisplayDevicesW(PCWSTR::null(), index, &mut device, 0) }.as_bool() {
+            break;
+        }
+        if device.StateFlags.0 & DISPLAY_DEVICE_ATTACHED_TO_DESKTOP.0 == 0 {
+            continue;
+        }
+
+        let name = wide_to_string(&device.DeviceName);
+        let device_name = HSTRING::from(name.as_str());
+
+        let mut modes: Vec<DisplayMode> = Vec::new();
+        for mode_index in 0.. {
+            let mut devmode = DEVMODEW {
+                dmSize: std::mem::size_of::<DEVMODEW>() as u16,
+                ..Default::default()
+            };
+            let found = unsafe {
+                EnumDisplaySettingsW(
+                    &device_name,
+                    ENUM_DISPLAY_SETTINGS_MODE(mode_index),
+                    &mut devmode,
+                )
+            };
+            if !found.as_bool() {
+                break;
+            }
+            let mode = DisplayMode {
+                width: devmode.dmPelsWidth,
+                height: devmode.dmPelsHeight,
+                refresh_rate: devmode.dmDisplayFrequency,
+            };
+            // 同一分辨率会按色深重复出现
+            if !modes.contains(&mode) {
+                modes.push(mode);
+            }
+        }
+        modes.sort_by(|a, b| {
+            (b.width, b.height, b.refresh_rate).cmp(&(a.width, a.height, a.refresh_rate))
+        });
+
+        let current = current_devmode(&device_name);
+        let position = current
+            .map(|devmode| unsafe { devmode.Anonymous1.Anonymous2.dmPosition })
+            .unwrap_or_default();
+
+        monitors.push(MonitorInfo {
+            name,
+            description: Some(wide_to_string(&device.DeviceString)).filter(|s| !s.is_empty()),
+            primary: device.StateFlags.0 & DISPLAY_DEVICE_PRIMARY_DEVICE.0 != 0,
+            position_x: position.x,
+            position_y: position.y,
+            current: current.map(|devmode| DisplayMode {
+                width: devmode.dmPelsWidth,
+                height: devmode.dmPelsHeight,
+                refresh_rate: devmode.dmDisplayFrequency,
+            }),
+            modes,
+        });
+    }
+
+    Ok(monitors)
+}
+
+#[cfg(target_os = "windows")]
+fn check_disp_change(result: windows::Win32::Graphics::Gdi::DISP_CHANGE) -> Result<(), String> {
+    use windows::Win32::Graphics::Gdi::{DISP_CHANGE_RESTART, DISP_CHANGE_SUCCESSFUL};
+
+    match result {
+        DISP_CHANGE_SUCCESSFUL => Ok(()),
+        DISP_CHANGE_RESTART => Err("The change requires a restart to take effect".to_string()),
+        other => Err(format!(
+            "Failed to change display settings (code {})",
+            other.0
+        )),
+    }
+}
+
+/// 切换显示器分辨率，refresh_rate 为空时使用该分辨率下最高的刷新率
+#[cfg(target_os = "windows")]
+pub fn set_mode(
+    name: &str,
+    width: u32,
+    height: u32,
+    refresh_rate: Option<u32>,
+) -> Result<(), String> {
+    use windows::core::HSTRING;
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::Graphics::Gdi::{
+        ChangeDisplaySettingsExW, CDS_UPDATEREGISTRY, DM_DISPLAYFREQUENCY, DM_PELSHEIGHT,
+        DM_PELSWIDTH,
+    };
+
+    let (_, mode) = find_supported_mode(name, width, height, refresh_rate)?;
+    let device_name = HSTRING::from(name);
+    let mut devmode = current_devmode(&device_name)
+        .ok_or_else(|| format!("Failed to read current mode of '{}'", name))?;
+    devmode.dmPelsWidth = mode.width;
+    devmode.dmPelsHeight = mode.height;
+    devmode.dmDisplayFrequency = mode.refresh_rate;
+    devmode.dmFields = DM_PELSWIDTH | DM_PELSHEIGHT | DM_DISPLAYFREQUENCY;
+
+    check_disp_change(unsafe {
+        ChangeDisplaySettingsExW(
+            &device_name,
+            Some(&devmode as *const _),
+            HWND::default(),
+            CDS_UPDATEREGISTRY,
+            None,
+        )
+    })
+}
+
+/// 设置主显示器，其余显示器按相对位置平移使新的主显示器位于原点
+#[cfg(target_os = "windows")]
+pub fn set_primary(name: &str) -> Result<(), String> {
+    use windows::core::{HSTRING, PCWSTR};
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::Graphics::Gdi::{
+        ChangeDisplaySettingsExW, CDS_NORESET, CDS_SET_PRIMARY, CDS_TYPE, CDS_UPDATEREGISTRY,
+        DM_POSITION,
+    };
+
+    let monitors = list_monitors()?;
+    let target = monitors
+        .iter()
+        .find(|m| m.name == name)
+        .ok_or_else(|| format!("Monitor '{}' not found", name))?;
+    if target.primary {
+        return Ok(());
+    }
+    let (offset_x, offset_y) = (target.position_x, target.position_y);
+
+    // 先以 CDS_NORESET 写入所有显示器的新位置，最后统一生效
+    for monitor in &monitors {
+        let device_name = HSTRING::from(monitor.name.as_str());
+        let Some(mut devmode) = current_devmode(&device_name) else {
+            continue;
+        };
+        devmode.Anonymous1.Anonymous2.dmPosition.x = monitor.position_x - offset_x;
+        devmode.Anonymous1.Anonymous2.dmPosition.y = monitor.position_y - offset_y;
+        devmode.dmFields = DM_POSITION;
+
+        let mut flags: CDS_TYPE = CDS_UPDATEREGISTRY | CDS_NORESET;
+        if monitor.name == name {
+            flags |= CDS_SET_PRIMARY;
+        }
+        check_disp_change(unsafe {
+            ChangeDisplaySettingsExW(
+                &device_name,
+                Some(&devmode as *const _),
+                HWND::default(),
+                flags,
+                None,
+            )
+        })?;
+    }
+
+    check_disp_change(unsafe {
+        ChangeDisplaySettingsExW(PCWSTR::null(), None, HWND::default(), CDS_TYPE(0), None)
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn run_xrandr(args: &[&str]) -> Result<String, String> {
+    let output = std::process::Command::new("xrandr")
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run xrandr: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// 解析 xrandr 输出：
+///   HDMI-1 connected primary 1920x1080+0+0 (normal left inverted right) 527mm x 296mm
+///      1920x1080     60.00*+  50.00    59.94
+#[cfg(target_os = "linux")]
+pub fn list_monitors() -> Result<Vec<MonitorInfo>, String> {
+    let output = run_xrandr(&["--query"])?;
+    let mut monitors: Vec<MonitorInfo> = Vec::new();
+    let mut in_connected = false;
+
+    for line in output.lines() {
+        if !line.starts_with(' ') {
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            in_connected = tokens.get(1) == Some(&"connected");
+            if !in_connected {
+                continue;
+            }
+            // 几何信息形如 1920x1080+0+0，未启用的输出没有该字段
+            let position = tokens
+                .iter()
+                .find_map(|t| {
+                    let mut parts = t.split('+');
+                    let _size = parts.next()?.split_once('x')?;
+                    Some((parts.next()?.parse().ok()?, parts.next()?.parse().ok()?))
+                })
+                .unwrap_or((0, 0));
+            monitors.push(MonitorInfo {
+                name: tokens[0].to_string(),
+                description: None,
+                primary: tokens.contains(&"primary"),
+                position_x: position.0,
+                position_y: position.1,
+                current: None,
+                modes: Vec::new(),
+            });
+            continue;
+        }
+
+        let Some(monitor) = monitors.last_mut().filter(|_| in_connected) else {
+            continue;
+        };
+        let mut tokens = line.split_whitespace();
+        let Some((width, height)) = tokens.next().and_then(|t| {
+            let (w, h) = t.split_once('x')?;
+            // 隔行模式形如 1920x1080i
+            Some((
+                w.parse::<u32>().ok()?,
+                h.trim_end_matches('i').parse::<u32>().ok()?,
+            ))
+        }) else {
+            continue;
+        };
+        for rate in tokens {
+            let Ok(value) = rate.trim_end_matches(['*', '+']).parse::<f32>() else {
+                continue;
+            };
+            let mode = DisplayMode {
+                width,
+                height,
+                refresh_rate: value.round() as u32,
+            };
+            if rate.contains('*') {
+                monitor.current = Some(mode);
+            }
+            if !monitor.modes.contains(&mode) {
+                monitor.modes.push(mode);
+            }
+        }
+    }
+
+    Ok(monitors)
+}
+
+#[cfg(target_os = "linux")]
+pub fn set_mode(
+    name: &str,
+    width: u32,
+    height: u32,
+    refresh_rate: Option<u32>,
+) -> Result<(), String> {
+    let (monitor, mode) = find_supported_mode(name, width, height, refresh_rate)?;
+    run_xrandr(&[
+        "--output",
+        &monitor.name,
+        "--mode",
+        &format!("{}x{}", mode.width, mode.height),
+        "--rate",
+        &mode.refresh_rate.to_string(),
+    ])
+    .map(|_| ())
+}
+
+#[cfg(target_os = "linux")]
+pub fn set_primary(name: &str) -> Result<(), String> {
+    let monitor = list_monitors()?
+        .into_iter()
+        .find(|m| m.name == name)
+        .ok_or_else(|| format!("Monitor '{}' not found", name))?;
+    run_xrandr(&["--output", &monitor.name, "--primary"]).map(|_| ())
+}
+
+#[cfg(target_os = "macos")]
+pub fn list_monitors() -> Result<Vec<MonitorInfo>, String> {
+    Err("Display mode control is not supported on macOS".to_string())
+}
+
+#[cfg(target_os = "macos")]
+pub fn set_mode(
+    _name: &str,
+    _width: u32,
+    _height: u32,
+    _refresh_rate: Option<u32>,
+) -> Result<(), String> {
+    Err("Display mode control is not supported on macOS".to_string())
+}
+
+#[cfg(target_os = "macos")]
+pub fn set_primary(_name: &str) -> Result<(), String> {
+    Err("Display mode control is not supported on macOS".to_string())
+}
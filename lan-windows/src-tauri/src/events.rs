@@ -0,0 +1,106 @@
+use chrono::{DateTime, Local};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+use crate::websocket::WsMessage;
+
+/// 为长轮询客户端保留的最近事件数量
+const EVENT_BUFFER_SIZE: usize = 200;
+
+/// 带序号的事件，序号即长轮询的游标
+#[derive(Debug, Clone, Serialize)]
+pub struct BufferedEvent {
+    pub seq: u64,
+    pub timestamp: DateTime<Local>,
+    pub event: WsMessage,
+}
+
+/// 一次长轮询的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct EventBatch {
+    /// 下次轮询时作为 since 传回
+    pub cursor: u64,
+    pub events: Vec<BufferedEvent>,
+    /// 游标之后的部分事件已被丢弃（或服务端已重启），客户端应重新拉取完整状态
+    pub missed: bool,
+}
+
+struct EventBus {
+    tx: broadcast::Sender<WsMessage>,
+    buffer: Mutex<(u64, VecDeque<BufferedEvent>)>,
+}
+
+// WebSocket 广播和长轮询共用同一个事件总线
+static BUS: Lazy<EventBus> = Lazy::new(|| EventBus {
+    tx: broadcast::channel(crate::config::get_config().ws_broadcast_capacity.max(1)).0,
+    buffer: Mutex::new((0, VecDeque::with_capacity(EVENT_BUFFER_SIZE))),
+});
+
+/// 订阅实时事件
+pub fn subscribe() -> broadcast::Receiver<WsMessage> {
+    BUS.tx.subscribe()
+}
+
+/// 发布事件：记录到缓冲区供长轮询读取，并广播给 WebSocket 连接，返回实时接收者数量
+pub fn publish(event: WsMessage) -> usize {
+    if let Ok(mut buffer) = BUS.buffer.lock() {
+        let (last_seq, events) = &mut *buffer;
+        *last_seq += 1;
+        if events.len() == EVENT_BUFFER_SIZE {
+            events.pop_front();
+        }
+        events.push_back(BufferedEvent {
+            seq: *last_seq,
+            timestamp: Local::now(),
+            event: event.clone(),
+        });
+    }
+    BUS.tx.send(event).unwrap_or(0)
+}
+
+/// 只发送给实时连接，不进入长轮询缓冲区，用于日志等高频消息
+pub fn broadcast(event: WsMessage) -> usize {
+    BUS.tx.send(event).unwrap_or(0)
+}
+
+/// 当前游标，即最新事件的序号
+pub fn cursor() -> u64 {
+    BUS.buffer.lock().map(|buffer| buffer.0).unwrap_or(0)
+}
+
+/// 读取游标之后的事件
+pub fn events_since(since: u64) -> EventBatch {
+    let Ok(buffer) = BUS.buffer.lock() else {
+        return EventBatch {
+            cursor: since,
+            events: Vec::new(),
+            missed: false,
+        };
+    };
+    let (last_seq, events) = &*buffer;
+    // 游标超过最新序号说明服务端重启过，返回全部缓冲事件
+    let restarted = since > *last_seq;
+    let since = if restarted { 0 } else { since };
+    let oldest = events.front().map_or(*last_seq + 1, |e| e.seq);
+    EventBatch {
+        cursor: *last_seq,
+        events: events.iter().filter(|e| e.seq > since).cloned().collect(),
+        missed: restarted || oldest > since + 1,
+    }
+}
+
+/// 等待游标之后的事件，最多等待 timeout；超时返回空列表
+pub async fn poll(since: u64, timeout: Duration) -> EventBatch {
+    // 先订阅再检查缓冲区，避免两步之间发布的事件被漏掉
+    let mut rx = subscribe();
+    let batch = events_since(since);
+    if !batch.events.is_empty() || batch.missed {
+        return batch;
+    }
+    let _ = tokio::time::timeout(timeout, rx.recv()).await;
+    events_since(since)
+}
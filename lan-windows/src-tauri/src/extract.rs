@@ -0,0 +1,142 @@
+use axum::async_trait;
+use axum::body::Bytes;
+use axum::extract::{FromRequest, Request};
+use axum::http::header::CONTENT_TYPE;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::messages::ErrorCode;
+
+/// 请求体无法解析时的说明，放在响应的 error_details 中
+#[derive(Debug, Clone, Serialize)]
+pub struct InvalidRequest {
+    /// 出错字段的路径，如 "args[0]"；整个请求体有误时为 "."
+    pub path: String,
+    /// 期望的类型，如 "a string"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected: Option<String>,
+    pub message: String,
+}
+
+impl InvalidRequest {
+    fn from_error(error: serde_path_to_error::Error<serde_json::Error>) -> Self {
+        let mut path = error.path().to_string();
+        let message = error.inner().to_string();
+        // 缺少字段时路径停在上一级，补上字段名
+        if let Some(field) = message
+            .strip_prefix("missing field `")
+            .and_then(|rest| rest.split('`').next())
+        {
+            path = if path == "." {
+                field.to_string()
+            } else {
+                format!("{}.{}", path, field)
+            };
+        }
+        let expected = message
+            .split_once(", expected ")
+            .map(|(_, rest)| rest.split(" at line ").next().unwrap_or(rest).to_string());
+        Self {
+            path,
+            expected,
+            message,
+        }
+    }
+
+    fn into_response(self) -> Response {
+        let error = match self.expected {
+            Some(ref expected) => format!(
+                "Invalid request: field '{}' expected {}",
+                self.path, expected
+            ),
+            None => format!("Invalid request: {}", self.message),
+        };
+        let body = serde_json::json!({
+            "success": false,
+            "data": null,
+            "error": error,
+            "error_code": ErrorCode::InvalidRequest.as_str(),
+            "error_details": self,
+        });
+        (StatusCode::BAD_REQUEST, axum::Json(body)).into_response()
+    }
+}
+
+/// JSON 请求体提取器，与 axum::Json 用法相同
+///
+/// 请求体格式错误时返回 400 和与 ApiResponse 一致的 JSON，
+/// 指出出错字段和期望类型，而不是 axum 默认的纯文本错误
+pub struct Json<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for Json<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let is_json = req
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map_or(false, |ct| ct.starts_with("application/json"));
+        if !is_json {
+            return Err(InvalidRequest {
+                path: ".".to_string(),
+                expected: Some("Content-Type: application/json".to_string()),
+                message: "Request body must be JSON".to_string(),
+            }
+            .into_response());
+        }
+
+        // 请求体过大等错误保持 axum 原有的响应
+        let bytes = Bytes::from_request(req, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        let mut deserializer = serde_json::Deserializer::from_slice(&bytes);
+        let value = serde_path_to_error::deserialize(&mut deserializer)
+            .map_err(|e| InvalidRequest::from_error(e).into_response())?;
+        deserializer.end().map_err(|e| {
+            InvalidRequest {
+                path: ".".to_string(),
+                expected: None,
+                message: e.to_string(),
+            }
+            .into_response()
+        })?;
+        Ok(Json(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, serde::Deserialize)]
+    #[allow(dead_code)]
+    struct Command {
+        command: String,
+        args: Option<Vec<String>>,
+    }
+
+    fn parse(json: &str) -> InvalidRequest {
+        let mut deserializer = serde_json::Deserializer::from_str(json);
+        let error = serde_path_to_error::deserialize::<_, Command>(&mut deserializer).unwrap_err();
+        InvalidRequest::from_error(error)
+    }
+
+    #[test]
+    fn reports_field_path_and_expected_type() {
+        let invalid = parse(r#"{"command": "ping", "args": ["-n", 4]}"#);
+        assert_eq!(invalid.path, "args[1]");
+        assert_eq!(invalid.expected.as_deref(), Some("a string"));
+
+        let invalid = parse(r#"{"args": []}"#);
+        assert_eq!(invalid.path, "command");
+        assert!(invalid.expected.is_none());
+    }
+}
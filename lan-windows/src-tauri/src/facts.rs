@@ -0,0 +1,207 @@
+use serde::Serialize;
+use std::process::Command;
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+/// 主机环境信息，用于资产盘点
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HostFacts {
+    pub hostname: String,
+    pub domain: Option<String>,
+    pub workgroup: Option<String>,
+    pub timezone: Option<String>,
+    pub locale: Option<String>,
+    pub dotnet_version: Option<String>,
+    pub powershell_version: Option<String>,
+    /// 是否运行在虚拟机中，无法判断时为 None
+    pub virtual_machine: Option<bool>,
+    pub manufacturer: Option<String>,
+    pub model: Option<String>,
+    pub serial_number: Option<String>,
+    pub bios_vendor: Option<String>,
+    pub bios_version: Option<String>,
+    pub bios_date: Option<String>,
+    pub board_manufacturer: Option<String>,
+    pub board_product: Option<String>,
+}
+
+/// 根据制造商和型号判断是否为虚拟机
+fn is_virtual_model(manufacturer: Option<&str>, model: Option<&str>) -> bool {
+    const MARKERS: [&str; 7] = [
+        "virtual", "vmware", "virtualbox", "kvm", "qemu", "xen", "parallels",
+    ];
+    [manufacturer, model].iter().flatten().any(|value| {
+        let value = value.to_lowercase();
+        MARKERS.iter().any(|marker| value.contains(marker))
+    })
+}
+
+/// 获取主机环境信息
+pub fn get_host_facts() -> Result<HostFacts, String> {
+    let hostname = hostname::get()
+        .map_err(|e| e.to_string())?
+        .into_string()
+        .unwrap_or_else(|_| "unknown".to_string());
+
+    let mut facts = collect_facts()?;
+    facts.hostname = hostname;
+    if facts.virtual_machine.is_none() {
+        facts.virtual_machine = Some(is_virtual_model(
+            facts.manufacturer.as_deref(),
+            facts.model.as_deref(),
+        ));
+    }
+    Ok(facts)
+}
+
+#[cfg(target_os = "windows")]
+fn collect_facts() -> Result<HostFacts, String> {
+    const SCRIPT: &str = r#"
+$cs = Get-CimInstance Win32_ComputerSystem
+$bios = Get-CimInstance Win32_BIOS
+$board = Get-CimInstance Win32_BaseBoard
+$ndp = Get-ItemProperty 'HKLM:\SOFTWARE\Microsoft\NET Framework Setup\NDP\v4\Full' -ErrorAction SilentlyContinue
+[PSCustomObject]@{
+    Domain = if ($cs.PartOfDomain) { $cs.Domain } else { $null }
+    Workgroup = $cs.Workgroup
+    TimeZone = (Get-TimeZone).Id
+    Locale = (Get-Culture).Name
+    DotNet = $ndp.Version
+    PowerShell = $PSVersionTable.PSVersion.ToString()
+    Manufacturer = $cs.Manufacturer
+    Model = $cs.Model
+    Serial = $bios.SerialNumber
+    BiosVendor = $bios.Manufacturer
+    BiosVersion = $bios.SMBIOSBIOSVersion
+    BiosDate = if ($bios.ReleaseDate) { $bios.ReleaseDate.ToString("yyyy-MM-dd") } else { $null }
+    BoardManufacturer = $board.Manufacturer
+    BoardProduct = $board.Product
+} | ConvertTo-Json -Compress
+"#;
+
+    let output = Command::new("powershell")
+        .args(["-NoProfile", "-Command", SCRIPT])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+
+    let value: serde_json::Value = serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("Invalid facts output: {}", e))?;
+    let text = |key: &str| {
+        value
+            .get(key)
+            .and_then(|v| v.as_str())
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+    };
+
+    Ok(HostFacts {
+        domain: text("Domain"),
+        workgroup: text("Workgroup"),
+        timezone: text("TimeZone"),
+        locale: text("Locale"),
+        dotnet_version: text("DotNet"),
+        powershell_version: text("PowerShell"),
+        manufacturer: text("Manufacturer"),
+        model: text("Model"),
+        serial_number: text("Serial"),
+        bios_vendor: text("BiosVendor"),
+        bios_version: text("BiosVersion"),
+        bios_date: text("BiosDate"),
+        board_manufacturer: text("BoardManufacturer"),
+        board_product: text("BoardProduct"),
+        ..Default::default()
+    })
+}
+
+/// 执行命令并返回去除空白后的非空输出
+#[cfg(not(target_os = "windows"))]
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+/// 系统区域设置
+#[cfg(not(target_os = "windows"))]
+fn env_locale() -> Option<String> {
+    ["LC_ALL", "LANG"]
+        .iter()
+        .filter_map(|key| std::env::var(key).ok())
+        .find(|v| !v.is_empty())
+}
+
+#[cfg(target_os = "linux")]
+fn collect_facts() -> Result<HostFacts, String> {
+    // 读取 DMI 信息，部分字段（如序列号）需要 root 权限
+    let dmi = |name: &str| {
+        std::fs::read_to_string(format!("/sys/class/dmi/id/{}", name))
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+    };
+
+    Ok(HostFacts {
+        domain: command_output("hostname", &["-d"]),
+        timezone: command_output("timedatectl", &["show", "-p", "Timezone", "--value"]).or_else(
+            || {
+                std::fs::read_to_string("/etc/timezone")
+                    .ok()
+                    .map(|s| s.trim().to_string())
+            },
+        ),
+        locale: env_locale(),
+        dotnet_version: command_output("dotnet", &["--version"]),
+        powershell_version: command_output(
+            "pwsh",
+            &["-NoProfile", "-Command", "$PSVersionTable.PSVersion.ToString()"],
+        ),
+        // systemd-detect-virt 在物理机上输出 none 并返回非 0
+        virtual_machine: Command::new("systemd-detect-virt")
+            .output()
+            .ok()
+            .map(|o| String::from_utf8_lossy(&o.stdout).trim() != "none"),
+        manufacturer: dmi("sys_vendor"),
+        model: dmi("product_name"),
+        serial_number: dmi("product_serial"),
+        bios_vendor: dmi("bios_vendor"),
+        bios_version: dmi("bios_version"),
+        bios_date: dmi("bios_date"),
+        board_manufacturer: dmi("board_vendor"),
+        board_product: dmi("board_name"),
+        ..Default::default()
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn collect_facts() -> Result<HostFacts, String> {
+    Ok(HostFacts {
+        timezone: std::fs::read_link("/etc/localtime").ok().and_then(|path| {
+            path.to_string_lossy()
+                .split("zoneinfo/")
+                .nth(1)
+                .map(str::to_string)
+        }),
+        locale: env_locale(),
+        dotnet_version: command_output("dotnet", &["--version"]),
+        powershell_version: command_output(
+            "pwsh",
+            &["-NoProfile", "-Command", "$PSVersionTable.PSVersion.ToString()"],
+        ),
+        virtual_machine: command_output("sysctl", &["-n", "kern.hv_vm_present"])
+            .map(|v| v == "1"),
+        manufacturer: Some("Apple Inc.".to_string()),
+        model: command_output("sysctl", &["-n", "hw.model"]),
+        ..Default::default()
+    })
+}
@@ -0,0 +1,568 @@
+use chrono::{DateTime, Local};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use regex::Regex;
+use serde::Serialize;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::config::get_config;
+
+/// 搜索时最大递归深度
+const MAX_SEARCH_DEPTH: usize = 16;
+/// 单次搜索最多扫描的条目数，防止遍历超大目录
+const MAX_SCANNED_ENTRIES: usize = 50_000;
+/// 默认每页结果数
+const DEFAULT_PAGE_SIZE: usize = 100;
+/// 每页最大结果数
+const MAX_PAGE_SIZE: usize = 500;
+/// 下载时单次响应最多返回的字节数，客户端按 Content-Range 继续请求
+pub const MAX_TRANSFER_CHUNK: u64 = 4 * 1024 * 1024;
+/// 上传未完成时保存数据的临时文件后缀
+const PARTIAL_SUFFIX: &str = ".part";
+
+/// 文件搜索参数
+#[derive(Debug, Clone)]
+pub struct FileSearchOptions {
+    /// 文件名匹配模式
+    pub pattern: String,
+    /// 为 true 时 pattern 按正则表达式处理，否则按通配符（* 和 ?）处理
+    pub regex: bool,
+    /// 只在指定的共享根目录下搜索
+    pub root: Option<String>,
+    /// 文件内容需要包含的文本（不区分大小写）
+    pub content: Option<String>,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+/// 单个搜索结果
+#[derive(Debug, Clone, Serialize)]
+pub struct FileEntry {
+    pub name: String,
+    pub path: String,
+    pub root: String,
+    pub is_dir: bool,
+    pub size: u64,
+    pub modified: Option<DateTime<Local>>,
+}
+
+/// 分页后的搜索结果
+#[derive(Debug, Clone, Serialize)]
+pub struct FileSearchResult {
+    pub entries: Vec<FileEntry>,
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+    /// 扫描条目数达到上限，结果可能不完整
+    pub truncated: bool,
+}
+
+/// 获取配置中存在的共享根目录
+pub fn shared_roots() -> Vec<PathBuf> {
+    get_config()
+        .shared_roots
+        .iter()
+        .map(|root| PathBuf::from(root.trim()))
+        .filter(|root| root.is_dir())
+        .collect()
+}
+
+/// 将路径解析为共享根目录内的绝对路径，不在任何共享根目录下时返回错误
+pub fn resolve_shared_path(path: &str) -> Result<PathBuf, String> {
+    resolve_in_roots(path, &shared_roots())
+}
+
+/// 按给定的根目录解析路径；先解析 .. 和符号链接，再判断是否位于某个根目录内
+fn resolve_in_roots(path: &str, roots: &[PathBuf]) -> Result<PathBuf, String> {
+    let canonical = Path::new(path)
+        .canonicalize()
+        .map_err(|e| format!("Invalid path '{}': {}", path, e))?;
+
+    let allowed = roots.iter().any(|root| {
+        root.canonicalize()
+            .map(|root| canonical.starts_with(root))
+            .unwrap_or(false)
+    });
+
+    if allowed {
+        Ok(canonical)
+    } else {
+        Err(format!("Path '{}' is outside the shared roots", path))
+    }
+}
+
+/// 将通配符模式转换为正则表达式（不区分大小写）
+fn glob_to_regex(pattern: &str) -> Result<Regex, String> {
+    let escaped = regex::escape(pattern)
+        .replace(r"\*", ".*")
+        .replace(r"\?", ".");
+    Regex::new(&format!("(?i)^{}$", escaped)).map_err(|e| e.to_string())
+}
+
+fn content_matches(path: &Path, needle: &str, max_bytes: u64) -> bool {
+    match std::fs::metadata(path) {
+        Ok(meta) if meta.len() <= max_bytes => {}
+        _ => return false,
+    }
+    match std::fs::read(path) {
+        Ok(bytes) => String::from_utf8_lossy(&bytes)
+            .to_lowercase()
+            .contains(needle),
+        Err(_) => false,
+    }
+}
+
+/// 在共享根目录下按文件名（以及可选的文件内容）搜索
+pub fn search(options: &FileSearchOptions) -> Result<FileSearchResult, String> {
+    let config = get_config();
+    let matcher = if options.regex {
+        Regex::new(&options.pattern).map_err(|e| format!("Invalid regex: {}", e))?
+    } else {
+        glob_to_regex(&options.pattern)?
+    };
+    let needle = options
+        .content
+        .as_ref()
+        .map(|c| c.to_lowercase())
+        .filter(|c| !c.is_empty());
+
+    let roots: Vec<PathBuf> = match &options.root {
+        Some(root) => {
+            let root = resolve_shared_path(root)?;
+            vec![root]
+        }
+        None => shared_roots(),
+    };
+    if roots.is_empty() {
+        return Err("No shared roots configured".to_string());
+    }
+
+    let mut matches = Vec::new();
+    let mut scanned = 0usize;
+    let mut truncated = false;
+
+    'roots: for root in &roots {
+        let root_label = root.to_string_lossy().to_string();
+        let mut stack = vec![(root.clone(), 0usize)];
+
+        while let Some((dir, depth)) = stack.pop() {
+            let entries = match std::fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+
+            for entry in entries.flatten() {
+                scanned += 1;
+                if scanned > MAX_SCANNED_ENTRIES {
+                    truncated = true;
+                    break 'roots;
+                }
+
+                // 不跟随符号链接，避免跳出共享目录
+                let file_type = match entry.file_type() {
+                    Ok(t) if !t.is_symlink() => t,
+                    _ => continue,
+                };
+                let path = entry.path();
+
+                if file_type.is_dir() && depth < MAX_SEARCH_DEPTH {
+                    stack.push((path.clone(), depth + 1));
+                }
+
+                let name = entry.file_name().to_string_lossy().to_string();
+                if !matcher.is_match(&name) {
+                    continue;
+                }
+
+                if let Some(ref needle) = needle {
+                    if !file_type.is_file()
+                        || !content_matches(&path, needle, config.file_search_max_content_bytes)
+                    {
+                        continue;
+                    }
+                }
+
+                let meta = entry.metadata().ok();
+                matches.push(FileEntry {
+                    name,
+                    path: path.to_string_lossy().to_string(),
+                    root: root_label.clone(),
+                    is_dir: file_type.is_dir(),
+                    size: meta.as_ref().map(|m| m.len()).unwrap_or(0),
+                    modified: meta
+                        .and_then(|m| m.modified().ok())
+                        .map(DateTime::<Local>::from),
+                });
+            }
+        }
+    }
+
+    matches.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let limit = match options.limit {
+        0 => DEFAULT_PAGE_SIZE,
+        n => n.min(MAX_PAGE_SIZE),
+    };
+    let total = matches.len();
+    let entries = matches
+        .into_iter()
+        .skip(options.offset)
+        .take(limit)
+        .collect();
+
+    Ok(FileSearchResult {
+        entries,
+        total,
+        offset: options.offset,
+        limit,
+        truncated,
+    })
+}
+
+fn entry_for(path: &Path, root: &str) -> Option<FileEntry> {
+    let meta = std::fs::symlink_metadata(path).ok()?;
+    // 不列出符号链接，避免跳出共享目录
+    if meta.file_type().is_symlink() {
+        return None;
+    }
+    Some(FileEntry {
+        name: path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.to_string_lossy().to_string()),
+        path: path.to_string_lossy().to_string(),
+        root: root.to_string(),
+        is_dir: meta.is_dir(),
+        size: if meta.is_dir() { 0 } else { meta.len() },
+        modified: meta.modified().ok().map(DateTime::<Local>::from),
+    })
+}
+
+/// 路径所在的共享根目录
+fn root_of(path: &Path) -> String {
+    shared_roots()
+        .into_iter()
+        .find(|root| {
+            root.canonicalize()
+                .map(|root| path.starts_with(root))
+                .unwrap_or(false)
+        })
+        .map(|root| root.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// 列出共享目录的内容，目录在前；不传路径时列出共享根目录本身
+pub fn list_dir(path: Option<&str>) -> Result<Vec<FileEntry>, String> {
+    let path = match path.map(str::trim).filter(|p| !p.is_empty()) {
+        Some(path) => path,
+        None => {
+            return Ok(shared_roots()
+                .iter()
+                .filter_map(|root| entry_for(root, &root.to_string_lossy()))
+                .collect());
+        }
+    };
+
+    let dir = resolve_shared_path(path)?;
+    if !dir.is_dir() {
+        return Err(format!("'{}' is not a directory", path));
+    }
+    let root = root_of(&dir);
+    let mut entries: Vec<FileEntry> = std::fs::read_dir(&dir)
+        .map_err(|e| format!("Failed to read '{}': {}", path, e))?
+        .flatten()
+        .filter_map(|entry| entry_for(&entry.path(), &root))
+        .collect();
+    entries.sort_by(|a, b| {
+        b.is_dir
+            .cmp(&a.is_dir)
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+    Ok(entries)
+}
+
+/// 读取共享文件的一段数据，end 为包含在内的结束位置
+///
+/// 返回数据和文件总大小，单次最多读取 MAX_TRANSFER_CHUNK 字节
+pub fn read_range(path: &str, start: u64, end: Option<u64>) -> Result<(Vec<u8>, u64), String> {
+    let path = resolve_shared_path(path)?;
+    if !path.is_file() {
+        return Err(format!("'{}' is not a file", path.display()));
+    }
+    let mut file = std::fs::File::open(&path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let total = file.metadata().map_err(|e| e.to_string())?.len();
+    if start > total {
+        return Err(format!(
+            "Range start {} is beyond file size {}",
+            start, total
+        ));
+    }
+
+    let last = end.unwrap_or(u64::MAX).min(total.saturating_sub(1));
+    let len = (last + 1).saturating_sub(start).min(MAX_TRANSFER_CHUNK);
+    file.seek(SeekFrom::Start(start))
+        .map_err(|e| format!("Failed to seek file: {}", e))?;
+    let mut data = Vec::with_capacity(len as usize);
+    file.take(len)
+        .read_to_end(&mut data)
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    Ok((data, total))
+}
+
+/// 上传的目标路径：所在目录必须位于共享根目录内，文件名不能包含路径分隔符
+fn upload_target(path: &str) -> Result<PathBuf, String> {
+    upload_target_in(path, &shared_roots())
+}
+
+fn upload_target_in(path: &str, roots: &[PathBuf]) -> Result<PathBuf, String> {
+    let path = Path::new(path);
+    let name = path
+        .file_name()
+        .filter(|name| !name.is_empty())
+        .ok_or_else(|| format!("Invalid file name in '{}'", path.display()))?;
+    let parent = path
+        .parent()
+        .ok_or_else(|| format!("Invalid path '{}'", path.display()))?;
+    let dir = resolve_in_roots(&parent.to_string_lossy(), roots)?;
+    if !dir.is_dir() {
+        return Err(format!("'{}' is not a directory", parent.display()));
+    }
+    Ok(dir.join(name))
+}
+
+fn partial_path(target: &Path) -> PathBuf {
+    let mut name = target.as_os_str().to_os_string();
+    name.push(PARTIAL_SUFFIX);
+    PathBuf::from(name)
+}
+
+/// 已上传的字节数，用于断点续传
+pub fn uploaded_size(path: &str) -> Result<u64, String> {
+    let partial = partial_path(&upload_target(path)?);
+    Ok(std::fs::metadata(partial).map(|m| m.len()).unwrap_or(0))
+}
+
+/// 目标已存在且未要求覆盖时返回错误
+fn check_overwrite(target: &Path, overwrite: bool) -> Result<(), String> {
+    if !overwrite && std::fs::symlink_metadata(target).is_ok() {
+        return Err(format!("'{}' already exists", target.display()));
+    }
+    Ok(())
+}
+
+/// 写入一段上传数据，offset 必须等于已上传的字节数
+///
+/// complete 为 true 时把临时文件重命名为目标文件，返回已上传的字节数；
+/// 目标文件已存在时，只有 overwrite 为 true 才会替换
+pub fn write_chunk(
+    path: &str,
+    offset: u64,
+    data: &[u8],
+    complete: bool,
+    overwrite: bool,
+) -> Result<u64, String> {
+    write_chunk_to(&upload_target(path)?, offset, data, complete, overwrite)
+}
+
+fn write_chunk_to(
+    target: &Path,
+    offset: u64,
+    data: &[u8],
+    complete: bool,
+    overwrite: bool,
+) -> Result<u64, String> {
+    let partial = partial_path(target);
+    // 临时文件被替换成符号链接时，写入会落到共享目录之外
+    if std::fs::symlink_metadata(&partial).map_or(false, |m| m.file_type().is_symlink()) {
+        return Err(format!("'{}' is not a regular file", partial.display()));
+    }
+
+    let mut file = if offset == 0 {
+        check_overwrite(target, overwrite)?;
+        let _ = std::fs::remove_file(&partial);
+        std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&partial)
+    } else {
+        std::fs::OpenOptions::new().append(true).open(&partial)
+    }
+    .map_err(|e| format!("Failed to open upload: {}", e))?;
+
+    let uploaded = file.metadata().map_err(|e| e.to_string())?.len();
+    if uploaded != offset {
+        return Err(format!(
+            "Upload offset {} does not match {} bytes received",
+            offset, uploaded
+        ));
+    }
+    file.write_all(data)
+        .map_err(|e| format!("Failed to write upload: {}", e))?;
+    let uploaded = uploaded + data.len() as u64;
+    drop(file);
+
+    if complete {
+        // 上传期间目标可能已被创建
+        check_overwrite(target, overwrite)?;
+        std::fs::rename(&partial, target)
+            .map_err(|e| format!("Failed to finish upload: {}", e))?;
+    }
+    Ok(uploaded)
+}
+
+/// 目录变化事件
+#[derive(Debug, Clone, Serialize)]
+pub struct FileChangeEvent {
+    pub path: String,
+    /// create / modify / remove
+    pub kind: String,
+}
+
+/// 监视共享目录下的路径，变化时回调；返回的 watcher 被 drop 时停止监视
+pub fn watch_path<F>(path: &str, recursive: bool, on_event: F) -> Result<RecommendedWatcher, String>
+where
+    F: Fn(FileChangeEvent) + Send + 'static,
+{
+    let path = resolve_shared_path(path)?;
+    if !path.is_dir() {
+        return Err(format!("'{}' is not a directory", path.display()));
+    }
+
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        let event = match res {
+            Ok(event) => event,
+            Err(e) => {
+                log::warn!("File watcher error: {}", e);
+                return;
+            }
+        };
+        let kind = match event.kind {
+            EventKind::Create(_) => "create",
+            EventKind::Modify(_) => "modify",
+            EventKind::Remove(_) => "remove",
+            _ => return,
+        };
+        for path in event.paths {
+            on_event(FileChangeEvent {
+                path: path.to_string_lossy().to_string(),
+                kind: kind.to_string(),
+            });
+        }
+    })
+    .map_err(|e| e.to_string())?;
+
+    let mode = if recursive {
+        RecursiveMode::Recursive
+    } else {
+        RecursiveMode::NonRecursive
+    };
+    watcher.watch(&path, mode).map_err(|e| e.to_string())?;
+
+    Ok(watcher)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试用的临时目录，drop 时删除
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("lan-files-{}", uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(&dir).unwrap();
+            Self(dir.canonicalize().unwrap())
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// 创建 root/shared（共享根目录）和 root/private（共享目录之外）
+    fn setup() -> (TempDir, PathBuf, PathBuf) {
+        let temp = TempDir::new();
+        let shared = temp.0.join("shared");
+        let private = temp.0.join("private");
+        std::fs::create_dir_all(shared.join("docs")).unwrap();
+        std::fs::create_dir_all(&private).unwrap();
+        std::fs::write(shared.join("docs").join("a.txt"), b"a").unwrap();
+        std::fs::write(private.join("secret.txt"), b"secret").unwrap();
+        (temp, shared, private)
+    }
+
+    fn path_str(path: &Path) -> String {
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn resolves_paths_inside_roots() {
+        let (_temp, shared, _) = setup();
+        let roots = [shared.clone()];
+        let file = shared.join("docs").join("a.txt");
+        assert_eq!(resolve_in_roots(&path_str(&file), &roots).unwrap(), file);
+    }
+
+    #[test]
+    fn rejects_parent_traversal() {
+        let (_temp, shared, _) = setup();
+        let roots = [shared.clone()];
+        let escaped = shared.join("docs").join("..").join("..").join("private").join("secret.txt");
+        assert!(resolve_in_roots(&path_str(&escaped), &roots).is_err());
+        let target = shared.join("..").join("private").join("new.txt");
+        assert!(upload_target_in(&path_str(&target), &roots).is_err());
+        assert!(upload_target_in(&path_str(&shared.join("docs").join("..")), &roots).is_err());
+    }
+
+    #[test]
+    fn rejects_absolute_paths_outside_roots() {
+        let (_temp, shared, private) = setup();
+        let roots = [shared];
+        assert!(resolve_in_roots(&path_str(&private.join("secret.txt")), &roots).is_err());
+        assert!(upload_target_in(&path_str(&private.join("new.txt")), &roots).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_symlinks_leading_outside_roots() {
+        let (_temp, shared, private) = setup();
+        let roots = [shared.clone()];
+        std::os::unix::fs::symlink(&private, shared.join("link")).unwrap();
+        let through_link = shared.join("link").join("secret.txt");
+        assert!(resolve_in_roots(&path_str(&through_link), &roots).is_err());
+        assert!(upload_target_in(&path_str(&shared.join("link").join("new.txt")), &roots).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn does_not_write_through_symlinked_partial_file() {
+        let (_temp, shared, private) = setup();
+        let target = shared.join("docs").join("b.txt");
+        std::os::unix::fs::symlink(private.join("secret.txt"), partial_path(&target)).unwrap();
+        assert!(write_chunk_to(&target, 0, b"x", true, false).is_err());
+        assert_eq!(std::fs::read(private.join("secret.txt")).unwrap(), b"secret");
+    }
+
+    #[test]
+    fn upload_does_not_overwrite_without_flag() {
+        let (_temp, shared, _) = setup();
+        let target = shared.join("docs").join("a.txt");
+        assert!(write_chunk_to(&target, 0, b"new", true, false).is_err());
+        assert_eq!(std::fs::read(&target).unwrap(), b"a");
+        assert_eq!(write_chunk_to(&target, 0, b"new", true, true).unwrap(), 3);
+        assert_eq!(std::fs::read(&target).unwrap(), b"new");
+    }
+
+    #[test]
+    fn upload_rejects_target_created_during_upload() {
+        let (_temp, shared, _) = setup();
+        let target = shared.join("docs").join("b.txt");
+        assert_eq!(write_chunk_to(&target, 0, b"ab", false, false).unwrap(), 2);
+        std::fs::write(&target, b"other").unwrap();
+        assert!(write_chunk_to(&target, 2, b"c", true, false).is_err());
+        assert_eq!(std::fs::read(&target).unwrap(), b"other");
+    }
+}
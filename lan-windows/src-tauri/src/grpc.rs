@@ -0,0 +1,383 @@
+use futures::{Stream, StreamExt};
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio_stream::wrappers::{IntervalStream, TcpListenerStream};
+use tonic::{Request, Response, Status};
+
+use crate::api::log_to_ui;
+use crate::auth::AuthManager;
+use crate::stats::AuthOutcome;
+
+/// 由 proto/lan_device.proto 生成的类型
+pub mod pb {
+    tonic::include_proto!("landevice.v1");
+}
+
+use pb::lan_device_server::{LanDevice, LanDeviceServer};
+
+/// 指标推送的最小间隔，避免客户端把采集变成忙等
+const MIN_METRICS_INTERVAL_MS: u64 = 500;
+
+type ResponseStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>;
+
+impl From<crate::models::GpuMetrics> for pb::GpuMetrics {
+    fn from(gpu: crate::models::GpuMetrics) -> Self {
+        Self {
+            name: gpu.name,
+            utilization_percent: gpu.utilization_percent,
+            memory_used: gpu.memory_used,
+            memory_total: gpu.memory_total,
+            temperature: gpu.temperature,
+        }
+    }
+}
+
+impl From<crate::models::SystemInfo> for pb::SystemInfo {
+    fn from(info: crate::models::SystemInfo) -> Self {
+        Self {
+            os_type: info.os_type,
+            os_version: info.os_version,
+            hostname: info.hostname,
+            architecture: info.architecture,
+            cpu_usage: info.cpu_usage,
+            memory_total: info.memory_total,
+            memory_used: info.memory_used,
+            uptime_seconds: info.uptime_seconds,
+            gpus: info.gpus.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<crate::models::LiveMetrics> for pb::LiveMetrics {
+    fn from(metrics: crate::models::LiveMetrics) -> Self {
+        Self {
+            cpu_usage: metrics.cpu_usage,
+            memory_used: metrics.memory_used,
+            uptime_seconds: metrics.uptime_seconds,
+            gpus: metrics.gpus.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+fn client_ip<T>(request: &Request<T>) -> String {
+    request
+        .remote_addr()
+        .map(|addr| addr.to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// 从 metadata 的 "authorization: Bearer <token>" 中取出令牌
+fn bearer_token<T>(request: &Request<T>) -> Option<String> {
+    let value = request.metadata().get("authorization")?.to_str().ok()?;
+    value
+        .strip_prefix("Bearer ")
+        .map(|token| token.trim().to_string())
+}
+
+/// 与 REST 中间件一致，拒绝黑名单中的 IP 和（启用 lan_only 时）局域网外的地址，
+/// 并与 REST 共用按IP的限流
+fn check_client(request: Request<()>) -> Result<Request<()>, Status> {
+    let ip = client_ip(&request);
+    if crate::api::is_ip_blacklisted(&crate::stats::ip_only(&ip)) {
+        log::warn!(
+            "[Security] gRPC request from blacklisted IP blocked: {}",
+            ip
+        );
+        return Err(Status::permission_denied(
+            "Access denied: IP is blacklisted",
+        ));
+    }
+    if crate::api::is_outside_lan(&ip) {
+        log::warn!("[Security] gRPC request from non-LAN IP blocked: {}", ip);
+        return Err(Status::permission_denied(
+            "Access denied: only local network clients are allowed",
+        ));
+    }
+    if let Some(retry_after) = crate::ratelimit::check(&crate::stats::ip_only(&ip)) {
+        return Err(Status::resource_exhausted(format!(
+            "Too many requests, retry after {} seconds",
+            retry_after
+        )));
+    }
+    Ok(request)
+}
+
+struct LanDeviceService {
+    auth_manager: AuthManager,
+}
+
+impl LanDeviceService {
+    fn reject_token(&self, ip: &str, action: &str) -> Status {
+        log::warn!("[Access] [{}] gRPC {} denied: Invalid token", ip, action);
+        crate::stats::record_auth(ip, AuthOutcome::TokenRejected);
+        log_to_ui(
+            "warn",
+            &format!("[{}] gRPC {} denied: Invalid token", ip, action),
+        );
+        Status::unauthenticated("Invalid or expired token")
+    }
+
+    /// gRPC 连接没有负载加密，协商了加密的令牌只能通过加密的 REST 和 WebSocket 请求使用
+    fn reject_encrypted(&self, ip: &str, token: Option<&str>) -> Result<(), Status> {
+        if token.is_some_and(|t| self.auth_manager.requires_payload_encryption(t)) {
+            log::warn!("[Security] [{}] Rejected gRPC token of an encrypted session", ip);
+            return Err(Status::permission_denied("This session requires encrypted requests"));
+        }
+        Ok(())
+    }
+
+    /// 读取类接口：与 REST 相同，未设置密码时允许匿名访问；
+    /// 配置了 client_permissions 时，没有适用条目的客户端不能读取
+    fn authorize_read<T>(
+        &self,
+        request: &Request<T>,
+        ip: &str,
+        action: &str,
+    ) -> Result<(), Status> {
+        let token = bearer_token(request);
+        self.reject_encrypted(ip, token.as_deref())?;
+        if !crate::api::is_token_authorized(&self.auth_manager, token.as_deref()) {
+            return Err(match token {
+                Some(_) => self.reject_token(ip, action),
+                None => Status::unauthenticated("Authentication required. Token missing."),
+            });
+        }
+        if !crate::api::client_allowed(&self.auth_manager, token.as_deref(), |p| {
+            p.allows_any_scope()
+        }) {
+            log::warn!("[Access] [{}] gRPC {} denied: Permission denied", ip, action);
+            return Err(Status::permission_denied("Permission denied"));
+        }
+        Ok(())
+    }
+}
+
+#[tonic::async_trait]
+impl LanDevice for LanDeviceService {
+    async fn get_challenge(
+        &self,
+        request: Request<pb::ChallengeRequest>,
+    ) -> Result<Response<pb::ChallengeReply>, Status> {
+        let ip = client_ip(&request);
+        match self
+            .auth_manager
+            .generate_challenge(&crate::stats::ip_only(&ip))
+        {
+            Ok(challenge) => {
+                log::info!("[Auth] [{}] gRPC challenge requested", ip);
+                let (salt, iterations) = self.auth_manager.verifier_params().unwrap_or_default();
+                Ok(Response::new(pb::ChallengeReply {
+                    challenge,
+                    salt,
+                    iterations,
+                }))
+            }
+            Err(e) => {
+                log::warn!("[Auth] [{}] gRPC challenge REJECTED: {}", ip, e);
+                Err(Status::resource_exhausted(e))
+            }
+        }
+    }
+
+    async fn login(
+        &self,
+        request: Request<pb::LoginRequest>,
+    ) -> Result<Response<pb::LoginReply>, Status> {
+        let ip = client_ip(&request);
+        let req = request.into_inner();
+        // gRPC 连接不协商负载加密
+        let login = crate::api::LoginRequest {
+            challenge: req.challenge,
+            response: req.response,
+            device_id: req.device_id,
+            ..Default::default()
+        };
+
+        match crate::api::login_session(&self.auth_manager, &ip, &login) {
+            Ok(response) => {
+                log::info!(
+                    "[Auth] [{}] gRPC login SUCCESS (device: {})",
+                    ip,
+                    login.device_id.as_deref().unwrap_or("unknown")
+                );
+                log_to_ui("success", &format!("[{}] gRPC login SUCCESS", ip));
+                Ok(Response::new(pb::LoginReply {
+                    token: response.token,
+                    expires_in: response.expires_in,
+                }))
+            }
+            Err(e) => {
+                log::warn!("[Auth] [{}] gRPC login FAILED: {}", ip, e);
+                log_to_ui("warn", &format!("[{}] gRPC login FAILED: {}", ip, e));
+                Err(Status::unauthenticated(e.to_string()))
+            }
+        }
+    }
+
+    async fn get_system_info(
+        &self,
+        request: Request<pb::SystemInfoRequest>,
+    ) -> Result<Response<pb::SystemInfo>, Status> {
+        let ip = client_ip(&request);
+        self.authorize_read(&request, &ip, "system info")?;
+
+        log::info!("[Access] [{}] gRPC system info requested", ip);
+        let info = tokio::task::spawn_blocking(crate::command::get_system_info)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+            .map_err(Status::internal)?;
+        Ok(Response::new(info.into()))
+    }
+
+    type StreamMetricsStream = ResponseStream<pb::LiveMetrics>;
+
+    async fn stream_metrics(
+        &self,
+        request: Request<pb::StreamMetricsRequest>,
+    ) -> Result<Response<Self::StreamMetricsStream>, Status> {
+        let ip = client_ip(&request);
+        self.authorize_read(&request, &ip, "metrics stream")?;
+
+        let interval_ms = u64::from(request.get_ref().interval_ms).max(MIN_METRICS_INTERVAL_MS);
+        log::info!(
+            "[Access] [{}] gRPC metrics stream started ({} ms)",
+            ip,
+            interval_ms
+        );
+
+        // 客户端取消后流被丢弃，定时器随之停止
+        let interval = tokio::time::interval(Duration::from_millis(interval_ms));
+        let stream = IntervalStream::new(interval).then(|_| async {
+            tokio::task::spawn_blocking(crate::command::get_live_metrics)
+                .await
+                .map(Into::into)
+                .map_err(|e| Status::internal(e.to_string()))
+        });
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    type ExecuteCommandStream = ResponseStream<pb::CommandOutput>;
+
+    async fn execute_command(
+        &self,
+        request: Request<pb::CommandRequest>,
+    ) -> Result<Response<Self::ExecuteCommandStream>, Status> {
+        let ip = client_ip(&request);
+        let token = bearer_token(&request).unwrap_or_default();
+        self.reject_encrypted(&ip, Some(&token))?;
+        if !self.auth_manager.verify_token(&token) {
+            return Err(self.reject_token(&ip, "execute"));
+        }
+
+        let req = request.into_inner();
+        let args = (!req.args.is_empty()).then_some(req.args);
+        let (command, args) = crate::api::resolve_command(&req.command, args);
+
+        let decision = crate::api::check_command(&self.auth_manager, &ip, &token, &command);
+        let identity = self.auth_manager.identity(&token).unwrap_or_default();
+        if !decision.allowed {
+            log::warn!(
+                "[Command] [{}] gRPC execute '{}' by {} REJECTED: {}",
+                ip,
+                command,
+                identity,
+                decision.reason
+            );
+            return Err(Status::permission_denied(decision.reason));
+        }
+
+        log::info!(
+            "[Command] [{}] gRPC execute '{}' by {} REQUEST",
+            ip,
+            command,
+            identity
+        );
+        log_to_ui(
+            "info",
+            &format!("[{}] gRPC execute '{}' REQUEST", ip, command),
+        );
+
+        // 命令执行完成后再按行推送输出，最后一条为退出状态
+        let result = crate::api::run_command(&command, args)
+            .await
+            .map_err(Status::internal)?;
+        if result.success {
+            log::info!(
+                "[Command] [{}] gRPC execute '{}' by {} SUCCESS",
+                ip,
+                command,
+                identity
+            );
+        } else {
+            log::warn!(
+                "[Command] [{}] gRPC execute '{}' by {} FAILED",
+                ip,
+                command,
+                identity
+            );
+        }
+
+        let lines = |text: &str, wrap: fn(String) -> pb::command_output::Output| {
+            text.lines()
+                .map(|line| wrap(line.to_string()))
+                .collect::<Vec<_>>()
+        };
+        let mut outputs = lines(&result.stdout, pb::command_output::Output::Stdout);
+        outputs.extend(lines(&result.stderr, pb::command_output::Output::Stderr));
+        outputs.push(pb::command_output::Output::Exit(pb::CommandExit {
+            success: result.success,
+            exit_code: result.exit_code,
+            execution_time_ms: result.execution_time_ms,
+        }));
+
+        let stream = futures::stream::iter(outputs.into_iter().map(|output| {
+            Ok(pb::CommandOutput {
+                output: Some(output),
+            })
+        }));
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
+
+/// 可选的 gRPC 服务，与 REST API 共用认证和命令执行
+pub struct GrpcServer {
+    shutdown: watch::Sender<bool>,
+}
+
+impl GrpcServer {
+    pub async fn start(
+        bind_addr: IpAddr,
+        port: u16,
+        auth_manager: AuthManager,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let addr = SocketAddr::new(bind_addr, port);
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        let (shutdown, mut shutdown_rx) = watch::channel(false);
+        let service = LanDeviceServer::with_interceptor(
+            LanDeviceService { auth_manager },
+            check_client,
+        );
+
+        tokio::spawn(async move {
+            let result = tonic::transport::Server::builder()
+                .add_service(service)
+                .serve_with_incoming_shutdown(TcpListenerStream::new(listener), async move {
+                    let _ = shutdown_rx.changed().await;
+                })
+                .await;
+            if let Err(e) = result {
+                log::error!("gRPC server error: {}", e);
+            }
+            log::info!("gRPC server stopped");
+        });
+
+        log::info!("gRPC server listening on {}", addr);
+        Ok(Self { shutdown })
+    }
+
+    pub fn stop(&self) {
+        let _ = self.shutdown.send(true);
+    }
+}
@@ -1,61 +1,196 @@
 use std::sync::Arc;
 use tauri::{
-    menu::{Menu, MenuItem, PredefinedMenuItem},
     tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
     window::{Effect, EffectsBuilder},
     Emitter, Listener, Manager,
 };
-use tokio::sync::Mutex;
 
 #[cfg(target_os = "windows")]
 use windows::Win32::System::Threading::{
     SetPriorityClass, BELOW_NORMAL_PRIORITY_CLASS,
 };
 
+pub mod access;
 pub mod api;
+pub mod apps;
 pub mod auth;
+pub mod autostart;
+pub mod capture;
+pub mod cleanup;
+pub mod clipboard;
 pub mod command;
 pub mod config;
+pub mod connections;
+pub mod crash;
 pub mod device_id;
+pub mod disks;
+pub mod display;
+pub mod events;
+pub mod extract;
+pub mod facts;
+pub mod files;
+pub mod grpc;
 pub mod logger;
 pub mod mdns;
+pub mod media;
+pub mod messages;
 pub mod models;
+pub mod network;
+pub mod onboarding;
+pub mod overrides;
+pub mod pairing;
+pub mod payload;
+pub mod peripherals;
+pub mod policy;
+pub mod power;
+pub mod printers;
+pub mod ratelimit;
+pub mod remote;
+pub mod self_update;
+pub mod sessions;
+pub mod shell;
+pub mod spake2;
+pub mod ssdp;
 pub mod state;
+pub mod stats;
+pub mod sync;
+pub mod tray;
+pub mod trusted_network;
+pub mod updates;
 pub mod websocket;
 
 use state::AppState;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    env_logger::init();
+    // 解析环境变量和命令行参数，日志级别参数优先于 RUST_LOG
+    let overrides = &*overrides::OVERRIDES;
+    match overrides.log_level {
+        Some(ref level) => env_logger::Builder::from_default_env()
+            .parse_filters(level)
+            .init(),
+        None => env_logger::init(),
+    }
+    for warning in &overrides.warnings {
+        log::warn!("{}", warning);
+    }
+    crash::install_panic_hook();
 
-    let state = Arc::new(Mutex::new(AppState::new()));
+    let state = Arc::new(AppState::new());
 
     tauri::Builder::default()
         .plugin(tauri_plugin_autostart::Builder::new().build())
+        .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_opener::init())
         .manage(state)
         .invoke_handler(tauri::generate_handler![
             start_server,
             stop_server,
             get_server_status,
+            set_mdns_advertising,
             get_system_info,
             execute_command,
+            test_whitelist,
             get_logs,
             clear_logs,
             get_config,
             save_config,
+            validate_config,
             set_config_password,
             verify_config_password,
+            password_needs_reentry,
+            restore_password_verifier,
             has_config_password,
             clear_config_password,
+            create_api_key,
+            delete_api_key,
+            get_paired_devices,
+            unpair_device,
             get_log_file_info,
+            set_min_log_level,
+            set_tray_quick_actions,
             reload_config,
+            export_config,
+            import_config,
             open_path,
+            notify_clients,
+            get_client_stats,
+            clear_client_stats,
+            get_policy_decisions,
+            get_access_log,
+            clear_access_log,
+            get_connections,
+            get_device_uuid,
+            get_onboarding_status,
+            get_autostart_status,
+            check_port_available,
+            suggest_api_port,
+            get_pairing_qr,
+            regenerate_device_uuid,
+            set_device_id_source,
+            set_debug_capture,
+            get_debug_capture_status,
+            get_debug_captures,
+            clear_debug_captures,
+            get_challenge_stats,
+            check_for_updates,
+            get_app_update_status,
+            get_crash_reports,
+            clear_crash_reports,
+            create_diagnostics_bundle,
+            start_remote_discovery,
+            stop_remote_discovery,
+            get_remote_devices,
+            connect_remote_device,
+            disconnect_remote_device,
+            get_remote_system_info,
+            execute_remote_command,
         ])
         .setup(|app| {
             log::info!("LanDevice Manager setup...");
 
+            app.state::<Arc<AppState>>().set_app_handle(app.handle().clone());
+            connections::set_app_handle(app.handle().clone());
+            clipboard::set_app_handle(app.handle().clone());
+            // 上次运行时专注模式关闭的通知横幅未能恢复（崩溃或强制结束）
+            power::restore_notifications_after_crash();
+            // 系统中的自启动可能被其他程序修改，启动时同步到配置
+            autostart::sync_config_from_system(app.handle());
+
+            // 配置文件被外部修改时自动重新加载
+            let state_for_watch = app.state::<Arc<AppState>>().inner().clone();
+            let app_for_watch = app.handle().clone();
+            if let Err(e) = config::watch_config_file(move |_| {
+                logger::reload_logger_config();
+                state_for_watch.auth_manager.reload_password();
+                state_for_watch.auth_manager.revoke_blacklisted_sessions();
+                let state = state_for_watch.clone();
+                tauri::async_runtime::spawn(async move { state.refresh_mdns_auth().await });
+                state_for_watch
+                    .logger
+                    .system("Config", "Config file changed on disk, reloaded");
+                tray::rebuild(&app_for_watch);
+                state_for_watch.emit("config-reloaded", ());
+            }) {
+                log::warn!("Failed to watch config file: {}", e);
+            }
+
+            // 离开受信任网络时停止服务器，回来后重新启动
+            trusted_network::spawn_watcher(app.state::<Arc<AppState>>().inner().clone());
+
+            // 定期检查新版本，发现后通知界面
+            let state_for_update = app.state::<Arc<AppState>>().inner().clone();
+            self_update::spawn_periodic_check(move |status| {
+                state_for_update.logger.system(
+                    "Update",
+                    &format!(
+                        "New version {} is available",
+                        status.latest_version.as_deref().unwrap_or_default()
+                    ),
+                );
+                state_for_update.emit("app-update-available", status.clone());
+            });
+
             #[cfg(target_os = "windows")]
             unsafe {
                 use windows::Win32::System::Threading::GetCurrentProcess;
@@ -107,31 +242,11 @@ pub fn run() {
                 });
             }
 
-            let show_i = MenuItem::with_id(app, "show", "Show", true, None::<&str>)?;
-            let hide_i = MenuItem::with_id(app, "hide", "Hide", true, None::<&str>)?;
-            let separator = PredefinedMenuItem::separator(app)?;
-            let start_server_i =
-                MenuItem::with_id(app, "start_server", "Start Server", true, None::<&str>)?;
-            let stop_server_i =
-                MenuItem::with_id(app, "stop_server", "Stop Server", true, None::<&str>)?;
-            let separator2 = PredefinedMenuItem::separator(app)?;
-            let quit_i = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
-
-            let menu = Menu::with_items(
-                app,
-                &[
-                    &show_i,
-                    &hide_i,
-                    &separator,
-                    &start_server_i,
-                    &stop_server_i,
-                    &separator2,
-                    &quit_i,
-                ],
-            )?;
-
-            let _tray = TrayIconBuilder::new()
+            let (menu, tray_items) = tray::build_menu(app.handle())?;
+
+            let tray = TrayIconBuilder::new()
                 .icon(app.default_window_icon().unwrap().clone())
+                .tooltip("LanDevice Manager - Server stopped")
                 .menu(&menu)
                 .show_menu_on_left_click(false)
                 .on_menu_event(|app, event| {
@@ -154,22 +269,22 @@ pub fn run() {
                             }
                         }
                         "start_server" => {
-                            if let Some(window) = app.get_webview_window("main") {
-                                let _ = window.emit("tray-start-server", ());
-                                show_notification("LanDevice Manager", "Starting API server...");
-                            }
+                            show_notification("LanDevice Manager", "Starting API server...");
+                            tray::start_server(app);
                         }
                         "stop_server" => {
-                            if let Some(window) = app.get_webview_window("main") {
-                                let _ = window.emit("tray-stop-server", ());
-                                show_notification("LanDevice Manager", "Stopping API server...");
-                            }
+                            show_notification("LanDevice Manager", "Stopping API server...");
+                            tray::stop_server(app);
                         }
                         "quit" => {
+                            // 退出前恢复被专注模式关闭的通知
+                            let _ = power::set_focus_mode(false);
                             show_notification("LanDevice Manager", "Application closed");
                             app.exit(0);
                         }
-                        _ => {}
+                        id => {
+                            tray::handle_quick_action(app, id);
+                        }
                     }
                 })
                 .on_tray_icon_event(|tray, event| {
@@ -190,6 +305,18 @@ pub fn run() {
                 })
                 .build(app)?;
 
+            app.manage(tray::TrayState::new(tray, tray_items));
+            tray::refresh(app.handle(), &app.state::<Arc<AppState>>().get_status());
+
+            // 无界面模式：隐藏主窗口，只保留托盘并直接启动服务器
+            if overrides::OVERRIDES.headless {
+                log::info!("Running in headless mode");
+                if let Some(window) = app.get_webview_window("main") {
+                    let _ = window.hide();
+                }
+                tray::start_server(app.handle());
+            }
+
             Ok(())
         })
         .run(tauri::generate_context!())
@@ -200,27 +327,45 @@ pub fn run() {
 
 #[tauri::command]
 async fn start_server(
-    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    state: tauri::State<'_, Arc<AppState>>,
     port: u16,
 ) -> Result<String, String> {
-    let mut state = state.lock().await;
     state.start_server(port).await.map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-async fn stop_server(state: tauri::State<'_, Arc<Mutex<AppState>>>) -> Result<String, String> {
-    let mut state = state.lock().await;
+async fn stop_server(state: tauri::State<'_, Arc<AppState>>) -> Result<String, String> {
     state.stop_server().await.map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+async fn set_mdns_advertising(
+    state: tauri::State<'_, Arc<AppState>>,
+    enabled: bool,
+) -> Result<String, String> {
+    state.set_advertising(enabled).await.map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 async fn get_server_status(
-    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    state: tauri::State<'_, Arc<AppState>>,
 ) -> Result<models::ServerStatus, String> {
-    let state = state.lock().await;
     Ok(state.get_status())
 }
 
+#[tauri::command]
+async fn notify_clients(
+    state: tauri::State<'_, Arc<AppState>>,
+    title: String,
+    message: String,
+    level: Option<String>,
+) -> Result<String, String> {
+    state
+        .notify_clients(&title, &message, level.as_deref().unwrap_or("info"))
+        .await
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 async fn get_system_info() -> Result<models::SystemInfo, String> {
     command::get_system_info().map_err(|e| e.to_string())
@@ -228,68 +373,337 @@ async fn get_system_info() -> Result<models::SystemInfo, String> {
 
 #[tauri::command]
 async fn execute_command(
-    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    state: tauri::State<'_, Arc<AppState>>,
     command_type: String,
     args: Option<Vec<String>>,
 ) -> Result<models::CommandResult, String> {
-    let state = state.lock().await;
-    state
-        .command_executor
-        .execute(&command_type, args.as_deref())
-        .map_err(|e| e.to_string())
+    // 在阻塞线程池中执行，避免占用异步运行时
+    let executor = state.command_executor.clone();
+    tokio::task::spawn_blocking(move || executor.execute(&command_type, args.as_deref()))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// 按当前保存的配置检查命令能否通过白名单，不执行命令
+#[tauri::command]
+async fn test_whitelist(
+    command: String,
+    args: Option<Vec<String>>,
+) -> Result<policy::CommandCheck, String> {
+    let (command, args) = api::resolve_command(&command, args);
+    let decision = policy::evaluate_command(&command, None, &config::get_config());
+    Ok(policy::CommandCheck { args, decision })
 }
 
 #[tauri::command]
 async fn get_logs(
-    state: tauri::State<'_, Arc<Mutex<AppState>>>,
     limit: Option<usize>,
+    before_timestamp: Option<chrono::DateTime<chrono::Local>>,
 ) -> Result<Vec<models::LogEntry>, String> {
-    let state = state.lock().await;
-    let mut logs = state.logger.get_logs(limit.unwrap_or(100));
-    let api_logs = api::get_api_logs(limit.unwrap_or(100));
-    logs.extend(api_logs);
-    logs.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
-    if logs.len() > limit.unwrap_or(100) {
-        logs.truncate(limit.unwrap_or(100));
-    }
-    Ok(logs)
+    Ok(logger::recent_logs(before_timestamp, limit.unwrap_or(100)))
 }
 
 #[tauri::command]
-async fn clear_logs(state: tauri::State<'_, Arc<Mutex<AppState>>>) -> Result<bool, String> {
-    let mut state = state.lock().await;
-    state.logger.clear_logs();
-    api::clear_api_logs();
+async fn clear_logs() -> Result<bool, String> {
+    logger::clear_logs();
     Ok(true)
 }
 
+#[tauri::command]
+async fn get_client_stats() -> Result<Vec<stats::ClientStats>, String> {
+    Ok(stats::get_client_stats())
+}
+
+/// 最近的命令允许/拒绝判定及原因
+#[tauri::command]
+async fn get_policy_decisions() -> Result<Vec<policy::PolicyLogEntry>, String> {
+    Ok(policy::recent_decisions())
+}
+
+#[tauri::command]
+async fn clear_client_stats() -> Result<(), String> {
+    stats::clear_client_stats();
+    Ok(())
+}
+
+/// 设备UUID，客户端以此识别本机
+/// 首次运行向导：密码、开机自启动和端口的当前状态
+#[tauri::command]
+async fn get_onboarding_status(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<onboarding::OnboardingStatus, String> {
+    let config = config::get_config();
+    Ok(onboarding::OnboardingStatus {
+        password_set: state.auth_manager.is_password_set(),
+        autostart_enabled: autostart::status(&app).registered,
+        autostart_configured: config.auto_start_on_boot,
+        api_port: config.api_port,
+        server_running: state.get_status().running,
+    })
+}
+
+/// 检查端口能否用于 API 服务器
+#[tauri::command]
+async fn check_port_available(
+    state: tauri::State<'_, Arc<AppState>>,
+    port: u16,
+) -> Result<onboarding::PortCheck, String> {
+    let server_port = state.get_status().port;
+    Ok(onboarding::check_port(port, server_port))
+}
+
+/// 从首选端口（默认为当前配置）开始找一个可用端口
+#[tauri::command]
+async fn suggest_api_port(
+    state: tauri::State<'_, Arc<AppState>>,
+    preferred: Option<u16>,
+) -> Result<u16, String> {
+    let preferred = preferred.unwrap_or_else(|| config::get_config().api_port);
+    let server_port = state.get_status().port;
+    onboarding::suggest_port(preferred, server_port)
+        .ok_or_else(|| format!("No free port found starting from {}", preferred))
+}
+
+/// 生成配对二维码，服务器未运行时使用配置的端口
+#[tauri::command]
+async fn get_pairing_qr(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<onboarding::PairingQr, String> {
+    let port = state
+        .get_status()
+        .port
+        .unwrap_or_else(|| config::get_config().api_port);
+    onboarding::pairing_qr(port)
+}
+
+#[tauri::command]
+async fn get_device_uuid() -> Result<String, String> {
+    device_id::DeviceId::get_or_create().map_err(|e| e.to_string())
+}
+
+/// 重新生成设备UUID，已保存本机的客户端会把它当作新设备
+#[tauri::command]
+async fn regenerate_device_uuid(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<String, String> {
+    state.regenerate_device_uuid()
+}
+
+/// 切换设备UUID来源（random 或 machine），返回切换后的UUID
+#[tauri::command]
+async fn set_device_id_source(
+    source: String,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<String, String> {
+    if !device_id::DEVICE_ID_SOURCES.contains(&source.as_str()) {
+        return Err(format!("Invalid device ID source '{}'", source));
+    }
+    if source == "machine" && device_id::DeviceId::machine_uuid().is_none() {
+        return Err("Failed to read a stable identifier from this machine".to_string());
+    }
+    config::update_config(|cfg| cfg.device_id_source = source.clone())
+        .map_err(|e| e.to_string())?;
+    state.reload_device_uuid();
+    state.logger.system(
+        "Config",
+        &format!("Device UUID source set to {}, restart the server to advertise it", source),
+    );
+    device_id::DeviceId::get_or_create().map_err(|e| e.to_string())
+}
+
+/// 当前连接的 WebSocket 客户端和已登录的 API 会话
+#[tauri::command]
+async fn get_connections() -> Result<Vec<connections::ClientConnection>, String> {
+    Ok(connections::list())
+}
+
+/// 最近的 HTTP 请求及其状态码和耗时
+#[tauri::command]
+async fn get_access_log() -> Result<Vec<access::AccessLogEntry>, String> {
+    Ok(access::recent_requests())
+}
+
+#[tauri::command]
+async fn clear_access_log() -> Result<(), String> {
+    access::clear();
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_debug_capture(
+    enabled: bool,
+    limit: Option<usize>,
+) -> Result<capture::CaptureStatus, String> {
+    Ok(capture::set_enabled(enabled, limit))
+}
+
+#[tauri::command]
+async fn get_debug_capture_status() -> Result<capture::CaptureStatus, String> {
+    Ok(capture::status())
+}
+
+#[tauri::command]
+async fn get_debug_captures() -> Result<Vec<capture::CapturedExchange>, String> {
+    Ok(capture::get_captures())
+}
+
+#[tauri::command]
+async fn clear_debug_captures() -> Result<(), String> {
+    capture::clear_captures();
+    Ok(())
+}
+
+#[tauri::command]
+async fn check_for_updates() -> Result<self_update::AppUpdateStatus, String> {
+    self_update::check_for_updates().await
+}
+
+#[tauri::command]
+async fn get_app_update_status() -> Result<self_update::AppUpdateStatus, String> {
+    Ok(self_update::get_status())
+}
+
+#[tauri::command]
+async fn get_crash_reports() -> Result<Vec<crash::CrashReportInfo>, String> {
+    Ok(crash::list_crash_reports())
+}
+
+#[tauri::command]
+async fn clear_crash_reports() -> Result<usize, String> {
+    Ok(crash::clear_crash_reports())
+}
+
+#[tauri::command]
+async fn create_diagnostics_bundle(path: String) -> Result<usize, String> {
+    tokio::task::spawn_blocking(move || {
+        crash::create_diagnostics_bundle(std::path::Path::new(&path))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn start_remote_discovery() -> Result<(), String> {
+    remote::start_discovery()
+}
+
+#[tauri::command]
+async fn stop_remote_discovery() -> Result<(), String> {
+    remote::stop_discovery()
+}
+
+#[tauri::command]
+async fn get_remote_devices() -> Result<Vec<models::DeviceInfo>, String> {
+    Ok(remote::get_devices())
+}
+
+#[tauri::command]
+async fn connect_remote_device(
+    ip: String,
+    port: u16,
+    password: Option<String>,
+) -> Result<remote::RemoteConnection, String> {
+    remote::connect(&ip, port, password).await
+}
+
+#[tauri::command]
+async fn disconnect_remote_device(key: String) -> Result<bool, String> {
+    Ok(remote::disconnect(&key))
+}
+
+#[tauri::command]
+async fn get_remote_system_info(key: String) -> Result<models::SystemInfo, String> {
+    remote::get_system_info(&key).await
+}
+
+#[tauri::command]
+async fn execute_remote_command(
+    key: String,
+    command: String,
+    args: Option<Vec<String>>,
+) -> Result<models::CommandResult, String> {
+    remote::execute_command(&key, &command, args).await
+}
+
+#[tauri::command]
+async fn get_challenge_stats(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<auth::ChallengeStats, String> {
+    Ok(state.auth_manager.challenge_stats())
+}
+
 #[tauri::command]
 async fn get_config() -> Result<config::AppConfig, String> {
     Ok(config::get_config())
 }
 
 #[tauri::command]
-async fn save_config(new_config: config::AppConfig, _app: tauri::AppHandle) -> Result<(), String> {
+async fn validate_config(config: config::AppConfig) -> Result<Vec<config::FieldError>, String> {
+    Ok(config.validate())
+}
+
+#[tauri::command]
+async fn save_config(
+    state: tauri::State<'_, Arc<AppState>>,
+    new_config: config::AppConfig,
+    app: tauri::AppHandle,
+) -> Result<(), String> {
     log::info!("Saving config - command_whitelist: {:?}, custom_commands: {:?}, ip_blacklist: {:?}, enable_ip_blacklist: {}", 
         new_config.command_whitelist, new_config.custom_commands, new_config.ip_blacklist, new_config.enable_ip_blacklist);
 
     config::update_config(|cfg| {
         cfg.api_port = new_config.api_port;
+        cfg.device_display_name = new_config.device_display_name.trim().to_string();
+        cfg.enable_mdns = new_config.enable_mdns;
+        cfg.enable_ssdp = new_config.enable_ssdp;
+        cfg.stealth_health = new_config.stealth_health;
+        cfg.health_secret = new_config.health_secret.filter(|s| !s.is_empty());
+        cfg.grpc_port = new_config.grpc_port;
         cfg.log_buffer_size = new_config.log_buffer_size;
         cfg.enable_log_file = new_config.enable_log_file;
         cfg.log_file_max_size = new_config.log_file_max_size;
+        cfg.min_log_level = new_config.min_log_level;
         cfg.auto_start_api = new_config.auto_start_api;
         cfg.auto_start_on_boot = new_config.auto_start_on_boot;
+        cfg.idle_stop_minutes = new_config.idle_stop_minutes;
         cfg.command_whitelist = new_config.command_whitelist;
         cfg.custom_commands = new_config.custom_commands;
         cfg.theme = new_config.theme;
         cfg.ip_blacklist = new_config.ip_blacklist;
         cfg.enable_ip_blacklist = new_config.enable_ip_blacklist;
+        cfg.lan_only = new_config.lan_only;
+        cfg.trusted_networks = new_config.trusted_networks;
+        cfg.enable_api_keys = new_config.enable_api_keys;
         if let Some(ref path) = new_config.log_file_path {
             cfg.log_file_path = Some(path.clone());
         }
     })
-    .map_err(|e| e.to_string())
+    .map_err(|e| e.to_string())?;
+
+    // 日志设置立即生效，无需重启
+    logger::reload_logger_config();
+
+    // 新加入黑名单的IP不能继续使用已签发的令牌
+    let ended = state.auth_manager.revoke_blacklisted_sessions();
+    if ended > 0 {
+        state.logger.warn(
+            "Security",
+            &format!("Ended {} session(s) from blacklisted IPs", ended),
+        );
+    }
+
+    // 以保存的配置为准注册或取消开机自启动
+    if let Some(e) = autostart::apply_config(&app).error {
+        return Err(format!("Failed to update autostart: {}", e));
+    }
+    Ok(())
+}
+
+/// 开机自启动的配置与系统实际注册状态
+#[tauri::command]
+async fn get_autostart_status(app: tauri::AppHandle) -> Result<autostart::AutostartStatus, String> {
+    Ok(autostart::status(&app))
 }
 
 fn show_notification(title: &str, message: &str) {
@@ -305,28 +719,57 @@ fn show_notification(title: &str, message: &str) {
 
 #[tauri::command]
 async fn set_config_password(
-    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    state: tauri::State<'_, Arc<AppState>>,
     password: String,
 ) -> Result<(), String> {
+    // 先校验密码策略，避免写入不符合要求的密码
+    config::get_config().password_policy.validate(&password)?;
+
     config::update_config(|cfg| {
         let _ = cfg.set_password(&password);
     })
     .map_err(|e| e.to_string())?;
     
-    let mut state = state.lock().await;
     state.auth_manager.set_password(&password)
         .map_err(|e| format!("Failed to update auth manager password: {}", e))?;
     
     state.auth_manager.revoke_all_sessions();
     state.logger.system("Auth", "Password updated, all sessions revoked");
+    state.refresh_mdns_auth().await;
     
     Ok(())
 }
 
 #[tauri::command]
-async fn verify_config_password(password: String) -> Result<bool, String> {
+async fn verify_config_password(
+    state: tauri::State<'_, Arc<AppState>>,
+    password: String,
+) -> Result<bool, String> {
     let cfg = config::get_config();
-    Ok(cfg.verify_password(&password))
+    let valid = cfg.verify_password(&password);
+    // 旧版本的密码在本机输入正确后即可补充登录验证器
+    if valid && state.auth_manager.needs_verifier() {
+        state.auth_manager.restore_verifier(&password)?;
+        state.logger.system("Auth", "Password re-entered, clients can log in again");
+    }
+    Ok(valid)
+}
+
+/// 旧版本设置的密码没有登录验证器，客户端无法登录，界面据此提示重新输入密码
+#[tauri::command]
+async fn password_needs_reentry(state: tauri::State<'_, Arc<AppState>>) -> Result<bool, String> {
+    Ok(state.auth_manager.needs_verifier())
+}
+
+/// 重新输入现有密码，为其补充登录验证器，不修改密码也不吊销会话
+#[tauri::command]
+async fn restore_password_verifier(
+    state: tauri::State<'_, Arc<AppState>>,
+    password: String,
+) -> Result<(), String> {
+    state.auth_manager.restore_verifier(&password)?;
+    state.logger.system("Auth", "Password re-entered, clients can log in again");
+    Ok(())
 }
 
 #[tauri::command]
@@ -337,69 +780,178 @@ async fn has_config_password() -> Result<bool, String> {
 
 #[tauri::command]
 async fn clear_config_password(
-    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    state: tauri::State<'_, Arc<AppState>>,
 ) -> Result<(), String> {
     config::update_config(|cfg| {
         cfg.clear_password();
     })
     .map_err(|e| e.to_string())?;
     
-    let mut state = state.lock().await;
     state.auth_manager.clear_password();
     state.auth_manager.revoke_all_sessions();
     state.logger.system("Auth", "Password cleared, all sessions revoked");
+    state.refresh_mdns_auth().await;
     
     Ok(())
 }
 
+/// 创建 API 密钥，返回的明文只显示这一次
+#[tauri::command]
+async fn create_api_key(
+    state: tauri::State<'_, Arc<AppState>>,
+    name: String,
+    permissions: config::ClientPermissions,
+) -> Result<String, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("API key name must not be empty".to_string());
+    }
+    if let Some(scope) = permissions
+        .scopes
+        .iter()
+        .find(|s| !config::PERMISSION_SCOPES.contains(&s.as_str()))
+    {
+        return Err(format!("Unknown scope: {}", scope));
+    }
+    if config::get_config().api_keys.iter().any(|k| k.name == name) {
+        return Err(format!("An API key named '{}' already exists", name));
+    }
+
+    let key = auth::AuthManager::generate_api_key();
+    let api_key = config::ApiKeyConfig {
+        name: name.clone(),
+        key_hash: config::ApiKeyConfig::hash_key(&key),
+        permissions,
+        created_at: chrono::Utc::now().timestamp(),
+    };
+    config::update_config(|cfg| cfg.api_keys.push(api_key)).map_err(|e| e.to_string())?;
+
+    state.logger.system("Auth", &format!("API key '{}' created", name));
+    Ok(key.to_string())
+}
+
+/// 删除 API 密钥，使用该密钥的请求立即失效
+#[tauri::command]
+async fn delete_api_key(
+    state: tauri::State<'_, Arc<AppState>>,
+    name: String,
+) -> Result<bool, String> {
+    let mut removed = false;
+    config::update_config(|cfg| {
+        let before = cfg.api_keys.len();
+        cfg.api_keys.retain(|k| k.name != name);
+        removed = cfg.api_keys.len() != before;
+    })
+    .map_err(|e| e.to_string())?;
+
+    if removed {
+        state.logger.system("Auth", &format!("API key '{}' deleted", name));
+    }
+    Ok(removed)
+}
+
+#[tauri::command]
+async fn get_paired_devices(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<Vec<pairing::PairedDeviceInfo>, String> {
+    Ok(state.auth_manager.paired_devices())
+}
+
+/// 取消配对，该设备下次连接时需要重新输入密码
+#[tauri::command]
+async fn unpair_device(
+    state: tauri::State<'_, Arc<AppState>>,
+    device_id: String,
+) -> Result<bool, String> {
+    let removed = state.auth_manager.unpair(&device_id);
+    if removed {
+        state.logger.system("Auth", &format!("Device {} unpaired", device_id));
+    }
+    Ok(removed)
+}
+
+/// 设置记录日志的最低级别（info、warn、error），立即生效
+#[tauri::command]
+async fn set_min_log_level(level: String) -> Result<(), String> {
+    if !logger::MIN_LOG_LEVELS.contains(&level.as_str()) {
+        return Err(format!("Invalid log level '{}'", level));
+    }
+    config::update_config(|cfg| cfg.min_log_level = level).map_err(|e| e.to_string())?;
+    logger::reload_logger_config();
+    Ok(())
+}
+
+/// 设置托盘菜单中的快捷操作（见 tray::QUICK_ACTIONS），立即重建菜单
+#[tauri::command]
+async fn set_tray_quick_actions(app: tauri::AppHandle, actions: Vec<String>) -> Result<(), String> {
+    if let Some(action) = actions.iter().find(|a| !tray::QUICK_ACTIONS.contains(&a.as_str())) {
+        return Err(format!("Invalid quick action '{}'", action));
+    }
+    config::update_config(|cfg| cfg.tray.quick_actions = actions).map_err(|e| e.to_string())?;
+    tray::rebuild(&app);
+    Ok(())
+}
+
 #[tauri::command]
 async fn get_log_file_info() -> Result<Option<(String, Option<u64>)>, String> {
     Ok(logger::get_log_file_info().map(|(path, size)| (path.to_string_lossy().to_string(), size)))
 }
 
 #[tauri::command]
-async fn reload_config(state: tauri::State<'_, Arc<Mutex<AppState>>>) -> Result<(), String> {
+async fn reload_config(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<(), String> {
     config::reload_config();
     logger::reload_logger_config();
-    
-    let state = state.lock().await;
+
     state.auth_manager.reload_password();
+    state.refresh_mdns_auth().await;
+    tray::rebuild(&app);
     
     Ok(())
 }
 
 #[tauri::command]
-async fn open_path(path: String) -> Result<(), String> {
-    #[cfg(target_os = "windows")]
-    {
-        use std::os::windows::process::CommandExt;
-        use std::process::Command;
-        const CREATE_NO_WINDOW: u32 = 0x08000000;
-
-        Command::new("explorer")
-            .args(["/select,", &path])
-            .creation_flags(CREATE_NO_WINDOW)
-            .spawn()
-            .map_err(|e| e.to_string())?;
-    }
+async fn export_config(
+    path: String,
+    include_secrets: bool,
+    include_device_uuid: bool,
+) -> Result<(), String> {
+    config::export_config(
+        std::path::Path::new(&path),
+        include_secrets,
+        include_device_uuid,
+    )
+}
 
-    #[cfg(target_os = "linux")]
-    {
-        use std::process::Command;
-        Command::new("xdg-open")
-            .arg(&path)
-            .spawn()
-            .map_err(|e| e.to_string())?;
-    }
+#[tauri::command]
+async fn import_config(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    path: String,
+) -> Result<config::ImportSummary, String> {
+    let summary = config::import_config(std::path::Path::new(&path))?;
+    logger::reload_logger_config();
+    tray::rebuild(&app);
 
-    #[cfg(target_os = "macos")]
-    {
-        use std::process::Command;
-        Command::new("open")
-            .arg(&path)
-            .spawn()
-            .map_err(|e| e.to_string())?;
+    if summary.password_imported {
+        state.auth_manager.reload_password();
+        state.auth_manager.revoke_all_sessions();
+        state.refresh_mdns_auth().await;
+    }
+    state.logger.system("Config", &format!("Config imported from {}", path));
+    if summary.device_uuid_imported {
+        state.reload_device_uuid();
+        state
+            .logger
+            .system("Config", "Device UUID imported, restart the server to advertise it");
     }
 
-    Ok(())
+    Ok(summary)
+}
+
+#[tauri::command]
+async fn open_path(path: String) -> Result<(), String> {
+    apps::reveal_path(&path)
 }
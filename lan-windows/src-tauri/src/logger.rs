@@ -1,12 +1,61 @@
-use chrono::Local;
+use chrono::{DateTime, Local};
 use once_cell::sync::Lazy;
+use std::collections::VecDeque;
 use std::fs::{self, OpenOptions};
 use std::io::Write;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 
 use crate::config::get_config;
 use crate::models::{LogEntry, LogLevel};
+use crate::websocket::WsMessage;
+
+/// 可配置的最低日志级别
+pub const MIN_LOG_LEVELS: &[&str] = &["info", "warn", "error"];
+
+/// 日志级别的严重程度；System 为应用启停、修改密码等少量关键记录，始终保留
+fn severity(level: &LogLevel) -> u8 {
+    match level {
+        LogLevel::Info | LogLevel::Success => 0,
+        LogLevel::Warn => 1,
+        LogLevel::Error => 2,
+        LogLevel::System => 3,
+    }
+}
+
+fn threshold(min_log_level: &str) -> u8 {
+    match min_log_level {
+        "warn" => 1,
+        "error" => 2,
+        _ => 0,
+    }
+}
+
+// 每条日志都要比较，缓存配置中的级别，配置重新加载时更新
+static MIN_SEVERITY: Lazy<AtomicU8> =
+    Lazy::new(|| AtomicU8::new(threshold(&get_config().min_log_level)));
+
+/// 该级别的日志是否需要记录
+pub fn is_enabled(level: &LogLevel) -> bool {
+    severity(level) >= MIN_SEVERITY.load(Ordering::Relaxed)
+}
+
+/// 记录一条日志：写入日志文件和内存缓冲区，并推送给 WebSocket 连接
+///
+/// 低于配置的最低级别时忽略
+pub fn record(buffer: &LogBuffer, entry: LogEntry) {
+    if !is_enabled(&entry.level) {
+        return;
+    }
+    write_log_to_file(&entry);
+    crate::events::broadcast(WsMessage::Log {
+        timestamp: entry.timestamp.to_rfc3339(),
+        level: level_to_string(&entry.level).to_string(),
+        message: entry.message.clone(),
+    });
+    buffer.push(entry);
+}
 
 /// 日志管理器
 pub struct Logger {
@@ -123,11 +172,16 @@ impl Logger {
     pub fn reload_config(&mut self) {
         let config = get_config();
 
-        self.log_file_path = config
+        let log_file_path = config
             .log_file_path
             .as_ref()
             .map(PathBuf::from)
             .unwrap_or_else(crate::config::AppConfig::default_log_path);
+        // 路径变化时关闭旧文件，之后写入新位置
+        if log_file_path != self.log_file_path {
+            self.log_file = None;
+            self.log_file_path = log_file_path;
+        }
 
         self.max_file_size = config.log_file_max_size * 1024 * 1024;
 
@@ -175,6 +229,93 @@ fn escape_json(s: &str) -> String {
         .replace('\t', "\\t")
 }
 
+/// 按时间排序的内存日志，超过容量时丢弃最旧的条目
+///
+/// 条目始终有序，分页读取只需二分查找游标位置，不必每次排序
+pub struct LogBuffer {
+    entries: Mutex<VecDeque<LogEntry>>,
+    capacity: AtomicUsize,
+}
+
+impl LogBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity: AtomicUsize::new(capacity),
+        }
+    }
+
+    /// 修改容量，缩小时丢弃最旧的条目
+    pub fn resize(&self, capacity: usize) {
+        self.capacity.store(capacity, Ordering::Relaxed);
+        if let Ok(mut entries) = self.entries.lock() {
+            let excess = entries.len().saturating_sub(capacity);
+            entries.drain(..excess);
+        }
+    }
+
+    pub fn push(&self, entry: LogEntry) {
+        if let Ok(mut entries) = self.entries.lock() {
+            // 通常追加在末尾；系统时间被调回时按时间戳插入，保持有序
+            let index = entries.partition_point(|e| e.timestamp <= entry.timestamp);
+            entries.insert(index, entry);
+            let excess = entries
+                .len()
+                .saturating_sub(self.capacity.load(Ordering::Relaxed));
+            entries.drain(..excess);
+        }
+    }
+
+    /// 早于 before 的最多 limit 条日志，最新的在前；before 为空时从最新的开始
+    pub fn page(&self, before: Option<DateTime<Local>>, limit: usize) -> Vec<LogEntry> {
+        let Ok(entries) = self.entries.lock() else {
+            return Vec::new();
+        };
+        let end = match before {
+            Some(before) => entries.partition_point(|e| e.timestamp < before),
+            None => entries.len(),
+        };
+        entries.range(..end).rev().take(limit).cloned().collect()
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.clear();
+        }
+    }
+}
+
+/// 应用日志（界面操作、服务器启停等），容量为配置中的 log_buffer_size
+pub static APP_LOGS: Lazy<LogBuffer> = Lazy::new(|| LogBuffer::new(get_config().log_buffer_size));
+/// API 请求日志
+pub static API_LOGS: Lazy<LogBuffer> = Lazy::new(|| LogBuffer::new(50));
+
+/// 分页读取应用日志和 API 日志，按时间倒序合并
+///
+/// 下一页以本页最后一条的时间戳作为 before
+pub fn recent_logs(before: Option<DateTime<Local>>, limit: usize) -> Vec<LogEntry> {
+    let app = APP_LOGS.page(before, limit);
+    let api = API_LOGS.page(before, limit);
+    let (mut app, mut api) = (app.into_iter().peekable(), api.into_iter().peekable());
+    let mut logs = Vec::with_capacity(limit);
+    while logs.len() < limit {
+        let take_app = match (app.peek(), api.peek()) {
+            (Some(a), Some(b)) => a.timestamp >= b.timestamp,
+            (Some(_), None) => true,
+            (None, Some(_)) => false,
+            (None, None) => break,
+        };
+        logs.extend(if take_app { app.next() } else { api.next() });
+    }
+    logs
+}
+
+/// 清空内存中的日志
+pub fn clear_logs() {
+    APP_LOGS.clear();
+    API_LOGS.clear();
+}
+
 // 全局日志管理器
 pub static GLOBAL_LOGGER: Lazy<Arc<Mutex<Logger>>> =
     Lazy::new(|| Arc::new(Mutex::new(Logger::new())));
@@ -186,11 +327,14 @@ pub fn write_log_to_file(entry: &LogEntry) {
     }
 }
 
-/// 重新加载日志配置
+/// 重新加载日志配置：日志文件设置和内存日志容量
 pub fn reload_logger_config() {
     if let Ok(mut logger) = GLOBAL_LOGGER.lock() {
         logger.reload_config();
     }
+    let config = get_config();
+    APP_LOGS.resize(config.log_buffer_size);
+    MIN_SEVERITY.store(threshold(&config.min_log_level), Ordering::Relaxed);
 }
 
 /// 获取日志文件信息
@@ -203,3 +347,37 @@ pub fn get_log_file_info() -> Option<(PathBuf, Option<u64>)> {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn entry(timestamp: DateTime<Local>, message: &str) -> LogEntry {
+        LogEntry {
+            timestamp,
+            level: LogLevel::Info,
+            category: "Test".to_string(),
+            message: message.to_string(),
+            source: None,
+        }
+    }
+
+    #[test]
+    fn pages_stay_sorted_when_clock_goes_back() {
+        let buffer = LogBuffer::new(3);
+        let now = Local::now();
+        buffer.push(entry(now, "b"));
+        buffer.push(entry(now - Duration::seconds(5), "a"));
+        buffer.push(entry(now + Duration::seconds(5), "c"));
+        buffer.push(entry(now + Duration::seconds(10), "d"));
+
+        let messages =
+            |logs: Vec<LogEntry>| logs.into_iter().map(|e| e.message).collect::<Vec<_>>();
+        // 容量为 3，最旧的 "a" 被丢弃
+        assert_eq!(messages(buffer.page(None, 10)), ["d", "c", "b"]);
+        let first = buffer.page(None, 2);
+        let before = first.last().map(|e| e.timestamp);
+        assert_eq!(messages(buffer.page(before, 2)), ["b"]);
+    }
+}
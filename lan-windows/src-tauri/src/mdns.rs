@@ -1,10 +1,42 @@
-use mdns_sd::{ServiceDaemon, ServiceInfo};
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
 use std::collections::HashMap;
 use std::net::IpAddr;
 use std::net::Ipv4Addr;
 
 use crate::device_id::DeviceId;
 
+/// DNS 标签最长 63 字节，服务实例名称受同样限制
+const MAX_INSTANCE_NAME_LEN: usize = 63;
+
+/// 注册前查找同名服务的等待时间
+const CONFLICT_PROBE_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(1500);
+
+/// 把显示名称转换为合法的实例名称：去掉点号和控制字符，并截断到 63 字节以内
+fn sanitize_instance_name(name: &str, max_len: usize) -> String {
+    let cleaned: String = name
+        .chars()
+        .filter(|c| *c != '.' && !c.is_control())
+        .collect();
+    let mut cleaned = cleaned.trim().to_string();
+    while cleaned.len() > max_len {
+        cleaned.pop();
+    }
+    cleaned.trim_end().to_string()
+}
+
+/// 生成带冲突后缀的名称，如 "Living Room PC (2)"
+fn name_with_suffix(base: &str, n: usize) -> String {
+    if n <= 1 {
+        return base.to_string();
+    }
+    let suffix = format!(" ({})", n);
+    format!(
+        "{}{}",
+        sanitize_instance_name(base, MAX_INSTANCE_NAME_LEN - suffix.len()),
+        suffix
+    )
+}
+
 pub struct MdnsService {
     daemon: ServiceDaemon,
     port: u16,
@@ -12,10 +44,14 @@ pub struct MdnsService {
     device_uuid: String,
     service_name: String,
     host_name: String,
+    /// 用户配置的显示名称，为空时使用默认服务名和主机名
+    display_name: String,
+    /// 是否已设置密码，对应 TXT 记录中的 auth
+    auth_required: bool,
 }
 
 impl MdnsService {
-    pub fn new(port: u16) -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn new(port: u16, auth_required: bool) -> Result<Self, Box<dyn std::error::Error>> {
         let daemon = ServiceDaemon::new()?;
         
         // 获取或创建设备UUID
@@ -37,8 +73,16 @@ impl MdnsService {
             .unwrap_or_else(|| "unknown-host".to_string());
         let host_name = format!("{}.local.", hostname);
         
-        // 使用设备UUID作为服务名称的一部分，确保唯一性
-        let service_name = format!("LanDevice-{}", &device_uuid[..8]);
+        // 配置了显示名称时直接作为服务名称，否则使用设备UUID的前缀确保唯一性
+        let display_name = sanitize_instance_name(
+            &crate::config::get_config().device_display_name,
+            MAX_INSTANCE_NAME_LEN,
+        );
+        let service_name = if display_name.is_empty() {
+            format!("LanDevice-{}", &device_uuid[..8])
+        } else {
+            display_name.clone()
+        };
 
         Ok(Self {
             daemon,
@@ -47,15 +91,79 @@ impl MdnsService {
             device_uuid,
             service_name,
             host_name,
+            display_name,
+            auth_required,
         })
     }
 
+    /// 自定义名称可能与局域网中其他设备重复，注册前查找已有服务，重复时追加 " (2)"、" (3)" 等后缀
+    fn resolve_name_conflict(&mut self) {
+        if self.display_name.is_empty() {
+            return;
+        }
+
+        let receiver = match self.daemon.browse(&self.service_type) {
+            Ok(receiver) => receiver,
+            Err(e) => {
+                log::warn!("Failed to probe for mDNS name conflicts: {}", e);
+                return;
+            }
+        };
+
+        let suffix = format!(".{}", self.service_type);
+        let mut taken = Vec::new();
+        let deadline = std::time::Instant::now() + CONFLICT_PROBE_TIMEOUT;
+        while let Some(remaining) = deadline.checked_duration_since(std::time::Instant::now()) {
+            match receiver.recv_timeout(remaining) {
+                Ok(ServiceEvent::ServiceResolved(info)) => {
+                    // 同一设备之前注册的服务不算冲突
+                    if info.get_property_val_str("uuid") == Some(self.device_uuid.as_str()) {
+                        continue;
+                    }
+                    if let Some(instance) = info.get_fullname().strip_suffix(&suffix) {
+                        taken.push(instance.to_lowercase());
+                    }
+                }
+                Ok(_) => {}
+                Err(_) => break,
+            }
+        }
+        let _ = self.daemon.stop_browse(&self.service_type);
+
+        let name = (1..)
+            .map(|n| name_with_suffix(&self.display_name, n))
+            .find(|name| !taken.contains(&name.to_lowercase()))
+            .unwrap_or_else(|| self.display_name.clone());
+        if name != self.service_name {
+            log::warn!(
+                "mDNS name '{}' is already in use, advertising as '{}'",
+                self.service_name,
+                name
+            );
+            self.service_name = name;
+        }
+    }
+
     pub fn start(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         log::info!("Starting mDNS service discovery on port {}", self.port);
+        self.resolve_name_conflict();
         log::info!("Device UUID: {}", self.device_uuid);
         log::info!("Service name: {}", self.service_name);
         log::info!("Using hostname: {}", self.host_name);
+        self.register()
+    }
 
+    /// 密码设置或清除后更新 TXT 记录中的 auth，以同一名称重新注册
+    pub fn set_auth_required(&mut self, auth_required: bool) -> Result<(), Box<dyn std::error::Error>> {
+        if self.auth_required == auth_required {
+            return Ok(());
+        }
+        self.auth_required = auth_required;
+        log::info!("Updating mDNS auth flag: {}", auth_required);
+        self.register()
+    }
+
+    fn register(&self) -> Result<(), Box<dyn std::error::Error>> {
         // Get local IP addresses
         let mut addrs: Vec<IpAddr> = Vec::new();
 
@@ -99,8 +207,22 @@ impl MdnsService {
         let mut properties = HashMap::new();
         properties.insert("version".to_string(), env!("CARGO_PKG_VERSION").to_string());
         properties.insert("protocol".to_string(), "tcp".to_string());
-        properties.insert("auth".to_string(), "required".to_string());
-        properties.insert("device".to_string(), self.host_name.trim_end_matches(".local.").to_string());
+        let auth = if self.auth_required { "required" } else { "none" };
+        properties.insert("auth".to_string(), auth.to_string());
+        let device_name = if self.display_name.is_empty() {
+            self.host_name.trim_end_matches(".local.").to_string()
+        } else {
+            self.service_name.clone()
+        };
+        properties.insert("device".to_string(), device_name);
+        // 客户端在连接前即可显示系统和用户设置的名称
+        if !self.display_name.is_empty() {
+            properties.insert("display_name".to_string(), self.service_name.clone());
+        }
+        if let Ok(info) = crate::command::get_static_system_info() {
+            properties.insert("os".to_string(), info.os_type);
+            properties.insert("os_version".to_string(), info.os_version);
+        }
         properties.insert("uuid".to_string(), self.device_uuid.clone());  // 添加UUID
         properties.insert("port".to_string(), self.port.to_string());  // 添加端口信息
 
@@ -123,6 +245,7 @@ impl MdnsService {
         log::info!("Port: {}", self.port);
         log::info!("Host: {}", self.host_name);
         log::info!("UUID: {}", self.device_uuid);
+        log::info!("Auth: {}", auth);
 
         Ok(())
     }
@@ -0,0 +1,240 @@
+use serde::Serialize;
+
+#[cfg(not(target_os = "macos"))]
+use std::process::Command;
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+/// 音频播放设备
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioDevice {
+    /// 设备标识，设置默认设备时使用
+    pub id: String,
+    pub name: String,
+    pub is_default: bool,
+}
+
+/// Core Audio 接口定义，IPolicyConfig 为未公开接口，系统声音设置也通过它切换默认设备
+#[cfg(target_os = "windows")]
+const CORE_AUDIO_TYPES: &str = r#"
+using System;
+using System.Collections.Generic;
+using System.Runtime.InteropServices;
+
+[ComImport, Guid("A95664D2-9614-4F35-A746-DE8DB63617E6"), InterfaceType(ComInterfaceType.InterfaceIsIUnknown)]
+interface IMMDeviceEnumerator {
+    int EnumAudioEndpoints(int dataFlow, int stateMask, out IMMDeviceCollection devices);
+    int GetDefaultAudioEndpoint(int dataFlow, int role, out IMMDevice device);
+}
+
+[ComImport, Guid("0BD7A1BE-7A1A-44DB-8397-CC5392387B5E"), InterfaceType(ComInterfaceType.InterfaceIsIUnknown)]
+interface IMMDeviceCollection {
+    int GetCount(out int count);
+    int Item(int index, out IMMDevice device);
+}
+
+[ComImport, Guid("D666063F-1587-4E43-81F1-B948E807363F"), InterfaceType(ComInterfaceType.InterfaceIsIUnknown)]
+interface IMMDevice {
+    int Activate(ref Guid iid, int clsCtx, IntPtr activationParams, [MarshalAs(UnmanagedType.IUnknown)] out object iface);
+    int OpenPropertyStore(int access, out IPropertyStore store);
+    int GetId([MarshalAs(UnmanagedType.LPWStr)] out string id);
+}
+
+[ComImport, Guid("886d8eeb-8cf2-4446-8d02-cdba1dbdcf99"), InterfaceType(ComInterfaceType.InterfaceIsIUnknown)]
+interface IPropertyStore {
+    int GetCount(out int count);
+    int GetAt(int index, out PropertyKey key);
+    int GetValue(ref PropertyKey key, out PropVariant value);
+}
+
+[StructLayout(LayoutKind.Sequential)]
+struct PropertyKey { public Guid fmtid; public int pid; }
+
+[StructLayout(LayoutKind.Explicit)]
+struct PropVariant { [FieldOffset(0)] public short vt; [FieldOffset(8)] public IntPtr pointer; }
+
+[ComImport, Guid("BCDE0395-E52F-467C-8E3D-C4579291692E")]
+class MMDeviceEnumerator {}
+
+[ComImport, Guid("f8679f50-850a-41cf-9c72-430f290290c8"), InterfaceType(ComInterfaceType.InterfaceIsIUnknown)]
+interface IPolicyConfig {
+    int GetMixFormat();
+    int GetDeviceFormat();
+    int ResetDeviceFormat();
+    int SetDeviceFormat();
+    int GetProcessingPeriod();
+    int SetProcessingPeriod();
+    int GetShareMode();
+    int SetShareMode();
+    int GetPropertyValue();
+    int SetPropertyValue();
+    int SetDefaultEndpoint([MarshalAs(UnmanagedType.LPWStr)] string deviceId, int role);
+}
+
+[ComImport, Guid("870af99c-171d-4f9e-af0d-e63df40c2bc9")]
+class PolicyConfigClient {}
+
+public class LanAudioDevice { public string Id; public string Name; public bool IsDefault; }
+
+public static class LanAudio {
+    public static LanAudioDevice[] List() {
+        var enumerator = (IMMDeviceEnumerator)new MMDeviceEnumerator();
+        string defaultId = null;
+        IMMDevice defaultDevice;
+        if (enumerator.GetDefaultAudioEndpoint(0, 1, out defaultDevice) == 0) {
+            defaultDevice.GetId(out defaultId);
+        }
+
+        // eRender = 0, DEVICE_STATE_ACTIVE = 1
+        IMMDeviceCollection collection;
+        Marshal.ThrowExceptionForHR(enumerator.EnumAudioEndpoints(0, 1, out collection));
+        int count;
+        collection.GetCount(out count);
+
+        var friendlyName = new PropertyKey { fmtid = new Guid("a45c254e-df1c-4efd-8020-67d146a850e0"), pid = 14 };
+        var devices = new List<LanAudioDevice>();
+        for (int i = 0; i < count; i++) {
+            IMMDevice device;
+            collection.Item(i, out device);
+            string id;
+            device.GetId(out id);
+            IPropertyStore store;
+            device.OpenPropertyStore(0, out store);
+            PropVariant value;
+            store.GetValue(ref friendlyName, out value);
+            devices.Add(new LanAudioDevice {
+                Id = id,
+                Name = value.vt == 31 ? Marshal.PtrToStringUni(value.pointer) : id,
+                IsDefault = id == defaultId
+            });
+        }
+        return devices.ToArray();
+    }
+
+    public static void SetDefault(string id) {
+        var policy = (IPolicyConfig)new PolicyConfigClient();
+        // eConsole、eMultimedia、eCommunications 三个角色都切换
+        for (int role = 0; role < 3; role++) {
+            Marshal.ThrowExceptionForHR(policy.SetDefaultEndpoint(id, role));
+        }
+    }
+}
+"#;
+
+#[cfg(target_os = "windows")]
+fn run_audio_script(command: &str) -> Result<String, String> {
+    let script = format!(
+        "Add-Type -TypeDefinition @'\n{}\n'@\n{}",
+        CORE_AUDIO_TYPES, command
+    );
+    let output = Command::new("powershell")
+        .args(["-NoProfile", "-Command", &script])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map_err(|e| e.to_string())?;
+
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// 列出可用的播放设备
+#[cfg(target_os = "windows")]
+pub fn list_playback_devices() -> Result<Vec<AudioDevice>, String> {
+    let text = run_audio_script("[LanAudio]::List() | ConvertTo-Json -Compress")?;
+    // 只有一个设备时 ConvertTo-Json 输出对象而不是数组
+    let items = match serde_json::from_str::<serde_json::Value>(text.trim()) {
+        Ok(serde_json::Value::Array(items)) => items,
+        Ok(item @ serde_json::Value::Object(_)) => vec![item],
+        _ => Vec::new(),
+    };
+
+    Ok(items
+        .iter()
+        .filter_map(|item| {
+            Some(AudioDevice {
+                id: item.get("Id")?.as_str()?.to_string(),
+                name: item.get("Name")?.as_str()?.to_string(),
+                is_default: item
+                    .get("IsDefault")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false),
+            })
+        })
+        .collect())
+}
+
+/// 设置默认播放设备
+#[cfg(target_os = "windows")]
+pub fn set_default_playback_device(id: &str) -> Result<(), String> {
+    // 只接受已枚举到的设备ID，避免把参数注入到脚本中
+    if !list_playback_devices()?.iter().any(|d| d.id == id) {
+        return Err(format!("Audio device '{}' not found", id));
+    }
+    run_audio_script(&format!("[LanAudio]::SetDefault('{}')", id.replace('\'', "''"))).map(|_| ())
+}
+
+#[cfg(target_os = "linux")]
+fn run_pactl(args: &[&str]) -> Result<String, String> {
+    // 固定英文输出，便于解析字段名
+    let output = Command::new("pactl")
+        .args(args)
+        .env("LC_ALL", "C")
+        .output()
+        .map_err(|e| format!("Failed to run pactl: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// 通过 PulseAudio / PipeWire 列出输出设备
+#[cfg(target_os = "linux")]
+pub fn list_playback_devices() -> Result<Vec<AudioDevice>, String> {
+    let default_sink = run_pactl(&["info"])?
+        .lines()
+        .find_map(|line| line.strip_prefix("Default Sink:"))
+        .map(|s| s.trim().to_string());
+
+    // 每个设备以 "Sink #N" 开头，包含 "Name:" 和 "Description:" 行
+    let mut devices: Vec<AudioDevice> = Vec::new();
+    for line in run_pactl(&["list", "sinks"])?.lines() {
+        let line = line.trim();
+        if let Some(name) = line.strip_prefix("Name:") {
+            let id = name.trim().to_string();
+            devices.push(AudioDevice {
+                is_default: default_sink.as_deref() == Some(id.as_str()),
+                name: id.clone(),
+                id,
+            });
+        } else if let Some(description) = line.strip_prefix("Description:") {
+            if let Some(device) = devices.last_mut() {
+                device.name = description.trim().to_string();
+            }
+        }
+    }
+    Ok(devices)
+}
+
+#[cfg(target_os = "linux")]
+pub fn set_default_playback_device(id: &str) -> Result<(), String> {
+    if !list_playback_devices()?.iter().any(|d| d.id == id) {
+        return Err(format!("Audio device '{}' not found", id));
+    }
+    run_pactl(&["set-default-sink", id]).map(|_| ())
+}
+
+#[cfg(target_os = "macos")]
+pub fn list_playback_devices() -> Result<Vec<AudioDevice>, String> {
+    Err("Audio device control is not supported on macOS".to_string())
+}
+
+#[cfg(target_os = "macos")]
+pub fn set_default_playback_device(_id: &str) -> Result<(), String> {
+    Err("Audio device control is not supported on macOS".to_string())
+}
@@ -0,0 +1,277 @@
+use axum::{
+    body::{to_bytes, Body, HttpBody},
+    extract::Request,
+    http::{
+        header::{ACCEPT_LANGUAGE, CONTENT_LENGTH, CONTENT_TYPE},
+        HeaderMap,
+    },
+    middleware::Next,
+    response::Response,
+};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// 支持的语言
+pub const SUPPORTED_LANGUAGES: &[&str] = &["en", "zh"];
+/// 只处理较小的 JSON 响应，错误响应通常只有几百字节
+const MAX_LOCALIZED_BODY: u64 = 16 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+    En,
+    Zh,
+}
+
+impl Lang {
+    fn parse(tag: &str) -> Option<Self> {
+        let primary = tag.trim().split(['-', '_']).next()?.to_lowercase();
+        match primary.as_str() {
+            "en" => Some(Lang::En),
+            "zh" => Some(Lang::Zh),
+            _ => None,
+        }
+    }
+
+    /// 按 Accept-Language 的权重选择语言，没有支持的语言时使用配置的默认语言
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let mut candidates: Vec<(f32, Lang)> = headers
+            .get(ACCEPT_LANGUAGE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("")
+            .split(',')
+            .filter_map(|item| {
+                let mut parts = item.split(';');
+                let lang = Lang::parse(parts.next()?)?;
+                let quality = parts
+                    .find_map(|p| p.trim().strip_prefix("q="))
+                    .and_then(|q| q.parse().ok())
+                    .unwrap_or(1.0);
+                Some((quality, lang))
+            })
+            .collect();
+        candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+        candidates
+            .first()
+            .map(|(_, lang)| *lang)
+            .unwrap_or_else(Self::default_language)
+    }
+
+    pub fn default_language() -> Self {
+        Lang::parse(&crate::config::get_config().message_language).unwrap_or(Lang::En)
+    }
+}
+
+/// 稳定的错误码，客户端可据此判断错误类型而不依赖文本
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    InvalidToken,
+    TokenMissing,
+    PermissionDenied,
+    InvalidConfiguration,
+    InvalidPassword,
+    IncorrectPassword,
+    InvalidChallenge,
+    ChallengeExpired,
+    TooManyChallenges,
+    CommandNotWhitelisted,
+    CustomCommandsDisabled,
+    UnknownCommand,
+    ExecutionError,
+    InvalidRequest,
+    RateLimited,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::InvalidToken => "auth.invalid_token",
+            ErrorCode::TokenMissing => "auth.token_missing",
+            ErrorCode::PermissionDenied => "auth.permission_denied",
+            ErrorCode::InvalidConfiguration => "config.invalid",
+            ErrorCode::InvalidPassword => "auth.invalid_password",
+            ErrorCode::IncorrectPassword => "auth.incorrect_password",
+            ErrorCode::InvalidChallenge => "auth.invalid_challenge",
+            ErrorCode::ChallengeExpired => "auth.challenge_expired",
+            ErrorCode::TooManyChallenges => "auth.too_many_challenges",
+            ErrorCode::CommandNotWhitelisted => "command.not_whitelisted",
+            ErrorCode::CustomCommandsDisabled => "command.custom_disabled",
+            ErrorCode::UnknownCommand => "command.unknown",
+            ErrorCode::ExecutionError => "command.execution_error",
+            // 请求体格式错误，由 extract::Json 返回
+            ErrorCode::InvalidRequest => "INVALID_REQUEST",
+            ErrorCode::RateLimited => "http.rate_limited",
+        }
+    }
+
+    /// 消息模板，{0} 为从原始错误中提取的参数
+    fn template(&self, lang: Lang) -> &'static str {
+        match (self, lang) {
+            (ErrorCode::InvalidToken, Lang::En) => {
+                "Your session has expired. Please sign in again."
+            }
+            (ErrorCode::InvalidToken, Lang::Zh) => "登录已过期，请重新登录。",
+            (ErrorCode::TokenMissing, Lang::En) => "Please sign in first.",
+            (ErrorCode::TokenMissing, Lang::Zh) => "请先登录。",
+            (ErrorCode::PermissionDenied, Lang::En) => {
+                "This device is not allowed to perform this action."
+            }
+            (ErrorCode::PermissionDenied, Lang::Zh) => "此设备无权执行该操作。",
+            (ErrorCode::InvalidConfiguration, Lang::En) => "The configuration is invalid.",
+            (ErrorCode::InvalidConfiguration, Lang::Zh) => "配置无效。",
+            (ErrorCode::InvalidPassword, Lang::En) => "Incorrect password.",
+            (ErrorCode::InvalidPassword, Lang::Zh) => "密码错误。",
+            (ErrorCode::IncorrectPassword, Lang::En) => "The current password is incorrect.",
+            (ErrorCode::IncorrectPassword, Lang::Zh) => "当前密码错误。",
+            (ErrorCode::InvalidChallenge, Lang::En) => "Sign-in failed. Please try again.",
+            (ErrorCode::InvalidChallenge, Lang::Zh) => "登录失败，请重试。",
+            (ErrorCode::ChallengeExpired, Lang::En) => "Sign-in timed out. Please try again.",
+            (ErrorCode::ChallengeExpired, Lang::Zh) => "登录超时，请重试。",
+            (ErrorCode::TooManyChallenges, Lang::En) => {
+                "Too many sign-in attempts. Please try again later."
+            }
+            (ErrorCode::TooManyChallenges, Lang::Zh) => "登录尝试次数过多，请稍后再试。",
+            (ErrorCode::CommandNotWhitelisted, Lang::En) => {
+                "The command '{0}' is not allowed on this computer."
+            }
+            (ErrorCode::CommandNotWhitelisted, Lang::Zh) => "此电脑不允许执行命令“{0}”。",
+            (ErrorCode::CustomCommandsDisabled, Lang::En) => {
+                "Custom commands are disabled on this computer."
+            }
+            (ErrorCode::CustomCommandsDisabled, Lang::Zh) => "此电脑已禁用自定义命令。",
+            (ErrorCode::UnknownCommand, Lang::En) => "Unknown command '{0}'.",
+            (ErrorCode::UnknownCommand, Lang::Zh) => "未知命令“{0}”。",
+            (ErrorCode::ExecutionError, Lang::En) => "The command could not be run: {0}",
+            (ErrorCode::ExecutionError, Lang::Zh) => "命令无法执行：{0}",
+            (ErrorCode::InvalidRequest, Lang::En) => "The request was not understood: {0}",
+            (ErrorCode::InvalidRequest, Lang::Zh) => "无法识别请求：{0}",
+            (ErrorCode::RateLimited, Lang::En) => {
+                "Too many requests. Please wait {0} seconds and try again."
+            }
+            (ErrorCode::RateLimited, Lang::Zh) => "请求过于频繁，请 {0} 秒后再试。",
+        }
+    }
+
+    pub fn message(&self, lang: Lang, arg: &str) -> String {
+        self.template(lang).replace("{0}", arg)
+    }
+}
+
+// 已知错误文本与错误码的对应关系，第一个捕获组作为消息参数
+static PATTERNS: Lazy<Vec<(Regex, ErrorCode)>> = Lazy::new(|| {
+    [
+        (r"^Invalid or expired token", ErrorCode::InvalidToken),
+        (
+            r"^Authentication required\. Token missing",
+            ErrorCode::TokenMissing,
+        ),
+        (r"^Permission denied", ErrorCode::PermissionDenied),
+        (r"^Invalid configuration", ErrorCode::InvalidConfiguration),
+        (r"^Invalid password", ErrorCode::InvalidPassword),
+        (r"^Invalid response", ErrorCode::InvalidPassword),
+        (
+            r"^Current password is incorrect",
+            ErrorCode::IncorrectPassword,
+        ),
+        (r"^Invalid challenge", ErrorCode::InvalidChallenge),
+        (r"^Challenge has expired", ErrorCode::ChallengeExpired),
+        (
+            r"^Too many pending challenges",
+            ErrorCode::TooManyChallenges,
+        ),
+        (
+            r"^Command '([^']*)' is not in whitelist",
+            ErrorCode::CommandNotWhitelisted,
+        ),
+        (
+            r"^Custom commands are disabled",
+            ErrorCode::CustomCommandsDisabled,
+        ),
+        (r"^Unknown command '([^']*)'", ErrorCode::UnknownCommand),
+        (r"^Execution error: (.*)$", ErrorCode::ExecutionError),
+        (r"^Invalid request: (.*)$", ErrorCode::InvalidRequest),
+        (
+            r"^Too many requests, retry after (\d+) seconds",
+            ErrorCode::RateLimited,
+        ),
+    ]
+    .into_iter()
+    .map(|(pattern, code)| (Regex::new(pattern).expect("invalid message pattern"), code))
+    .collect()
+});
+
+/// 将服务端的错误文本转换为错误码和本地化消息，未知错误返回 None
+pub fn localize(error: &str, lang: Lang) -> Option<(ErrorCode, String)> {
+    PATTERNS.iter().find_map(|(pattern, code)| {
+        let captures = pattern.captures(error)?;
+        let arg = captures.get(1).map_or("", |m| m.as_str());
+        Some((*code, code.message(lang, arg)))
+    })
+}
+
+/// 为 JSON 对象中的错误文本补充 error_code 和 error_message，返回是否有修改
+fn annotate(
+    object: &mut serde_json::Map<String, serde_json::Value>,
+    key: &str,
+    lang: Lang,
+) -> bool {
+    let Some(error) = object.get(key).and_then(|v| v.as_str()) else {
+        return false;
+    };
+    let Some((code, message)) = localize(error, lang) else {
+        return false;
+    };
+    object.insert("error_code".to_string(), code.as_str().into());
+    object.insert("error_message".to_string(), message.into());
+    true
+}
+
+/// 为失败的响应补充错误码和本地化消息的中间件
+///
+/// 处理两种情况：`success` 为 false 的 ApiResponse（`error` 字段），
+/// 以及执行失败的命令结果（`data.stderr` 字段）
+pub async fn localize_middleware(request: Request, next: Next) -> Response {
+    let lang = Lang::from_headers(request.headers());
+    let response = next.run(request).await;
+
+    let is_json = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map_or(false, |ct| ct.starts_with("application/json"));
+    let small = response
+        .body()
+        .size_hint()
+        .exact()
+        .map_or(false, |len| len <= MAX_LOCALIZED_BODY);
+    if !is_json || !small {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, MAX_LOCALIZED_BODY as usize).await {
+        Ok(bytes) => bytes,
+        Err(_) => return Response::from_parts(parts, Body::empty()),
+    };
+    let Ok(serde_json::Value::Object(mut json)) = serde_json::from_slice(&bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+
+    let failed = |object: &serde_json::Map<String, serde_json::Value>| {
+        object.get("success").and_then(|v| v.as_bool()) == Some(false)
+    };
+    let annotated = if failed(&json) {
+        annotate(&mut json, "error", lang)
+    } else if let Some(serde_json::Value::Object(data)) = json.get_mut("data") {
+        failed(data) && annotate(data, "stderr", lang)
+    } else {
+        false
+    };
+    // 没有可本地化的错误时原样返回
+    if !annotated {
+        return Response::from_parts(parts, Body::from(bytes));
+    }
+
+    let body = serde_json::Value::Object(json).to_string();
+    parts.headers.remove(CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(body))
+}
@@ -8,6 +8,12 @@ pub struct ServerStatus {
     pub device_name: String,
     pub ip_address: Option<String>,
     pub version: String,
+    /// 是否正在通过 mDNS 广播
+    pub advertising: bool,
+    /// mDNS 注册失败的原因，此时服务器照常运行并在后台重试注册；为空表示正常
+    pub discovery_error: Option<String>,
+    /// 设备UUID，客户端以此识别本机
+    pub device_uuid: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,6 +26,65 @@ pub struct SystemInfo {
     pub memory_total: u64,
     pub memory_used: u64,
     pub uptime_seconds: u64,
+    #[serde(default)]
+    pub gpus: Vec<GpuMetrics>,
+}
+
+/// 显卡指标，无法获取的项为 None
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GpuMetrics {
+    pub name: String,
+    pub utilization_percent: Option<f32>,
+    /// 显存（MB）
+    pub memory_used: Option<u64>,
+    pub memory_total: Option<u64>,
+    /// 温度（摄氏度）
+    pub temperature: Option<f32>,
+}
+
+/// 用户在场状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PresenceInfo {
+    /// 工作站是否已锁定，无法判断时为 None
+    pub locked: Option<bool>,
+    /// 距离最后一次用户输入的秒数，无法获取时为 None
+    pub idle_seconds: Option<u64>,
+}
+
+/// 很少变化的系统信息，可以长时间缓存
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StaticSystemInfo {
+    pub os_type: String,
+    pub os_version: String,
+    pub hostname: String,
+    pub architecture: String,
+    pub memory_total: u64,
+}
+
+/// 实时变化的系统指标，每次请求时刷新
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiveMetrics {
+    pub cpu_usage: f32,
+    pub memory_used: u64,
+    pub uptime_seconds: u64,
+    pub gpus: Vec<GpuMetrics>,
+}
+
+impl SystemInfo {
+    /// 合并静态信息和实时指标
+    pub fn from_parts(info: StaticSystemInfo, metrics: LiveMetrics) -> Self {
+        Self {
+            os_type: info.os_type,
+            os_version: info.os_version,
+            hostname: info.hostname,
+            architecture: info.architecture,
+            cpu_usage: metrics.cpu_usage,
+            memory_total: info.memory_total,
+            memory_used: metrics.memory_used,
+            uptime_seconds: metrics.uptime_seconds,
+            gpus: metrics.gpus,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -53,6 +118,9 @@ pub enum LogLevel {
 pub struct AuthChallenge {
     pub challenge: String,
     pub expires_at: DateTime<Utc>,
+    /// 申请挑战的客户端IP，用于按IP限制数量
+    #[serde(default)]
+    pub client: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -65,6 +133,12 @@ pub struct AuthRequest {
 pub struct AuthResponse {
     pub token: String,
     pub expires_in: u64,
+    /// 登录时协商成功的负载加密算法
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encryption: Option<String>,
+    /// 负载加密的密钥ID，加密请求以它代替明文令牌
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -88,6 +162,9 @@ impl Default for ServerStatus {
                 .unwrap_or_else(|| "Unknown".to_string()),
             ip_address: None,
             version: env!("CARGO_PKG_VERSION").to_string(),
+            advertising: false,
+            discovery_error: None,
+            device_uuid: crate::device_id::DeviceId::get_or_create().ok(),
         }
     }
 }
@@ -0,0 +1,427 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+use std::net::IpAddr;
+use std::process::Command;
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+/// ping 次数上限
+const MAX_PING_COUNT: u32 = 10;
+/// traceroute 最大跳数上限
+const MAX_HOPS: u32 = 30;
+/// 网速测试数据块大小
+const SPEEDTEST_CHUNK_SIZE: usize = 64 * 1024;
+
+// 匹配 "time=12.3 ms"、"时间<1ms" 等延迟字段，与系统语言无关
+static RTT_PATTERN: Lazy<Regex> = Lazy::new(|| Regex::new(r"([\d.]+)\s*ms").unwrap());
+
+/// ping 结果
+#[derive(Debug, Clone, Serialize)]
+pub struct PingResult {
+    pub target: String,
+    pub sent: u32,
+    pub received: u32,
+    pub loss_percent: f32,
+    pub min_ms: Option<f32>,
+    pub avg_ms: Option<f32>,
+    pub max_ms: Option<f32>,
+    pub times_ms: Vec<f32>,
+}
+
+/// traceroute 的一跳
+#[derive(Debug, Clone, Serialize)]
+pub struct TraceHop {
+    pub hop: u32,
+    /// 超时无响应时为 None
+    pub address: Option<String>,
+    pub rtt_ms: Vec<f32>,
+}
+
+/// traceroute 结果
+#[derive(Debug, Clone, Serialize)]
+pub struct TraceResult {
+    pub target: String,
+    pub hops: Vec<TraceHop>,
+}
+
+/// 校验目标地址，只允许 IP 地址或主机名，避免把参数注入到命令中
+pub fn validate_target(target: &str) -> Result<String, String> {
+    let target = target.trim();
+    if target.parse::<IpAddr>().is_ok() {
+        return Ok(target.to_string());
+    }
+
+    let valid_hostname = !target.is_empty()
+        && target.len() <= 253
+        && !target.starts_with('-')
+        && target
+            .split('.')
+            .all(|label| {
+                !label.is_empty()
+                    && label.len() <= 63
+                    && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            });
+
+    if valid_hostname {
+        Ok(target.to_string())
+    } else {
+        Err(format!("Invalid target '{}'", target))
+    }
+}
+
+fn run(command: &mut Command) -> Result<String, String> {
+    #[cfg(target_os = "windows")]
+    command.creation_flags(CREATE_NO_WINDOW);
+
+    let output = command.output().map_err(|e| e.to_string())?;
+    // ping 在目标不可达时返回非 0，仍然解析输出
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// ping 目标主机
+pub fn ping(target: &str, count: u32, timeout_ms: u64) -> Result<PingResult, String> {
+    let target = validate_target(target)?;
+    let count = count.clamp(1, MAX_PING_COUNT);
+    let timeout_ms = timeout_ms.clamp(100, 5000);
+
+    let mut command = Command::new("ping");
+    #[cfg(target_os = "windows")]
+    command.args([
+        "-n",
+        &count.to_string(),
+        "-w",
+        &timeout_ms.to_string(),
+        &target,
+    ]);
+    #[cfg(target_os = "linux")]
+    command.args([
+        "-c",
+        &count.to_string(),
+        "-W",
+        &timeout_ms.div_ceil(1000).to_string(),
+        &target,
+    ]);
+    #[cfg(target_os = "macos")]
+    command.args([
+        "-c",
+        &count.to_string(),
+        "-W",
+        &timeout_ms.to_string(),
+        &target,
+    ]);
+
+    let output = run(&mut command)?;
+
+    // 只统计带 TTL 的回复行
+    let times_ms: Vec<f32> = output
+        .lines()
+        .filter(|line| line.to_lowercase().contains("ttl="))
+        .filter_map(|line| {
+            RTT_PATTERN
+                .captures(line)
+                .and_then(|c| c.get(1))
+                .and_then(|m| m.as_str().parse().ok())
+        })
+        .collect();
+
+    let received = times_ms.len() as u32;
+    let min_ms = times_ms.iter().cloned().reduce(f32::min);
+    let max_ms = times_ms.iter().cloned().reduce(f32::max);
+    let avg_ms = if times_ms.is_empty() {
+        None
+    } else {
+        Some(times_ms.iter().sum::<f32>() / times_ms.len() as f32)
+    };
+
+    Ok(PingResult {
+        target,
+        sent: count,
+        received,
+        loss_percent: (count - received.min(count)) as f32 * 100.0 / count as f32,
+        min_ms,
+        avg_ms,
+        max_ms,
+        times_ms,
+    })
+}
+
+/// 追踪到目标主机的路由
+pub fn traceroute(target: &str, max_hops: u32) -> Result<TraceResult, String> {
+    let target = validate_target(target)?;
+    let max_hops = max_hops.clamp(1, MAX_HOPS);
+
+    #[cfg(target_os = "windows")]
+    let output = run(Command::new("tracert").args([
+        "-d",
+        "-h",
+        &max_hops.to_string(),
+        "-w",
+        "1000",
+        &target,
+    ]))?;
+
+    #[cfg(not(target_os = "windows"))]
+    let output = run(Command::new("traceroute").args([
+        "-n",
+        "-m",
+        &max_hops.to_string(),
+        "-w",
+        "1",
+        &target,
+    ]))?;
+
+    // 每跳一行，以跳数开头，例如：
+    //   1    <1 ms    <1 ms    <1 ms  192.168.1.1
+    //   1  192.168.1.1  0.412 ms  0.380 ms  0.361 ms
+    //   2  * * *
+    let hops = output
+        .lines()
+        .filter_map(|line| {
+            let mut tokens = line.split_whitespace();
+            let hop = tokens.next()?.parse::<u32>().ok()?;
+            let address = line
+                .split_whitespace()
+                .map(|t| t.trim_matches(|c| c == '[' || c == ']' || c == '(' || c == ')'))
+                .find(|t| t.parse::<IpAddr>().is_ok())
+                .map(str::to_string);
+            let rtt_ms = RTT_PATTERN
+                .captures_iter(line)
+                .filter_map(|c| c.get(1).and_then(|m| m.as_str().parse().ok()))
+                .collect();
+            Some(TraceHop {
+                hop,
+                address,
+                rtt_ms,
+            })
+        })
+        .collect();
+
+    Ok(TraceResult { target, hops })
+}
+
+/// 网速测试上传结果
+#[derive(Debug, Clone, Serialize)]
+pub struct SpeedTestUpload {
+    pub bytes: u64,
+    /// 服务端从开始接收到接收完成的耗时
+    pub duration_ms: u64,
+}
+
+/// 生成网速测试用的随机数据块，随机数据无法被压缩，测得的是实际吞吐量
+pub fn speedtest_chunk() -> Vec<u8> {
+    use rand::RngCore;
+    let mut chunk = vec![0u8; SPEEDTEST_CHUNK_SIZE];
+    rand::thread_rng().fill_bytes(&mut chunk);
+    chunk
+}
+
+/// 网络连接或监听端口
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectionEntry {
+    /// tcp / udp
+    pub protocol: String,
+    pub local_address: String,
+    pub local_port: u16,
+    /// 监听端口没有远端地址
+    pub remote_address: Option<String>,
+    pub remote_port: Option<u16>,
+    pub pid: Option<u32>,
+    pub process: Option<String>,
+}
+
+/// 监听端口和已建立的连接
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectionsInfo {
+    pub listening: Vec<ConnectionEntry>,
+    pub established: Vec<ConnectionEntry>,
+}
+
+/// 获取监听端口和已建立的连接，port 不为空时只返回本地或远端端口匹配的项
+pub fn get_connections(port: Option<u16>) -> Result<ConnectionsInfo, String> {
+    let (mut listening, mut established) = list_connections()?;
+
+    if let Some(port) = port {
+        listening.retain(|c| c.local_port == port);
+        established.retain(|c| c.local_port == port || c.remote_port == Some(port));
+    }
+    listening.sort_by(|a, b| (a.local_port, &a.protocol).cmp(&(b.local_port, &b.protocol)));
+    established.sort_by_key(|c| c.local_port);
+
+    Ok(ConnectionsInfo {
+        listening,
+        established,
+    })
+}
+
+/// 通过 tasklist 建立 PID 到进程名的映射
+#[cfg(target_os = "windows")]
+fn process_names() -> std::collections::HashMap<u32, String> {
+    // 格式："svchost.exe","1234","Services","0","12,345 K"
+    run(Command::new("tasklist").args(["/FO", "CSV", "/NH"]))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.trim().trim_matches('"').split("\",\"");
+            let name = fields.next()?.to_string();
+            let pid = fields.next()?.parse().ok()?;
+            Some((pid, name))
+        })
+        .collect()
+}
+
+/// 调用 GetExtendedTcpTable / GetExtendedUdpTable，缓冲区不足时按返回的大小重试
+#[cfg(target_os = "windows")]
+fn read_ip_table<F>(query: F) -> Result<Vec<u32>, String>
+where
+    F: Fn(Option<*mut std::ffi::c_void>, *mut u32) -> u32,
+{
+    const ERROR_INSUFFICIENT_BUFFER: u32 = 122;
+
+    let mut size = 0u32;
+    query(None, &mut size);
+    loop {
+        // 使用 u32 缓冲区保证表结构的对齐
+        let mut buffer = vec![0u32; size as usize / 4 + 1];
+        match query(Some(buffer.as_mut_ptr() as *mut _), &mut size) {
+            0 => return Ok(buffer),
+            ERROR_INSUFFICIENT_BUFFER => continue,
+            code => return Err(format!("Failed to read connection table (error {})", code)),
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn list_connections() -> Result<(Vec<ConnectionEntry>, Vec<ConnectionEntry>), String> {
+    use std::net::Ipv4Addr;
+    use windows::Win32::NetworkManagement::IpHelper::{
+        GetExtendedTcpTable, GetExtendedUdpTable, MIB_TCPTABLE_OWNER_PID, MIB_TCP_STATE_ESTAB,
+        MIB_TCP_STATE_LISTEN, MIB_UDPTABLE_OWNER_PID, TCP_TABLE_OWNER_PID_ALL,
+        UDP_TABLE_OWNER_PID,
+    };
+    use windows::Win32::Networking::WinSock::AF_INET;
+
+    let names = process_names();
+    let entry = |protocol: &str, local: (u32, u32), remote: Option<(u32, u32)>, pid: u32| {
+        // 地址和端口均为网络字节序
+        let address = |addr: u32| Ipv4Addr::from(u32::from_be(addr)).to_string();
+        let port = |port: u32| u16::from_be(port as u16);
+        ConnectionEntry {
+            protocol: protocol.to_string(),
+            local_address: address(local.0),
+            local_port: port(local.1),
+            remote_address: remote.map(|r| address(r.0)),
+            remote_port: remote.map(|r| port(r.1)),
+            pid: Some(pid),
+            process: names.get(&pid).cloned(),
+        }
+    };
+
+    let mut listening = Vec::new();
+    let mut established = Vec::new();
+
+    let tcp = read_ip_table(|table, size| unsafe {
+        GetExtendedTcpTable(table, size, false, AF_INET.0 as u32, TCP_TABLE_OWNER_PID_ALL, 0)
+    })?;
+    unsafe {
+        let table = &*(tcp.as_ptr() as *const MIB_TCPTABLE_OWNER_PID);
+        let rows = std::slice::from_raw_parts(table.table.as_ptr(), table.dwNumEntries as usize);
+        for row in rows {
+            let local = (row.dwLocalAddr, row.dwLocalPort);
+            if row.dwState == MIB_TCP_STATE_LISTEN.0 as u32 {
+                listening.push(entry("tcp", local, None, row.dwOwningPid));
+            } else if row.dwState == MIB_TCP_STATE_ESTAB.0 as u32 {
+                let remote = (row.dwRemoteAddr, row.dwRemotePort);
+                established.push(entry("tcp", local, Some(remote), row.dwOwningPid));
+            }
+        }
+    }
+
+    let udp = read_ip_table(|table, size| unsafe {
+        GetExtendedUdpTable(table, size, false, AF_INET.0 as u32, UDP_TABLE_OWNER_PID, 0)
+    })?;
+    unsafe {
+        let table = &*(udp.as_ptr() as *const MIB_UDPTABLE_OWNER_PID);
+        let rows = std::slice::from_raw_parts(table.table.as_ptr(), table.dwNumEntries as usize);
+        for row in rows {
+            listening.push(entry("udp", (row.dwLocalAddr, row.dwLocalPort), None, row.dwOwningPid));
+        }
+    }
+
+    Ok((listening, established))
+}
+
+#[cfg(target_os = "linux")]
+fn list_connections() -> Result<(Vec<ConnectionEntry>, Vec<ConnectionEntry>), String> {
+    static PROCESS_PATTERN: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r#"\("([^"]+)",pid=(\d+)"#).unwrap());
+
+    // 拆分 "0.0.0.0:22"、"[::]:22"、"*:68"、"127.0.0.53%lo:53"
+    fn split_endpoint(endpoint: &str) -> Option<(String, u16)> {
+        let (address, port) = endpoint.rsplit_once(':')?;
+        let address = address.trim_matches(|c| c == '[' || c == ']');
+        let address = address.split('%').next().unwrap_or(address);
+        Some((address.to_string(), port.parse().ok()?))
+    }
+
+    let output = Command::new("ss")
+        .args(["-tunapH"])
+        .output()
+        .map_err(|e| format!("Failed to run ss: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+
+    let mut listening = Vec::new();
+    let mut established = Vec::new();
+
+    // 格式：tcp LISTEN 0 128 0.0.0.0:22 0.0.0.0:* users:(("sshd",pid=812,fd=3))
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 6 {
+            continue;
+        }
+        let Some((local_address, local_port)) = split_endpoint(fields[4]) else {
+            continue;
+        };
+        let process = PROCESS_PATTERN.captures(line);
+        let mut entry = ConnectionEntry {
+            protocol: fields[0].to_string(),
+            local_address,
+            local_port,
+            remote_address: None,
+            remote_port: None,
+            pid: process
+                .as_ref()
+                .and_then(|c| c.get(2))
+                .and_then(|m| m.as_str().parse().ok()),
+            process: process
+                .as_ref()
+                .and_then(|c| c.get(1))
+                .map(|m| m.as_str().to_string()),
+        };
+
+        match (fields[0], fields[1]) {
+            ("tcp", "LISTEN") | ("udp", "UNCONN") => listening.push(entry),
+            ("tcp", "ESTAB") | ("udp", "ESTAB") => {
+                if let Some((address, port)) = split_endpoint(fields[5]) {
+                    entry.remote_address = Some(address);
+                    entry.remote_port = Some(port);
+                }
+                established.push(entry);
+            }
+            _ => {}
+        }
+    }
+
+    Ok((listening, established))
+}
+
+#[cfg(target_os = "macos")]
+fn list_connections() -> Result<(Vec<ConnectionEntry>, Vec<ConnectionEntry>), String> {
+    Err("Connection listing is not supported on macOS".to_string())
+}
@@ -0,0 +1,175 @@
+use qrcode::render::svg;
+use qrcode::QrCode;
+use serde::Serialize;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener};
+
+/// 首选端口被占用时向后尝试的端口数量
+const PORT_SEARCH_RANGE: u16 = 20;
+
+/// 配对二维码中链接的协议前缀
+const PAIRING_URI_SCHEME: &str = "landevice://pair";
+
+/// 首次运行向导需要的当前状态
+#[derive(Debug, Clone, Serialize)]
+pub struct OnboardingStatus {
+    pub password_set: bool,
+    /// 系统中是否已注册开机自启动
+    pub autostart_enabled: bool,
+    /// 配置中的开机自启动设置，与系统状态不一致时向导应提示
+    pub autostart_configured: bool,
+    pub api_port: u16,
+    pub server_running: bool,
+}
+
+/// 端口检查结果
+#[derive(Debug, Clone, Serialize)]
+pub struct PortCheck {
+    pub port: u16,
+    pub available: bool,
+    /// 正在运行的服务器已经使用该端口
+    pub in_use_by_server: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 供手机扫描的配对二维码
+#[derive(Debug, Clone, Serialize)]
+pub struct PairingQr {
+    /// 二维码内容，如 landevice://pair?host=192.168.1.10&port=8080&uuid=...&name=...
+    pub uri: String,
+    /// 二维码图像（SVG）
+    pub svg: String,
+    /// 二维码中使用的地址，界面可同时以文字显示
+    pub host: String,
+    pub port: u16,
+}
+
+/// 检查端口能否在配置的监听地址上绑定
+///
+/// server_port 为正在运行的服务器端口，该端口视为可用
+pub fn check_port(port: u16, server_port: Option<u16>) -> PortCheck {
+    if port == 0 {
+        return PortCheck {
+            port,
+            available: false,
+            in_use_by_server: false,
+            error: Some("Port must be between 1 and 65535".to_string()),
+        };
+    }
+    if server_port == Some(port) {
+        return PortCheck {
+            port,
+            available: true,
+            in_use_by_server: true,
+            error: None,
+        };
+    }
+
+    let bind_addr: IpAddr = crate::config::get_config()
+        .bind_addr
+        .parse()
+        .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+    // 绑定后立即释放，只用于判断端口是否被占用
+    match TcpListener::bind(SocketAddr::new(bind_addr, port)) {
+        Ok(_) => PortCheck {
+            port,
+            available: true,
+            in_use_by_server: false,
+            error: None,
+        },
+        Err(e) => PortCheck {
+            port,
+            available: false,
+            in_use_by_server: false,
+            error: Some(e.to_string()),
+        },
+    }
+}
+
+/// 从首选端口开始找一个可用端口
+pub fn suggest_port(preferred: u16, server_port: Option<u16>) -> Option<u16> {
+    let start = preferred.max(1);
+    (0..PORT_SEARCH_RANGE)
+        .filter_map(|offset| start.checked_add(offset))
+        .find(|port| check_port(*port, server_port).available)
+}
+
+/// 本机的局域网 IPv4 地址，私有网段在前
+fn lan_address() -> Option<Ipv4Addr> {
+    let mut addrs: Vec<Ipv4Addr> = if_addrs::get_if_addrs()
+        .ok()?
+        .into_iter()
+        .filter_map(|iface| match iface.addr {
+            if_addrs::IfAddr::V4(v4) if !v4.ip.is_loopback() && !v4.ip.is_link_local() => {
+                Some(v4.ip)
+            }
+            _ => None,
+        })
+        .collect();
+    addrs.sort_by_key(|ip| !ip.is_private());
+    addrs.into_iter().next()
+}
+
+/// 把参数值中的保留字符编码为 %XX
+fn encode_component(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// 生成配对二维码，手机扫描后即可添加本机，无需等待局域网发现
+pub fn pairing_qr(port: u16) -> Result<PairingQr, String> {
+    let host = lan_address()
+        .ok_or("No local network address found")?
+        .to_string();
+    let uuid = crate::device_id::DeviceId::get_or_create().map_err(|e| e.to_string())?;
+    let display_name = crate::config::get_config().device_display_name;
+    let name = if display_name.trim().is_empty() {
+        hostname::get()
+            .ok()
+            .and_then(|h| h.into_string().ok())
+            .unwrap_or_default()
+    } else {
+        display_name.trim().to_string()
+    };
+
+    let uri = format!(
+        "{}?host={}&port={}&uuid={}&name={}",
+        PAIRING_URI_SCHEME,
+        host,
+        port,
+        encode_component(&uuid),
+        encode_component(&name)
+    );
+    let code = QrCode::new(uri.as_bytes()).map_err(|e| e.to_string())?;
+    let svg = code
+        .render::<svg::Color>()
+        .min_dimensions(200, 200)
+        .quiet_zone(true)
+        .build();
+
+    Ok(PairingQr {
+        uri,
+        svg,
+        host,
+        port,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_reserved_characters() {
+        assert_eq!(encode_component("Living Room PC"), "Living%20Room%20PC");
+        assert_eq!(encode_component("a&b=c"), "a%26b%3Dc");
+        assert_eq!(encode_component("书房"), "%E4%B9%A6%E6%88%BF");
+    }
+}
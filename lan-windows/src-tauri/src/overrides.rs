@@ -0,0 +1,173 @@
+use once_cell::sync::Lazy;
+use std::net::IpAddr;
+
+use crate::config::AppConfig;
+
+/// 启动参数，命令行参数优先于环境变量，均优先于配置文件
+///
+/// 环境变量：LANDM_API_PORT、LANDM_BIND_ADDR、LANDM_LOG_LEVEL、LANDM_LOG_FILE、
+/// LANDM_AUTO_START_API、LANDM_HEADLESS
+///
+/// 命令行参数：--port <端口>、--bind <地址>、--log-level <级别>、--headless
+#[derive(Debug, Clone, Default)]
+pub struct StartupOverrides {
+    pub api_port: Option<u16>,
+    pub bind_addr: Option<IpAddr>,
+    pub log_level: Option<String>,
+    pub log_file_path: Option<String>,
+    pub auto_start_api: Option<bool>,
+    /// 无界面模式：不显示主窗口，启动后直接运行服务器
+    pub headless: bool,
+    /// 解析时遇到的无效参数，日志初始化后再输出
+    pub warnings: Vec<String>,
+}
+
+/// 启动时解析一次的覆盖参数
+pub static OVERRIDES: Lazy<StartupOverrides> = Lazy::new(|| {
+    let env = |key: &str| std::env::var(key).ok().filter(|v| !v.trim().is_empty());
+    let mut overrides = StartupOverrides::from_env(env);
+    overrides.apply_args(std::env::args().skip(1));
+    overrides
+});
+
+/// 解析布尔值，支持 1/0、true/false、yes/no、on/off
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+/// 校验日志级别
+fn parse_log_level(value: &str) -> Option<String> {
+    let level = value.trim().to_lowercase();
+    matches!(
+        level.as_str(),
+        "off" | "error" | "warn" | "info" | "debug" | "trace"
+    )
+    .then_some(level)
+}
+
+impl StartupOverrides {
+    fn from_env(env: impl Fn(&str) -> Option<String>) -> Self {
+        let mut warnings = Vec::new();
+        // 无效的值只记录警告，不影响启动
+        let parse = |key: &str| -> Option<(String, String)> {
+            env(key).map(|value| (key.to_string(), value))
+        };
+        let mut check = |entry: Option<(String, String)>, ok: bool| {
+            if let Some((key, value)) = entry {
+                if !ok {
+                    warnings.push(format!("Ignoring invalid {}: {}", key, value));
+                }
+            }
+        };
+
+        let port = parse("LANDM_API_PORT");
+        let api_port = port.as_ref().and_then(|(_, v)| v.trim().parse().ok());
+        check(port, api_port.is_some());
+
+        let bind = parse("LANDM_BIND_ADDR");
+        let bind_addr = bind.as_ref().and_then(|(_, v)| v.trim().parse().ok());
+        check(bind, bind_addr.is_some());
+
+        let level = parse("LANDM_LOG_LEVEL");
+        let log_level = level.as_ref().and_then(|(_, v)| parse_log_level(v));
+        check(level, log_level.is_some());
+
+        let auto_start = parse("LANDM_AUTO_START_API");
+        let auto_start_api = auto_start.as_ref().and_then(|(_, v)| parse_bool(v));
+        check(auto_start, auto_start_api.is_some());
+
+        let headless_var = parse("LANDM_HEADLESS");
+        let headless = headless_var.as_ref().and_then(|(_, v)| parse_bool(v));
+        check(headless_var, headless.is_some());
+
+        Self {
+            api_port,
+            bind_addr,
+            log_level,
+            log_file_path: env("LANDM_LOG_FILE"),
+            auto_start_api,
+            headless: headless.unwrap_or(false),
+            warnings,
+        }
+    }
+
+    fn apply_args(&mut self, args: impl Iterator<Item = String>) {
+        let mut args = args.peekable();
+        while let Some(arg) = args.next() {
+            // 同时支持 "--port 8080" 和 "--port=8080"
+            let (name, inline) = match arg.split_once('=') {
+                Some((name, value)) => (name.to_string(), Some(value.to_string())),
+                None => (arg, None),
+            };
+            let mut value = || inline.clone().or_else(|| args.next());
+
+            match name.as_str() {
+                "--port" => match value().and_then(|v| v.parse().ok()) {
+                    Some(port) => self.api_port = Some(port),
+                    None => self
+                        .warnings
+                        .push("Ignoring invalid --port value".to_string()),
+                },
+                "--bind" => match value().and_then(|v| v.parse().ok()) {
+                    Some(addr) => self.bind_addr = Some(addr),
+                    None => self
+                        .warnings
+                        .push("Ignoring invalid --bind value".to_string()),
+                },
+                "--log-level" => match value().as_deref().and_then(parse_log_level) {
+                    Some(level) => self.log_level = Some(level),
+                    None => self
+                        .warnings
+                        .push("Ignoring invalid --log-level value".to_string()),
+                },
+                "--headless" => self.headless = true,
+                // 其他参数（如开机自启动插件附加的参数）忽略
+                _ => {}
+            }
+        }
+    }
+
+    /// 将覆盖参数应用到配置
+    pub fn apply(&self, config: &mut AppConfig) {
+        if let Some(port) = self.api_port {
+            config.api_port = port;
+        }
+        if let Some(addr) = self.bind_addr {
+            config.bind_addr = addr.to_string();
+        }
+        if let Some(ref path) = self.log_file_path {
+            config.log_file_path = Some(path.clone());
+        }
+        if let Some(auto_start) = self.auto_start_api {
+            config.auto_start_api = auto_start;
+        }
+    }
+
+    /// 保存配置前还原被覆盖的字段，避免把临时参数写入配置文件
+    pub fn restore(&self, config: &mut AppConfig, on_disk: &AppConfig) {
+        if self.api_port.is_some() {
+            config.api_port = on_disk.api_port;
+        }
+        if self.bind_addr.is_some() {
+            config.bind_addr = on_disk.bind_addr.clone();
+        }
+        if self.log_file_path.is_some() {
+            config.log_file_path = on_disk.log_file_path.clone();
+        }
+        if self.auto_start_api.is_some() {
+            config.auto_start_api = on_disk.auto_start_api;
+        }
+    }
+
+    /// 是否有任何字段被覆盖
+    pub fn is_empty(&self) -> bool {
+        self.api_port.is_none()
+            && self.bind_addr.is_none()
+            && self.log_file_path.is_none()
+            && self.auto_start_api.is_none()
+    }
+}
@@ -0,0 +1,382 @@
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use uuid::Uuid;
+use zeroize::Zeroizing;
+
+use crate::spake2::Spake2;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// SPAKE2 中服务端一方的身份，客户端一方为其设备ID
+pub const SERVER_IDENTITY: &[u8] = b"lan-device-manager";
+/// 未完成的配对保留时间（秒）
+const PENDING_TTL_SECS: i64 = 120;
+/// 同时进行中的配对数量上限；每次确认可以猜测一次密码，猜测次数由失败锁定限制
+const MAX_PENDING: usize = 64;
+/// 同一客户端IP或设备ID连续失败多少次后锁定
+const MAX_FAILURES: u32 = 5;
+/// 锁定时间（秒），期间该IP或设备ID不能开始或确认配对
+const LOCKOUT_SECS: i64 = 300;
+/// 失败记录数量上限，超出时丢弃未锁定的记录
+const MAX_FAILURE_RECORDS: usize = 1024;
+
+/// 密钥确认消息：HMAC(配对密钥, 角色 + ":" + 配对ID)
+pub fn confirmation(key: &[u8], role: &str, pairing_id: &str) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC can take key of any size");
+    mac.update(role.as_bytes());
+    mac.update(b":");
+    mac.update(pairing_id.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// 已配对的客户端
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PairedDevice {
+    /// 配对密钥（十六进制）
+    key: String,
+    paired_at: DateTime<Utc>,
+    #[serde(default)]
+    last_used: Option<DateTime<Utc>>,
+}
+
+/// 配对信息，不含密钥，供界面显示
+#[derive(Debug, Clone, Serialize)]
+pub struct PairedDeviceInfo {
+    pub device_id: String,
+    pub paired_at: DateTime<Utc>,
+    pub last_used: Option<DateTime<Utc>>,
+}
+
+struct PendingPairing {
+    device_id: String,
+    /// 开始配对的客户端IP，确认失败时计入该IP
+    client: String,
+    key: Zeroizing<Vec<u8>>,
+    expires_at: DateTime<Utc>,
+}
+
+/// 按客户端IP或设备ID记录的连续失败次数
+#[derive(Default)]
+struct FailureRecord {
+    count: u32,
+    locked_until: Option<DateTime<Utc>>,
+}
+
+/// 失败记录的键，IP 和设备ID分别计数
+fn failure_keys(client: &str, device_id: &str) -> [String; 2] {
+    [format!("ip:{}", client), format!("device:{}", device_id)]
+}
+
+/// 基于 SPAKE2 的设备配对
+///
+/// 双方以登录验证器作为 SPAKE2 口令协商出每台设备独立的密钥，之后用该密钥代替密码登录。
+/// 与挑战-响应登录不同，窃听者拿到完整的配对过程也无法离线猜测密码
+pub struct PairingStore {
+    pending: Mutex<HashMap<String, PendingPairing>>,
+    paired: Mutex<HashMap<String, PairedDevice>>,
+    /// 配对失败记录，连续失败达到上限后锁定一段时间，限制在线猜测密码
+    failures: Mutex<HashMap<String, FailureRecord>>,
+    /// 保存位置，为空时只保存在内存中
+    path: Option<PathBuf>,
+}
+
+impl PairingStore {
+    pub fn load() -> Self {
+        let path = crate::config::AppConfig::config_path().with_file_name("paired_devices.json");
+        let paired = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self {
+            pending: Mutex::new(HashMap::new()),
+            paired: Mutex::new(paired),
+            failures: Mutex::new(HashMap::new()),
+            path: Some(path),
+        }
+    }
+
+    /// 只保存在内存中的配对记录，供测试使用
+    #[cfg(test)]
+    pub(crate) fn in_memory() -> Self {
+        Self {
+            pending: Mutex::new(HashMap::new()),
+            paired: Mutex::new(HashMap::new()),
+            failures: Mutex::new(HashMap::new()),
+            path: None,
+        }
+    }
+
+    fn save(&self, paired: &HashMap<String, PairedDevice>) {
+        let Some(ref path) = self.path else {
+            return;
+        };
+        let result = crate::config::AppConfig::ensure_config_dir().and_then(|_| {
+            let content = serde_json::to_string(paired).map_err(std::io::Error::other)?;
+            crate::config::write_atomic(path, content.as_bytes())
+        });
+        if let Err(e) = result {
+            log::error!("Failed to save paired devices: {}", e);
+        }
+    }
+
+    /// 任一键处于锁定期时返回错误
+    fn check_locked(&self, keys: &[String], now: DateTime<Utc>) -> Result<(), String> {
+        let failures = self.failures.lock().unwrap();
+        let locked_until = keys
+            .iter()
+            .filter_map(|key| failures.get(key)?.locked_until)
+            .filter(|until| *until > now)
+            .max();
+        match locked_until {
+            Some(until) => Err(format!(
+                "Too many failed pairing attempts, please try again in {} seconds",
+                (until - now).num_seconds().max(1)
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// 记录一次失败，连续失败达到 MAX_FAILURES 次时锁定
+    fn record_failure(&self, keys: &[String], now: DateTime<Utc>) {
+        let mut failures = self.failures.lock().unwrap();
+        if failures.len() >= MAX_FAILURE_RECORDS {
+            failures.retain(|_, record| record.locked_until.is_some_and(|until| until > now));
+        }
+        for key in keys {
+            let record = failures.entry(key.clone()).or_default();
+            if record.locked_until.is_some_and(|until| until <= now) {
+                *record = FailureRecord::default();
+            }
+            record.count += 1;
+            if record.count >= MAX_FAILURES {
+                record.count = 0;
+                record.locked_until = Some(now + Duration::seconds(LOCKOUT_SECS));
+                log::warn!("[Auth] Pairing locked for {} after repeated failures", key);
+            }
+        }
+    }
+
+    /// 处理客户端的 SPAKE2 消息，返回配对ID和服务端消息；client 为客户端IP
+    pub fn start(
+        &self,
+        verifier: &[u8],
+        device_id: &str,
+        client: &str,
+        message: &[u8],
+    ) -> Result<(String, Vec<u8>), String> {
+        if device_id.is_empty() {
+            return Err("Device ID is required for pairing".to_string());
+        }
+
+        let now = Utc::now();
+        let keys = failure_keys(client, device_id);
+        self.check_locked(&keys, now)?;
+        let mut pending = self.pending.lock().unwrap();
+        pending.retain(|_, p| p.expires_at > now);
+        if pending.len() >= MAX_PENDING {
+            return Err("Too many pairing attempts, please try again later".to_string());
+        }
+
+        let (spake, outbound) = Spake2::start_b(verifier, device_id.as_bytes(), SERVER_IDENTITY);
+        // 口令不一致时这里仍会得到密钥，由后续的密钥确认发现
+        let key = match spake.finish(message) {
+            Ok(key) => key,
+            Err(_) => {
+                drop(pending);
+                self.record_failure(&keys, now);
+                return Err("Invalid pairing message".to_string());
+            }
+        };
+
+        let pairing_id = Uuid::new_v4().to_string();
+        pending.insert(
+            pairing_id.clone(),
+            PendingPairing {
+                device_id: device_id.to_string(),
+                client: client.to_string(),
+                key: Zeroizing::new(key),
+                expires_at: now + Duration::seconds(PENDING_TTL_SECS),
+            },
+        );
+        Ok((pairing_id, outbound))
+    }
+
+    /// 校验客户端的密钥确认并保存配对，返回服务端的确认消息
+    ///
+    /// 配对无论成功与否都只能确认一次
+    pub fn confirm(&self, pairing_id: &str, proof: &[u8]) -> Result<(String, Vec<u8>), String> {
+        let pairing = self
+            .pending
+            .lock()
+            .unwrap()
+            .remove(pairing_id)
+            .filter(|p| p.expires_at > Utc::now())
+            .ok_or_else(|| "Invalid or expired pairing".to_string())?;
+
+        // 锁定前已经开始的配对同样不能再确认
+        let now = Utc::now();
+        let keys = failure_keys(&pairing.client, &pairing.device_id);
+        self.check_locked(&keys, now)?;
+
+        let mut mac =
+            HmacSha256::new_from_slice(&pairing.key).expect("HMAC can take key of any size");
+        mac.update(b"client:");
+        mac.update(pairing_id.as_bytes());
+        if mac.verify_slice(proof).is_err() {
+            self.record_failure(&keys, now);
+            return Err("Pairing failed: wrong password".to_string());
+        }
+        {
+            let mut failures = self.failures.lock().unwrap();
+            for key in &keys {
+                failures.remove(key);
+            }
+        }
+
+        let mut paired = self.paired.lock().unwrap();
+        paired.insert(
+            pairing.device_id.clone(),
+            PairedDevice {
+                key: hex::encode(&*pairing.key),
+                paired_at: Utc::now(),
+                last_used: None,
+            },
+        );
+        self.save(&paired);
+
+        Ok((
+            pairing.device_id,
+            confirmation(&pairing.key, "server", pairing_id),
+        ))
+    }
+
+    /// 设备的配对密钥
+    pub fn key(&self, device_id: &str) -> Option<Zeroizing<Vec<u8>>> {
+        let paired = self.paired.lock().unwrap();
+        paired
+            .get(device_id)
+            .and_then(|device| hex::decode(&device.key).ok())
+            .map(Zeroizing::new)
+    }
+
+    /// 记录配对密钥的使用时间
+    pub fn touch(&self, device_id: &str) {
+        let mut paired = self.paired.lock().unwrap();
+        if let Some(device) = paired.get_mut(device_id) {
+            device.last_used = Some(Utc::now());
+            self.save(&paired);
+        }
+    }
+
+    pub fn list(&self) -> Vec<PairedDeviceInfo> {
+        let paired = self.paired.lock().unwrap();
+        let mut devices: Vec<_> = paired
+            .iter()
+            .map(|(device_id, device)| PairedDeviceInfo {
+                device_id: device_id.clone(),
+                paired_at: device.paired_at,
+                last_used: device.last_used,
+            })
+            .collect();
+        devices.sort_by_key(|d| d.paired_at);
+        devices
+    }
+
+    /// 取消设备配对
+    pub fn remove(&self, device_id: &str) -> bool {
+        let mut paired = self.paired.lock().unwrap();
+        let removed = paired.remove(device_id).is_some();
+        if removed {
+            self.save(&paired);
+        }
+        removed
+    }
+
+    /// 取消全部配对（修改或清除密码时）
+    pub fn clear(&self) {
+        self.pending.lock().unwrap().clear();
+        let mut paired = self.paired.lock().unwrap();
+        if !paired.is_empty() {
+            paired.clear();
+            self.save(&paired);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client_start(password: &[u8], device_id: &str) -> (Spake2, Vec<u8>) {
+        Spake2::start_a(password, device_id.as_bytes(), SERVER_IDENTITY)
+    }
+
+    fn store() -> PairingStore {
+        PairingStore::in_memory()
+    }
+
+    /// 以错误的密码完成一次配对，返回确认结果
+    fn guess(store: &PairingStore, device_id: &str, client: &str) -> Result<(), String> {
+        let (spake, message) = client_start(b"guess", device_id);
+        let (pairing_id, reply) = store.start(b"verifier", device_id, client, &message)?;
+        let key = spake.finish(&reply).unwrap();
+        let proof = confirmation(&key, "client", &pairing_id);
+        store.confirm(&pairing_id, &proof).map(|_| ())
+    }
+
+    #[test]
+    fn wrong_password_fails_confirmation() {
+        let store = store();
+        let (client, message) = client_start(b"guess", "phone");
+        let (pairing_id, reply) = store.start(b"verifier", "phone", "10.0.0.2", &message).unwrap();
+        let key = client.finish(&reply).unwrap();
+        let proof = confirmation(&key, "client", &pairing_id);
+        assert!(store.confirm(&pairing_id, &proof).is_err());
+        assert!(store.key("phone").is_none());
+    }
+
+    #[test]
+    fn matching_password_pairs_once() {
+        let store = store();
+        let (client, message) = client_start(b"verifier", "phone");
+        let (pairing_id, reply) = store.start(b"verifier", "phone", "10.0.0.2", &message).unwrap();
+        let key = client.finish(&reply).unwrap();
+        let proof = confirmation(&key, "client", &pairing_id);
+        let (device_id, server_proof) = store.confirm(&pairing_id, &proof).unwrap();
+        assert_eq!(device_id, "phone");
+        assert_eq!(server_proof, confirmation(&key, "server", &pairing_id));
+        assert_eq!(store.key("phone").unwrap().as_slice(), key.as_slice());
+        // 同一个配对不能重复确认
+        assert!(store.confirm(&pairing_id, &proof).is_err());
+    }
+
+    #[test]
+    fn repeated_failures_lock_the_client_and_device() {
+        let store = store();
+        for _ in 0..MAX_FAILURES {
+            assert!(guess(&store, "phone", "10.0.0.2").is_err());
+        }
+        let error = guess(&store, "phone", "10.0.0.2").unwrap_err();
+        assert!(error.starts_with("Too many failed pairing attempts"));
+        // 换设备ID或换IP都不能绕过锁定
+        assert!(guess(&store, "tablet", "10.0.0.2").unwrap_err().starts_with("Too many"));
+        assert!(guess(&store, "phone", "10.0.0.3").unwrap_err().starts_with("Too many"));
+        assert!(guess(&store, "tablet", "10.0.0.3").unwrap_err().starts_with("Pairing failed"));
+    }
+
+    #[test]
+    fn invalid_start_messages_count_as_failures() {
+        let store = store();
+        for _ in 0..MAX_FAILURES {
+            assert!(store.start(b"verifier", "phone", "10.0.0.2", b"junk").is_err());
+        }
+        let (_, message) = client_start(b"verifier", "phone");
+        let error = store.start(b"verifier", "phone", "10.0.0.2", &message).unwrap_err();
+        assert!(error.starts_with("Too many failed pairing attempts"));
+    }
+}
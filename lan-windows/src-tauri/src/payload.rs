@@ -0,0 +1,558 @@
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use axum::body::{to_bytes, Body};
+use axum::extract::{Query, Request, State};
+use axum::http::header::{CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE};
+use axum::http::{HeaderValue, Method, StatusCode, Uri};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use hkdf::Hkdf;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+use crate::auth::AuthManager;
+
+/// 登录时协商的负载加密算法
+///
+/// v2 的请求只携带密钥ID，附加认证数据绑定方法、路径和请求计数；
+/// 请求 v1 的旧客户端不会协商加密，继续使用明文
+pub const PAYLOAD_CIPHER: &str = "aes-256-gcm-v2";
+/// HKDF 的 info 参数，区分用途和协议版本
+const HKDF_INFO: &[u8] = b"lan-device-manager payload v2";
+/// 附加认证数据的方向前缀，防止把请求密文当作响应重放
+const AAD_REQUEST: &[u8] = b"request";
+const AAD_RESPONSE: &[u8] = b"response";
+const AAD_REQUEST_BODY: &[u8] = b"request-body";
+const AAD_CLIENT_FRAME: &[u8] = b"client-frame";
+const AAD_SERVER_FRAME: &[u8] = b"server-frame";
+/// 加密的查询请求头，GET、DELETE 和二进制请求体的请求以它代替查询参数中的明文令牌
+///
+/// 值为 {"key_id": ..., "counter": ..., "payload": {...}} 形式的 JSON，明文见 EncryptedQuery
+pub const PAYLOAD_HEADER: &str = "x-payload-request";
+/// 加密响应中原始的 Content-Type，客户端解密后按它解析
+pub const PAYLOAD_CONTENT_TYPE_HEADER: &str = "x-payload-content-type";
+/// 重放窗口的大小：并发请求可能乱序到达，窗口内未使用过的较小计数仍然接受
+const REPLAY_WINDOW: u64 = 64;
+
+pub type PayloadKey = Zeroizing<[u8; 32]>;
+
+/// 加密后的请求或响应体
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptedPayload {
+    /// 12 字节随机 nonce（十六进制）
+    pub nonce: String,
+    /// 密文和认证标签（十六进制）
+    pub ciphertext: String,
+}
+
+/// 加密请求：{"key_id": ..., "counter": ..., "payload": {...}}，令牌在密文中
+#[derive(Debug, Deserialize)]
+struct EncryptedRequest {
+    key_id: String,
+    /// 客户端为每个请求递增的计数，从 1 开始
+    counter: u64,
+    payload: EncryptedPayload,
+}
+
+/// 加密请求头的明文
+#[derive(Debug, Deserialize)]
+struct EncryptedQuery {
+    /// 原始查询字符串，包含令牌
+    query: String,
+    /// 请求体是否为 EncryptedPayload 形式的密文，网速测试等无需保密的数据可以明文发送
+    #[serde(default)]
+    encrypted_body: bool,
+}
+
+/// 查询参数中的令牌
+#[derive(Debug, Deserialize)]
+struct QueryToken {
+    token: Option<String>,
+}
+
+/// 通过加密请求头认证的请求所属的会话，WebSocket 升级后用它加密帧
+#[derive(Clone)]
+pub struct PayloadSession {
+    pub key_id: String,
+    pub key: PayloadKey,
+}
+
+/// handler 在响应中加入此扩展时不加密响应体，用于网速测试的随机数据
+#[derive(Debug, Clone, Copy)]
+pub struct PlainResponse;
+
+/// 已接受的请求计数：最大值以及它之前 REPLAY_WINDOW 个计数的使用情况
+#[derive(Debug, Default)]
+pub struct ReplayWindow {
+    max: u64,
+    /// 第 i 位表示计数 max - i 已被使用
+    seen: u64,
+}
+
+impl ReplayWindow {
+    /// 计数未被使用过且不早于窗口时接受并记录
+    pub fn accept(&mut self, counter: u64) -> bool {
+        if counter == 0 {
+            return false;
+        }
+        if counter > self.max {
+            let shift = counter - self.max;
+            self.seen = if shift >= REPLAY_WINDOW { 0 } else { self.seen << shift };
+            self.seen |= 1;
+            self.max = counter;
+            return true;
+        }
+        let offset = self.max - counter;
+        if offset >= REPLAY_WINDOW || self.seen & (1 << offset) != 0 {
+            return false;
+        }
+        self.seen |= 1 << offset;
+        true
+    }
+}
+
+/// 附加认证数据：方向、方法、路径和请求计数，密文不能用于其他接口或重复提交
+fn aad(direction: &[u8], method: &Method, path: &str, counter: u64) -> Vec<u8> {
+    let mut aad = direction.to_vec();
+    aad.extend_from_slice(format!(" {} {} {}", method, path, counter).as_bytes());
+    aad
+}
+
+/// 从登录验证器和本次登录的挑战派生会话密钥
+///
+/// 挑战只使用一次，因此每次登录得到的密钥都不同
+pub fn derive_key(verifier: &[u8], challenge: &str) -> PayloadKey {
+    let hkdf = Hkdf::<Sha256>::new(Some(challenge.as_bytes()), verifier);
+    let mut key = Zeroizing::new([0u8; 32]);
+    hkdf.expand(HKDF_INFO, key.as_mut())
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    key
+}
+
+fn encrypt(key: &PayloadKey, aad: &[u8], plaintext: &[u8]) -> Result<EncryptedPayload, String> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key.as_ref()));
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext,
+                aad,
+            },
+        )
+        .map_err(|_| "Failed to encrypt payload".to_string())?;
+    Ok(EncryptedPayload {
+        nonce: hex::encode(nonce),
+        ciphertext: hex::encode(ciphertext),
+    })
+}
+
+fn decrypt(key: &PayloadKey, aad: &[u8], payload: &EncryptedPayload) -> Result<Vec<u8>, String> {
+    let nonce = hex::decode(&payload.nonce).map_err(|_| "Invalid nonce".to_string())?;
+    if nonce.len() != 12 {
+        return Err("Invalid nonce".to_string());
+    }
+    let ciphertext =
+        hex::decode(&payload.ciphertext).map_err(|_| "Invalid ciphertext".to_string())?;
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key.as_ref()));
+    cipher
+        .decrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: &ciphertext,
+                aad,
+            },
+        )
+        .map_err(|_| "Failed to decrypt payload".to_string())
+}
+
+/// 加密会话中单个方向的 WebSocket 帧，帧序号从 1 开始逐帧递增
+///
+/// 文本帧的明文为 JSON 消息，加密后为 {"payload": {...}}；二进制帧加密后为 12 字节 nonce
+/// 接密文。附加认证数据绑定方向、路径和帧序号，帧被重放、调换或丢弃时解密失败
+pub struct FrameCipher {
+    key: PayloadKey,
+    direction: &'static [u8],
+    path: String,
+    sequence: u64,
+}
+
+impl FrameCipher {
+    /// 服务端发出的帧
+    pub fn outgoing(session: &PayloadSession, path: &str) -> Self {
+        Self::new(session, AAD_SERVER_FRAME, path)
+    }
+
+    /// 客户端发来的帧
+    pub fn incoming(session: &PayloadSession, path: &str) -> Self {
+        Self::new(session, AAD_CLIENT_FRAME, path)
+    }
+
+    fn new(session: &PayloadSession, direction: &'static [u8], path: &str) -> Self {
+        Self {
+            key: session.key.clone(),
+            direction,
+            path: path.to_string(),
+            sequence: 0,
+        }
+    }
+
+    fn next_aad(&mut self) -> Vec<u8> {
+        self.sequence += 1;
+        aad(self.direction, &Method::GET, &self.path, self.sequence)
+    }
+
+    pub fn seal_text(&mut self, text: &str) -> Result<String, String> {
+        let aad = self.next_aad();
+        let payload = encrypt(&self.key, &aad, text.as_bytes())?;
+        Ok(serde_json::json!({ "payload": payload }).to_string())
+    }
+
+    pub fn open_text(&mut self, text: &str) -> Result<String, String> {
+        #[derive(Deserialize)]
+        struct Frame {
+            payload: EncryptedPayload,
+        }
+        let frame: Frame =
+            serde_json::from_str(text).map_err(|_| "Invalid encrypted frame".to_string())?;
+        let aad = self.next_aad();
+        let plaintext = decrypt(&self.key, &aad, &frame.payload)?;
+        String::from_utf8(plaintext).map_err(|_| "Invalid encrypted frame".to_string())
+    }
+
+    pub fn seal_binary(&mut self, data: &[u8]) -> Result<Vec<u8>, String> {
+        let aad = self.next_aad();
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(self.key.as_ref()));
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: data,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| "Failed to encrypt payload".to_string())?;
+        let mut frame = nonce.to_vec();
+        frame.extend_from_slice(&ciphertext);
+        Ok(frame)
+    }
+
+    pub fn open_binary(&mut self, frame: &[u8]) -> Result<Vec<u8>, String> {
+        if frame.len() < 12 {
+            return Err("Invalid encrypted frame".to_string());
+        }
+        let (nonce, ciphertext) = frame.split_at(12);
+        let aad = self.next_aad();
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(self.key.as_ref()));
+        cipher
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| "Failed to decrypt payload".to_string())
+    }
+}
+
+fn rejection(message: &str) -> Response {
+    axum::Json(serde_json::json!({
+        "success": false,
+        "data": null,
+        "error": message,
+    }))
+    .into_response()
+}
+
+/// 解密 {"key_id": ..., "counter": ..., "payload": {...}} 形式的请求，并加密对应的响应
+///
+/// 未协商加密的令牌的明文请求原样通过；协商了加密的令牌不再接受明文请求（包括查询参数中的令牌），
+/// 密钥ID未协商、计数重复或密文中的令牌与密钥ID不符时拒绝。不带 JSON 请求体的请求把查询参数
+/// 加密后放在 PAYLOAD_HEADER 请求头中
+pub async fn payload_middleware(
+    State(auth_manager): State<AuthManager>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if request.headers().contains_key(PAYLOAD_HEADER) {
+        return encrypted_query(&auth_manager, request, next).await;
+    }
+    let query_token = Query::<QueryToken>::try_from_uri(request.uri())
+        .ok()
+        .and_then(|query| query.0.token);
+    if query_token.is_some_and(|t| auth_manager.requires_payload_encryption(&t)) {
+        log::warn!("[Security] Rejected plaintext query token for an encrypted session");
+        return rejection("This session requires encrypted requests");
+    }
+
+    let is_json = request
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.starts_with("application/json"));
+    if request.method() != Method::POST || !is_json {
+        return next.run(request).await;
+    }
+
+    let limit = crate::config::get_config().http_max_body_bytes;
+    let (mut parts, body) = request.into_parts();
+    let bytes = match to_bytes(body, limit).await {
+        Ok(bytes) => bytes,
+        Err(_) => return rejection("Request body too large"),
+    };
+    let Ok(envelope) = serde_json::from_slice::<EncryptedRequest>(&bytes) else {
+        let token = serde_json::from_slice::<serde_json::Value>(&bytes)
+            .ok()
+            .and_then(|json| json.get("token")?.as_str().map(str::to_string));
+        if token.is_some_and(|t| auth_manager.requires_payload_encryption(&t)) {
+            log::warn!("[Security] Rejected plaintext request for an encrypted session");
+            return rejection("This session requires encrypted requests");
+        }
+        return next
+            .run(Request::from_parts(parts, Body::from(bytes)))
+            .await;
+    };
+
+    let Some(key) = auth_manager.payload_key(&envelope.key_id) else {
+        return rejection("Payload encryption was not negotiated for this token");
+    };
+    let method = parts.method.clone();
+    let path = parts.uri.path().to_string();
+    let request_aad = aad(AAD_REQUEST, &method, &path, envelope.counter);
+    let plaintext = match decrypt(&key, &request_aad, &envelope.payload) {
+        Ok(plaintext) => plaintext,
+        Err(e) => {
+            log::warn!("[Security] Rejected encrypted request: {}", e);
+            return rejection(&e);
+        }
+    };
+    // 计数在解密成功后才记录，伪造的请求不能占用计数
+    if !auth_manager.accept_payload_counter(&envelope.key_id, envelope.counter) {
+        log::warn!("[Security] Rejected replayed request to {}", path);
+        return rejection("Request has already been used");
+    }
+    // 解密后的请求体需要是 JSON 对象，其中的令牌必须属于该密钥
+    let inner = match serde_json::from_slice::<serde_json::Value>(&plaintext) {
+        Ok(serde_json::Value::Object(inner)) => inner,
+        _ => return rejection("Encrypted payload must be a JSON object"),
+    };
+    let token = inner.get("token").and_then(|t| t.as_str());
+    if !token_matches_key(&auth_manager, token, &envelope.key_id) {
+        return rejection("Invalid or expired token");
+    }
+    parts.headers.remove(CONTENT_LENGTH);
+    let request = Request::from_parts(
+        parts,
+        Body::from(serde_json::Value::Object(inner).to_string()),
+    );
+
+    let response = next.run(request).await;
+    encrypt_response(&key, &method, &path, envelope.counter, response).await
+}
+
+/// 处理带 PAYLOAD_HEADER 请求头的请求：解密查询参数（和请求体）后交给内部处理，并加密响应
+async fn encrypted_query(auth_manager: &AuthManager, request: Request, next: Next) -> Response {
+    let (mut parts, body) = request.into_parts();
+    let Some(envelope) = parts
+        .headers
+        .get(PAYLOAD_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| serde_json::from_str::<EncryptedRequest>(v).ok())
+    else {
+        return rejection("Invalid encrypted request header");
+    };
+    let Some(key) = auth_manager.payload_key(&envelope.key_id) else {
+        return rejection("Payload encryption was not negotiated for this token");
+    };
+    let method = parts.method.clone();
+    let path = parts.uri.path().to_string();
+    let request_aad = aad(AAD_REQUEST, &method, &path, envelope.counter);
+    let query = match decrypt(&key, &request_aad, &envelope.payload) {
+        Ok(plaintext) => match serde_json::from_slice::<EncryptedQuery>(&plaintext) {
+            Ok(query) => query,
+            Err(_) => return rejection("Invalid encrypted request header"),
+        },
+        Err(e) => {
+            log::warn!("[Security] Rejected encrypted request: {}", e);
+            return rejection(&e);
+        }
+    };
+    if !auth_manager.accept_payload_counter(&envelope.key_id, envelope.counter) {
+        log::warn!("[Security] Rejected replayed request to {}", path);
+        return rejection("Request has already been used");
+    }
+    let Ok(uri) = format!("{}?{}", path, query.query).parse::<Uri>() else {
+        return rejection("Invalid encrypted request header");
+    };
+    let token = Query::<QueryToken>::try_from_uri(&uri)
+        .ok()
+        .and_then(|query| query.0.token);
+    if !token_matches_key(auth_manager, token.as_deref(), &envelope.key_id) {
+        return rejection("Invalid or expired token");
+    }
+    parts.uri = uri;
+    parts.headers.remove(PAYLOAD_HEADER);
+
+    let body = if query.encrypted_body {
+        // 十六进制密文约为明文的两倍，解密后的请求体仍受各接口的大小限制
+        let config = crate::config::get_config();
+        let limit = config
+            .http_max_body_bytes
+            .max(config.print_max_upload_bytes)
+            .saturating_mul(2)
+            .saturating_add(1024);
+        let bytes = match to_bytes(body, limit).await {
+            Ok(bytes) => bytes,
+            Err(_) => return rejection("Request body too large"),
+        };
+        let Ok(payload) = serde_json::from_slice::<EncryptedPayload>(&bytes) else {
+            return rejection("Invalid encrypted request body");
+        };
+        let body_aad = aad(AAD_REQUEST_BODY, &method, &path, envelope.counter);
+        match decrypt(&key, &body_aad, &payload) {
+            Ok(plaintext) => {
+                parts.headers.remove(CONTENT_LENGTH);
+                Body::from(plaintext)
+            }
+            Err(e) => return rejection(&e),
+        }
+    } else {
+        body
+    };
+    parts.extensions.insert(PayloadSession {
+        key_id: envelope.key_id.clone(),
+        key: key.clone(),
+    });
+
+    let response = next.run(Request::from_parts(parts, body)).await;
+    // 升级为 WebSocket 的连接改为逐帧加密，304 响应没有响应体
+    if matches!(
+        response.status(),
+        StatusCode::SWITCHING_PROTOCOLS | StatusCode::NOT_MODIFIED
+    ) || response.extensions().get::<PlainResponse>().is_some()
+    {
+        return response;
+    }
+    encrypt_response(&key, &method, &path, envelope.counter, response).await
+}
+
+/// 令牌有效且属于该密钥ID
+fn token_matches_key(auth_manager: &AuthManager, token: Option<&str>, key_id: &str) -> bool {
+    token
+        .and_then(|t| auth_manager.decode_token(t))
+        .is_some_and(|claims| claims.jti == key_id)
+}
+
+/// 加密响应体，原始的 Content-Type 放在 PAYLOAD_CONTENT_TYPE_HEADER 中
+async fn encrypt_response(
+    key: &PayloadKey,
+    method: &Method,
+    path: &str,
+    counter: u64,
+    response: Response,
+) -> Response {
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(_) => return rejection("Failed to read response"),
+    };
+    let response_aad = aad(AAD_RESPONSE, method, path, counter);
+    match encrypt(key, &response_aad, &bytes) {
+        Ok(payload) => {
+            parts.headers.remove(CONTENT_LENGTH);
+            parts.headers.remove(CONTENT_ENCODING);
+            if let Some(content_type) = parts.headers.remove(CONTENT_TYPE) {
+                parts
+                    .headers
+                    .insert(PAYLOAD_CONTENT_TYPE_HEADER, content_type);
+            }
+            parts
+                .headers
+                .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+            let body = serde_json::json!({ "payload": payload }).to_string();
+            Response::from_parts(parts, Body::from(body))
+        }
+        Err(e) => rejection(&e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_and_direction_binding() {
+        let key = derive_key(b"verifier", "challenge");
+        let payload = encrypt(&key, AAD_REQUEST, b"{\"command\":\"ipconfig\"}").unwrap();
+        assert_eq!(
+            decrypt(&key, AAD_REQUEST, &payload).unwrap(),
+            b"{\"command\":\"ipconfig\"}"
+        );
+        // 请求密文不能被当作响应解密
+        assert!(decrypt(&key, AAD_RESPONSE, &payload).is_err());
+        // 不同挑战派生出不同的密钥
+        let other = derive_key(b"verifier", "other challenge");
+        assert!(decrypt(&other, AAD_REQUEST, &payload).is_err());
+    }
+
+    #[test]
+    fn aad_binds_method_path_and_counter() {
+        let key = derive_key(b"verifier", "challenge");
+        let sent = aad(AAD_REQUEST, &Method::POST, "/api/system/lock", 1);
+        let payload = encrypt(&key, &sent, b"{}").unwrap();
+        assert!(decrypt(&key, &sent, &payload).is_ok());
+        for other in [
+            aad(AAD_REQUEST, &Method::POST, "/api/system/shutdown", 1),
+            aad(AAD_REQUEST, &Method::POST, "/api/system/lock", 2),
+            aad(AAD_REQUEST, &Method::PUT, "/api/system/lock", 1),
+        ] {
+            assert!(decrypt(&key, &other, &payload).is_err());
+        }
+    }
+
+    #[test]
+    fn frames_must_arrive_in_order() {
+        let session = PayloadSession {
+            key_id: "session".to_string(),
+            key: derive_key(b"verifier", "challenge"),
+        };
+        let mut sender = FrameCipher::outgoing(&session, "/ws");
+        let mut receiver = FrameCipher::outgoing(&session, "/ws");
+        let first = sender.seal_text("{\"type\":\"pong\"}").unwrap();
+        let second = sender.seal_text("{\"type\":\"ping\"}").unwrap();
+        assert_eq!(receiver.open_text(&first).unwrap(), "{\"type\":\"pong\"}");
+        assert_eq!(receiver.open_text(&second).unwrap(), "{\"type\":\"ping\"}");
+        // 重放的帧序号不符
+        assert!(receiver.open_text(&second).is_err());
+        // 服务端发出的帧不能当作客户端的帧
+        let mut incoming = FrameCipher::incoming(&session, "/ws");
+        assert!(incoming.open_text(&first).is_err());
+
+        // 二进制帧与文本帧共用同一方向的帧序号
+        let mut sender = FrameCipher::outgoing(&session, "/ws/shell");
+        let mut receiver = FrameCipher::outgoing(&session, "/ws/shell");
+        let output = sender.seal_binary(b"PS C:\\> ").unwrap();
+        let exit = sender.seal_text("{\"type\":\"exit\"}").unwrap();
+        assert_eq!(receiver.open_binary(&output).unwrap(), b"PS C:\\> ");
+        assert_eq!(receiver.open_text(&exit).unwrap(), "{\"type\":\"exit\"}");
+        assert!(receiver.open_binary(&output[..8]).is_err());
+    }
+
+    #[test]
+    fn replay_window_rejects_reused_counters() {
+        let mut window = ReplayWindow::default();
+        assert!(!window.accept(0));
+        assert!(window.accept(2));
+        assert!(!window.accept(2));
+        // 乱序到达的较小计数仍然接受一次
+        assert!(window.accept(1));
+        assert!(!window.accept(1));
+        assert!(window.accept(2 + REPLAY_WINDOW));
+        // 落在窗口之外的计数一律拒绝
+        assert!(!window.accept(2));
+        assert!(window.accept(3));
+    }
+}
@@ -0,0 +1,213 @@
+use serde::Serialize;
+use std::process::Command;
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+/// USB 设备
+#[derive(Debug, Clone, Serialize)]
+pub struct UsbDevice {
+    pub name: String,
+    /// 设备标识（Windows 为实例ID，Linux 为 VID:PID）
+    pub id: Option<String>,
+    pub status: Option<String>,
+}
+
+/// 蓝牙设备
+#[derive(Debug, Clone, Serialize)]
+pub struct BluetoothDevice {
+    pub name: String,
+    pub address: Option<String>,
+    /// 是否已连接，无法判断时为 None
+    pub connected: Option<bool>,
+}
+
+/// 外设清单
+#[derive(Debug, Clone, Serialize)]
+pub struct PeripheralsInfo {
+    pub usb: Vec<UsbDevice>,
+    pub bluetooth: Vec<BluetoothDevice>,
+}
+
+/// 获取已连接的 USB 设备和已配对/已连接的蓝牙设备
+pub fn get_peripherals() -> PeripheralsInfo {
+    PeripheralsInfo {
+        usb: list_usb_devices(),
+        bluetooth: list_bluetooth_devices(),
+    }
+}
+
+/// 通过 Get-PnpDevice（SetupAPI）查询当前存在的设备
+#[cfg(target_os = "windows")]
+fn query_pnp_devices(filter: &str) -> Vec<serde_json::Value> {
+    let script = format!(
+        "Get-PnpDevice -PresentOnly | Where-Object {{ {} }} | Select-Object FriendlyName,InstanceId,Status | ConvertTo-Json -Compress",
+        filter
+    );
+    let output = match Command::new("powershell")
+        .args(["-NoProfile", "-Command", &script])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+    {
+        Ok(output) => output,
+        Err(e) => {
+            log::warn!("Failed to query PnP devices: {}", e);
+            return Vec::new();
+        }
+    };
+
+    let text = String::from_utf8_lossy(&output.stdout);
+    // 只有一个结果时 ConvertTo-Json 输出对象而不是数组
+    match serde_json::from_str::<serde_json::Value>(text.trim()) {
+        Ok(serde_json::Value::Array(items)) => items,
+        Ok(item @ serde_json::Value::Object(_)) => vec![item],
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn list_usb_devices() -> Vec<UsbDevice> {
+    query_pnp_devices("$_.InstanceId -like 'USB\\*' -and $_.FriendlyName")
+        .iter()
+        .filter_map(|item| {
+            Some(UsbDevice {
+                name: item.get("FriendlyName")?.as_str()?.to_string(),
+                id: item.get("InstanceId").and_then(|v| v.as_str()).map(str::to_string),
+                status: item.get("Status").and_then(|v| v.as_str()).map(str::to_string),
+            })
+        })
+        .collect()
+}
+
+#[cfg(target_os = "windows")]
+fn list_bluetooth_devices() -> Vec<BluetoothDevice> {
+    // 已配对的设备实例ID形如 BTHENUM\...\..._AABBCCDDEEFF 或 BTHLE\DEV_AABBCCDDEEFF\...
+    query_pnp_devices("$_.Class -eq 'Bluetooth' -and $_.InstanceId -match 'DEV_[0-9A-F]{12}'")
+        .iter()
+        .filter_map(|item| {
+            let instance_id = item.get("InstanceId")?.as_str()?;
+            let address = instance_id
+                .split(['\\', '_', '&'])
+                .find(|part| part.len() == 12 && part.chars().all(|c| c.is_ascii_hexdigit()))
+                .map(|mac| {
+                    mac.as_bytes()
+                        .chunks(2)
+                        .map(|pair| String::from_utf8_lossy(pair).to_string())
+                        .collect::<Vec<_>>()
+                        .join(":")
+                });
+            Some(BluetoothDevice {
+                name: item.get("FriendlyName")?.as_str()?.to_string(),
+                address,
+                connected: item.get("Status").and_then(|v| v.as_str()).map(|s| s == "OK"),
+            })
+        })
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+fn list_usb_devices() -> Vec<UsbDevice> {
+    // 格式：Bus 001 Device 002: ID 8087:0024 Intel Corp. Integrated Rate Matching Hub
+    Command::new("lsusb")
+        .output()
+        .ok()
+        .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let rest = line.split(" ID ").nth(1)?;
+            let (id, name) = rest.split_once(' ').unwrap_or((rest, ""));
+            Some(UsbDevice {
+                name: if name.trim().is_empty() {
+                    id.to_string()
+                } else {
+                    name.trim().to_string()
+                },
+                id: Some(id.to_string()),
+                status: None,
+            })
+        })
+        .collect()
+}
+
+/// 解析 bluetoothctl 设备列表，格式：Device AA:BB:CC:DD:EE:FF Name
+#[cfg(target_os = "linux")]
+fn bluetoothctl_devices(args: &[&str]) -> Option<Vec<(String, String)>> {
+    let output = Command::new("bluetoothctl").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| {
+                let rest = line.trim().strip_prefix("Device ")?;
+                let (address, name) = rest.split_once(' ').unwrap_or((rest, rest));
+                Some((address.to_string(), name.to_string()))
+            })
+            .collect(),
+    )
+}
+
+#[cfg(target_os = "linux")]
+fn list_bluetooth_devices() -> Vec<BluetoothDevice> {
+    // 旧版 bluetoothctl 不支持 "devices Paired"，回退到 paired-devices
+    let paired = bluetoothctl_devices(&["devices", "Paired"])
+        .or_else(|| bluetoothctl_devices(&["paired-devices"]))
+        .unwrap_or_default();
+    let connected = bluetoothctl_devices(&["devices", "Connected"]);
+
+    paired
+        .into_iter()
+        .map(|(address, name)| BluetoothDevice {
+            connected: connected
+                .as_ref()
+                .map(|list| list.iter().any(|(a, _)| *a == address)),
+            name,
+            address: Some(address),
+        })
+        .collect()
+}
+
+#[cfg(target_os = "macos")]
+fn list_usb_devices() -> Vec<UsbDevice> {
+    fn collect(items: &[serde_json::Value], devices: &mut Vec<UsbDevice>) {
+        for item in items {
+            if let Some(name) = item.get("_name").and_then(|v| v.as_str()) {
+                if item.get("vendor_id").is_some() {
+                    devices.push(UsbDevice {
+                        name: name.to_string(),
+                        id: item.get("product_id").and_then(|v| v.as_str()).map(str::to_string),
+                        status: None,
+                    });
+                }
+            }
+            if let Some(children) = item.get("_items").and_then(|v| v.as_array()) {
+                collect(children, devices);
+            }
+        }
+    }
+
+    let mut devices = Vec::new();
+    let value = Command::new("system_profiler")
+        .args(["SPUSBDataType", "-json"])
+        .output()
+        .ok()
+        .and_then(|o| serde_json::from_slice::<serde_json::Value>(&o.stdout).ok());
+    if let Some(items) = value
+        .as_ref()
+        .and_then(|v| v.get("SPUSBDataType"))
+        .and_then(|v| v.as_array())
+    {
+        collect(items, &mut devices);
+    }
+    devices
+}
+
+#[cfg(target_os = "macos")]
+fn list_bluetooth_devices() -> Vec<BluetoothDevice> {
+    Vec::new()
+}
@@ -0,0 +1,257 @@
+use chrono::{DateTime, Local};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use crate::config::{AppConfig, ClientPermissions};
+
+/// 保留的最近判定数量
+const DECISION_LOG_SIZE: usize = 200;
+
+/// 决定命令能否执行的规则
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyRule {
+    /// 命令在白名单中，客户端权限也允许
+    Allowed,
+    /// 客户端权限缺少所需的功能范围
+    MissingScope,
+    /// 客户端权限不允许执行自定义命令
+    ClientCustomCommandsDenied,
+    /// 命令不在客户端权限的 allowed_commands 中
+    ClientCommandNotAllowed,
+    /// 白名单未启用 "custom" 总开关
+    CustomCommandsDisabled,
+    /// 命令不在白名单中
+    NotWhitelisted,
+}
+
+/// 一次命令判定的结果，拒绝时随响应的 error_details 返回
+#[derive(Debug, Clone, Serialize)]
+pub struct PolicyDecision {
+    pub command: String,
+    pub allowed: bool,
+    pub rule: PolicyRule,
+    /// 缺少的功能范围
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub missing_scope: Option<String>,
+    /// 决定结果的配置项，修改它即可改变判定
+    pub config_key: String,
+    pub reason: String,
+}
+
+impl PolicyDecision {
+    fn new(command: &str, rule: PolicyRule, config_key: String, reason: String) -> Self {
+        Self {
+            command: command.to_string(),
+            allowed: rule == PolicyRule::Allowed,
+            rule,
+            missing_scope: None,
+            config_key,
+            reason,
+        }
+    }
+}
+
+/// 判定命令能否执行：先检查客户端权限，再检查命令白名单
+///
+/// client 为令牌适用的权限及其配置项（见 AuthManager::permission_source），None 表示不受限制
+pub fn evaluate_command(
+    command: &str,
+    client: Option<(&str, &ClientPermissions)>,
+    config: &AppConfig,
+) -> PolicyDecision {
+    let is_custom = config.custom_commands.iter().any(|c| c == command);
+
+    if let Some((source, permissions)) = client {
+        if !permissions.allows_scope("commands") {
+            let mut decision = PolicyDecision::new(
+                command,
+                PolicyRule::MissingScope,
+                format!("{}.scopes", source),
+                format!("Permission denied: {} lacks the 'commands' scope", source),
+            );
+            decision.missing_scope = Some("commands".to_string());
+            return decision;
+        }
+        if is_custom && !permissions.allow_custom_commands {
+            return PolicyDecision::new(
+                command,
+                PolicyRule::ClientCustomCommandsDenied,
+                format!("{}.allow_custom_commands", source),
+                format!(
+                    "Permission denied: {} does not allow custom commands",
+                    source
+                ),
+            );
+        }
+        if !permissions.allowed_commands.is_empty()
+            && !permissions.allowed_commands.iter().any(|c| c == command)
+        {
+            return PolicyDecision::new(
+                command,
+                PolicyRule::ClientCommandNotAllowed,
+                format!("{}.allowed_commands", source),
+                format!(
+                    "Permission denied: '{}' is not in {}.allowed_commands",
+                    command, source
+                ),
+            );
+        }
+    }
+
+    let whitelisted = |name: &str| config.command_whitelist.iter().any(|c| c == name);
+    // 文本与之前保持一致，客户端和错误本地化依赖其前缀
+    if is_custom && !whitelisted("custom") {
+        return PolicyDecision::new(
+            command,
+            PolicyRule::CustomCommandsDisabled,
+            "command_whitelist".to_string(),
+            "Custom commands are disabled. Please enable 'Custom Commands' in the whitelist."
+                .to_string(),
+        );
+    }
+    if !whitelisted(command) {
+        return PolicyDecision::new(
+            command,
+            PolicyRule::NotWhitelisted,
+            "command_whitelist".to_string(),
+            format!("Command '{}' is not in whitelist", command),
+        );
+    }
+
+    PolicyDecision::new(
+        command,
+        PolicyRule::Allowed,
+        "command_whitelist".to_string(),
+        format!("Command '{}' is allowed", command),
+    )
+}
+
+/// 试运行的结果：解析后的参数和判定结果，不会执行命令
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandCheck {
+    pub args: Option<Vec<String>>,
+    #[serde(flatten)]
+    pub decision: PolicyDecision,
+}
+
+/// 判定日志中的一条记录
+#[derive(Debug, Clone, Serialize)]
+pub struct PolicyLogEntry {
+    pub timestamp: DateTime<Local>,
+    pub ip: String,
+    /// 发起请求的客户端身份（见 Claims::identity）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identity: Option<String>,
+    #[serde(flatten)]
+    pub decision: PolicyDecision,
+}
+
+static DECISIONS: Lazy<Mutex<VecDeque<PolicyLogEntry>>> =
+    Lazy::new(|| Mutex::new(VecDeque::with_capacity(DECISION_LOG_SIZE)));
+
+/// 记录判定结果：写入日志文件，并保留最近的记录供界面查看
+///
+/// identity 为令牌对应的客户端身份，未知时为 None
+pub fn record(ip: &str, identity: Option<&str>, decision: &PolicyDecision) {
+    let who = match identity {
+        Some(identity) => format!("{} {}", ip, identity),
+        None => ip.to_string(),
+    };
+    if decision.allowed {
+        log::info!("[Policy] [{}] ALLOW '{}'", who, decision.command);
+    } else {
+        log::warn!(
+            "[Policy] [{}] DENY '{}' ({:?}, see {}): {}",
+            who,
+            decision.command,
+            decision.rule,
+            decision.config_key,
+            decision.reason
+        );
+    }
+
+    if let Ok(mut decisions) = DECISIONS.lock() {
+        if decisions.len() == DECISION_LOG_SIZE {
+            decisions.pop_front();
+        }
+        decisions.push_back(PolicyLogEntry {
+            timestamp: Local::now(),
+            ip: ip.to_string(),
+            identity: identity.map(str::to_string),
+            decision: decision.clone(),
+        });
+    }
+}
+
+/// 最近的判定记录，最新的在前
+pub fn recent_decisions() -> Vec<PolicyLogEntry> {
+    DECISIONS
+        .lock()
+        .map(|decisions| decisions.iter().rev().cloned().collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> AppConfig {
+        AppConfig {
+            command_whitelist: vec!["lock".to_string(), "custom".to_string()],
+            custom_commands: vec!["backup".to_string()],
+            ..AppConfig::default()
+        }
+    }
+
+    #[test]
+    fn whitelist_rules_name_the_config_entry() {
+        let config = config();
+        assert!(evaluate_command("lock", None, &config).allowed);
+
+        let decision = evaluate_command("shutdown", None, &config);
+        assert_eq!(decision.rule, PolicyRule::NotWhitelisted);
+        assert_eq!(decision.config_key, "command_whitelist");
+
+        let config = AppConfig {
+            command_whitelist: vec!["backup".to_string()],
+            ..config
+        };
+        let decision = evaluate_command("backup", None, &config);
+        assert_eq!(decision.rule, PolicyRule::CustomCommandsDisabled);
+    }
+
+    #[test]
+    fn client_rules_come_before_whitelist() {
+        let config = config();
+        let permissions = ClientPermissions {
+            scopes: vec!["files".to_string()],
+            ..ClientPermissions::default()
+        };
+        let decision = evaluate_command(
+            "lock",
+            Some(("client_permissions.phone", &permissions)),
+            &config,
+        );
+        assert_eq!(decision.rule, PolicyRule::MissingScope);
+        assert_eq!(decision.missing_scope.as_deref(), Some("commands"));
+        assert_eq!(decision.config_key, "client_permissions.phone.scopes");
+
+        let permissions = ClientPermissions {
+            allowed_commands: vec!["sleep".to_string()],
+            ..ClientPermissions::default()
+        };
+        let decision = evaluate_command(
+            "lock",
+            Some(("client_permissions.default", &permissions)),
+            &config,
+        );
+        assert_eq!(decision.rule, PolicyRule::ClientCommandNotAllowed);
+        assert_eq!(
+            decision.config_key,
+            "client_permissions.default.allowed_commands"
+        );
+    }
+}
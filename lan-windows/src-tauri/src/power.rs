@@ -0,0 +1,435 @@
+use chrono::{DateTime, Local};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::Duration;
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+/// 单个防睡眠锁的最长持续时间（秒）
+const MAX_INHIBIT_SECS: u64 = 24 * 60 * 60;
+/// 同时存在的防睡眠锁上限
+const MAX_INHIBITORS: usize = 16;
+
+/// 阻止系统睡眠的句柄，drop 时自动释放
+pub struct AwakeGuard {
+    #[cfg(target_os = "windows")]
+    release: Option<std::sync::mpsc::Sender<()>>,
+    #[cfg(not(target_os = "windows"))]
+    child: Option<std::process::Child>,
+}
+
+impl AwakeGuard {
+    /// 阻止系统睡眠，keep_display 为 true 时同时保持屏幕常亮
+    #[cfg(target_os = "windows")]
+    pub fn acquire(_reason: &str, keep_display: bool) -> Result<Self, String> {
+        use windows::Win32::System::Power::{
+            SetThreadExecutionState, ES_CONTINUOUS, ES_DISPLAY_REQUIRED, ES_SYSTEM_REQUIRED,
+        };
+
+        // SetThreadExecutionState 只对调用线程生效，因此用独立线程持有，直到释放
+        let (release_tx, release_rx) = std::sync::mpsc::channel::<()>();
+        let (ready_tx, ready_rx) = std::sync::mpsc::channel::<bool>();
+        std::thread::spawn(move || {
+            let mut flags = ES_CONTINUOUS | ES_SYSTEM_REQUIRED;
+            if keep_display {
+                flags |= ES_DISPLAY_REQUIRED;
+            }
+            let ok = unsafe { SetThreadExecutionState(flags) }.0 != 0;
+            let _ = ready_tx.send(ok);
+            if ok {
+                // sender 被 drop 时 recv 同样会返回
+                let _ = release_rx.recv();
+                unsafe {
+                    SetThreadExecutionState(ES_CONTINUOUS);
+                }
+            }
+        });
+
+        match ready_rx.recv() {
+            Ok(true) => Ok(Self {
+                release: Some(release_tx),
+            }),
+            _ => Err("SetThreadExecutionState failed".to_string()),
+        }
+    }
+
+    /// 阻止系统睡眠，keep_display 为 true 时同时保持屏幕常亮
+    #[cfg(target_os = "linux")]
+    pub fn acquire(reason: &str, keep_display: bool) -> Result<Self, String> {
+        let what = if keep_display { "sleep:idle" } else { "sleep" };
+        let child = Command::new("systemd-inhibit")
+            .args([
+                &format!("--what={}", what),
+                "--who=LanDeviceManager",
+                &format!("--why={}", reason),
+                "--mode=block",
+                "sleep",
+                "infinity",
+            ])
+            .spawn()
+            .map_err(|e| format!("Failed to start systemd-inhibit: {}", e))?;
+        Ok(Self { child: Some(child) })
+    }
+
+    /// 阻止系统睡眠，keep_display 为 true 时同时保持屏幕常亮
+    #[cfg(target_os = "macos")]
+    pub fn acquire(_reason: &str, keep_display: bool) -> Result<Self, String> {
+        let flags = if keep_display { "-dis" } else { "-is" };
+        let child = Command::new("caffeinate")
+            .arg(flags)
+            .spawn()
+            .map_err(|e| format!("Failed to start caffeinate: {}", e))?;
+        Ok(Self { child: Some(child) })
+    }
+}
+
+impl Drop for AwakeGuard {
+    fn drop(&mut self) {
+        #[cfg(target_os = "windows")]
+        {
+            if let Some(release) = self.release.take() {
+                let _ = release.send(());
+            }
+        }
+
+        #[cfg(not(target_os = "windows"))]
+        {
+            if let Some(mut child) = self.child.take() {
+                let _ = child.kill();
+                let _ = child.wait();
+            }
+        }
+    }
+}
+
+/// 专注模式（勿扰/演示）状态
+#[derive(Debug, Clone, Serialize)]
+pub struct FocusStatus {
+    pub enabled: bool,
+    pub since: Option<DateTime<Local>>,
+    /// 是否已关闭系统通知横幅
+    pub notifications_suppressed: bool,
+    /// 是否已阻止系统睡眠
+    pub sleep_inhibited: bool,
+}
+
+struct FocusMode {
+    since: DateTime<Local>,
+    notifications_suppressed: bool,
+    /// 通知横幅是由专注模式关闭的，结束时需要重新开启
+    restore_notifications: bool,
+    awake: Option<AwakeGuard>,
+}
+
+// 当前专注模式
+static FOCUS_MODE: Lazy<Mutex<Option<FocusMode>>> = Lazy::new(|| Mutex::new(None));
+
+/// 专注模式关闭通知横幅后写入的标记文件，程序异常退出后下次启动时据此恢复
+fn notifications_marker_path() -> std::path::PathBuf {
+    crate::config::AppConfig::config_path().with_file_name("focus_notifications")
+}
+
+/// 系统通知横幅当前是否开启
+#[cfg(target_os = "windows")]
+fn notifications_enabled() -> Result<bool, String> {
+    let output = Command::new("reg")
+        .args([
+            "query",
+            r"HKCU\Software\Microsoft\Windows\CurrentVersion\PushNotifications",
+            "/v",
+            "ToastEnabled",
+        ])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map_err(|e| e.to_string())?;
+    // 从未修改过该开关时没有这个值，默认开启
+    if !output.status.success() {
+        return Ok(true);
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(!stdout
+        .split_whitespace()
+        .skip_while(|word| *word != "REG_DWORD")
+        .nth(1)
+        .map_or(false, |value| value == "0x0"))
+}
+
+/// 系统通知横幅当前是否开启
+#[cfg(target_os = "linux")]
+fn notifications_enabled() -> Result<bool, String> {
+    let output = Command::new("gsettings")
+        .args(["get", "org.gnome.desktop.notifications", "show-banners"])
+        .output()
+        .map_err(|e| e.to_string())?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim() != "false")
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+/// 系统通知横幅当前是否开启
+#[cfg(target_os = "macos")]
+fn notifications_enabled() -> Result<bool, String> {
+    Err("Notification control is not supported on macOS".to_string())
+}
+
+/// 关闭通知横幅，返回之后是否需要由专注模式重新开启
+///
+/// 用户原本就关闭了通知时不做修改；由本程序关闭时先写入标记文件
+fn suppress_notifications() -> Result<bool, String> {
+    if !notifications_enabled()? {
+        return Ok(false);
+    }
+    let marker = notifications_marker_path();
+    crate::config::AppConfig::ensure_config_dir()
+        .and_then(|_| std::fs::write(&marker, b""))
+        .map_err(|e| format!("Failed to record notification state: {}", e))?;
+    if let Err(e) = set_notifications_enabled(false) {
+        let _ = std::fs::remove_file(&marker);
+        return Err(e);
+    }
+    Ok(true)
+}
+
+/// 重新开启专注模式关闭的通知横幅，成功后删除标记文件
+fn restore_notifications() -> Result<(), String> {
+    set_notifications_enabled(true)?;
+    let _ = std::fs::remove_file(notifications_marker_path());
+    Ok(())
+}
+
+/// 启动时恢复上次运行中专注模式关闭、但因异常退出未能恢复的通知横幅
+pub fn restore_notifications_after_crash() {
+    if !notifications_marker_path().exists() {
+        return;
+    }
+    match restore_notifications() {
+        Ok(()) => log::info!("Restored notifications left disabled by focus mode"),
+        Err(e) => log::warn!("Failed to restore notifications: {}", e),
+    }
+}
+
+/// 开启或关闭系统通知横幅
+#[cfg(target_os = "windows")]
+fn set_notifications_enabled(enabled: bool) -> Result<(), String> {
+    // 对应 设置 > 系统 > 通知 的总开关，与 Focus Assist 一样会屏蔽通知横幅
+    let output = Command::new("reg")
+        .args([
+            "add",
+            r"HKCU\Software\Microsoft\Windows\CurrentVersion\PushNotifications",
+            "/v",
+            "ToastEnabled",
+            "/t",
+            "REG_DWORD",
+            "/d",
+            if enabled { "1" } else { "0" },
+            "/f",
+        ])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map_err(|e| e.to_string())?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+/// 开启或关闭系统通知横幅
+#[cfg(target_os = "linux")]
+fn set_notifications_enabled(enabled: bool) -> Result<(), String> {
+    let output = Command::new("gsettings")
+        .args([
+            "set",
+            "org.gnome.desktop.notifications",
+            "show-banners",
+            if enabled { "true" } else { "false" },
+        ])
+        .output()
+        .map_err(|e| e.to_string())?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+/// 开启或关闭系统通知横幅
+#[cfg(target_os = "macos")]
+fn set_notifications_enabled(_enabled: bool) -> Result<(), String> {
+    Err("Notification control is not supported on macOS".to_string())
+}
+
+/// 获取专注模式状态
+pub fn get_focus_status() -> FocusStatus {
+    match FOCUS_MODE.lock() {
+        Ok(focus) => match focus.as_ref() {
+            Some(mode) => FocusStatus {
+                enabled: true,
+                since: Some(mode.since),
+                notifications_suppressed: mode.notifications_suppressed,
+                sleep_inhibited: mode.awake.is_some(),
+            },
+            None => FocusStatus {
+                enabled: false,
+                since: None,
+                notifications_suppressed: false,
+                sleep_inhibited: false,
+            },
+        },
+        Err(_) => FocusStatus {
+            enabled: false,
+            since: None,
+            notifications_suppressed: false,
+            sleep_inhibited: false,
+        },
+    }
+}
+
+/// 开启或关闭专注模式：屏蔽通知横幅并阻止系统睡眠和息屏
+pub fn set_focus_mode(enabled: bool) -> Result<FocusStatus, String> {
+    {
+        let mut focus = FOCUS_MODE.lock().map_err(|e| e.to_string())?;
+
+        if enabled && focus.is_none() {
+            let restore_notifications = match suppress_notifications() {
+                Ok(restore) => Some(restore),
+                Err(e) => {
+                    log::warn!("Failed to suppress notifications: {}", e);
+                    None
+                }
+            };
+            let awake = match AwakeGuard::acquire("Focus mode enabled remotely", true) {
+                Ok(guard) => Some(guard),
+                Err(e) => {
+                    log::warn!("Failed to inhibit sleep: {}", e);
+                    None
+                }
+            };
+
+            *focus = Some(FocusMode {
+                since: Local::now(),
+                notifications_suppressed: restore_notifications.is_some(),
+                restore_notifications: restore_notifications.unwrap_or(false),
+                awake,
+            });
+            log::info!("Focus mode enabled");
+        } else if !enabled {
+            if let Some(mode) = focus.take() {
+                if mode.restore_notifications {
+                    if let Err(e) = restore_notifications() {
+                        log::warn!("Failed to restore notifications: {}", e);
+                    }
+                }
+                // mode.awake 在此 drop，恢复睡眠
+                log::info!("Focus mode disabled");
+            }
+        }
+    }
+
+    Ok(get_focus_status())
+}
+
+/// 防睡眠锁信息
+#[derive(Debug, Clone, Serialize)]
+pub struct InhibitorInfo {
+    pub id: String,
+    pub reason: String,
+    /// 申请者（客户端IP）
+    pub client: String,
+    pub keep_display: bool,
+    pub created_at: DateTime<Local>,
+    pub expires_at: DateTime<Local>,
+}
+
+struct Inhibitor {
+    info: InhibitorInfo,
+    _guard: AwakeGuard,
+}
+
+// 当前持有的防睡眠锁
+static INHIBITORS: Lazy<Mutex<HashMap<String, Inhibitor>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 申请一个防睡眠锁，到期后自动释放
+pub fn add_inhibitor(
+    reason: &str,
+    client: &str,
+    duration_secs: u64,
+    keep_display: bool,
+) -> Result<InhibitorInfo, String> {
+    if duration_secs == 0 {
+        return Err("Duration must be greater than 0".to_string());
+    }
+    let duration_secs = duration_secs.min(MAX_INHIBIT_SECS);
+
+    let mut inhibitors = INHIBITORS.lock().map_err(|e| e.to_string())?;
+    if inhibitors.len() >= MAX_INHIBITORS {
+        return Err(format!("Too many active inhibitors, limit is {}", MAX_INHIBITORS));
+    }
+
+    let guard = AwakeGuard::acquire(reason, keep_display)?;
+    let now = Local::now();
+    let info = InhibitorInfo {
+        id: uuid::Uuid::new_v4().to_string(),
+        reason: reason.to_string(),
+        client: client.to_string(),
+        keep_display,
+        created_at: now,
+        expires_at: now + chrono::Duration::seconds(duration_secs as i64),
+    };
+    inhibitors.insert(
+        info.id.clone(),
+        Inhibitor {
+            info: info.clone(),
+            _guard: guard,
+        },
+    );
+
+    // 到期自动释放
+    let id = info.id.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_secs(duration_secs));
+        if release_inhibitor(&id) {
+            log::info!("Sleep inhibitor {} expired and was released", id);
+        }
+    });
+
+    log::info!(
+        "Sleep inhibitor {} acquired by {} for {}s: {}",
+        info.id,
+        client,
+        duration_secs,
+        reason
+    );
+    Ok(info)
+}
+
+/// 释放防睡眠锁，返回是否存在该锁
+pub fn release_inhibitor(id: &str) -> bool {
+    match INHIBITORS.lock() {
+        Ok(mut inhibitors) => inhibitors.remove(id).is_some(),
+        Err(_) => false,
+    }
+}
+
+/// 列出当前持有的防睡眠锁，按到期时间排序
+pub fn list_inhibitors() -> Vec<InhibitorInfo> {
+    let mut list: Vec<InhibitorInfo> = match INHIBITORS.lock() {
+        Ok(inhibitors) => inhibitors.values().map(|i| i.info.clone()).collect(),
+        Err(_) => Vec::new(),
+    };
+    list.sort_by(|a, b| a.expires_at.cmp(&b.expires_at));
+    list
+}
@@ -0,0 +1,208 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+/// 上传的待打印文件保留时间，超过后在下次打印时清理
+const UPLOAD_RETENTION: Duration = Duration::from_secs(60 * 60);
+
+/// 已安装的打印机
+#[derive(Debug, Clone, Serialize)]
+pub struct PrinterInfo {
+    pub name: String,
+    pub is_default: bool,
+    pub status: String,
+}
+
+/// 列出已安装的打印机
+#[cfg(target_os = "windows")]
+pub fn list_printers() -> Result<Vec<PrinterInfo>, String> {
+    let output = Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-Command",
+            "Get-CimInstance Win32_Printer | Select-Object Name,Default,PrinterStatus | ConvertTo-Json -Compress",
+        ])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map_err(|e| e.to_string())?;
+
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+
+    let text = String::from_utf8_lossy(&output.stdout);
+    if text.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // 只有一台打印机时 ConvertTo-Json 输出对象而不是数组
+    let value: serde_json::Value = serde_json::from_str(text.trim()).map_err(|e| e.to_string())?;
+    let items = match value {
+        serde_json::Value::Array(items) => items,
+        other => vec![other],
+    };
+
+    Ok(items
+        .iter()
+        .filter_map(|item| {
+            let name = item.get("Name")?.as_str()?.to_string();
+            let status = match item.get("PrinterStatus").and_then(|s| s.as_u64()) {
+                Some(3) => "idle",
+                Some(4) => "printing",
+                Some(5) => "warmup",
+                Some(6) => "stopped",
+                Some(7) => "offline",
+                _ => "unknown",
+            };
+            Some(PrinterInfo {
+                name,
+                is_default: item.get("Default").and_then(|d| d.as_bool()).unwrap_or(false),
+                status: status.to_string(),
+            })
+        })
+        .collect())
+}
+
+/// 列出已安装的打印机
+#[cfg(not(target_os = "windows"))]
+pub fn list_printers() -> Result<Vec<PrinterInfo>, String> {
+    let default = Command::new("lpstat")
+        .arg("-d")
+        .output()
+        .ok()
+        .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+        .and_then(|s| s.split(':').nth(1).map(|name| name.trim().to_string()));
+
+    let output = Command::new("lpstat")
+        .arg("-p")
+        .output()
+        .map_err(|e| format!("Failed to run lpstat: {}", e))?;
+
+    // 格式：printer NAME is idle.  enabled since ...
+    let text = String::from_utf8_lossy(&output.stdout);
+    Ok(text
+        .lines()
+        .filter_map(|line| {
+            let rest = line.strip_prefix("printer ")?;
+            let name = rest.split_whitespace().next()?.to_string();
+            let status = if rest.contains("disabled") {
+                "stopped"
+            } else if rest.contains("printing") {
+                "printing"
+            } else if rest.contains("idle") {
+                "idle"
+            } else {
+                "unknown"
+            };
+            Some(PrinterInfo {
+                is_default: default.as_deref() == Some(name.as_str()),
+                name,
+                status: status.to_string(),
+            })
+        })
+        .collect())
+}
+
+/// 上传文件的暂存目录
+fn upload_dir() -> PathBuf {
+    std::env::temp_dir().join("LanDeviceManager").join("print")
+}
+
+/// 清理过期的上传文件
+fn cleanup_uploads(dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let expired = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .map(|age| age > UPLOAD_RETENTION)
+            .unwrap_or(false);
+        if expired {
+            let _ = std::fs::remove_file(entry.path());
+        }
+    }
+}
+
+/// 保存上传的文件，返回保存路径
+pub fn save_upload(filename: &str, content: &[u8]) -> Result<PathBuf, String> {
+    let dir = upload_dir();
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    cleanup_uploads(&dir);
+
+    // 只保留文件名中的安全字符，扩展名决定使用哪个程序打印
+    let safe_name: String = Path::new(filename)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '.' || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    if safe_name.is_empty() {
+        return Err("Invalid file name".to_string());
+    }
+
+    let path = dir.join(format!("{}_{}", uuid::Uuid::new_v4(), safe_name));
+    std::fs::write(&path, content).map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+/// 打印文件，printer 为 None 时使用默认打印机
+pub fn print_file(path: &Path, printer: Option<&str>) -> Result<(), String> {
+    if !path.is_file() {
+        return Err(format!("'{}' is not a file", path.display()));
+    }
+
+    if let Some(printer) = printer {
+        // 只允许已安装的打印机，同时避免把任意字符串拼进命令
+        let known = list_printers()?.iter().any(|p| p.name == printer);
+        if !known {
+            return Err(format!("Printer '{}' not found", printer));
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        let file = path.to_string_lossy().replace('\'', "''");
+        let script = match printer {
+            Some(printer) => format!(
+                "Start-Process -FilePath '{}' -Verb PrintTo -ArgumentList '\"{}\"' -WindowStyle Hidden",
+                file,
+                printer.replace('\'', "''")
+            ),
+            None => format!("Start-Process -FilePath '{}' -Verb Print -WindowStyle Hidden", file),
+        };
+        let output = Command::new("powershell")
+            .args(["-NoProfile", "-Command", &script])
+            .creation_flags(CREATE_NO_WINDOW)
+            .output()
+            .map_err(|e| e.to_string())?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        let mut command = Command::new("lp");
+        if let Some(printer) = printer {
+            command.args(["-d", printer]);
+        }
+        let output = command.arg(path).output().map_err(|e| e.to_string())?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+    }
+
+    Ok(())
+}
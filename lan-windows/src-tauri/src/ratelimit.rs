@@ -0,0 +1,134 @@
+use axum::extract::Request;
+use axum::http::header::RETRY_AFTER;
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::api::ClientIp;
+use crate::config::get_config;
+use crate::messages::Lang;
+
+/// 记录的客户端数量超过该值时清理已空闲的令牌桶
+const PRUNE_THRESHOLD: usize = 1024;
+
+/// 每个客户端IP的令牌桶
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+    /// 已被限流，用于只在开始限流时写一次日志
+    limited: bool,
+}
+
+static BUCKETS: Lazy<Mutex<HashMap<String, Bucket>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 为 ip 取一个令牌；允许时返回 None，超限时返回建议等待的秒数
+fn acquire(ip: &str, rps: u32, burst: u32, now: Instant) -> Option<u64> {
+    let rps = f64::from(rps);
+    let burst = f64::from(burst.max(1));
+    let mut buckets = BUCKETS.lock().ok()?;
+
+    // 空闲到令牌已回满的桶与新建的没有区别，可以直接删除
+    if buckets.len() >= PRUNE_THRESHOLD {
+        let refill = Duration::from_secs_f64(burst / rps);
+        buckets.retain(|_, bucket| now.duration_since(bucket.updated) < refill);
+    }
+
+    let bucket = buckets.entry(ip.to_string()).or_insert(Bucket {
+        tokens: burst,
+        updated: now,
+        limited: false,
+    });
+    let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+    bucket.tokens = (bucket.tokens + elapsed * rps).min(burst);
+    bucket.updated = now;
+
+    if bucket.tokens >= 1.0 {
+        bucket.tokens -= 1.0;
+        bucket.limited = false;
+        return None;
+    }
+    if !bucket.limited {
+        bucket.limited = true;
+        log::warn!(
+            "[Security] [{}] Rate limit exceeded ({} requests/s, burst {})",
+            ip,
+            rps,
+            burst
+        );
+    }
+    Some(((1.0 - bucket.tokens) / rps).ceil().max(1.0) as u64)
+}
+
+/// 按配置为客户端IP取一个令牌，未启用限流或允许时返回 None，超限时返回建议等待的秒数
+///
+/// REST 中间件和 gRPC 拦截器共用同一组令牌桶
+pub fn check(ip: &str) -> Option<u64> {
+    let config = get_config();
+    if config.http_rate_limit_rps == 0 {
+        return None;
+    }
+    acquire(
+        ip,
+        config.http_rate_limit_rps,
+        config.http_rate_limit_burst,
+        Instant::now(),
+    )
+}
+
+/// 按客户端IP限制请求速率的中间件，与登录失败的暴力破解保护相互独立
+///
+/// 覆盖包括 /api/health 和 /api/auth/challenge 在内的所有路由，
+/// 超限时返回 429 和 Retry-After。位于本地化中间件之外，错误码在这里直接补充
+pub async fn rate_limit_middleware(request: Request, next: Next) -> Response {
+    let ip = request
+        .extensions()
+        .get::<ClientIp>()
+        .map(|ip| crate::stats::ip_only(&ip.0))
+        .unwrap_or_else(|| "unknown".to_string());
+    let Some(retry_after) = check(&ip) else {
+        return next.run(request).await;
+    };
+
+    let error = format!("Too many requests, retry after {} seconds", retry_after);
+    let mut body = serde_json::json!({
+        "success": false,
+        "data": null,
+        "error": error,
+        "error_details": null,
+    });
+    let lang = Lang::from_headers(request.headers());
+    if let Some((code, message)) = crate::messages::localize(&error, lang) {
+        body["error_code"] = code.as_str().into();
+        body["error_message"] = message.into();
+    }
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        [(RETRY_AFTER, retry_after.to_string())],
+        axum::Json(body),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refills_at_configured_rate() {
+        let ip = "198.51.100.7";
+        let start = Instant::now();
+        assert!(acquire(ip, 2, 3, start).is_none());
+        assert!(acquire(ip, 2, 3, start).is_none());
+        assert!(acquire(ip, 2, 3, start).is_none());
+        assert_eq!(acquire(ip, 2, 3, start), Some(1));
+
+        // 0.5 秒补充一个令牌
+        let later = start + Duration::from_millis(500);
+        assert!(acquire(ip, 2, 3, later).is_none());
+        assert!(acquire(ip, 2, 3, later).is_some());
+    }
+}
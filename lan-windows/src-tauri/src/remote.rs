@@ -0,0 +1,409 @@
+use hmac::{Hmac, Mac};
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use once_cell::sync::Lazy;
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT_LANGUAGE};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::config::PasswordVerifier;
+use crate::device_id::DeviceId;
+use crate::models::{CommandResult, DeviceInfo, SystemInfo};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// 与 mdns 模块广播的服务类型一致
+const SERVICE_TYPE: &str = "_lanmanager._tcp.local.";
+/// 局域网内请求超时
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(12);
+
+/// 其他设备返回的响应，错误消息优先使用服务端本地化后的文本
+#[derive(Debug, Deserialize)]
+struct RemoteResponse<T> {
+    success: bool,
+    data: Option<T>,
+    error: Option<String>,
+    #[serde(default)]
+    error_message: Option<String>,
+}
+
+impl<T> RemoteResponse<T> {
+    fn into_result(self) -> Result<T, String> {
+        match self.data {
+            Some(data) if self.success => Ok(data),
+            _ => Err(self
+                .error_message
+                .or(self.error)
+                .unwrap_or_else(|| "Unknown error".to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ChallengeData {
+    challenge: String,
+    /// 旧版本服务端不返回验证器参数
+    #[serde(default)]
+    salt: Option<String>,
+    #[serde(default)]
+    iterations: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LoginData {
+    token: String,
+}
+
+/// 已连接的远程设备
+#[derive(Debug, Clone, Serialize)]
+pub struct RemoteConnection {
+    /// 连接标识，格式为 "ip:port"
+    pub key: String,
+    pub ip_address: String,
+    pub port: u16,
+    pub authenticated: bool,
+}
+
+/// 访问其他设备 API 的客户端，与 Android 端的 ApiClient 对应
+pub struct RemoteClient {
+    client: reqwest::Client,
+    base_url: String,
+    token: Option<Zeroizing<String>>,
+}
+
+impl RemoteClient {
+    pub fn new(ip: &str, port: u16) -> Result<Self, String> {
+        // 按本机配置的语言请求错误消息
+        let mut headers = HeaderMap::new();
+        if let Ok(language) = HeaderValue::from_str(&crate::config::get_config().message_language) {
+            headers.insert(ACCEPT_LANGUAGE, language);
+        }
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .default_headers(headers)
+            .user_agent(concat!("lan-device-manager/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .map_err(|e| e.to_string())?;
+        let host = match ip.parse::<std::net::IpAddr>() {
+            Ok(std::net::IpAddr::V6(v6)) => format!("[{}]", v6),
+            _ => ip.to_string(),
+        };
+        Ok(Self {
+            client,
+            base_url: format!("http://{}:{}", host, port),
+            token: None,
+        })
+    }
+
+    async fn parse<T: DeserializeOwned>(response: reqwest::Response) -> Result<T, String> {
+        response
+            .json::<RemoteResponse<T>>()
+            .await
+            .map_err(|e| format!("Failed to parse response: {}", e))?
+            .into_result()
+    }
+
+    pub async fn health_check(&self) -> Result<bool, String> {
+        let url = format!("{}/api/health", self.base_url);
+        match self.client.get(&url).send().await {
+            // 服务端要求健康检查密钥时返回 404，改用无需认证的 auth/check 确认服务在线
+            Ok(response) if response.status() == reqwest::StatusCode::NOT_FOUND => {
+                let url = format!("{}/api/auth/check", self.base_url);
+                match self.client.get(&url).send().await {
+                    Ok(response) => Ok(response.status().is_success()),
+                    Err(e) => Err(format!("Request failed: {}", e)),
+                }
+            }
+            Ok(response) => Ok(response.status().is_success()),
+            Err(e) => Err(format!("Request failed: {}", e)),
+        }
+    }
+
+    /// 检查是否需要认证，请求失败时按需要认证处理
+    pub async fn check_auth_required(&self) -> Result<bool, String> {
+        let url = format!("{}/api/auth/check", self.base_url);
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+        Ok(Self::parse::<serde_json::Value>(response)
+            .await
+            .ok()
+            .and_then(|data| data.get("requires_auth")?.as_bool())
+            .unwrap_or(true))
+    }
+
+    /// 挑战-响应登录，成功后保存令牌
+    pub async fn authenticate(&mut self, password: &str) -> Result<(), String> {
+        let device_id = DeviceId::get_or_create().ok();
+
+        let url = format!("{}/api/auth/challenge", self.base_url);
+        let response = self
+            .client
+            .post(&url)
+            .json(&serde_json::json!({ "device_id": device_id }))
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+        let data = Self::parse::<ChallengeData>(response).await?;
+        let (Some(salt), Some(iterations)) = (data.salt, data.iterations) else {
+            return Err(
+                "Device did not offer a password verifier, update it or set its password again"
+                    .to_string(),
+            );
+        };
+
+        // 以验证器为密钥计算响应，密码本身不发送
+        let verifier = PasswordVerifier::derive(password, &salt, iterations);
+        let mut mac =
+            HmacSha256::new_from_slice(verifier.as_ref()).expect("HMAC can take key of any size");
+        mac.update(data.challenge.as_bytes());
+        let mut bytes = mac.finalize().into_bytes();
+        let hmac_response = hex::encode(&bytes);
+        bytes.as_mut_slice().zeroize();
+
+        let url = format!("{}/api/auth/login", self.base_url);
+        let body = serde_json::json!({
+            "challenge": data.challenge,
+            "response": hmac_response,
+            "device_id": device_id,
+        });
+        let response = self
+            .client
+            .post(&url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let login = Self::parse::<LoginData>(response).await?;
+        self.token = Some(Zeroizing::new(login.token));
+        Ok(())
+    }
+
+    pub fn is_authenticated(&self) -> bool {
+        self.token.is_some()
+    }
+
+    pub async fn get_system_info(&self) -> Result<SystemInfo, String> {
+        let url = format!("{}/api/system/info", self.base_url);
+        let mut request = self.client.get(&url);
+        if let Some(ref token) = self.token {
+            request = request.query(&[("token", token.as_str())]);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+        Self::parse(response).await
+    }
+
+    pub async fn execute_command(
+        &self,
+        command: &str,
+        args: Option<Vec<String>>,
+    ) -> Result<CommandResult, String> {
+        let token = self
+            .token
+            .as_deref()
+            .ok_or_else(|| "Not authenticated".to_string())?;
+
+        let url = format!("{}/api/command/execute", self.base_url);
+        let response = self
+            .client
+            .post(&url)
+            .json(&serde_json::json!({
+                "token": token,
+                "command": command,
+                "args": args,
+            }))
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+        Self::parse(response).await
+    }
+}
+
+// mDNS 浏览使用独立的 daemon，与本机的服务广播互不影响
+static DISCOVERY: Lazy<Mutex<Option<ServiceDaemon>>> = Lazy::new(|| Mutex::new(None));
+// 服务全名 -> 设备信息
+static DEVICES: Lazy<Mutex<HashMap<String, DeviceInfo>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+// 连接标识 -> 客户端，请求时复制出 Arc，不在等待响应时持有锁
+static CONNECTIONS: Lazy<Mutex<HashMap<String, Arc<RemoteClient>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn device_from_service(info: &ServiceInfo) -> Option<DeviceInfo> {
+    let addresses = info.get_addresses();
+    // 优先选择非回环的 IPv4 地址
+    let ip = addresses
+        .iter()
+        .find(|ip| ip.is_ipv4() && !ip.is_loopback())
+        .or_else(|| addresses.iter().find(|ip| !ip.is_loopback()))?;
+    let hostname = info
+        .get_hostname()
+        .trim_end_matches('.')
+        .trim_end_matches(".local")
+        .to_string();
+    let name = info
+        .get_property_val_str("device")
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .unwrap_or(hostname);
+
+    Some(DeviceInfo {
+        // 没有 UUID 的旧版本使用服务全名
+        id: info
+            .get_property_val_str("uuid")
+            .map(|v| v.to_string())
+            .unwrap_or_else(|| info.get_fullname().to_string()),
+        name,
+        ip_address: ip.to_string(),
+        port: info.get_port(),
+        version: info
+            .get_property_val_str("version")
+            .unwrap_or("unknown")
+            .to_string(),
+        requires_auth: info.get_property_val_str("auth") == Some("required"),
+    })
+}
+
+/// 开始在局域网中查找其他设备，已在查找时直接返回
+pub fn start_discovery() -> Result<(), String> {
+    let mut discovery = DISCOVERY.lock().map_err(|e| e.to_string())?;
+    if discovery.is_some() {
+        return Ok(());
+    }
+
+    let daemon = ServiceDaemon::new().map_err(|e| e.to_string())?;
+    let receiver = daemon.browse(SERVICE_TYPE).map_err(|e| e.to_string())?;
+    let own_uuid = DeviceId::get_or_create().ok();
+
+    std::thread::spawn(move || {
+        while let Ok(event) = receiver.recv() {
+            match event {
+                ServiceEvent::ServiceResolved(info) => {
+                    let Some(device) = device_from_service(&info) else {
+                        log::warn!("No usable address for service: {}", info.get_fullname());
+                        continue;
+                    };
+                    // 跳过本机
+                    if own_uuid.as_deref() == Some(device.id.as_str()) {
+                        continue;
+                    }
+                    log::info!(
+                        "Discovered remote device {} at {}:{}",
+                        device.name,
+                        device.ip_address,
+                        device.port
+                    );
+                    if let Ok(mut devices) = DEVICES.lock() {
+                        // 同一设备更换服务名（如端口变化）时移除旧条目
+                        devices.retain(|_, d| d.id != device.id);
+                        devices.insert(info.get_fullname().to_string(), device);
+                    }
+                }
+                ServiceEvent::ServiceRemoved(_, fullname) => {
+                    log::info!("Remote device removed: {}", fullname);
+                    if let Ok(mut devices) = DEVICES.lock() {
+                        devices.remove(&fullname);
+                    }
+                }
+                _ => {}
+            }
+        }
+        log::info!("Remote discovery listener ended");
+    });
+
+    *discovery = Some(daemon);
+    log::info!("Remote device discovery started");
+    Ok(())
+}
+
+/// 停止查找并清空已发现的设备
+pub fn stop_discovery() -> Result<(), String> {
+    let daemon = DISCOVERY.lock().map_err(|e| e.to_string())?.take();
+    if let Some(daemon) = daemon {
+        daemon.shutdown().map_err(|e| e.to_string())?;
+        log::info!("Remote device discovery stopped");
+    }
+    if let Ok(mut devices) = DEVICES.lock() {
+        devices.clear();
+    }
+    Ok(())
+}
+
+/// 已发现的设备，按名称排序
+pub fn get_devices() -> Vec<DeviceInfo> {
+    let mut devices: Vec<DeviceInfo> = DEVICES
+        .lock()
+        .map(|devices| devices.values().cloned().collect())
+        .unwrap_or_default();
+    devices.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+    devices
+}
+
+/// 连接设备，需要认证时使用给定密码登录；已有连接会被替换
+pub async fn connect(
+    ip: &str,
+    port: u16,
+    password: Option<String>,
+) -> Result<RemoteConnection, String> {
+    let password = password.map(Zeroizing::new);
+    let mut client = RemoteClient::new(ip, port)?;
+    if !client.health_check().await? {
+        return Err(format!("Device at {}:{} is not responding", ip, port));
+    }
+    if client.check_auth_required().await? {
+        let password = password
+            .as_deref()
+            .ok_or_else(|| "Password required".to_string())?;
+        client.authenticate(password).await?;
+    }
+
+    let connection = RemoteConnection {
+        key: format!("{}:{}", ip, port),
+        ip_address: ip.to_string(),
+        port,
+        authenticated: client.is_authenticated(),
+    };
+    log::info!("Connected to remote device {}", connection.key);
+    CONNECTIONS
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(connection.key.clone(), Arc::new(client));
+    Ok(connection)
+}
+
+pub fn disconnect(key: &str) -> bool {
+    CONNECTIONS
+        .lock()
+        .map_or(false, |mut connections| connections.remove(key).is_some())
+}
+
+fn connection(key: &str) -> Result<Arc<RemoteClient>, String> {
+    CONNECTIONS
+        .lock()
+        .map_err(|e| e.to_string())?
+        .get(key)
+        .cloned()
+        .ok_or_else(|| format!("Not connected to {}", key))
+}
+
+pub async fn get_system_info(key: &str) -> Result<SystemInfo, String> {
+    connection(key)?.get_system_info().await
+}
+
+pub async fn execute_command(
+    key: &str,
+    command: &str,
+    args: Option<Vec<String>>,
+) -> Result<CommandResult, String> {
+    let client = connection(key)?;
+    log::info!("Executing '{}' on remote device {}", command, key);
+    client.execute_command(command, args).await
+}
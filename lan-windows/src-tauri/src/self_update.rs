@@ -0,0 +1,226 @@
+use chrono::{DateTime, Local};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// GitHub 最新发布版本接口
+const RELEASES_URL: &str =
+    "https://api.github.com/repos/maxwellnie/lan-device-manager/releases/latest";
+/// 请求超时，安装包下载单独使用更长的超时
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// 本程序的更新状态（与 updates 模块的 Windows Update 无关）
+#[derive(Debug, Clone, Serialize)]
+pub struct AppUpdateStatus {
+    pub current_version: String,
+    pub latest_version: Option<String>,
+    pub update_available: bool,
+    /// 发布页面地址
+    pub release_url: Option<String>,
+    pub release_notes: Option<String>,
+    /// 已下载的安装包路径
+    pub installer_path: Option<String>,
+    pub checked_at: Option<DateTime<Local>>,
+    pub last_error: Option<String>,
+}
+
+impl Default for AppUpdateStatus {
+    fn default() -> Self {
+        Self {
+            current_version: env!("CARGO_PKG_VERSION").to_string(),
+            latest_version: None,
+            update_available: false,
+            release_url: None,
+            release_notes: None,
+            installer_path: None,
+            checked_at: None,
+            last_error: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    html_url: String,
+    #[serde(default)]
+    body: Option<String>,
+    #[serde(default)]
+    assets: Vec<GithubAsset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubAsset {
+    name: String,
+    browser_download_url: String,
+}
+
+// 最近一次检查的结果，API 和界面共用
+static STATUS: Lazy<Mutex<AppUpdateStatus>> = Lazy::new(|| Mutex::new(AppUpdateStatus::default()));
+
+/// 获取最近一次检查的结果
+pub fn get_status() -> AppUpdateStatus {
+    match STATUS.lock() {
+        Ok(status) => status.clone(),
+        Err(poisoned) => poisoned.into_inner().clone(),
+    }
+}
+
+/// 解析版本号为数字序列，忽略前缀 v，例如 "v0.1.0-1" -> [0, 1, 0, 1]
+fn parse_version(version: &str) -> Vec<u64> {
+    version
+        .trim()
+        .trim_start_matches(['v', 'V'])
+        .split(['.', '-', '+'])
+        .map_while(|part| part.parse().ok())
+        .collect()
+}
+
+/// 判断 latest 是否比 current 新
+pub fn is_newer(latest: &str, current: &str) -> bool {
+    let (latest, current) = (parse_version(latest), parse_version(current));
+    let len = latest.len().max(current.len());
+    let pad = |v: &[u64], i: usize| v.get(i).copied().unwrap_or(0);
+    (0..len)
+        .map(|i| pad(&latest, i).cmp(&pad(&current, i)))
+        .find(|o| o.is_ne())
+        .map_or(false, |o| o.is_gt())
+}
+
+/// 选择 Windows 安装包，优先 NSIS 安装程序，其次 MSI
+fn pick_installer(assets: &[GithubAsset]) -> Option<&GithubAsset> {
+    let by_suffix = |suffix: &str| {
+        assets
+            .iter()
+            .find(|a| a.name.to_lowercase().ends_with(suffix))
+    };
+    by_suffix("-setup.exe").or_else(|| by_suffix(".msi"))
+}
+
+fn download_dir() -> std::io::Result<PathBuf> {
+    let dir = crate::config::AppConfig::ensure_config_dir()?.join("updates");
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+fn client(timeout: Duration) -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        .user_agent(concat!("lan-device-manager/", env!("CARGO_PKG_VERSION")))
+        .timeout(timeout)
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+async fn download_installer(asset: &GithubAsset) -> Result<PathBuf, String> {
+    let path = download_dir().map_err(|e| e.to_string())?.join(&asset.name);
+    // 已下载过的安装包直接使用
+    if path.is_file() {
+        return Ok(path);
+    }
+
+    log::info!("Downloading update installer {}", asset.name);
+    let bytes = client(DOWNLOAD_TIMEOUT)?
+        .get(&asset.browser_download_url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to download installer: {}", e))?
+        .bytes()
+        .await
+        .map_err(|e| format!("Failed to download installer: {}", e))?;
+
+    crate::config::write_atomic(&path, &bytes).map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+async fn fetch_latest() -> Result<AppUpdateStatus, String> {
+    let release: GithubRelease = client(REQUEST_TIMEOUT)?
+        .get(RELEASES_URL)
+        .header("Accept", "application/vnd.github+json")
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to query releases: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse release: {}", e))?;
+
+    let current = env!("CARGO_PKG_VERSION");
+    let latest = release.tag_name.trim_start_matches(['v', 'V']).to_string();
+    let update_available = is_newer(&latest, current);
+
+    let mut status = AppUpdateStatus {
+        latest_version: Some(latest),
+        update_available,
+        release_url: Some(release.html_url),
+        release_notes: release.body,
+        checked_at: Some(Local::now()),
+        ..Default::default()
+    };
+
+    if update_available {
+        match pick_installer(&release.assets) {
+            Some(asset) => match download_installer(asset).await {
+                Ok(path) => status.installer_path = Some(path.to_string_lossy().into_owned()),
+                // 下载失败仍然报告有新版本
+                Err(e) => status.last_error = Some(e),
+            },
+            None => status.last_error = Some("No installer found in release".to_string()),
+        }
+    }
+    Ok(status)
+}
+
+/// 检查是否有新版本，有新版本时下载安装包
+pub async fn check_for_updates() -> Result<AppUpdateStatus, String> {
+    match fetch_latest().await {
+        Ok(status) => {
+            if status.update_available {
+                log::info!(
+                    "Update available: {} -> {}",
+                    status.current_version,
+                    status.latest_version.as_deref().unwrap_or_default()
+                );
+            }
+            if let Ok(mut current) = STATUS.lock() {
+                *current = status.clone();
+            }
+            Ok(status)
+        }
+        Err(e) => {
+            log::warn!("Update check failed: {}", e);
+            if let Ok(mut current) = STATUS.lock() {
+                current.checked_at = Some(Local::now());
+                current.last_error = Some(e.clone());
+            }
+            Err(e)
+        }
+    }
+}
+
+/// 定期检查更新，间隔为 0 时不检查；发现新版本时调用 on_available
+pub fn spawn_periodic_check<F>(on_available: F)
+where
+    F: Fn(&AppUpdateStatus) + Send + 'static,
+{
+    tauri::async_runtime::spawn(async move {
+        // 启动后稍等片刻，避免与服务器启动争抢资源
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        loop {
+            let hours = crate::config::get_config().update_check_interval_hours;
+            if hours > 0 {
+                let was_available = get_status().update_available;
+                if let Ok(status) = check_for_updates().await {
+                    if status.update_available && !was_available {
+                        on_available(&status);
+                    }
+                }
+            }
+            // 间隔为 0 时每小时重新读取配置
+            tokio::time::sleep(Duration::from_secs(hours.max(1) * 3600)).await;
+        }
+    });
+}
@@ -0,0 +1,204 @@
+use serde::Serialize;
+
+#[cfg(target_os = "linux")]
+use std::process::Command;
+
+/// 用户会话
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionInfo {
+    pub id: u32,
+    pub user: Option<String>,
+    pub domain: Option<String>,
+    /// 会话所在的站点（console、RDP-Tcp#0、seat0 等）
+    pub station: Option<String>,
+    /// active / connected / disconnected / idle / ...
+    pub state: String,
+    /// 是否为本服务所在的会话
+    pub current: bool,
+}
+
+/// 会话操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionAction {
+    Lock,
+    Disconnect,
+    Logoff,
+}
+
+impl SessionAction {
+    pub fn parse(action: &str) -> Result<Self, String> {
+        match action {
+            "lock" => Ok(Self::Lock),
+            "disconnect" => Ok(Self::Disconnect),
+            "logoff" => Ok(Self::Logoff),
+            _ => Err(format!("Unknown session action '{}'", action)),
+        }
+    }
+}
+
+/// 列出已登录的用户会话
+#[cfg(target_os = "windows")]
+pub fn list_sessions() -> Result<Vec<SessionInfo>, String> {
+    use windows::Win32::System::RemoteDesktop::{
+        ProcessIdToSessionId, WTSDomainName, WTSEnumerateSessionsW, WTSFreeMemory,
+        WTSQuerySessionInformationW, WTSUserName, WTS_CURRENT_SERVER_HANDLE, WTS_INFO_CLASS,
+        WTS_SESSION_INFOW,
+    };
+    use windows::core::PWSTR;
+
+    let query = |id: u32, class: WTS_INFO_CLASS| -> Option<String> {
+        let mut buffer = PWSTR::null();
+        let mut len = 0u32;
+        unsafe {
+            WTSQuerySessionInformationW(WTS_CURRENT_SERVER_HANDLE, id, class, &mut buffer, &mut len)
+                .ok()?;
+            let value = buffer.to_string().ok();
+            WTSFreeMemory(buffer.0 as _);
+            value.filter(|v| !v.is_empty())
+        }
+    };
+
+    let mut current_session = u32::MAX;
+    unsafe {
+        let _ = ProcessIdToSessionId(std::process::id(), &mut current_session);
+    }
+
+    let mut info: *mut WTS_SESSION_INFOW = std::ptr::null_mut();
+    let mut count = 0u32;
+    unsafe {
+        WTSEnumerateSessionsW(WTS_CURRENT_SERVER_HANDLE, 0, 1, &mut info, &mut count)
+            .map_err(|e| e.to_string())?;
+    }
+
+    let sessions = unsafe {
+        let list = std::slice::from_raw_parts(info, count as usize)
+            .iter()
+            .map(|session| SessionInfo {
+                id: session.SessionId,
+                user: query(session.SessionId, WTSUserName),
+                domain: query(session.SessionId, WTSDomainName),
+                station: session.pWinStationName.to_string().ok().filter(|s| !s.is_empty()),
+                // WTS_CONNECTSTATE_CLASS 的取值顺序
+                state: match session.State.0 {
+                    0 => "active",
+                    1 => "connected",
+                    2 => "connect_query",
+                    3 => "shadow",
+                    4 => "disconnected",
+                    5 => "idle",
+                    6 => "listen",
+                    7 => "reset",
+                    8 => "down",
+                    _ => "init",
+                }
+                .to_string(),
+                current: session.SessionId == current_session,
+            })
+            // 会话 0 和监听会话没有交互用户
+            .filter(|s| s.user.is_some())
+            .collect();
+        WTSFreeMemory(info as _);
+        list
+    };
+
+    Ok(sessions)
+}
+
+/// 对指定会话执行锁定、断开或注销
+#[cfg(target_os = "windows")]
+pub fn session_action(id: u32, action: SessionAction) -> Result<(), String> {
+    use windows::Win32::System::RemoteDesktop::{
+        WTSDisconnectSession, WTSLogoffSession, WTS_CURRENT_SERVER_HANDLE,
+    };
+
+    let session = list_sessions()?
+        .into_iter()
+        .find(|s| s.id == id)
+        .ok_or_else(|| format!("Session {} not found", id))?;
+
+    unsafe {
+        match action {
+            // 其他会话无法直接调用 LockWorkStation，断开会话后重新连接同样需要输入密码
+            SessionAction::Lock if session.current => {
+                windows::Win32::System::Shutdown::LockWorkStation().map_err(|e| e.to_string())
+            }
+            SessionAction::Lock | SessionAction::Disconnect => {
+                WTSDisconnectSession(WTS_CURRENT_SERVER_HANDLE, id, false)
+                    .map_err(|e| e.to_string())
+            }
+            SessionAction::Logoff => {
+                WTSLogoffSession(WTS_CURRENT_SERVER_HANDLE, id, false).map_err(|e| e.to_string())
+            }
+        }
+    }
+}
+
+/// 通过 loginctl 列出会话
+#[cfg(target_os = "linux")]
+pub fn list_sessions() -> Result<Vec<SessionInfo>, String> {
+    let output = Command::new("loginctl")
+        .args(["list-sessions", "--no-legend"])
+        .output()
+        .map_err(|e| format!("Failed to run loginctl: {}", e))?;
+    let current = std::env::var("XDG_SESSION_ID").ok();
+
+    // 格式：SESSION UID USER SEAT TTY ...
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let id = fields.first()?.parse::<u32>().ok()?;
+            let show = Command::new("loginctl")
+                .args(["show-session", &id.to_string(), "-p", "State", "-p", "Seat"])
+                .output()
+                .ok()?;
+            let props = String::from_utf8_lossy(&show.stdout).to_string();
+            let prop = |key: &str| {
+                props
+                    .lines()
+                    .find_map(|l| l.strip_prefix(key)?.strip_prefix('='))
+                    .map(|v| v.trim().to_string())
+                    .filter(|v| !v.is_empty())
+            };
+            Some(SessionInfo {
+                id,
+                user: fields.get(2).map(|s| s.to_string()),
+                domain: None,
+                station: prop("Seat"),
+                state: prop("State").unwrap_or_else(|| "unknown".to_string()),
+                current: current.as_deref() == Some(fields[0]),
+            })
+        })
+        .collect())
+}
+
+#[cfg(target_os = "linux")]
+pub fn session_action(id: u32, action: SessionAction) -> Result<(), String> {
+    let verb = match action {
+        SessionAction::Lock => "lock-session",
+        SessionAction::Logoff => "terminate-session",
+        SessionAction::Disconnect => {
+            return Err("Disconnecting sessions is not supported on Linux".to_string())
+        }
+    };
+
+    let output = Command::new("loginctl")
+        .args([verb, &id.to_string()])
+        .output()
+        .map_err(|e| format!("Failed to run loginctl: {}", e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub fn list_sessions() -> Result<Vec<SessionInfo>, String> {
+    Err("Session listing is not supported on macOS".to_string())
+}
+
+#[cfg(target_os = "macos")]
+pub fn session_action(_id: u32, _action: SessionAction) -> Result<(), String> {
+    Err("Session actions are not supported on macOS".to_string())
+}
@@ -0,0 +1,272 @@
+use axum::extract::ws::{Message, WebSocket};
+use futures::StreamExt;
+use portable_pty::{native_pty_system, CommandBuilder, PtySize};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::sync::mpsc;
+
+use crate::api::log_to_ui;
+use crate::payload::{FrameCipher, PayloadSession};
+
+/// 同时打开的终端数上限
+const MAX_SESSIONS: usize = 4;
+/// 每次读取终端输出的缓冲区大小
+const READ_BUFFER_SIZE: usize = 8 * 1024;
+
+static ACTIVE_SESSIONS: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ShellInput {
+    Input { data: String },
+    Resize { cols: u16, rows: u16 },
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ShellControl {
+    Exit { code: Option<u32> },
+    Error { message: String },
+}
+
+/// 终端会话计数，drop 时释放名额
+pub struct SessionSlot(());
+
+impl Drop for SessionSlot {
+    fn drop(&mut self) {
+        ACTIVE_SESSIONS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// 占用一个终端名额，已达上限时返回 None
+pub fn acquire_slot() -> Option<SessionSlot> {
+    ACTIVE_SESSIONS
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+            (n < MAX_SESSIONS).then_some(n + 1)
+        })
+        .ok()
+        .map(|_| SessionSlot(()))
+}
+
+/// 系统默认的 shell
+fn default_shell() -> CommandBuilder {
+    #[cfg(target_os = "windows")]
+    {
+        CommandBuilder::new("powershell.exe")
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        CommandBuilder::new(std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string()))
+    }
+}
+
+fn pty_size(cols: u16, rows: u16) -> PtySize {
+    PtySize {
+        rows: rows.clamp(1, 500),
+        cols: cols.clamp(1, 500),
+        pixel_width: 0,
+        pixel_height: 0,
+    }
+}
+
+async fn send_control(
+    socket: &mut WebSocket,
+    cipher: &mut Option<FrameCipher>,
+    control: &ShellControl,
+) {
+    let Ok(text) = serde_json::to_string(control) else {
+        return;
+    };
+    let text = match cipher {
+        Some(cipher) => match cipher.seal_text(&text) {
+            Ok(text) => text,
+            Err(_) => return,
+        },
+        None => text,
+    };
+    let _ = socket.send(Message::Text(text)).await;
+}
+
+/// 发送终端输出，连接已断开时返回 false
+async fn send_output(
+    socket: &mut WebSocket,
+    cipher: &mut Option<FrameCipher>,
+    data: Vec<u8>,
+) -> bool {
+    let data = match cipher {
+        Some(cipher) => match cipher.seal_binary(&data) {
+            Ok(data) => data,
+            Err(_) => return false,
+        },
+        None => data,
+    };
+    socket.send(Message::Binary(data)).await.is_ok()
+}
+
+/// 在伪终端中启动 shell，并在 WebSocket 与终端之间转发数据，直到任一方关闭
+///
+/// 客户端发送文本消息：{"type":"input","data":"..."} 写入终端，
+/// {"type":"resize","cols":80,"rows":24} 调整大小；服务端以二进制消息发送终端输出，
+/// shell 退出时发送 {"type":"exit","code":0} 后关闭连接。通过加密请求头连接的会话
+/// 逐帧加密（见 payload::FrameCipher）
+pub async fn run_session(
+    mut socket: WebSocket,
+    ip: String,
+    cols: u16,
+    rows: u16,
+    session: Option<PayloadSession>,
+    _slot: SessionSlot,
+) {
+    let mut outgoing = session
+        .as_ref()
+        .map(|session| FrameCipher::outgoing(session, "/ws/shell"));
+    let mut incoming = session
+        .as_ref()
+        .map(|session| FrameCipher::incoming(session, "/ws/shell"));
+    let pair = match native_pty_system().openpty(pty_size(cols, rows)) {
+        Ok(pair) => pair,
+        Err(e) => {
+            log::error!("[Shell] [{}] Failed to open terminal: {}", ip, e);
+            let message = format!("Failed to open terminal: {}", e);
+            send_control(&mut socket, &mut outgoing, &ShellControl::Error { message }).await;
+            return;
+        }
+    };
+    let mut child = match pair.slave.spawn_command(default_shell()) {
+        Ok(child) => child,
+        Err(e) => {
+            log::error!("[Shell] [{}] Failed to start shell: {}", ip, e);
+            let message = format!("Failed to start shell: {}", e);
+            send_control(&mut socket, &mut outgoing, &ShellControl::Error { message }).await;
+            return;
+        }
+    };
+    // 子进程持有从端，父进程关闭后 shell 退出时读取端才能收到 EOF
+    drop(pair.slave);
+    let master = pair.master;
+
+    let (reader, writer) = match (master.try_clone_reader(), master.take_writer()) {
+        (Ok(reader), Ok(writer)) => (reader, writer),
+        (Err(e), _) | (_, Err(e)) => {
+            let _ = child.kill();
+            let message = format!("Failed to attach terminal: {}", e);
+            send_control(&mut socket, &mut outgoing, &ShellControl::Error { message }).await;
+            return;
+        }
+    };
+
+    log::info!("[Shell] [{}] Session started", ip);
+    log_to_ui("warn", &format!("[{}] Remote shell session started", ip));
+
+    // 终端读写是阻塞操作，放在单独的线程中
+    let (output_tx, mut output_rx) = mpsc::channel::<Vec<u8>>(32);
+    std::thread::spawn(move || {
+        let mut reader = reader;
+        let mut buffer = [0u8; READ_BUFFER_SIZE];
+        loop {
+            match reader.read(&mut buffer) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    if output_tx.blocking_send(buffer[..n].to_vec()).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+    let (input_tx, input_rx) = std::sync::mpsc::channel::<Vec<u8>>();
+    std::thread::spawn(move || {
+        let mut writer = writer;
+        while let Ok(data) = input_rx.recv() {
+            if writer
+                .write_all(&data)
+                .and_then(|_| writer.flush())
+                .is_err()
+            {
+                break;
+            }
+        }
+    });
+
+    // shell 启动的后台进程可能仍持有从端，读取端不一定收到 EOF，因此单独等待 shell 退出
+    let mut killer = child.clone_killer();
+    let mut exited = tokio::task::spawn_blocking(move || {
+        child.wait().ok().map(|status| status.exit_code())
+    });
+    let mut output_closed = false;
+
+    loop {
+        tokio::select! {
+            output = output_rx.recv(), if !output_closed => match output {
+                Some(data) => {
+                    if !send_output(&mut socket, &mut outgoing, data).await {
+                        break;
+                    }
+                }
+                // 终端已关闭，等待 shell 退出
+                None => output_closed = true,
+            },
+            code = &mut exited => {
+                // 关闭终端，读取线程随之结束；先发出已读取的输出
+                drop(master);
+                while let Ok(data) = output_rx.try_recv() {
+                    if !send_output(&mut socket, &mut outgoing, data).await {
+                        break;
+                    }
+                }
+                let code = code.ok().flatten();
+                send_control(&mut socket, &mut outgoing, &ShellControl::Exit { code }).await;
+                let _ = socket.send(Message::Close(None)).await;
+                log::info!("[Shell] [{}] Session closed", ip);
+                log_to_ui("info", &format!("[{}] Remote shell session closed", ip));
+                return;
+            }
+            message = socket.next() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    let text = match incoming.as_mut().map(|cipher| cipher.open_text(&text)) {
+                        Some(Ok(text)) => text,
+                        Some(Err(e)) => {
+                            log::warn!("[Shell] [{}] Invalid encrypted frame: {}", ip, e);
+                            break;
+                        }
+                        None => text,
+                    };
+                    match serde_json::from_str::<ShellInput>(&text) {
+                        Ok(ShellInput::Input { data }) => {
+                            if input_tx.send(data.into_bytes()).is_err() {
+                                break;
+                            }
+                        }
+                        Ok(ShellInput::Resize { cols, rows }) => {
+                            if let Err(e) = master.resize(pty_size(cols, rows)) {
+                                log::debug!("[Shell] [{}] Resize failed: {}", ip, e);
+                            }
+                        }
+                        Err(e) => log::debug!("[Shell] [{}] Ignored message: {}", ip, e),
+                    }
+                }
+                Some(Ok(Message::Binary(data))) => {
+                    let data = match incoming.as_mut().map(|cipher| cipher.open_binary(&data)) {
+                        Some(Ok(data)) => data,
+                        Some(Err(e)) => {
+                            log::warn!("[Shell] [{}] Invalid encrypted frame: {}", ip, e);
+                            break;
+                        }
+                        None => data,
+                    };
+                    if input_tx.send(data).is_err() {
+                        break;
+                    }
+                }
+                Some(Ok(Message::Close(_))) | None | Some(Err(_)) => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+
+    // 客户端断开时结束 shell，读写线程随之退出
+    let _ = killer.kill();
+    log::info!("[Shell] [{}] Session closed", ip);
+    log_to_ui("info", &format!("[{}] Remote shell session closed", ip));
+}
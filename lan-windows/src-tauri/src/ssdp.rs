@@ -0,0 +1,206 @@
+use rand::Rng;
+use socket2::{Domain, Protocol, Socket, Type};
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::sync::watch;
+
+use crate::device_id::DeviceId;
+
+/// SSDP 多播地址和端口
+const SSDP_ADDR: Ipv4Addr = Ipv4Addr::new(239, 255, 255, 250);
+const SSDP_PORT: u16 = 1900;
+/// 本服务的搜索目标，客户端按此查找
+pub const SEARCH_TARGET: &str = "urn:lan-device-manager:service:agent:1";
+/// 广播有效期（秒）
+const MAX_AGE_SECS: u64 = 1800;
+/// 定期发送 ssdp:alive 的间隔，小于有效期的一半
+const NOTIFY_INTERVAL: Duration = Duration::from_secs(600);
+/// 响应的最大随机延迟，避免多台设备同时回复
+const MAX_RESPONSE_DELAY_MS: u64 = 1000;
+
+/// SSDP 响应器，作为 mDNS 之外的另一种发现方式
+///
+/// 部分企业网络屏蔽了 mDNS 但允许 SSDP
+pub struct SsdpResponder {
+    shutdown: watch::Sender<bool>,
+}
+
+/// 广播内容
+#[derive(Clone)]
+struct Advertisement {
+    uuid: String,
+    name: String,
+    port: u16,
+}
+
+impl Advertisement {
+    fn usn(&self) -> String {
+        format!("uuid:{}::{}", self.uuid, SEARCH_TARGET)
+    }
+
+    /// 公共头部，X- 开头的字段与 mDNS TXT 记录对应
+    fn headers(&self, local_ip: Ipv4Addr) -> String {
+        format!(
+            "CACHE-CONTROL: max-age={}\r\n\
+             LOCATION: http://{}:{}/api/health\r\n\
+             SERVER: {}/1.0 UPnP/1.1 lan-device-manager/{}\r\n\
+             X-DEVICE-NAME: {}\r\n\
+             X-DEVICE-UUID: {}\r\n\
+             X-DEVICE-VERSION: {}\r\n\
+             X-DEVICE-AUTH: required\r\n",
+            MAX_AGE_SECS,
+            local_ip,
+            self.port,
+            std::env::consts::OS,
+            env!("CARGO_PKG_VERSION"),
+            self.name,
+            self.uuid,
+            env!("CARGO_PKG_VERSION"),
+        )
+    }
+
+    fn search_response(&self, st: &str, local_ip: Ipv4Addr) -> String {
+        format!(
+            "HTTP/1.1 200 OK\r\nEXT:\r\nST: {}\r\nUSN: {}\r\n{}\r\n",
+            st,
+            self.usn(),
+            self.headers(local_ip)
+        )
+    }
+
+    fn notify(&self, nts: &str, local_ip: Ipv4Addr) -> String {
+        format!(
+            "NOTIFY * HTTP/1.1\r\nHOST: {}:{}\r\nNT: {}\r\nNTS: {}\r\nUSN: {}\r\n{}\r\n",
+            SSDP_ADDR,
+            SSDP_PORT,
+            SEARCH_TARGET,
+            nts,
+            self.usn(),
+            self.headers(local_ip)
+        )
+    }
+}
+
+/// 解析 M-SEARCH 请求，返回搜索目标和 MX（最大等待秒数）
+fn parse_search(message: &str) -> Option<(String, u64)> {
+    let mut lines = message.lines();
+    if !lines.next()?.trim().starts_with("M-SEARCH") {
+        return None;
+    }
+    let mut st = None;
+    let mut mx = 1;
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        match name.trim().to_ascii_uppercase().as_str() {
+            "ST" => st = Some(value.trim().to_string()),
+            "MX" => mx = value.trim().parse().unwrap_or(1),
+            _ => {}
+        }
+    }
+    st.map(|st| (st, mx))
+}
+
+/// 向请求方回复时使用的本机地址
+fn local_ip_for(peer: SocketAddr) -> Option<Ipv4Addr> {
+    // 只选择路由，不会真正发送数据
+    let socket = std::net::UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect(peer).ok()?;
+    match socket.local_addr().ok()?.ip() {
+        std::net::IpAddr::V4(ip) => Some(ip),
+        std::net::IpAddr::V6(_) => None,
+    }
+}
+
+fn bind_multicast() -> std::io::Result<UdpSocket> {
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+    // Windows 自带的 SSDP 服务同样监听 1900 端口
+    socket.set_reuse_address(true)?;
+    socket.bind(&SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, SSDP_PORT).into())?;
+    socket.join_multicast_v4(&SSDP_ADDR, &Ipv4Addr::UNSPECIFIED)?;
+    socket.set_multicast_ttl_v4(2)?;
+    socket.set_nonblocking(true)?;
+    UdpSocket::from_std(socket.into())
+}
+
+fn device_name() -> String {
+    let display_name = crate::config::get_config().device_display_name;
+    if !display_name.trim().is_empty() {
+        return display_name.trim().to_string();
+    }
+    hostname::get()
+        .ok()
+        .and_then(|h| h.into_string().ok())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+impl SsdpResponder {
+    pub fn start(port: u16) -> Result<Self, Box<dyn std::error::Error>> {
+        let socket = Arc::new(bind_multicast()?);
+        let advertisement = Advertisement {
+            uuid: DeviceId::get_or_create()?,
+            name: device_name(),
+            port,
+        };
+        let (shutdown, mut shutdown_rx) = watch::channel(false);
+        let group = SocketAddr::V4(SocketAddrV4::new(SSDP_ADDR, SSDP_PORT));
+
+        tokio::spawn(async move {
+            let mut buf = [0u8; 2048];
+            let mut notify_timer = tokio::time::interval(NOTIFY_INTERVAL);
+            loop {
+                tokio::select! {
+                    _ = shutdown_rx.changed() => break,
+                    _ = notify_timer.tick() => {
+                        if let Some(ip) = local_ip_for(group) {
+                            let message = advertisement.notify("ssdp:alive", ip);
+                            let _ = socket.send_to(message.as_bytes(), group).await;
+                        }
+                    }
+                    received = socket.recv_from(&mut buf) => {
+                        let Ok((len, peer)) = received else {
+                            continue;
+                        };
+                        let message = String::from_utf8_lossy(&buf[..len]);
+                        let Some((st, mx)) = parse_search(&message) else {
+                            continue;
+                        };
+                        if st != "ssdp:all" && st != SEARCH_TARGET {
+                            continue;
+                        }
+                        let Some(ip) = local_ip_for(peer) else {
+                            continue;
+                        };
+                        log::debug!("SSDP search from {} for {}", peer, st);
+
+                        let response = advertisement.search_response(&st, ip);
+                        let socket = socket.clone();
+                        let max_delay = (mx.clamp(1, 5) * 1000).min(MAX_RESPONSE_DELAY_MS);
+                        let delay = rand::thread_rng().gen_range(0..max_delay);
+                        tokio::spawn(async move {
+                            tokio::time::sleep(Duration::from_millis(delay)).await;
+                            let _ = socket.send_to(response.as_bytes(), peer).await;
+                        });
+                    }
+                }
+            }
+
+            // 停止时通知客户端移除本设备
+            if let Some(ip) = local_ip_for(group) {
+                let message = advertisement.notify("ssdp:byebye", ip);
+                let _ = socket.send_to(message.as_bytes(), group).await;
+            }
+            log::info!("SSDP responder stopped");
+        });
+
+        log::info!("SSDP responder started on port {}", SSDP_PORT);
+        Ok(Self { shutdown })
+    }
+
+    pub fn stop(&self) {
+        let _ = self.shutdown.send(true);
+    }
+}
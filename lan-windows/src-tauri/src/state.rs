@@ -1,37 +1,61 @@
 use crate::{
-    api::ApiServer,
+    api::{ApiServer, ExitSignal},
     auth::AuthManager,
     command::CommandExecutor,
-    logger::write_log_to_file,
+    grpc::GrpcServer,
+    logger::{record, APP_LOGS},
     mdns::MdnsService,
     models::{LogEntry, LogLevel, ServerStatus},
+    ssdp::SsdpResponder,
+    websocket::WsMessage,
 };
-use std::sync::Arc;
+use std::sync::{Arc, RwLock as StdRwLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
 use tokio::sync::Mutex;
 
+/// 应用状态
+///
+/// 各组件独立加锁，只读查询（状态、日志）不会等待命令执行或服务器启停
 pub struct AppState {
     pub auth_manager: AuthManager,
     pub command_executor: CommandExecutor,
     pub logger: Logger,
+    /// 服务器句柄，仅在启动/停止服务器时持有
+    pub server: Mutex<ServerHandles>,
+    status: StdRwLock<ServerStatus>,
+    app_handle: StdRwLock<Option<AppHandle>>,
+}
+
+/// mDNS 注册失败后首次重试的等待时间，之后每次翻倍
+const MDNS_RETRY_INITIAL: Duration = Duration::from_secs(5);
+/// mDNS 重试的最长等待时间
+const MDNS_RETRY_MAX: Duration = Duration::from_secs(120);
+
+/// 空闲自动停止的检查间隔
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 创建并注册 mDNS 服务，auth_required 为当前是否已设置密码
+fn start_mdns(port: u16, auth_required: bool) -> Result<MdnsService, String> {
+    let mut mdns = MdnsService::new(port, auth_required).map_err(|e| e.to_string())?;
+    mdns.start().map_err(|e| e.to_string())?;
+    Ok(mdns)
+}
+
+/// API 服务器与 mDNS/SSDP 服务句柄
+#[derive(Default)]
+pub struct ServerHandles {
     pub mdns_service: Option<MdnsService>,
+    pub ssdp_responder: Option<SsdpResponder>,
+    pub grpc_server: Option<GrpcServer>,
     pub api_server: Option<Arc<Mutex<ApiServer>>>,
-    pub status: ServerStatus,
 }
 
-pub struct Logger {
-    logs: Vec<LogEntry>,
-    max_logs: usize,
-}
+/// 应用日志，写入内存（logger::APP_LOGS）和日志文件，按配置的最低级别过滤
+pub struct Logger;
 
 impl Logger {
-    pub fn new(max_logs: usize) -> Self {
-        Self {
-            logs: Vec::new(),
-            max_logs,
-        }
-    }
-
-    pub fn log(&mut self, level: LogLevel, category: &str, message: &str, source: Option<&str>) {
+    pub fn log(&self, level: LogLevel, category: &str, message: &str, source: Option<&str>) {
         let entry = LogEntry {
             timestamp: chrono::Local::now(),
             level,
@@ -40,49 +64,34 @@ impl Logger {
             source: source.map(|s| s.to_string()),
         };
 
-        // 写入到内存日志
-        self.logs.push(entry.clone());
-
-        if self.logs.len() > self.max_logs {
-            self.logs.remove(0);
-        }
-
-        // 写入到文件日志
-        write_log_to_file(&entry);
+        // 写入到文件日志和内存日志
+        record(&APP_LOGS, entry);
     }
 
-    pub fn info(&mut self, category: &str, message: &str) {
+    pub fn info(&self, category: &str, message: &str) {
         self.log(LogLevel::Info, category, message, None);
         log::info!("[{}] {}", category, message);
     }
 
-    pub fn warn(&mut self, category: &str, message: &str) {
+    pub fn warn(&self, category: &str, message: &str) {
         self.log(LogLevel::Warn, category, message, None);
         log::warn!("[{}] {}", category, message);
     }
 
-    pub fn error(&mut self, category: &str, message: &str) {
+    pub fn error(&self, category: &str, message: &str) {
         self.log(LogLevel::Error, category, message, None);
         log::error!("[{}] {}", category, message);
     }
 
-    pub fn success(&mut self, category: &str, message: &str) {
+    pub fn success(&self, category: &str, message: &str) {
         self.log(LogLevel::Success, category, message, None);
         log::info!("[{}] ✓ {}", category, message);
     }
 
-    pub fn system(&mut self, category: &str, message: &str) {
+    pub fn system(&self, category: &str, message: &str) {
         self.log(LogLevel::System, category, message, None);
         log::info!("[{}] ⚙ {}", category, message);
     }
-
-    pub fn get_logs(&self, limit: usize) -> Vec<LogEntry> {
-        self.logs.iter().rev().take(limit).cloned().collect()
-    }
-
-    pub fn clear_logs(&mut self) {
-        self.logs.clear();
-    }
 }
 
 impl Default for AppState {
@@ -93,24 +102,52 @@ impl Default for AppState {
 
 impl AppState {
     pub fn new() -> Self {
-        let mut logger = Logger::new(500);
+        let logger = Logger;
         logger.system("Init", "Application state initialized");
 
         Self {
             auth_manager: AuthManager::new(),
             command_executor: CommandExecutor::new(),
             logger,
-            mdns_service: None,
-            api_server: None,
-            status: ServerStatus::default(),
+            server: Mutex::new(ServerHandles::default()),
+            status: StdRwLock::new(ServerStatus::default()),
+            app_handle: StdRwLock::new(None),
         }
     }
 
-    pub async fn start_server(&mut self, port: u16) -> Result<String, Box<dyn std::error::Error>> {
-        if self.status.running {
+    /// 设置 Tauri 应用句柄，用于向前端发送事件
+    pub fn set_app_handle(&self, handle: AppHandle) {
+        if let Ok(mut app_handle) = self.app_handle.write() {
+            *app_handle = Some(handle);
+        }
+    }
+
+    /// 向前端发送事件
+    pub fn emit<S: serde::Serialize + Clone>(&self, event: &str, payload: S) {
+        if let Ok(app_handle) = self.app_handle.read() {
+            if let Some(handle) = app_handle.as_ref() {
+                let _ = handle.emit(event, payload);
+            }
+        }
+    }
+
+    pub async fn start_server(self: &Arc<Self>, port: u16) -> Result<String, Box<dyn std::error::Error>> {
+        let mut handles = self.server.lock().await;
+
+        if self.get_status().running {
             return Err("Server is already running".into());
         }
 
+        if !crate::trusted_network::is_trusted() {
+            // 回到受信任网络后由网络监视任务启动
+            crate::trusted_network::mark_paused();
+            self.logger.warn(
+                "Network",
+                "Not on a trusted network, server will start when one is joined",
+            );
+            return Err("Not on a trusted network".into());
+        }
+
         self.logger
             .system("Server", &format!("Starting server on port {}", port));
 
@@ -124,17 +161,67 @@ impl AppState {
             server.start().await?;
         }
 
-        self.api_server = Some(api_server);
-
-        // Start mDNS service
-        let mut mdns = MdnsService::new(port)?;
-        mdns.start()?;
-        self.mdns_service = Some(mdns);
+        let exit_signal = api_server.lock().await.exit_signal();
+        handles.api_server = Some(api_server.clone());
+        // 空闲计时从启动时开始
+        crate::access::mark_active();
+        self.spawn_idle_monitor(api_server.clone());
+        self.spawn_supervisor(api_server, exit_signal);
+
+        // Start mDNS service，隐身模式下不广播
+        // 注册失败不影响 API 服务器，客户端仍可通过 IP 连接，后台继续重试
+        let config = crate::config::get_config();
+        let mut discovery_error = None;
+        if config.enable_mdns {
+            match start_mdns(port, self.auth_manager.is_password_set()) {
+                Ok(mdns) => handles.mdns_service = Some(mdns),
+                Err(e) => {
+                    self.logger.warn(
+                        "Server",
+                        &format!("Failed to start mDNS advertising, will retry: {}", e),
+                    );
+                    discovery_error = Some(e);
+                }
+            }
+        } else {
+            self.logger
+                .info("Server", "mDNS advertising disabled, server is reachable by IP only");
+        }
+        // SSDP 启动失败（如端口被占用）不影响服务器运行
+        if config.enable_ssdp {
+            match SsdpResponder::start(port) {
+                Ok(responder) => handles.ssdp_responder = Some(responder),
+                Err(e) => self
+                    .logger
+                    .warn("Server", &format!("Failed to start SSDP responder: {}", e)),
+            }
+        }
+        // gRPC 与 SSDP 一样是可选功能，启动失败只记录警告
+        if config.grpc_port != 0 {
+            let bind_addr = config
+                .bind_addr
+                .parse()
+                .unwrap_or(std::net::IpAddr::V4(std::net::Ipv4Addr::UNSPECIFIED));
+            match GrpcServer::start(bind_addr, config.grpc_port, self.auth_manager.clone()).await {
+                Ok(server) => handles.grpc_server = Some(server),
+                Err(e) => self
+                    .logger
+                    .warn("Server", &format!("Failed to start gRPC server: {}", e)),
+            }
+        }
+        let advertising = handles.mdns_service.is_some() || handles.ssdp_responder.is_some();
 
         // Update status
-        self.status.running = true;
-        self.status.port = Some(port);
-        self.status.ip_address = get_local_ip();
+        self.update_status(|status| {
+            status.running = true;
+            status.port = Some(port);
+            status.ip_address = get_local_ip();
+            status.advertising = advertising;
+            status.discovery_error = discovery_error.clone();
+        });
+        if discovery_error.is_some() {
+            self.spawn_mdns_retry(port);
+        }
 
         self.logger.success(
             "Server",
@@ -144,8 +231,10 @@ impl AppState {
         Ok(format!("Server started on port {}", port))
     }
 
-    pub async fn stop_server(&mut self) -> Result<String, Box<dyn std::error::Error>> {
-        if !self.status.running {
+    pub async fn stop_server(&self) -> Result<String, Box<dyn std::error::Error>> {
+        let mut handles = self.server.lock().await;
+
+        if !self.get_status().running {
             return Err("Server is not running".into());
         }
 
@@ -153,7 +242,7 @@ impl AppState {
             .system("Server", "Stopping server immediately...");
 
         // 首先立即停止 API 服务器（最重要）
-        if let Some(api_server) = &self.api_server {
+        if let Some(api_server) = &handles.api_server {
             let mut server = api_server.lock().await;
             // 使用较短的超时时间，确保快速关闭
             let stop_result =
@@ -172,25 +261,345 @@ impl AppState {
                 }
             }
         }
-        self.api_server = None;
+        handles.api_server = None;
 
         // 然后停止 mDNS 服务
-        if let Some(mdns) = &self.mdns_service {
+        if let Some(mdns) = &handles.mdns_service {
             let _ = mdns.stop();
         }
-        self.mdns_service = None;
+        handles.mdns_service = None;
+        if let Some(responder) = handles.ssdp_responder.take() {
+            responder.stop();
+        }
+        if let Some(server) = handles.grpc_server.take() {
+            server.stop();
+        }
 
         // Update status
-        self.status.running = false;
-        self.status.port = None;
+        self.update_status(|status| {
+            status.running = false;
+            status.port = None;
+            status.advertising = false;
+            status.discovery_error = None;
+        });
 
         self.logger.success("Server", "Server stopped successfully");
 
         Ok("Server stopped".to_string())
     }
 
+    /// 向所有已连接的客户端发送通知，返回通知ID
+    pub async fn notify_clients(
+        &self,
+        title: &str,
+        message: &str,
+        level: &str,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let api_server = match &self.server.lock().await.api_server {
+            Some(server) => server.clone(),
+            None => return Err("Server is not running".into()),
+        };
+
+        let id = uuid::Uuid::new_v4().to_string();
+        let delivered = api_server
+            .lock()
+            .await
+            .broadcast(WsMessage::Notification {
+                id: id.clone(),
+                title: title.to_string(),
+                message: message.to_string(),
+                level: level.to_string(),
+            })
+            .await;
+
+        self.logger.info(
+            "Notify",
+            &format!("Notification '{}' sent to {} client(s)", title, delivered),
+        );
+
+        Ok(id)
+    }
+
+    /// 监视 API 服务器任务，意外退出时按退避策略自动重启
+    fn spawn_supervisor(self: &Arc<Self>, api_server: Arc<Mutex<ApiServer>>, exit_signal: ExitSignal) {
+        let state = self.clone();
+
+        tokio::spawn(async move {
+            let mut attempt: u32 = 0;
+
+            loop {
+                if !exit_signal.wait().await {
+                    // 主动停止，监视结束
+                    return;
+                }
+
+                let config = crate::config::get_config();
+                state.logger.error("Server", "API server exited unexpectedly");
+                state.update_status(|status| status.running = false);
+
+                let will_restart =
+                    config.auto_restart_api && attempt < config.auto_restart_max_attempts;
+                state.emit(
+                    "server-crashed",
+                    serde_json::json!({
+                        "attempt": attempt,
+                        "restarting": will_restart,
+                    }),
+                );
+
+                if !will_restart {
+                    break;
+                }
+
+                // 指数退避：1s, 2s, 4s ... 最多 60s
+                let delay = Duration::from_secs((1u64 << attempt.min(6)).min(60));
+                attempt += 1;
+                state.logger.warn(
+                    "Server",
+                    &format!(
+                        "Restarting API server in {}s (attempt {}/{})",
+                        delay.as_secs(),
+                        attempt,
+                        config.auto_restart_max_attempts
+                    ),
+                );
+                tokio::time::sleep(delay).await;
+
+                // 退避期间用户可能已停止或替换了服务器
+                let still_current = state
+                    .server
+                    .lock()
+                    .await
+                    .api_server
+                    .as_ref()
+                    .map(|current| Arc::ptr_eq(current, &api_server))
+                    .unwrap_or(false);
+                if exit_signal.stop_requested() || !still_current {
+                    return;
+                }
+
+                let result = api_server.lock().await.start().await;
+                match result {
+                    Ok(()) => {
+                        attempt = 0;
+                        state.logger.success("Server", "API server restarted");
+                        state.update_status(|status| status.running = true);
+                    }
+                    Err(e) => {
+                        state
+                            .logger
+                            .error("Server", &format!("API server restart failed: {}", e));
+                        // 重启失败时不会再收到退出信号，直接进入下一轮判断
+                        exit_signal.notify_exited();
+                    }
+                }
+            }
+
+            // 放弃重启，清理残留的服务句柄
+            let mut handles = state.server.lock().await;
+            if handles
+                .api_server
+                .as_ref()
+                .map(|current| Arc::ptr_eq(current, &api_server))
+                .unwrap_or(false)
+            {
+                handles.api_server = None;
+                if let Some(mdns) = handles.mdns_service.take() {
+                    let _ = mdns.stop();
+                }
+                if let Some(responder) = handles.ssdp_responder.take() {
+                    responder.stop();
+                }
+                if let Some(server) = handles.grpc_server.take() {
+                    server.stop();
+                }
+                state.update_status(|status| {
+                    status.running = false;
+                    status.port = None;
+                    status.advertising = false;
+                    status.discovery_error = None;
+                });
+                state
+                    .logger
+                    .error("Server", "API server could not be recovered, server stopped");
+            }
+        });
+    }
+
+    /// 配置了 idle_stop_minutes 时，长时间没有认证通过的请求就停止服务器，之后可从托盘再次启动
+    fn spawn_idle_monitor(self: &Arc<Self>, api_server: Arc<Mutex<ApiServer>>) {
+        let state = self.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(IDLE_CHECK_INTERVAL).await;
+
+                // 服务器已停止或重启时结束，由新的实例重新监视
+                let current = state
+                    .server
+                    .lock()
+                    .await
+                    .api_server
+                    .as_ref()
+                    .map(|current| Arc::ptr_eq(current, &api_server))
+                    .unwrap_or(false);
+                if !current {
+                    return;
+                }
+
+                let minutes = crate::config::get_config().idle_stop_minutes;
+                if minutes == 0 || crate::access::idle_for() < Duration::from_secs(minutes * 60) {
+                    continue;
+                }
+
+                state.logger.system(
+                    "Server",
+                    &format!("No authenticated requests for {} minutes, stopping server", minutes),
+                );
+                if state.stop_server().await.is_ok() {
+                    crate::show_notification(
+                        "LanDevice Manager",
+                        "Server stopped after being idle. Start it again from the tray",
+                    );
+                }
+                return;
+            }
+        });
+    }
+
+    /// mDNS 注册失败后在后台重试，成功、服务器停止或广播被暂停时结束
+    fn spawn_mdns_retry(self: &Arc<Self>, port: u16) {
+        let state = self.clone();
+        tokio::spawn(async move {
+            let mut delay = MDNS_RETRY_INITIAL;
+            loop {
+                tokio::time::sleep(delay).await;
+
+                let mut handles = state.server.lock().await;
+                let status = state.get_status();
+                if !status.running
+                    || status.port != Some(port)
+                    || status.discovery_error.is_none()
+                    || handles.mdns_service.is_some()
+                {
+                    return;
+                }
+
+                match start_mdns(port, state.auth_manager.is_password_set()) {
+                    Ok(mdns) => {
+                        handles.mdns_service = Some(mdns);
+                        state.update_status(|status| {
+                            status.advertising = true;
+                            status.discovery_error = None;
+                        });
+                        state.logger.success("Server", "mDNS advertising started after retry");
+                        return;
+                    }
+                    Err(e) => {
+                        log::warn!("mDNS registration retry failed: {}", e);
+                        state.update_status(|status| status.discovery_error = Some(e));
+                        delay = (delay * 2).min(MDNS_RETRY_MAX);
+                    }
+                }
+            }
+        });
+    }
+
+    /// 暂停或恢复 mDNS 广播，不影响正在运行的 API 服务器
+    pub async fn set_advertising(&self, enabled: bool) -> Result<String, Box<dyn std::error::Error>> {
+        let mut handles = self.server.lock().await;
+        let status = self.get_status();
+        let Some(port) = status.port.filter(|_| status.running) else {
+            return Err("Server is not running".into());
+        };
+
+        if enabled {
+            if handles.mdns_service.is_none() {
+                handles.mdns_service =
+                    Some(start_mdns(port, self.auth_manager.is_password_set())?);
+                self.logger.info("Server", "mDNS advertising resumed");
+            }
+            if handles.ssdp_responder.is_none() && crate::config::get_config().enable_ssdp {
+                handles.ssdp_responder = Some(SsdpResponder::start(port)?);
+                self.logger.info("Server", "SSDP responder resumed");
+            }
+        } else {
+            if let Some(mdns) = handles.mdns_service.take() {
+                let _ = mdns.stop();
+                self.logger.info("Server", "mDNS advertising paused");
+            }
+            if let Some(responder) = handles.ssdp_responder.take() {
+                responder.stop();
+                self.logger.info("Server", "SSDP responder paused");
+            }
+        }
+
+        // 手动暂停或恢复成功后不再需要后台重试
+        self.update_status(|status| {
+            status.advertising = enabled;
+            status.discovery_error = None;
+        });
+        crate::events::publish(WsMessage::StatusChanged {
+            field: "advertising".to_string(),
+            value: serde_json::json!(enabled),
+        });
+        Ok(if enabled {
+            "Advertising resumed".to_string()
+        } else {
+            "Advertising paused".to_string()
+        })
+    }
+
+    /// 密码设置或清除后更新 mDNS 广播中的 auth 标记，避免客户端对无密码设备显示锁图标
+    pub async fn refresh_mdns_auth(&self) {
+        let mut handles = self.server.lock().await;
+        if let Some(mdns) = handles.mdns_service.as_mut() {
+            if let Err(e) = mdns.set_auth_required(self.auth_manager.is_password_set()) {
+                log::warn!("Failed to update mDNS auth flag: {}", e);
+            }
+        }
+    }
+
+    /// 重新生成设备UUID，正在广播时需重启服务器才会使用新的UUID
+    pub fn regenerate_device_uuid(&self) -> Result<String, String> {
+        let uuid = crate::device_id::DeviceId::regenerate().map_err(|e| e.to_string())?;
+        self.reload_device_uuid();
+        self.logger.system(
+            "Config",
+            "Device UUID regenerated, clients will see a new device. Restart the server to advertise it",
+        );
+        Ok(uuid)
+    }
+
+    /// 设备UUID文件变化后（重新生成或导入配置）更新状态中的UUID
+    pub fn reload_device_uuid(&self) {
+        let uuid = crate::device_id::DeviceId::get_or_create().ok();
+        self.update_status(|status| status.device_uuid = uuid);
+    }
+
     pub fn get_status(&self) -> ServerStatus {
-        self.status.clone()
+        match self.status.read() {
+            Ok(status) => status.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    /// 更新服务器状态并通知前端
+    fn update_status<F>(&self, f: F)
+    where
+        F: FnOnce(&mut ServerStatus),
+    {
+        match self.status.write() {
+            Ok(mut status) => f(&mut status),
+            Err(poisoned) => f(&mut poisoned.into_inner()),
+        }
+        let status = self.get_status();
+        self.emit("server-status-changed", status.clone());
+
+        if let Ok(app_handle) = self.app_handle.read() {
+            if let Some(handle) = app_handle.as_ref() {
+                crate::tray::refresh(handle, &status);
+            }
+        }
     }
 }
 
@@ -0,0 +1,145 @@
+use chrono::{DateTime, Local};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// 最多跟踪的客户端IP数量，超出时淘汰最久未访问的
+const MAX_TRACKED_CLIENTS: usize = 1000;
+/// 每个客户端单独统计的接口数上限，路径由客户端决定，超出的请求计入 OTHER_ENDPOINTS
+const MAX_ENDPOINTS_PER_CLIENT: usize = 64;
+/// 未匹配任何路由（404）或超出上限的请求合并统计的键
+const OTHER_ENDPOINTS: &str = "other";
+
+/// 认证结果
+#[derive(Debug, Clone, Copy)]
+pub enum AuthOutcome {
+    LoginSuccess,
+    LoginFailure,
+    TokenRejected,
+}
+
+/// 单个客户端IP的访问统计
+#[derive(Debug, Clone, Serialize)]
+pub struct ClientStats {
+    pub ip: String,
+    pub request_count: u64,
+    pub first_seen: DateTime<Local>,
+    pub last_seen: DateTime<Local>,
+    /// 按 "METHOD path" 统计的请求数，404 和超出上限的接口合并为 "other"
+    pub endpoints: HashMap<String, u64>,
+    /// 按响应状态码统计的请求数
+    pub status_counts: HashMap<u16, u64>,
+    /// 状态码为 4xx/5xx 的请求数
+    pub failed_requests: u64,
+    pub total_latency_ms: u64,
+    pub max_latency_ms: u64,
+    pub login_success: u64,
+    pub login_failure: u64,
+    pub token_rejected: u64,
+}
+
+impl ClientStats {
+    fn new(ip: String) -> Self {
+        let now = Local::now();
+        Self {
+            ip,
+            request_count: 0,
+            first_seen: now,
+            last_seen: now,
+            endpoints: HashMap::new(),
+            status_counts: HashMap::new(),
+            failed_requests: 0,
+            total_latency_ms: 0,
+            max_latency_ms: 0,
+            login_success: 0,
+            login_failure: 0,
+            token_rejected: 0,
+        }
+    }
+}
+
+// 全局客户端统计
+static CLIENT_STATS: Lazy<Mutex<HashMap<String, ClientStats>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 去掉端口号，只保留IP地址
+pub fn ip_only(addr: &str) -> String {
+    addr.parse::<SocketAddr>()
+        .map(|a| a.ip().to_string())
+        .unwrap_or_else(|_| addr.to_string())
+}
+
+fn with_client<F>(addr: &str, f: F)
+where
+    F: FnOnce(&mut ClientStats),
+{
+    let ip = ip_only(addr);
+    if let Ok(mut stats) = CLIENT_STATS.lock() {
+        if !stats.contains_key(&ip) && stats.len() >= MAX_TRACKED_CLIENTS {
+            let oldest = stats
+                .values()
+                .min_by_key(|s| s.last_seen)
+                .map(|s| s.ip.clone());
+            if let Some(oldest) = oldest {
+                stats.remove(&oldest);
+            }
+        }
+
+        let entry = stats
+            .entry(ip.clone())
+            .or_insert_with(|| ClientStats::new(ip));
+        entry.last_seen = Local::now();
+        f(entry);
+    }
+}
+
+/// 记录一次处理完成的请求及其状态码和耗时
+pub fn record_request(addr: &str, method: &str, path: &str, status: u16, latency: Duration) {
+    let latency_ms = latency.as_millis() as u64;
+    with_client(addr, |stats| {
+        stats.request_count += 1;
+        let mut endpoint = format!("{} {}", method, path);
+        if status == 404
+            || (!stats.endpoints.contains_key(&endpoint)
+                && stats.endpoints.len() >= MAX_ENDPOINTS_PER_CLIENT)
+        {
+            endpoint = OTHER_ENDPOINTS.to_string();
+        }
+        *stats.endpoints.entry(endpoint).or_insert(0) += 1;
+        *stats.status_counts.entry(status).or_insert(0) += 1;
+        if status >= 400 {
+            stats.failed_requests += 1;
+        }
+        stats.total_latency_ms += latency_ms;
+        stats.max_latency_ms = stats.max_latency_ms.max(latency_ms);
+    });
+}
+
+/// 记录认证结果
+pub fn record_auth(addr: &str, outcome: AuthOutcome) {
+    with_client(addr, |stats| match outcome {
+        AuthOutcome::LoginSuccess => stats.login_success += 1,
+        AuthOutcome::LoginFailure => stats.login_failure += 1,
+        AuthOutcome::TokenRejected => stats.token_rejected += 1,
+    });
+}
+
+/// 获取所有客户端统计，按最近访问时间倒序
+pub fn get_client_stats() -> Vec<ClientStats> {
+    let mut list: Vec<ClientStats> = match CLIENT_STATS.lock() {
+        Ok(stats) => stats.values().cloned().collect(),
+        Err(_) => Vec::new(),
+    };
+    list.sort_by(|a, b| b.last_seen.cmp(&a.last_seen));
+    list
+}
+
+/// 清空客户端统计
+pub fn clear_client_stats() {
+    if let Ok(mut stats) = CLIENT_STATS.lock() {
+        stats.clear();
+    }
+}
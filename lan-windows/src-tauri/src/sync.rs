@@ -0,0 +1,130 @@
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::config::AppConfig;
+
+/// 共享目录最多保存的设备数量
+pub const MAX_CATALOG_ENTRIES: usize = 1000;
+/// 名称、分组等文本字段的最大长度
+const MAX_FIELD_LEN: usize = 256;
+
+/// 客户端保存的设备条目，多个客户端通过本机交换并保持一致
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CatalogEntry {
+    /// 设备UUID，作为合并时的唯一标识
+    pub uuid: String,
+    pub name: String,
+    #[serde(default)]
+    pub custom_name: Option<String>,
+    pub ip_address: String,
+    pub port: u16,
+    #[serde(default)]
+    pub group: Option<String>,
+    #[serde(default)]
+    pub favorite: bool,
+    /// 已删除的设备保留为墓碑，避免被其他客户端重新同步回来
+    #[serde(default)]
+    pub deleted: bool,
+    /// 最后修改时间（Unix 毫秒），合并时较新的条目覆盖较旧的
+    pub updated_at: i64,
+}
+
+impl CatalogEntry {
+    fn validate(&self) -> Result<(), String> {
+        if self.uuid.trim().is_empty() || self.uuid.len() > MAX_FIELD_LEN {
+            return Err("Invalid device uuid".to_string());
+        }
+        let too_long = [
+            Some(&self.name),
+            self.custom_name.as_ref(),
+            Some(&self.ip_address),
+            self.group.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        .any(|field| field.len() > MAX_FIELD_LEN);
+        if too_long {
+            return Err(format!("Field too long for device {}", self.uuid));
+        }
+        Ok(())
+    }
+}
+
+// 首次使用时从磁盘加载
+static CATALOG: Lazy<Mutex<HashMap<String, CatalogEntry>>> =
+    Lazy::new(|| Mutex::new(load_catalog()));
+
+fn catalog_path() -> PathBuf {
+    AppConfig::config_path()
+        .parent()
+        .map(|dir| dir.join("device_catalog.json"))
+        .unwrap_or_else(|| PathBuf::from("device_catalog.json"))
+}
+
+fn load_catalog() -> HashMap<String, CatalogEntry> {
+    let entries = std::fs::read_to_string(catalog_path())
+        .ok()
+        .and_then(
+            |content| match serde_json::from_str::<Vec<CatalogEntry>>(&content) {
+                Ok(entries) => Some(entries),
+                Err(e) => {
+                    log::warn!("Failed to parse device catalog: {}", e);
+                    None
+                }
+            },
+        )
+        .unwrap_or_default();
+    entries.into_iter().map(|e| (e.uuid.clone(), e)).collect()
+}
+
+fn save_catalog(catalog: &HashMap<String, CatalogEntry>) -> Result<(), String> {
+    let mut entries: Vec<&CatalogEntry> = catalog.values().collect();
+    entries.sort_by(|a, b| a.uuid.cmp(&b.uuid));
+    let json = serde_json::to_string_pretty(&entries).map_err(|e| e.to_string())?;
+    AppConfig::ensure_config_dir().map_err(|e| e.to_string())?;
+    crate::config::write_atomic(&catalog_path(), json.as_bytes()).map_err(|e| e.to_string())
+}
+
+/// 合并客户端提交的条目：同一设备保留 updated_at 较新的一方，返回是否有变化
+fn merge_into(catalog: &mut HashMap<String, CatalogEntry>, incoming: Vec<CatalogEntry>) -> bool {
+    let mut changed = false;
+    for entry in incoming {
+        let newer = catalog
+            .get(&entry.uuid)
+            .map_or(true, |existing| entry.updated_at > existing.updated_at);
+        if newer {
+            catalog.insert(entry.uuid.clone(), entry);
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// 合并客户端的设备目录并返回合并后的完整目录（包括墓碑）
+///
+/// 提交空列表即可只拉取目录
+pub fn sync_catalog(incoming: Vec<CatalogEntry>) -> Result<Vec<CatalogEntry>, String> {
+    for entry in &incoming {
+        entry.validate()?;
+    }
+
+    let mut catalog = CATALOG.lock().map_err(|e| e.to_string())?;
+    let mut merged = catalog.clone();
+    if merge_into(&mut merged, incoming) {
+        if merged.len() > MAX_CATALOG_ENTRIES {
+            return Err(format!(
+                "Device catalog is limited to {} entries",
+                MAX_CATALOG_ENTRIES
+            ));
+        }
+        save_catalog(&merged)?;
+        *catalog = merged;
+    }
+
+    let mut entries: Vec<CatalogEntry> = catalog.values().cloned().collect();
+    entries.sort_by(|a, b| a.uuid.cmp(&b.uuid));
+    Ok(entries)
+}
@@ -0,0 +1,250 @@
+use std::sync::{Arc, Mutex};
+use tauri::{
+    menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem},
+    tray::TrayIcon,
+    AppHandle, Manager, Wry,
+};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+use crate::models::ServerStatus;
+use crate::state::AppState;
+
+/// 可加入托盘菜单的快捷操作
+pub const QUICK_ACTIONS: &[&str] = &["lock", "sleep", "toggle_mdns", "copy_pairing_code"];
+
+/// 快捷操作菜单项ID的前缀
+const QUICK_ACTION_PREFIX: &str = "quick:";
+
+/// 托盘中需要随服务器状态变化的菜单项
+pub struct TrayItems {
+    pub status_item: CheckMenuItem<Wry>,
+    pub start_item: MenuItem<Wry>,
+    pub stop_item: MenuItem<Wry>,
+    /// 配置了 toggle_mdns 快捷操作时的广播开关
+    pub mdns_item: Option<CheckMenuItem<Wry>>,
+}
+
+pub struct TrayState {
+    pub tray: TrayIcon<Wry>,
+    /// 配置变化后菜单会重建，菜单项随之替换
+    items: Mutex<TrayItems>,
+}
+
+impl TrayState {
+    pub fn new(tray: TrayIcon<Wry>, items: TrayItems) -> Self {
+        Self {
+            tray,
+            items: Mutex::new(items),
+        }
+    }
+}
+
+fn quick_action_label(action: &str) -> &'static str {
+    match action {
+        "lock" => "Lock Screen",
+        "sleep" => "Sleep",
+        "toggle_mdns" => "Advertise on Network",
+        "copy_pairing_code" => "Copy Pairing Code",
+        _ => "",
+    }
+}
+
+/// 按配置构建托盘菜单，快捷操作位于服务器控制和退出之间
+pub fn build_menu(app: &AppHandle) -> tauri::Result<(Menu<Wry>, TrayItems)> {
+    let show_i = MenuItem::with_id(app, "show", "Show", true, None::<&str>)?;
+    let hide_i = MenuItem::with_id(app, "hide", "Hide", true, None::<&str>)?;
+    let server_status_i = CheckMenuItem::with_id(
+        app,
+        "server_status",
+        "Server stopped",
+        false,
+        false,
+        None::<&str>,
+    )?;
+    let start_server_i =
+        MenuItem::with_id(app, "start_server", "Start Server", true, None::<&str>)?;
+    let stop_server_i = MenuItem::with_id(app, "stop_server", "Stop Server", false, None::<&str>)?;
+    let quit_i = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+
+    let menu = Menu::with_items(
+        app,
+        &[
+            &show_i,
+            &hide_i,
+            &PredefinedMenuItem::separator(app)?,
+            &server_status_i,
+            &start_server_i,
+            &stop_server_i,
+        ],
+    )?;
+
+    let mut mdns_item = None;
+    let actions = crate::config::get_config().tray.quick_actions;
+    if !actions.is_empty() {
+        menu.append(&PredefinedMenuItem::separator(app)?)?;
+    }
+    for action in actions
+        .iter()
+        .filter(|a| QUICK_ACTIONS.contains(&a.as_str()))
+    {
+        let id = format!("{}{}", QUICK_ACTION_PREFIX, action);
+        if action == "toggle_mdns" {
+            let item = CheckMenuItem::with_id(
+                app,
+                id,
+                quick_action_label(action),
+                false,
+                false,
+                None::<&str>,
+            )?;
+            menu.append(&item)?;
+            mdns_item = Some(item);
+        } else {
+            menu.append(&MenuItem::with_id(
+                app,
+                id,
+                quick_action_label(action),
+                true,
+                None::<&str>,
+            )?)?;
+        }
+    }
+
+    menu.append(&PredefinedMenuItem::separator(app)?)?;
+    menu.append(&quit_i)?;
+
+    Ok((
+        menu,
+        TrayItems {
+            status_item: server_status_i,
+            start_item: start_server_i,
+            stop_item: stop_server_i,
+            mdns_item,
+        },
+    ))
+}
+
+/// 快捷操作配置变化后重建托盘菜单
+pub fn rebuild(app: &AppHandle) {
+    let Some(tray) = app.try_state::<TrayState>() else {
+        return;
+    };
+    match build_menu(app) {
+        Ok((menu, items)) => {
+            let _ = tray.tray.set_menu(Some(menu));
+            if let Ok(mut current) = tray.items.lock() {
+                *current = items;
+            }
+            refresh(app, &app.state::<Arc<AppState>>().get_status());
+        }
+        Err(e) => log::warn!("Failed to rebuild tray menu: {}", e),
+    }
+}
+
+/// 处理快捷操作菜单项，不是快捷操作时返回 false
+pub fn handle_quick_action(app: &AppHandle, id: &str) -> bool {
+    let Some(action) = id.strip_prefix(QUICK_ACTION_PREFIX) else {
+        return false;
+    };
+    log::info!("[Tray] Quick action: {}", action);
+    let state = app.state::<Arc<AppState>>().inner().clone();
+    match action {
+        "lock" | "sleep" => {
+            let command = action.to_string();
+            tauri::async_runtime::spawn(async move {
+                // 本机用户的操作，不受远程命令白名单限制
+                if let Err(e) = crate::api::run_command(&command, None).await {
+                    crate::show_notification(
+                        "LanDevice Manager",
+                        &format!("Failed to {}: {}", command, e),
+                    );
+                }
+            });
+        }
+        "toggle_mdns" => {
+            let enabled = !state.get_status().advertising;
+            tauri::async_runtime::spawn(async move {
+                let message = match state.set_advertising(enabled).await {
+                    Ok(message) => message,
+                    Err(e) => format!("Failed to change advertising: {}", e),
+                };
+                crate::show_notification("LanDevice Manager", &message);
+            });
+        }
+        "copy_pairing_code" => {
+            let port = state
+                .get_status()
+                .port
+                .unwrap_or_else(|| crate::config::get_config().api_port);
+            let message = match crate::onboarding::pairing_qr(port).and_then(|qr| {
+                app.clipboard()
+                    .write_text(qr.uri)
+                    .map_err(|e| e.to_string())
+            }) {
+                Ok(()) => "Pairing code copied".to_string(),
+                Err(e) => format!("Failed to copy pairing code: {}", e),
+            };
+            crate::show_notification("LanDevice Manager", &message);
+        }
+        _ => log::warn!("[Tray] Unknown quick action: {}", action),
+    }
+    true
+}
+
+/// 根据服务器状态刷新托盘菜单和提示文字
+pub fn refresh(app: &AppHandle, status: &ServerStatus) {
+    let Some(tray) = app.try_state::<TrayState>() else {
+        return;
+    };
+
+    let Ok(items) = tray.items.lock() else {
+        return;
+    };
+
+    let _ = items.start_item.set_enabled(!status.running);
+    let _ = items.stop_item.set_enabled(status.running);
+    let _ = items.status_item.set_checked(status.running);
+    if let Some(ref mdns_item) = items.mdns_item {
+        let _ = mdns_item.set_enabled(status.running);
+        let _ = mdns_item.set_checked(status.advertising);
+    }
+
+    let tooltip = if status.running {
+        let label = match (&status.ip_address, status.port) {
+            (Some(ip), Some(port)) => format!("Server running on {}:{}", ip, port),
+            (None, Some(port)) => format!("Server running on port {}", port),
+            _ => "Server running".to_string(),
+        };
+        let _ = items.status_item.set_text(&label);
+        format!("LanDevice Manager - {}", label)
+    } else {
+        let _ = items.status_item.set_text("Server stopped");
+        "LanDevice Manager - Server stopped".to_string()
+    };
+    let _ = tray.tray.set_tooltip(Some(&tooltip));
+}
+
+/// 从托盘直接启动服务器
+pub fn start_server(app: &AppHandle) {
+    let state = app.state::<Arc<AppState>>().inner().clone();
+    tauri::async_runtime::spawn(async move {
+        let port = crate::config::get_config().api_port;
+        let message = match state.start_server(port).await {
+            Ok(message) => message,
+            Err(e) => format!("Failed to start server: {}", e),
+        };
+        crate::show_notification("LanDevice Manager", &message);
+    });
+}
+
+/// 从托盘直接停止服务器
+pub fn stop_server(app: &AppHandle) {
+    let state = app.state::<Arc<AppState>>().inner().clone();
+    tauri::async_runtime::spawn(async move {
+        let message = match state.stop_server().await {
+            Ok(message) => message,
+            Err(e) => format!("Failed to stop server: {}", e),
+        };
+        crate::show_notification("LanDevice Manager", &message);
+    });
+}
@@ -0,0 +1,174 @@
+use std::net::IpAddr;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+use crate::state::AppState;
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+/// 检查网络变化的间隔
+const WATCH_INTERVAL: Duration = Duration::from_secs(15);
+
+// 服务器因不在受信任网络而停止或未能启动，回到受信任网络时自动启动
+static PAUSED: AtomicBool = AtomicBool::new(false);
+
+/// 当前连接的 Wi-Fi 名称
+fn current_ssids() -> Vec<String> {
+    #[cfg(target_os = "windows")]
+    {
+        // 各语言的 Windows 都使用 "SSID" 作为字段名，需排除 "BSSID"
+        let output = Command::new("netsh")
+            .args(["wlan", "show", "interfaces"])
+            .creation_flags(CREATE_NO_WINDOW)
+            .output();
+        output
+            .map(|output| {
+                String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .filter_map(|line| {
+                        let (key, value) = line.split_once(':')?;
+                        (key.trim() == "SSID").then(|| value.trim().to_string())
+                    })
+                    .filter(|ssid| !ssid.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        let output = Command::new("nmcli")
+            .args(["-t", "-f", "active,ssid", "dev", "wifi"])
+            .output();
+        output
+            .map(|output| {
+                String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .filter_map(|line| line.strip_prefix("yes:"))
+                    .filter(|ssid| !ssid.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+    {
+        Vec::new()
+    }
+}
+
+/// 本机各网卡的地址，不含回环地址
+fn current_addresses() -> Vec<IpAddr> {
+    if_addrs::get_if_addrs()
+        .map(|interfaces| {
+            interfaces
+                .into_iter()
+                .map(|iface| iface.ip())
+                .filter(|ip| !ip.is_loopback())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 返回匹配到的受信任网络条目：CIDR 网段或单个地址与网卡地址比较，其余按 Wi-Fi 名称比较
+fn matching_entry(entries: &[String], ssids: &[String], addresses: &[IpAddr]) -> Option<String> {
+    entries
+        .iter()
+        .find(|entry| {
+            let entry = entry.trim();
+            if let Some((network, prefix)) = crate::config::parse_cidr(entry) {
+                addresses
+                    .iter()
+                    .any(|ip| crate::config::cidr_contains(network, prefix, *ip))
+            } else if let Ok(ip) = entry.parse::<IpAddr>() {
+                addresses.contains(&ip)
+            } else {
+                ssids.iter().any(|ssid| ssid == entry)
+            }
+        })
+        .cloned()
+}
+
+/// 是否在受信任网络中；未配置 trusted_networks 时不做限制
+pub fn is_trusted() -> bool {
+    let entries = crate::config::get_config().trusted_networks;
+    entries.is_empty() || matching_entry(&entries, &current_ssids(), &current_addresses()).is_some()
+}
+
+/// 因不在受信任网络而拒绝启动时调用，回到受信任网络后由监视任务启动
+pub fn mark_paused() {
+    PAUSED.store(true, Ordering::SeqCst);
+}
+
+/// 后台监视网络变化：离开受信任网络时停止服务器，回来后重新启动
+pub fn spawn_watcher(state: Arc<AppState>) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(WATCH_INTERVAL).await;
+
+            let trusted = tokio::task::spawn_blocking(is_trusted)
+                .await
+                .unwrap_or(true);
+            let running = state.get_status().running;
+
+            if !trusted && running {
+                state.logger.warn(
+                    "Network",
+                    "Not on a trusted network, stopping server and advertising",
+                );
+                if state.stop_server().await.is_ok() {
+                    PAUSED.store(true, Ordering::SeqCst);
+                    crate::show_notification(
+                        "LanDevice Manager",
+                        "Server stopped: not on a trusted network",
+                    );
+                }
+            } else if trusted && !running && PAUSED.swap(false, Ordering::SeqCst) {
+                let port = crate::config::get_config().api_port;
+                state
+                    .logger
+                    .info("Network", "Back on a trusted network, starting server");
+                if let Err(e) = state.start_server(port).await {
+                    state.logger.error(
+                        "Network",
+                        &format!("Failed to start server on trusted network: {}", e),
+                    );
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_subnets_addresses_and_ssids() {
+        let entries = vec![
+            "192.168.1.0/24".to_string(),
+            "10.0.0.5".to_string(),
+            "Home WiFi".to_string(),
+        ];
+        let home: IpAddr = "192.168.1.20".parse().unwrap();
+        let hotel: IpAddr = "172.20.3.9".parse().unwrap();
+
+        assert_eq!(
+            matching_entry(&entries, &[], &[home]).as_deref(),
+            Some("192.168.1.0/24")
+        );
+        assert!(matching_entry(&entries, &[], &[hotel]).is_none());
+        assert!(matching_entry(&entries, &["Hotel Guest".to_string()], &[hotel]).is_none());
+        assert_eq!(
+            matching_entry(&entries, &["Home WiFi".to_string()], &[hotel]).as_deref(),
+            Some("Home WiFi")
+        );
+    }
+}
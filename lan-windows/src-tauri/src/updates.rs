@@ -0,0 +1,236 @@
+use chrono::{DateTime, Local};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::sync::Mutex;
+
+use crate::websocket::WsMessage;
+
+/// 最近一次扫描得到的更新状态
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateStatus {
+    pub pending_count: u64,
+    pub pending_titles: Vec<String>,
+    /// 最近一次安装更新的时间
+    pub last_install: Option<String>,
+    pub reboot_required: bool,
+    pub checked_at: DateTime<Local>,
+}
+
+/// Windows Update 状态及正在进行的操作
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UpdateState {
+    pub status: Option<UpdateStatus>,
+    /// 正在进行的操作：scan / install
+    pub operation: Option<String>,
+    pub last_error: Option<String>,
+    /// 最近一次安装的结果
+    pub last_install_result: Option<String>,
+}
+
+// 扫描和安装可能持续数分钟，在后台线程执行，结果保存在这里
+static UPDATE_STATE: Lazy<Mutex<UpdateState>> = Lazy::new(|| Mutex::new(UpdateState::default()));
+
+/// 获取更新状态
+pub fn get_state() -> UpdateState {
+    match UPDATE_STATE.lock() {
+        Ok(state) => state.clone(),
+        Err(poisoned) => poisoned.into_inner().clone(),
+    }
+}
+
+/// 标记开始一个后台操作，已有操作在进行时返回错误
+fn begin_operation(operation: &str) -> Result<(), String> {
+    if !cfg!(target_os = "windows") {
+        return Err("Windows Update is only available on Windows".to_string());
+    }
+
+    let mut state = UPDATE_STATE.lock().map_err(|e| e.to_string())?;
+    if let Some(ref current) = state.operation {
+        return Err(format!("An update {} is already in progress", current));
+    }
+    state.operation = Some(operation.to_string());
+    state.last_error = None;
+    Ok(())
+}
+
+fn finish_operation<F>(f: F)
+where
+    F: FnOnce(&mut UpdateState),
+{
+    if let Ok(mut state) = UPDATE_STATE.lock() {
+        state.operation = None;
+        f(&mut state);
+    }
+}
+
+/// 通知客户端后台操作已完成
+fn publish_completion(job: &str, error: Option<String>) {
+    crate::events::publish(WsMessage::JobCompleted {
+        job: job.to_string(),
+        success: error.is_none(),
+        exit_code: None,
+        message: error,
+    });
+}
+
+/// 在后台扫描可用更新
+pub fn start_scan() -> Result<(), String> {
+    begin_operation("scan")?;
+    std::thread::spawn(|| {
+        let result = scan();
+        let error = result.as_ref().err().cloned();
+        finish_operation(|state| match result {
+            Ok(status) => state.status = Some(status),
+            Err(e) => {
+                log::error!("Windows Update scan failed: {}", e);
+                state.last_error = Some(e);
+            }
+        });
+        publish_completion("update_scan", error);
+    });
+    Ok(())
+}
+
+/// 在后台下载并安装所有可用的软件更新
+pub fn start_install() -> Result<(), String> {
+    begin_operation("install")?;
+    std::thread::spawn(|| {
+        let result = install();
+        // 安装后重新扫描，刷新待安装数量
+        let status = scan();
+        let error = result.as_ref().err().cloned();
+        finish_operation(|state| {
+            match result {
+                Ok(summary) => state.last_install_result = Some(summary),
+                Err(e) => {
+                    log::error!("Windows Update install failed: {}", e);
+                    state.last_error = Some(e);
+                }
+            }
+            if let Ok(status) = status {
+                state.status = Some(status);
+            }
+        });
+        publish_completion("update_install", error);
+    });
+    Ok(())
+}
+
+/// 通过 PowerShell 调用 Windows Update COM API，返回 JSON 输出
+#[cfg(target_os = "windows")]
+fn run_update_script(script: &str) -> Result<serde_json::Value, String> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+    let output = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-Command", script])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map_err(|e| e.to_string())?;
+
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    serde_json::from_slice(&output.stdout).map_err(|e| format!("Invalid update output: {}", e))
+}
+
+#[cfg(target_os = "windows")]
+fn scan() -> Result<UpdateStatus, String> {
+    const SCRIPT: &str = r#"
+$session = New-Object -ComObject Microsoft.Update.Session
+$searcher = $session.CreateUpdateSearcher()
+$result = $searcher.Search("IsInstalled=0 and IsHidden=0")
+$last = $null
+if ($searcher.GetTotalHistoryCount() -gt 0) {
+    $last = $searcher.QueryHistory(0, 1).Item(0).Date.ToString("o")
+}
+[PSCustomObject]@{
+    Pending = $result.Updates.Count
+    Titles = @($result.Updates | ForEach-Object { $_.Title })
+    LastInstall = $last
+    RebootRequired = (New-Object -ComObject Microsoft.Update.SystemInfo).RebootRequired
+} | ConvertTo-Json -Compress
+"#;
+
+    let value = run_update_script(SCRIPT)?;
+    Ok(UpdateStatus {
+        pending_count: value.get("Pending").and_then(|v| v.as_u64()).unwrap_or(0),
+        pending_titles: value
+            .get("Titles")
+            .and_then(|v| v.as_array())
+            .map(|titles| {
+                titles
+                    .iter()
+                    .filter_map(|t| t.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        last_install: value
+            .get("LastInstall")
+            .and_then(|v| v.as_str())
+            .map(str::to_string),
+        reboot_required: value
+            .get("RebootRequired")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
+        checked_at: Local::now(),
+    })
+}
+
+#[cfg(target_os = "windows")]
+fn install() -> Result<String, String> {
+    const SCRIPT: &str = r#"
+$session = New-Object -ComObject Microsoft.Update.Session
+$result = $session.CreateUpdateSearcher().Search("IsInstalled=0 and IsHidden=0 and Type='Software'")
+if ($result.Updates.Count -eq 0) {
+    [PSCustomObject]@{ Count = 0; ResultCode = 2; RebootRequired = $false } | ConvertTo-Json -Compress
+    exit
+}
+foreach ($update in $result.Updates) {
+    if (-not $update.EulaAccepted) { $update.AcceptEula() }
+}
+$downloader = $session.CreateUpdateDownloader()
+$downloader.Updates = $result.Updates
+$downloader.Download() | Out-Null
+$installer = $session.CreateUpdateInstaller()
+$installer.Updates = $result.Updates
+$install = $installer.Install()
+[PSCustomObject]@{
+    Count = $result.Updates.Count
+    ResultCode = $install.ResultCode
+    RebootRequired = $install.RebootRequired
+} | ConvertTo-Json -Compress
+"#;
+
+    let value = run_update_script(SCRIPT)?;
+    let count = value.get("Count").and_then(|v| v.as_u64()).unwrap_or(0);
+    // OperationResultCode: 2 成功, 3 部分成功, 4 失败, 5 已取消
+    let result = match value.get("ResultCode").and_then(|v| v.as_u64()) {
+        Some(2) => "succeeded",
+        Some(3) => "succeeded with errors",
+        Some(4) => "failed",
+        Some(5) => "aborted",
+        _ => "unknown",
+    };
+    let reboot = value
+        .get("RebootRequired")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    Ok(format!(
+        "{} update(s) {}{}",
+        count,
+        result,
+        if reboot { ", reboot required" } else { "" }
+    ))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn scan() -> Result<UpdateStatus, String> {
+    Err("Windows Update is only available on Windows".to_string())
+}
+
+#[cfg(not(target_os = "windows"))]
+fn install() -> Result<String, String> {
+    Err("Windows Update is only available on Windows".to_string())
+}
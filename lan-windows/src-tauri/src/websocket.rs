@@ -1,20 +1,36 @@
 use axum::{
     extract::{
         ws::{Message, WebSocket, WebSocketUpgrade},
-        State,
+        Extension, Query, State,
     },
     response::Response,
 };
 use futures::{sink::SinkExt, stream::StreamExt};
 use serde::{Deserialize, Serialize};
-use tokio::sync::broadcast;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, mpsc, oneshot, Semaphore};
 use uuid::Uuid;
 
 use crate::api::{is_ip_blacklisted, AppState};
 use crate::auth::AuthManager;
+use crate::config::get_config;
+use crate::payload::{FrameCipher, PayloadSession};
 use axum::extract::ConnectInfo;
 use std::net::SocketAddr;
 
+/// 协议层允许的单条消息上限，超过该大小的帧会直接断开连接
+pub const WS_HARD_MESSAGE_LIMIT: usize = 1024 * 1024;
+
+/// 连接结束后等待写任务发出已排队消息的最长时间
+const WRITER_DRAIN_TIMEOUT: Duration = Duration::from_millis(500);
+/// 每个连接最多同时监视的目录数
+const MAX_WATCHES_PER_CONNECTION: usize = 8;
+/// 订阅实时指标时的默认和最短推送间隔（毫秒）
+const DEFAULT_METRICS_INTERVAL_MS: u64 = 2000;
+const MIN_METRICS_INTERVAL_MS: u64 = 500;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", content = "data")]
 pub enum WsMessage {
@@ -52,122 +68,695 @@ pub enum WsMessage {
         success: bool,
         output: String,
     },
+    #[serde(rename = "notification")]
+    Notification {
+        id: String,
+        title: String,
+        message: String,
+        level: String,
+    },
+    #[serde(rename = "notification_ack")]
+    NotificationAck {
+        id: String,
+        response: Option<String>,
+    },
+    #[serde(rename = "limit_exceeded")]
+    LimitExceeded {
+        limit: String,
+        message: String,
+        id: Option<String>,
+    },
+    #[serde(rename = "watch_path")]
+    WatchPath {
+        path: String,
+        recursive: Option<bool>,
+    },
+    #[serde(rename = "unwatch_path")]
+    UnwatchPath { path: String },
+    #[serde(rename = "watch_started")]
+    WatchStarted { path: String },
+    #[serde(rename = "file_changed")]
+    FileChanged {
+        watch: String,
+        path: String,
+        kind: String,
+    },
+    #[serde(rename = "job_completed")]
+    JobCompleted {
+        job: String,
+        success: bool,
+        exit_code: Option<i32>,
+        message: Option<String>,
+    },
+    /// 按 interval_ms 定时推送 metrics 消息，重复订阅时使用新的间隔
+    #[serde(rename = "subscribe_metrics")]
+    SubscribeMetrics { interval_ms: Option<u64> },
+    #[serde(rename = "unsubscribe_metrics")]
+    UnsubscribeMetrics,
+    #[serde(rename = "metrics")]
+    Metrics(crate::models::LiveMetrics),
+    #[serde(rename = "status_changed")]
+    StatusChanged {
+        field: String,
+        value: serde_json::Value,
+    },
+    /// 连接处理过慢，丢失了 missed 条服务端消息，客户端应重新拉取完整状态
+    #[serde(rename = "resync")]
+    Resync { missed: u64 },
     #[serde(rename = "error")]
     Error { message: String },
 }
 
+/// 单个 WebSocket 连接的限制
+#[derive(Debug, Clone)]
+pub struct WsLimits {
+    pub max_messages_per_sec: u32,
+    pub max_message_size: usize,
+    pub max_concurrent_commands: usize,
+    pub auth_timeout: Duration,
+    pub max_auth_failures: u32,
+}
+
+impl WsLimits {
+    /// 从全局配置读取限制
+    pub fn from_config() -> Self {
+        let config = get_config();
+        Self {
+            max_messages_per_sec: config.ws_max_messages_per_sec.max(1),
+            max_message_size: config.ws_max_message_size.clamp(1, WS_HARD_MESSAGE_LIMIT),
+            max_concurrent_commands: config.ws_max_concurrent_commands.max(1),
+            auth_timeout: Duration::from_secs(config.ws_auth_timeout_secs.max(1)),
+            max_auth_failures: config.ws_max_auth_failures.max(1),
+        }
+    }
+}
+
+/// 固定窗口消息计数器（1秒窗口）
+struct RateWindow {
+    started: Instant,
+    count: u32,
+}
+
+impl RateWindow {
+    fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            count: 0,
+        }
+    }
+
+    /// 记录一条消息，超出限制时返回 false
+    fn hit(&mut self, max_per_sec: u32) -> bool {
+        if self.started.elapsed() >= Duration::from_secs(1) {
+            self.started = Instant::now();
+            self.count = 0;
+        }
+        self.count += 1;
+        self.count <= max_per_sec
+    }
+}
+
+/// 序列化要发送的消息，加密会话中逐帧加密
+fn to_text(message: &WsMessage, cipher: &mut Option<FrameCipher>) -> Option<Message> {
+    let text = serde_json::to_string(message).unwrap();
+    match cipher {
+        Some(cipher) => cipher.seal_text(&text).ok().map(Message::Text),
+        None => Some(Message::Text(text)),
+    }
+}
+
+/// 校验 Auth 消息中的令牌
+///
+/// 协商了负载加密的令牌只能在加密的连接中使用（见 ws_handler），不接受以明文帧发送
+fn check_auth_token(
+    auth_manager: &AuthManager,
+    token: &str,
+    encrypted: bool,
+) -> Result<(), &'static str> {
+    if !encrypted && auth_manager.requires_payload_encryption(token) {
+        return Err("This session requires encrypted requests");
+    }
+    if !auth_manager.verify_token(token) {
+        return Err("Invalid or expired token");
+    }
+    Ok(())
+}
+
+/// 连接是否仍需认证：未设置密码时无需认证即可使用，连接也不会因认证超时断开
+fn awaiting_auth(auth_manager: &AuthManager, authenticated: bool) -> bool {
+    !authenticated && auth_manager.is_password_set()
+}
+
+/// 加密会话的升级请求：PAYLOAD_HEADER 中的令牌和会话密钥
+pub struct EncryptedUpgrade {
+    pub token: String,
+    pub session: PayloadSession,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WsQuery {
+    token: Option<String>,
+}
+
 #[derive(Clone)]
 pub struct WebSocketManager {
     auth_manager: AuthManager,
-    tx: broadcast::Sender<WsMessage>,
 }
 
 impl WebSocketManager {
     pub fn new(auth_manager: AuthManager) -> Self {
-        let (tx, _rx) = broadcast::channel(50);
-        Self { auth_manager, tx }
+        Self { auth_manager }
     }
 
     pub fn subscribe(&self) -> broadcast::Receiver<WsMessage> {
-        self.tx.subscribe()
+        crate::events::subscribe()
     }
 
-    pub fn broadcast(&self, message: WsMessage) {
-        let _ = self.tx.send(message);
+    /// 广播消息给所有连接（同时供长轮询读取），返回接收者数量
+    pub fn broadcast(&self, message: WsMessage) -> usize {
+        crate::events::publish(message)
     }
 
-    pub async fn handle_socket(&self, socket: WebSocket, auth_manager: AuthManager, client_ip: String) {
+    pub async fn handle_socket(
+        &self,
+        socket: WebSocket,
+        auth_manager: AuthManager,
+        client_ip: String,
+        upgrade: Option<EncryptedUpgrade>,
+    ) {
         let (mut sender, mut receiver) = socket.split();
-        let _rx = self.subscribe();
+        let mut rx = self.subscribe();
         let mut authenticated = false;
+        // 认证后记录该客户端的权限限制，None 表示不受限制
+        // 令牌适用的客户端权限及其配置项
+        let mut permissions: Option<(String, crate::config::ClientPermissions)> = None;
         let client_id = Uuid::new_v4().to_string();
+        // 认证后为令牌对应的设备或会话（见 Claims::identity），用于命令日志和审计记录
+        let mut identity: Option<String> = None;
+        let limits = WsLimits::from_config();
+        let mut rate = RateWindow::new();
+        let mut auth_failures = 0u32;
+        // 设置了密码时，未认证的连接在期限到达后断开，认证成功后不再检查
+        let auth_deadline = tokio::time::sleep(limits.auth_timeout);
+        tokio::pin!(auth_deadline);
+        let command_slots = Arc::new(Semaphore::new(limits.max_concurrent_commands));
+        // 目录监视器，连接断开时随之 drop 并停止监视
+        let mut watchers: HashMap<String, notify::RecommendedWatcher> = HashMap::new();
+        // 实时指标推送任务，取消订阅或连接断开时停止
+        let mut metrics_task: Option<tokio::task::JoinHandle<()>> = None;
 
         log::info!("WebSocket client connected: {} from IP: {}", client_id, client_ip);
+        let (connection_id, close_signal) = crate::connections::connected(&client_ip);
+
+        // 加密会话的两个方向各自计数，写任务持有发出方向
+        let (mut outgoing, mut incoming) = match upgrade {
+            Some(ref upgrade) => (
+                Some(FrameCipher::outgoing(&upgrade.session, "/ws")),
+                Some(FrameCipher::incoming(&upgrade.session, "/ws")),
+            ),
+            None => (None, None),
+        };
+
+        // 所有发往客户端的消息都经过此通道，由写任务统一发送
+        let (out_tx, mut out_rx) = mpsc::channel::<WsMessage>(64);
+        // 命令和推送任务可能仍持有 out_tx 的克隆，连接结束时通过此信号让写任务收尾
+        let (writer_stop, mut stop_rx) = oneshot::channel::<()>();
+        let mut writer = tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    biased;
+                    message = out_rx.recv() => match message {
+                        Some(message) => {
+                            let Some(frame) = to_text(&message, &mut outgoing) else {
+                                return;
+                            };
+                            if sender.send(frame).await.is_err() {
+                                return;
+                            }
+                        }
+                        None => break,
+                    },
+                    _ = &mut stop_rx => {
+                        // 不再接受新消息，发出已排队的消息后关闭
+                        out_rx.close();
+                        while let Some(message) = out_rx.recv().await {
+                            let Some(frame) = to_text(&message, &mut outgoing) else {
+                                return;
+                            };
+                            if sender.send(frame).await.is_err() {
+                                return;
+                            }
+                        }
+                        break;
+                    }
+                }
+            }
+            let _ = sender.close().await;
+        });
 
         // 发送欢迎消息
-        let welcome = WsMessage::Pong;
-        let _ = sender
-            .send(Message::Text(serde_json::to_string(&welcome).unwrap()))
-            .await;
+        let _ = out_tx.send(WsMessage::Pong).await;
+
+        // 加密会话在升级请求中已提供令牌，无需再发送 Auth 消息
+        if let Some(upgrade) = upgrade {
+            if auth_manager.verify_token(&upgrade.token) {
+                authenticated = true;
+                permissions = auth_manager.permission_source(&upgrade.token);
+                identity = auth_manager.identity(&upgrade.token);
+                crate::connections::authenticated(&connection_id, identity.clone());
+                let _ = out_tx.send(WsMessage::AuthSuccess).await;
+                log::info!(
+                    "WebSocket client {} authenticated as {} (encrypted)",
+                    client_id,
+                    identity.as_deref().unwrap_or("unknown")
+                );
+            }
+        }
+
+        // 处理接收到的消息以及服务端广播
+        loop {
+            let msg = tokio::select! {
+                incoming = receiver.next() => match incoming {
+                    Some(Ok(msg)) => msg,
+                    _ => break,
+                },
+                _ = close_signal.notified() => {
+                    log::warn!(
+                        "WebSocket client {} from {} closed by server: IP is blacklisted",
+                        client_id,
+                        client_ip
+                    );
+                    let _ = out_tx
+                        .send(WsMessage::AuthError {
+                            message: "Access denied: IP is blacklisted".to_string(),
+                        })
+                        .await;
+                    break;
+                }
+                _ = &mut auth_deadline, if awaiting_auth(&auth_manager, authenticated) => {
+                    log::warn!(
+                        "WebSocket client {} from {} did not authenticate within {}s, closing",
+                        client_id,
+                        client_ip,
+                        limits.auth_timeout.as_secs()
+                    );
+                    let _ = out_tx
+                        .send(WsMessage::AuthError {
+                            message: "Authentication timeout".to_string(),
+                        })
+                        .await;
+                    break;
+                }
+                event = rx.recv() => {
+                    // 只向已认证且有 events 权限的客户端转发服务端消息
+                    let receives_events = authenticated
+                        && permissions
+                            .as_ref()
+                            .map_or(true, |(_, permissions)| permissions.allows_scope("events"));
+                    match event {
+                        Ok(message) => {
+                            if receives_events {
+                                let _ = out_tx.send(message).await;
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            log::warn!(
+                                "WebSocket client {} missed {} broadcast messages",
+                                client_id,
+                                skipped
+                            );
+                            if receives_events {
+                                let _ = out_tx.send(WsMessage::Resync { missed: skipped }).await;
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    }
+                    continue;
+                }
+            };
 
-        // 处理接收到的消息
-        while let Some(Ok(msg)) = receiver.next().await {
             match msg {
                 Message::Text(text) => {
+                    if !rate.hit(limits.max_messages_per_sec) {
+                        log::warn!("WebSocket client {} exceeded message rate limit", client_id);
+                        let _ = out_tx
+                            .send(WsMessage::LimitExceeded {
+                                limit: "messages_per_sec".to_string(),
+                                message: format!(
+                                    "Too many messages, limit is {} per second",
+                                    limits.max_messages_per_sec
+                                ),
+                                id: None,
+                            })
+                            .await;
+                        continue;
+                    }
+
+                    let text = match incoming.as_mut() {
+                        Some(cipher) => match cipher.open_text(&text) {
+                            Ok(text) => text,
+                            Err(e) => {
+                                log::warn!(
+                                    "WebSocket client {} sent an invalid encrypted frame: {}",
+                                    client_id,
+                                    e
+                                );
+                                break;
+                            }
+                        },
+                        None => text,
+                    };
+
+                    if text.len() > limits.max_message_size {
+                        log::warn!(
+                            "WebSocket client {} sent oversized message: {} bytes",
+                            client_id,
+                            text.len()
+                        );
+                        let _ = out_tx
+                            .send(WsMessage::LimitExceeded {
+                                limit: "message_size".to_string(),
+                                message: format!(
+                                    "Message too large, limit is {} bytes",
+                                    limits.max_message_size
+                                ),
+                                id: None,
+                            })
+                            .await;
+                        continue;
+                    }
+
                     match serde_json::from_str::<WsMessage>(&text) {
                         Ok(ws_msg) => {
                             match ws_msg {
                                 WsMessage::Ping => {
-                                    let pong = WsMessage::Pong;
-                                    let _ = sender
-                                        .send(Message::Text(serde_json::to_string(&pong).unwrap()))
-                                        .await;
+                                    let _ = out_tx.send(WsMessage::Pong).await;
                                 }
                                 WsMessage::Auth { token } => {
-                                    if auth_manager.verify_token(&token) {
-                                        authenticated = true;
-                                        let success = WsMessage::AuthSuccess;
-                                        let _ = sender
-                                            .send(Message::Text(
-                                                serde_json::to_string(&success).unwrap(),
-                                            ))
-                                            .await;
-                                        log::info!("WebSocket client authenticated: {}", client_id);
-                                    } else {
-                                        let error = WsMessage::AuthError {
-                                            message: "Invalid or expired token".to_string(),
-                                        };
-                                        let _ = sender
-                                            .send(Message::Text(
-                                                serde_json::to_string(&error).unwrap(),
-                                            ))
-                                            .await;
+                                    match check_auth_token(&auth_manager, &token, incoming.is_some()) {
+                                        Ok(()) => {
+                                            authenticated = true;
+                                            permissions = auth_manager.permission_source(&token);
+                                            identity = auth_manager.identity(&token);
+                                            crate::connections::authenticated(&connection_id, identity.clone());
+                                            let _ = out_tx.send(WsMessage::AuthSuccess).await;
+                                            log::info!(
+                                                "WebSocket client {} authenticated as {}",
+                                                client_id,
+                                                identity.as_deref().unwrap_or("unknown")
+                                            );
+                                        }
+                                        Err(message) => {
+                                            auth_failures += 1;
+                                            crate::stats::record_auth(
+                                                &client_ip,
+                                                crate::stats::AuthOutcome::TokenRejected,
+                                            );
+                                            let _ = out_tx
+                                                .send(WsMessage::AuthError {
+                                                    message: message.to_string(),
+                                                })
+                                                .await;
+                                            if auth_failures >= limits.max_auth_failures {
+                                                log::warn!(
+                                                    "WebSocket client {} from {} failed authentication {} times, closing",
+                                                    client_id,
+                                                    client_ip,
+                                                    auth_failures
+                                                );
+                                                break;
+                                            }
+                                        }
                                     }
                                 }
                                 WsMessage::CommandRequest { id, command, args } => {
                                     if !authenticated {
-                                        let error = WsMessage::Error {
-                                            message: "Not authenticated".to_string(),
-                                        };
-                                        let _ = sender
-                                            .send(Message::Text(
-                                                serde_json::to_string(&error).unwrap(),
-                                            ))
+                                        let _ = out_tx
+                                            .send(WsMessage::Error {
+                                                message: "Not authenticated".to_string(),
+                                            })
                                             .await;
                                         continue;
                                     }
 
-                                    // 检查白名单
-                                    let executor = crate::command::CommandExecutor::new();
-                                    match executor.execute(&command, args.as_deref()) {
-                                        Ok(result) => {
-                                            let response = WsMessage::CommandResponse {
-                                                id,
-                                                success: result.success,
-                                                output: if result.success {
-                                                    result.stdout
-                                                } else {
-                                                    result.stderr
-                                                },
-                                            };
-                                            let _ = sender
-                                                .send(Message::Text(
-                                                    serde_json::to_string(&response).unwrap(),
-                                                ))
+                                    let decision = crate::policy::evaluate_command(
+                                        &command,
+                                        permissions
+                                            .as_ref()
+                                            .map(|(source, p)| (source.as_str(), p)),
+                                        &crate::config::get_config(),
+                                    );
+                                    let who = identity.clone().unwrap_or_else(|| client_id.clone());
+                                    crate::policy::record(&client_ip, Some(&who), &decision);
+                                    if !decision.allowed {
+                                        log::warn!(
+                                            "[Command] [{}] WebSocket execute '{}' by {} REJECTED: {}",
+                                            client_ip,
+                                            command,
+                                            who,
+                                            decision.reason
+                                        );
+                                        let _ = out_tx
+                                            .send(WsMessage::Error {
+                                                message: decision.reason,
+                                            })
+                                            .await;
+                                        continue;
+                                    }
+
+                                    // 限制同时执行的命令数
+                                    let permit = match command_slots.clone().try_acquire_owned() {
+                                        Ok(permit) => permit,
+                                        Err(_) => {
+                                            log::warn!(
+                                                "WebSocket client {} exceeded concurrent command limit",
+                                                client_id
+                                            );
+                                            let _ = out_tx
+                                                .send(WsMessage::LimitExceeded {
+                                                    limit: "concurrent_commands".to_string(),
+                                                    message: format!(
+                                                        "Too many commands in progress, limit is {}",
+                                                        limits.max_concurrent_commands
+                                                    ),
+                                                    id: Some(id),
+                                                })
                                                 .await;
+                                            continue;
                                         }
-                                        Err(_) => {
-                                            let error = WsMessage::CommandResponse {
-                                                id,
-                                                success: false,
-                                                output: "Command execution failed".to_string(),
+                                    };
+
+                                    log::info!(
+                                        "[Command] [{}] WebSocket execute '{}' by {} REQUEST",
+                                        client_ip,
+                                        command,
+                                        who
+                                    );
+                                    let out_tx = out_tx.clone();
+                                    let job = command.clone();
+                                    let ip = client_ip.clone();
+                                    tokio::spawn(async move {
+                                        // 检查白名单
+                                        let result = tokio::task::spawn_blocking(move || {
+                                            let executor = crate::command::CommandExecutor::new();
+                                            executor.execute(&command, args.as_deref())
+                                        })
+                                        .await;
+
+                                        let succeeded = matches!(result, Ok(Ok(ref r)) if r.success);
+                                        if succeeded {
+                                            log::info!(
+                                                "[Command] [{}] WebSocket execute '{}' by {} SUCCESS",
+                                                ip,
+                                                job,
+                                                who
+                                            );
+                                        } else {
+                                            log::warn!(
+                                                "[Command] [{}] WebSocket execute '{}' by {} FAILED",
+                                                ip,
+                                                job,
+                                                who
+                                            );
+                                        }
+
+                                        let (response, completion) = match result {
+                                            Ok(Ok(result)) => (
+                                                WsMessage::CommandResponse {
+                                                    id,
+                                                    success: result.success,
+                                                    output: if result.success {
+                                                        result.stdout
+                                                    } else {
+                                                        result.stderr.clone()
+                                                    },
+                                                },
+                                                WsMessage::JobCompleted {
+                                                    job,
+                                                    success: result.success,
+                                                    exit_code: result.exit_code,
+                                                    message: (!result.success)
+                                                        .then(|| result.stderr.trim().to_string()),
+                                                },
+                                            ),
+                                            _ => (
+                                                WsMessage::CommandResponse {
+                                                    id,
+                                                    success: false,
+                                                    output: "Command execution failed".to_string(),
+                                                },
+                                                WsMessage::JobCompleted {
+                                                    job,
+                                                    success: false,
+                                                    exit_code: None,
+                                                    message: Some("Command execution failed".to_string()),
+                                                },
+                                            ),
+                                        };
+                                        let _ = out_tx.send(response).await;
+                                        // 发起命令的连接可能已经断开，通过事件总线通知所有客户端（包括长轮询）
+                                        crate::events::publish(completion);
+                                        drop(permit);
+                                    });
+                                }
+                                WsMessage::NotificationAck { id, response } => {
+                                    log::info!(
+                                        "WebSocket client {} acknowledged notification {}: {:?}",
+                                        client_id,
+                                        id,
+                                        response
+                                    );
+                                    crate::api::log_to_ui(
+                                        "info",
+                                        &format!(
+                                            "[{}] Notification {} acknowledged: {}",
+                                            client_ip,
+                                            id,
+                                            response.as_deref().unwrap_or("ok")
+                                        ),
+                                    );
+                                }
+                                WsMessage::WatchPath { path, recursive } => {
+                                    if !authenticated {
+                                        let _ = out_tx
+                                            .send(WsMessage::Error {
+                                                message: "Not authenticated".to_string(),
+                                            })
+                                            .await;
+                                        continue;
+                                    }
+
+                                    if !watchers.contains_key(&path)
+                                        && watchers.len() >= MAX_WATCHES_PER_CONNECTION
+                                    {
+                                        let _ = out_tx
+                                            .send(WsMessage::LimitExceeded {
+                                                limit: "watches".to_string(),
+                                                message: format!(
+                                                    "Too many watched paths, limit is {}",
+                                                    MAX_WATCHES_PER_CONNECTION
+                                                ),
+                                                id: None,
+                                            })
+                                            .await;
+                                        continue;
+                                    }
+
+                                    let event_tx = out_tx.clone();
+                                    let watch = path.clone();
+                                    let result = crate::files::watch_path(
+                                        &path,
+                                        recursive.unwrap_or(true),
+                                        move |event| {
+                                            // 在监视线程中调用，通道已满时丢弃事件
+                                            let _ = event_tx.try_send(WsMessage::FileChanged {
+                                                watch: watch.clone(),
+                                                path: event.path,
+                                                kind: event.kind,
+                                            });
+                                        },
+                                    );
+
+                                    match result {
+                                        Ok(watcher) => {
+                                            log::info!(
+                                                "WebSocket client {} watching {}",
+                                                client_id,
+                                                path
+                                            );
+                                            watchers.insert(path.clone(), watcher);
+                                            let _ = out_tx.send(WsMessage::WatchStarted { path }).await;
+                                        }
+                                        Err(e) => {
+                                            log::warn!(
+                                                "WebSocket client {} failed to watch {}: {}",
+                                                client_id,
+                                                path,
+                                                e
+                                            );
+                                            let _ = out_tx.send(WsMessage::Error { message: e }).await;
+                                        }
+                                    }
+                                }
+                                WsMessage::SubscribeMetrics { interval_ms } => {
+                                    // 与 HTTP 接口一致，未设置密码时无需认证即可读取指标
+                                    if awaiting_auth(&auth_manager, authenticated) {
+                                        let _ = out_tx
+                                            .send(WsMessage::Error {
+                                                message: "Not authenticated".to_string(),
+                                            })
+                                            .await;
+                                        continue;
+                                    }
+
+                                    let interval_ms = interval_ms
+                                        .unwrap_or(DEFAULT_METRICS_INTERVAL_MS)
+                                        .max(MIN_METRICS_INTERVAL_MS);
+                                    if let Some(task) = metrics_task.take() {
+                                        task.abort();
+                                    }
+                                    log::info!(
+                                        "WebSocket client {} subscribed to metrics ({} ms)",
+                                        client_id,
+                                        interval_ms
+                                    );
+                                    let metrics_tx = out_tx.clone();
+                                    metrics_task = Some(tokio::spawn(async move {
+                                        let mut interval =
+                                            tokio::time::interval(Duration::from_millis(interval_ms));
+                                        interval.set_missed_tick_behavior(
+                                            tokio::time::MissedTickBehavior::Skip,
+                                        );
+                                        loop {
+                                            interval.tick().await;
+                                            let Ok(metrics) =
+                                                tokio::task::spawn_blocking(crate::command::get_live_metrics)
+                                                    .await
+                                            else {
+                                                continue;
                                             };
-                                            let _ = sender
-                                                .send(Message::Text(
-                                                    serde_json::to_string(&error).unwrap(),
-                                                ))
-                                                .await;
+                                            if metrics_tx.send(WsMessage::Metrics(metrics)).await.is_err() {
+                                                break;
+                                            }
                                         }
+                                    }));
+                                }
+                                WsMessage::UnsubscribeMetrics => {
+                                    if let Some(task) = metrics_task.take() {
+                                        task.abort();
+                                        log::info!(
+                                            "WebSocket client {} unsubscribed from metrics",
+                                            client_id
+                                        );
+                                    }
+                                }
+                                WsMessage::UnwatchPath { path } => {
+                                    if watchers.remove(&path).is_some() {
+                                        log::info!(
+                                            "WebSocket client {} stopped watching {}",
+                                            client_id,
+                                            path
+                                        );
                                     }
                                 }
                                 _ => {}
@@ -175,11 +764,10 @@ impl WebSocketManager {
                         }
                         Err(e) => {
                             log::warn!("Failed to parse WebSocket message: {}", e);
-                            let error = WsMessage::Error {
-                                message: "Invalid message format".to_string(),
-                            };
-                            let _ = sender
-                                .send(Message::Text(serde_json::to_string(&error).unwrap()))
+                            let _ = out_tx
+                                .send(WsMessage::Error {
+                                    message: "Invalid message format".to_string(),
+                                })
                                 .await;
                         }
                     }
@@ -191,17 +779,33 @@ impl WebSocketManager {
                 _ => {}
             }
         }
+
+        crate::connections::disconnected(&connection_id);
+        drop(watchers);
+        if let Some(task) = metrics_task {
+            task.abort();
+        }
+        drop(out_tx);
+        let _ = writer_stop.send(());
+        // 等待写任务发出已排队的消息（如断开原因），只有客户端不再读取时才强制结束
+        if tokio::time::timeout(WRITER_DRAIN_TIMEOUT, &mut writer).await.is_err() {
+            writer.abort();
+        }
     }
 }
 
 // WebSocket 升级处理函数
+//
+// 加密会话通过 PAYLOAD_HEADER 请求头连接，令牌在加密的查询参数中，之后的帧逐帧加密
 pub async fn ws_handler(
     ws: WebSocketUpgrade,
     State(state): State<AppState>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(query): Query<WsQuery>,
+    session: Option<Extension<PayloadSession>>,
 ) -> Response {
     let client_ip = addr.to_string();
-    
+
     // 检查IP黑名单
     if is_ip_blacklisted(&client_ip) {
         log::warn!("[Security] WebSocket connection from blacklisted IP blocked: {}", client_ip);
@@ -210,11 +814,56 @@ pub async fn ws_handler(
             .body(axum::body::Body::from("Access denied: IP is blacklisted"))
             .unwrap();
     }
-    
+
     let manager = state.ws_manager.lock().await.clone();
     let auth_manager = state.auth_manager.clone();
 
-    ws.on_upgrade(move |socket| async move {
-        manager.handle_socket(socket, auth_manager, client_ip).await;
-    })
+    let upgrade = match (session, query.token) {
+        (Some(Extension(session)), Some(token)) => Some(EncryptedUpgrade { token, session }),
+        _ => None,
+    };
+
+    ws.max_message_size(WS_HARD_MESSAGE_LIMIT)
+        .on_upgrade(move |socket| async move {
+            manager
+                .handle_socket(socket, auth_manager, client_ip, upgrade)
+                .await;
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auth_deadline_only_applies_when_password_is_set() {
+        let auth_manager = AuthManager::in_memory();
+        assert!(awaiting_auth(&auth_manager, false));
+        assert!(!awaiting_auth(&auth_manager, true));
+
+        // 未设置密码时连接无需认证，不因认证超时断开
+        auth_manager.clear_password();
+        assert!(!awaiting_auth(&auth_manager, false));
+    }
+
+    #[test]
+    fn encrypted_session_token_is_refused_in_plaintext_auth() {
+        let auth_manager = AuthManager::in_memory();
+        let token = auth_manager.issue_test_token();
+        assert_eq!(check_auth_token(&auth_manager, &token, false), Ok(()));
+
+        auth_manager
+            .enable_payload_encryption(&token, "challenge")
+            .unwrap();
+        assert_eq!(
+            check_auth_token(&auth_manager, &token, false),
+            Err("This session requires encrypted requests")
+        );
+        // 通过加密的升级请求建立的连接中仍可认证
+        assert_eq!(check_auth_token(&auth_manager, &token, true), Ok(()));
+        assert_eq!(
+            check_auth_token(&auth_manager, "not a token", false),
+            Err("Invalid or expired token")
+        );
+    }
 }
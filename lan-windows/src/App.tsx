@@ -1,4 +1,4 @@
-import { useState, useEffect, useRef } from "react";
+import { useState, useEffect } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { createPortal } from "react-dom";
@@ -17,6 +17,9 @@ interface ServerStatus {
   device_name: string;
   ip_address: string | null;
   version: string;
+  advertising: boolean;
+  discovery_error: string | null;
+  device_uuid: string | null;
 }
 
 interface SystemInfo {
@@ -38,6 +41,16 @@ interface LogEntry {
   source: string | null;
 }
 
+interface ClientConnection {
+  id: string;
+  kind: "api" | "web_socket";
+  ip: string;
+  device: string | null;
+  authenticated: boolean;
+  connected_at: string;
+  expires_at?: string;
+}
+
 type Theme = "light" | "dark" | "system" | "glass";
 
 interface AppConfig {
@@ -68,7 +81,10 @@ function App() {
   const [serverStatus, setServerStatus] = useState<ServerStatus | null>(null);
   const [systemInfo, setSystemInfo] = useState<SystemInfo | null>(null);
   const [logs, setLogs] = useState<LogEntry[]>([]);
+  const [connections, setConnections] = useState<ClientConnection[]>([]);
   const [config, setConfig] = useState<AppConfig | null>(null);
+  // 旧版本设置的密码需要在设置中重新输入一次，客户端才能登录
+  const [passwordNeedsReentry, setPasswordNeedsReentry] = useState(false);
   const [logFilter, setLogFilter] = useState("all");
   const [searchQuery, setSearchQuery] = useState("");
   const [isLoading, setIsLoading] = useState(false);
@@ -214,47 +230,28 @@ function App() {
     };
   }, [serverStatus?.running, setManagedInterval, clearManagedTimer]);
 
-  // 监听托盘事件 - 使用 ref 防止重复触发
-  const isProcessingRef = useRef(false);
-
   useEffect(() => {
-    // 监听托盘启动服务事件
-    const unlistenStart = listen('tray-start-server', async () => {
-      if (isProcessingRef.current) return;
-      isProcessingRef.current = true;
-      
-      try {
-        const status = await invoke<ServerStatus>('get_server_status');
-        if (!status.running) {
-          await startServer();
-        }
-      } finally {
-        setTimeout(() => {
-          isProcessingRef.current = false;
-        }, 1000); // 1秒后重置标志
-      }
+    // 监听后端推送的服务器状态变化（启动、停止、意外退出、自动重启、托盘操作）
+    const unlistenStatus = listen<ServerStatus>('server-status-changed', (event) => {
+      setServerStatus(event.payload);
     });
 
-    // 监听托盘停止服务事件
-    const unlistenStop = listen('tray-stop-server', async () => {
-      if (isProcessingRef.current) return;
-      isProcessingRef.current = true;
-      
-      try {
-        const status = await invoke<ServerStatus>('get_server_status');
-        if (status.running) {
-          await stopServer();
-        }
-      } finally {
-        setTimeout(() => {
-          isProcessingRef.current = false;
-        }, 1000); // 1秒后重置标志
-      }
+    // 客户端连接、认证、断开时更新连接列表
+    invoke<ClientConnection[]>("get_connections").then(setConnections).catch(console.error);
+    const upsertConnection = (event: { payload: ClientConnection }) => {
+      setConnections(prev => [...prev.filter(c => c.id !== event.payload.id), event.payload]);
+    };
+    const unlistenConnected = listen<ClientConnection>('client-connected', upsertConnection);
+    const unlistenAuthenticated = listen<ClientConnection>('client-authenticated', upsertConnection);
+    const unlistenDisconnected = listen<ClientConnection>('client-disconnected', (event) => {
+      setConnections(prev => prev.filter(c => c.id !== event.payload.id));
     });
 
     return () => {
-      unlistenStart.then(fn => fn());
-      unlistenStop.then(fn => fn());
+      unlistenStatus.then(fn => fn());
+      unlistenConnected.then(fn => fn());
+      unlistenAuthenticated.then(fn => fn());
+      unlistenDisconnected.then(fn => fn());
     };
   }, []); // 空依赖数组，只注册一次
 
@@ -264,6 +261,7 @@ function App() {
       try {
         const cfg = await invoke<AppConfig>("get_config");
         setConfig(cfg);
+        setPasswordNeedsReentry(await invoke<boolean>("password_needs_reentry"));
         return cfg;
       } catch (error) {
         console.error(`Failed to load config (attempt ${i + 1}/${retries}):`, error);
@@ -371,6 +369,16 @@ function App() {
   };
 
   // 停止服务器
+  // 暂停或恢复 mDNS 广播
+  const toggleAdvertising = async () => {
+    try {
+      await invoke("set_mdns_advertising", { enabled: !serverStatus?.advertising });
+      await refreshStatus();
+    } catch (error) {
+      showToast(`${error}`, "error");
+    }
+  };
+
   const stopServer = async () => {
     setIsLoading(true);
     try {
@@ -672,6 +680,28 @@ function App() {
                     </div>
                   </div>
                 )}
+                {serverStatus?.running && (
+                  <div className="detail-item">
+                    <span className="detail-label">{t('sidebar.discoverable')}</span>
+                    <div className="detail-value">
+                      <span className="material-icon">
+                        {serverStatus.advertising ? "wifi_tethering" : "wifi_tethering_off"}
+                      </span>
+                      {serverStatus.discovery_error ? (
+                        <span title={serverStatus.discovery_error}>{t('sidebar.discoveryDegraded')}</span>
+                      ) : (
+                        <span>{serverStatus.advertising ? t('sidebar.discoverableOn') : t('sidebar.discoverableOff')}</span>
+                      )}
+                      <button
+                        className="icon-btn"
+                        onClick={toggleAdvertising}
+                        title={serverStatus.advertising ? t('sidebar.pauseAdvertising') : t('sidebar.resumeAdvertising')}
+                      >
+                        <span className="material-icon">{serverStatus.advertising ? "pause" : "play_arrow"}</span>
+                      </button>
+                    </div>
+                  </div>
+                )}
                 {getCurrentPort() && (
                   <div className="detail-item">
                     <span className="detail-label">{t('sidebar.port')}</span>
@@ -699,9 +729,39 @@ function App() {
                   <span className="material-icon">edit</span>
                 </button>
               </div>
-              <small style={{ color: "#5c6b85", fontSize: "11px" }}>
-                {hasPassword(config) ? t('sidebar.passwordSet') : t('sidebar.passwordNotSet')}
-              </small>
+              {passwordNeedsReentry ? (
+                <small style={{ color: "var(--warning)", fontSize: "11px" }}>
+                  {t('sidebar.passwordNeedsReentry')}
+                </small>
+              ) : (
+                <small style={{ color: "#5c6b85", fontSize: "11px" }}>
+                  {hasPassword(config) ? t('sidebar.passwordSet') : t('sidebar.passwordNotSet')}
+                </small>
+              )}
+            </div>
+
+            {/* 已连接的客户端 */}
+            <div className="connections-section">
+              <label className="section-label">
+                {t('sidebar.connections')} ({connections.length})
+              </label>
+              {connections.length === 0 ? (
+                <small className="connections-empty">{t('sidebar.noConnections')}</small>
+              ) : (
+                <ul className="connections-list">
+                  {connections.map((connection) => (
+                    <li key={connection.id} className="connection-item">
+                      <span className="material-icon">
+                        {connection.kind === "web_socket" ? "sync_alt" : "api"}
+                      </span>
+                      <span className="connection-ip">{connection.ip}</span>
+                      <span className="connection-device">
+                        {connection.authenticated ? connection.device : t('sidebar.notAuthenticated')}
+                      </span>
+                    </li>
+                  ))}
+                </ul>
+              )}
             </div>
 
             {/* 服务器控制 */}
@@ -1,6 +1,6 @@
 import { useState, useEffect } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { enable, disable, isEnabled } from "@tauri-apps/plugin-autostart";
+import { listen } from "@tauri-apps/api/event";
 import { useTranslation } from "react-i18next";
 import { createPortal } from "react-dom";
 import "./Settings.css";
@@ -9,18 +9,71 @@ type Theme = "light" | "dark" | "system" | "glass";
 
 interface AppConfig {
   api_port: number;
+  device_display_name?: string;
+  device_id_source?: string;
+  enable_mdns?: boolean;
+  enable_ssdp?: boolean;
+  stealth_health?: boolean;
+  health_secret?: string | null;
+  grpc_port?: number;
   password_hash: string | null;
   log_buffer_size: number;
   log_file_path: string | null;
   enable_log_file: boolean;
   log_file_max_size: number;
+  min_log_level?: string;
   auto_start_api: boolean;
   auto_start_on_boot: boolean;
+  idle_stop_minutes?: number;
+  tray?: { quick_actions: string[] };
   command_whitelist: string[];
   custom_commands: string[];
   theme: Theme;
   ip_blacklist: string[];
   enable_ip_blacklist: boolean;
+  lan_only?: boolean;
+  password_policy?: PasswordPolicy;
+  enable_api_keys?: boolean;
+  api_keys?: ApiKey[];
+}
+
+interface ApiKey {
+  name: string;
+  key_hash: string;
+  permissions: { allowed_commands: string[]; allow_custom_commands: boolean; scopes: string[] };
+  created_at: number;
+}
+
+interface CommandCheck {
+  command: string;
+  args: string[] | null;
+  allowed: boolean;
+  rule: string;
+  config_key: string;
+  reason: string;
+}
+
+interface ConfigFieldError {
+  field: string;
+  message: string;
+}
+
+interface PasswordPolicy {
+  min_length: number;
+  require_uppercase: boolean;
+  require_lowercase: boolean;
+  require_digit: boolean;
+  require_symbol: boolean;
+  deny_common: boolean;
+}
+
+interface AppUpdateStatus {
+  current_version: string;
+  latest_version: string | null;
+  update_available: boolean;
+  release_url: string | null;
+  installer_path: string | null;
+  last_error: string | null;
 }
 
 interface SettingsProps {
@@ -32,6 +85,9 @@ interface SettingsProps {
   onServerRestart?: () => Promise<void>;
 }
 
+// 托盘菜单可用的快捷操作，与后端 tray::QUICK_ACTIONS 一致
+const TRAY_ACTIONS = ["lock", "sleep", "toggle_mdns", "copy_pairing_code"];
+
 function Settings({ isOpen, onClose, onThemeChange, currentTheme, serverRunning, onServerRestart }: SettingsProps) {
   const { t, i18n } = useTranslation();
   const [config, setConfig] = useState<AppConfig | null>(null);
@@ -40,11 +96,19 @@ function Settings({ isOpen, onClose, onThemeChange, currentTheme, serverRunning,
 
   // 表单状态
   const [apiPort, setApiPort] = useState(8080);
+  const [deviceDisplayName, setDeviceDisplayName] = useState("");
+  const [enableMdns, setEnableMdns] = useState(true);
+  const [enableSsdp, setEnableSsdp] = useState(false);
+  const [stealthHealth, setStealthHealth] = useState(false);
+  const [healthSecret, setHealthSecret] = useState("");
+  const [grpcPort, setGrpcPort] = useState(0);
   const [logBufferSize, setLogBufferSize] = useState(100);
   const [enableLogFile, setEnableLogFile] = useState(true);
   const [logFileMaxSize, setLogFileMaxSize] = useState(10);
+  const [minLogLevel, setMinLogLevel] = useState("info");
   const [autoStartApi, setAutoStartApi] = useState(false);
   const [autoStartOnBoot, setAutoStartOnBoot] = useState(false);
+  const [idleStopMinutes, setIdleStopMinutes] = useState(0);
   const [logFilePath, setLogFilePath] = useState("");
   const [theme, setTheme] = useState<Theme>("system");
   const [language, setLanguage] = useState(i18n.language || "en");
@@ -53,12 +117,27 @@ function Settings({ isOpen, onClose, onThemeChange, currentTheme, serverRunning,
   const [commandWhitelist, setCommandWhitelist] = useState<string[]>([]);
   const [customCommands, setCustomCommands] = useState<string[]>([]);
   const [newCustomCommand, setNewCustomCommand] = useState("");
+  const [testCommand, setTestCommand] = useState("");
+  const [deviceUuid, setDeviceUuid] = useState("");
+  const [machineDeviceId, setMachineDeviceId] = useState(false);
+  const [trayActions, setTrayActions] = useState<string[]>([]);
+  const [testResult, setTestResult] = useState<CommandCheck | null>(null);
 
   // IP黑名单
   const [ipBlacklist, setIpBlacklist] = useState<string[]>([]);
   const [enableIpBlacklist, setEnableIpBlacklist] = useState(false);
+  const [lanOnly, setLanOnly] = useState(false);
+  const [trustedNetworks, setTrustedNetworks] = useState<string[]>([]);
+  const [newTrustedNetwork, setNewTrustedNetwork] = useState("");
   const [newBlockedIp, setNewBlockedIp] = useState("");
 
+  // API 密钥
+  const [enableApiKeys, setEnableApiKeys] = useState(false);
+  const [apiKeys, setApiKeys] = useState<ApiKey[]>([]);
+  const [newApiKeyName, setNewApiKeyName] = useState("");
+  const [newApiKeyScopes, setNewApiKeyScopes] = useState("commands");
+  const [createdApiKey, setCreatedApiKey] = useState<string | null>(null);
+
   // 内置命令列表（供用户选择）
   const builtInCommands = [
     { id: "shutdown", desc: t('commands.shutdownDesc') },
@@ -68,6 +147,13 @@ function Settings({ isOpen, onClose, onThemeChange, currentTheme, serverRunning,
     { id: "systeminfo", desc: t('commands.systeminfoDesc') },
     { id: "tasklist", desc: t('commands.tasklistDesc') },
     { id: "wmic", desc: t('commands.wmicDesc') },
+    { id: "flushdns", desc: t('commands.flushdnsDesc') },
+    { id: "renew-dhcp", desc: t('commands.renew-dhcpDesc') },
+    { id: "reset-network-adapter", desc: t('commands.reset-network-adapterDesc') },
+    { id: "empty-recycle-bin", desc: t('commands.empty-recycle-binDesc') },
+    { id: "clean-temp", desc: t('commands.clean-tempDesc') },
+    { id: "open-file", desc: t('commands.open-fileDesc') },
+    { id: "shell", desc: t('commands.shellDesc') },
     { id: "custom", desc: t('commands.customDesc') },
   ];
 
@@ -75,16 +161,36 @@ function Settings({ isOpen, onClose, onThemeChange, currentTheme, serverRunning,
   const [newPassword, setNewPassword] = useState("");
   const [confirmPassword, setConfirmPassword] = useState("");
   const [hasPassword, setHasPassword] = useState(false);
+  // 旧版本设置的密码缺少登录验证器，重新输入后客户端才能登录
+  const [needsReentry, setNeedsReentry] = useState(false);
+  const [currentPassword, setCurrentPassword] = useState("");
   const [showNewPassword, setShowNewPassword] = useState(false);
   const [showConfirmPassword, setShowConfirmPassword] = useState(false);
 
+  // 本程序更新状态
+  const [updateStatus, setUpdateStatus] = useState<AppUpdateStatus | null>(null);
+  const [checkingUpdate, setCheckingUpdate] = useState(false);
+
   // 当设置页面打开时，加载配置
   useEffect(() => {
     if (isOpen) {
       loadConfig();
+      invoke<AppUpdateStatus>("get_app_update_status").then(setUpdateStatus).catch(() => {});
+      invoke<string>("get_device_uuid").then(setDeviceUuid).catch(() => {});
     }
   }, [isOpen]);
 
+  // 配置文件被外部修改并自动重新加载后，刷新设置页面
+  useEffect(() => {
+    if (!isOpen) return;
+    const unlisten = listen("config-reloaded", () => {
+      loadConfig();
+    });
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, [isOpen]);
+
   // 当传入的 currentTheme 变化时，更新主题状态（仅在初始化时）
   useEffect(() => {
     if (currentTheme && !config) {
@@ -102,18 +208,32 @@ function Settings({ isOpen, onClose, onThemeChange, currentTheme, serverRunning,
       const cfg = await invoke<AppConfig>("get_config");
       setConfig(cfg);
       setApiPort(cfg.api_port);
+      setDeviceDisplayName(cfg.device_display_name || "");
+      setMachineDeviceId(cfg.device_id_source === "machine");
+      setEnableMdns(cfg.enable_mdns ?? true);
+      setEnableSsdp(cfg.enable_ssdp ?? false);
+      setStealthHealth(cfg.stealth_health ?? false);
+      setHealthSecret(cfg.health_secret || "");
+      setGrpcPort(cfg.grpc_port ?? 0);
       setLogBufferSize(cfg.log_buffer_size);
       setEnableLogFile(cfg.enable_log_file);
       setLogFileMaxSize(cfg.log_file_max_size);
+      setMinLogLevel(cfg.min_log_level || "info");
       setAutoStartApi(cfg.auto_start_api);
-      // 从插件获取实际的开机自启动状态
-      const autoStartEnabled = await isEnabled();
-      setAutoStartOnBoot(autoStartEnabled);
+      setIdleStopMinutes(cfg.idle_stop_minutes ?? 0);
+      setTrayActions(cfg.tray?.quick_actions || []);
+      // 使用系统中实际的开机自启动状态
+      const autostart = await invoke<{ registered: boolean }>("get_autostart_status");
+      setAutoStartOnBoot(autostart.registered);
       setLogFilePath(cfg.log_file_path || "");
       setCommandWhitelist(cfg.command_whitelist || []);
       setCustomCommands(cfg.custom_commands || []);
       setIpBlacklist(cfg.ip_blacklist || []);
       setEnableIpBlacklist(cfg.enable_ip_blacklist || false);
+      setLanOnly(cfg.lan_only ?? false);
+      setTrustedNetworks(cfg.trusted_networks || []);
+      setEnableApiKeys(cfg.enable_api_keys ?? false);
+      setApiKeys(cfg.api_keys || []);
       // 只有在没有传入 currentTheme 时才从配置加载主题
       if (!currentTheme) {
         setTheme(cfg.theme || "dark");
@@ -122,6 +242,7 @@ function Settings({ isOpen, onClose, onThemeChange, currentTheme, serverRunning,
       // 检查是否有密码
       const hasPwd = await invoke<boolean>("has_config_password");
       setHasPassword(hasPwd);
+      setNeedsReentry(await invoke<boolean>("password_needs_reentry"));
 
       // 同步当前语言状态
       setLanguage(i18n.language || "en");
@@ -138,32 +259,42 @@ function Settings({ isOpen, onClose, onThemeChange, currentTheme, serverRunning,
       const portChanged = config && config.api_port !== apiPort;
       const needsRestart = portChanged && serverRunning;
 
-      // 处理开机自启动设置
-      const currentAutoStart = await isEnabled();
-      if (autoStartOnBoot !== currentAutoStart) {
-        if (autoStartOnBoot) {
-          await enable();
-        } else {
-          await disable();
-        }
-      }
-
       const newConfig: AppConfig = {
         api_port: apiPort,
+        device_display_name: deviceDisplayName,
+        enable_mdns: enableMdns,
+        enable_ssdp: enableSsdp,
+        stealth_health: stealthHealth,
+        health_secret: healthSecret.trim() || null,
+        grpc_port: grpcPort,
         password_hash: config?.password_hash || null,
         log_buffer_size: logBufferSize,
         log_file_path: logFilePath || null,
         enable_log_file: enableLogFile,
         log_file_max_size: logFileMaxSize,
+        min_log_level: minLogLevel,
         auto_start_api: autoStartApi,
+        // 后端保存时按此注册或取消开机自启动
         auto_start_on_boot: autoStartOnBoot,
+        idle_stop_minutes: idleStopMinutes,
         command_whitelist: commandWhitelist,
         custom_commands: customCommands,
         theme: theme,
         ip_blacklist: ipBlacklist,
         enable_ip_blacklist: enableIpBlacklist,
+        lan_only: lanOnly,
+        trusted_networks: trustedNetworks,
+        enable_api_keys: enableApiKeys,
+        api_keys: apiKeys,
       };
 
+      // 保存前由后端校验，出错时提示第一个错误
+      const errors = await invoke<ConfigFieldError[]>("validate_config", { config: newConfig });
+      if (errors.length > 0) {
+        showToast(`${t('settings.saveError')}: ${errors[0].field}: ${errors[0].message}`, "error");
+        return;
+      }
+
       await invoke("save_config", { newConfig });
       await invoke("reload_config");
 
@@ -185,8 +316,10 @@ function Settings({ isOpen, onClose, onThemeChange, currentTheme, serverRunning,
   };
 
   const handlePasswordChange = async () => {
-    if (newPassword.length < 8) {
-      showToast(t('settings.passwordMinLength'), "error");
+    // 其余规则由后端按密码策略校验
+    const minLength = config?.password_policy?.min_length ?? 8;
+    if (newPassword.length < minLength) {
+      showToast(t('settings.passwordMinLength', { count: minLength }), "error");
       return;
     }
     if (newPassword !== confirmPassword) {
@@ -210,6 +343,35 @@ function Settings({ isOpen, onClose, onThemeChange, currentTheme, serverRunning,
     }
   };
 
+  const handleReenterPassword = async () => {
+    setLoading(true);
+    try {
+      await invoke("restore_password_verifier", { password: currentPassword });
+      showToast(t('settings.password.reenterSuccess'));
+      setCurrentPassword("");
+      setNeedsReentry(false);
+    } catch (error) {
+      showToast(`${t('settings.password.reenterFailed')}: ${error}`, "error");
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  const handleCheckForUpdates = async () => {
+    setCheckingUpdate(true);
+    try {
+      const status = await invoke<AppUpdateStatus>("check_for_updates");
+      setUpdateStatus(status);
+      if (!status.update_available) {
+        showToast(t('settings.updates.upToDate'));
+      }
+    } catch (error) {
+      showToast(t('settings.updates.checkFailed', { error: String(error) }), "error");
+    } finally {
+      setCheckingUpdate(false);
+    }
+  };
+
   const handleClearPassword = async () => {
     if (!hasPassword) return;
     
@@ -227,6 +389,33 @@ function Settings({ isOpen, onClose, onThemeChange, currentTheme, serverRunning,
     }
   };
 
+  const createApiKey = async () => {
+    const name = newApiKeyName.trim();
+    if (!name) return;
+    const scopes = newApiKeyScopes.split(",").map(s => s.trim()).filter(Boolean);
+    try {
+      const key = await invoke<string>("create_api_key", {
+        name,
+        permissions: { allowed_commands: [], allow_custom_commands: false, scopes },
+      });
+      setCreatedApiKey(key);
+      setNewApiKeyName("");
+      const cfg = await invoke<AppConfig>("get_config");
+      setApiKeys(cfg.api_keys || []);
+    } catch (error) {
+      showToast(`${t('settings.apiKeys.createFailed')}: ${error}`, "error");
+    }
+  };
+
+  const deleteApiKey = async (name: string) => {
+    try {
+      await invoke<boolean>("delete_api_key", { name });
+      setApiKeys(apiKeys.filter(k => k.name !== name));
+    } catch (error) {
+      showToast(`${t('settings.apiKeys.deleteFailed')}: ${error}`, "error");
+    }
+  };
+
   const openLogFileLocation = async () => {
     try {
       const info = await invoke<[string, number | null]>("get_log_file_info");
@@ -277,6 +466,68 @@ function Settings({ isOpen, onClose, onThemeChange, currentTheme, serverRunning,
     showToast(t('toast.customCommandAdded'));
   };
 
+  const copyDeviceUuid = async () => {
+    try {
+      await navigator.clipboard.writeText(deviceUuid);
+      showToast(t('settings.server.deviceUuidCopied'));
+    } catch (error) {
+      showToast(`${error}`, "error");
+    }
+  };
+
+  // 客户端以UUID识别设备，重新生成前提醒用户
+  const regenerateDeviceUuid = async () => {
+    if (!window.confirm(t('settings.server.regenerateUuidConfirm'))) return;
+    try {
+      const uuid = await invoke<string>("regenerate_device_uuid");
+      setDeviceUuid(uuid);
+      showToast(t('settings.server.deviceUuidRegenerated'));
+    } catch (error) {
+      showToast(`${t('settings.server.regenerateUuidFailed')}: ${error}`, "error");
+    }
+  };
+
+  // 由硬件标识派生的UUID在重装应用后保持不变
+  const toggleMachineDeviceId = async (enabled: boolean) => {
+    try {
+      const uuid = await invoke<string>("set_device_id_source", {
+        source: enabled ? "machine" : "random",
+      });
+      setMachineDeviceId(enabled);
+      setDeviceUuid(uuid);
+    } catch (error) {
+      showToast(`${t('settings.server.machineDeviceIdFailed')}: ${error}`, "error");
+    }
+  };
+
+  // 托盘快捷操作立即生效，保持 TRAY_ACTIONS 中的顺序
+  const toggleTrayAction = async (action: string, enabled: boolean) => {
+    const actions = TRAY_ACTIONS.filter((a) =>
+      a === action ? enabled : trayActions.includes(a)
+    );
+    try {
+      await invoke("set_tray_quick_actions", { actions });
+      setTrayActions(actions);
+    } catch (error) {
+      showToast(`${t('settings.server.trayActionsFailed')}: ${error}`, "error");
+    }
+  };
+
+  // 按已保存的配置检查命令能否执行，命令后的内容作为参数
+  const runWhitelistTest = async () => {
+    const [command, ...args] = testCommand.trim().split(/\s+/);
+    if (!command) return;
+    try {
+      const result = await invoke<CommandCheck>("test_whitelist", {
+        command,
+        args: args.length > 0 ? args : null,
+      });
+      setTestResult(result);
+    } catch (error) {
+      showToast(`${t('settings.commands.testFailed')}: ${error}`, "error");
+    }
+  };
+
   // 删除自定义命令
   const removeCustomCommand = (command: string) => {
     // 从自定义命令列表和白名单中同时移除
@@ -319,6 +570,24 @@ function Settings({ isOpen, onClose, onThemeChange, currentTheme, serverRunning,
     showToast(t('toast.ipRemoved'));
   };
 
+  // 添加受信任网络（Wi-Fi 名称、IP 地址或 CIDR 网段）
+  const addTrustedNetwork = () => {
+    const network = newTrustedNetwork.trim();
+    if (!network) {
+      return;
+    }
+    if (trustedNetworks.includes(network)) {
+      showToast(t('settings.trustedNetworks.alreadyExists'), "error");
+      return;
+    }
+    setTrustedNetworks(prev => [...prev, network]);
+    setNewTrustedNetwork("");
+  };
+
+  const removeTrustedNetwork = (network: string) => {
+    setTrustedNetworks(prev => prev.filter(n => n !== network));
+  };
+
   if (!isOpen) return null;
 
   return (
@@ -446,6 +715,104 @@ function Settings({ isOpen, onClose, onThemeChange, currentTheme, serverRunning,
               <small>{t('settings.server.apiPortDescription')}</small>
             </div>
 
+            <div className="form-group">
+              <label>{t('settings.server.displayName')}</label>
+              <input
+                type="text"
+                value={deviceDisplayName}
+                onChange={(e) => setDeviceDisplayName(e.target.value)}
+                maxLength={63}
+              />
+              <small>{t('settings.server.displayNameDescription')}</small>
+            </div>
+
+            <div className="form-group">
+              <label>{t('settings.server.deviceUuid')}</label>
+              <input type="text" value={deviceUuid} readOnly />
+              <div className="button-group">
+                <button className="btn btn-small" onClick={copyDeviceUuid} disabled={!deviceUuid}>
+                  <span className="material-icon">content_copy</span>
+                  {t('settings.server.copyUuid')}
+                </button>
+                <button
+                  className="btn btn-small btn-danger"
+                  onClick={regenerateDeviceUuid}
+                  disabled={machineDeviceId}
+                >
+                  <span className="material-icon">autorenew</span>
+                  {t('settings.server.regenerateUuid')}
+                </button>
+              </div>
+              <small>{t('settings.server.deviceUuidDescription')}</small>
+            </div>
+
+            <div className="form-group checkbox">
+              <label>
+                <input
+                  type="checkbox"
+                  checked={machineDeviceId}
+                  onChange={(e) => toggleMachineDeviceId(e.target.checked)}
+                />
+                <span>{t('settings.server.machineDeviceId')}</span>
+              </label>
+            </div>
+
+            <div className="form-group checkbox">
+              <label>
+                <input
+                  type="checkbox"
+                  checked={enableMdns}
+                  onChange={(e) => setEnableMdns(e.target.checked)}
+                />
+                <span>{t('settings.server.enableMdns')}</span>
+              </label>
+            </div>
+
+            <div className="form-group checkbox">
+              <label>
+                <input
+                  type="checkbox"
+                  checked={enableSsdp}
+                  onChange={(e) => setEnableSsdp(e.target.checked)}
+                />
+                <span>{t('settings.server.enableSsdp')}</span>
+              </label>
+            </div>
+
+            <div className="form-group checkbox">
+              <label>
+                <input
+                  type="checkbox"
+                  checked={stealthHealth}
+                  onChange={(e) => setStealthHealth(e.target.checked)}
+                />
+                <span>{t('settings.server.stealthHealth')}</span>
+              </label>
+            </div>
+
+            <div className="form-group">
+              <label>{t('settings.server.healthSecret')}</label>
+              <input
+                type="text"
+                value={healthSecret}
+                onChange={(e) => setHealthSecret(e.target.value)}
+                autoComplete="off"
+              />
+              <small>{t('settings.server.healthSecretDescription')}</small>
+            </div>
+
+            <div className="form-group">
+              <label>{t('settings.server.grpcPort')}</label>
+              <input
+                type="number"
+                value={grpcPort}
+                onChange={(e) => setGrpcPort(parseInt(e.target.value) || 0)}
+                min={0}
+                max={65535}
+              />
+              <small>{t('settings.server.grpcPortDescription')}</small>
+            </div>
+
             <div className="form-group checkbox">
               <label>
                 <input
@@ -467,6 +834,33 @@ function Settings({ isOpen, onClose, onThemeChange, currentTheme, serverRunning,
                 <span>{t('settings.server.startOnBoot')}</span>
               </label>
             </div>
+
+            <div className="form-group">
+              <label>{t('settings.server.idleStopMinutes')}</label>
+              <input
+                type="number"
+                value={idleStopMinutes}
+                onChange={(e) => setIdleStopMinutes(parseInt(e.target.value) || 0)}
+                min={0}
+              />
+              <small>{t('settings.server.idleStopMinutesDescription')}</small>
+            </div>
+
+            <div className="form-group">
+              <label>{t('settings.server.trayActions')}</label>
+              {TRAY_ACTIONS.map((action) => (
+                <div key={action} className="form-group checkbox">
+                  <label>
+                    <input
+                      type="checkbox"
+                      checked={trayActions.includes(action)}
+                      onChange={(e) => toggleTrayAction(action, e.target.checked)}
+                    />
+                    <span>{t(`settings.server.trayAction.${action}`)}</span>
+                  </label>
+                </div>
+              ))}
+            </div>
           </section>
 
           {/* 日志设置 */}
@@ -488,6 +882,23 @@ function Settings({ isOpen, onClose, onThemeChange, currentTheme, serverRunning,
               <small>{t('settings.logs.bufferSizeDescription')}</small>
             </div>
 
+            <div className="form-group">
+              <label>{t('settings.logs.minLevel')}</label>
+              <div className="theme-options">
+                {["info", "warn", "error"].map(level => (
+                  <button
+                    key={level}
+                    className={`theme-option ${minLogLevel === level ? 'active' : ''}`}
+                    onClick={() => setMinLogLevel(level)}
+                    type="button"
+                  >
+                    <span>{t(`settings.logs.level.${level}`)}</span>
+                  </button>
+                ))}
+              </div>
+              <small>{t('settings.logs.minLevelDescription')}</small>
+            </div>
+
             <div className="form-group checkbox">
               <label>
                 <input
@@ -602,6 +1013,32 @@ function Settings({ isOpen, onClose, onThemeChange, currentTheme, serverRunning,
               </button>
               <small>{t('settings.commands.customCommandNote')}</small>
             </div>
+
+            <div className="form-group">
+              <label>{t('settings.commands.test')}</label>
+              <input
+                type="text"
+                value={testCommand}
+                onChange={(e) => setTestCommand(e.target.value)}
+                placeholder={t('settings.commands.testPlaceholder')}
+                onKeyPress={(e) => e.key === 'Enter' && runWhitelistTest()}
+              />
+              <button className="btn btn-small btn-full" onClick={runWhitelistTest}>
+                <span className="material-icon">rule</span>
+                {t('settings.commands.testButton')}
+              </button>
+              {testResult && (
+                <small>
+                  {testResult.allowed
+                    ? t('settings.commands.testAllowed', { command: testResult.command })
+                    : t('settings.commands.testDenied', {
+                        reason: testResult.reason,
+                        setting: testResult.config_key,
+                      })}
+                </small>
+              )}
+              <small>{t('settings.commands.testHint')}</small>
+            </div>
           </section>
 
           {/* IP黑名单设置 */}
@@ -612,6 +1049,17 @@ function Settings({ isOpen, onClose, onThemeChange, currentTheme, serverRunning,
             </h3>
             <p className="section-desc">{t('settings.ipBlacklist.description')}</p>
 
+            <div className="form-group checkbox">
+              <label>
+                <input
+                  type="checkbox"
+                  checked={lanOnly}
+                  onChange={(e) => setLanOnly(e.target.checked)}
+                />
+                <span>{t('settings.ipBlacklist.lanOnly')}</span>
+              </label>
+            </div>
+
             <div className="form-group checkbox">
               <label>
                 <input
@@ -662,6 +1110,120 @@ function Settings({ isOpen, onClose, onThemeChange, currentTheme, serverRunning,
             )}
           </section>
 
+          {/* 受信任网络 */}
+          <section className="settings-section">
+            <h3>
+              <span className="material-icon">wifi_lock</span>
+              {t('settings.trustedNetworks.title')}
+            </h3>
+            <p className="section-desc">{t('settings.trustedNetworks.description')}</p>
+
+            <div className="form-group">
+              <label>{t('settings.trustedNetworks.add')}</label>
+              <input
+                type="text"
+                value={newTrustedNetwork}
+                onChange={(e) => setNewTrustedNetwork(e.target.value)}
+                placeholder={t('settings.trustedNetworks.placeholder')}
+                onKeyPress={(e) => e.key === 'Enter' && addTrustedNetwork()}
+              />
+              <button className="btn btn-small btn-full" onClick={addTrustedNetwork}>
+                <span className="material-icon">add</span>
+                {t('common.add')}
+              </button>
+              <small>{t('settings.trustedNetworks.hint')}</small>
+            </div>
+
+            {trustedNetworks.length > 0 && (
+              <div className="ip-blacklist">
+                {trustedNetworks.map(network => (
+                  <div key={network} className="ip-item">
+                    <span className="ip-address">{network}</span>
+                    <button
+                      className="remove-btn"
+                      onClick={() => removeTrustedNetwork(network)}
+                      title={t('common.delete')}
+                    >
+                      <span className="material-icon">close</span>
+                    </button>
+                  </div>
+                ))}
+              </div>
+            )}
+          </section>
+
+          {/* API 密钥 */}
+          <section className="settings-section">
+            <h3>
+              <span className="material-icon">key</span>
+              {t('settings.apiKeys.title')}
+            </h3>
+            <p className="section-desc">{t('settings.apiKeys.description')}</p>
+
+            <div className="form-group checkbox">
+              <label>
+                <input
+                  type="checkbox"
+                  checked={enableApiKeys}
+                  onChange={(e) => setEnableApiKeys(e.target.checked)}
+                />
+                <span>{t('settings.apiKeys.enable')}</span>
+              </label>
+            </div>
+
+            {enableApiKeys && (
+              <>
+                <div className="form-group">
+                  <label>{t('settings.apiKeys.name')}</label>
+                  <input
+                    type="text"
+                    value={newApiKeyName}
+                    onChange={(e) => setNewApiKeyName(e.target.value)}
+                    placeholder={t('settings.apiKeys.namePlaceholder')}
+                  />
+                  <label>{t('settings.apiKeys.scopes')}</label>
+                  <input
+                    type="text"
+                    value={newApiKeyScopes}
+                    onChange={(e) => setNewApiKeyScopes(e.target.value)}
+                  />
+                  <small>{t('settings.apiKeys.scopesHint')}</small>
+                  <button className="btn btn-small btn-full" onClick={createApiKey}>
+                    <span className="material-icon">add</span>
+                    {t('common.add')}
+                  </button>
+                </div>
+
+                {createdApiKey && (
+                  <div className="form-group">
+                    <label>{t('settings.apiKeys.created')}</label>
+                    <input type="text" value={createdApiKey} readOnly onFocus={(e) => e.target.select()} />
+                    <small>{t('settings.apiKeys.createdHint')}</small>
+                  </div>
+                )}
+
+                {apiKeys.length > 0 && (
+                  <div className="ip-blacklist">
+                    {apiKeys.map(key => (
+                      <div key={key.name} className="ip-item">
+                        <span className="ip-address">
+                          {key.name} ({key.permissions.scopes.join(", ") || t('settings.apiKeys.allScopes')})
+                        </span>
+                        <button
+                          className="remove-btn"
+                          onClick={() => deleteApiKey(key.name)}
+                          title={t('common.delete')}
+                        >
+                          <span className="material-icon">close</span>
+                        </button>
+                      </div>
+                    ))}
+                  </div>
+                )}
+              </>
+            )}
+          </section>
+
           {/* 密码设置 */}
           <section className="settings-section">
             <h3>
@@ -669,6 +1231,30 @@ function Settings({ isOpen, onClose, onThemeChange, currentTheme, serverRunning,
               {t('settings.password.title')}
             </h3>
 
+            {needsReentry && (
+              <div className="form-group">
+                <label>{t('settings.password.currentPassword')}</label>
+                <small style={{ color: "var(--warning)" }}>
+                  {t('settings.password.reenterDescription')}
+                </small>
+                <input
+                  type="password"
+                  value={currentPassword}
+                  onChange={(e) => setCurrentPassword(e.target.value)}
+                  onKeyDown={(e) => e.key === "Enter" && currentPassword && handleReenterPassword()}
+                />
+                <div className="button-group">
+                  <button
+                    className="btn btn-primary"
+                    onClick={handleReenterPassword}
+                    disabled={loading || !currentPassword}
+                  >
+                    {t('settings.password.reenter')}
+                  </button>
+                </div>
+              </div>
+            )}
+
             <div className="form-group">
               <label>{t('settings.password.newPassword')}</label>
               <div className="input-with-toggle">
@@ -676,7 +1262,7 @@ function Settings({ isOpen, onClose, onThemeChange, currentTheme, serverRunning,
                   type={showNewPassword ? "text" : "password"}
                   value={newPassword}
                   onChange={(e) => setNewPassword(e.target.value)}
-                  placeholder={t('settings.password.passwordPlaceholder')}
+                  placeholder={t('settings.password.passwordPlaceholder', { count: config?.password_policy?.min_length ?? 8 })}
                 />
                 <button
                   className="icon-btn"
@@ -731,6 +1317,44 @@ function Settings({ isOpen, onClose, onThemeChange, currentTheme, serverRunning,
               )}
             </div>
           </section>
+
+          {/* 软件更新 */}
+          <section className="settings-section">
+            <h3>
+              <span className="material-icon">system_update</span>
+              {t('settings.updates.title')}
+            </h3>
+
+            <div className="form-group">
+              <label>{t('settings.updates.currentVersion')}</label>
+              <span>{updateStatus?.current_version ?? "-"}</span>
+            </div>
+
+            {updateStatus?.latest_version && (
+              <div className="form-group">
+                <label>{t('settings.updates.latestVersion')}</label>
+                <span>{updateStatus.latest_version}</span>
+                <small>
+                  {updateStatus.update_available
+                    ? t('settings.updates.available', { version: updateStatus.latest_version })
+                    : t('settings.updates.upToDate')}
+                </small>
+                {updateStatus.installer_path && (
+                  <small>{t('settings.updates.installerReady', { path: updateStatus.installer_path })}</small>
+                )}
+              </div>
+            )}
+
+            <div className="button-group">
+              <button
+                className="btn btn-secondary"
+                onClick={handleCheckForUpdates}
+                disabled={checkingUpdate}
+              >
+                {checkingUpdate ? t('settings.updates.checking') : t('settings.updates.check')}
+              </button>
+            </div>
+          </section>
         </div>
 
         <div className="settings-footer">